@@ -0,0 +1,182 @@
+use crate::{
+    bundle::{Bundle, Path},
+    eval::{EvalError, Evaluator, int_bits},
+    layout::{LayoutEngine, LayoutError},
+    resolve::{Item, ResolveError, expand_type, module_of, resolve_name},
+    target::TargetSpec,
+    tydef::{TypeDef, TypeDefBody},
+    uses::{Signature, Type},
+};
+
+/// The bundle and module that the names in a type or signature are resolved in
+#[derive(Copy, Clone, Debug)]
+pub struct Scope<'a> {
+    pub bundle: &'a Bundle,
+    pub module: &'a Path,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(bundle: &'a Bundle, module: &'a Path) -> Self {
+        Self { bundle, module }
+    }
+}
+
+impl Signature {
+    /// Whether a function declared with `self` (seen from `this`) can be called through `other` (seen from `that`) on `target`, and vice versa.
+    ///
+    /// Signatures are compared up to alias expansion, and up to types that are passed the same way:
+    /// * Integers, characters, bytes, and enums are compared by their width on `target` and signedness,
+    /// * Pointers are compared by the type they point to, regardless of their kind,
+    /// * Named types are compared by the path they are defined at and their layout, so a struct that changed layout is not compatible with itself,
+    /// * `void` and `!` are interchangeable, as is `uninit(T)` and `T`.
+    ///
+    /// Parameter names and attributes are not compared.
+    pub fn abi_eq(&self, other: &Signature, target: &TargetSpec, this: Scope, that: Scope) -> bool {
+        AbiComparer::new([this.bundle, that.bundle], target)
+            .sig_eq([this.module, that.module], [self, other])
+    }
+}
+
+struct Side<'a> {
+    bundle: &'a Bundle,
+    layout: LayoutEngine<'a>,
+    eval: Evaluator<'a>,
+}
+
+struct AbiComparer<'a> {
+    target: &'a TargetSpec,
+    sides: [Side<'a>; 2],
+}
+
+impl<'a> AbiComparer<'a> {
+    fn new(bundles: [&'a Bundle; 2], target: &'a TargetSpec) -> Self {
+        Self {
+            target,
+            sides: bundles.map(|bundle| Side {
+                bundle,
+                layout: LayoutEngine::new(bundle, target),
+                eval: Evaluator::new(bundle, target),
+            }),
+        }
+    }
+
+    fn sig_eq(&mut self, modules: [&Path; 2], [a, b]: [&Signature; 2]) -> bool {
+        a.params.len() == b.params.len()
+            && a.params
+                .iter()
+                .zip(&b.params)
+                .all(|(pa, pb)| self.ty_eq(modules, [&pa.ty, &pb.ty]))
+            && self.ty_eq(modules, [&a.retty, &b.retty])
+    }
+
+    /// Replaces type aliases at the head of `ty` with the type they alias, returning that type and the module it is seen from
+    fn unalias(&self, side: usize, module: &Path, ty: &Type) -> Result<(Path, Type), ResolveError> {
+        let bundle = self.sides[side].bundle;
+        let mut visiting = Vec::new();
+        let (mut module, mut ty) = (module.clone(), ty.clone());
+        while let Type::Named(name, args) = &ty {
+            let resolved = resolve_name(bundle, &module, name)?;
+            let Item::Type(TypeDef {
+                body: TypeDefBody::Alias(alias),
+                ..
+            }) = resolved.item
+            else {
+                break;
+            };
+            if args.is_some() {
+                // The arguments of generic aliases are seen from the current module, so expand them all at once
+                return Ok((module.clone(), expand_type(bundle, &module, &ty)?));
+            }
+            if visiting.contains(&resolved.path) {
+                visiting.push(resolved.path);
+                return Err(ResolveError::Cycle(visiting));
+            }
+            visiting.push(resolved.path.clone());
+            module = module_of(&resolved.path);
+            ty = alias.alias.clone();
+        }
+        Ok((module, ty))
+    }
+
+    /// The width and signedness of `ty`, if it is passed as an integer
+    fn scalar(&self, side: usize, module: &Path, ty: &Type) -> Option<(u32, bool)> {
+        match ty {
+            Type::Int(int) | Type::Char(int) => Some((int_bits(*int, self.target), int.signed)),
+            Type::Byte => Some((8, false)),
+            Type::Named(name, None) => match resolve_name(self.sides[side].bundle, module, name) {
+                Ok(resolved) => match resolved.item {
+                    Item::Type(TypeDef {
+                        body: TypeDefBody::Enum(en),
+                        ..
+                    }) => Some((int_bits(en.underlying, self.target), en.underlying.signed)),
+                    _ => None,
+                },
+                Err(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn ty_eq(&mut self, [ma, mb]: [&Path; 2], [a, b]: [&Type; 2]) -> bool {
+        let (Ok((ma, a)), Ok((mb, b))) = (self.unalias(0, ma, a), self.unalias(1, mb, b)) else {
+            return false;
+        };
+        let modules = [&ma, &mb];
+
+        if let (Some(sa), Some(sb)) = (self.scalar(0, &ma, &a), self.scalar(1, &mb, &b)) {
+            return sa == sb;
+        }
+
+        match (&a, &b) {
+            (Type::Uninit(a), b) => self.ty_eq(modules, [a, b]),
+            (a, Type::Uninit(b)) => self.ty_eq(modules, [a, b]),
+            (Type::Named(na, aa), Type::Named(nb, ab)) => {
+                let (Ok(ra), Ok(rb)) = (
+                    resolve_name(self.sides[0].bundle, &ma, na),
+                    resolve_name(self.sides[1].bundle, &mb, nb),
+                ) else {
+                    return false;
+                };
+                let (aa, ab) = (aa.as_deref().unwrap_or(&[]), ab.as_deref().unwrap_or(&[]));
+                ra.path == rb.path
+                    && aa.len() == ab.len()
+                    && aa.iter().zip(ab).all(|(x, y)| self.ty_eq(modules, [x, y]))
+                    && self.layout_eq(modules, [&a, &b])
+            }
+            (Type::Param(x, _), Type::Param(y, _)) => x == y,
+            (Type::Pointer(_, a), Type::Pointer(_, b)) => self.ty_eq(modules, [a, b]),
+            (Type::Func(a), Type::Func(b)) => self.sig_eq(modules, [a, b]),
+            (Type::Void | Type::Never, Type::Void | Type::Never) => true,
+            (Type::Array(a), Type::Array(b)) => {
+                let la = self.sides[0].eval.eval(&ma, &a.len);
+                let lb = self.sides[1].eval.eval(&mb, &b.len);
+                let len_eq = match (la, lb) {
+                    (Ok(la), Ok(lb)) => la == lb,
+                    // Lengths that refer to generic parameters are compared as written
+                    (Err(EvalError::UnboundParam(_)), Err(EvalError::UnboundParam(_))) => {
+                        a.len == b.len
+                    }
+                    _ => false,
+                };
+                len_eq && self.ty_eq(modules, [&a.base, &b.base])
+            }
+            (Type::Const(a), Type::Const(b)) => {
+                let la = self.sides[0].eval.eval(&ma, a);
+                let lb = self.sides[1].eval.eval(&mb, b);
+                matches!((la, lb), (Ok(la), Ok(lb)) if la == lb)
+            }
+            _ => false,
+        }
+    }
+
+    fn layout_eq(&mut self, [ma, mb]: [&Path; 2], [a, b]: [&Type; 2]) -> bool {
+        let la = self.sides[0].layout.layout_of(ma, a);
+        let lb = self.sides[1].layout.layout_of(mb, b);
+        match (la, lb) {
+            (Ok(la), Ok(lb)) => la == lb,
+            // Opaque types are only passed behind pointers, so their layout does not matter
+            (Err(LayoutError::Unsized(_)), Err(LayoutError::Unsized(_))) => true,
+            _ => false,
+        }
+    }
+}
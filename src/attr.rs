@@ -2,6 +2,8 @@ use core::any::Any;
 use std::{borrow::Cow, hash::Hash, marker::PhantomData};
 
 use crate::uuid::Uuid;
+use schema::{Describe, Schema};
+
 use bincode::{
     BorrowDecode, Decode, Encode,
     de::read::Reader,
@@ -12,12 +14,169 @@ use bincode::{
 bitflags::bitflags! {
     #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
     pub struct AttributeFlags : u32 {
+        /// Readers that don't recognize the attribute may skip it
         const IGNORE = 0x0000_0001;
-
+        /// Code generators must recognize the attribute to produce correct output, even if it is [`AttributeFlags::IGNORE`]
+        const REQUIRED_FOR_CODEGEN = 0x0000_0002;
+        /// The payload uses a format that is retained for compatibility but will be removed
+        const DEPRECATED_PAYLOAD = 0x0000_0004;
+        /// The payload starts with a version byte (see [`AttributeType::VERSION`])
+        const VERSIONED = 0x0000_0008;
+        /// The payload (including its version byte, if any) is deflate-compressed.
+        /// Only honoured when encoding with the `compress` feature, which is worthwhile for large payloads such as long docs
+        const COMPRESSED = 0x0000_0010;
+
+        /// The [`AttributeClass`] of the attribute
         const TYPE_MASK = 0xFF80_0000;
     }
 }
 
+/// The most bytes that a payload with [`AttributeFlags::COMPRESSED`] is decompressed to. Larger payloads are rejected,
+/// so that a small attribute that decompresses to far more data cannot exhaust memory
+#[cfg(feature = "compress")]
+pub const MAX_DECOMPRESSED_PAYLOAD: usize = 16 << 20;
+
+const TYPE_SHIFT: u32 = AttributeFlags::TYPE_MASK.bits().trailing_zeros();
+
+impl AttributeFlags {
+    pub const fn type_class(self) -> AttributeClass {
+        AttributeClass(((self.bits() & Self::TYPE_MASK.bits()) >> TYPE_SHIFT) as u16)
+    }
+
+    pub const fn with_type_class(self, class: AttributeClass) -> Self {
+        Self::from_bits_retain(
+            (self.bits() & !Self::TYPE_MASK.bits()) | ((class.0 as u32) << TYPE_SHIFT),
+        )
+    }
+}
+
+/// Broad category of an attribute, stored in [`AttributeFlags::TYPE_MASK`].
+/// Lets readers reason about attributes they don't recognize.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct AttributeClass(u16);
+
+impl AttributeClass {
+    pub const UNSPECIFIED: AttributeClass = AttributeClass(0);
+    /// Documentation for humans, with no effect on the interface
+    pub const DOCUMENTATION: AttributeClass = AttributeClass(1);
+    /// Affects the layout or calling convention of the item
+    pub const ABI: AttributeClass = AttributeClass(2);
+    /// Affects the meaning of the item without changing its ABI
+    pub const SEMANTIC: AttributeClass = AttributeClass(3);
+    /// Information for tools that process IMT files
+    pub const TOOLING: AttributeClass = AttributeClass(4);
+
+    pub const fn new(class: u16) -> Self {
+        assert!(class < 512, "Attribute class must be less than 512");
+        Self(class)
+    }
+
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+}
+
+impl core::fmt::Display for AttributeClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            AttributeClass::UNSPECIFIED => f.write_str("unspecified"),
+            AttributeClass::DOCUMENTATION => f.write_str("documentation"),
+            AttributeClass::ABI => f.write_str("abi"),
+            AttributeClass::SEMANTIC => f.write_str("semantic"),
+            AttributeClass::TOOLING => f.write_str("tooling"),
+            AttributeClass(class) => f.write_fmt(format_args!("class {class}")),
+        }
+    }
+}
+
+/// The reason an attribute could not be decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeDecodeError {
+    IllegalFlags(AttributeFlags),
+    /// The attribute's flags declare a class that differs from the class of the recognized attribute type
+    ClassMismatch {
+        id: Uuid,
+        class: AttributeClass,
+        declared: AttributeClass,
+    },
+    InvalidCompressedPayload {
+        id: Uuid,
+        reason: String,
+    },
+    /// The payload is compressed, and imt was built without the `compress` feature
+    CompressionUnsupported(Uuid),
+    MissingPayloadVersion(Uuid),
+    /// The payload version is newer than the version supported by the attribute type
+    UnsupportedPayloadVersion {
+        id: Uuid,
+        version: u8,
+        supported: u8,
+    },
+    /// The attribute type cannot migrate payloads of an older version
+    UnmigratablePayloadVersion {
+        id: Uuid,
+        version: u8,
+    },
+    /// The attribute is not recognized and does not have [`AttributeFlags::IGNORE`] set
+    Unrecognized(Uuid),
+    TrailingBytes {
+        expected: usize,
+        read: usize,
+    },
+}
+
+impl core::fmt::Display for AttributeDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeDecodeError::IllegalFlags(flags) => {
+                f.write_fmt(format_args!("Flags {flags:?} sets illegal flags"))
+            }
+            AttributeDecodeError::ClassMismatch {
+                id,
+                class,
+                declared,
+            } => f.write_fmt(format_args!(
+                "Attribute with id {id} has class {}, but is declared with class {}",
+                class.get(),
+                declared.get()
+            )),
+            AttributeDecodeError::InvalidCompressedPayload { id, reason } => f.write_fmt(
+                format_args!("Attribute with id {id} has an invalid compressed payload: {reason}"),
+            ),
+            AttributeDecodeError::CompressionUnsupported(id) => f.write_fmt(format_args!(
+                "Attribute with id {id} is compressed, but imt was built without the compress feature"
+            )),
+            AttributeDecodeError::MissingPayloadVersion(id) => f.write_fmt(format_args!(
+                "Versioned attribute with id {id} is missing its payload version"
+            )),
+            AttributeDecodeError::UnsupportedPayloadVersion {
+                id,
+                version,
+                supported,
+            } => f.write_fmt(format_args!(
+                "Attribute with id {id} has payload version {version}, but at most version {supported} is supported"
+            )),
+            AttributeDecodeError::UnmigratablePayloadVersion { id, version } => f.write_fmt(
+                format_args!("Attribute with id {id} does not support payload version {version}"),
+            ),
+            AttributeDecodeError::Unrecognized(id) => f.write_fmt(format_args!(
+                "Non-ignorable attribute with id {id} is not recognized"
+            )),
+            AttributeDecodeError::TrailingBytes { expected, read } => f.write_fmt(format_args!(
+                "Extra slop found on stream. Expected {expected} bytes, got {read}"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for AttributeDecodeError {}
+
+impl From<AttributeDecodeError> for DecodeError {
+    fn from(value: AttributeDecodeError) -> Self {
+        DecodeError::OtherString(value.to_string())
+    }
+}
+
 impl<C> Decode<C> for AttributeFlags {
     fn decode<D: bincode::de::Decoder<Context = C>>(
         decoder: &mut D,
@@ -25,10 +184,7 @@ impl<C> Decode<C> for AttributeFlags {
         let v = u32::decode(decoder)?;
 
         AttributeFlags::from_bits(v).ok_or_else(|| {
-            DecodeError::OtherString(format!(
-                "Flags {:?} sets illegal flags",
-                AttributeFlags::from_bits_retain(v)
-            ))
+            AttributeDecodeError::IllegalFlags(AttributeFlags::from_bits_retain(v)).into()
         })
     }
 }
@@ -47,13 +203,48 @@ impl Encode for AttributeFlags {
     }
 }
 
-#[derive(Clone, Encode)]
+#[derive(Clone)]
 pub struct Attribute<Targ> {
     id: Uuid,
     flags: AttributeFlags,
     payload: ErasedAttributeContent<Targ>,
 }
 
+impl<Targ> Encode for Attribute<Targ> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        // Unknown payloads retain their version byte (if any) and compression, so only recognized attributes need the flags fixed up
+        let (flags, bytes): (_, Cow<[u8]>) = match &self.payload {
+            ErasedAttributeContent::Real(attr, _) => {
+                let flags = if attr.version() != 0 {
+                    self.flags | AttributeFlags::VERSIONED
+                } else {
+                    self.flags - AttributeFlags::VERSIONED
+                };
+                let bytes = attr.to_bytes()?;
+                #[cfg(feature = "compress")]
+                let bytes = if flags.contains(AttributeFlags::COMPRESSED) {
+                    miniz_oxide::deflate::compress_to_vec(&bytes, 6)
+                } else {
+                    bytes
+                };
+                #[cfg(not(feature = "compress"))]
+                let flags = flags - AttributeFlags::COMPRESSED;
+                (flags, Cow::Owned(bytes))
+            }
+            ErasedAttributeContent::Unknown(bytes) => (self.flags, Cow::Borrowed(&**bytes)),
+        };
+
+        let len: u32 = bytes.len().try_into().map_err(|_| {
+            EncodeError::Other("Attribute length limit supports no more than 2^32 bytes")
+        })?;
+
+        self.id.encode(encoder)?;
+        flags.encode(encoder)?;
+        len.encode(encoder)?;
+        encoder.writer().write(&bytes)
+    }
+}
+
 impl<Targ> core::hash::Hash for Attribute<Targ> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -114,7 +305,12 @@ impl<Targ: AttributeTarget> Attribute<Targ> {
     pub fn new<T: Target<Targ> + Sync>(x: T) -> Self {
         Attribute {
             id: T::ID,
-            flags: AttributeFlags::empty(),
+            flags: if T::VERSION != 0 {
+                AttributeFlags::VERSIONED
+            } else {
+                AttributeFlags::empty()
+            }
+            .with_type_class(T::CLASS),
             payload: ErasedAttributeContent::Real(Box::new(x), PhantomData),
         }
     }
@@ -130,6 +326,51 @@ impl<Targ: AttributeTarget> Attribute<Targ> {
         }
     }
 
+    pub fn downcast_mut<T: AttributeType>(&mut self) -> Option<&mut T> {
+        if self.id != T::ID {
+            return None;
+        }
+
+        match &mut self.payload {
+            ErasedAttributeContent::Real(real, _) => <dyn Any>::downcast_mut(&mut **real),
+            _ => None,
+        }
+    }
+
+    /// The payload of a recognized attribute, encoded without its version byte, with the [`Schema`] that describes it.
+    /// `None` if the attribute is unknown, as its payload cannot be described
+    pub fn described_payload(&self) -> Option<Result<(Schema, Vec<u8>), EncodeError>> {
+        match &self.payload {
+            ErasedAttributeContent::Real(attr, _) => {
+                Some(attr.encode_payload().map(|bytes| (attr.schema(), bytes)))
+            }
+            ErasedAttributeContent::Unknown(_) => None,
+        }
+    }
+
+    /// Creates the recognized attribute `id` from its payload, encoded without its version byte as [`Attribute::described_payload`] returns it.
+    /// `None` if the attribute is not recognized, or cannot be attached to `Targ`
+    pub fn from_payload(id: Uuid, bytes: &[u8]) -> Option<Result<Self, DecodeError>> {
+        let mut attr = create_attribute_blob::<Targ>(id)?;
+        Some(
+            attr.decode_into(bytes, attr.version(), UnknownAttributePolicy::Error)
+                .map(|()| Attribute {
+                    id,
+                    flags: if attr.version() != 0 {
+                        AttributeFlags::VERSIONED
+                    } else {
+                        AttributeFlags::empty()
+                    }
+                    .with_type_class(attr.class()),
+                    payload: ErasedAttributeContent::Real(attr, PhantomData),
+                }),
+        )
+    }
+
+    pub const fn is_unknown(&self) -> bool {
+        matches!(self.payload, ErasedAttributeContent::Unknown(_))
+    }
+
     pub const fn id(&self) -> &Uuid {
         &self.id
     }
@@ -148,12 +389,32 @@ impl<Targ: AttributeTarget> Attribute<Targ> {
     }
 }
 
-impl<C, Targ: AttributeTarget> Decode<C> for Attribute<Targ> {
-    fn decode<D: bincode::de::Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+/// How to decode attributes that are not recognized and do not have [`AttributeFlags::IGNORE`] set.
+///
+/// This includes recognized attributes with a payload version newer than supported.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default)]
+pub enum UnknownAttributePolicy {
+    /// Fail to decode the file
+    #[default]
+    Error,
+    /// Keep the attribute as an unknown attribute.
+    /// [`AttributeAudit`](crate::audit::AttributeAudit) can be used to report the preserved attributes
+    WarnAndPreserve,
+    /// Discard the attribute. Decoding a [`File`](crate::file::File) or [`FileMetadata`](crate::file::FileMetadata) removes these attributes
+    Drop,
+}
+
+/// Items that contain attributes are decoded with the [`UnknownAttributePolicy`] to use as the bincode context,
+/// such as with [`bincode::decode_from_slice_with_context`].
+impl<Targ: AttributeTarget> Decode<UnknownAttributePolicy> for Attribute<Targ> {
+    fn decode<D: bincode::de::Decoder<Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
         let id = Uuid::decode(decoder)?;
         let flags = AttributeFlags::decode(decoder)?;
 
-        let mut decoder = decoder.with_context((flags, id));
+        let policy = *decoder.context();
+        let mut decoder = decoder.with_context((flags, id, policy));
 
         let payload = ErasedAttributeContent::decode(&mut decoder)?;
 
@@ -161,8 +422,8 @@ impl<C, Targ: AttributeTarget> Decode<C> for Attribute<Targ> {
     }
 }
 
-impl<'de, C, Targ: AttributeTarget> BorrowDecode<'de, C> for Attribute<Targ> {
-    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = C>>(
+impl<'de, Targ: AttributeTarget> BorrowDecode<'de, UnknownAttributePolicy> for Attribute<Targ> {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = UnknownAttributePolicy>>(
         decoder: &mut D,
     ) -> Result<Self, DecodeError> {
         Self::decode(decoder)
@@ -183,47 +444,94 @@ impl<Targ> Clone for ErasedAttributeContent<Targ> {
     }
 }
 
-impl<Targ> Encode for ErasedAttributeContent<Targ> {
-    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
-        let bytes: Cow<[u8]> = match self {
-            Self::Real(attr, _) => Cow::Owned(attr.to_bytes()?),
-            Self::Unknown(bytes) => Cow::Borrowed(&**bytes),
-        };
-
-        let len: u32 = bytes.len().try_into().map_err(|_| {
-            EncodeError::Other("Attribute length limit supports no more than 2^32 bytes")
-        })?;
-
-        len.encode(encoder)?;
-        encoder.writer().write(&bytes)
-    }
-}
-
-impl<Targ: AttributeTarget> Decode<(AttributeFlags, Uuid)> for ErasedAttributeContent<Targ> {
-    fn decode<D: bincode::de::Decoder<Context = (AttributeFlags, Uuid)>>(
+impl<Targ: AttributeTarget> Decode<(AttributeFlags, Uuid, UnknownAttributePolicy)>
+    for ErasedAttributeContent<Targ>
+{
+    fn decode<D: bincode::de::Decoder<Context = (AttributeFlags, Uuid, UnknownAttributePolicy)>>(
         decoder: &mut D,
     ) -> Result<Self, DecodeError> {
-        let (flags, id) = *decoder.context();
+        let (flags, id, policy) = *decoder.context();
+        let preserve =
+            flags.contains(AttributeFlags::IGNORE) || policy != UnknownAttributePolicy::Error;
 
-        let mut attr = create_attribute_blob::<Targ>(id);
+        let attr = create_attribute_blob::<Targ>(id);
 
         let data_len = u32::decode(decoder)? as usize;
 
-        let mut data = Vec::with_capacity(data_len);
-        data.resize(data_len, 0u8);
+        let mut data = vec![0u8; data_len];
         decoder.reader().read(&mut data)?;
 
         match attr {
             Some(mut attr) => {
-                attr.from_bytes(&data)?;
+                let class = flags.type_class();
+                if class != AttributeClass::UNSPECIFIED && class != attr.class() {
+                    return Err(AttributeDecodeError::ClassMismatch {
+                        id,
+                        class: attr.class(),
+                        declared: class,
+                    }
+                    .into());
+                }
+
+                let payload = if flags.contains(AttributeFlags::COMPRESSED) {
+                    #[cfg(feature = "compress")]
+                    {
+                        let payload = miniz_oxide::inflate::decompress_to_vec_with_limit(
+                            &data,
+                            MAX_DECOMPRESSED_PAYLOAD,
+                        );
+                        Cow::Owned(payload.map_err(|e| {
+                            let reason = if e.status
+                                == miniz_oxide::inflate::TINFLStatus::HasMoreOutput
+                            {
+                                format!(
+                                    "it decompresses to more than {MAX_DECOMPRESSED_PAYLOAD} bytes"
+                                )
+                            } else {
+                                e.to_string()
+                            };
+                            AttributeDecodeError::InvalidCompressedPayload { id, reason }
+                        })?)
+                    }
+                    #[cfg(not(feature = "compress"))]
+                    {
+                        if preserve {
+                            return Ok(Self::Unknown(data));
+                        }
+                        return Err(AttributeDecodeError::CompressionUnsupported(id).into());
+                    }
+                } else {
+                    Cow::Borrowed(&*data)
+                };
+
+                let (version, payload_start) = if flags.contains(AttributeFlags::VERSIONED) {
+                    let version = *payload
+                        .first()
+                        .ok_or(AttributeDecodeError::MissingPayloadVersion(id))?;
+                    (version, 1)
+                } else {
+                    (0, 0)
+                };
+
+                if version > attr.version() {
+                    if preserve {
+                        return Ok(Self::Unknown(data));
+                    }
+                    return Err(AttributeDecodeError::UnsupportedPayloadVersion {
+                        id,
+                        version,
+                        supported: attr.version(),
+                    }
+                    .into());
+                }
+
+                attr.decode_into(&payload[payload_start..], version, policy)?;
 
                 Ok(Self::Real(attr, PhantomData))
             }
             None => {
-                if !flags.contains(AttributeFlags::IGNORE) {
-                    return Err(DecodeError::OtherString(format!(
-                        "Non-ignorable attribute with id {id} is not recognized"
-                    )));
+                if !preserve {
+                    return Err(AttributeDecodeError::Unrecognized(id).into());
                 }
 
                 Ok(Self::Unknown(data))
@@ -237,10 +545,27 @@ pub trait AttributeTarget {
 }
 
 macro_rules! def_attribute_targets {
-    ($(target $name:ident;)*) => {
+    ($(target $name:ident;)* $(generic $generic:ident = [$($member:ident),*];)*) => {
         #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
         pub enum AttributeTargetKind {
-            $($name),*
+            $($name,)*
+            $($generic,)*
+        }
+
+        impl AttributeTargetKind {
+            /// The kinds of items an attribute declared for `self` can be attached to.
+            /// A generic kind, such as [`AttributeTargetKind::TypeDef`], covers each of its member kinds
+            pub const fn members(self) -> &'static [AttributeTargetKind] {
+                match self {
+                    $(Self:: $name => &[Self:: $name],)*
+                    $(Self:: $generic => &[$(Self:: $member),*],)*
+                }
+            }
+
+            /// Whether an attribute declared for `self` can be attached to an item of the kind `kind`
+            pub fn includes(self, kind: AttributeTargetKind) -> bool {
+                self.members().contains(&kind)
+            }
         }
 
         $(impl AttributeTarget for $name {
@@ -252,7 +577,7 @@ macro_rules! def_attribute_targets {
 use crate::{
     file::{File, UseItem},
     tydef::{Enum, Field, Struct, TypeAlias, Union, Variant},
-    uses::Param,
+    uses::{Param, ReturnValue},
     value::{Const, Function},
 };
 
@@ -268,23 +593,94 @@ def_attribute_targets! {
     target Const;
     target Function;
     target Param;
+    target ReturnValue;
+    generic TypeDef = [Struct, Union, Enum, TypeAlias];
 }
 
 pub trait Target<T: AttributeTarget>: AttributeType {}
 
+pub fn find_attribute<T: Target<Targ>, Targ: AttributeTarget>(
+    attrs: &[Attribute<Targ>],
+) -> Option<&T> {
+    attrs.iter().find_map(Attribute::downcast)
+}
+
+pub fn find_attribute_mut<T: Target<Targ>, Targ: AttributeTarget>(
+    attrs: &mut [Attribute<Targ>],
+) -> Option<&mut T> {
+    attrs.iter_mut().find_map(Attribute::downcast_mut)
+}
+
+pub fn find_attributes<T: Target<Targ>, Targ: AttributeTarget>(
+    attrs: &[Attribute<Targ>],
+) -> impl Iterator<Item = &T> {
+    attrs.iter().filter_map(Attribute::downcast)
+}
+
 pub trait AttributeType:
-    Any + Clone + Hash + Eq + Encode + Decode<()> + Default + core::fmt::Debug + Sync + Send
+    Any
+    + Clone
+    + Hash
+    + Eq
+    + Encode
+    + Decode<UnknownAttributePolicy>
+    + Default
+    + Describe
+    + core::fmt::Debug
+    + Sync
+    + Send
 {
     const ID: Uuid;
     const TARGET: Option<&[AttributeTargetKind]>;
+    const CLASS: AttributeClass;
+
+    /// The current version of the payload format. Payloads with a nonzero version are prefixed with it when encoded
+    const VERSION: u8 = 0;
+
+    /// Decodes a payload encoded with an older `version` of the payload format
+    fn migrate(version: u8, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let _ = bytes;
+        Err(AttributeDecodeError::UnmigratablePayloadVersion {
+            id: Self::ID,
+            version,
+        }
+        .into())
+    }
+}
+
+/// Decodes a complete attribute payload, rejecting any trailing bytes.
+/// Attributes within the payload, such as those of a function type, are decoded according to `policy`
+pub fn decode_payload<T: Decode<UnknownAttributePolicy>>(
+    bytes: &[u8],
+    policy: UnknownAttributePolicy,
+) -> Result<T, DecodeError> {
+    let (val, read) =
+        bincode::decode_from_slice_with_context(bytes, crate::config::format_config(), policy)?;
+    if read != bytes.len() {
+        return Err(AttributeDecodeError::TrailingBytes {
+            expected: bytes.len(),
+            read,
+        }
+        .into());
+    }
+    Ok(val)
 }
 
 trait DynAttr: Any + Sync + Send {
     fn clone_box(&self) -> Box<dyn DynAttr>;
+    fn class(&self) -> AttributeClass;
     fn dyn_hash(&self, hasher: &mut dyn core::hash::Hasher);
     fn dyn_eq(&self, other: &dyn DynAttr) -> bool;
-    fn from_bytes(&mut self, bytes: &[u8]) -> Result<(), DecodeError>;
+    fn version(&self) -> u8;
+    fn decode_into(
+        &mut self,
+        bytes: &[u8],
+        version: u8,
+        policy: UnknownAttributePolicy,
+    ) -> Result<(), DecodeError>;
     fn to_bytes(&self) -> Result<Vec<u8>, EncodeError>;
+    fn schema(&self) -> Schema;
+    fn encode_payload(&self) -> Result<Vec<u8>, EncodeError>;
     fn fmt_debug<'a>(&self, f: &mut core::fmt::Formatter<'a>) -> core::fmt::Result;
 }
 
@@ -292,19 +688,42 @@ impl<A: AttributeType> DynAttr for A {
     fn clone_box(&self) -> Box<dyn DynAttr> {
         Box::new(self.clone())
     }
-    fn from_bytes(&mut self, bytes: &[u8]) -> Result<(), DecodeError> {
-        let (val, read) = bincode::decode_from_slice(bytes, crate::config::format_config())?;
-        if read != bytes.len() {
-            return Err(DecodeError::OtherString(format!(
-                "Extra slop found on stream. Expected {} bytes, got {read}",
-                bytes.len()
-            )));
-        }
-        *self = val;
+    fn class(&self) -> AttributeClass {
+        A::CLASS
+    }
+    fn version(&self) -> u8 {
+        A::VERSION
+    }
+
+    fn decode_into(
+        &mut self,
+        bytes: &[u8],
+        version: u8,
+        policy: UnknownAttributePolicy,
+    ) -> Result<(), DecodeError> {
+        *self = if version == A::VERSION {
+            decode_payload(bytes, policy)?
+        } else {
+            A::migrate(version, bytes)?
+        };
         Ok(())
     }
 
     fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        if A::VERSION == 0 {
+            return bincode::encode_to_vec(self, crate::config::format_config());
+        }
+
+        let mut bytes = vec![A::VERSION];
+        bincode::encode_into_std_write(self, &mut bytes, crate::config::format_config())?;
+        Ok(bytes)
+    }
+
+    fn schema(&self) -> Schema {
+        A::SCHEMA
+    }
+
+    fn encode_payload(&self) -> Result<Vec<u8>, EncodeError> {
         bincode::encode_to_vec(self, crate::config::format_config())
     }
 
@@ -331,9 +750,34 @@ impl core::fmt::Debug for dyn DynAttr {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct AttributeInfo {
+    pub name: &'static str,
+    pub id: Uuid,
+    pub targets: Option<&'static [AttributeTargetKind]>,
+    pub class: AttributeClass,
+    pub version: u8,
+    pub schema: Schema,
+}
+
+/// Every attribute type recognized by this version of imt
+pub fn registered_attributes() -> &'static [AttributeInfo] {
+    REGISTERED_ATTRIBUTES
+}
+
+pub fn attribute_info(id: Uuid) -> Option<&'static AttributeInfo> {
+    REGISTERED_ATTRIBUTES.iter().find(|info| info.id == id)
+}
+
 macro_rules! impl_target {
     (attr $ty:path [$($target:ident),*]) => {
-        $(impl Target<$target> for $ty{})*
+        $(impl_target!(kind $ty, $target);)*
+    };
+    (kind $ty:path, TypeDef) => {
+        impl_target!(attr $ty [Struct, Union, Enum, TypeAlias]);
+    };
+    (kind $ty:path, $target:ident) => {
+        impl Target<$target> for $ty{}
     };
     (attr $ty:path) => {
         impl<T: AttributeTarget> Target<T> for $ty{}
@@ -341,21 +785,40 @@ macro_rules! impl_target {
 }
 
 macro_rules! attribute_types {
-    ($(attr $ty:path = $id:literal $([$($target:ident),* $(,)?])?;)*) => {
+    ($($(#[$meta:meta])* attr $ty:path = $id:literal $([$($target:ident),* $(,)?])? : $class:ident $(version $version:literal ($migrate:path))?;)*) => {
         $(
+            $(#[$meta])*
             impl AttributeType for $ty {
                 const ID: Uuid = Uuid::parse($id);
                 const TARGET: Option<&[AttributeTargetKind]> = ($(Some(&[$(AttributeTargetKind:: $target),*] as &[AttributeTargetKind]),)? None::<&[AttributeTargetKind]>,).0;
+                const CLASS: AttributeClass = AttributeClass:: $class;
+                $(
+                    const VERSION: u8 = $version;
+
+                    fn migrate(version: u8, bytes: &[u8]) -> Result<Self, DecodeError> {
+                        $migrate(version, bytes)
+                    }
+                )?
             }
+            $(#[$meta])*
             impl_target!(attr $ty $([$($target),*])?);
         )*
 
+        static REGISTERED_ATTRIBUTES: &[AttributeInfo] = &[$($(#[$meta])* AttributeInfo {
+            name: stringify!($ty),
+            id: <$ty as AttributeType>::ID,
+            targets: <$ty as AttributeType>::TARGET,
+            class: <$ty as AttributeType>::CLASS,
+            version: <$ty as AttributeType>::VERSION,
+            schema: <$ty as Describe>::SCHEMA,
+        }),*];
+
         fn create_attribute_blob<__T: AttributeTarget>(id: Uuid) -> Option<Box<dyn DynAttr>> {
             match id {
-                $(<$ty as AttributeType>::ID if (
+                $($(#[$meta])* <$ty as AttributeType>::ID if (
                     match <$ty as AttributeType>::TARGET {
                         Some(arr) => {
-                            arr.contains(&<__T as AttributeTarget>::KIND)
+                            arr.iter().any(|kind| kind.includes(<__T as AttributeTarget>::KIND))
                         }
                         None => true
                     }
@@ -367,19 +830,58 @@ macro_rules! attribute_types {
 }
 
 // v5 based on fcdc6c4f-f218-5a30-a2e5-7e8d7d2a38a6
+// Attributes whose payload has changed since it was introduced are declared with `version <n> (<migrate fn>)`
 attribute_types! {
-    attr types::SafetyHint = "8649000c-291a-566c-b171-0da33515ea61" [Function];
-    attr types::OptionType = "74404322-8d86-5623-93b0-2a8659f9cd09" [Struct];
-    attr types::PolymorphicOption = "3072a4e5-598e-55dc-9824-bbc1da1ccaea" [Struct];
-    attr types::ItemDoc = "a5a3cce8-4f49-5084-9761-36603109808a";
-    attr types::SubsystemDescriptor = "50f98361-bbf6-5f10-8594-7354b4c7c313" [File];
-    attr types::SystemFunction = "c130fb9b-ed3f-55e7-9bf9-2ae163bfc4d3" [Function];
-    attr types::ExportInline = "df372d18-045d-5d4e-8aad-26db0300c707" [UseItem];
-    attr types::DefinesBuiltinTypes = "360cb09a-155e-5bc9-ac7b-d8cb6662687a" [File];
-    attr types::ToolComment = "d6ade778-923c-573d-8c88-948fb053d49b" [File];
-    attr types::Align = "c9c12154-f381-5d48-88e1-ce31d9d1bd1f" [Struct, Union];
-    attr types::Synthetic = "5d4ceb6f-dc75-581c-ba8e-d014a77091fe";
-    attr types::OptionBaseType = "9ad6f840-9415-511d-80de-5cb77002f1d7" [Struct];
+    attr types::SafetyHint = "8649000c-291a-566c-b171-0da33515ea61" [Function] : SEMANTIC;
+    attr types::OptionType = "74404322-8d86-5623-93b0-2a8659f9cd09" [Struct] : SEMANTIC;
+    attr types::PolymorphicOption = "3072a4e5-598e-55dc-9824-bbc1da1ccaea" [Struct] : SEMANTIC;
+    attr types::ItemDoc = "a5a3cce8-4f49-5084-9761-36603109808a" : DOCUMENTATION;
+    attr types::SubsystemDescriptor = "50f98361-bbf6-5f10-8594-7354b4c7c313" [File] : ABI;
+    attr types::SystemFunction = "c130fb9b-ed3f-55e7-9bf9-2ae163bfc4d3" [Function] : ABI;
+    attr types::ExportInline = "df372d18-045d-5d4e-8aad-26db0300c707" [UseItem] : SEMANTIC;
+    attr types::DefinesBuiltinTypes = "360cb09a-155e-5bc9-ac7b-d8cb6662687a" [File] : SEMANTIC;
+    attr types::ToolComment = "d6ade778-923c-573d-8c88-948fb053d49b" [File] : TOOLING;
+    attr types::Align = "c9c12154-f381-5d48-88e1-ce31d9d1bd1f" [Struct, Union] : ABI;
+    attr types::Synthetic = "5d4ceb6f-dc75-581c-ba8e-d014a77091fe" : TOOLING;
+    attr types::OptionBaseType = "9ad6f840-9415-511d-80de-5cb77002f1d7" [Struct] : SEMANTIC;
+    attr types::Feature = "d420fb8a-5967-516c-8168-71d25f0a2ae7" [File, UseItem, TypeDef, Variant, Const, Function] : SEMANTIC;
+    attr types::Requires = "35ac0cb4-26bb-5dc5-8123-d45bedf577f4" [File] : SEMANTIC;
+    attr types::ParamDirection = "d6db1f23-5a7a-554f-b3ce-b5d51e987c03" [Param] : SEMANTIC;
+    attr types::LengthOf = "49062002-15ac-5422-8eb5-f980eae18e17" [Param] : SEMANTIC;
+    attr types::NulTerminated = "60e2a35d-cb9c-5b58-947b-8b6cdac8802a" [Param] : SEMANTIC;
+    attr types::ErrorReturn = "a5b499d2-07bc-54dd-bf3e-03e589881758" [Function] : SEMANTIC;
+    attr types::TargetCfg = "bdde767f-c513-5918-ab10-00459cf92498" [File, UseItem, TypeDef, Field, Variant, Const, Function] : SEMANTIC;
+    attr types::SpecialPointer = "68f77e4c-d673-5ca2-b801-631e06739207" [File] : ABI;
+    attr types::SpecialConstant = "a8ab3cc7-d843-54d0-b1ca-6f6f6c4b16a1" [File] : ABI;
+    attr types::MustUse = "2de08206-785b-5848-84a4-67cb73796af1" [Function, ReturnValue] : SEMANTIC;
+    attr types::Concurrency = "68732ed2-e3b6-50be-893d-93fe5afe95e6" [Function] : SEMANTIC;
+    attr types::BlockingHint = "71c5f995-1ae6-5aba-9c72-80a120d04a4c" [Function] : SEMANTIC;
+    attr types::Niche = "7235db72-50cf-5e78-8958-05aefd1c2e9e" [Struct, TypeAlias, Field] : SEMANTIC;
+    attr types::Endian = "5ad865fc-e7ab-56a6-bb1b-1bde1437a41c" [Field] : ABI;
+    attr types::DiscriminatedBy = "2fb8c824-df7d-5ef6-821a-48f04a773803" [Union] : SEMANTIC;
+    attr types::ActiveWhen = "1c53a0f2-4694-5ac7-afdb-d9d9ab1712cb" [Field] : SEMANTIC;
+    attr types::ParamDoc = "2511e468-bc46-53a9-ae45-5d3e3369053c" [Param] : DOCUMENTATION;
+    attr types::ReturnDoc = "6d9ceb42-7d1a-5587-823c-ad0f2e96a3b1" [ReturnValue] : DOCUMENTATION;
+    attr types::ErrorDoc = "0af9d7c4-85e1-5106-a843-39c536b5bfdc" [Function] : DOCUMENTATION;
+    attr types::ExampleDoc = "e370b061-0674-5a9c-a227-3b5344a65c15" : DOCUMENTATION;
+    attr types::LocalizedDoc = "66b5f585-8be1-5807-91d6-0274173850eb" : DOCUMENTATION;
+    attr types::DeriveHint = "48483344-d5c3-519f-888e-8b84c72d4492" [Struct, Enum] : SEMANTIC;
+    attr types::Deprecated = "bb4c9f47-0ec2-5105-990a-aa1e9860c537" [TypeDef, Field, Variant, Const, Function] : SEMANTIC;
+    attr types::LinkName = "6a90797a-94e0-5fe1-8ea7-c54edf7f0b65" [Function] : ABI;
+    attr types::IntroducedIn = "3c0e4ab4-afc6-5d48-900b-0b4c6781cd40" [TypeDef, Field, Variant, Const, Function] : SEMANTIC;
+    attr types::IpcMethod = "a43c1fab-f8ec-512b-972f-e8167305e74a" [Function] : ABI;
+    attr types::WireFormat = "e93cff30-e516-56b6-84ed-1d50f0cacfaf" [Struct] : ABI;
+    attr types::Reserved = "6f28d475-5288-557d-8fbf-7d30c4134677" [Field] : SEMANTIC;
+    attr types::SinceField = "dc67a6f3-2790-51eb-80ae-d6ea3a8108e7" [Field] : ABI;
+    attr types::Frozen = "e559668b-5082-5c60-9407-2b0c43a58a32" [Struct, Enum, Function] : ABI;
+    attr types::Stability = "e5404d83-9af4-5a31-b11c-eb9da67c3194" [File, TypeDef, Const, Function] : SEMANTIC;
+    attr types::DataClass = "ccbae8b2-c74a-5b66-8946-ac541f550580" [Param, Field] : SEMANTIC;
+    #[cfg(test)]
+    attr tests::Versioned = "0b4d1c8e-6a2f-5e37-9c15-3f8a7d6e2b90" [File] : TOOLING version 2 (tests::Versioned::migrate_v1);
 }
 
+pub mod schema;
 pub mod types;
+
+#[cfg(test)]
+mod tests;
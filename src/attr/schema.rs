@@ -0,0 +1,388 @@
+use bincode::{Decode, error::DecodeError};
+
+use crate::{
+    attr::{UnknownAttributePolicy, types::*},
+    config::format_config,
+    header::Version,
+    intern::Symbol,
+    uses::Type,
+    uuid::Uuid,
+};
+
+/// Machine-readable description of the encoded form of an attribute payload
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Schema {
+    Unit,
+    Bool,
+    UInt(u8),
+    Int(u8),
+    String,
+    Uuid,
+    Version,
+    Type,
+    Option(&'static Schema),
+    List(&'static Schema),
+    Struct(&'static [(&'static str, Schema)]),
+    /// Variants are encoded by index, followed by the variant's fields
+    Enum(&'static [(&'static str, Schema)]),
+}
+
+impl core::fmt::Display for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schema::Unit => f.write_str("()"),
+            Schema::Bool => f.write_str("bool"),
+            Schema::UInt(bits) => f.write_fmt(format_args!("u{bits}")),
+            Schema::Int(bits) => f.write_fmt(format_args!("i{bits}")),
+            Schema::String => f.write_str("string"),
+            Schema::Uuid => f.write_str("uuid"),
+            Schema::Version => f.write_str("version"),
+            Schema::Type => f.write_str("type"),
+            Schema::Option(inner) => f.write_fmt(format_args!("option<{inner}>")),
+            Schema::List(inner) => f.write_fmt(format_args!("[{inner}]")),
+            Schema::Struct([]) => f.write_str("struct {}"),
+            Schema::Struct(fields) => {
+                f.write_str("struct {")?;
+                let mut sep = " ";
+                for (name, schema) in *fields {
+                    f.write_fmt(format_args!("{sep}{name}: {schema}"))?;
+                    sep = ", ";
+                }
+                f.write_str(" }")
+            }
+            Schema::Enum(variants) => {
+                f.write_str("enum {")?;
+                let mut sep = " ";
+                for (name, schema) in *variants {
+                    f.write_str(sep)?;
+                    f.write_str(name)?;
+                    if *schema != Schema::Unit {
+                        f.write_fmt(format_args!("({schema})"))?;
+                    }
+                    sep = ", ";
+                }
+                f.write_str(" }")
+            }
+        }
+    }
+}
+
+pub trait Describe {
+    const SCHEMA: Schema;
+}
+
+macro_rules! describe_primitives {
+    ($($ty:ty => $schema:expr;)*) => {
+        $(impl Describe for $ty {
+            const SCHEMA: Schema = $schema;
+        })*
+    };
+}
+
+describe_primitives! {
+    () => Schema::Unit;
+    bool => Schema::Bool;
+    u8 => Schema::UInt(8);
+    u16 => Schema::UInt(16);
+    u32 => Schema::UInt(32);
+    u64 => Schema::UInt(64);
+    u128 => Schema::UInt(128);
+    i8 => Schema::Int(8);
+    i16 => Schema::Int(16);
+    i32 => Schema::Int(32);
+    i64 => Schema::Int(64);
+    i128 => Schema::Int(128);
+    String => Schema::String;
+    Symbol => Schema::String;
+    Uuid => Schema::Uuid;
+    Version => Schema::Version;
+    Type => Schema::Type;
+}
+
+impl<T: Describe> Describe for Option<T> {
+    const SCHEMA: Schema = Schema::Option(&T::SCHEMA);
+}
+
+impl<T: Describe> Describe for Vec<T> {
+    const SCHEMA: Schema = Schema::List(&T::SCHEMA);
+}
+
+macro_rules! describe {
+    ($(struct $ty:ident { $($field:ident : $field_ty:ty),* $(,)? })*) => {
+        $(impl Describe for $ty {
+            const SCHEMA: Schema = Schema::Struct(&[$((stringify!($field), <$field_ty as Describe>::SCHEMA)),*]);
+        })*
+    };
+    ($(enum $ty:ident { $($variant:ident),* $(,)? })*) => {
+        $(impl Describe for $ty {
+            const SCHEMA: Schema = Schema::Enum(&[$((stringify!($variant), Schema::Unit)),*]);
+        })*
+    };
+}
+
+describe! {
+    struct OptionType { option: Uuid }
+    struct PolymorphicOption {}
+    struct ItemDoc { doc_lines: Vec<Symbol> }
+    struct SubsystemDescriptor { subsys_id: Uuid, subsys_index: Option<u32>, version: Version, max_sysfn: u16 }
+    struct SystemFunction { function_id: u16 }
+    struct ExportInline {}
+    struct ToolComment { comment: String }
+    struct Align { alignment: u128 }
+    struct Synthetic {}
+    struct OptionBaseType { ty: Type }
+    struct Feature { name: String }
+    struct Requires { id: Uuid, version: Version }
+    struct LengthOf { param: String }
+    struct NulTerminated {}
+    struct ErrorReturn { error: Type }
+    struct TargetCfg { cfg: String }
+    struct SpecialPointer { id: Uuid, name: String, repr: SpecialPointerRepr }
+    struct SpecialConstant { id: Uuid, name: String, default: Option<u128> }
+    struct MustUse { reason: Option<String> }
+    struct DiscriminatedBy { field: String }
+    struct ActiveWhen { variant: String }
+    struct ParamDoc { doc_lines: Vec<Symbol> }
+    struct ReturnDoc { doc_lines: Vec<Symbol> }
+    struct ErrorDoc { error: String, doc_lines: Vec<Symbol> }
+    struct ExampleDoc { lang: String, code: Vec<Symbol> }
+    struct LocalizedDoc { lang: String, doc_lines: Vec<String> }
+    struct DeriveHint { traits: Vec<String> }
+    struct Deprecated { note: Option<String> }
+    struct LinkName { name: String }
+    struct IntroducedIn { version: Version }
+    struct IpcMethod { method_id: u32 }
+    struct WireFormat { endian: Endian }
+    struct Reserved { must_be_zero: bool }
+    struct SinceField { version: Version }
+    struct Frozen { since: Version }
+}
+
+describe! {
+    enum SafetyHint { NoHint, Safe, Unsafe }
+    enum DefinesBuiltinTypes { None, Handle, SysResult2 }
+    enum ParamDirection { In, Out, InOut }
+    enum SpecialPointerRepr { Const, Mut, Address }
+    enum Concurrency { NoHint, Reentrant, ThreadSafe, ThreadUnsafe }
+    enum BlockingHint { NoHint, NonBlocking, MayBlock, AsyncCompletion }
+    enum Niche { Zero, AllOnes }
+    enum Endian { Little, Big, Native }
+    enum Stability { Stable, Experimental }
+    enum DataClass { UserControlled, KernelInternal, Secret }
+}
+
+/// Renders an encoded payload described by `schema` in a human-readable form.
+/// This allows displaying attributes that are described but not otherwise recognized.
+pub fn render_payload(schema: &Schema, bytes: &[u8]) -> Result<String, DecodeError> {
+    let mut renderer = Renderer {
+        bytes,
+        out: String::new(),
+    };
+
+    renderer.render(schema)?;
+
+    if !renderer.bytes.is_empty() {
+        return Err(DecodeError::OtherString(format!(
+            "Extra slop found on stream. {} bytes remaining",
+            renderer.bytes.len()
+        )));
+    }
+
+    Ok(renderer.out)
+}
+
+struct Renderer<'a> {
+    bytes: &'a [u8],
+    out: String,
+}
+
+impl Renderer<'_> {
+    fn read<T: Decode<UnknownAttributePolicy>>(&mut self) -> Result<T, DecodeError> {
+        // Attributes within the payload, such as those of a function type, are not rendered
+        let (val, read) = bincode::decode_from_slice_with_context(
+            self.bytes,
+            format_config(),
+            UnknownAttributePolicy::WarnAndPreserve,
+        )?;
+        self.bytes = &self.bytes[read..];
+        Ok(val)
+    }
+
+    fn render(&mut self, schema: &Schema) -> Result<(), DecodeError> {
+        use core::fmt::Write as _;
+
+        let out = match schema {
+            Schema::Unit => "()".to_string(),
+            Schema::Bool => self.read::<bool>()?.to_string(),
+            Schema::UInt(8) => self.read::<u8>()?.to_string(),
+            Schema::UInt(16) => self.read::<u16>()?.to_string(),
+            Schema::UInt(32) => self.read::<u32>()?.to_string(),
+            Schema::UInt(64) => self.read::<u64>()?.to_string(),
+            Schema::UInt(128) => self.read::<u128>()?.to_string(),
+            Schema::Int(8) => self.read::<i8>()?.to_string(),
+            Schema::Int(16) => self.read::<i16>()?.to_string(),
+            Schema::Int(32) => self.read::<i32>()?.to_string(),
+            Schema::Int(64) => self.read::<i64>()?.to_string(),
+            Schema::Int(128) => self.read::<i128>()?.to_string(),
+            Schema::UInt(bits) | Schema::Int(bits) => {
+                return Err(DecodeError::OtherString(format!(
+                    "Unsupported integer width {bits}"
+                )));
+            }
+            Schema::String => format!("{:?}", self.read::<String>()?),
+            Schema::Uuid => self.read::<Uuid>()?.to_string(),
+            Schema::Version => self.read::<Version>()?.to_string(),
+            Schema::Type => self.read::<Type>()?.to_string(),
+            Schema::Option(inner) => {
+                if self.read::<bool>()? {
+                    self.out.push_str("Some(");
+                    self.render(inner)?;
+                    ")".to_string()
+                } else {
+                    "None".to_string()
+                }
+            }
+            Schema::List(inner) => {
+                let len = self.read::<u64>()?;
+                self.out.push('[');
+                for i in 0..len {
+                    if i != 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.render(inner)?;
+                }
+                "]".to_string()
+            }
+            Schema::Struct([]) => "{}".to_string(),
+            Schema::Struct(fields) => {
+                self.out.push('{');
+                let mut sep = " ";
+                for (name, schema) in *fields {
+                    let _ = write!(self.out, "{sep}{name}: ");
+                    self.render(schema)?;
+                    sep = ", ";
+                }
+                " }".to_string()
+            }
+            Schema::Enum(variants) => {
+                let idx = self.read::<u32>()?;
+                let (name, schema) = variants.get(idx as usize).ok_or_else(|| {
+                    DecodeError::OtherString(format!("Unknown variant index {idx}"))
+                })?;
+                self.out.push_str(name);
+                if *schema == Schema::Unit {
+                    return Ok(());
+                }
+                self.out.push('(');
+                self.render(schema)?;
+                ")".to_string()
+            }
+        };
+
+        self.out.push_str(&out);
+        Ok(())
+    }
+}
+
+/// Renders an encoded payload described by `schema` as JSON, like [`render_payload`].
+///
+/// Structs are objects, options are `null` or their value, lists are arrays, and enum variants are their name,
+/// or an object with their name as its only key if they have fields. UUIDs and versions are strings, and so are integers
+/// that a JSON number cannot hold exactly (see [`ToJson`](crate::json::ToJson)).
+pub fn payload_json(schema: &Schema, bytes: &[u8]) -> Result<String, DecodeError> {
+    let mut renderer = Renderer {
+        bytes,
+        out: String::new(),
+    };
+
+    renderer.render_json(schema)?;
+
+    if !renderer.bytes.is_empty() {
+        return Err(DecodeError::OtherString(format!(
+            "Extra slop found on stream. {} bytes remaining",
+            renderer.bytes.len()
+        )));
+    }
+
+    Ok(renderer.out)
+}
+
+impl Renderer<'_> {
+    fn render_json(&mut self, schema: &Schema) -> Result<(), DecodeError> {
+        use crate::json::{ToJson, integer, string};
+
+        let out = match schema {
+            Schema::Unit => "null".to_string(),
+            Schema::Bool => self.read::<bool>()?.to_string(),
+            Schema::UInt(8) => self.read::<u8>()?.to_string(),
+            Schema::UInt(16) => self.read::<u16>()?.to_string(),
+            Schema::UInt(32) => self.read::<u32>()?.to_string(),
+            Schema::UInt(64) => integer(self.read::<u64>()?),
+            Schema::UInt(128) => integer(self.read::<u128>()?),
+            Schema::Int(8) => self.read::<i8>()?.to_string(),
+            Schema::Int(16) => self.read::<i16>()?.to_string(),
+            Schema::Int(32) => self.read::<i32>()?.to_string(),
+            Schema::Int(64) => integer(self.read::<i64>()?),
+            Schema::Int(128) => integer(self.read::<i128>()?),
+            Schema::UInt(bits) | Schema::Int(bits) => {
+                return Err(DecodeError::OtherString(format!(
+                    "Unsupported integer width {bits}"
+                )));
+            }
+            Schema::String => string(&self.read::<String>()?),
+            Schema::Uuid => string(&self.read::<Uuid>()?.to_string()),
+            Schema::Version => string(&self.read::<Version>()?.to_string()),
+            Schema::Type => {
+                self.read::<Type>()?.write_json(&mut self.out);
+                return Ok(());
+            }
+            Schema::Option(inner) => {
+                if self.read::<bool>()? {
+                    return self.render_json(inner);
+                }
+                "null".to_string()
+            }
+            Schema::List(inner) => {
+                let len = self.read::<u64>()?;
+                self.out.push('[');
+                for i in 0..len {
+                    if i != 0 {
+                        self.out.push(',');
+                    }
+                    self.render_json(inner)?;
+                }
+                "]".to_string()
+            }
+            Schema::Struct(fields) => {
+                self.out.push('{');
+                let mut sep = "";
+                for (name, schema) in *fields {
+                    self.out.push_str(sep);
+                    self.out.push_str(&string(name));
+                    self.out.push(':');
+                    self.render_json(schema)?;
+                    sep = ",";
+                }
+                "}".to_string()
+            }
+            Schema::Enum(variants) => {
+                let idx = self.read::<u32>()?;
+                let (name, schema) = variants.get(idx as usize).ok_or_else(|| {
+                    DecodeError::OtherString(format!("Unknown variant index {idx}"))
+                })?;
+                if *schema == Schema::Unit {
+                    string(name)
+                } else {
+                    self.out.push('{');
+                    self.out.push_str(&string(name));
+                    self.out.push(':');
+                    self.render_json(schema)?;
+                    "}".to_string()
+                }
+            }
+        };
+
+        self.out.push_str(&out);
+        Ok(())
+    }
+}
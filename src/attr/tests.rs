@@ -0,0 +1,105 @@
+use bincode::{Decode, Encode, error::DecodeError};
+
+use super::{
+    Attribute, AttributeClass, AttributeDecodeError, AttributeFlags, AttributeType,
+    ErasedAttributeContent, UnknownAttributePolicy, decode_payload,
+    schema::{Describe, Schema},
+};
+use crate::{config::format_config, file::File};
+
+/// An attribute whose payload gained `width` in version 2. Version 1 payloads only have the name
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Versioned {
+    pub name: String,
+    pub width: u32,
+}
+
+impl Describe for Versioned {
+    const SCHEMA: Schema = Schema::Struct(&[("name", Schema::String), ("width", Schema::UInt(32))]);
+}
+
+impl Versioned {
+    /// The width of the attributes decoded from version 1 payloads
+    const V1_WIDTH: u32 = 32;
+
+    pub fn migrate_v1(version: u8, bytes: &[u8]) -> Result<Self, DecodeError> {
+        match version {
+            1 => Ok(Self {
+                name: decode_payload(bytes, UnknownAttributePolicy::Error)?,
+                width: Self::V1_WIDTH,
+            }),
+            _ => Err(AttributeDecodeError::UnmigratablePayloadVersion {
+                id: Self::ID,
+                version,
+            }
+            .into()),
+        }
+    }
+}
+
+fn encode<T: Encode>(val: &T) -> Vec<u8> {
+    bincode::encode_to_vec(val, format_config()).unwrap()
+}
+
+/// A [`Versioned`] attribute with the payload `version` followed by `fields`, as another version of imt would encode it
+fn encoded(version: u8, fields: &[u8]) -> Vec<u8> {
+    let attr = Attribute::<File> {
+        id: Versioned::ID,
+        flags: AttributeFlags::VERSIONED.with_type_class(AttributeClass::TOOLING),
+        payload: ErasedAttributeContent::Unknown([&[version], fields].concat()),
+    };
+    encode(&attr)
+}
+
+fn decode(bytes: &[u8], policy: UnknownAttributePolicy) -> Result<Attribute<File>, DecodeError> {
+    decode_payload(bytes, policy)
+}
+
+#[test]
+fn round_trips_the_current_version() {
+    let versioned = Versioned {
+        name: "handle".into(),
+        width: 64,
+    };
+    let bytes = encode(&Attribute::<File>::new(versioned.clone()));
+    assert_eq!(bytes, encoded(2, &encode(&versioned)));
+
+    let attr = decode(&bytes, UnknownAttributePolicy::Error).unwrap();
+    assert_eq!(attr.downcast::<Versioned>(), Some(&versioned));
+}
+
+#[test]
+fn migrates_older_payloads() {
+    let bytes = encoded(1, &encode(&String::from("handle")));
+    let attr = decode(&bytes, UnknownAttributePolicy::Error).unwrap();
+    let migrated = Versioned {
+        name: "handle".into(),
+        width: Versioned::V1_WIDTH,
+    };
+    assert_eq!(attr.downcast::<Versioned>(), Some(&migrated));
+    // Migrated attributes are encoded in the current version
+    assert_eq!(encode(&attr), encoded(2, &encode(&migrated)));
+
+    // Payloads older than any version the attribute can migrate are rejected
+    let e = decode(&encoded(0, &[]), UnknownAttributePolicy::Error).unwrap_err();
+    assert!(
+        e.to_string().contains("does not support payload version 0"),
+        "{e}"
+    );
+}
+
+#[test]
+fn rejects_newer_payloads() {
+    let bytes = encoded(3, &encode(&Versioned::default()));
+    let e = decode(&bytes, UnknownAttributePolicy::Error).unwrap_err();
+    assert!(
+        e.to_string()
+            .contains("has payload version 3, but at most version 2 is supported"),
+        "{e}"
+    );
+
+    // Newer payloads can still be preserved as they were encoded
+    let attr = decode(&bytes, UnknownAttributePolicy::WarnAndPreserve).unwrap();
+    assert!(attr.is_unknown());
+    assert_eq!(encode(&attr), bytes);
+}
@@ -1,6 +1,6 @@
 use bincode::{Decode, Encode};
 
-use crate::{header::Version, uses::Type, uuid::Uuid};
+use crate::{header::Version, intern::Symbol, uses::Type, uuid::Uuid};
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
 pub enum SafetyHint {
@@ -20,9 +20,45 @@ pub struct PolymorphicOption;
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
 pub struct ItemDoc {
+    pub doc_lines: Vec<Symbol>,
+}
+
+/// A translation of the [`ItemDoc`] of the item into the language `lang`, a BCP 47 language tag such as `de` or `pt-BR`.
+/// An item has a `LocalizedDoc` attribute for each language it is translated into
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct LocalizedDoc {
+    pub lang: String,
     pub doc_lines: Vec<String>,
 }
 
+/// Documents a parameter of a function
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct ParamDoc {
+    pub doc_lines: Vec<Symbol>,
+}
+
+/// Documents the return value of a function
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct ReturnDoc {
+    pub doc_lines: Vec<Symbol>,
+}
+
+/// Documents when a function returns the error `error`, a variant of the enum named by its [`ErrorReturn`] attribute.
+/// A function has an `ErrorDoc` attribute for each error it documents
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct ErrorDoc {
+    pub error: String,
+    pub doc_lines: Vec<Symbol>,
+}
+
+/// An example of using the item, written in the language `lang` (such as `c` or `rust`).
+/// An item has an `ExampleDoc` attribute for each example
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct ExampleDoc {
+    pub lang: String,
+    pub code: Vec<Symbol>,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
 pub struct SubsystemDescriptor {
     pub subsys_id: Uuid,
@@ -62,6 +98,7 @@ pub struct Align {
 pub struct Synthetic;
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct OptionBaseType {
     pub ty: Type,
 }
@@ -71,3 +108,235 @@ impl Default for OptionBaseType {
         OptionBaseType { ty: Type::Void }
     }
 }
+
+/// Declares that the file depends on another file (by `file_id`) or subsystem (by `subsys_id`).
+/// `version` is the minimum compatible version of the [`SubsystemDescriptor`] providing it.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Requires {
+    pub id: Uuid,
+    pub version: Version,
+}
+
+/// Marks the item as only present when the named feature is enabled.
+/// An item with multiple `Feature` attributes requires all of them.
+/// Fields cannot belong to a feature, as the layout of their type would depend on the enabled features.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Feature {
+    pub name: String,
+}
+
+/// The direction data flows through a pointer parameter
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum ParamDirection {
+    #[default]
+    In,
+    Out,
+    InOut,
+}
+
+/// Declares that the parameter is the number of elements pointed to by the parameter named `param`
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct LengthOf {
+    pub param: String,
+}
+
+/// Declares that the pointer parameter points to a NUL-terminated string
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct NulTerminated;
+
+/// Declares that negative return values of the function are error codes of the enum `error`
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
+pub struct ErrorReturn {
+    pub error: Type,
+}
+
+impl Default for ErrorReturn {
+    fn default() -> Self {
+        ErrorReturn { error: Type::Void }
+    }
+}
+
+/// Restricts the item to targets matching `cfg`, a Rust `cfg` predicate (such as `target_arch = "x86_64"`)
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct TargetCfg {
+    pub cfg: String,
+}
+
+/// How pointers of a [`SpecialPointer`] kind are represented in generated code
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum SpecialPointerRepr {
+    /// A pointer to immutable data
+    Const,
+    /// A pointer to mutable data
+    #[default]
+    Mut,
+    /// An address that cannot be dereferenced by the caller, represented as a pointer-sized unsigned integer
+    Address,
+}
+
+/// Declares the special pointer kind `id`, for use by `PointerKind::Special`
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct SpecialPointer {
+    pub id: Uuid,
+    pub name: String,
+    pub repr: SpecialPointerRepr,
+}
+
+/// Declares the special constant `id`, for use by `SpecialConst::Custom`.
+/// Its value is given by the target, or is `default` on targets that don't give it a value
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct SpecialConstant {
+    pub id: Uuid,
+    pub name: String,
+    pub default: Option<u128>,
+}
+
+/// The result of calling the function should not be ignored
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct MustUse {
+    pub reason: Option<String>,
+}
+
+/// Whether a function may be called concurrently with itself
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum Concurrency {
+    #[default]
+    NoHint,
+    /// The function may be called from any thread, and from signal handlers or interrupts that preempt another call
+    Reentrant,
+    /// The function may be called concurrently from multiple threads, but not from a signal handler or interrupt
+    ThreadSafe,
+    /// The function must not be called while another call is in progress
+    ThreadUnsafe,
+}
+
+/// Whether a function can block the calling thread
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum BlockingHint {
+    #[default]
+    NoHint,
+    /// The function always returns without waiting
+    NonBlocking,
+    /// The function may wait, such as for I/O or to acquire a lock
+    MayBlock,
+    /// The function starts an operation and returns without waiting, and the operation completes asynchronously
+    AsyncCompletion,
+}
+
+/// Declares a value that is never valid for the type or field, which bindings may use as a niche
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum Niche {
+    /// The value with every bit clear (zero, or a null pointer) is invalid
+    #[default]
+    Zero,
+    /// The value with every bit set is invalid
+    AllOnes,
+}
+
+/// The byte order of an integer field, for structures that are exchanged with hardware or over a network
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum Endian {
+    Little,
+    Big,
+    /// The byte order of the target
+    #[default]
+    Native,
+}
+
+/// Declares that the active member of the union is determined by `field`, a sibling field in each struct containing the union
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct DiscriminatedBy {
+    pub field: String,
+}
+
+/// Declares that a member of a [`DiscriminatedBy`] union is active when the tag field holds the enum variant `variant`
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct ActiveWhen {
+    pub variant: String,
+}
+
+/// Requests additional trait implementations for bindings of the struct or enum, named by their Rust traits (such as `PartialEq` or `Ord`).
+/// Generators map each trait to their language's equivalent, and ignore traits that have none
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct DeriveHint {
+    pub traits: Vec<String>,
+}
+
+/// The item should no longer be used, for the reason given by `note`
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Deprecated {
+    pub note: Option<String>,
+}
+
+/// The symbol that the function is linked by, when it differs from the name of the function
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct LinkName {
+    pub name: String,
+}
+
+/// The version of the [`SubsystemDescriptor`] of the file that the item was added in
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct IntroducedIn {
+    pub version: Version,
+}
+
+/// Marks the function as a method of a user-mode IPC service, called by sending a message to the service rather than by linking to a symbol.
+/// `method_id` identifies the method in the messages of the service, and is unique within the file
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct IpcMethod {
+    pub method_id: u32,
+}
+
+/// Requests an encoder and decoder for the struct, which converts it to and from its layout with each padding byte zeroed.
+/// Integer fields without an [`Endian`] attribute are encoded with the byte order `endian`
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct WireFormat {
+    pub endian: Endian,
+}
+
+/// Marks the field as reserved, for padding or future use. Generated code writes zero to the field,
+/// and if `must_be_zero` is set, rejects values of the struct with a non-zero value in the field
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Reserved {
+    pub must_be_zero: bool,
+}
+
+/// The version of the [`SubsystemDescriptor`] of the file that the field was appended to its struct in.
+/// Fields with this attribute come after every field without one, in the order of their versions,
+/// so that the struct as of each earlier version is a prefix of it
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct SinceField {
+    pub version: Version,
+}
+
+/// The layout of the type, or the signature of the function, is frozen as of version `since` of the [`SubsystemDescriptor`] of the file,
+/// and must not change in later versions. Types that a frozen item uses by value must be frozen as well
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Frozen {
+    pub since: Version,
+}
+
+/// Whether the item is stable. Items without this attribute are stable, unless their file is experimental.
+/// Experimental items may only be declared in modules designated for them, and stable items may not use experimental types
+/// (see [`EXPERIMENTAL_MODULE`](crate::validate::EXPERIMENTAL_MODULE))
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum Stability {
+    #[default]
+    Stable,
+    /// The item may change or be removed in any version
+    Experimental,
+}
+
+/// Classifies the data of a parameter or field for security review. A parameter or field may have several classes.
+/// On a pointer parameter, the class applies to the memory it points to
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Default, Encode, Decode)]
+pub enum DataClass {
+    /// The data is controlled by, and visible to, user mode
+    UserControlled,
+    /// The data is internal to the kernel
+    #[default]
+    KernelInternal,
+    /// The data must not be disclosed, such as a key or a password
+    Secret,
+}
@@ -0,0 +1,409 @@
+use indexmap::IndexMap;
+
+use std::collections::HashSet;
+
+use crate::{
+    attr::{
+        Attribute, AttributeFlags, AttributeInfo, AttributeTarget, attribute_info, find_attribute,
+        find_attributes,
+        types::{
+            DataClass, LengthOf, Niche, NulTerminated, ParamDirection, SafetyHint,
+            SubsystemDescriptor,
+        },
+    },
+    bundle::{Bundle, Path},
+    resolve::{Item, module_of, resolve_name},
+    tydef::{StructBody, TypeDefBody},
+    uses::{PointerKind, Type},
+    uuid::Uuid,
+    value::ValueBody,
+    visit::{AttributeVisitor, visit_file},
+};
+
+/// How an attribute is used throughout a bundle
+#[derive(Clone, Debug)]
+pub struct AttributeUsage {
+    pub id: Uuid,
+    /// The attribute type with this id, if it is recognized by this version of imt
+    pub info: Option<&'static AttributeInfo>,
+    /// The number of instances of the attribute
+    pub count: usize,
+    /// The number of instances whose payload was not recognized,
+    /// either because the attribute is unknown or because the payload version is newer than supported
+    pub unknown: usize,
+    /// The number of unrecognized instances that do not have [`AttributeFlags::IGNORE`] set
+    pub unignorable: usize,
+    /// The files that use the attribute
+    pub files: Vec<Path>,
+}
+
+/// Statistics about every attribute used in a bundle.
+///
+/// Attributes that are unknown to a consumer and lack [`AttributeFlags::IGNORE`] cause that consumer to reject the file,
+/// so an audit can be used to check a bundle before shipping it to consumers built with an older version of imt.
+#[derive(Clone, Debug, Default)]
+pub struct AttributeAudit {
+    pub usages: IndexMap<Uuid, AttributeUsage>,
+}
+
+impl AttributeAudit {
+    pub fn of(bundle: &Bundle) -> Self {
+        let mut audit = Self::default();
+        for (path, file) in bundle {
+            visit_file(
+                &mut AuditVisitor {
+                    file: path,
+                    audit: &mut audit,
+                },
+                file,
+            );
+        }
+        audit
+    }
+
+    /// The attributes that have any instances which are not recognized by this version of imt
+    pub fn unknown(&self) -> impl Iterator<Item = &AttributeUsage> {
+        self.usages.values().filter(|usage| usage.unknown != 0)
+    }
+
+    /// The attributes that have any unrecognized instances without [`AttributeFlags::IGNORE`].
+    pub fn unignorable(&self) -> impl Iterator<Item = &AttributeUsage> {
+        self.usages.values().filter(|usage| usage.unignorable != 0)
+    }
+}
+
+struct AuditVisitor<'a> {
+    file: &'a Path,
+    audit: &'a mut AttributeAudit,
+}
+
+impl AttributeVisitor for AuditVisitor<'_> {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &[Attribute<Targ>]) {
+        for attr in attrs {
+            let id = *attr.id();
+            let usage = self
+                .audit
+                .usages
+                .entry(id)
+                .or_insert_with(|| AttributeUsage {
+                    id,
+                    info: attribute_info(id),
+                    count: 0,
+                    unknown: 0,
+                    unignorable: 0,
+                    files: Vec::new(),
+                });
+
+            usage.count += 1;
+            if attr.is_unknown() {
+                usage.unknown += 1;
+                if !attr.flags().contains(AttributeFlags::IGNORE) {
+                    usage.unignorable += 1;
+                }
+            }
+            if !usage.files.contains(self.file) {
+                usage.files.push(self.file.clone());
+            }
+        }
+    }
+}
+
+/// A kind of security-relevant fact about an interface, which reviewers of the interface should check
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FindingKind {
+    /// The function has a [`SafetyHint::Unsafe`] attribute
+    UnsafeFunction,
+    /// The parameter is a mutable pointer without a [`ParamDirection`] attribute, so whether the function reads or writes through it is unknown
+    MissingDirection,
+    /// The parameter points to a buffer of bytes, integers, or characters (or to `void`),
+    /// but has no [`NulTerminated`] attribute and no other parameter has a [`LengthOf`] attribute naming it, so its length is unknown
+    MissingLength,
+    /// The parameter, return value, or field is a pointer that is not declared to be non-null, because neither it nor any type alias it is declared through has a [`Niche`] attribute
+    MissingNullability,
+    /// The file declares functions, but has no [`SubsystemDescriptor`], so it has no version
+    UnversionedFile,
+}
+
+impl core::fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FindingKind::UnsafeFunction => "unsafe functions",
+            FindingKind::MissingDirection => "mutable pointer parameters without a direction",
+            FindingKind::MissingLength => "buffer parameters without a length",
+            FindingKind::MissingNullability => "pointers without nullability information",
+            FindingKind::UnversionedFile => "files without a subsystem version",
+        })
+    }
+}
+
+/// A security-relevant fact about a file, or about an item of it
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Finding {
+    pub file: Path,
+    /// The item the finding is about, such as `read::buf` for the parameter `buf` of the function `read`, or `None` for the file itself
+    pub item: Option<String>,
+    pub kind: FindingKind,
+}
+
+/// A checklist of security-relevant facts about a bundle, for reviewing its interface.
+/// Unlike validation, none of the findings are errors, but each one is worth a look
+#[derive(Clone, Debug, Default)]
+pub struct SecurityAudit {
+    pub findings: Vec<Finding>,
+}
+
+impl SecurityAudit {
+    pub fn of(bundle: &Bundle) -> Self {
+        let mut audit = Self::default();
+        for (path, file) in bundle {
+            let mut push = |item: Option<String>, kind| {
+                audit.findings.push(Finding {
+                    file: path.clone(),
+                    item,
+                    kind,
+                })
+            };
+
+            let mut has_functions = false;
+            for val in &file.values {
+                let ValueBody::Function(f) = &val.body else {
+                    continue;
+                };
+                has_functions = true;
+                let name = &val.name;
+                if find_attribute::<SafetyHint, _>(&f.attrs) == Some(&SafetyHint::Unsafe) {
+                    push(Some(name.to_string()), FindingKind::UnsafeFunction);
+                }
+
+                let lengths = f
+                    .signature
+                    .params
+                    .iter()
+                    .flat_map(|param| find_attributes::<LengthOf, _>(&param.attrs))
+                    .map(|length| &*length.param)
+                    .collect::<HashSet<_>>();
+                for (n, param) in f.signature.params.iter().enumerate() {
+                    let Some(niche) = pointer_niche(bundle, path, &param.ty) else {
+                        continue;
+                    };
+                    let param_name = param.name.as_deref();
+                    let item = Some(match param_name {
+                        Some(param) => format!("{name}::{param}"),
+                        None => format!("{name}::{n}"),
+                    });
+                    if !niche {
+                        push(item.clone(), FindingKind::MissingNullability);
+                    }
+                    let Type::Pointer(kind, pointee) = &param.ty else {
+                        continue;
+                    };
+                    if *kind == PointerKind::Mut
+                        && find_attribute::<ParamDirection, _>(&param.attrs).is_none()
+                    {
+                        push(item.clone(), FindingKind::MissingDirection);
+                    }
+                    if matches!(
+                        **pointee,
+                        Type::Void | Type::Byte | Type::Int(_) | Type::Char(_)
+                    ) && find_attribute::<NulTerminated, _>(&param.attrs).is_none()
+                        && param_name.is_none_or(|param| !lengths.contains(param))
+                    {
+                        push(item, FindingKind::MissingLength);
+                    }
+                }
+                if pointer_niche(bundle, path, &f.signature.retty) == Some(false) {
+                    push(Some(name.to_string()), FindingKind::MissingNullability);
+                }
+            }
+
+            for ty in &file.types {
+                let fields = match &ty.body {
+                    TypeDefBody::Struct(st) => match &st.body {
+                        StructBody::Fields(fields) => fields,
+                        StructBody::Opaque(_) => continue,
+                    },
+                    TypeDefBody::Union(un) => &un.fields,
+                    TypeDefBody::Alias(_) | TypeDefBody::Enum(_) => continue,
+                };
+                for field in &fields.field {
+                    if find_attribute::<Niche, _>(&field.attrs).is_none()
+                        && pointer_niche(bundle, path, &field.ty) == Some(false)
+                    {
+                        push(
+                            Some(format!("{}::{}", ty.name, field.name)),
+                            FindingKind::MissingNullability,
+                        );
+                    }
+                }
+            }
+
+            if has_functions && find_attribute::<SubsystemDescriptor, _>(&file.attributes).is_none()
+            {
+                push(None, FindingKind::UnversionedFile);
+            }
+        }
+        audit
+    }
+
+    /// The findings of kind `kind`
+    pub fn of_kind(&self, kind: FindingKind) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(move |finding| finding.kind == kind)
+    }
+}
+
+/// If `ty`, as seen from `module`, is a pointer (directly or through type aliases), whether any of those aliases has a [`Niche`] attribute
+fn pointer_niche(bundle: &Bundle, module: &Path, ty: &Type) -> Option<bool> {
+    let mut module = module.clone();
+    let mut ty = ty;
+    let mut niche = false;
+    let mut visited = HashSet::new();
+    loop {
+        match ty {
+            Type::Pointer(_, _) => return Some(niche),
+            Type::Named(name, _) => {
+                let resolved = resolve_name(bundle, &module, name).ok()?;
+                let Item::Type(def) = resolved.item else {
+                    return None;
+                };
+                let TypeDefBody::Alias(alias) = &def.body else {
+                    return None;
+                };
+                if !visited.insert(resolved.path.clone()) {
+                    return None;
+                }
+                niche |= find_attribute::<Niche, _>(&alias.attrs).is_some();
+                module = module_of(&resolved.path);
+                ty = &alias.alias;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Secret data that a function passes through a user-controlled out-pointer, found by [`secret_flows`]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct SecretFlow {
+    pub file: Path,
+    pub function: String,
+    /// The name of the out-pointer parameter, or its index if it has none
+    pub param: String,
+    /// The field classed [`DataClass::Secret`] that the pointer points to (such as `Credentials::key`, or `Session::creds.key` through nested structs),
+    /// or `None` if the parameter itself is classed secret
+    pub field: Option<String>,
+}
+
+impl core::fmt::Display for SecretFlow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{}::{}::{}",
+            self.file, self.function, self.param
+        ))?;
+        if let Some(field) = &self.field {
+            f.write_fmt(format_args!(" ({field})"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the functions that pass secret data to user mode: each [`ParamDirection::Out`] or [`ParamDirection::InOut`] pointer parameter classed [`DataClass::UserControlled`]
+/// that is classed [`DataClass::Secret`] itself, or points to a type containing a field classed [`DataClass::Secret`] by value.
+///
+/// The analysis only uses the declarations of the functions, not what they do, so each flow is a candidate for review rather than a leak
+pub fn secret_flows(bundle: &Bundle) -> Vec<SecretFlow> {
+    let mut flows = Vec::new();
+    for (path, file) in bundle {
+        for val in &file.values {
+            let ValueBody::Function(f) = &val.body else {
+                continue;
+            };
+            for (n, param) in f.signature.params.iter().enumerate() {
+                let classes = find_attributes::<DataClass, _>(&param.attrs).collect::<Vec<_>>();
+                let out = matches!(
+                    find_attribute::<ParamDirection, _>(&param.attrs),
+                    Some(ParamDirection::Out | ParamDirection::InOut)
+                );
+                let Type::Pointer(_, pointee) = &param.ty else {
+                    continue;
+                };
+                if !out || !classes.contains(&&DataClass::UserControlled) {
+                    continue;
+                }
+
+                let mut fields = Vec::new();
+                if classes.contains(&&DataClass::Secret) {
+                    fields.push(None);
+                }
+                let mut visited = HashSet::new();
+                secret_fields(bundle, path, pointee, None, &mut visited, &mut fields);
+                for field in fields {
+                    flows.push(SecretFlow {
+                        file: path.clone(),
+                        function: val.name.to_string(),
+                        param: param
+                            .name
+                            .as_ref()
+                            .map_or_else(|| n.to_string(), |name| name.to_string()),
+                        field,
+                    });
+                }
+            }
+        }
+    }
+    flows
+}
+
+/// Adds each field classed [`DataClass::Secret`] that `ty`, as seen from `module`, contains by value to `out`, as `<type>::<field>`.
+/// `prefix` is the name of the outermost struct and the fields leading to `ty`, if `ty` is the type of a field
+fn secret_fields(
+    bundle: &Bundle,
+    module: &Path,
+    ty: &Type,
+    prefix: Option<&str>,
+    visited: &mut HashSet<Path>,
+    out: &mut Vec<Option<String>>,
+) {
+    match ty {
+        Type::Named(name, _) => {
+            let Ok(resolved) = resolve_name(bundle, module, name) else {
+                return;
+            };
+            let Item::Type(def) = resolved.item else {
+                return;
+            };
+            if !visited.insert(resolved.path.clone()) {
+                return;
+            }
+            let module = module_of(&resolved.path);
+            let fields = match &def.body {
+                TypeDefBody::Alias(alias) => {
+                    secret_fields(bundle, &module, &alias.alias, prefix, visited, out);
+                    return;
+                }
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => fields,
+                    StructBody::Opaque(_) => return,
+                },
+                TypeDefBody::Union(un) => &un.fields,
+                TypeDefBody::Enum(_) => return,
+            };
+            for field in &fields.field {
+                let name = match prefix {
+                    Some(prefix) => format!("{prefix}.{}", field.name),
+                    None => format!("{}::{}", def.name, field.name),
+                };
+                if find_attributes::<DataClass, _>(&field.attrs)
+                    .any(|class| *class == DataClass::Secret)
+                {
+                    out.push(Some(name.clone()));
+                }
+                secret_fields(bundle, &module, &field.ty, Some(&name), visited, out);
+            }
+            visited.remove(&resolved.path);
+        }
+        Type::Array(arr) => secret_fields(bundle, module, &arr.base, prefix, visited, out),
+        Type::Uninit(ty) => secret_fields(bundle, module, ty, prefix, visited, out),
+        _ => {}
+    }
+}
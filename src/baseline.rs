@@ -0,0 +1,93 @@
+use std::io::{BufRead, Write};
+
+use indexmap::IndexMap;
+
+use crate::lock::fnv1a;
+
+/// The fingerprint of a finding identified by `parts`, such as its kind and the path of the item it is about
+pub(crate) fn fingerprint(parts: &[&str]) -> u64 {
+    fnv1a(parts.join("\0").as_bytes())
+}
+
+/// The findings that have been accepted in a bundle, by their fingerprint, so that checks only fail on new findings.
+///
+/// Findings are fingerprinted by what they are about (such as the code and item of a [`Diagnostic`](crate::diagnostics::Diagnostic)),
+/// rather than by their message, so that a baseline keeps matching as messages are reworded.
+/// Each fingerprint is stored with a description of the finding, for the humans reviewing the baseline.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Baseline {
+    pub entries: IndexMap<u64, String>,
+}
+
+#[derive(Debug)]
+pub enum BaselineError {
+    Io(std::io::Error),
+    /// The line (starting from 1) is not a hexadecimal fingerprint followed by a description
+    InvalidEntry(usize),
+}
+
+impl core::fmt::Display for BaselineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BaselineError::Io(e) => e.fmt(f),
+            BaselineError::InvalidEntry(line) => {
+                f.write_fmt(format_args!("invalid baseline entry on line {line}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for BaselineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BaselineError::Io(e) => Some(e),
+            BaselineError::InvalidEntry(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BaselineError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl Baseline {
+    pub fn contains(&self, fingerprint: u64) -> bool {
+        self.entries.contains_key(&fingerprint)
+    }
+
+    /// Accepts the finding with the given fingerprint, described by `description`
+    pub fn insert(&mut self, fingerprint: u64, description: impl Into<String>) {
+        self.entries.insert(fingerprint, description.into());
+    }
+
+    /// Writes the baseline as one `fingerprint description` line per finding, sorted by description so that it diffs well
+    pub fn write<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|(a, a_desc), (b, b_desc)| a_desc.cmp(b_desc).then(a.cmp(b)));
+        for (fingerprint, description) in entries {
+            // Descriptions are read back up to the end of the line
+            let description = description.replace('\n', " ");
+            writeln!(w, "{fingerprint:016x} {description}")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a baseline in the format produced by [`Baseline::write`]. Empty lines and lines starting with `#` are ignored.
+    pub fn read<R: BufRead>(r: R) -> Result<Self, BaselineError> {
+        let mut entries = IndexMap::new();
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (fingerprint, description) = line.split_once(' ').unwrap_or((line, ""));
+            let fingerprint = u64::from_str_radix(fingerprint, 16)
+                .map_err(|_| BaselineError::InvalidEntry(n + 1))?;
+            entries.insert(fingerprint, description.trim().to_string());
+        }
+        Ok(Self { entries })
+    }
+}
@@ -0,0 +1,753 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{BufRead, ErrorKind, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use imt::{
+    attr::{registered_attributes, types::ItemDoc},
+    bundle::{Bundle, Path},
+    diagnostics::Severity,
+    resolve::{Item, ResolvedItem, expand_type, resolve_export, resolve_name},
+    text::{
+        ItemRef, SourceFile, Span,
+        compile::{module_path, resolve_includes, source_members},
+        lex::{BUILTIN_TYPES, Token, TokenKind, tokenize, unescape},
+        parse::{ParseError, parse},
+    },
+    tydef::TypeDefBody,
+    value::ValueBody,
+};
+use serde_json::{Value, json};
+
+fn main() -> ExitCode {
+    let mut args = std::env::args();
+    let prg_name = args.next().unwrap();
+    for arg in args {
+        match &*arg {
+            "--help" => {
+                println!("Usage: {prg_name} [--stdio]");
+                println!(
+                    "Runs a language server for textual IMT sources, which speaks the Language Server Protocol over standard input and output"
+                );
+                println!(
+                    "Every .imts source in the root of the workspace is compiled into a bundle, with the text of each open document in place of its file, so that names resolve across sources"
+                );
+                println!(
+                    "The server publishes the errors of each source and the problems found by validating the bundle, and offers go to definition, hover, and completion of type and attribute names"
+                );
+                return ExitCode::SUCCESS;
+            }
+            "--version" => {
+                println!("imt-lsp {}", core::env!("CARGO_PKG_VERSION"));
+                return ExitCode::SUCCESS;
+            }
+            // Clients commonly pass this to select the transport, and standard input and output is the only one
+            "--stdio" => {}
+            x => {
+                eprintln!("{prg_name}: unknown option {x}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let mut server = Server::new();
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout().lock();
+    loop {
+        let msg = match read_message(&mut input) {
+            Ok(Some(msg)) => msg,
+            // The client closed the connection without an exit notification
+            Ok(None) => return ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("{prg_name}: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        match server.handle(&msg, &mut output) {
+            Ok(None) => {}
+            Ok(Some(code)) => return code,
+            Err(e) => {
+                eprintln!("{prg_name}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+}
+
+/// Reads a message of the base protocol, which is a JSON value preceded by a `Content-Length` header.
+/// Returns `None` at the end of the input
+fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<Value>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            len = Some(value.trim().parse::<usize>().map_err(|_| {
+                std::io::Error::new(ErrorKind::InvalidData, "invalid Content-Length")
+            })?);
+        }
+    }
+    let len = len.ok_or_else(|| {
+        std::io::Error::new(ErrorKind::InvalidData, "message without a Content-Length")
+    })?;
+    let mut body = vec![0; len];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))
+}
+
+fn send(output: &mut impl Write, msg: &Value) -> std::io::Result<()> {
+    let body = msg.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+/// The path of the `file:` URI `uri`
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut n = 0;
+    while n < path.len() {
+        if path[n] == b'%' {
+            let hex = core::str::from_utf8(path.get(n + 1..n + 3)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            n += 3;
+        } else {
+            bytes.push(path[n]);
+            n += 1;
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// The `file:` URI of the absolute path `path`
+fn path_uri(path: &std::path::Path) -> String {
+    let mut uri = String::from("file://");
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+    uri
+}
+
+/// The LSP position (with the character counted in UTF-16 code units) of the byte `offset` of `text`
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+    let character = before[line_start..]
+        .chars()
+        .map(char::len_utf16)
+        .sum::<usize>();
+    json!({ "line": line, "character": character })
+}
+
+fn range(text: &str, span: Span) -> Value {
+    json!({ "start": position(text, span.start), "end": position(text, span.end) })
+}
+
+/// The byte offset in `text` of the LSP position `pos`, clamped to the end of its line
+fn offset(text: &str, pos: &Value) -> usize {
+    let line = pos["line"].as_u64().unwrap_or(0) as usize;
+    let character = pos["character"].as_u64().unwrap_or(0) as usize;
+    let Some(line_start) = (match line {
+        0 => Some(0),
+        _ => text.match_indices('\n').nth(line - 1).map(|(n, _)| n + 1),
+    }) else {
+        return text.len();
+    };
+    let mut units = 0;
+    for (n, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + n;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// A textual source of the workspace
+struct Source {
+    module: Path,
+    text: String,
+    /// The parsed source, if its text parses. Its file is in the bundle if its includes resolve too. Otherwise the file the source was
+    /// last compiled to is kept in the bundle, so that other sources do not get errors from names in it while it is being edited
+    parsed: Option<SourceFile>,
+    error: Option<ParseError>,
+}
+
+impl Source {
+    /// The span of the declaration of the item `name`
+    fn declaration(&self, name: &str) -> Option<Span> {
+        let parsed = self.parsed.as_ref()?;
+        let item = match parsed.file.types.iter().position(|ty| *ty.name == *name) {
+            Some(n) => ItemRef::Type(n),
+            None => ItemRef::Value(
+                parsed
+                    .file
+                    .values
+                    .iter()
+                    .position(|val| *val.name == *name)?,
+            ),
+        };
+        parsed.declaration(item)
+    }
+
+    /// The token of the text at the byte `offset`, or ending at it
+    fn token_at(&self, offset: usize) -> Option<(Vec<Token>, usize)> {
+        let tokens = tokenize(&self.text);
+        let n = tokens
+            .iter()
+            .rposition(|token| token.span.start <= offset && offset <= token.span.end)?;
+        Some((tokens, n))
+    }
+}
+
+struct Server {
+    root: PathBuf,
+    /// The sources of the workspace, by their path
+    sources: BTreeMap<PathBuf, Source>,
+    /// The sources open in the client, whose text is not read from their file
+    open: BTreeSet<PathBuf>,
+    bundle: Bundle,
+    /// The sources that diagnostics were last published for
+    published: BTreeSet<PathBuf>,
+    shut_down: bool,
+}
+
+impl Server {
+    fn new() -> Self {
+        Self {
+            root: std::env::current_dir().unwrap_or_default(),
+            sources: BTreeMap::new(),
+            open: BTreeSet::new(),
+            bundle: Bundle::create(),
+            published: BTreeSet::new(),
+            shut_down: false,
+        }
+    }
+
+    /// Handles the message `msg`, returning the status to exit with if it is the exit notification
+    fn handle(
+        &mut self,
+        msg: &Value,
+        output: &mut impl Write,
+    ) -> std::io::Result<Option<ExitCode>> {
+        let Some(method) = msg["method"].as_str() else {
+            // A response to a request of the server, which sends none
+            return Ok(None);
+        };
+        let params = &msg["params"];
+
+        let Some(id) = msg.get("id") else {
+            match method {
+                "initialized" => self.publish(output)?,
+                "exit" => {
+                    return Ok(Some(if self.shut_down {
+                        ExitCode::SUCCESS
+                    } else {
+                        ExitCode::FAILURE
+                    }));
+                }
+                "textDocument/didOpen" => {
+                    let doc = &params["textDocument"];
+                    if let Some(path) = doc["uri"].as_str().and_then(uri_path) {
+                        self.open.insert(path.clone());
+                        self.load(path, doc["text"].as_str().unwrap_or_default().to_string());
+                        self.publish(output)?;
+                    }
+                }
+                "textDocument/didChange" => {
+                    // Documents are synced in full, so the last change is the whole text
+                    let text = params["contentChanges"]
+                        .as_array()
+                        .and_then(|changes| changes.last())
+                        .and_then(|change| change["text"].as_str());
+                    if let Some(path) = params["textDocument"]["uri"].as_str().and_then(uri_path)
+                        && let Some(text) = text
+                    {
+                        self.load(path, text.to_string());
+                        self.publish(output)?;
+                    }
+                }
+                "textDocument/didClose" => {
+                    if let Some(path) = params["textDocument"]["uri"].as_str().and_then(uri_path) {
+                        self.open.remove(&path);
+                        match std::fs::read_to_string(&path) {
+                            Ok(text) => self.load(path, text),
+                            Err(_) => self.unload(&path),
+                        }
+                        self.publish(output)?;
+                    }
+                }
+                // Other notifications, such as didSave and $/cancelRequest, need nothing done
+                _ => {}
+            }
+            return Ok(None);
+        };
+
+        let res = match method {
+            _ if self.shut_down => Err((-32600, "the server was shut down".to_string())),
+            "initialize" => {
+                let root = params["rootUri"]
+                    .as_str()
+                    .or_else(|| params["workspaceFolders"][0]["uri"].as_str())
+                    .and_then(uri_path)
+                    .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+                if let Some(root) = root {
+                    self.root = root;
+                }
+                self.load_root();
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "definitionProvider": true,
+                        "hoverProvider": true,
+                        "completionProvider": { "triggerCharacters": [":", "["] },
+                    },
+                    "serverInfo": { "name": "imt-lsp", "version": core::env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => Ok(self
+                .at_position(params)
+                .and_then(|(path, offset)| self.definition(&path, offset))
+                .unwrap_or(Value::Null)),
+            "textDocument/hover" => Ok(self
+                .at_position(params)
+                .and_then(|(path, offset)| self.hover(&path, offset))
+                .unwrap_or(Value::Null)),
+            "textDocument/completion" => Ok(self
+                .at_position(params)
+                .map(|(path, offset)| self.completion(&path, offset))
+                .unwrap_or_else(|| json!([]))),
+            _ => Err((-32601, format!("unknown method {method}"))),
+        };
+        let response = match res {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        send(output, &response)?;
+        Ok(None)
+    }
+
+    /// Compiles each source in the root of the workspace that is not open
+    fn load_root(&mut self) {
+        let Ok(members) = source_members(Path(vec![]), &self.root) else {
+            return;
+        };
+        for (_, path) in members {
+            if self.open.contains(&path) {
+                continue;
+            }
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                self.load(path, text);
+            }
+        }
+    }
+
+    /// Compiles the source at `path` with the text `text`
+    fn load(&mut self, path: PathBuf, text: String) {
+        // Sources outside the root of the workspace are compiled as if their directory were the root
+        let mut root = self.root.clone();
+        let module = module_path(&Path(vec![]), &root, &path).or_else(|| {
+            root = path.parent()?.to_path_buf();
+            module_path(&Path(vec![]), &root, &path)
+        });
+        let Some(module) = module else {
+            return;
+        };
+
+        let (parsed, error) = match parse(&text) {
+            Ok(mut source) => match resolve_includes(&mut source, &Path(vec![]), &root, &path) {
+                Ok(()) => {
+                    self.bundle.add_file(module.clone(), source.file.clone());
+                    (Some(source), None)
+                }
+                Err(e) => (Some(source), Some(e)),
+            },
+            Err(e) => (None, Some(e)),
+        };
+        self.sources.insert(
+            path,
+            Source {
+                module,
+                text,
+                parsed,
+                error,
+            },
+        );
+    }
+
+    fn unload(&mut self, path: &std::path::Path) {
+        if let Some(source) = self.sources.remove(path) {
+            self.bundle.remove_file(&source.module);
+        }
+    }
+
+    /// Publishes the errors of each source, and the problems found by validating the bundle
+    fn publish(&mut self, output: &mut impl Write) -> std::io::Result<()> {
+        let mut diags = BTreeMap::<&std::path::Path, Vec<Value>>::new();
+        for (path, source) in &self.sources {
+            if let Some(e) = &source.error {
+                diags.entry(path).or_default().push(json!({
+                    "range": range(&source.text, e.span),
+                    "severity": 1,
+                    "source": "imt",
+                    "message": e.message,
+                }));
+            }
+        }
+
+        for diag in self.bundle.validate() {
+            let Some((path, source)) = self
+                .sources
+                .iter()
+                .find(|(_, source)| source.module == diag.file)
+            else {
+                continue;
+            };
+            // Problems with a member of an item are shown at the name of the item
+            let span = diag
+                .item
+                .as_deref()
+                .and_then(|item| source.declaration(item.split("::").next().unwrap_or(item)))
+                .unwrap_or_default();
+            let mut message = diag.message.clone();
+            for note in &diag.notes {
+                message.push('\n');
+                message.push_str(note);
+            }
+            diags.entry(path).or_default().push(json!({
+                "range": range(&source.text, span),
+                "severity": match diag.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                },
+                "code": diag.code,
+                "source": "imt",
+                "message": message,
+            }));
+        }
+
+        // Sources whose problems were fixed are published with none, to clear them
+        let mut published = BTreeSet::new();
+        for path in self
+            .published
+            .iter()
+            .map(PathBuf::as_path)
+            .chain(diags.keys().copied())
+        {
+            if !published.insert(path.to_path_buf()) {
+                continue;
+            }
+            let diagnostics = diags.get(path).cloned().unwrap_or_default();
+            send(
+                output,
+                &json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": path_uri(path), "diagnostics": diagnostics },
+                }),
+            )?;
+        }
+        published.retain(|path| diags.contains_key(path.as_path()));
+        self.published = published;
+        Ok(())
+    }
+
+    /// The path and byte offset of the document position in the parameters `params` of a request
+    fn at_position(&self, params: &Value) -> Option<(PathBuf, usize)> {
+        let path = uri_path(params["textDocument"]["uri"].as_str()?)?;
+        let offset = offset(&self.sources.get(&path)?.text, &params["position"]);
+        Some((path, offset))
+    }
+
+    /// The location of the declaration of the item at `item_path`, if it is declared in a source
+    fn location(&self, item_path: &Path) -> Option<Value> {
+        let module = item_path.parent()?;
+        let (path, source) = self
+            .sources
+            .iter()
+            .find(|(_, source)| source.module == module)?;
+        let span = source.declaration(item_path.name()?)?;
+        Some(json!({ "uri": path_uri(path), "range": range(&source.text, span) }))
+    }
+
+    /// The path written at the token `n` of `tokens`: the names joined by `::` that end with it
+    fn path_at(text: &str, tokens: &[Token], n: usize) -> Vec<String> {
+        let mut start = n;
+        while start >= 2
+            && tokens[start - 1].span.text(text) == "::"
+            && tokens[start - 2].kind == TokenKind::Ident
+        {
+            start -= 2;
+        }
+        tokens[start..=n]
+            .iter()
+            .step_by(2)
+            .map(|token| token.span.text(text).to_string())
+            .collect()
+    }
+
+    /// Resolves the name `segments` as written in `module`: a single name by the items and uses of the module, and a path from the
+    /// root of the bundle
+    fn resolve(&self, module: &Path, segments: Vec<String>) -> Option<ResolvedItem<'_>> {
+        match &*segments {
+            [name] => resolve_name(&self.bundle, module, name).ok(),
+            _ => resolve_export(&self.bundle, &Path(segments)).ok(),
+        }
+    }
+
+    fn definition(&self, path: &std::path::Path, offset: usize) -> Option<Value> {
+        let source = self.sources.get(path)?;
+        let (tokens, n) = source.token_at(offset)?;
+        let token = tokens[n];
+
+        // An include goes to the included source
+        if token.kind == TokenKind::Str
+            && n > 0
+            && tokens[n - 1].span.text(&source.text) == "include"
+        {
+            let included = path
+                .parent()?
+                .join(unescape(token.span.text(&source.text))?);
+            let included = std::fs::canonicalize(included).ok()?;
+            return Some(json!({
+                "uri": path_uri(&included),
+                "range": range("", Span::default()),
+            }));
+        }
+        if token.kind != TokenKind::Ident {
+            return None;
+        }
+
+        let segments = Self::path_at(&source.text, &tokens, n);
+        let start = n + 2 - 2 * segments.len();
+        // The path of a use is from the root of the bundle, and can name a module
+        if start > 0 && tokens[start - 1].span.text(&source.text) == "use" {
+            let target = Path(segments);
+            if let Some((path, _)) = self
+                .sources
+                .iter()
+                .find(|(_, source)| source.module == target)
+            {
+                return Some(json!({
+                    "uri": path_uri(path),
+                    "range": range("", Span::default()),
+                }));
+            }
+            return self.location(&resolve_export(&self.bundle, &target).ok()?.path);
+        }
+        let resolved = self.resolve(&source.module, segments)?;
+        self.location(&resolved.path)
+    }
+
+    fn hover(&self, path: &std::path::Path, offset: usize) -> Option<Value> {
+        let source = self.sources.get(path)?;
+        let (tokens, n) = source.token_at(offset)?;
+        let token = tokens[n];
+        if token.kind != TokenKind::Ident {
+            return None;
+        }
+        let segments = Self::path_at(&source.text, &tokens, n);
+        let span = Span::new(
+            tokens[n + 2 - 2 * segments.len()].span.start,
+            token.span.end,
+        );
+        let resolved = self.resolve(&source.module, segments)?;
+        let module = resolved.path.parent().unwrap_or(Path(vec![]));
+        let item_path = &resolved.path;
+
+        let (decl, doc) = match resolved.item {
+            Item::Type(def) => {
+                let decl = match &def.body {
+                    TypeDefBody::Alias(alias) => {
+                        // The aliased type is shown with every alias in it expanded, and every other type by its full path
+                        let expanded = expand_type(&self.bundle, &module, &alias.alias)
+                            .unwrap_or_else(|_| alias.alias.clone());
+                        format!("type {item_path} = {expanded}")
+                    }
+                    TypeDefBody::Struct(_) => format!("struct {item_path}"),
+                    TypeDefBody::Union(_) => format!("union {item_path}"),
+                    TypeDefBody::Enum(en) => format!("enum {item_path}: {}", en.underlying),
+                    _ => format!("type {item_path}"),
+                };
+                (decl, def.body.find_attribute::<ItemDoc>())
+            }
+            Item::Value(val) => {
+                let decl = match &val.body {
+                    ValueBody::Const(c) => {
+                        let ty = expand_type(&self.bundle, &module, &c.ty)
+                            .unwrap_or_else(|_| c.ty.clone());
+                        format!("const {item_path}: {ty} = {}", c.val)
+                    }
+                    ValueBody::Function(f) => {
+                        let mut sig = f.signature.clone();
+                        for param in &mut sig.params {
+                            if let Ok(ty) = expand_type(&self.bundle, &module, &param.ty) {
+                                param.ty = ty;
+                            }
+                        }
+                        if let Ok(ty) = expand_type(&self.bundle, &module, &sig.retty) {
+                            *sig.retty = ty;
+                        }
+                        // The signature is written like a declaration, so `fn(` is replaced by the name of the function
+                        let sig = sig.to_string();
+                        format!("fn {item_path}{}", sig.strip_prefix("fn").unwrap_or(&sig))
+                    }
+                };
+                (decl, val.body.find_attribute::<ItemDoc>())
+            }
+        };
+
+        let mut value = format!("```imt\n{decl}\n```");
+        if let Some(doc) = doc {
+            value.push_str("\n\n");
+            for line in &doc.doc_lines {
+                value.push_str(line);
+                value.push('\n');
+            }
+        }
+        Some(json!({
+            "contents": { "kind": "markdown", "value": value },
+            "range": range(&source.text, span),
+        }))
+    }
+
+    fn completion(&self, path: &std::path::Path, offset: usize) -> Value {
+        let Some(source) = self.sources.get(path) else {
+            return json!([]);
+        };
+        let text = &*source.text;
+        let tokens = tokenize(text);
+        // The name being typed is not part of the context
+        let mut end = tokens.partition_point(|token| token.span.end <= offset);
+        if end > 0 && tokens[end - 1].kind == TokenKind::Ident && tokens[end - 1].span.end == offset
+        {
+            end -= 1;
+        }
+        let before = &tokens[..end];
+        let text_of = |n: usize| {
+            before
+                .get(n)
+                .map_or("", |token: &Token| token.span.text(text))
+        };
+
+        // A qualifier such as `types::` or `base::types::` before the name
+        let mut qualifier = Vec::new();
+        let mut start = before.len();
+        while start >= 2 && text_of(start - 1) == "::" && before[start - 2].kind == TokenKind::Ident
+        {
+            qualifier.insert(0, text_of(start - 2).to_string());
+            start -= 2;
+        }
+
+        // An attribute is written after `#[`, `#![`, or `#[return:`
+        let is_attr = (start >= 2 && text_of(start - 1) == "[" && text_of(start - 2) == "#")
+            || (start >= 3
+                && text_of(start - 1) == "["
+                && text_of(start - 2) == "!"
+                && text_of(start - 3) == "#")
+            || (start >= 4
+                && text_of(start - 1) == ":"
+                && text_of(start - 2) == "return"
+                && text_of(start - 3) == "["
+                && text_of(start - 4) == "#");
+        if is_attr {
+            let qualifier = qualifier.join("::");
+            let items = registered_attributes()
+                .iter()
+                .filter_map(|info| {
+                    let label = match &*qualifier {
+                        "" => info.name,
+                        qualifier => info.name.strip_prefix(qualifier)?.strip_prefix("::")?,
+                    };
+                    let targets = info.targets.map_or("any item".to_string(), |targets| {
+                        targets
+                            .iter()
+                            .map(|target| format!("{target:?}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    });
+                    Some(json!({
+                        "label": label,
+                        // Property
+                        "kind": 10,
+                        "detail": format!("attribute of {targets}"),
+                    }))
+                })
+                .collect::<Vec<_>>();
+            return Value::Array(items);
+        }
+
+        let mut items = Vec::new();
+        let push_types = |module: &Path, items: &mut Vec<Value>| {
+            let Some(file) = self.bundle.get(module) else {
+                return;
+            };
+            for def in &file.types {
+                items.push(type_completion(&module.join(&def.name), &def.body));
+            }
+        };
+        if !qualifier.is_empty() {
+            push_types(&Path(qualifier), &mut items);
+            return Value::Array(items);
+        }
+
+        for name in BUILTIN_TYPES {
+            // Keyword
+            items.push(json!({ "label": name, "kind": 14, "detail": "builtin type" }));
+        }
+        push_types(&source.module, &mut items);
+        if let Some(file) = self.bundle.get(&source.module) {
+            for use_item in &file.uses {
+                let target = Path(use_item.path.clone());
+                if self.bundle.get(&target).is_some() {
+                    push_types(&target, &mut items);
+                } else if let Ok(resolved) = resolve_export(&self.bundle, &target)
+                    && let Item::Type(def) = resolved.item
+                {
+                    items.push(type_completion(&target, &def.body));
+                }
+            }
+        }
+        Value::Array(items)
+    }
+}
+
+/// The completion of the name of the type at `path`
+fn type_completion(path: &Path, body: &TypeDefBody) -> Value {
+    let kind = match body {
+        // Enum
+        TypeDefBody::Enum(_) => 13,
+        // Struct
+        TypeDefBody::Struct(_) | TypeDefBody::Union(_) => 22,
+        // TypeParameter, the closest to an alias
+        TypeDefBody::Alias(_) => 25,
+        // Class
+        _ => 7,
+    };
+    json!({ "label": path.name(), "kind": kind, "detail": path.to_string() })
+}
@@ -0,0 +1,32 @@
+use imt::attr::registered_attributes;
+
+use crate::{
+    cli::{Args, print_help},
+    usage_error,
+};
+
+pub(crate) fn attrs_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    if let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "attrs"),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("attrs: unknown option {x}")));
+            }
+            x => {
+                return Err(usage_error(format!("attrs: unexpected argument {x}")));
+            }
+        }
+    }
+
+    for info in registered_attributes() {
+        println!("{} {}", info.name, info.id);
+        println!("\tclass: {}", info.class);
+        println!("\tpayload version: {}", info.version);
+        match info.targets {
+            Some(targets) => println!("\ttargets: {targets:?}"),
+            None => println!("\ttargets: any"),
+        }
+        println!("\tschema: {}", info.schema);
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use imt::audit::{AttributeAudit, FindingKind, SecurityAudit, secret_flows};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    failure,
+    io::{Input, InputOptions},
+    usage_error,
+};
+
+pub(crate) fn audit_attrs_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("audit-attrs");
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "audit-attrs"),
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("audit-attrs: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+
+    let bundle = inputs.load(prg_name, None)?;
+    let audit = AttributeAudit::of(&bundle);
+    for usage in audit.usages.values() {
+        let name = usage.info.map_or("<unknown>", |info| info.name);
+        print!("{name} {}: {} instance(s)", usage.id, usage.count);
+        if usage.unknown != 0 {
+            print!(", {} unrecognized", usage.unknown);
+        }
+        if usage.unignorable != 0 {
+            print!(", {} without IGNORE", usage.unignorable);
+        }
+        println!();
+    }
+    let unignorable = audit.unignorable().count();
+    if unignorable != 0 {
+        return Err(failure(
+            FailureClass::Validation,
+            format!("{unignorable} unrecognized attribute(s) are not ignorable"),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn audit_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("audit");
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "audit"),
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("audit: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("audit requires at least one input"));
+    }
+
+    let bundle = inputs.load_containers(None)?;
+
+    let audit = SecurityAudit::of(&bundle);
+    for kind in [
+        FindingKind::UnsafeFunction,
+        FindingKind::MissingDirection,
+        FindingKind::MissingLength,
+        FindingKind::MissingNullability,
+        FindingKind::UnversionedFile,
+    ] {
+        let findings = audit.of_kind(kind).collect::<Vec<_>>();
+        println!("{kind}: {}", findings.len());
+        for finding in findings {
+            match &finding.item {
+                Some(item) => println!("\t{}::{item}", finding.file),
+                None => println!("\t{}", finding.file),
+            }
+        }
+    }
+    let flows = secret_flows(&bundle);
+    println!(
+        "secret data passed through user-controlled out-pointers: {}",
+        flows.len()
+    );
+    for flow in &flows {
+        println!("\t{flow}");
+    }
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::io::ErrorKind;
+
+#[cfg(feature = "tar")]
+use imt::provenance::Provenance;
+use imt::{
+    attr::UnknownAttributePolicy,
+    bundle::{Bundle, Path},
+    config::format_config,
+    diagnostics::Severity,
+    project::{MANIFEST_NAME, Project, ProjectError, Workspace},
+};
+
+use crate::{
+    FailureClass,
+    cli::print_help,
+    create_bundle, failure,
+    io::{Container, load_container, write_output},
+    usage_error,
+};
+
+pub(crate) fn build_main(
+    prg_name: &str,
+    mut args: impl Iterator<Item = String>,
+) -> std::io::Result<()> {
+    let mut projects = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "build"),
+            "--" => projects.extend(args.by_ref()),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("build: unknown option {x}")));
+            }
+            _ => projects.push(arg),
+        }
+    }
+
+    let manifest = match &projects[..] {
+        [] => MANIFEST_NAME,
+        [project] => project,
+        _ => {
+            return Err(usage_error("build takes at most one project"));
+        }
+    };
+    let workspace = Workspace::read(std::path::Path::new(manifest)).map_err(|e| match e {
+        ProjectError::Io(e) => e,
+        e => std::io::Error::new(ErrorKind::InvalidData, format!("{manifest}: {e}")),
+    })?;
+
+    // The files of each package, without those of its dependencies
+    let mut packages: Vec<(&Project, Bundle)> = Vec::new();
+    for project in &workspace.members {
+        let dependencies = workspace
+            .dependencies(project)
+            .into_iter()
+            .map(|dependency| {
+                let (_, bundle) = packages
+                    .iter()
+                    .find(|(package, _)| package.name == dependency.name)
+                    .expect("dependencies are built first");
+                bundle
+            })
+            .collect::<Vec<_>>();
+        let bundle = build_package(prg_name, project, &dependencies)?;
+        packages.push((project, bundle));
+    }
+
+    if let Some(output) = &workspace.output {
+        let mut system = create_bundle(UnknownAttributePolicy::Error, None)?;
+        for (project, bundle) in &packages {
+            for (path, file) in bundle {
+                if let Some((other, _)) = packages.iter().find(|(other, other_bundle)| {
+                    other.name != project.name && other_bundle.get(path).is_some()
+                }) {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("{path} is in both {} and {}", project.name, other.name),
+                    ));
+                }
+                system.add_file(path.clone(), file.clone());
+            }
+        }
+        write_bundle(prg_name, &mut system, &Path(vec![]), output)?;
+    }
+
+    Ok(())
+}
+
+/// Builds the package `project`, whose sources may refer to the files of the bundles of its `dependencies`.
+/// Returns the files of the package itself, which have been written to its output
+fn build_package(
+    prg_name: &str,
+    project: &Project,
+    dependencies: &[&Bundle],
+) -> std::io::Result<Bundle> {
+    let mut bundle = create_bundle(project.unknown_attrs, None)?;
+    for source in &project.sources {
+        let source = source.to_str().ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{}: not a UTF-8 path", source.display()),
+            )
+        })?;
+        load_container(&mut bundle, source, None, &project.prefix, None)?;
+    }
+    if let Some(features) = &project.features {
+        bundle.filter_features(&features.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+
+    // Names are resolved against the files of the dependencies, which are checked and generated along with those of the package
+    let mut linked = create_bundle(project.unknown_attrs, None)?;
+    for dependency in dependencies {
+        linked.add_files(
+            dependency
+                .iter()
+                .map(|(path, file)| (path.clone(), file.clone())),
+        );
+    }
+    linked.add_files(
+        bundle
+            .iter()
+            .map(|(path, file)| (path.clone(), file.clone())),
+    );
+
+    if project.validate {
+        // The files of the dependencies were checked when they were built
+        let mut diags = linked.validate();
+        for (_, target) in &project.targets {
+            diags.extend(linked.validate_target(target));
+        }
+        diags.retain(|diag| bundle.get(&diag.file).is_some());
+        for diag in &diags {
+            print!("{}", diag.render(&linked));
+        }
+        let errors = diags
+            .iter()
+            .filter(|diag| diag.severity == Severity::Error)
+            .count();
+        if errors != 0 {
+            return Err(failure(
+                FailureClass::Validation,
+                format!("{}: validation found {errors} error(s)", project.name),
+            ));
+        }
+    }
+
+    write_bundle(prg_name, &mut bundle, &project.prefix, &project.output)?;
+
+    for codegen in &project.codegen {
+        let backend = codegen.backend.backend()?;
+        let targets = if codegen.backend.is_target_independent() {
+            &project.targets[..1]
+        } else {
+            &project.targets[..]
+        };
+        for (name, target) in targets {
+            // Each target has its own directory, unless there is only one
+            let out = if targets.len() == 1 {
+                codegen.out.clone()
+            } else {
+                codegen.out.join(name.replace('/', "_"))
+            };
+            let mut sink = |name: &str, contents: &[u8]| {
+                let path = out.join(name);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, contents)
+            };
+            backend.generate(&linked, target, &mut sink)?;
+            eprintln!(
+                "{prg_name}: generated {} ({}) for {} in {}",
+                codegen.name,
+                backend.name(),
+                project.name,
+                out.display()
+            );
+        }
+    }
+
+    Ok(bundle)
+}
+
+/// Writes `bundle` to `output`, as a single IMT file if its extension is `.imt`, and as a TAR archive under `prefix` otherwise
+fn write_bundle(
+    prg_name: &str,
+    bundle: &mut Bundle,
+    prefix: &Path,
+    output: &std::path::Path,
+) -> std::io::Result<()> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let output = output.to_string_lossy();
+    let content = if Container::detect(&output) == Some(Container::Imt) {
+        let mut files = bundle.iter();
+        let (Some((_, file)), None) = (files.next(), files.next()) else {
+            return Err(usage_error(format!(
+                "{output}: an IMT file holds exactly one file of the bundle"
+            )));
+        };
+        bincode::encode_to_vec(file, format_config())
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?
+    } else {
+        #[cfg(feature = "tar")]
+        {
+            bundle.set_provenance(Provenance::from_env());
+            let mut tar = std::io::Cursor::new(Vec::new());
+            bundle.write_tar(prefix, &mut tar)?;
+            tar.into_inner()
+        }
+        #[cfg(not(feature = "tar"))]
+        {
+            let _ = prefix;
+            return Err(std::io::Error::other(format!(
+                "{output}: writing TAR archives requires building with the tar feature"
+            )));
+        }
+    };
+    write_output(&output, &content)?;
+    eprintln!("{prg_name}: wrote {output}");
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use std::io::ErrorKind;
+
+#[cfg(feature = "dwarf")]
+use imt::dwarf::DebugInfo;
+#[cfg(feature = "rustdoc")]
+use imt::rustdoc::{RustdocCrate, cross_check};
+use imt::{
+    bundle::Bundle,
+    diff::{Severity as ChangeSeverity, diff, diff_target},
+    target::TargetSpec,
+};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    create_bundle, failure,
+    io::{Input, InputOptions, load_container, read_input},
+    load_target, usage_error,
+};
+
+/// Where `check-system` looks for the interface bundle of the running system, unless `--system` or `IMT_SYSTEM_BUNDLE` says otherwise
+const DEFAULT_SYSTEM_BUNDLE: &str = "/usr/share/imt/system.tar";
+
+pub(crate) fn check_system_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("check-system");
+    let mut system = None;
+    let mut target = None;
+    let mut bundles = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "check-system"),
+            "--system" => {
+                system = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--system requires an argument"))?,
+                );
+            }
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--" => {
+                bundles.extend(args.by_ref());
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("check-system: unknown option {x}")));
+            }
+            _ => bundles.push(arg),
+        }
+    }
+
+    let [input] = &bundles[..] else {
+        return Err(usage_error("check-system requires exactly one bundle"));
+    };
+    let system = system
+        .or_else(|| std::env::var("IMT_SYSTEM_BUNDLE").ok())
+        .unwrap_or_else(|| DEFAULT_SYSTEM_BUNDLE.to_string());
+
+    inputs.inputs.push(Input::from(input.clone()));
+
+    let local = inputs.load_containers(None)?;
+    let mut installed = create_bundle(inputs.unknown_attrs, None)?;
+    load_container(&mut installed, &system, inputs.from, &inputs.prefix(), None)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{system}: {e}")))?;
+
+    // Bindings are usually generated for a few subsystems, so the rest of the system is not compared
+    let others = installed
+        .iter()
+        .map(|(path, _)| path.clone())
+        .filter(|path| local.get(path).is_none())
+        .collect::<Vec<_>>();
+    for path in &others {
+        installed.remove_file(path);
+    }
+
+    let diff = match &target {
+        Some(target) => diff_target(&local, &installed, target),
+        None => diff(&local, &installed),
+    };
+    for change in &diff.changes {
+        println!("{change} ({})", change.severity());
+    }
+    match diff.severity() {
+        Some(ChangeSeverity::Breaking) => Err(failure(
+            FailureClass::Validation,
+            format!("bindings generated from {input} are stale and incompatible with {system}"),
+        )),
+        Some(ChangeSeverity::Compatible) => {
+            println!(
+                "bindings generated from {input} are stale, but compatible with {system}: regenerate them to use the added items"
+            );
+            Ok(())
+        }
+        Some(ChangeSeverity::Docs) | None => {
+            println!("bindings generated from {input} are up to date with {system}");
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn check_bindings_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("check-bindings");
+    let mut rustdoc = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "check-bindings"),
+            "--rustdoc" => {
+                rustdoc = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--rustdoc requires an argument"))?,
+                );
+            }
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("check-bindings: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let rustdoc = rustdoc.ok_or_else(|| usage_error("check-bindings requires --rustdoc <json>"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("check-bindings requires at least one input"));
+    }
+
+    let bundle = inputs.load_containers(None)?;
+
+    let json = String::from_utf8(read_input(&rustdoc, inputs.manifest.as_deref())?)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("{rustdoc}: {e}")))?;
+    check_bindings(&bundle, &rustdoc, &json)
+}
+
+/// Reports the drift between `bundle` and the bindings described by the rustdoc JSON output `json`, read from `path`
+#[cfg(feature = "rustdoc")]
+fn check_bindings(bundle: &Bundle, path: &str, json: &str) -> std::io::Result<()> {
+    let krate = RustdocCrate::parse(json)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("{path}: {e}")))?;
+    let drift = cross_check(bundle, &krate);
+    for drift in &drift {
+        println!("{drift}");
+    }
+    if !drift.is_empty() {
+        return Err(failure(
+            FailureClass::Validation,
+            format!(
+                "the bindings described by {path} differ from the bundle in {} place(s)",
+                drift.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "rustdoc"))]
+fn check_bindings(_: &Bundle, _: &str, _: &str) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "check-bindings requires building with the rustdoc feature",
+    ))
+}
+
+pub(crate) fn check_dwarf_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("check-dwarf");
+    let mut object = None;
+    let mut target = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "check-dwarf"),
+            "--object" => {
+                object = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--object requires an argument"))?,
+                );
+            }
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("check-dwarf: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let object = object.ok_or_else(|| usage_error("check-dwarf requires --object <file>"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("check-dwarf requires at least one input"));
+    }
+
+    let bundle = inputs.load_containers(None)?;
+
+    let target = target.unwrap_or_else(|| TargetSpec::preset("x86_64").unwrap());
+    check_dwarf(&bundle, &object, &target)
+}
+
+/// Reports the differences between `bundle` and the DWARF debug information of the object file at `path`
+#[cfg(feature = "dwarf")]
+fn check_dwarf(bundle: &Bundle, path: &str, target: &TargetSpec) -> std::io::Result<()> {
+    let info = DebugInfo::read(&std::fs::read(path)?)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("{path}: {e}")))?;
+    let mismatches = imt::dwarf::cross_check(bundle, &info, target);
+    for mismatch in &mismatches {
+        println!("{mismatch}");
+    }
+    if !mismatches.is_empty() {
+        return Err(failure(
+            FailureClass::Validation,
+            format!(
+                "the debug information of {path} differs from the bundle in {} place(s)",
+                mismatches.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "dwarf"))]
+fn check_dwarf(_: &Bundle, _: &str, _: &TargetSpec) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "check-dwarf requires building with the dwarf feature",
+    ))
+}
+
+pub(crate) fn check_symbols_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("check-symbols");
+    let mut object = None;
+    let mut undocumented = true;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "check-symbols"),
+            "--object" => {
+                object = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--object requires an argument"))?,
+                );
+            }
+            "--allow-undocumented" => undocumented = false,
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("check-symbols: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let object = object.ok_or_else(|| usage_error("check-symbols requires --object <file>"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("check-symbols requires at least one input"));
+    }
+
+    let bundle = inputs.load_containers(None)?;
+
+    check_symbols(&bundle, &object, undocumented)
+}
+
+/// Reports the functions of `bundle` that the shared object at `path` does not export, and, if `undocumented` is set, the functions it exports that `bundle` does not have
+#[cfg(feature = "elf")]
+fn check_symbols(bundle: &Bundle, path: &str, undocumented: bool) -> std::io::Result<()> {
+    let exported = imt::elf::exported_functions(&std::fs::read(path)?)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("{path}: {e}")))?;
+    let mismatches = imt::elf::cross_check(bundle, &exported, undocumented);
+    for mismatch in &mismatches {
+        println!("{mismatch}");
+    }
+    if !mismatches.is_empty() {
+        return Err(failure(
+            FailureClass::Validation,
+            format!(
+                "the symbols of {path} differ from the bundle in {} place(s)",
+                mismatches.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "elf"))]
+fn check_symbols(_: &Bundle, _: &str, _: bool) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "check-symbols requires building with the elf feature",
+    ))
+}
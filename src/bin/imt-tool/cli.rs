@@ -0,0 +1,1275 @@
+use imt::target::PRESETS;
+
+#[cfg(feature = "project")]
+use crate::build::build_main;
+#[cfg(feature = "text")]
+use crate::fmt::fmt_main;
+#[cfg(feature = "registry")]
+use crate::registry::{fetch_main, publish_main};
+#[cfg(feature = "watch")]
+use crate::watch::watch_main;
+use crate::{
+    FailureClass,
+    attrs::attrs_main,
+    audit::{audit_attrs_main, audit_main},
+    check::{check_bindings_main, check_dwarf_main, check_symbols_main, check_system_main},
+    completions::{SHELLS, cli_json_main, completions_main},
+    convert::{convert_main, create_main, extract_main, strip_main},
+    diff::{bump_main, diff_main},
+    doc::doc_main,
+    dump::dump_main,
+    explain::explain_main,
+    frozen::{check_frozen_main, freeze_main},
+    generate::gen_main,
+    grep::grep_main,
+    padding::padding_main,
+    provenance::provenance_main,
+    real_main,
+    resolve::resolve_main,
+    size::size_main,
+    validate::validate_main,
+};
+
+/// Runs `command` (the default command, if empty) with the options `preset` given before `args`, for subcommands that
+/// name a common use of another command. `--help` prints the help of the subcommand `name`, which accepts the options of `command`.
+pub(crate) fn preset_main(
+    prg_name: &str,
+    name: &str,
+    command: &str,
+    preset: Vec<String>,
+    args: Args,
+) -> std::io::Result<()> {
+    let cmd = self::command(command);
+    let args = args.collect::<Vec<_>>();
+    if args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--help")
+    {
+        return print_help(prg_name, name);
+    }
+    (cmd.main)(
+        prg_name,
+        preset
+            .into_iter()
+            .chain(args)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .peekable(),
+    )
+}
+
+/// The kind of value taken by an option or positional argument of imt-tool, which determines how shells complete it
+#[derive(Copy, Clone)]
+pub(crate) enum ValueKind {
+    File,
+    Dir,
+    /// A program to run
+    Command,
+    /// One of a fixed set of values
+    Choice(&'static [&'static str]),
+    /// A target spec file, or one of [`PRESETS`]
+    Target,
+    /// Anything else, which isn't completed
+    Text,
+}
+
+impl ValueKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Dir => "dir",
+            Self::Command => "command",
+            Self::Choice(_) => "choice",
+            Self::Target => "target",
+            Self::Text => "text",
+        }
+    }
+
+    pub(crate) fn choices(self) -> &'static [&'static str] {
+        match self {
+            Self::Choice(choices) => choices,
+            Self::Target => PRESETS,
+            _ => &[],
+        }
+    }
+}
+
+/// An option of imt-tool, as listed by `--help`
+pub(crate) struct CliOption {
+    pub(crate) name: &'static str,
+    pub(crate) short: Option<&'static str>,
+    /// The name of the argument of the option, and its kind
+    pub(crate) value: Option<(&'static str, ValueKind)>,
+    pub(crate) help: &'static str,
+}
+
+/// The arguments of a command of imt-tool
+pub(crate) type Args = std::iter::Peekable<std::vec::IntoIter<String>>;
+
+/// imt-tool (with an empty `name`) or one of its subcommands
+pub(crate) struct CliCommand {
+    pub(crate) name: &'static str,
+    pub(crate) usage: &'static str,
+    pub(crate) about: &'static str,
+    /// Lines that `--help` prints after `about`
+    pub(crate) notes: &'static [&'static str],
+    /// Runs the command with the arguments that follow its name
+    pub(crate) main: fn(&str, Args) -> std::io::Result<()>,
+    pub(crate) options: &'static [CliOption],
+    /// The kind of the positional arguments, after the options
+    pub(crate) inputs: ValueKind,
+}
+
+/// The entry of the command `name` in [`CLI`]
+pub(crate) fn command(name: &str) -> &'static CliCommand {
+    CLI.iter().find(|cmd| cmd.name == name).unwrap()
+}
+
+/// Prints the `--help` of the command `name` from its entry in [`CLI`]. The help of imt-tool itself also lists each command,
+/// and the exit statuses
+pub(crate) fn print_help(prg_name: &str, name: &str) -> std::io::Result<()> {
+    let cmd = command(name);
+    let is_root = name.is_empty();
+    if is_root {
+        println!("Usage: {prg_name} {}", cmd.usage);
+        for sub in &CLI[1..] {
+            println!("       {prg_name} {} {}", sub.name, sub.usage);
+        }
+    } else {
+        println!("Usage: {prg_name} {name} {}", cmd.usage);
+    }
+    println!("{}", cmd.about);
+    for note in cmd.notes {
+        println!("{note}");
+    }
+    if is_root {
+        println!("Commands:");
+        for sub in &CLI[1..] {
+            println!("\t{}: {}", sub.name, sub.about);
+        }
+    }
+    println!("Options:");
+    for opt in cmd.options {
+        let short = opt
+            .short
+            .map_or(String::new(), |short| format!("{short}, "));
+        let value = opt
+            .value
+            .map_or(String::new(), |(value, _)| format!(" <{value}>"));
+        match opt.value {
+            Some((_, ValueKind::Target)) => println!(
+                "\t{short}{}{value}: {} ({})",
+                opt.name,
+                opt.help,
+                PRESETS.join(", ")
+            ),
+            _ => println!("\t{short}{}{value}: {}", opt.name, opt.help),
+        }
+    }
+    if is_root {
+        println!("Exit status:");
+        println!("\t0: Success");
+        for class in FailureClass::ALL {
+            println!("\t{}: {}", class as u8, class.about());
+        }
+    }
+    Ok(())
+}
+
+/// Runs the command `name` of an imt-tool built without `feature`, which the command requires. Only its `--help` can be printed
+#[cfg(not(all(
+    feature = "project",
+    feature = "registry",
+    feature = "text",
+    feature = "watch"
+)))]
+fn unavailable(
+    prg_name: &str,
+    name: &str,
+    feature: &str,
+    args: impl Iterator<Item = String>,
+) -> std::io::Result<()> {
+    let mut args = args.take_while(|arg| arg != "--");
+    if args.any(|arg| arg == "--help") {
+        return print_help(prg_name, name);
+    }
+    Err(std::io::Error::other(format!(
+        "{name} requires building with the {feature} feature"
+    )))
+}
+
+#[cfg(not(feature = "project"))]
+fn build_main(prg_name: &str, args: impl Iterator<Item = String>) -> std::io::Result<()> {
+    unavailable(prg_name, "build", "project", args)
+}
+
+#[cfg(not(feature = "text"))]
+fn fmt_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    unavailable(prg_name, "fmt", "text", args)
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    unavailable(prg_name, "watch", "watch", args)
+}
+
+#[cfg(not(feature = "registry"))]
+fn publish_main(prg_name: &str, args: impl Iterator<Item = String>) -> std::io::Result<()> {
+    unavailable(prg_name, "publish", "registry", args)
+}
+
+#[cfg(not(feature = "registry"))]
+fn fetch_main(prg_name: &str, args: impl Iterator<Item = String>) -> std::io::Result<()> {
+    unavailable(prg_name, "fetch", "registry", args)
+}
+
+const FORMATS: &[&str] = &["imt", "tar", "zip", "dir", "src"];
+
+const OUTPUT_FORMATS: &[&str] = &["imt", "tar", "zip", "dir", "json"];
+
+const UNKNOWN_ATTRS_POLICIES: &[&str] = &["error", "warn", "drop"];
+
+const STRIP_CONTENTS: &[&str] = &["docs", "comments", "synthetic", "unknown", "all"];
+
+const HELP_OPTION: CliOption = CliOption {
+    name: "--help",
+    short: None,
+    value: None,
+    help: "Print this message, and exit",
+};
+
+const ERROR_FORMAT_OPTION: CliOption = CliOption {
+    name: "--error-format",
+    short: None,
+    value: Some(("format", ValueKind::Choice(&["human", "json"]))),
+    help: "How to print the error imt-tool fails with: human (the default), or json (an object with the class of the error, its exit status, and its message)",
+};
+
+const FROM_OPTION: CliOption = CliOption {
+    name: "--from",
+    short: None,
+    value: Some(("format", ValueKind::Choice(FORMATS))),
+    help: "The format of each <input> (imt, tar, zip, dir, or src, a directory tree of textual sources to compile with the text feature). Detected from the contents of each <input>, or guessed from its extension, if not specified",
+};
+
+const PREFIX_OPTION: CliOption = CliOption {
+    name: "--prefix",
+    short: None,
+    value: Some(("path", ValueKind::Text)),
+    help: "The module that the root of each bundle corresponds to",
+};
+
+const INPUT_OPTION: CliOption = CliOption {
+    name: "--input",
+    short: None,
+    value: Some(("input", ValueKind::File)),
+    help: "Adds the bundle <input>, whose root corresponds to the module <path> instead of that of --prefix. Written as <input>[=<path>]",
+};
+
+const MANIFEST_OPTION: CliOption = CliOption {
+    name: "--manifest",
+    short: None,
+    value: Some(("file", ValueKind::File)),
+    help: "Verifies each downloaded input against the SHA-256 checksum listed for it in <file>, which has the format of sha256sum",
+};
+
+const METRICS_OPTION: CliOption = CliOption {
+    name: "--metrics",
+    short: None,
+    value: Some(("file", ValueKind::File)),
+    help: "Writes the time taken to decode each file and by each phase of the command, the number of files, types, and values processed, and the size of each generated output, as JSON to <file>",
+};
+
+const UNKNOWN_ATTRS_OPTION: CliOption = CliOption {
+    name: "--unknown-attrs",
+    short: None,
+    value: Some(("policy", ValueKind::Choice(UNKNOWN_ATTRS_POLICIES))),
+    help: "How to handle unrecognized attributes without the IGNORE flag. <policy> is error (the default), warn (keep the attribute and print a warning), or drop",
+};
+
+const VERSION_OPTION: CliOption = CliOption {
+    name: "--version",
+    short: None,
+    value: None,
+    help: "Print version information and exit",
+};
+
+const BUNDLE_OPTION: CliOption = CliOption {
+    name: "--bundle",
+    short: None,
+    value: None,
+    help: "Treats the input file as a TAR archives containing a bundle. TAR archives are also detected from their contents",
+};
+
+const DUMP_PREFIX_OPTION: CliOption = CliOption {
+    name: "--prefix",
+    short: None,
+    value: Some(("path", ValueKind::Text)),
+    help: "treats the files as if it starts in module <path>",
+};
+
+const DUMP_INPUT_OPTION: CliOption = CliOption {
+    name: "--input",
+    short: None,
+    value: Some(("file", ValueKind::File)),
+    help: "Adds the input <file>, treating it as if it starts in module <path> instead of that of --prefix. Written as <file>[=<path>]",
+};
+
+const UNZIP_OPTION: CliOption = CliOption {
+    name: "--unzip",
+    short: None,
+    value: Some(("prg", ValueKind::Command)),
+    help: "Processes each input file through <prg> (e.g. gzip/xz/lzma - expects the command to follow gzip CLI). Inputs compressed with gzip, xz, lzma, bzip2, or zstd are otherwise detected and decompressed automatically",
+};
+
+const CACHE_OPTION: CliOption = CliOption {
+    name: "--cache",
+    short: None,
+    value: Some(("dir", ValueKind::Dir)),
+    help: "Caches decoded files in <dir>, so that unchanged files are not decoded again by later invocations",
+};
+
+const REVISION_TARGET_OPTION: CliOption = CliOption {
+    name: "--target",
+    short: None,
+    value: Some(("target", ValueKind::Target)),
+    help: "Treats changes to functions that keep them ABI-compatible on <target> as compatible, and checks the layout of frozen items on <target>. <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+};
+
+const DENY_BREAKING_OPTION: CliOption = CliOption {
+    name: "--deny-breaking",
+    short: None,
+    value: None,
+    help: "Fails if there is a breaking change",
+};
+
+const REVISION_BASELINE_OPTION: CliOption = CliOption {
+    name: "--baseline",
+    short: None,
+    value: Some(("file", ValueKind::File)),
+    help: "Accepts the breaking changes and changes to frozen items listed in the baseline file <file>. Accepted changes to frozen items are not printed, and no accepted change fails its check",
+};
+
+const REVISION_WRITE_BASELINE_OPTION: CliOption = CliOption {
+    name: "--write-baseline",
+    short: None,
+    value: Some(("file", ValueKind::File)),
+    help: "Writes each breaking change and change to a frozen item to the baseline file <file>, instead of failing on them",
+};
+
+const REVISION_METRICS_OPTION: CliOption = CliOption {
+    name: "--metrics",
+    short: None,
+    value: Some(("file", ValueKind::File)),
+    help: "Writes the time taken to decode each file and to compare the revisions, and the number of files, types, and values processed, as JSON to <file>",
+};
+
+const CONVERT_OPTIONS: &[CliOption] = &[
+    HELP_OPTION,
+    ERROR_FORMAT_OPTION,
+    CliOption {
+        name: "--from",
+        short: None,
+        value: Some(("format", ValueKind::Choice(FORMATS))),
+        help: "The format of <input>. Detected from the contents of <input>, or guessed from its extension, if not specified",
+    },
+    CliOption {
+        name: "--to",
+        short: None,
+        value: Some(("format", ValueKind::Choice(OUTPUT_FORMATS))),
+        help: "The format of <output>",
+    },
+    CliOption {
+        name: "--encrypt",
+        short: None,
+        value: Some(("key-id", ValueKind::Text)),
+        help: "Encrypts each file of the bundle that holds experimental items with the key <key-id> from IMT_KEY_DIR (with the crypto feature), so that pre-release interfaces can be distributed with the rest of the bundle",
+    },
+    CliOption {
+        name: "--prefix",
+        short: None,
+        value: Some(("path", ValueKind::Text)),
+        help: "The module that the root of the bundle corresponds to",
+    },
+    MANIFEST_OPTION,
+    UNKNOWN_ATTRS_OPTION,
+];
+
+/// Each command of imt-tool, which `main` dispatches to, and describes for `--help`, `completions`, and `cli-json`
+pub(crate) const CLI: &[CliCommand] = &[
+    CliCommand {
+        name: "",
+        main: real_main,
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Displays contents of IMT files or IMT Bundle files, like imt-tool dump",
+        notes: &[
+            "See imt-tool dump --help for its options, and imt-tool <command> --help for those of each other command",
+        ],
+        options: &[HELP_OPTION, ERROR_FORMAT_OPTION, VERSION_OPTION],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "dump",
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Displays contents of IMT files or IMT Bundle files",
+        notes: &[
+            "If no file is provided, or a file is -, read from standard input",
+            "A file that is an http:// or https:// URL is downloaded (with the fetch feature)",
+            "Encrypted files are decrypted (with the crypto feature) with the keys in the directory named by IMT_KEY_DIR, where the key <id> is stored as 64 hexadecimal digits in <id>.key",
+        ],
+        main: dump_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+            CliOption {
+                name: "--strip",
+                short: None,
+                value: Some(("content", ValueKind::Choice(STRIP_CONTENTS))),
+                help: "Removes content from the input before output. <content> is a comma separated list of docs, comments, synthetic, unknown, or all",
+            },
+            CliOption {
+                name: "--doc-lang",
+                short: None,
+                value: Some(("lang", ValueKind::Text)),
+                help: "Replaces the documentation of each item with its translation into the language <lang> (e.g. de or pt-BR), where it has one",
+            },
+            CliOption {
+                name: "--fold-consts",
+                short: None,
+                value: None,
+                help: "Simplifies constant expressions in the input before output, such as replacing arithmetic on literals with its result",
+            },
+            CliOption {
+                name: "--explicit-padding",
+                short: None,
+                value: None,
+                help: "Replaces the implicit padding of each struct on the target of --target (x86_64 by default) with reserved fields before output, so that its layout is explicit",
+            },
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "The target of --explicit-padding. <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+            CliOption {
+                name: "--format",
+                short: None,
+                value: Some(("format", ValueKind::Choice(&["debug", "json", "text"]))),
+                help: "How to display the input: debug (the default, the Rust debug representation of the decoded bundle), json (an object with a key for the module of each file, whose value is the decoded file, with integers too large for a JSON number to hold exactly written as strings), or text (each file in a Rust-like IDL syntax, preceded by its module)",
+            },
+            CliOption {
+                name: "--output",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "Writes the (possibly stripped) input to <file> instead of displaying it. Writes a TAR archive if --bundle is specified, with a PROVENANCE member recording the build time (SOURCE_DATE_EPOCH, if set), the builder (IMT_BUILDER, or the user), and the source revision (IMT_SOURCE_REVISION)",
+            },
+            CliOption {
+                name: "--metrics",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "Writes the time taken to decode each file, and the number of files, types, and values processed, as JSON to <file>",
+            },
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "validate",
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Checks IMT files or IMT Bundle files for errors, prints each problem found, and fails if any are errors",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: validate_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "Also checks the target-specific properties of the input. <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+            CliOption {
+                name: "--plugin-dir",
+                short: None,
+                value: Some(("dir", ValueKind::Dir)),
+                help: "Also checks the input using the lint plugins in <dir>, which are dynamic libraries (with the dylib feature). Defaults to the value of IMT_PLUGIN_DIR, if set",
+            },
+            CliOption {
+                name: "--diagnostic-format",
+                short: None,
+                value: Some(("format", ValueKind::Choice(&["human", "sarif"]))),
+                help: "How to print each problem found: human (the default), or sarif (a SARIF 2.1.0 log, for code review systems and editors)",
+            },
+            CliOption {
+                name: "--baseline",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "Accepts the problems listed in the baseline file <file>, which are not printed, and do not fail the check",
+            },
+            CliOption {
+                name: "--write-baseline",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "Writes each problem found to the baseline file <file>, instead of failing on them",
+            },
+            CliOption {
+                name: "--metrics",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "Writes the time taken to decode each file and to validate the input, and the number of files, types, values, and problems found, as JSON to <file>",
+            },
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "diff",
+        usage: "[OPTIONS...] [--] <old> [file..]",
+        about: "Prints the changes between the older revision <old> and the input as a Markdown changelog, and fails if the layout (on the target of --target, x86_64 by default) or signature of an item frozen in <old> changed",
+        notes: &[
+            "Inputs are read like those of imt-tool dump (see imt-tool dump --help). <old> is an IMT file or an archive, possibly compressed, whose format is detected from its contents",
+        ],
+        main: diff_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+            REVISION_TARGET_OPTION,
+            DENY_BREAKING_OPTION,
+            REVISION_BASELINE_OPTION,
+            REVISION_WRITE_BASELINE_OPTION,
+            REVISION_METRICS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "bump",
+        usage: "[OPTIONS...] [--] <old> [file..]",
+        about: "Compares the input against the older revision <old>, prints each change, and suggests the next version of each subsystem from the changes to its file, leaving unchanged files alone. Fails if the layout (on the target of --target, x86_64 by default) or signature of an item frozen in <old> changed",
+        notes: &[
+            "Inputs are read like those of imt-tool dump (see imt-tool dump --help). <old> is an IMT file or an archive, possibly compressed, whose format is detected from its contents",
+        ],
+        main: bump_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+            REVISION_TARGET_OPTION,
+            DENY_BREAKING_OPTION,
+            REVISION_BASELINE_OPTION,
+            REVISION_WRITE_BASELINE_OPTION,
+            CliOption {
+                name: "--output",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "Writes the input with the suggested versions to <file>. Writes a TAR archive if --bundle is specified",
+            },
+            REVISION_METRICS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "audit-attrs",
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Prints each attribute used by the input with its number of instances, and fails if any unrecognized attribute is not ignorable",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: audit_attrs_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "padding",
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Prints the padding bytes and misaligned fields of each struct of the input, and an order of its fields that needs less padding",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: padding_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "The target to lay out each struct on (x86_64 by default). <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "resolve",
+        usage: "<path> [OPTIONS...] [--] [file..]",
+        about: "Prints where the item <path> (e.g. base::io::Handle) is defined, the re-exports followed to reach it, and its type with all aliases expanded",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: resolve_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "freeze",
+        usage: "<lockfile> [OPTIONS...] [--] [file..]",
+        about: "Writes a lockfile containing the content hash of each item of the input to <lockfile>, for imt-tool check-frozen",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: freeze_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "check-frozen",
+        usage: "<lockfile> [OPTIONS...] [--] [file..]",
+        about: "Compares the input against <lockfile>, written by imt-tool freeze, prints each item that was added, removed, or changed, and fails if there are any",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: check_frozen_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "create",
+        usage: "[OPTIONS...] <output> <input>..",
+        about: "Creates a bundle from the IMT files and bundles <input>, and writes it to <output>",
+        notes: &[
+            "Outputs with a .gz, .tgz, .xz, .lzma, .bz2, or .zst extension are compressed with the corresponding program, and TAR archives and directories get a PROVENANCE member recording the build time (SOURCE_DATE_EPOCH, if set), the builder (IMT_BUILDER, or the user), and the source revision (IMT_SOURCE_REVISION)",
+        ],
+        main: create_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--to",
+                short: None,
+                value: Some(("format", ValueKind::Choice(OUTPUT_FORMATS))),
+                help: "The format of <output> (imt, tar, zip, dir, or json). Guessed from the extension of <output>, if not specified",
+            },
+            FROM_OPTION,
+            CliOption {
+                name: "--encrypt",
+                short: None,
+                value: Some(("key-id", ValueKind::Text)),
+                help: "Encrypts each file of the bundle that holds experimental items with the key <key-id> from IMT_KEY_DIR (with the crypto feature)",
+            },
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "convert",
+        main: convert_main,
+        usage: "[OPTIONS...] <input> <output>",
+        about: "Converts the bundle <input> to another container format, and writes it to <output>",
+        notes: &[
+            "Formats are imt (a single IMT file), tar (a TAR archive), zip (a ZIP archive), dir (a directory tree of IMT files), src (a directory tree of textual sources, which is only read), and json (the JSON form of the bundle, which is only written)",
+            "Compressed inputs are detected from their contents and decompressed, and outputs with a .gz, .tgz, .xz, .lzma, .bz2, or .zst extension are compressed with the corresponding program",
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature). An <output> of - writes to standard output",
+        ],
+        options: CONVERT_OPTIONS,
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "extract",
+        usage: "[OPTIONS...] <input> <dir>",
+        about: "Extracts the files of the bundle <input> into a directory tree of IMT files at <dir>",
+        notes: &[
+            "Runs imt-tool convert --to dir, and accepts its options",
+            "Compressed inputs are detected from their contents and decompressed",
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature)",
+        ],
+        main: extract_main,
+        options: CONVERT_OPTIONS,
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "strip",
+        main: strip_main,
+        usage: "[OPTIONS...] <input> <output>",
+        about: "Removes documentation, tool comments, synthetic items, or unrecognized attributes from the bundle <input>, and writes the smaller bundle to <output>. Types, values, and every other attribute are kept, so the bundle describes the same ABI",
+        notes: &[
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature). An <output> of - writes to standard output",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--from",
+                short: None,
+                value: Some(("format", ValueKind::Choice(FORMATS))),
+                help: "The format of <input>. Detected from the contents of <input>, or guessed from its extension, if not specified",
+            },
+            CliOption {
+                name: "--to",
+                short: None,
+                value: Some(("format", ValueKind::Choice(OUTPUT_FORMATS))),
+                help: "The format of <output>. Guessed from the extension of <output>, or the same as <input>, if not specified",
+            },
+            CliOption {
+                name: "--content",
+                short: None,
+                value: Some(("content", ValueKind::Choice(STRIP_CONTENTS))),
+                help: "The content to remove. <content> is a comma separated list of docs, comments, synthetic, unknown, or all (the default)",
+            },
+            CliOption {
+                name: "--prefix",
+                short: None,
+                value: Some(("path", ValueKind::Text)),
+                help: "The module that the root of the bundle corresponds to",
+            },
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "size",
+        main: size_main,
+        usage: "[OPTIONS...] <input>..",
+        about: "Prints the provenance of the bundles <input> (when, by whom, and from which source revision they were built), if recorded, and the encoded size of each of their files, and of each item and attribute type within each file, largest first",
+        notes: &[
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature)",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            CliOption {
+                name: "--top",
+                short: None,
+                value: Some(("n", ValueKind::Text)),
+                help: "Only prints the <n> largest files, and the <n> largest items and attribute types of each file",
+            },
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "provenance",
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Prints the provenance of the input: when, by whom, and from which source revision it was built, if it is a bundle that records it",
+        notes: &["Inputs are read like those of imt-tool dump (see imt-tool dump --help)"],
+        main: provenance_main,
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            BUNDLE_OPTION,
+            DUMP_PREFIX_OPTION,
+            DUMP_INPUT_OPTION,
+            UNZIP_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            CACHE_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "audit",
+        main: audit_main,
+        usage: "[OPTIONS...] <input>..",
+        about: "Prints a checklist of the security-relevant facts about the bundles <input>, for reviewing their interface: unsafe functions, pointer parameters without a direction or length, pointers without nullability information, files without a subsystem version, and secret data passed through user-controlled out-pointers (see the DataClass attribute)",
+        notes: &[
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature)",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "check-system",
+        main: check_system_main,
+        usage: "[OPTIONS...] <bundle>",
+        about: "Compares <bundle>, which bindings were generated from, against the interface bundle installed on the running system, and reports whether the bindings are stale",
+        notes: &[
+            "Only the modules of <bundle> are compared. Fails if the system changed or removed any of their items, and succeeds if it only added items or changed documentation",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--from",
+                short: None,
+                value: Some(("format", ValueKind::Choice(FORMATS))),
+                help: "The format of <bundle> and the system bundle (imt, tar, zip, dir, or src, a directory tree of textual sources to compile with the text feature). Detected from the contents of each, or guessed from its extension, if not specified",
+            },
+            PREFIX_OPTION,
+            CliOption {
+                name: "--system",
+                short: None,
+                value: Some(("bundle", ValueKind::File)),
+                help: "The interface bundle of the system. Defaults to the value of IMT_SYSTEM_BUNDLE, if set, or /usr/share/imt/system.tar",
+            },
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "Treats changes to functions that keep them ABI-compatible on <target> as compatible. <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "check-bindings",
+        main: check_bindings_main,
+        usage: "[OPTIONS...] --rustdoc <json> <input>..",
+        about: "Cross-checks the functions and structs of the bundles <input> against the hand-written Rust bindings (such as lilium-sys) described by the rustdoc JSON output <json>, and reports where they have drifted apart (with the rustdoc feature)",
+        notes: &[
+            "<json> is written by `cargo rustdoc -- -Z unstable-options --output-format json`. Fails if any item of the bundles is missing from the bindings, or declared with different parameters, return type, or fields",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--rustdoc",
+                short: None,
+                value: Some(("json", ValueKind::File)),
+                help: "The rustdoc JSON output for the bindings",
+            },
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "check-dwarf",
+        main: check_dwarf_main,
+        usage: "[OPTIONS...] --object <file> <input>..",
+        about: "Verifies the size and field offsets of each struct, and the parameters and return type of each function, of the bundles <input> against the DWARF debug information of the compiled object <file> (such as a kernel image or libc), to catch compiler or ABI mismatches (with the dwarf feature)",
+        notes: &[
+            "Structs and functions are matched by name (functions by the symbol they are linked by), and items that <file> does not describe are not checked. Fails if any checked item differs",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--object",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "The executable or shared object with DWARF debug information. Relocatable objects (.o files) are not supported, since their debug information is only resolved when they are linked",
+            },
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "The target that <file> was compiled for, which layouts are computed for (x86_64 by default). <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "check-symbols",
+        main: check_symbols_main,
+        usage: "[OPTIONS...] --object <file> <input>..",
+        about: "Verifies that the ELF shared object <file> exports each function of the bundles <input>, by the symbol of its LinkName attribute or by its name, and that each function <file> exports is a function of the bundles (with the elf feature)",
+        notes: &[
+            "Functions that are IPC methods have no symbol, and are not checked. Fails if any function is not exported, or any exported function is undocumented",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--object",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "The ELF shared object whose dynamic symbol table is checked",
+            },
+            CliOption {
+                name: "--allow-undocumented",
+                short: None,
+                value: None,
+                help: "Does not report functions that <file> exports, but that are not functions of the bundles",
+            },
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "doc",
+        main: doc_main,
+        usage: "[OPTIONS...] --out <dir> <input>..",
+        about: "Generates a page of documentation for each file of the bundles <input>, and an index page, in <dir>",
+        notes: &[
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature)",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--out",
+                short: None,
+                value: Some(("dir", ValueKind::Dir)),
+                help: "The directory to write the documentation to",
+            },
+            CliOption {
+                name: "--format",
+                short: None,
+                value: Some(("format", ValueKind::Choice(&["html", "md", "man"]))),
+                help: "The format of the documentation: html (the default), md, or man",
+            },
+            CliOption {
+                name: "--doc-lang",
+                short: None,
+                value: Some(("lang", ValueKind::Text)),
+                help: "Uses the translation of the documentation into the language <lang> (e.g. de or pt-BR), where there is one",
+            },
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            METRICS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "gen",
+        main: gen_main,
+        usage: "[OPTIONS...] --plugin <program> --out <dir> <input>..",
+        about: "Generates code for the bundles <input> with the external generator <program>, writing its outputs to <dir>",
+        notes: &[
+            "<program> receives the target and each file of the bundle on standard input, and writes each output (a name and its contents) to standard output.",
+            "Each integer is little-endian, and each string or blob is preceded by its length as a u64:",
+            "\tRequest: the magic number IMTPLUG\\0, the protocol version (1) as a u32, the target as a spec file, and the number of files as a u32, followed by the path (like base::io) and IMT encoding of each file",
+            "\tResponse: the name and contents of each output, until end of file",
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature)",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--plugin",
+                short: None,
+                value: Some(("program", ValueKind::Command)),
+                help: "The generator to run. If a plugin in the plugin directory is named <program>, it is run instead",
+            },
+            CliOption {
+                name: "--plugin-arg",
+                short: None,
+                value: Some(("arg", ValueKind::Text)),
+                help: "Passes <arg> to <program>. May be given more than once",
+            },
+            CliOption {
+                name: "--plugin-dir",
+                short: None,
+                value: Some(("dir", ValueKind::Dir)),
+                help: "Loads the dynamic library plugins in <dir> (with the dylib feature). Defaults to the value of IMT_PLUGIN_DIR, if set",
+            },
+            CliOption {
+                name: "--out",
+                short: None,
+                value: Some(("dir", ValueKind::Dir)),
+                help: "The directory to write the outputs of <program> to",
+            },
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "The target to generate code for (x86_64 by default). <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+            METRICS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "grep",
+        main: grep_main,
+        usage: "[OPTIONS...] <pattern> <input>..",
+        about: "Prints each name, line of documentation, and string constant in the bundles <input> that matches the regular expression <pattern>, with the item it belongs to",
+        notes: &[
+            "An <input> of - reads from standard input, and an http:// or https:// URL is downloaded (with the fetch feature)",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--ignore-case",
+                short: Some("-i"),
+                value: None,
+                help: "Matches <pattern> without regard to case",
+            },
+            CliOption {
+                name: "--context",
+                short: Some("-C"),
+                value: Some(("n", ValueKind::Text)),
+                help: "Prints <n> lines of documentation before and after each matching line",
+            },
+            CliOption {
+                name: "--only",
+                short: None,
+                value: Some(("kinds", ValueKind::Choice(&["names", "docs", "strings"]))),
+                help: "Only searches the given kinds of text. <kinds> is a comma separated list of names, docs, or strings",
+            },
+            FROM_OPTION,
+            PREFIX_OPTION,
+            INPUT_OPTION,
+            MANIFEST_OPTION,
+            UNKNOWN_ATTRS_OPTION,
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "publish",
+        main: publish_main,
+        usage: "[OPTIONS...] --index <index> --version <version> <bundle>",
+        about: "Adds the bundle <bundle> (an IMT file or TAR archive, possibly compressed) to the registry index <index> as version <version>, creating <index> if it does not exist",
+        notes: &[
+            "Each line of an index is the name, version, SHA-256 hash, and download location of a bundle",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--index",
+                short: None,
+                value: Some(("index", ValueKind::File)),
+                help: "The index file to add <bundle> to",
+            },
+            CliOption {
+                name: "--version",
+                short: None,
+                value: Some(("version", ValueKind::Text)),
+                help: "The version of <bundle>, of the form <major>.<minor>",
+            },
+            CliOption {
+                name: "--name",
+                short: None,
+                value: Some(("name", ValueKind::Text)),
+                help: "The name of <bundle>. Defaults to the file name of <bundle> without extensions",
+            },
+            CliOption {
+                name: "--location",
+                short: None,
+                value: Some(("location", ValueKind::Text)),
+                help: "The URL or path that <bundle> is downloaded from, relative to <index> unless absolute. Defaults to the file name of <bundle>",
+            },
+            CliOption {
+                name: "--unknown-attrs",
+                short: None,
+                value: Some(("policy", ValueKind::Choice(UNKNOWN_ATTRS_POLICIES))),
+                help: "How to handle unrecognized attributes without the IGNORE flag while checking <bundle>. <policy> is error (the default), warn (keep the attribute and print a warning), or drop",
+            },
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "fetch",
+        main: fetch_main,
+        usage: "[OPTIONS...] --index <index> <name>[@<version>]",
+        about: "Downloads the latest version of the bundle <name> listed by the registry index <index>, or the latest version compatible with <version>, and checks its hash",
+        notes: &[
+            "<index> and the locations it lists may be paths or http:// or https:// URLs (with the fetch feature)",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--index",
+                short: None,
+                value: Some(("index", ValueKind::File)),
+                help: "The index listing the bundle",
+            },
+            CliOption {
+                name: "--out",
+                short: None,
+                value: Some(("file", ValueKind::File)),
+                help: "The file to write the bundle to, or - for standard output. Defaults to the file name of its location, in the current directory",
+            },
+        ],
+        inputs: ValueKind::Text,
+    },
+    CliCommand {
+        name: "build",
+        main: build_main,
+        usage: "[OPTIONS...] [<project>]",
+        about: "Builds the project or workspace described by the manifest <project>, or by the imt.toml in the directory <project>. Defaults to the imt.toml in the current directory",
+        notes: &[
+            "The bundle is loaded from the sources of the project, filtered to its features, validated for each of its targets, and written to its output. Then each of its codegen backends generates code for each target",
+            "Manifest:",
+            "\t[package]: name, prefix (the module of the sources), sources (default [\"src\"]), output (default build/<name>.tar), features, targets (default [\"x86_64\"]), dependencies (the packages of the workspace the sources refer to), validate (default true), and unknown-attrs",
+            "\t[codegen.<name>]: backend (default <name>), out (default build/<name>), and the options of the backend. The plugin backend runs the external generator program with args, as in imt-tool gen",
+            "\t[workspace]: members (the directories of the packages of the workspace), and output (default build/system.tar). Replaces [package] and [codegen]",
+            "The packages of a workspace are built in the order of their dependencies, and are then merged into a single system bundle",
+        ],
+        options: &[HELP_OPTION, ERROR_FORMAT_OPTION],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "fmt",
+        main: fmt_main,
+        usage: "[OPTIONS...] [--] [file..]",
+        about: "Formats the textual IMT sources <file> in the canonical style (with the text feature), rewriting those that are not already formatted. Formats standard input to standard output if no <file> is given",
+        notes: &[
+            "The attributes of each item are ordered with its documentation first, then the rest by name, and lines of documentation are wrapped to 80 characters",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--check",
+                short: None,
+                value: None,
+                help: "Rewrites nothing, but prints the name of each <file> that is not formatted, and fails if there are any",
+            },
+        ],
+        inputs: ValueKind::File,
+    },
+    CliCommand {
+        name: "watch",
+        main: watch_main,
+        usage: "[OPTIONS...] [--] <dir>",
+        about: "Compiles the textual IMT sources in the directory tree <dir> into a bundle and validates it, then recompiles each source when it changes and validates the bundle again, until interrupted",
+        notes: &[
+            "Each validation prints the problems that were not found by the previous one, and the number that were fixed. A source that fails to compile is reported, and its previous version is kept in the bundle until it compiles again",
+        ],
+        options: &[
+            HELP_OPTION,
+            ERROR_FORMAT_OPTION,
+            CliOption {
+                name: "--prefix",
+                short: None,
+                value: Some(("path", ValueKind::Text)),
+                help: "The module that <dir> corresponds to. Each source is compiled to the module at its path relative to <dir>, under <path>",
+            },
+            CliOption {
+                name: "--target",
+                short: None,
+                value: Some(("target", ValueKind::Target)),
+                help: "Also checks the target-specific properties of the bundle. <target> is a target spec file, a target triple (e.g. x86_64-pc-lilium-std), or a preset",
+            },
+        ],
+        inputs: ValueKind::Dir,
+    },
+    CliCommand {
+        name: "attrs",
+        main: attrs_main,
+        usage: "[OPTIONS...]",
+        about: "Prints the id, class, payload version, targets, and payload schema of each attribute known to imt-tool",
+        notes: &[],
+        options: &[HELP_OPTION, ERROR_FORMAT_OPTION],
+        inputs: ValueKind::Text,
+    },
+    CliCommand {
+        name: "explain",
+        main: explain_main,
+        usage: "[OPTIONS...] <code>",
+        about: "Prints the explanation of the diagnostic code <code> (e.g. IMT0001), which validation prints with each problem it finds",
+        notes: &[],
+        options: &[HELP_OPTION, ERROR_FORMAT_OPTION],
+        inputs: ValueKind::Text,
+    },
+    CliCommand {
+        name: "completions",
+        main: completions_main,
+        usage: "[OPTIONS...] <shell>",
+        about: "Prints a script that completes the arguments of imt-tool in <shell> (bash, zsh, or fish)",
+        notes: &[],
+        options: &[HELP_OPTION, ERROR_FORMAT_OPTION],
+        inputs: ValueKind::Choice(SHELLS),
+    },
+    CliCommand {
+        name: "cli-json",
+        main: cli_json_main,
+        usage: "[OPTIONS...]",
+        about: "Prints a JSON description of each command of imt-tool and its options, and of its exit statuses",
+        notes: &[],
+        options: &[HELP_OPTION, ERROR_FORMAT_OPTION],
+        inputs: ValueKind::Text,
+    },
+];
@@ -0,0 +1,325 @@
+use core::fmt::Write as _;
+
+use imt::{json::string as json_string, target::PRESETS};
+
+use crate::{
+    FailureClass,
+    cli::{Args, CLI, ValueKind, print_help},
+    usage_error,
+};
+
+pub(crate) fn completions_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    let mut positional = Vec::new();
+    for arg in args {
+        match &*arg {
+            "--help" => return print_help(prg_name, "completions"),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("completions: unknown option {x}")));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let [shell] = &positional[..] else {
+        return Err(usage_error("completions requires exactly one shell"));
+    };
+    print!("{}", completions(shell)?);
+    Ok(())
+}
+
+pub(crate) fn cli_json_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    if let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "cli-json"),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("cli-json: unknown option {x}")));
+            }
+            x => {
+                return Err(usage_error(format!("cli-json: unexpected argument {x}")));
+            }
+        }
+    }
+
+    println!("{}", cli_json());
+    Ok(())
+}
+
+pub(crate) const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// The script that completes the arguments of imt-tool in `shell`
+fn completions(shell: &str) -> std::io::Result<String> {
+    let mut out = String::new();
+    let subcommands = CLI
+        .iter()
+        .filter(|cmd| !cmd.name.is_empty())
+        .map(|cmd| cmd.name)
+        .collect::<Vec<_>>()
+        .join(" ");
+    match shell {
+        "bash" => {
+            out.push_str("_imt_tool() {\n");
+            out.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\" cmd=\"\"\n");
+            out.push_str("    COMPREPLY=()\n");
+            let _ = writeln!(
+                out,
+                "    if [[ $COMP_CWORD -gt 1 && \" {subcommands} \" == *\" ${{COMP_WORDS[1]}} \"* ]]; then"
+            );
+            out.push_str("        cmd=\"${COMP_WORDS[1]}\"\n");
+            out.push_str("    fi\n");
+            out.push_str("    case \"$cmd\" in\n");
+            for cmd in CLI {
+                let _ = writeln!(
+                    out,
+                    "    {})",
+                    if cmd.name.is_empty() {
+                        "\"\""
+                    } else {
+                        cmd.name
+                    }
+                );
+                out.push_str("        case \"$prev\" in\n");
+                for opt in cmd.options {
+                    let Some((_, kind)) = opt.value else {
+                        continue;
+                    };
+                    let names = opt.short.map_or(opt.name.to_string(), |short| {
+                        format!("{short}|{}", opt.name)
+                    });
+                    let reply = match kind {
+                        ValueKind::File => "compgen -f -- \"$cur\"".to_string(),
+                        ValueKind::Dir => "compgen -d -- \"$cur\"".to_string(),
+                        ValueKind::Command => "compgen -c -- \"$cur\"".to_string(),
+                        ValueKind::Choice(choices) => {
+                            format!("compgen -W \"{}\" -- \"$cur\"", choices.join(" "))
+                        }
+                        ValueKind::Target => {
+                            format!("compgen -f -W \"{}\" -- \"$cur\"", PRESETS.join(" "))
+                        }
+                        ValueKind::Text => String::new(),
+                    };
+                    if reply.is_empty() {
+                        let _ = writeln!(out, "        {names}) return ;;");
+                    } else {
+                        let _ = writeln!(out, "        {names}) COMPREPLY=($({reply})); return ;;");
+                    }
+                }
+                out.push_str("        esac\n");
+                let options = cmd
+                    .options
+                    .iter()
+                    .flat_map(|opt| opt.short.into_iter().chain([opt.name]))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                out.push_str("        if [[ $cur == -* ]]; then\n");
+                let _ = writeln!(
+                    out,
+                    "            COMPREPLY=($(compgen -W \"{options}\" -- \"$cur\"))"
+                );
+                out.push_str("        else\n");
+                out.push_str("            COMPREPLY=($(compgen -f -- \"$cur\"))\n");
+                if cmd.name.is_empty() {
+                    out.push_str("            if [[ $COMP_CWORD -eq 1 ]]; then\n");
+                    let _ = writeln!(
+                        out,
+                        "                COMPREPLY+=($(compgen -W \"{subcommands}\" -- \"$cur\"))"
+                    );
+                    out.push_str("            fi\n");
+                }
+                out.push_str("        fi\n");
+                out.push_str("        ;;\n");
+            }
+            out.push_str("    esac\n");
+            out.push_str("}\n");
+            out.push_str("complete -o filenames -F _imt_tool imt-tool\n");
+        }
+        "zsh" => {
+            // Descriptions are within single quotes and brackets
+            let escape = |s: &str| {
+                s.replace('\'', "'\\''")
+                    .replace('[', "\\[")
+                    .replace(']', "\\]")
+                    .replace(':', "\\:")
+            };
+            out.push_str("#compdef imt-tool\n\n");
+            out.push_str("_imt_tool() {\n");
+            out.push_str("    case $words[2] in\n");
+            // The fallback for imt-tool itself must come last
+            let mut cmds = CLI.iter().collect::<Vec<_>>();
+            cmds.sort_by_key(|cmd| cmd.name.is_empty());
+            for cmd in cmds {
+                if cmd.name.is_empty() {
+                    out.push_str("    *)\n");
+                    out.push_str("        _arguments -s \\\n");
+                } else {
+                    let _ = writeln!(out, "    {})", cmd.name);
+                    out.push_str("        shift words\n");
+                    out.push_str("        (( CURRENT-- ))\n");
+                    out.push_str("        _arguments -s \\\n");
+                }
+                for opt in cmd.options {
+                    let action = match opt.value {
+                        None => String::new(),
+                        Some((name, ValueKind::File)) => format!(":{name}:_files"),
+                        Some((name, ValueKind::Dir)) => format!(":{name}:_files -/"),
+                        Some((name, ValueKind::Command)) => format!(":{name}:_command_names -e"),
+                        Some((name, ValueKind::Choice(choices))) => {
+                            format!(":{name}:({})", choices.join(" "))
+                        }
+                        Some((name, ValueKind::Target)) => format!(
+                            ":{name}:_alternative \"presets:preset:({})\" \"files:file:_files\"",
+                            PRESETS.join(" ")
+                        ),
+                        Some((name, ValueKind::Text)) => format!(":{name}: "),
+                    };
+                    let help = escape(opt.help);
+                    match opt.short {
+                        Some(short) => {
+                            let _ = writeln!(
+                                out,
+                                "            '({short} {long})'{{{short},{long}}}'[{help}]{action}' \\",
+                                long = opt.name
+                            );
+                        }
+                        None => {
+                            let _ = writeln!(out, "            '{}[{help}]{action}' \\", opt.name);
+                        }
+                    }
+                }
+                if cmd.name.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        "            '1: :_alternative \"commands:command:({subcommands})\" \"files:file:_files\"' \\"
+                    );
+                }
+                out.push_str("            '*:file:_files'\n");
+                out.push_str("        ;;\n");
+            }
+            out.push_str("    esac\n");
+            out.push_str("}\n\n");
+            out.push_str("_imt_tool \"$@\"\n");
+        }
+        "fish" => {
+            let escape = |s: &str| s.replace('\\', "\\\\").replace('\'', "\\'");
+            for cmd in CLI {
+                let condition = if cmd.name.is_empty() {
+                    format!("not __fish_seen_subcommand_from {subcommands}")
+                } else {
+                    format!("__fish_seen_subcommand_from {}", cmd.name)
+                };
+                if !cmd.name.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        "complete -c imt-tool -n '__fish_use_subcommand' -f -a {} -d '{}'",
+                        cmd.name,
+                        escape(cmd.about)
+                    );
+                }
+                for opt in cmd.options {
+                    let _ = write!(
+                        out,
+                        "complete -c imt-tool -n '{condition}' -l {}",
+                        opt.name.trim_start_matches('-')
+                    );
+                    if let Some(short) = opt.short {
+                        let _ = write!(out, " -s {}", short.trim_start_matches('-'));
+                    }
+                    match opt.value {
+                        None => out.push_str(" -f"),
+                        Some((_, ValueKind::File)) => out.push_str(" -r -F"),
+                        Some((_, ValueKind::Dir)) => {
+                            out.push_str(" -x -a '(__fish_complete_directories)'")
+                        }
+                        Some((_, ValueKind::Command)) => {
+                            out.push_str(" -x -a '(__fish_complete_command)'")
+                        }
+                        Some((_, ValueKind::Choice(choices))) => {
+                            let _ = write!(out, " -x -a '{}'", choices.join(" "));
+                        }
+                        Some((_, ValueKind::Target)) => {
+                            let _ = write!(out, " -r -F -a '{}'", PRESETS.join(" "));
+                        }
+                        Some((_, ValueKind::Text)) => out.push_str(" -x"),
+                    }
+                    let _ = writeln!(out, " -d '{}'", escape(opt.help));
+                }
+            }
+        }
+        _ => {
+            return Err(usage_error(format!(
+                "completions: unknown shell {shell} (expected bash, zsh, or fish)"
+            )));
+        }
+    }
+    Ok(out)
+}
+
+/// A JSON description of each command of imt-tool and its options, for `cli-json`
+fn cli_json() -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{{\"name\":\"imt-tool\",\"version\":{},\"commands\":[",
+        json_string(core::env!("CARGO_PKG_VERSION"))
+    );
+    for (i, cmd) in CLI.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "{{\"name\":{},\"usage\":{},\"about\":{},\"inputs\":{},\"options\":[",
+            if cmd.name.is_empty() {
+                "null".to_string()
+            } else {
+                json_string(cmd.name)
+            },
+            json_string(cmd.usage),
+            json_string(cmd.about),
+            json_string(cmd.inputs.as_str())
+        );
+        for (j, opt) in cmd.options.iter().enumerate() {
+            if j != 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "{{\"name\":{},\"short\":{},",
+                json_string(opt.name),
+                opt.short.map_or("null".to_string(), json_string)
+            );
+            match opt.value {
+                Some((name, kind)) => {
+                    let choices = kind
+                        .choices()
+                        .iter()
+                        .map(|choice| json_string(choice))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    let _ = write!(
+                        out,
+                        "\"value\":{{\"name\":{},\"kind\":{},\"choices\":[{choices}]}},",
+                        json_string(name),
+                        json_string(kind.as_str())
+                    );
+                }
+                None => out.push_str("\"value\":null,"),
+            }
+            let _ = write!(out, "\"help\":{}}}", json_string(opt.help));
+        }
+        out.push_str("]}");
+    }
+    out.push_str("],\"exit_codes\":[");
+    for (i, class) in FailureClass::ALL.into_iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "{{\"code\":{},\"class\":{}}}",
+            class as u8,
+            json_string(class.as_str())
+        );
+    }
+    out.push_str("]}");
+    out
+}
@@ -0,0 +1,283 @@
+use std::io::ErrorKind;
+
+use imt::{
+    bundle::{Bundle, Path},
+    config::format_config,
+    json::ToJson,
+    provenance::Provenance,
+    strip::StripOptions,
+};
+
+use crate::{
+    cli::{Args, preset_main, print_help},
+    io::{Container, Input, InputOptions, write_output},
+    parse_strip_content, usage_error,
+};
+
+pub(crate) fn create_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("create");
+    let mut to = None;
+    let mut encrypt = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "create"),
+            "--to" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--to requires an argument"))?;
+                to = Some(Container::parse(&arg)?);
+            }
+            "--encrypt" => {
+                encrypt = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--encrypt requires an argument"))?,
+                );
+            }
+            "--" => {
+                for arg in args.by_ref() {
+                    match output {
+                        None => output = Some(arg),
+                        Some(_) => inputs.inputs.push(Input::from(arg)),
+                    }
+                }
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("create: unknown option {x}")));
+            }
+            _ if output.is_none() => output = Some(arg),
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let output = output.ok_or_else(|| usage_error("create requires an output"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("create requires at least one input"));
+    }
+    let to = to.or_else(|| Container::detect(&output)).ok_or_else(|| {
+        usage_error(format!(
+            "{output}: cannot guess the format of the output, specify it with --to"
+        ))
+    })?;
+
+    let mut bundle = inputs.load_containers(None)?;
+    if let Some(key_id) = &encrypt {
+        set_member_encryption(&mut bundle, key_id)?;
+    }
+    bundle.set_provenance(Provenance::from_env());
+
+    write_container(
+        &mut bundle,
+        to,
+        &output,
+        &inputs.prefix(),
+        encrypt.is_some(),
+    )
+}
+
+pub(crate) fn extract_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    preset_main(
+        prg_name,
+        "extract",
+        "convert",
+        vec!["--to".to_string(), "dir".to_string()],
+        args,
+    )
+}
+
+pub(crate) fn convert_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("convert");
+    let mut to = None;
+    let mut encrypt = None;
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "convert"),
+            "--to" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--to requires an argument"))?;
+                to = Some(Container::parse(&arg)?);
+            }
+            "--encrypt" => {
+                encrypt = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--encrypt requires an argument"))?,
+                );
+            }
+            "--" => {
+                paths.extend(args.by_ref());
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("convert: unknown option {x}")));
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    let [input, output] = &paths[..] else {
+        return Err(usage_error("convert requires an input and an output"));
+    };
+    let to = to.ok_or_else(|| usage_error("convert requires --to <format>"))?;
+
+    inputs.inputs.push(Input::from(input.clone()));
+
+    let mut bundle = inputs.load_containers(None)?;
+    if let Some(key_id) = &encrypt {
+        set_member_encryption(&mut bundle, key_id)?;
+    }
+    // Converting does not rebuild the bundle, so the record of its original build is kept
+    if bundle.provenance().is_none() {
+        bundle.set_provenance(Provenance::from_env());
+    }
+
+    write_container(&mut bundle, to, output, &inputs.prefix(), encrypt.is_some())
+}
+
+/// Writes `bundle` to `output` in the format `to`. `encrypted` is set if the files of the bundle are encrypted when written
+fn write_container(
+    bundle: &mut Bundle,
+    to: Container,
+    output: &str,
+    prefix: &Path,
+    encrypted: bool,
+) -> std::io::Result<()> {
+    match to {
+        Container::Imt if encrypted => Err(usage_error(
+            "--encrypt only applies to the files of tar, zip, and dir outputs",
+        )),
+        Container::Imt => {
+            let mut files = bundle.iter();
+            let (Some((_, file)), None) = (files.next(), files.next()) else {
+                return Err(usage_error(format!(
+                    "{output}: an imt file can only hold one file of the bundle"
+                )));
+            };
+            let content = bincode::encode_to_vec(file, format_config())
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+            write_output(output, &content)
+        }
+        #[cfg(feature = "tar")]
+        Container::Tar => {
+            let mut content = std::io::Cursor::new(Vec::new());
+            bundle.write_tar(prefix, &mut content)?;
+            write_output(output, content.get_ref())
+        }
+        #[cfg(not(feature = "tar"))]
+        Container::Tar => Err(std::io::Error::other(
+            "writing TAR archives requires building with the tar feature",
+        )),
+        #[cfg(feature = "zip")]
+        Container::Zip => {
+            let mut content = std::io::Cursor::new(Vec::new());
+            bundle.write_zip(prefix, &mut content)?;
+            write_output(output, content.get_ref())
+        }
+        #[cfg(not(feature = "zip"))]
+        Container::Zip => Err(std::io::Error::other(
+            "writing ZIP archives requires building with the zip feature",
+        )),
+        Container::Dir if output == "-" => Err(usage_error(
+            "a directory cannot be written to standard output",
+        )),
+        Container::Dir => bundle.write_dir(prefix, std::path::Path::new(output)),
+        Container::Json if encrypted => Err(usage_error(
+            "--encrypt only applies to the files of tar, zip, and dir outputs",
+        )),
+        Container::Json => {
+            let mut content = bundle.to_json();
+            content.push('\n');
+            write_output(output, content.as_bytes())
+        }
+        Container::Src => Err(usage_error(
+            "textual sources are only compiled, not written (render files with dump)",
+        )),
+    }
+}
+
+/// Encrypts the files of `bundle` that hold experimental items with the key `key_id` from `IMT_KEY_DIR` when it is written
+#[cfg(feature = "crypto")]
+fn set_member_encryption(bundle: &mut Bundle, key_id: &str) -> std::io::Result<()> {
+    use imt::crypto::KeyLookup;
+
+    let dir = std::env::var_os("IMT_KEY_DIR").ok_or_else(|| {
+        usage_error("--encrypt requires IMT_KEY_DIR to name the directory of the key")
+    })?;
+    let key = imt::crypto::KeyDir::new(dir).key(key_id).ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("--encrypt: the key {key_id} is not in IMT_KEY_DIR"),
+        )
+    })?;
+    bundle
+        .set_member_encryption(key_id, key)
+        .map_err(|e| usage_error(format!("--encrypt: {e}")))
+}
+
+#[cfg(not(feature = "crypto"))]
+fn set_member_encryption(_: &mut Bundle, _: &str) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "--encrypt requires building with the crypto feature",
+    ))
+}
+
+pub(crate) fn strip_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("strip");
+    let mut to = None;
+    let mut strip = StripOptions::all();
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "strip"),
+            "--to" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--to requires an argument"))?;
+                to = Some(Container::parse(&arg)?);
+            }
+            "--content" => {
+                let content = args
+                    .next()
+                    .ok_or_else(|| usage_error("--content requires an argument"))?;
+                strip = parse_strip_content("--content", &content)?;
+            }
+            "--" => {
+                paths.extend(args.by_ref());
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("strip: unknown option {x}")));
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    let [input, output] = &paths[..] else {
+        return Err(usage_error("strip requires an input and an output"));
+    };
+    let to = to
+        .or_else(|| Container::detect(output))
+        .or(inputs.from)
+        .or_else(|| Container::detect(input))
+        .ok_or_else(|| {
+            usage_error(format!(
+                "{output}: cannot guess the format to write, specify --to <format>"
+            ))
+        })?;
+
+    inputs.inputs.push(Input::from(input.clone()));
+
+    let mut bundle = inputs.load_containers(None)?;
+    bundle.strip(strip);
+    // Stripping does not rebuild the bundle, so the record of its original build is kept
+    if bundle.provenance().is_none() {
+        bundle.set_provenance(Provenance::from_env());
+    }
+
+    write_container(&mut bundle, to, output, &inputs.prefix(), false)
+}
@@ -0,0 +1,203 @@
+use std::time::Instant;
+
+use imt::{
+    attr::{find_attribute, find_attribute_mut, types::SubsystemDescriptor},
+    bundle::Bundle,
+    changelog::changelog,
+    diff::{BundleDiff, diff, diff_target},
+    metrics::Metrics,
+    target::TargetSpec,
+};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    dump::write_dump_output,
+    failure,
+    findings::Findings,
+    frozen::check_frozen_changes,
+    io::{Input, InputOptions},
+    load_target, usage_error, write_metrics,
+};
+
+/// The inputs of diff or bump, compared to their older revision
+struct Comparison {
+    inputs: InputOptions,
+    old: Bundle,
+    new: Bundle,
+    diff: BundleDiff,
+    target: Option<TargetSpec>,
+    deny_breaking: bool,
+    findings: Findings,
+    /// Whether a breaking change is not accepted by --baseline, once [`Comparison::check`] found it
+    breaking: bool,
+    /// Where bump writes the inputs, if given by --output
+    output: Option<String>,
+    metrics: Metrics,
+    metrics_file: Option<String>,
+    /// When the comparison started, after decoding the inputs
+    start: Instant,
+}
+
+impl Comparison {
+    /// Parses the arguments of the command `name`, which is diff or bump, and compares the inputs to the older revision they name.
+    /// Returns `None` if the command only printed its `--help`
+    fn new(prg_name: &str, name: &str, mut args: Args) -> std::io::Result<Option<Self>> {
+        // Options can come before, between, or after the positional arguments, the first of which is the older revision
+        let mut positionals = Vec::new();
+        let mut inputs = InputOptions::new(name);
+        let mut target = None;
+        let mut deny_breaking = false;
+        let mut findings = Findings::default();
+        let mut output = None;
+        let mut metrics_file = None;
+
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "--help" => {
+                    print_help(prg_name, name)?;
+                    return Ok(None);
+                }
+                "--target" => {
+                    let arg = args
+                        .next()
+                        .ok_or_else(|| usage_error("--target requires an argument"))?;
+                    target = Some(load_target(&arg)?);
+                }
+                "--deny-breaking" => {
+                    deny_breaking = true;
+                }
+                "--output" if name == "bump" => {
+                    output = Some(
+                        args.next()
+                            .ok_or_else(|| usage_error("--output requires an argument"))?,
+                    );
+                }
+                "--metrics" => {
+                    metrics_file = Some(
+                        args.next()
+                            .ok_or_else(|| usage_error("--metrics requires an argument"))?,
+                    );
+                }
+                "--" => break,
+                x if inputs.parse_option(x, &mut args)? => {}
+                x if findings.parse_option(x, &mut args)? => {}
+                x if x.starts_with("--") => {
+                    return Err(usage_error(format!("{name}: unknown option {x}")));
+                }
+                _ => positionals.push(arg),
+            }
+        }
+        positionals.extend(args);
+        let mut positionals = positionals.into_iter();
+        let old = positionals
+            .next()
+            .ok_or_else(|| usage_error(format!("{name} requires an older revision")))?;
+        inputs.inputs.extend(positionals.map(Input::from));
+
+        let mut metrics = Metrics::default();
+        let new = inputs.load(prg_name, metrics_file.as_ref().map(|_| &mut metrics))?;
+
+        let start = Instant::now();
+        let old = inputs.load_revision(&old)?;
+        let diff = match &target {
+            Some(target) => diff_target(&old, &new, target),
+            None => diff(&old, &new),
+        };
+        Ok(Some(Self {
+            inputs,
+            old,
+            new,
+            diff,
+            target,
+            deny_breaking,
+            findings,
+            breaking: false,
+            output,
+            metrics,
+            metrics_file,
+            start,
+        }))
+    }
+
+    /// Looks for the breaking changes and changes to frozen items that --baseline does not accept, printing the latter
+    fn check(&mut self) -> std::io::Result<()> {
+        self.breaking = self.findings.any_new_breaking(&self.diff);
+        check_frozen_changes(
+            &self.old,
+            &self.new,
+            self.target.as_ref(),
+            &mut self.findings,
+        )
+    }
+
+    /// Ends the comparison, which took the time of the phase `phase`. Writes the outputs of the options that name one, and then
+    /// fails if --deny-breaking is given and [`Comparison::check`] found a breaking change
+    fn finish(mut self, phase: &str) -> std::io::Result<()> {
+        self.metrics.add_phase(phase, self.start.elapsed());
+
+        self.findings.write()?;
+        if let Some(output) = &self.output {
+            write_dump_output(&mut self.new, &self.inputs, output)?;
+        }
+        write_metrics(self.metrics_file.as_deref(), self.metrics, &self.new)?;
+
+        if self.deny_breaking && self.breaking && self.findings.deny_new() {
+            return Err(failure(
+                FailureClass::BreakingChange,
+                "the input has breaking changes from the older revision",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn diff_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    let Some(mut cmp) = Comparison::new(prg_name, "diff", args)? else {
+        return Ok(());
+    };
+    print!("{}", changelog(&cmp.old, &cmp.new, &cmp.diff));
+    cmp.check()?;
+    cmp.finish("changelog")
+}
+
+pub(crate) fn bump_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    let Some(mut cmp) = Comparison::new(prg_name, "bump", args)? else {
+        return Ok(());
+    };
+    for change in &cmp.diff.changes {
+        println!("{change} ({})", change.severity());
+    }
+    cmp.check()?;
+
+    let paths = cmp
+        .new
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    for path in paths {
+        let old_version = cmp
+            .old
+            .get(&path)
+            .and_then(|file| find_attribute::<SubsystemDescriptor, _>(&file.attributes))
+            .map(|desc| desc.version);
+        let file = cmp.new.get_mut(&path).unwrap();
+        let Some(desc) = find_attribute_mut::<SubsystemDescriptor, _>(&mut file.attributes) else {
+            continue;
+        };
+        // Each file is versioned by its own changes, and a file without any keeps its version
+        let file_diff = cmp.diff.in_module(&path);
+        if file_diff.changes.is_empty() {
+            continue;
+        }
+        let old_version = old_version.unwrap_or(desc.version);
+        let version = file_diff.suggest_version(old_version).ok_or_else(|| {
+            std::io::Error::other(format!(
+                "{path}: no version follows {old_version} for these changes, as versions cannot exceed 127.511"
+            ))
+        })?;
+        println!("{path}: {old_version} -> {version}");
+        desc.version = version;
+    }
+    cmp.finish("bump")
+}
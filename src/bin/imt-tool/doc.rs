@@ -0,0 +1,98 @@
+use std::time::Instant;
+
+use imt::{
+    codegen::{
+        CodegenBackend,
+        doc::{DocBackend, DocFormat},
+    },
+    metrics::Metrics,
+    target::TargetSpec,
+};
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    usage_error, write_metrics,
+};
+
+pub(crate) fn doc_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("doc");
+    let mut out = None;
+    let mut format = DocFormat::Html;
+    let mut doc_lang = None;
+    let mut metrics_file = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "doc"),
+            "--out" => {
+                out = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--out requires an argument"))?,
+                );
+            }
+            "--format" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--format requires an argument"))?;
+                format = match &*arg {
+                    "html" => DocFormat::Html,
+                    "md" | "markdown" => DocFormat::Markdown,
+                    "man" => DocFormat::Man,
+                    _ => {
+                        return Err(usage_error(format!(
+                            "--format: unknown format {arg} (expected html, md, or man)"
+                        )));
+                    }
+                };
+            }
+            "--doc-lang" => {
+                doc_lang = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--doc-lang requires an argument"))?,
+                );
+            }
+            "--metrics" => {
+                metrics_file = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--metrics requires an argument"))?,
+                );
+            }
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("doc: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let out = out.ok_or_else(|| usage_error("doc requires --out <dir>"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("doc requires at least one input"));
+    }
+
+    let mut metrics = Metrics::default();
+    let mut bundle = inputs.load_containers(metrics_file.as_ref().map(|_| &mut metrics))?;
+    if let Some(lang) = &doc_lang {
+        bundle.localize_docs(lang);
+    }
+
+    let out = std::path::Path::new(&out);
+    let mut sink = |name: &str, contents: &[u8]| {
+        metrics.add_output(name, contents);
+        let path = out.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)
+    };
+    // The documentation does not depend on the target
+    let target = TargetSpec::preset("x86_64").unwrap();
+    let start = Instant::now();
+    DocBackend { format }.generate(&bundle, &target, &mut sink)?;
+    metrics.add_phase("generate", start.elapsed());
+    write_metrics(metrics_file.as_deref(), metrics, &bundle)
+}
@@ -0,0 +1,173 @@
+use std::io::ErrorKind;
+
+use bincode::error::EncodeError;
+use imt::{
+    bundle::Bundle, config::format_config, json::ToJson, metrics::Metrics, print::render_file,
+    provenance::Provenance, target::TargetSpec,
+};
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    load_target, parse_strip_content, usage_error, write_metrics,
+};
+
+/// Writes `bundle`, decoded from `inputs`, to `output`: as a TAR archive if `--bundle` is given, or else as its only file
+pub(crate) fn write_dump_output(
+    bundle: &mut Bundle,
+    inputs: &InputOptions,
+    output: &str,
+) -> std::io::Result<()> {
+    if inputs.is_bundle {
+        bundle.set_provenance(Provenance::from_env());
+        #[cfg(feature = "tar")]
+        return bundle.write_tar(&inputs.prefix(), std::fs::File::create(output)?);
+        #[cfg(not(feature = "tar"))]
+        return Err(std::io::Error::other(
+            "writing TAR archives requires building with the tar feature",
+        ));
+    }
+
+    // Checked before creating the output, so that an existing file is not truncated
+    let mut files = bundle.iter();
+    let (Some((_, file)), None) = (files.next(), files.next()) else {
+        return Err(usage_error(
+            "--output requires exactly one input file unless --bundle is specified",
+        ));
+    };
+    let mut out = std::fs::File::create(output)?;
+    bincode::encode_into_std_write(file, &mut out, format_config()).map_err(|e| match e {
+        EncodeError::Io { inner, .. } => inner,
+        e => std::io::Error::new(ErrorKind::InvalidData, e),
+    })?;
+    Ok(())
+}
+
+pub(crate) fn dump_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("dump");
+    let mut strip = None;
+    let mut output = None;
+    let mut target = None;
+    let mut fold_consts = false;
+    let mut explicit_padding = false;
+    let mut doc_lang = None;
+    let mut format = DumpFormat::Debug;
+    let mut metrics_file = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "dump"),
+            "--strip" => {
+                let content = args
+                    .next()
+                    .ok_or_else(|| usage_error("--strip requires an argument"))?;
+                strip = Some(parse_strip_content("--strip", &content)?);
+            }
+            "--doc-lang" => {
+                doc_lang = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--doc-lang requires an argument"))?,
+                );
+            }
+            "--fold-consts" => {
+                fold_consts = true;
+            }
+            "--explicit-padding" => {
+                explicit_padding = true;
+            }
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--format" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--format requires an argument"))?;
+                format = match &*arg {
+                    "debug" => DumpFormat::Debug,
+                    "json" => DumpFormat::Json,
+                    "text" => DumpFormat::Text,
+                    _ => {
+                        return Err(usage_error(format!(
+                            "--format: unknown format {arg} (expected debug, json, or text)"
+                        )));
+                    }
+                };
+            }
+            "--output" => {
+                output = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--output requires an argument"))?,
+                );
+            }
+            "--metrics" => {
+                metrics_file = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--metrics requires an argument"))?,
+                );
+            }
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("dump: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+
+    let mut metrics = Metrics::default();
+    let mut bundle = inputs.load(prg_name, metrics_file.as_ref().map(|_| &mut metrics))?;
+
+    if let Some(lang) = &doc_lang {
+        bundle.localize_docs(lang);
+    }
+
+    if let Some(strip) = strip {
+        bundle.strip(strip);
+    }
+
+    if fold_consts {
+        bundle.fold_constants();
+    }
+
+    if explicit_padding {
+        let target = target.unwrap_or_else(|| TargetSpec::preset("x86_64").unwrap());
+        bundle.materialize_padding(&target);
+    }
+
+    if let Some(output) = &output {
+        write_dump_output(&mut bundle, &inputs, output)?;
+    } else {
+        match format {
+            DumpFormat::Debug => println!("bundle: {bundle:#?}"),
+            DumpFormat::Json => println!("{}", bundle.to_json()),
+            DumpFormat::Text => {
+                let mut sep = "";
+                for (path, file) in bundle.iter() {
+                    print!("{sep}");
+                    sep = "\n";
+                    if !path.0.is_empty() {
+                        println!("// module {path}");
+                    }
+                    print!("{}", render_file(file));
+                }
+            }
+        }
+    }
+
+    write_metrics(metrics_file.as_deref(), metrics, &bundle)
+}
+
+/// How the default command displays the input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum DumpFormat {
+    Debug,
+    Json,
+    Text,
+}
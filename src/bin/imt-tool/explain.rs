@@ -0,0 +1,29 @@
+use imt::diagnostics::codes;
+
+use crate::{
+    cli::{Args, print_help},
+    usage_error,
+};
+
+pub(crate) fn explain_main(prg_name: &str, args: Args) -> std::io::Result<()> {
+    let mut positional = Vec::new();
+    for arg in args {
+        match &*arg {
+            "--help" => return print_help(prg_name, "explain"),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("explain: unknown option {x}")));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let [code] = &positional[..] else {
+        return Err(usage_error("explain requires exactly one code"));
+    };
+    let entry =
+        codes::explain(code).ok_or_else(|| usage_error(format!("explain: unknown code {code}")))?;
+    println!("{}: {}", entry.code, entry.summary);
+    println!();
+    println!("{}", entry.explanation);
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::io::ErrorKind;
+
+use imt::{
+    baseline::Baseline,
+    diff::{BundleDiff, Severity as ChangeSeverity},
+};
+
+use crate::{cli::Args, usage_error};
+
+/// The findings of validate, bump, and diff that are accepted by --baseline, and those recorded for --write-baseline
+#[derive(Default)]
+pub(crate) struct Findings {
+    accepted: Baseline,
+    recorded: Option<Baseline>,
+    /// The file that --write-baseline writes the recorded findings to
+    file: Option<String>,
+}
+
+impl Findings {
+    /// Parses `opt`, taking its argument from `args`, if it is --baseline or --write-baseline. Returns whether it was
+    pub(crate) fn parse_option(&mut self, opt: &str, args: &mut Args) -> std::io::Result<bool> {
+        match opt {
+            "--baseline" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--baseline requires an argument"))?;
+                self.accepted = Baseline::read(std::io::BufReader::new(std::fs::File::open(&arg)?))
+                    .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+            }
+            "--write-baseline" => {
+                self.file = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--write-baseline requires an argument"))?,
+                );
+                self.recorded = Some(Baseline::default());
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Writes the recorded findings for --write-baseline, if given
+    pub(crate) fn write(&self) -> std::io::Result<()> {
+        if let (Some(file), Some(recorded)) = (&self.file, &self.recorded) {
+            recorded.write(std::fs::File::create(file)?)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the finding is not accepted by --baseline. Records the finding if writing a baseline
+    pub(crate) fn is_new(
+        &mut self,
+        fingerprint: u64,
+        description: impl core::fmt::Display,
+    ) -> bool {
+        if let Some(recorded) = &mut self.recorded {
+            recorded.insert(fingerprint, description.to_string());
+        }
+        !self.accepted.contains(fingerprint)
+    }
+
+    /// Whether new findings fail their check, which they don't when writing a baseline that accepts them
+    pub(crate) fn deny_new(&self) -> bool {
+        self.recorded.is_none()
+    }
+
+    /// Whether `diff` has a breaking change that is not accepted by --baseline. Records each breaking change if writing a baseline
+    pub(crate) fn any_new_breaking(&mut self, diff: &BundleDiff) -> bool {
+        let mut breaking = false;
+        for change in &diff.changes {
+            if change.severity() == ChangeSeverity::Breaking
+                && self.is_new(change.fingerprint(), change)
+            {
+                breaking = true;
+            }
+        }
+        breaking
+    }
+}
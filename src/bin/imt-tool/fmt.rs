@@ -0,0 +1,70 @@
+use std::io::ErrorKind;
+
+use imt::text::{format::format_source, parse::ParseError};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    failure, usage_error,
+};
+
+pub(crate) fn fmt_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut check = false;
+    let mut files = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "fmt"),
+            "--check" => check = true,
+            "--" => files.extend(args.by_ref()),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("fmt: unknown option {x}")));
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    if files.is_empty() {
+        let mut src = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin().lock(), &mut src)?;
+        let formatted = format_source(&src).map_err(|e| source_error("<stdin>", &src, &e))?;
+        if !check {
+            print!("{formatted}");
+        } else if formatted != src {
+            return Err(failure(
+                FailureClass::Validation,
+                "standard input is not formatted",
+            ));
+        }
+        return Ok(());
+    }
+
+    let mut unformatted = 0;
+    for file in &files {
+        let src = std::fs::read_to_string(file)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("{file}: {e}")))?;
+        let formatted = format_source(&src).map_err(|e| source_error(file, &src, &e))?;
+        if formatted == src {
+            continue;
+        }
+        if check {
+            println!("{file}");
+            unformatted += 1;
+        } else {
+            std::fs::write(file, formatted)?;
+        }
+    }
+    if unformatted != 0 {
+        return Err(failure(
+            FailureClass::Validation,
+            format!("{unformatted} of {} files are not formatted", files.len()),
+        ));
+    }
+    Ok(())
+}
+
+/// An error for `e` in the source `src` read from `path`, located by its line and column
+fn source_error(path: &str, src: &str, e: &ParseError) -> std::io::Error {
+    let (line, col) = e.span.line_col(src);
+    std::io::Error::new(ErrorKind::InvalidData, format!("{path}:{line}:{col}: {e}"))
+}
@@ -0,0 +1,103 @@
+use std::io::ErrorKind;
+
+use imt::{bundle::Bundle, diff::frozen_changes, lock::Lockfile, target::TargetSpec};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    failure,
+    findings::Findings,
+    io::{Input, InputOptions},
+    usage_error,
+};
+
+pub(crate) fn freeze_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    // The lockfile comes first, so that it cannot be mistaken for an input
+    let lockfile = args.next_if(|arg| !arg.starts_with("--"));
+    let mut inputs = InputOptions::new("freeze");
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "freeze"),
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("freeze: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+    let lockfile = lockfile.ok_or_else(|| usage_error("freeze requires a lockfile"))?;
+
+    let bundle = inputs.load(prg_name, None)?;
+    Lockfile::freeze(&bundle).write(std::fs::File::create(lockfile)?)
+}
+
+pub(crate) fn check_frozen_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    // The lockfile comes first, so that it cannot be mistaken for an input
+    let lockfile = args.next_if(|arg| !arg.starts_with("--"));
+    let mut inputs = InputOptions::new("check-frozen");
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "check-frozen"),
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("check-frozen: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+    let lockfile = lockfile.ok_or_else(|| usage_error("check-frozen requires a lockfile"))?;
+
+    let bundle = inputs.load(prg_name, None)?;
+    let frozen = Lockfile::read(std::io::BufReader::new(std::fs::File::open(&lockfile)?))
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+    let mismatches = frozen.check(&bundle);
+    for mismatch in &mismatches {
+        println!("{mismatch}");
+    }
+    if !mismatches.is_empty() {
+        return Err(failure(
+            FailureClass::Validation,
+            format!(
+                "{lockfile}: {} item(s) differ from the lockfile",
+                mismatches.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Fails if a new change (see [`Findings`]) between `old` and `new` affects the layout (on `target`, or x86_64 by default) or signature of an item frozen in `old`
+pub(crate) fn check_frozen_changes(
+    old: &Bundle,
+    new: &Bundle,
+    target: Option<&TargetSpec>,
+    findings: &mut Findings,
+) -> std::io::Result<()> {
+    let target = target
+        .cloned()
+        .unwrap_or_else(|| TargetSpec::preset("x86_64").unwrap());
+    let mut changes = frozen_changes(old, new, &target);
+    changes.retain(|change| findings.is_new(change.fingerprint(), change));
+    for change in &changes {
+        println!("{change}");
+    }
+    if !changes.is_empty() && findings.deny_new() {
+        return Err(failure(
+            FailureClass::Validation,
+            format!("{} change(s) to frozen items", changes.len()),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::time::Instant;
+
+use imt::{
+    codegen::{CodegenBackend, plugin::PluginBackend},
+    metrics::Metrics,
+    target::TargetSpec,
+};
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    load_target,
+    plugin::dylib_generator,
+    usage_error, write_metrics,
+};
+
+pub(crate) fn gen_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("gen");
+    let mut out = None;
+    let mut plugin = None;
+    let mut plugin_args = Vec::new();
+    let mut plugin_dir = None;
+    let mut target = None;
+    let mut metrics_file = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "gen"),
+            "--plugin" => {
+                plugin = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--plugin requires an argument"))?,
+                );
+            }
+            "--plugin-dir" => {
+                plugin_dir = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--plugin-dir requires an argument"))?,
+                );
+            }
+            "--plugin-arg" => {
+                plugin_args.push(
+                    args.next()
+                        .ok_or_else(|| usage_error("--plugin-arg requires an argument"))?,
+                );
+            }
+            "--out" => {
+                out = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--out requires an argument"))?,
+                );
+            }
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--metrics" => {
+                metrics_file = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--metrics requires an argument"))?,
+                );
+            }
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("gen: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let program = plugin.ok_or_else(|| usage_error("gen requires --plugin <program>"))?;
+    let out = out.ok_or_else(|| usage_error("gen requires --out <dir>"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("gen requires at least one input"));
+    }
+
+    let mut metrics = Metrics::default();
+    let bundle = inputs.load_containers(metrics_file.as_ref().map(|_| &mut metrics))?;
+
+    let out = std::path::Path::new(&out);
+    let mut sink = |name: &str, contents: &[u8]| {
+        metrics.add_output(name, contents);
+        let path = out.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)
+    };
+    let target = target.unwrap_or_else(|| TargetSpec::preset("x86_64").unwrap());
+    let start = Instant::now();
+    match dylib_generator(plugin_dir.as_deref(), &program)? {
+        Some(plugin) => plugin.generate(&bundle, &target, &mut sink)?,
+        None => PluginBackend {
+            program,
+            args: plugin_args,
+        }
+        .generate(&bundle, &target, &mut sink)?,
+    }
+    metrics.add_phase("generate", start.elapsed());
+    write_metrics(metrics_file.as_deref(), metrics, &bundle)
+}
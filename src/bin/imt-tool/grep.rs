@@ -0,0 +1,132 @@
+use imt::grep::{MatchKind, grep};
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    usage_error,
+};
+
+pub(crate) fn grep_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("grep");
+    let mut ignore_case = false;
+    let mut context = 0;
+    let mut kinds = None;
+    let mut pattern = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "grep"),
+            "-i" | "--ignore-case" => ignore_case = true,
+            "-C" | "--context" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--context requires an argument"))?;
+                context = arg
+                    .parse()
+                    .map_err(|_| usage_error(format!("--context: {arg} is not a number")))?;
+            }
+            "--only" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--only requires an argument"))?;
+                let mut only = (false, false, false);
+                for kind in arg.split(',') {
+                    match kind {
+                        "names" => only.0 = true,
+                        "docs" => only.1 = true,
+                        "strings" => only.2 = true,
+                        _ => {
+                            return Err(usage_error(format!(
+                                "--only: unknown kind {kind} (expected names, docs, or strings)"
+                            )));
+                        }
+                    }
+                }
+                kinds = Some(only);
+            }
+            "--" => {
+                if pattern.is_none() {
+                    pattern = args.next();
+                }
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with('-') && x.len() > 1 => {
+                return Err(usage_error(format!("grep: unknown option {x}")));
+            }
+            _ if pattern.is_none() => pattern = Some(arg),
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    let pattern = pattern.ok_or_else(|| usage_error("grep requires a pattern"))?;
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("grep requires at least one input"));
+    }
+    let is_match = grep_matcher(&pattern, ignore_case)?;
+
+    let bundle = inputs.load_containers(None)?;
+
+    let mut matches = grep(&bundle, is_match);
+    if let Some((names, docs, strings)) = kinds {
+        matches.retain(|m| match m.kind {
+            MatchKind::Name(_) => names,
+            MatchKind::Doc => docs,
+            MatchKind::String => strings,
+        });
+    }
+
+    // Matches in the same text are printed together, so that overlapping context is only printed once
+    let mut printed_group = false;
+    let mut rest = &matches[..];
+    while let Some(first) = rest.first() {
+        let len = rest
+            .iter()
+            .take_while(|m| {
+                m.module == first.module && m.item == first.item && m.kind == first.kind
+            })
+            .count();
+        let (group, next) = rest.split_at(len);
+        rest = next;
+
+        let path = first.path();
+        let kind = first.kind.as_str();
+        let mut end = None;
+        for m in group {
+            let start = m.line.saturating_sub(context).max(end.unwrap_or(0));
+            // Like grep, lines that aren't adjacent are separated when context is printed
+            if context != 0 && printed_group && end != Some(start) {
+                println!("--");
+            }
+            printed_group = true;
+            let group_end = (m.line + context + 1).min(m.lines.len());
+            end = Some(group_end);
+            for (n, line) in m.lines.iter().enumerate().take(group_end).skip(start) {
+                let sep = if group.iter().any(|m| m.line == n) {
+                    ':'
+                } else {
+                    '-'
+                };
+                println!("{path}{sep} {kind}{sep} {line}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "grep")]
+fn grep_matcher(pattern: &str, ignore_case: bool) -> std::io::Result<impl Fn(&str) -> bool> {
+    let regex = regex_lite::RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| usage_error(format!("{pattern}: {e}")))?;
+    Ok(move |line: &str| regex.is_match(line))
+}
+
+#[cfg(not(feature = "grep"))]
+fn grep_matcher(_: &str, _: bool) -> std::io::Result<fn(&str) -> bool> {
+    Err(std::io::Error::other(
+        "grep requires building with the grep feature",
+    ))
+}
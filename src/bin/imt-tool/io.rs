@@ -0,0 +1,609 @@
+use std::{
+    io::ErrorKind,
+    process::{Command, Stdio},
+    time::Instant,
+};
+
+use bincode::error::DecodeError;
+#[cfg(feature = "fetch")]
+use imt::fetch::{FetchError, Manifest};
+#[cfg(feature = "text")]
+use imt::text::compile::compile_dir;
+use imt::{
+    attr::UnknownAttributePolicy,
+    audit::AttributeAudit,
+    bundle::{Bundle, Format, Path},
+    metrics::Metrics,
+};
+
+#[cfg(feature = "fetch")]
+use crate::{FailureClass, failure};
+use crate::{
+    cli::{Args, CliOption, command},
+    create_bundle, usage_error,
+};
+
+/// The options of a command that name its inputs and how they are decoded. Each command accepts those of its options in [`CLI`](crate::cli::CLI)
+pub(crate) struct InputOptions {
+    /// The options of the command, which are the only ones parsed
+    accepted: &'static [CliOption],
+    pub(crate) inputs: Vec<Input>,
+    /// The format of each input, if given by `--from`
+    pub(crate) from: Option<Container>,
+    pub(crate) is_bundle: bool,
+    unzip_prg: Option<String>,
+    prefix: Option<String>,
+    pub(crate) manifest: Option<String>,
+    pub(crate) unknown_attrs: UnknownAttributePolicy,
+    cache: Option<String>,
+}
+
+impl InputOptions {
+    /// The input options of the command `name`, none of which have been given yet
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            accepted: command(name).options,
+            inputs: Vec::new(),
+            from: None,
+            is_bundle: false,
+            unzip_prg: None,
+            prefix: None,
+            manifest: None,
+            unknown_attrs: UnknownAttributePolicy::Error,
+            cache: None,
+        }
+    }
+
+    /// Parses `opt`, taking its argument from `args`, if it is one of the input options that the command accepts. Returns whether it was
+    pub(crate) fn parse_option(&mut self, opt: &str, args: &mut Args) -> std::io::Result<bool> {
+        if !self.accepted.iter().any(|accepted| accepted.name == opt) {
+            return Ok(false);
+        }
+        match opt {
+            "--from" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--from requires an argument"))?;
+                self.from = Some(Container::parse(&arg)?);
+            }
+            "--bundle" => {
+                self.is_bundle = true;
+            }
+            "--prefix" => {
+                self.prefix = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--prefix requires an argument"))?,
+                );
+            }
+            "--input" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--input requires an argument"))?;
+                self.inputs.push(Input::parse(&arg));
+            }
+            "--unzip" => {
+                self.unzip_prg = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--unzip requires an argument"))?,
+                );
+            }
+            "--manifest" => {
+                self.manifest = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--manifest requires an argument"))?,
+                );
+            }
+            "--unknown-attrs" => {
+                let policy = args
+                    .next()
+                    .ok_or_else(|| usage_error("--unknown-attrs requires an argument"))?;
+                self.unknown_attrs = match &*policy {
+                    "error" => UnknownAttributePolicy::Error,
+                    "warn" => UnknownAttributePolicy::WarnAndPreserve,
+                    "drop" => UnknownAttributePolicy::Drop,
+                    _ => {
+                        return Err(usage_error(format!(
+                            "--unknown-attrs: unknown policy {policy}"
+                        )));
+                    }
+                };
+            }
+            "--cache" => {
+                self.cache = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--cache requires an argument"))?,
+                );
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// The module that the root of each input corresponds to, unless given by `--input`
+    pub(crate) fn prefix(&self) -> Path {
+        self.prefix.as_deref().map_or(Path(vec![]), parse_prefix)
+    }
+
+    /// Loads the older revision `old` of the inputs, which is an IMT file or an archive
+    pub(crate) fn load_revision(&self, old: &str) -> std::io::Result<Bundle> {
+        load_revision(
+            old,
+            &self.prefix(),
+            self.unknown_attrs,
+            self.cache.as_deref(),
+        )
+    }
+
+    /// Decodes the inputs (or standard input, if there are none) into a bundle.
+    /// The time taken to decode each input, and all of them, is recorded in `metrics`, if given.
+    pub(crate) fn load(
+        &self,
+        prg_name: &str,
+        metrics: Option<&mut Metrics>,
+    ) -> std::io::Result<Bundle> {
+        #[cfg(not(feature = "tar"))]
+        if self.is_bundle {
+            return Err(std::io::Error::other(
+                "--bundle requires building with the tar feature",
+            ));
+        }
+
+        let mut bundle = create_bundle(self.unknown_attrs, self.cache.as_deref())?;
+        if metrics.is_some() {
+            bundle.record_decode_times();
+        }
+
+        let stdin = [Input::from("-".to_string())];
+        let inputs = if self.inputs.is_empty() {
+            &stdin[..]
+        } else {
+            &self.inputs[..]
+        };
+        let prefix = self.prefix();
+
+        let start = Instant::now();
+        for input in inputs {
+            let bytes = match &self.unzip_prg {
+                Some(unzip_prg) => {
+                    let file = if input.path == "-" {
+                        Stdio::inherit()
+                    } else {
+                        Stdio::from(std::fs::File::open(&input.path)?)
+                    };
+                    let mut child = Command::new(unzip_prg)
+                        .arg("-d")
+                        .stdin(file)
+                        .stdout(Stdio::piped())
+                        .spawn()?;
+                    let mut bytes = Vec::new();
+                    std::io::Read::read_to_end(&mut child.stdout.take().unwrap(), &mut bytes)?;
+                    let status = child.wait()?;
+                    if !status.success() {
+                        return Err(std::io::Error::other(format!(
+                            "{}: {unzip_prg} exited with status: {status}",
+                            input.path
+                        )));
+                    }
+                    bytes
+                }
+                None => read_input(&input.path, self.manifest.as_deref())?,
+            };
+
+            let prefix = input.prefix(&prefix).clone();
+            let decoded = if Format::sniff(&bytes) == Some(Format::Zip) {
+                bundle.parse_sniffed(prefix, &bytes).map(|_| ())
+            } else if self.is_bundle || Format::sniff(&bytes) == Some(Format::Tar) {
+                #[cfg(feature = "tar")]
+                {
+                    bundle.parse_tar(prefix, &*bytes)
+                }
+                #[cfg(not(feature = "tar"))]
+                {
+                    return Err(std::io::Error::other(format!(
+                        "{}: reading TAR archives requires building with the tar feature",
+                        input.path
+                    )));
+                }
+            } else if input.path == "-" {
+                // Standard input has no name, so its file is the module `prefix` itself
+                bundle.parse_file(prefix, &*bytes)
+            } else {
+                let name = std::path::Path::new(&input.path)
+                    .file_name()
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            ErrorKind::IsADirectory,
+                            "input files must be files, not directories",
+                        )
+                    })?
+                    .to_str()
+                    .unwrap();
+                bundle.parse_file(prefix.join(module_name(name)), &*bytes)
+            };
+            decoded.map_err(|e| match e {
+                DecodeError::Io { inner, .. } => inner,
+                e => std::io::Error::new(ErrorKind::InvalidData, e),
+            })?;
+        }
+        if let Some(metrics) = metrics {
+            metrics.add_phase("decode", start.elapsed());
+        }
+
+        if self.unknown_attrs == UnknownAttributePolicy::WarnAndPreserve {
+            for usage in AttributeAudit::of(&bundle).unignorable() {
+                let files = usage
+                    .files
+                    .iter()
+                    .map(Path::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                eprintln!(
+                    "{prg_name}: warning: {} instance(s) of non-ignorable attribute {} are not recognized (in {files})",
+                    usage.unignorable, usage.id
+                );
+            }
+        }
+
+        Ok(bundle)
+    }
+
+    /// Adds each input, in the format of `--from`, to a new bundle like [`load_container`].
+    /// The time taken to decode each input, and all of them, is recorded in `metrics`, if given.
+    pub(crate) fn load_containers(&self, metrics: Option<&mut Metrics>) -> std::io::Result<Bundle> {
+        let mut bundle = create_bundle(self.unknown_attrs, self.cache.as_deref())?;
+        if metrics.is_some() {
+            bundle.record_decode_times();
+        }
+        let prefix = self.prefix();
+
+        let start = Instant::now();
+        for input in &self.inputs {
+            load_container(
+                &mut bundle,
+                &input.path,
+                self.from,
+                input.prefix(&prefix),
+                self.manifest.as_deref(),
+            )?;
+        }
+        if let Some(metrics) = metrics {
+            metrics.add_phase("decode", start.elapsed());
+        }
+        Ok(bundle)
+    }
+}
+
+/// A representation of a bundle that `convert` reads or writes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Container {
+    /// A single IMT file
+    Imt,
+    /// A TAR archive, possibly compressed
+    Tar,
+    /// A ZIP archive of IMT files
+    Zip,
+    /// A directory tree of IMT files
+    Dir,
+    /// The JSON form of the bundle (see [`ToJson`]), which is only written
+    Json,
+    /// A directory tree of textual sources, which are compiled when they are read
+    Src,
+}
+
+impl Container {
+    pub(crate) fn parse(name: &str) -> std::io::Result<Self> {
+        match name {
+            "imt" => Ok(Self::Imt),
+            "tar" => Ok(Self::Tar),
+            "zip" => Ok(Self::Zip),
+            "dir" => Ok(Self::Dir),
+            "json" => Ok(Self::Json),
+            "src" => Ok(Self::Src),
+            _ => Err(usage_error(format!(
+                "unknown format {name} (expected imt, tar, zip, dir, json, or src)"
+            ))),
+        }
+    }
+
+    /// Guesses the format of `path` from its extension, or from whether it is a directory
+    pub(crate) fn detect(path: &str) -> Option<Self> {
+        if std::path::Path::new(path).is_dir() {
+            return Some(Self::Dir);
+        }
+        let name = std::path::Path::new(path).file_name()?.to_str()?;
+        if name.ends_with(".tgz") || name.ends_with(".tar") || name.contains(".tar.") {
+            Some(Self::Tar)
+        } else if name.ends_with(".imt") || compressor(name).is_some() {
+            Some(Self::Imt)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".json") {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// The program (following the gzip CLI) that compresses files with the extension of `name`, if it is compressed
+fn compressor(name: &str) -> Option<&'static str> {
+    let (_, ext) = name.rsplit_once('.')?;
+    match ext {
+        "gz" | "tgz" => Some("gzip"),
+        "xz" => Some("xz"),
+        "lzma" => Some("lzma"),
+        "bz2" => Some("bzip2"),
+        "zst" => Some("zstd"),
+        _ => None,
+    }
+}
+
+/// The module named by the IMT file `name`, which is `name` without the extension of a compressed format (if any), and then without `.imt`
+fn module_name(name: &str) -> &str {
+    let name = match compressor(name) {
+        Some(_) => name.rsplit_once('.').map_or(name, |(name, _)| name),
+        None => name,
+    };
+    name.strip_suffix(".imt").unwrap_or(name)
+}
+
+/// Reads the file at `path` (or standard input, if `path` is `-`, or downloads it, if `path` is a URL),
+/// decompressing it for as long as its magic number names a compressed format.
+/// Downloads are verified against the manifest file `manifest`, if any.
+pub(crate) fn read_input(path: &str, manifest: Option<&str>) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    if path == "-" {
+        std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut bytes)?;
+    } else if path.starts_with("https://") || path.starts_with("http://") {
+        bytes = fetch_input(path, manifest)?;
+    } else {
+        std::io::Read::read_to_end(&mut std::fs::File::open(path)?, &mut bytes)?;
+    }
+    while let Some(prg) = Format::sniff(&bytes).and_then(Format::decompressor) {
+        bytes = decompress(path, prg, &bytes)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(feature = "fetch")]
+pub(crate) fn fetch_input(url: &str, manifest: Option<&str>) -> std::io::Result<Vec<u8>> {
+    let manifest = match manifest {
+        Some(manifest) => {
+            let text = String::from_utf8(read_input(manifest, None)?)
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+            Some(Manifest::parse(&text).map_err(|e| {
+                std::io::Error::new(ErrorKind::InvalidData, format!("{manifest}: {e}"))
+            })?)
+        }
+        None => None,
+    };
+    imt::fetch::fetch(url, manifest.as_ref()).map_err(|e| match e {
+        FetchError::MissingChecksum(_) | FetchError::ChecksumMismatch { .. } => {
+            failure(FailureClass::Validation, e.to_string())
+        }
+        e => std::io::Error::other(e),
+    })
+}
+
+#[cfg(not(feature = "fetch"))]
+pub(crate) fn fetch_input(url: &str, _: Option<&str>) -> std::io::Result<Vec<u8>> {
+    Err(std::io::Error::other(format!(
+        "{url}: downloading inputs requires building with the fetch feature"
+    )))
+}
+
+/// Decompresses `bytes`, read from `path`, with `prg` (following the gzip CLI)
+fn decompress(path: &str, prg: &str, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut child = Command::new(prg)
+        .arg("-d")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    // The input is written from another thread, so that neither pipe can fill up while the other is waited on
+    let out = std::thread::scope(|scope| {
+        scope.spawn(move || std::io::Write::write_all(&mut stdin, bytes));
+        child.wait_with_output()
+    })?;
+    if !out.status.success() {
+        return Err(std::io::Error::other(format!(
+            "{path}: {prg} exited with status: {}",
+            out.status
+        )));
+    }
+    Ok(out.stdout)
+}
+
+/// Writes `content` to the file at `path` (or standard output, if `path` is `-`), compressing it if its extension names a compressed format
+pub(crate) fn write_output(path: &str, content: &[u8]) -> std::io::Result<()> {
+    if path == "-" {
+        return std::io::Write::write_all(&mut std::io::stdout().lock(), content);
+    }
+    let file = std::fs::File::create(path)?;
+    let Some(prg) = compressor(path) else {
+        return std::io::Write::write_all(&mut { file }, content);
+    };
+    let mut child = Command::new(prg)
+        .arg("-c")
+        .stdin(Stdio::piped())
+        .stdout(file)
+        .spawn()?;
+    let res = std::io::Write::write_all(&mut child.stdin.take().unwrap(), content);
+    let status = child.wait()?;
+    res?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "{path}: {prg} exited with status: {status}"
+        )));
+    }
+    Ok(())
+}
+
+/// The module path `prefix`, written like `base::io`
+fn parse_prefix(prefix: &str) -> Path {
+    if prefix.is_empty() {
+        return Path(vec![]);
+    }
+    Path(prefix.split("::").map(str::to_string).collect())
+}
+
+/// An input of a command
+pub(crate) struct Input {
+    /// The path of the input, or `-` for standard input
+    path: String,
+    /// The module that the root of the input corresponds to, if given by `--input <path>=<prefix>`
+    prefix: Option<Path>,
+}
+
+impl Input {
+    /// Parses the argument of `--input`, which is `<path>` or `<path>=<prefix>`. The prefix follows the last `=`.
+    pub(crate) fn parse(arg: &str) -> Self {
+        match arg.rsplit_once('=') {
+            Some((path, prefix)) => Self {
+                path: path.to_string(),
+                prefix: Some(parse_prefix(prefix)),
+            },
+            None => Self::from(arg.to_string()),
+        }
+    }
+
+    /// The module that the root of the input corresponds to, which is `default` unless given by `--input`
+    pub(crate) fn prefix<'a>(&'a self, default: &'a Path) -> &'a Path {
+        self.prefix.as_ref().unwrap_or(default)
+    }
+}
+
+impl From<String> for Input {
+    fn from(path: String) -> Self {
+        Self { path, prefix: None }
+    }
+}
+
+/// Adds the bundle `input` in format `from` to `bundle`, under `prefix`. Downloaded inputs are verified against the manifest file `manifest`, if any.
+/// Without `from`, the format is detected from the magic number of `input`, or else guessed from its name.
+pub(crate) fn load_container(
+    bundle: &mut Bundle,
+    input: &str,
+    from: Option<Container>,
+    prefix: &Path,
+    manifest: Option<&str>,
+) -> std::io::Result<()> {
+    let is_dir = input != "-" && std::path::Path::new(input).is_dir();
+    let from = match from {
+        Some(from) => Some(from),
+        None if is_dir => Some(Container::Dir),
+        None => None,
+    };
+    let decoded = match from {
+        Some(Container::Dir) if input == "-" => {
+            return Err(usage_error(
+                "a directory cannot be read from standard input",
+            ));
+        }
+        Some(Container::Dir) => bundle.parse_dir(prefix.clone(), std::path::Path::new(input)),
+        Some(Container::Src) if !is_dir => {
+            return Err(usage_error(format!(
+                "{input}: textual sources are read from a directory"
+            )));
+        }
+        #[cfg(feature = "text")]
+        Some(Container::Src) => {
+            return compile_dir(bundle, prefix.clone(), std::path::Path::new(input))
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e));
+        }
+        #[cfg(not(feature = "text"))]
+        Some(Container::Src) => {
+            return Err(std::io::Error::other(
+                "compiling textual sources requires building with the text feature",
+            ));
+        }
+        Some(Container::Json) => {
+            return Err(usage_error(format!(
+                "{input}: bundles are only written as JSON, not read from it"
+            )));
+        }
+        Some(Container::Imt | Container::Tar | Container::Zip) | None => {
+            let bytes = read_input(input, manifest)?;
+            let from = from
+                .or_else(|| match Format::sniff(&bytes)? {
+                    Format::Tar => Some(Container::Tar),
+                    Format::Zip => Some(Container::Zip),
+                    _ => Some(Container::Imt),
+                })
+                .or_else(|| Container::detect(input))
+                .ok_or_else(|| {
+                    usage_error(format!("{input}: cannot determine the format, use --from"))
+                })?;
+            match from {
+                // Standard input has no name, so its file is the module `prefix` itself
+                Container::Imt if input == "-" => bundle.parse_file(prefix.clone(), &*bytes),
+                Container::Imt => {
+                    let name = std::path::Path::new(input)
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map(module_name)
+                        .ok_or_else(|| usage_error(format!("{input}: not a file name")))?;
+                    bundle.parse_file(prefix.join(name), &*bytes)
+                }
+                #[cfg(feature = "tar")]
+                Container::Tar => bundle.parse_tar(prefix.clone(), &*bytes),
+                #[cfg(not(feature = "tar"))]
+                Container::Tar => {
+                    return Err(std::io::Error::other(
+                        "reading TAR archives requires building with the tar feature",
+                    ));
+                }
+                #[cfg(feature = "zip")]
+                Container::Zip => bundle.parse_zip(prefix.clone(), std::io::Cursor::new(&*bytes)),
+                #[cfg(not(feature = "zip"))]
+                Container::Zip => {
+                    return Err(std::io::Error::other(
+                        "reading ZIP archives requires building with the zip feature",
+                    ));
+                }
+                Container::Json => {
+                    return Err(usage_error(format!(
+                        "{input}: bundles are only written as JSON, not read from it"
+                    )));
+                }
+                Container::Dir | Container::Src => {
+                    unreachable!("directories are detected before reading")
+                }
+            }
+        }
+    };
+    decoded.map_err(|e| match e {
+        DecodeError::Io { inner, .. } => inner,
+        e => std::io::Error::new(ErrorKind::InvalidData, e),
+    })
+}
+
+/// Loads the file or archive `input` given on the command line, detecting its format from its contents like
+/// [`Bundle::parse_sniffed`]. The members of an archive are modules under `prefix`, and any other file is the module named after it
+pub(crate) fn load_revision(
+    input: &str,
+    prefix: &Path,
+    unknown_attrs: UnknownAttributePolicy,
+    cache: Option<&str>,
+) -> std::io::Result<Bundle> {
+    let mut bundle = create_bundle(unknown_attrs, cache)?;
+    let bytes = read_input(input, None)?;
+    let prefix = match Format::sniff(&bytes) {
+        Some(Format::Tar | Format::Zip) => prefix.clone(),
+        // Standard input has no name, so its file is the module `prefix` itself
+        _ if input == "-" => prefix.clone(),
+        _ => {
+            let name = std::path::Path::new(input)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| usage_error(format!("{input}: not a file name")))?;
+            prefix.join(module_name(name))
+        }
+    };
+    bundle.parse_sniffed(prefix, &bytes).map_err(|e| match e {
+        DecodeError::Io { inner, .. } => inner,
+        e => std::io::Error::new(ErrorKind::InvalidData, e),
+    })?;
+    Ok(bundle)
+}
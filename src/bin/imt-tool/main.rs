@@ -0,0 +1,272 @@
+use std::{io::ErrorKind, process::ExitCode};
+
+use bincode::error::DecodeError;
+use imt::{
+    attr::UnknownAttributePolicy,
+    bundle::Bundle,
+    json::{ToJson, string as json_string},
+    metrics::Metrics,
+    strip::StripOptions,
+    target::{TargetSpec, TargetSpecError},
+};
+
+use crate::{
+    cli::{Args, CLI, command, print_help},
+    dump::dump_main,
+    io::write_output,
+};
+
+mod attrs;
+mod audit;
+#[cfg(feature = "project")]
+mod build;
+mod check;
+mod cli;
+mod completions;
+mod convert;
+mod diff;
+mod doc;
+mod dump;
+mod explain;
+mod findings;
+#[cfg(feature = "text")]
+mod fmt;
+mod frozen;
+mod generate;
+mod grep;
+mod io;
+mod padding;
+mod plugin;
+mod provenance;
+#[cfg(feature = "registry")]
+mod registry;
+mod resolve;
+mod size;
+mod validate;
+#[cfg(feature = "watch")]
+mod watch;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args();
+    let prg_name = args.next().unwrap();
+    let mut args = args.collect::<Vec<_>>();
+    let (error_format, res) = match take_error_format(&mut args) {
+        Ok(error_format) => {
+            let mut args = args.into_iter().peekable();
+            let cmd = args
+                .next_if(|arg| {
+                    CLI.iter()
+                        .any(|cmd| !cmd.name.is_empty() && cmd.name == arg)
+                })
+                .map_or(&CLI[0], |name| command(&name));
+            let res = (cmd.main)(&prg_name, args);
+            (error_format, res)
+        }
+        Err(e) => (ErrorFormat::Human, Err(e)),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let class = FailureClass::of(&e);
+            match error_format {
+                ErrorFormat::Human => eprintln!("{prg_name}: {e}"),
+                ErrorFormat::Json => eprintln!(
+                    "{{\"class\":{},\"exit_code\":{},\"message\":{}}}",
+                    json_string(class.as_str()),
+                    class as u8,
+                    json_string(&e.to_string())
+                ),
+            }
+            ExitCode::from(class as u8)
+        }
+    }
+}
+
+/// The class of failure of imt-tool, which determines its exit status
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum FailureClass {
+    /// Any failure without a more specific class, such as failing to read an input
+    Other = 1,
+    /// The command line is invalid
+    Usage = 2,
+    /// An input could not be decoded
+    Decode = 3,
+    /// The input failed a check, such as validate, audit-attrs, check-frozen, --manifest, or fmt --check, or changed a frozen item
+    Validation = 4,
+    /// --deny-breaking found a breaking change
+    BreakingChange = 5,
+}
+
+impl FailureClass {
+    const ALL: [Self; 5] = [
+        Self::Other,
+        Self::Usage,
+        Self::Decode,
+        Self::Validation,
+        Self::BreakingChange,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Usage => "usage",
+            Self::Decode => "decode",
+            Self::Validation => "validation",
+            Self::BreakingChange => "breaking-change",
+        }
+    }
+
+    /// The description of the exit status printed by `--help`
+    fn about(self) -> &'static str {
+        match self {
+            Self::Other => "Any other failure, such as failing to read an input",
+            Self::Usage => "The command line is invalid",
+            Self::Decode => "An input could not be decoded",
+            Self::Validation => {
+                "The input failed validate, audit-attrs, or check-frozen, bump or diff found a change to a frozen item, a downloaded input failed --manifest, or fmt --check found a source that is not formatted"
+            }
+            Self::BreakingChange => "--deny-breaking found a breaking change",
+        }
+    }
+
+    /// The class of `e`, which is that of the [`Failure`] it contains, or is derived from the error it wraps and its kind.
+    /// Only errors made by [`usage_error`] are usage errors
+    fn of(e: &std::io::Error) -> Self {
+        let inner = e.get_ref();
+        if let Some(failure) = inner.and_then(|e| e.downcast_ref::<Failure>()) {
+            return failure.class;
+        }
+        if inner.is_some_and(|e| e.is::<DecodeError>()) {
+            return Self::Decode;
+        }
+        match e.kind() {
+            ErrorKind::InvalidData | ErrorKind::UnexpectedEof => Self::Decode,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// An error of imt-tool with an explicit [`FailureClass`]
+#[derive(Debug)]
+struct Failure {
+    class: FailureClass,
+    msg: String,
+}
+
+impl core::fmt::Display for Failure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn failure(class: FailureClass, msg: impl Into<String>) -> std::io::Error {
+    std::io::Error::other(Failure {
+        class,
+        msg: msg.into(),
+    })
+}
+
+/// An error in the command line, such as an unknown option or a missing argument
+fn usage_error(msg: impl Into<String>) -> std::io::Error {
+    failure(FailureClass::Usage, msg)
+}
+
+/// How imt-tool prints the error it fails with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    /// A JSON object with the class of the error, its exit status, and its message
+    Json,
+}
+
+/// Removes each `--error-format <format>`, which is accepted by every command, from the options in `args`
+fn take_error_format(args: &mut Vec<String>) -> std::io::Result<ErrorFormat> {
+    let mut error_format = ErrorFormat::Human;
+    loop {
+        let end = args
+            .iter()
+            .position(|arg| arg == "--")
+            .unwrap_or(args.len());
+        let Some(pos) = args[..end].iter().position(|arg| arg == "--error-format") else {
+            return Ok(error_format);
+        };
+        if pos + 1 == end {
+            return Err(usage_error("--error-format requires an argument"));
+        }
+        error_format = match &*args[pos + 1] {
+            "human" => ErrorFormat::Human,
+            "json" => ErrorFormat::Json,
+            format => {
+                return Err(usage_error(format!(
+                    "--error-format: unknown format {format} (expected human or json)"
+                )));
+            }
+        };
+        args.drain(pos..pos + 2);
+    }
+}
+
+fn real_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    match args.peek().map(String::as_str) {
+        Some("--version") => {
+            println!("imt-tool v{}", core::env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        Some("--help") => print_help(prg_name, ""),
+        _ => dump_main(prg_name, args),
+    }
+}
+
+/// Parses the comma separated list of content `content` given to `option`
+fn parse_strip_content(option: &str, content: &str) -> std::io::Result<StripOptions> {
+    StripOptions::parse(content)
+        .ok_or_else(|| usage_error(format!("{option}: unknown content in {content}")))
+}
+
+/// Writes `metrics`, with the decode times and counts of `bundle`, to `file` for --metrics, if given
+fn write_metrics(file: Option<&str>, mut metrics: Metrics, bundle: &Bundle) -> std::io::Result<()> {
+    let Some(file) = file else {
+        return Ok(());
+    };
+    metrics.add_bundle(bundle);
+    write_output(file, metrics.to_json().as_bytes())
+}
+
+fn create_bundle(
+    unknown_attrs: UnknownAttributePolicy,
+    cache: Option<&str>,
+) -> std::io::Result<Bundle> {
+    let mut bundle = Bundle::create();
+    bundle.set_unknown_attribute_policy(unknown_attrs);
+    if let Some(dir) = cache {
+        #[cfg(not(feature = "cache"))]
+        {
+            let _ = dir;
+            return Err(std::io::Error::other(
+                "--cache requires building with the cache feature",
+            ));
+        }
+        #[cfg(feature = "cache")]
+        bundle.set_cache(imt::cache::FileCache::open(dir)?);
+    }
+    #[cfg(feature = "crypto")]
+    if let Some(dir) = std::env::var_os("IMT_KEY_DIR") {
+        bundle.set_key_lookup(imt::crypto::KeyDir::new(dir));
+    }
+    Ok(bundle)
+}
+
+/// Loads the target given on the command line, either from a spec file or a target triple
+fn load_target(arg: &str) -> std::io::Result<TargetSpec> {
+    let res = match std::fs::File::open(arg) {
+        Ok(file) => TargetSpec::read(std::io::BufReader::new(file)),
+        Err(e) if e.kind() == ErrorKind::NotFound => TargetSpec::from_triple(arg),
+        Err(e) => return Err(e),
+    };
+    res.map_err(|e| match e {
+        TargetSpecError::Io(e) => e,
+        e => usage_error(format!("--target: {e}")),
+    })
+}
@@ -0,0 +1,81 @@
+use imt::{padding::PaddingReport, target::TargetSpec};
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    load_target, usage_error,
+};
+
+pub(crate) fn padding_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("padding");
+    let mut target = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "padding"),
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("padding: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+
+    let bundle = inputs.load(prg_name, None)?;
+    let target = target.unwrap_or_else(|| TargetSpec::preset("x86_64").unwrap());
+    print_padding(&PaddingReport::of(&bundle, &target));
+    Ok(())
+}
+
+fn print_padding(report: &PaddingReport) {
+    for st in report.wasteful() {
+        println!(
+            "{}: size {}, align {}, {} padding byte(s)",
+            st.path,
+            st.layout.size,
+            st.layout.align,
+            st.padding()
+        );
+        for gap in &st.gaps {
+            match &gap.before {
+                Some(field) => println!(
+                    "\t{} byte(s) at offset {}, before {field}",
+                    gap.size, gap.offset
+                ),
+                None => println!(
+                    "\t{} byte(s) at offset {}, at the end",
+                    gap.size, gap.offset
+                ),
+            }
+        }
+        for field in &st.misaligned {
+            println!(
+                "\t{} is at offset {}, which is not a multiple of its size {}",
+                field.name, field.offset, field.size
+            );
+        }
+        if let Some(reordering) = &st.reordering {
+            let fields = reordering
+                .fields
+                .iter()
+                .map(|field| field.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "\tsuggestion: ordering the fields as {fields} would make it {} byte(s)",
+                reordering.size
+            );
+        }
+    }
+}
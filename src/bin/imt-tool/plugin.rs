@@ -0,0 +1,71 @@
+#[cfg(feature = "dylib")]
+use imt::dylib::{DylibError, DylibPlugin};
+use imt::{bundle::Bundle, codegen::CodegenBackend, diagnostics::Diagnostic, target::TargetSpec};
+
+/// Loads the dynamic library plugins in `dir`, or in `IMT_PLUGIN_DIR` if `dir` is not given
+#[cfg(feature = "dylib")]
+fn load_plugins(dir: Option<&str>) -> std::io::Result<Vec<DylibPlugin>> {
+    let Some(dir) = dir
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("IMT_PLUGIN_DIR").map(std::path::PathBuf::from))
+    else {
+        return Ok(Vec::new());
+    };
+    // SAFETY: the plugins in the plugin directory are trusted by the user who put them there
+    unsafe { imt::dylib::discover(&dir) }.map_err(|e| match e {
+        DylibError::Io(e) => std::io::Error::new(e.kind(), format!("{}: {e}", dir.display())),
+        e => std::io::Error::other(e),
+    })
+}
+
+/// The generator plugin named `name` in the plugin directory `dir`, if there is one
+#[cfg(feature = "dylib")]
+pub(crate) fn dylib_generator(
+    dir: Option<&str>,
+    name: &str,
+) -> std::io::Result<Option<DylibPlugin>> {
+    Ok(load_plugins(dir)?
+        .into_iter()
+        .find(|plugin| plugin.is_generator() && plugin.name() == name))
+}
+
+#[cfg(not(feature = "dylib"))]
+pub(crate) fn dylib_generator(
+    dir: Option<&str>,
+    _: &str,
+) -> std::io::Result<Option<Box<dyn CodegenBackend>>> {
+    match dir {
+        Some(_) => Err(std::io::Error::other(
+            "--plugin-dir requires building with the dylib feature",
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Runs the lint plugins in the plugin directory `dir` on `bundle`
+#[cfg(feature = "dylib")]
+pub(crate) fn lint_plugins(
+    dir: Option<&str>,
+    bundle: &Bundle,
+    target: &TargetSpec,
+) -> std::io::Result<Vec<Diagnostic>> {
+    let mut diags = Vec::new();
+    for plugin in load_plugins(dir)? {
+        diags.extend(plugin.lint(bundle, target)?);
+    }
+    Ok(diags)
+}
+
+#[cfg(not(feature = "dylib"))]
+pub(crate) fn lint_plugins(
+    dir: Option<&str>,
+    _: &Bundle,
+    _: &TargetSpec,
+) -> std::io::Result<Vec<Diagnostic>> {
+    match dir {
+        Some(_) => Err(std::io::Error::other(
+            "--plugin-dir requires building with the dylib feature",
+        )),
+        None => Ok(Vec::new()),
+    }
+}
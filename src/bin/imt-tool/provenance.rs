@@ -0,0 +1,32 @@
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    usage_error,
+};
+
+pub(crate) fn provenance_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("provenance");
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "provenance"),
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("provenance: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+
+    let bundle = inputs.load(prg_name, None)?;
+    match bundle.provenance() {
+        Some(provenance) => println!("{provenance}"),
+        None => println!("no provenance recorded"),
+    }
+    Ok(())
+}
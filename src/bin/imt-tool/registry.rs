@@ -0,0 +1,209 @@
+use std::io::ErrorKind;
+
+use bincode::error::DecodeError;
+use imt::{
+    bundle::Path,
+    header::Version,
+    registry::{Index, IndexEntry, IndexError, sha256, sha256_hex},
+};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    create_bundle, failure,
+    io::{InputOptions, fetch_input, read_input},
+    usage_error,
+};
+
+pub(crate) fn publish_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut index_path = None;
+    let mut name = None;
+    let mut version = None;
+    let mut location = None;
+    let mut inputs = InputOptions::new("publish");
+    let mut bundles = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "publish"),
+            "--index" => {
+                index_path = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--index requires an argument"))?,
+                );
+            }
+            "--name" => {
+                name = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--name requires an argument"))?,
+                );
+            }
+            "--version" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--version requires an argument"))?;
+                version = Some(Version::try_parse(&arg).ok_or_else(|| {
+                    usage_error(format!(
+                        "--version: {arg} is not of the form <major>.<minor>"
+                    ))
+                })?);
+            }
+            "--location" => {
+                location = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--location requires an argument"))?,
+                );
+            }
+            "--" => bundles.extend(args.by_ref()),
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("publish: unknown option {x}")));
+            }
+            _ => bundles.push(arg),
+        }
+    }
+
+    let [bundle_path] = &bundles[..] else {
+        return Err(usage_error("publish requires exactly one bundle"));
+    };
+    let index_path = index_path.ok_or_else(|| usage_error("publish requires --index <index>"))?;
+    let version = version.ok_or_else(|| usage_error("publish requires --version <version>"))?;
+    let file_name = std::path::Path::new(bundle_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| usage_error(format!("{bundle_path}: not a file name")))?;
+    let name = name.unwrap_or_else(|| file_name.split('.').next().unwrap().to_string());
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(usage_error(format!(
+            "--name: {name:?} is not a valid bundle name"
+        )));
+    }
+    let location = location.unwrap_or_else(|| file_name.to_string());
+
+    // The bundle is hashed as it is downloaded, but checked after decompressing it
+    let bytes = std::fs::read(bundle_path)?;
+    let mut bundle = create_bundle(inputs.unknown_attrs, None)?;
+    bundle
+        .parse_sniffed(Path(vec![]), &read_input(bundle_path, None)?)
+        .map_err(|e| match e {
+            DecodeError::Io { inner, .. } => inner,
+            e => std::io::Error::new(ErrorKind::InvalidData, format!("{bundle_path}: {e}")),
+        })?;
+
+    let mut index = match std::fs::File::open(&index_path) {
+        Ok(file) => Index::read(std::io::BufReader::new(file)).map_err(|e| match e {
+            IndexError::Io(e) => e,
+            e => std::io::Error::new(ErrorKind::InvalidData, format!("{index_path}: {e}")),
+        })?,
+        Err(e) if e.kind() == ErrorKind::NotFound => Index::default(),
+        Err(e) => return Err(e),
+    };
+    index.publish(IndexEntry {
+        name,
+        version,
+        sha256: sha256(&bytes),
+        location,
+    });
+    index.write(std::io::BufWriter::new(std::fs::File::create(&index_path)?))
+}
+
+pub(crate) fn fetch_main(
+    prg_name: &str,
+    mut args: impl Iterator<Item = String>,
+) -> std::io::Result<()> {
+    let mut index_path = None;
+    let mut out = None;
+    let mut bundles = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "fetch"),
+            "--index" => {
+                index_path = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--index requires an argument"))?,
+                );
+            }
+            "--out" => {
+                out = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--out requires an argument"))?,
+                );
+            }
+            "--" => bundles.extend(args.by_ref()),
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("fetch: unknown option {x}")));
+            }
+            _ => bundles.push(arg),
+        }
+    }
+
+    let [bundle] = &bundles[..] else {
+        return Err(usage_error("fetch requires exactly one bundle"));
+    };
+    let index_path = index_path.ok_or_else(|| usage_error("fetch requires --index <index>"))?;
+    let (name, version) = match bundle.split_once('@') {
+        Some((name, version)) => (
+            name,
+            Some(Version::try_parse(version).ok_or_else(|| {
+                usage_error(format!(
+                    "{bundle}: {version} is not of the form <major>.<minor>"
+                ))
+            })?),
+        ),
+        None => (&**bundle, None),
+    };
+
+    let index = Index::read(&*read_input(&index_path, None)?).map_err(|e| match e {
+        IndexError::Io(e) => e,
+        e => std::io::Error::new(ErrorKind::InvalidData, format!("{index_path}: {e}")),
+    })?;
+    let entry = index.find(name, version).ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::NotFound,
+            match version {
+                Some(version) => {
+                    format!("{index_path}: no version of {name} compatible with {version}")
+                }
+                None => format!("{index_path}: no version of {name}"),
+            },
+        )
+    })?;
+
+    let location = entry.resolve_location(&index_path);
+    let bytes = if location.starts_with("https://") || location.starts_with("http://") {
+        fetch_input(&location, None)?
+    } else {
+        std::fs::read(&location)?
+    };
+    if !entry.matches(&bytes) {
+        return Err(failure(
+            FailureClass::Validation,
+            format!(
+                "{location}: checksum mismatch (expected sha256 {}, got {})",
+                sha256_hex(&entry.sha256),
+                sha256_hex(&sha256(&bytes))
+            ),
+        ));
+    }
+
+    let out = match out {
+        Some(out) => out,
+        None => location
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(name)
+            .to_string(),
+    };
+    if out == "-" {
+        std::io::Write::write_all(&mut std::io::stdout().lock(), &bytes)
+    } else {
+        std::fs::write(&out, &bytes)?;
+        eprintln!(
+            "{prg_name}: fetched {} {} to {out}",
+            entry.name, entry.version
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::ErrorKind;
+
+use imt::{
+    bundle::{Bundle, Path},
+    resolve::{Item, expand_type, resolve_export},
+    tydef::{StructBody, TypeDefBody},
+    value::ValueBody,
+};
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    usage_error,
+};
+
+pub(crate) fn resolve_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    // The item comes first, so that it cannot be mistaken for an input
+    let path = args.next_if(|arg| !arg.starts_with("--"));
+    let mut inputs = InputOptions::new("resolve");
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "resolve"),
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("resolve: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+    let path = path.ok_or_else(|| usage_error("resolve requires an item"))?;
+
+    let bundle = inputs.load(prg_name, None)?;
+    print_resolved(&bundle, &path)
+}
+
+fn print_resolved(bundle: &Bundle, path: &str) -> std::io::Result<()> {
+    let path = Path(path.split("::").map(str::to_string).collect());
+    let resolved =
+        resolve_export(bundle, &path).map_err(|e| std::io::Error::new(ErrorKind::NotFound, e))?;
+    let module = resolved.path.parent().unwrap_or_else(|| Path(vec![]));
+    let expand = |ty| {
+        expand_type(bundle, &module, ty).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))
+    };
+
+    println!("{path}: defined at {}", resolved.path);
+    println!("via:");
+    for step in &resolved.chain {
+        println!("\t{step}");
+    }
+
+    match resolved.item {
+        Item::Type(def) => match &def.body {
+            TypeDefBody::Alias(alias) => println!("type alias: {}", expand(&alias.alias)?),
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => {
+                    println!("struct:");
+                    for field in &fields.field {
+                        println!("\t{}: {}", field.name, expand(&field.ty)?);
+                    }
+                }
+                StructBody::Opaque(Some(repr)) => {
+                    println!("opaque struct: {}", expand(repr)?)
+                }
+                StructBody::Opaque(None) => println!("opaque struct"),
+            },
+            TypeDefBody::Union(un) => {
+                println!("union:");
+                for field in &un.fields.field {
+                    println!("\t{}: {}", field.name, expand(&field.ty)?);
+                }
+            }
+            TypeDefBody::Enum(en) => println!("enum: {}", en.underlying),
+            body => println!("{body:#?}"),
+        },
+        Item::Value(val) => match &val.body {
+            ValueBody::Const(c) => println!("const: {} = {}", expand(&c.ty)?, c.val),
+            ValueBody::Function(f) => {
+                println!("function:");
+                for param in &f.signature.params {
+                    println!(
+                        "\t{}: {}",
+                        param.name.as_deref().unwrap_or("_"),
+                        expand(&param.ty)?
+                    );
+                }
+                println!("\treturns: {}", expand(&f.signature.retty)?);
+            }
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::io::ErrorKind;
+
+use imt::size::SizeReport;
+
+use crate::{
+    cli::{Args, print_help},
+    io::{Input, InputOptions},
+    usage_error,
+};
+
+pub(crate) fn size_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("size");
+    let mut top = usize::MAX;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "size"),
+            "--top" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--top requires an argument"))?;
+                top = arg
+                    .parse()
+                    .map_err(|_| usage_error(format!("--top: {arg} is not a number")))?;
+            }
+            "--" => {
+                inputs.inputs.extend(args.by_ref().map(Input::from));
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("size: unknown option {x}")));
+            }
+            _ => inputs.inputs.push(Input::from(arg)),
+        }
+    }
+
+    if inputs.inputs.is_empty() {
+        return Err(usage_error("size requires at least one input"));
+    }
+
+    let bundle = inputs.load_containers(None)?;
+
+    let report =
+        SizeReport::of(&bundle).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+    if let Some(provenance) = bundle.provenance() {
+        println!("provenance: {provenance}");
+    }
+    println!(
+        "total: {} bytes in {} file(s)",
+        report.total(),
+        report.members.len()
+    );
+    for member in report.largest_members(top) {
+        println!("{}: {} bytes", member.path, member.bytes);
+        let items = member.largest_items(top);
+        if !items.is_empty() {
+            println!("\titems:");
+            for item in items {
+                println!("\t\t{:>8} {}", item.bytes, item.name);
+            }
+        }
+        let attrs = member.largest_attributes(top);
+        if !attrs.is_empty() {
+            println!("\tattributes:");
+            for attr in attrs {
+                let name = attr.info.map_or("<unknown>", |info| info.name);
+                println!(
+                    "\t\t{:>8} {name} {} ({} instance(s))",
+                    attr.bytes, attr.id, attr.count
+                );
+            }
+        }
+    }
+    Ok(())
+}
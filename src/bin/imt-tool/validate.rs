@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use imt::{
+    diagnostics::{Severity, sarif::sarif_log},
+    metrics::Metrics,
+    target::TargetSpec,
+};
+
+use crate::{
+    FailureClass,
+    cli::{Args, print_help},
+    failure,
+    findings::Findings,
+    io::{Input, InputOptions},
+    load_target,
+    plugin::lint_plugins,
+    usage_error, write_metrics,
+};
+
+pub(crate) fn validate_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    let mut inputs = InputOptions::new("validate");
+    let mut target = None;
+    let mut plugin_dir = None;
+    let mut sarif = false;
+    let mut findings = Findings::default();
+    let mut metrics_file = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "validate"),
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--plugin-dir" => {
+                plugin_dir = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--plugin-dir requires an argument"))?,
+                );
+            }
+            "--diagnostic-format" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--diagnostic-format requires an argument"))?;
+                sarif = match &*arg {
+                    "human" => false,
+                    "sarif" => true,
+                    _ => {
+                        return Err(usage_error(format!(
+                            "--diagnostic-format: unknown format {arg} (expected human or sarif)"
+                        )));
+                    }
+                };
+            }
+            "--metrics" => {
+                metrics_file = Some(
+                    args.next()
+                        .ok_or_else(|| usage_error("--metrics requires an argument"))?,
+                );
+            }
+            "--" => break,
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if findings.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("validate: unknown option {x}")));
+            }
+            _ => {
+                inputs.inputs.push(Input::from(arg));
+                break;
+            }
+        }
+    }
+    inputs.inputs.extend(args.map(Input::from));
+
+    let mut metrics = Metrics::default();
+    let bundle = inputs.load(prg_name, metrics_file.as_ref().map(|_| &mut metrics))?;
+
+    let start = Instant::now();
+    let mut diags = bundle.validate();
+    if let Some(target) = &target {
+        diags.extend(bundle.validate_target(target));
+    }
+    let lint_target = target.unwrap_or_else(|| TargetSpec::preset("x86_64").unwrap());
+    diags.extend(lint_plugins(plugin_dir.as_deref(), &bundle, &lint_target)?);
+    metrics.add_phase("validate", start.elapsed());
+    metrics.add_count("diagnostics", diags.len() as u64);
+    let total = diags.len();
+    diags.retain(|diag| findings.is_new(diag.fingerprint(), diag));
+    if diags.len() != total {
+        eprintln!(
+            "{} problem(s) accepted by the baseline were not printed",
+            total - diags.len()
+        );
+    }
+    if sarif {
+        println!(
+            "{}",
+            sarif_log("imt-tool", core::env!("CARGO_PKG_VERSION"), &diags)
+        );
+    } else {
+        for diag in &diags {
+            print!("{}", diag.render(&bundle));
+        }
+    }
+    findings.write()?;
+    write_metrics(metrics_file.as_deref(), metrics, &bundle)?;
+
+    let errors = diags
+        .iter()
+        .filter(|diag| diag.severity == Severity::Error)
+        .count();
+    if errors != 0 && findings.deny_new() {
+        return Err(failure(
+            FailureClass::Validation,
+            format!("validation found {errors} error(s)"),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use imt::{
+    bundle::{Bundle, Path},
+    diagnostics::{Diagnostic, Severity},
+    target::TargetSpec,
+};
+
+use crate::{
+    cli::{Args, print_help},
+    io::InputOptions,
+    load_target, usage_error,
+};
+
+pub(crate) fn watch_main(prg_name: &str, mut args: Args) -> std::io::Result<()> {
+    use std::{collections::BTreeSet, time::Duration};
+
+    use imt::text::compile::{SOURCE_EXTENSION, module_path, source_members};
+    use notify::{
+        EventKind, RecursiveMode, Watcher,
+        event::{AccessKind, AccessMode, ModifyKind},
+    };
+
+    let mut inputs = InputOptions::new("watch");
+    let mut target = None;
+    let mut dir = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--help" => return print_help(prg_name, "watch"),
+            "--target" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| usage_error("--target requires an argument"))?;
+                target = Some(load_target(&arg)?);
+            }
+            "--" => {
+                dir = args.next();
+                break;
+            }
+            x if inputs.parse_option(x, &mut args)? => {}
+            x if x.starts_with("--") => {
+                return Err(usage_error(format!("watch: unknown option {x}")));
+            }
+            _ => {
+                dir = Some(arg);
+                break;
+            }
+        }
+    }
+    if let Some(arg) = args.next() {
+        return Err(usage_error(format!("watch: unexpected argument {arg}")));
+    }
+    let dir = dir.ok_or_else(|| usage_error("watch requires a directory"))?;
+    let root = std::path::Path::new(&dir);
+    let prefix = inputs.prefix();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(std::io::Error::other)?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(std::io::Error::other)?;
+
+    let mut bundle = Bundle::create();
+    let mut reported = Vec::new();
+    for (path, fs_path) in source_members(prefix.clone(), root)? {
+        recompile(&mut bundle, &prefix, root, path, &fs_path);
+    }
+    report_changes(&bundle, target.as_ref(), &mut reported);
+    eprintln!("{prg_name}: watching {dir} for changes");
+
+    // Reading a source is an event too, so only events that can change its contents are acted on
+    let changes =
+        |event: notify::Result<notify::Event>| -> std::io::Result<Vec<std::path::PathBuf>> {
+            let event = event.map_err(std::io::Error::other)?;
+            Ok(match event.kind {
+                EventKind::Access(AccessKind::Close(AccessMode::Write))
+                | EventKind::Create(_)
+                | EventKind::Remove(_) => event.paths,
+                EventKind::Modify(ModifyKind::Metadata(_)) => Vec::new(),
+                EventKind::Modify(_) => event.paths,
+                _ => Vec::new(),
+            })
+        };
+
+    // The watcher only stops sending events when it is dropped, which it is not until this returns
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        changed.extend(changes(event)?);
+        // Editors often save a file as several events, so the events that follow closely are handled together
+        while let Ok(event) = rx.recv_timeout(Duration::from_millis(100)) {
+            changed.extend(changes(event)?);
+        }
+        changed.retain(|fs_path| {
+            fs_path.extension().and_then(|ext| ext.to_str()) == Some(SOURCE_EXTENSION)
+        });
+        if changed.is_empty() {
+            continue;
+        }
+
+        for fs_path in &changed {
+            let Some(path) = module_path(&prefix, root, fs_path) else {
+                continue;
+            };
+            if fs_path.is_file() {
+                recompile(&mut bundle, &prefix, root, path, fs_path);
+            } else if bundle.remove_file(&path).is_some() {
+                println!("removed {path}");
+            }
+        }
+        report_changes(&bundle, target.as_ref(), &mut reported);
+    }
+    Ok(())
+}
+
+/// Compiles the source `fs_path` of the directory tree `root` into the module `path` of `bundle`, printing the error if it fails.
+/// The file that the source was compiled to before is kept if it fails, so that the problems it causes elsewhere are not reported
+fn recompile(
+    bundle: &mut Bundle,
+    prefix: &Path,
+    root: &std::path::Path,
+    path: Path,
+    fs_path: &std::path::Path,
+) {
+    match imt::text::compile::compile_file(prefix, root, fs_path) {
+        Ok(file) => bundle.add_file(path, file),
+        Err(imt::text::compile::CompileError::Parse {
+            path: fs_path,
+            line,
+            col,
+            error,
+        }) => {
+            println!("error: {error}");
+            println!("  --> {}:{line}:{col}", fs_path.display());
+        }
+        Err(e) => println!("error: {e}"),
+    }
+}
+
+/// Validates `bundle`, printing the problems whose fingerprints are not in `reported`, and the number of those in `reported` that
+/// were not found. `reported` is updated to the fingerprints of the problems found
+fn report_changes(bundle: &Bundle, target: Option<&TargetSpec>, reported: &mut Vec<u64>) {
+    let mut diags = bundle.validate();
+    if let Some(target) = target {
+        diags.extend(bundle.validate_target(target));
+    }
+    let found = diags
+        .iter()
+        .map(Diagnostic::fingerprint)
+        .collect::<Vec<_>>();
+
+    for (diag, fingerprint) in diags.iter().zip(&found) {
+        if !reported.contains(fingerprint) {
+            print!("{}", diag.render(bundle));
+        }
+    }
+    let fixed = reported
+        .iter()
+        .filter(|fingerprint| !found.contains(fingerprint))
+        .count();
+    let errors = diags
+        .iter()
+        .filter(|diag| diag.severity == Severity::Error)
+        .count();
+    println!(
+        "{} module(s): {errors} error(s), {} warning(s), {fixed} fixed",
+        bundle.iter().len(),
+        diags.len() - errors
+    );
+    *reported = found;
+}
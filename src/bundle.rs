@@ -1,14 +1,25 @@
-#[cfg(feature = "tar")]
-use std::io::{Seek, Write};
+#[cfg(any(feature = "tar", feature = "zip"))]
+use std::io::Seek;
 use std::{
-    io::{ErrorKind, Read},
+    io::{ErrorKind, Read, Write},
     iter::FusedIterator,
 };
 
 use bincode::error::{DecodeError, EncodeError};
 use indexmap::IndexMap;
 
-use crate::{config::format_config, file::File};
+use crate::{
+    attr::UnknownAttributePolicy,
+    config::format_config,
+    diagnostics::Diagnostic,
+    file::File,
+    intern::{Interner, Symbol, with_interner},
+    metrics::DecodeTime,
+    provenance::{PROVENANCE_MEMBER, Provenance},
+    resolve::{ResolveError, ResolvedItem},
+    strip::StripOptions,
+    target::TargetSpec,
+};
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Path(pub Vec<String>);
@@ -21,7 +32,21 @@ impl Path {
 
         let l = other.0.len();
 
-        &self.0[..l] == &other.0
+        self.0[..l] == other.0
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    pub fn parent(&self) -> Option<Path> {
+        self.0.split_last().map(|(_, parent)| Path(parent.to_vec()))
+    }
+
+    pub fn join(&self, name: &str) -> Path {
+        let mut path = self.0.clone();
+        path.push(name.to_string());
+        Path(path)
     }
 }
 
@@ -38,9 +63,134 @@ impl core::fmt::Display for Path {
     }
 }
 
+/// A format of the contents of a file that holds IMT files, as detected from its leading bytes by [`Format::sniff`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A single IMT file, which starts with [`MAGIC`](crate::header::MAGIC)
+    Imt,
+    /// A TAR archive, with the `ustar` magic of POSIX and GNU archives
+    Tar,
+    /// A ZIP archive, which starts with the signature of a local file header, or of the end of an empty archive
+    Zip,
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+    /// The legacy LZMA format of LZMA Utils, detected by the default properties byte of its header
+    Lzma,
+    /// An IMT file encrypted with the `crypto` feature, which starts with `b"\xFEIMTAE"`
+    Encrypted,
+}
+
+impl Format {
+    /// Detects the format of `bytes` from its magic number, if it has a recognized one
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&crate::header::MAGIC) {
+            Some(Self::Imt)
+        } else if bytes.starts_with(b"\xFEIMTAE") {
+            Some(Self::Encrypted)
+        } else if bytes.starts_with(b"\x1F\x8B") {
+            Some(Self::Gzip)
+        } else if bytes.starts_with(b"\xFD7zXZ\0") {
+            Some(Self::Xz)
+        } else if bytes.starts_with(b"\x28\xB5\x2F\xFD") {
+            Some(Self::Zstd)
+        } else if bytes.starts_with(b"BZh") {
+            Some(Self::Bzip2)
+        } else if bytes.starts_with(b"\x5D\0\0") {
+            Some(Self::Lzma)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the format is a compressed stream of another format
+    pub fn is_compressed(self) -> bool {
+        matches!(
+            self,
+            Self::Gzip | Self::Xz | Self::Zstd | Self::Bzip2 | Self::Lzma
+        )
+    }
+
+    /// The program (following the gzip CLI) that decompresses the format, if it is compressed
+    pub fn decompressor(self) -> Option<&'static str> {
+        match self {
+            Self::Gzip => Some("gzip"),
+            Self::Xz => Some("xz"),
+            Self::Zstd => Some("zstd"),
+            Self::Bzip2 => Some("bzip2"),
+            Self::Lzma => Some("lzma"),
+            Self::Imt | Self::Tar | Self::Zip | Self::Encrypted => None,
+        }
+    }
+}
+
+/// The most bytes that a gzip-compressed input is decompressed to. Larger inputs are rejected, so that a small input that decompresses
+/// to far more data cannot exhaust memory
+#[cfg(feature = "compress")]
+pub const MAX_GUNZIP_SIZE: usize = 1 << 30;
+
+/// Decompresses the first member of the gzip stream `bytes`, which must decompress to at most [`MAX_GUNZIP_SIZE`] bytes
+#[cfg(feature = "compress")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    let truncated = || DecodeError::Other("truncated gzip stream");
+    let header = bytes.get(..10).ok_or_else(truncated)?;
+    // Deflate is the only compression method gzip defines
+    if header[2] != 8 {
+        return Err(DecodeError::Other("unknown gzip compression method"));
+    }
+    let flags = header[3];
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        let len = bytes.get(pos..pos + 2).ok_or_else(truncated)?;
+        pos += 2 + usize::from(u16::from_le_bytes([len[0], len[1]]));
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = bytes
+                .get(pos..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0))
+                .ok_or_else(truncated)?;
+            pos += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+    let stream = bytes.get(pos..).ok_or_else(truncated)?;
+    miniz_oxide::inflate::decompress_to_vec_with_limit(stream, MAX_GUNZIP_SIZE).map_err(|e| {
+        if e.status == miniz_oxide::inflate::TINFLStatus::HasMoreOutput {
+            DecodeError::OtherString(format!(
+                "gzip stream decompresses to more than {MAX_GUNZIP_SIZE} bytes"
+            ))
+        } else {
+            DecodeError::OtherString(format!("invalid gzip stream: {e}"))
+        }
+    })
+}
+
 #[derive(Clone)]
 pub struct Bundle {
     files: IndexMap<Path, File>,
+    unknown_attributes: UnknownAttributePolicy,
+    interner: Interner,
+    provenance: Option<Provenance>,
+    decode_times: Option<Vec<DecodeTime>>,
+    #[cfg(feature = "cache")]
+    cache: Option<crate::cache::FileCache>,
+    #[cfg(feature = "crypto")]
+    keys: Option<std::sync::Arc<dyn crate::crypto::KeyLookup + Send + Sync>>,
+    #[cfg(feature = "crypto")]
+    encryption: Option<(String, crate::crypto::Key)>,
 }
 
 impl core::fmt::Debug for Bundle {
@@ -53,6 +203,16 @@ impl Bundle {
     pub fn create() -> Self {
         Self {
             files: IndexMap::new(),
+            unknown_attributes: UnknownAttributePolicy::Error,
+            interner: Interner::new(),
+            provenance: None,
+            decode_times: None,
+            #[cfg(feature = "cache")]
+            cache: None,
+            #[cfg(feature = "crypto")]
+            keys: None,
+            #[cfg(feature = "crypto")]
+            encryption: None,
         }
     }
 
@@ -60,14 +220,149 @@ impl Bundle {
         self.files.insert(path, file);
     }
 
-    pub fn parse_file<R: Read>(&mut self, path: Path, mut file: R) -> Result<(), DecodeError> {
-        let file = bincode::decode_from_std_read(&mut file, format_config())?;
+    /// The interner that names and doc lines of files parsed into the bundle share storage through
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
 
-        self.add_file(path, file);
+    /// Interns `s` with the bundle's interner, for use in items added to the bundle
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        self.interner.intern(s)
+    }
+
+    /// The record of how the bundle was built, if it was read from a bundle with one or set by [`Bundle::set_provenance`]
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Sets the record of how the bundle was built, which is written alongside its files by [`Bundle::write_tar`] and [`Bundle::write_dir`]
+    pub fn set_provenance(&mut self, provenance: Provenance) {
+        self.provenance = Some(provenance);
+    }
+
+    /// Sets how files parsed into the bundle handle attributes that are not recognized and not ignorable
+    pub fn set_unknown_attribute_policy(&mut self, policy: UnknownAttributePolicy) {
+        self.unknown_attributes = policy;
+    }
+
+    /// Records the time taken to parse each file parsed into the bundle from now on, including decrypting it and looking it up in the cache
+    pub fn record_decode_times(&mut self) {
+        self.decode_times.get_or_insert_with(Vec::new);
+    }
+
+    /// The time taken to parse each file since [`Bundle::record_decode_times`] was called, or `None` if it was not called
+    pub fn decode_times(&self) -> Option<&[DecodeTime]> {
+        self.decode_times.as_deref()
+    }
+
+    /// Sets the cache that files parsed into the bundle are looked up in before being decoded, and stored in once decoded
+    #[cfg(feature = "cache")]
+    pub fn set_cache(&mut self, cache: crate::cache::FileCache) {
+        self.cache = Some(cache);
+    }
+
+    /// Sets the hook that finds the keys of encrypted files parsed into the bundle
+    #[cfg(feature = "crypto")]
+    pub fn set_key_lookup(&mut self, keys: impl crate::crypto::KeyLookup + Send + Sync + 'static) {
+        self.keys = Some(std::sync::Arc::new(keys));
+    }
 
+    /// Encrypts each file that [holds pre-release interfaces](crate::validate::has_experimental_items) with `key`
+    /// when writing the bundle, so that embargoed interfaces can be distributed alongside the rest of the bundle.
+    /// Readers need a [`KeyLookup`](crate::crypto::KeyLookup) that finds `key` from `key_id` to parse those files.
+    #[cfg(feature = "crypto")]
+    pub fn set_member_encryption(
+        &mut self,
+        key_id: &str,
+        key: crate::crypto::Key,
+    ) -> Result<(), crate::crypto::CryptoError> {
+        crate::crypto::check_key_id(key_id)?;
+        self.encryption = Some((key_id.to_string(), key));
         Ok(())
     }
 
+    pub fn parse_file<R: Read>(&mut self, path: Path, file: R) -> Result<(), DecodeError> {
+        let start = std::time::Instant::now();
+        let file = self.read_file(file)?;
+        if let Some(times) = &mut self.decode_times {
+            times.push(DecodeTime {
+                path: path.clone(),
+                time: start.elapsed(),
+            });
+        }
+        self.add_file(path, file);
+        Ok(())
+    }
+
+    /// Decrypts and decodes `file`, or looks it up in the cache
+    fn read_file<R: Read>(&mut self, file: R) -> Result<File, DecodeError> {
+        #[cfg(feature = "crypto")]
+        let (file, encrypted) = self.decrypt_member(file)?;
+        #[cfg(not(feature = "crypto"))]
+        let encrypted = false;
+
+        // Decrypted files are not cached, since the cache would store them unencrypted
+        #[cfg(feature = "cache")]
+        if let Some(cache) = self.cache.clone().filter(|_| !encrypted) {
+            let mut file = file;
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)
+                .map_err(|e| DecodeError::Io {
+                    inner: e,
+                    additional: 0,
+                })?;
+
+            let policy = self.unknown_attributes;
+            let file = match with_interner(&mut self.interner, || cache.get(&contents, policy)) {
+                Some(file) => file,
+                None => {
+                    let file = self.decode_file(&*contents)?;
+                    // A cache that cannot be written to only costs the time saved by it
+                    let _ = cache.insert(&contents, policy, &file);
+                    file
+                }
+            };
+            return Ok(file);
+        }
+
+        #[cfg(not(feature = "cache"))]
+        let _ = encrypted;
+
+        self.decode_file(file)
+    }
+
+    /// Reads `file`, decrypting it if it is an encrypted member, and returns its contents and whether it was encrypted
+    #[cfg(feature = "crypto")]
+    fn decrypt_member<R: Read>(
+        &self,
+        mut file: R,
+    ) -> Result<(std::io::Cursor<Vec<u8>>, bool), DecodeError> {
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .map_err(|e| DecodeError::Io {
+                inner: e,
+                additional: 0,
+            })?;
+        if !crate::crypto::is_encrypted(&contents) {
+            return Ok((std::io::Cursor::new(contents), false));
+        }
+
+        let res = match &self.keys {
+            Some(keys) => crate::crypto::decrypt(&contents, &**keys),
+            None => crate::crypto::key_id(&contents)
+                .and_then(|id| Err(crate::crypto::CryptoError::MissingKey(id.to_string()))),
+        };
+        let contents = res.map_err(|e| DecodeError::OtherString(e.to_string()))?;
+        Ok((std::io::Cursor::new(contents), true))
+    }
+
+    fn decode_file<R: Read>(&mut self, mut file: R) -> Result<File, DecodeError> {
+        let policy = self.unknown_attributes;
+        with_interner(&mut self.interner, || {
+            bincode::decode_from_std_read_with_context(&mut file, format_config(), policy)
+        })
+    }
+
     pub fn add_files<I: IntoIterator<Item = (Path, File)>>(&mut self, files: I) {
         for (path, file) in files {
             self.add_file(path, file);
@@ -116,6 +411,19 @@ impl Bundle {
         for (path, file) in &self.files {
             let (check, without_prefix) = path.0.split_at(prefix.0.len());
             assert_eq!(check, &prefix.0);
+            #[cfg(feature = "crypto")]
+            if let Some((key_id, key)) = &self.encryption
+                && crate::validate::has_experimental_items(path, file)
+            {
+                supplier(without_prefix, &mut |w| {
+                    let bytes = bincode::encode_to_vec(file, format_config())
+                        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e))?;
+                    let member = crate::crypto::encrypt(key_id, key, &bytes)
+                        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e))?;
+                    w.write_all(&member)
+                })?;
+                continue;
+            }
             supplier(without_prefix, &mut |mut w| {
                 bincode::encode_into_std_write(file, &mut w, format_config())
                     .map_err(|e| match e {
@@ -128,59 +436,162 @@ impl Bundle {
         Ok(())
     }
 
-    #[cfg(feature = "tar")]
-    pub fn parse_tar<R: Read>(&mut self, prefix: Path, tar: R) -> Result<(), DecodeError> {
-        use tar::Archive;
-
-        let mut archive = Archive::new(tar);
-
-        self.parse_files(
-            archive
-                .entries()
-                .map_err(|e| DecodeError::Io {
+    /// Parses each `.imt` file in the directory tree `dir` as the module at its path relative to `dir`, under `prefix`.
+    /// The [`Provenance`] of the bundle is read from the file [`PROVENANCE_MEMBER`] of `dir`, if there is one.
+    pub fn parse_dir(&mut self, prefix: Path, dir: &std::path::Path) -> Result<(), DecodeError> {
+        match std::fs::File::open(dir.join(PROVENANCE_MEMBER)) {
+            Ok(file) => {
+                self.provenance = Some(
+                    Provenance::read(std::io::BufReader::new(file)).map_err(|e| {
+                        DecodeError::OtherString(format!("{PROVENANCE_MEMBER}: {e}"))
+                    })?,
+                );
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(DecodeError::Io {
                     inner: e,
                     additional: 0,
-                })?
-                .filter_map(|e| {
-                    let entry = match e {
-                        Ok(e) => e,
-                        Err(e) => return Some(Err(e)),
-                    };
-
-                    let name = match entry.path() {
-                        Ok(name) => name,
-                        Err(e) => {
-                            return Some(Err(e));
-                        }
-                    };
+                });
+            }
+        }
+
+        let mut members = Vec::new();
+        dir_members(prefix, dir, "imt", &mut members).map_err(|e| DecodeError::Io {
+            inner: e,
+            additional: 0,
+        })?;
 
-                    let path = name.as_os_str().to_str()?;
+        self.parse_files(members.into_iter().map(|(path, fs_path)| {
+            Ok((path, std::io::BufReader::new(std::fs::File::open(fs_path)?)))
+        }))
+    }
 
-                    let path = path.strip_suffix(".imt")?;
+    /// Writes each file of the bundle to the directory tree `dir`, in the layout read by [`Bundle::parse_dir`].
+    /// Directories are created as needed.
+    ///
+    /// # Panics
+    /// Panics if any file of the bundle is not within `prefix`
+    pub fn write_dir(&self, prefix: &Path, dir: &std::path::Path) -> std::io::Result<()> {
+        if let Some(provenance) = &self.provenance {
+            std::fs::create_dir_all(dir)?;
+            provenance.write(std::fs::File::create(dir.join(PROVENANCE_MEMBER))?)?;
+        }
+        self.write_files(prefix, |path, writer_cb| {
+            if path.is_empty() {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "the module at the prefix itself cannot be written to a directory",
+                ));
+            }
+            let mut fs_path = path.iter().collect::<std::path::PathBuf>();
+            fs_path.add_extension("imt");
+            let fs_path = dir.join(fs_path);
+            if let Some(parent) = fs_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let mut w = std::io::BufWriter::new(std::fs::File::create(fs_path)?);
+            writer_cb(&mut w)?;
+            w.flush()
+        })
+    }
 
-                    let mut gpath = prefix.0.clone();
+    /// Parses `bytes` according to the format detected by [`Format::sniff`], decompressing it first if it is compressed.
+    /// An IMT file is parsed as the module `prefix`, and the members of a TAR or ZIP archive as modules under `prefix`.
+    /// Contents without a recognized magic number are parsed as an IMT file.
+    ///
+    /// Only gzip streams can be decompressed, and only with the `compress` feature, so other compressed formats must be decompressed
+    /// (such as with [`Format::decompressor`]) before parsing them.
+    pub fn parse_sniffed(&mut self, prefix: Path, bytes: &[u8]) -> Result<Format, DecodeError> {
+        match Format::sniff(bytes) {
+            #[cfg(feature = "compress")]
+            Some(Format::Gzip) => {
+                let bytes = gunzip(bytes)?;
+                self.parse_sniffed(prefix, &bytes)?;
+                Ok(Format::Gzip)
+            }
+            #[cfg(not(feature = "crypto"))]
+            Some(Format::Encrypted) => Err(DecodeError::Other(
+                "reading encrypted files requires building with the crypto feature",
+            )),
+            Some(format) if format.is_compressed() => Err(DecodeError::OtherString(format!(
+                "cannot decompress {format:?} streams, which must be decompressed first"
+            ))),
+            #[cfg(feature = "tar")]
+            Some(Format::Tar) => {
+                self.parse_tar(prefix, bytes)?;
+                Ok(Format::Tar)
+            }
+            #[cfg(not(feature = "tar"))]
+            Some(Format::Tar) => Err(DecodeError::Other(
+                "reading TAR archives requires building with the tar feature",
+            )),
+            #[cfg(feature = "zip")]
+            Some(Format::Zip) => {
+                self.parse_zip(prefix, std::io::Cursor::new(bytes))?;
+                Ok(Format::Zip)
+            }
+            #[cfg(not(feature = "zip"))]
+            Some(Format::Zip) => Err(DecodeError::Other(
+                "reading ZIP archives requires building with the zip feature",
+            )),
+            _ => {
+                self.parse_file(prefix, bytes)?;
+                Ok(Format::Imt)
+            }
+        }
+    }
 
-                    gpath.extend(
-                        path.split(std::path::MAIN_SEPARATOR)
-                            .map(String::from)
-                            .collect::<Vec<_>>(),
-                    );
+    /// Parses each `.imt` member of the TAR archive `tar` as a module under `prefix`,
+    /// and the [`Provenance`] of the bundle from the member [`PROVENANCE_MEMBER`], if there is one
+    #[cfg(feature = "tar")]
+    pub fn parse_tar<R: Read>(&mut self, prefix: Path, tar: R) -> Result<(), DecodeError> {
+        let mut archive = tar::Archive::new(tar);
+        let mut provenance = None;
 
-                    Some(Ok((Path(gpath), entry)))
-                }),
-        )
+        self.parse_files(tar_members(&prefix, &mut archive, Some(&mut provenance))?)?;
+        if provenance.is_some() {
+            self.provenance = provenance;
+        }
+        Ok(())
+    }
+
+    /// Reads the [`FileMetadata`](crate::file::FileMetadata) of each member of a TAR archive containing a bundle, without decoding the rest of each file.
+    ///
+    /// Unrecognized attributes are handled according to `policy`.
+    #[cfg(feature = "tar")]
+    pub fn scan_metadata<R: Read>(
+        prefix: Path,
+        tar: R,
+        policy: UnknownAttributePolicy,
+    ) -> Result<Vec<(Path, crate::file::FileMetadata)>, DecodeError> {
+        let mut archive = tar::Archive::new(tar);
+
+        tar_members(&prefix, &mut archive, None)?
+            .map(|member| {
+                let (path, entry) = member.map_err(|e| DecodeError::Io {
+                    inner: e,
+                    additional: 0,
+                })?;
+                Ok((path, crate::file::FileMetadata::read(entry, policy)?))
+            })
+            .collect()
     }
 
     #[cfg(feature = "tar")]
     pub fn write_tar<W: Write + Seek>(&mut self, prefix: &Path, tar: W) -> std::io::Result<()> {
-        use tar::Builder;
+        use tar::{Builder, Header};
 
         let mut archive = Builder::new(tar);
 
-        self.write_files(&prefix, |path, writer_cb| {
-            use std::path::PathBuf;
+        if let Some(provenance) = &self.provenance {
+            let mut header = Header::new_gnu();
+            provenance.write(archive.append_writer(&mut header, PROVENANCE_MEMBER)?)?;
+        }
 
-            use tar::Header;
+        self.write_files(prefix, |path, writer_cb| {
+            use std::path::PathBuf;
 
             let mut path = path.iter().collect::<PathBuf>();
             path.add_extension("imt");
@@ -191,10 +602,116 @@ impl Bundle {
         })
     }
 
+    /// Parses each `.imt` member of the ZIP archive `zip` as a module under `prefix`, like [`Bundle::parse_tar`].
+    ///
+    /// Members can be stored uncompressed or compressed with deflate. Members compressed with other methods are rejected.
+    #[cfg(feature = "zip")]
+    pub fn parse_zip<R: Read + Seek>(&mut self, prefix: Path, zip: R) -> Result<(), DecodeError> {
+        let io = |e: std::io::Error| DecodeError::Io {
+            inner: e,
+            additional: 0,
+        };
+        let mut archive = zip::ZipArchive::new(zip).map_err(|e| io(e.into()))?;
+
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i).map_err(|e| io(e.into()))?;
+            let name = entry.name().to_string();
+            if name == PROVENANCE_MEMBER {
+                let record = Provenance::read(std::io::BufReader::new(entry)).map_err(|e| {
+                    io(std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{PROVENANCE_MEMBER}: {e}"),
+                    ))
+                })?;
+                self.provenance = Some(record);
+                continue;
+            }
+            // Members are always named with `/`, whatever the platform that wrote the archive
+            let Some(path) = name.strip_suffix(".imt") else {
+                continue;
+            };
+            let mut gpath = prefix.0.clone();
+            gpath.extend(path.split('/').map(String::from));
+
+            self.parse_file(Path(gpath), entry)?;
+        }
+        Ok(())
+    }
+
+    /// Writes each file of the bundle to the ZIP archive `zip`, in the layout read by [`Bundle::parse_zip`]. Members are stored uncompressed.
+    ///
+    /// # Panics
+    /// Panics if any file of the bundle is not within `prefix`
+    #[cfg(feature = "zip")]
+    pub fn write_zip<W: Write + Seek>(&self, prefix: &Path, zip: W) -> std::io::Result<()> {
+        use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut archive = ZipWriter::new(zip);
+
+        if let Some(provenance) = &self.provenance {
+            archive.start_file(PROVENANCE_MEMBER, options)?;
+            provenance.write(&mut archive)?;
+        }
+
+        self.write_files(prefix, |path, writer_cb| {
+            archive.start_file(format!("{}.imt", path.join("/")), options)?;
+            writer_cb(&mut archive)
+        })?;
+        archive.finish()?;
+        Ok(())
+    }
+
     pub fn get(&self, path: &Path) -> Option<&File> {
         self.files.get(path)
     }
 
+    pub fn remove_file(&mut self, path: &Path) -> Option<File> {
+        self.files.shift_remove(path)
+    }
+
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut File> {
+        self.files.get_mut(path)
+    }
+
+    pub fn resolve_export(&self, path: &Path) -> Result<ResolvedItem<'_>, ResolveError> {
+        crate::resolve::resolve_export(self, path)
+    }
+
+    /// Removes every file and item that belongs to a feature not listed in `enabled`
+    pub fn filter_features(&mut self, enabled: &[&str]) {
+        self.files
+            .retain(|_, file| crate::features::filter_file(file, enabled));
+    }
+
+    pub fn strip(&mut self, opts: StripOptions) {
+        for file in self.files.values_mut() {
+            crate::strip::strip_file(file, opts);
+        }
+    }
+
+    /// Uses the documentation of each item in the language `lang`, where it is available. See [`localize_file`](crate::docs::localize_file)
+    pub fn localize_docs(&mut self, lang: &str) {
+        for file in self.files.values_mut() {
+            crate::docs::localize_file(file, lang);
+        }
+    }
+
+    /// Simplifies every constant expression in the bundle. See [`fold_expr`](crate::normalize::fold_expr)
+    pub fn fold_constants(&mut self) {
+        for file in self.files.values_mut() {
+            crate::normalize::fold_file(file);
+        }
+    }
+
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        crate::validate::validate(self)
+    }
+
+    pub fn validate_target(&self, target: &TargetSpec) -> Vec<Diagnostic> {
+        crate::validate::validate_target(self, target)
+    }
+
     pub fn iter(&self) -> Iter<'_> {
         Iter(self.files.iter())
     }
@@ -218,6 +735,92 @@ impl<'a> IntoIterator for &'a Bundle {
     }
 }
 
+/// The `.imt` members of a TAR archive, with the module path each one corresponds to.
+/// The member [`PROVENANCE_MEMBER`] is read into `provenance`, if given, as it is reached
+#[cfg(feature = "tar")]
+fn tar_members<'a, R: Read>(
+    prefix: &'a Path,
+    archive: &'a mut tar::Archive<R>,
+    mut provenance: Option<&'a mut Option<Provenance>>,
+) -> Result<impl Iterator<Item = Result<(Path, tar::Entry<'a, R>), std::io::Error>> + 'a, DecodeError>
+{
+    Ok(archive
+        .entries()
+        .map_err(|e| DecodeError::Io {
+            inner: e,
+            additional: 0,
+        })?
+        .filter_map(move |e| {
+            let mut entry = match e {
+                Ok(e) => e,
+                Err(e) => return Some(Err(e)),
+            };
+
+            let name = match entry.path() {
+                Ok(name) => name,
+                Err(e) => {
+                    return Some(Err(e));
+                }
+            };
+
+            let path = name.as_os_str().to_str()?;
+
+            if path == PROVENANCE_MEMBER {
+                if let Some(provenance) = provenance.as_deref_mut() {
+                    match Provenance::read(std::io::BufReader::new(&mut entry)) {
+                        Ok(record) => *provenance = Some(record),
+                        Err(e) => {
+                            return Some(Err(std::io::Error::new(
+                                ErrorKind::InvalidData,
+                                format!("{PROVENANCE_MEMBER}: {e}"),
+                            )));
+                        }
+                    }
+                }
+                return None;
+            }
+
+            let path = path.strip_suffix(".imt")?;
+
+            let mut gpath = prefix.0.clone();
+
+            gpath.extend(
+                path.split(std::path::MAIN_SEPARATOR)
+                    .map(String::from)
+                    .collect::<Vec<_>>(),
+            );
+
+            Some(Ok((Path(gpath), entry)))
+        }))
+}
+
+/// Collects the files with the extension `ext` (such as `imt`) in the directory tree `dir`, with the module path each one corresponds to.
+/// Entries are visited in order of their names, so that the modules are added in the same order on every platform
+pub(crate) fn dir_members(
+    prefix: Path,
+    dir: &std::path::Path,
+    ext: &str,
+    members: &mut Vec<(Path, std::path::PathBuf)>,
+) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if entry.file_type()?.is_dir() {
+            dir_members(prefix.join(&name), &entry.path(), ext, members)?;
+        } else if let Some(name) = name
+            .strip_suffix(ext)
+            .and_then(|name| name.strip_suffix('.'))
+        {
+            members.push((prefix.join(name), entry.path()));
+        }
+    }
+    Ok(())
+}
+
 pub struct Iter<'a>(indexmap::map::Iter<'a, Path, File>);
 
 impl<'a> Iterator for Iter<'a> {
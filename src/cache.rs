@@ -0,0 +1,126 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    attr::{Attribute, AttributeFlags, AttributeTarget, UnknownAttributePolicy},
+    config::format_config,
+    file::File,
+    visit::{AttributeVisitorMut, visit_file_mut},
+};
+
+/// An on-disk cache of decoded [`File`]s, keyed by the SHA-256 hash of the encoded file they were decoded from.
+///
+/// Entries are stored with the payloads of recognized attributes uncompressed, so that loading an entry is cheaper than decoding the original file.
+/// Which attributes were compressed is stored alongside, so that loaded files keep the [`AttributeFlags`] they were decoded with.
+/// Since the result of decoding depends on the [`UnknownAttributePolicy`], entries are also keyed by the policy used.
+///
+/// The cache does not validate entries beyond decoding them: an entry that fails to decode is treated as missing.
+#[derive(Clone, Debug)]
+pub struct FileCache {
+    dir: PathBuf,
+}
+
+impl FileCache {
+    /// Opens the cache stored in `dir`, creating the directory if it does not exist
+    pub fn open(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, contents: &[u8], policy: UnknownAttributePolicy) -> PathBuf {
+        let policy = match policy {
+            UnknownAttributePolicy::Error => "error",
+            UnknownAttributePolicy::WarnAndPreserve => "warn",
+            UnknownAttributePolicy::Drop => "drop",
+        };
+        let hash = Sha256::digest(contents)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        self.dir.join(format!("{hash}-{policy}.imtc"))
+    }
+
+    /// Loads the file previously decoded from `contents` under `policy`, if it is cached.
+    ///
+    /// Symbols are interned by the active interner (see [`with_interner`](crate::intern::with_interner)).
+    pub fn get(&self, contents: &[u8], policy: UnknownAttributePolicy) -> Option<File> {
+        let bytes = std::fs::read(self.entry_path(contents, policy)).ok()?;
+        // Any unrecognized attributes were already accepted by `policy` when the entry was stored
+        let ((compressed, mut file), _): ((Vec<bool>, File), _) =
+            bincode::decode_from_slice_with_context(
+                &bytes,
+                format_config(),
+                UnknownAttributePolicy::WarnAndPreserve,
+            )
+            .ok()?;
+        let mut recompressor = Recompressor(compressed.into_iter());
+        visit_file_mut(&mut recompressor, &mut file);
+        Some(file)
+    }
+
+    /// Stores `file`, which was decoded from `contents` under `policy`
+    pub fn insert(
+        &self,
+        contents: &[u8],
+        policy: UnknownAttributePolicy,
+        file: &File,
+    ) -> std::io::Result<()> {
+        let mut file = file.clone();
+        let mut decompressor = Decompressor(Vec::new());
+        visit_file_mut(&mut decompressor, &mut file);
+        let bytes = bincode::encode_to_vec((decompressor.0, &file), format_config())
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e))?;
+
+        // Write to a temporary file first so that concurrent readers never see a partial entry
+        let path = self.entry_path(contents, policy);
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Removes every entry from the cache
+    pub fn clear(&self) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "imtc") {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Removes [`AttributeFlags::COMPRESSED`] from recognized attributes, recording whether each had it in the order they are visited
+struct Decompressor(Vec<bool>);
+
+impl AttributeVisitorMut for Decompressor {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>) {
+        for attr in attrs {
+            // Unrecognized payloads are kept as-is, so they can only be written back with the flags they were read with
+            if !attr.is_unknown() {
+                self.0
+                    .push(attr.flags().contains(AttributeFlags::COMPRESSED));
+                *attr.flags_mut() -= AttributeFlags::COMPRESSED;
+            }
+        }
+    }
+}
+
+/// Restores the [`AttributeFlags::COMPRESSED`] flags recorded by a [`Decompressor`]
+struct Recompressor(std::vec::IntoIter<bool>);
+
+impl AttributeVisitorMut for Recompressor {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>) {
+        for attr in attrs {
+            if !attr.is_unknown() && self.0.next() == Some(true) {
+                *attr.flags_mut() |= AttributeFlags::COMPRESSED;
+            }
+        }
+    }
+}
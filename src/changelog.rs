@@ -0,0 +1,108 @@
+use core::fmt::Write;
+
+use indexmap::IndexMap;
+
+use crate::{
+    attr::types::ItemDoc,
+    bundle::{Bundle, Path},
+    diff::{BundleDiff, ChangeKind, ChangeSubject, ItemChange, attribute_name},
+    resolve::{Item, module_of},
+};
+
+/// The first line of the documentation of the item at `path` in `bundle`, if it has any
+fn doc_snippet<'a>(bundle: &'a Bundle, path: &Path) -> Option<&'a str> {
+    let file = bundle.get(&module_of(path))?;
+    let doc = match Item::find_in(file, path.name()?)? {
+        Item::Type(def) => def.body.find_attribute::<ItemDoc>(),
+        Item::Value(val) => val.body.find_attribute::<ItemDoc>(),
+    }?;
+    doc.doc_lines
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+}
+
+fn write_section(
+    out: &mut String,
+    title: &str,
+    changes: &[&ItemChange],
+    bundle: &Bundle,
+) -> core::fmt::Result {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "### {title}\n")?;
+    for change in changes {
+        match &change.subject {
+            ChangeSubject::Item => write!(out, "- `{}`", change.path.name().unwrap_or_default())?,
+            ChangeSubject::Use(used) => write!(out, "- `use {used}`")?,
+            ChangeSubject::Attribute(id) => write!(out, "- attribute `{}`", attribute_name(*id))?,
+            ChangeSubject::Field(field) => write!(
+                out,
+                "- field `{}::{field}`",
+                change.path.name().unwrap_or_default()
+            )?,
+            ChangeSubject::AppendedField(field) => write!(
+                out,
+                "- field `{}::{field}` (appended)",
+                change.path.name().unwrap_or_default()
+            )?,
+            ChangeSubject::Variant(variant) => write!(
+                out,
+                "- variant `{}::{variant}`",
+                change.path.name().unwrap_or_default()
+            )?,
+        }
+        match change.kind {
+            ChangeKind::Changed {
+                docs_only: true, ..
+            } => out.push_str(" (documentation only)"),
+            ChangeKind::Changed {
+                abi_compatible: true,
+                ..
+            } => out.push_str(" (ABI compatible)"),
+            _ => {}
+        }
+        if change.subject == ChangeSubject::Item
+            && let Some(doc) = doc_snippet(bundle, &change.path)
+        {
+            write!(out, ": {doc}")?;
+        }
+        out.push('\n');
+    }
+    out.push('\n');
+    Ok(())
+}
+
+/// Renders `diff`, the changes between the `old` and `new` revisions of a bundle, as a Markdown changelog.
+///
+/// Changes are grouped by module into added, removed, and changed items, `use`s, and file attributes,
+/// with the first line of the documentation of each item.
+/// Removed items are documented from `old`, and all other items from `new`.
+pub fn changelog(old: &Bundle, new: &Bundle, diff: &BundleDiff) -> String {
+    let mut modules = IndexMap::<Path, Vec<&ItemChange>>::new();
+    for change in &diff.changes {
+        modules.entry(change.module()).or_default().push(change);
+    }
+
+    let mut out = String::new();
+    for (module, changes) in modules {
+        let of_kind = |pred: fn(ChangeKind) -> bool| {
+            changes
+                .iter()
+                .copied()
+                .filter(|change| pred(change.kind))
+                .collect::<Vec<_>>()
+        };
+        let added = of_kind(|kind| kind == ChangeKind::Added);
+        let removed = of_kind(|kind| kind == ChangeKind::Removed);
+        let changed = of_kind(|kind| matches!(kind, ChangeKind::Changed { .. }));
+
+        // Writing to a `String` does not fail
+        let _ = writeln!(out, "## `{module}`\n");
+        let _ = write_section(&mut out, "Added", &added, new);
+        let _ = write_section(&mut out, "Removed", &removed, old);
+        let _ = write_section(&mut out, "Changed", &changed, new);
+    }
+    out
+}
@@ -0,0 +1,304 @@
+use std::io::ErrorKind;
+
+use crate::{
+    attr::{
+        Attribute, Target, find_attribute, find_attributes,
+        types::{
+            ActiveWhen, BlockingHint, Concurrency, DeriveHint, DiscriminatedBy, Endian, ErrorDoc,
+            ExampleDoc, ItemDoc, MustUse, Niche, ParamDoc, ReturnDoc,
+        },
+    },
+    bundle::{Bundle, Path},
+    intern::Symbol,
+    special::SpecialConsts,
+    target::TargetSpec,
+    tydef::{Field, Union},
+    uses::{IntBits, IntType, SpecialConst},
+    value::Function,
+};
+
+pub mod asm;
+pub mod c;
+pub mod cpp;
+pub mod doc;
+pub mod plugin;
+pub mod rust;
+pub mod symbols;
+#[cfg(feature = "template")]
+pub mod template;
+mod wire;
+pub mod zig;
+
+/// Receives the files produced by a [`CodegenBackend`]
+pub trait OutputSink {
+    fn write_output(&mut self, name: &str, contents: &[u8]) -> std::io::Result<()>;
+}
+
+impl<F: FnMut(&str, &[u8]) -> std::io::Result<()>> OutputSink for F {
+    fn write_output(&mut self, name: &str, contents: &[u8]) -> std::io::Result<()> {
+        self(name, contents)
+    }
+}
+
+pub trait CodegenBackend {
+    fn name(&self) -> &str;
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()>;
+}
+
+pub(crate) fn unsupported(msg: String) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidInput, msg)
+}
+
+pub(crate) fn output_name(path: &Path, ext: &str) -> String {
+    let mut name = path.0.join("/");
+    if name.is_empty() {
+        name.push_str("root");
+    }
+    name.push('.');
+    name.push_str(ext);
+    name
+}
+
+pub(crate) fn doc_lines<Targ: crate::attr::AttributeTarget>(attrs: &[Attribute<Targ>]) -> &[Symbol]
+where
+    ItemDoc: Target<Targ>,
+{
+    find_attribute::<ItemDoc, _>(attrs)
+        .map(|doc| &*doc.doc_lines)
+        .unwrap_or_default()
+}
+
+/// Copies the documentation lines of an item, to be extended with notes
+pub(crate) fn doc_strings<Targ: crate::attr::AttributeTarget>(
+    attrs: &[Attribute<Targ>],
+) -> Vec<String>
+where
+    ItemDoc: Target<Targ>,
+{
+    doc_lines(attrs).iter().map(Symbol::to_string).collect()
+}
+
+/// The documentation of `f`, followed by notes describing its [`Concurrency`] and [`BlockingHint`],
+/// and sections for its [`ParamDoc`], [`ReturnDoc`], [`ErrorDoc`], and [`ExampleDoc`] attributes
+pub(crate) fn function_doc(f: &Function) -> Vec<String> {
+    let mut lines = doc_strings(&f.attrs);
+    let concurrency = match find_attribute::<Concurrency, _>(&f.attrs)
+        .copied()
+        .unwrap_or_default()
+    {
+        Concurrency::NoHint => None,
+        Concurrency::Reentrant => {
+            Some("This function is reentrant, and may be called from any thread or interrupt.")
+        }
+        Concurrency::ThreadSafe => Some(
+            "This function is thread-safe, but must not be called from a signal handler or interrupt.",
+        ),
+        Concurrency::ThreadUnsafe => {
+            Some("This function must not be called concurrently with itself.")
+        }
+    };
+    let blocking = match find_attribute::<BlockingHint, _>(&f.attrs)
+        .copied()
+        .unwrap_or_default()
+    {
+        BlockingHint::NoHint => None,
+        BlockingHint::NonBlocking => Some("This function does not block."),
+        BlockingHint::MayBlock => Some("This function may block the calling thread."),
+        BlockingHint::AsyncCompletion => Some(
+            "This function does not block, and the operation it starts completes asynchronously.",
+        ),
+    };
+    for note in [concurrency, blocking].into_iter().flatten() {
+        push_note(&mut lines, note);
+    }
+
+    let mut params = Vec::new();
+    for (i, param) in f.signature.params.iter().enumerate() {
+        if let Some(doc) = find_attribute::<ParamDoc, _>(&param.attrs) {
+            match &param.name {
+                Some(name) => push_entry(&mut params, name, &doc.doc_lines),
+                None => push_entry(&mut params, &format!("#{i}"), &doc.doc_lines),
+            }
+        }
+    }
+    push_section(&mut lines, "Parameters", params);
+
+    let returns = find_attribute::<ReturnDoc, _>(&f.signature.ret_attrs)
+        .map(|doc| doc.doc_lines.iter().map(Symbol::to_string).collect())
+        .unwrap_or_default();
+    push_section(&mut lines, "Returns", returns);
+
+    let mut errors = Vec::new();
+    for doc in find_attributes::<ErrorDoc, _>(&f.attrs) {
+        push_entry(&mut errors, &doc.error, &doc.doc_lines);
+    }
+    push_section(&mut lines, "Errors", errors);
+
+    lines.extend(example_doc(&f.attrs));
+    lines
+}
+
+/// An `# Examples` section, with a fenced code block for each [`ExampleDoc`] attribute in `attrs`
+pub(crate) fn example_doc<Targ: crate::attr::AttributeTarget>(
+    attrs: &[Attribute<Targ>],
+) -> Vec<String>
+where
+    ExampleDoc: Target<Targ>,
+{
+    let mut examples = Vec::new();
+    for example in find_attributes::<ExampleDoc, _>(attrs) {
+        if !examples.is_empty() {
+            examples.push(String::new());
+        }
+        examples.push(format!("```{}", example.lang));
+        examples.extend(example.code.iter().map(Symbol::to_string));
+        examples.push("```".to_string());
+    }
+    let mut lines = Vec::new();
+    push_section(&mut lines, "Examples", examples);
+    lines
+}
+
+/// Appends a list item documenting `name`, with the first line of `doc` after the name and the remaining lines indented below it
+fn push_entry(lines: &mut Vec<String>, name: &str, doc: &[Symbol]) {
+    let mut doc = doc.iter();
+    match doc.next() {
+        Some(first) => lines.push(format!("* `{name}`: {first}")),
+        None => lines.push(format!("* `{name}`")),
+    }
+    lines.extend(doc.map(|line| format!("  {line}")));
+}
+
+/// Appends a `# title` section containing `body`, unless `body` is empty
+fn push_section(lines: &mut Vec<String>, title: &str, body: Vec<String>) {
+    if body.is_empty() {
+        return;
+    }
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines.push(format!("# {title}"));
+    lines.extend(body);
+}
+
+/// The documentation of an item that may have a [`Niche`], followed by a note describing the reserved value
+pub(crate) fn niche_doc<Targ: crate::attr::AttributeTarget>(
+    attrs: &[Attribute<Targ>],
+) -> Vec<String>
+where
+    ItemDoc: Target<Targ>,
+    Niche: Target<Targ>,
+{
+    let mut lines = doc_strings(attrs);
+    match find_attribute::<Niche, _>(attrs) {
+        Some(Niche::Zero) => push_note(&mut lines, "The value zero (or null) is reserved."),
+        Some(Niche::AllOnes) => push_note(&mut lines, "The value with every bit set is reserved."),
+        None => {}
+    }
+    lines
+}
+
+/// The documentation of `un`, followed by a note describing its [`DiscriminatedBy`] attribute
+pub(crate) fn union_doc(un: &Union) -> Vec<String> {
+    let mut lines = doc_strings(&un.attrs);
+    if let Some(discrim) = find_attribute::<DiscriminatedBy, _>(&un.attrs) {
+        push_note(
+            &mut lines,
+            &format!(
+                "The active member is determined by the `{}` field of the containing struct.",
+                discrim.field
+            ),
+        );
+    }
+    lines
+}
+
+/// The documentation of `field`, followed by notes describing its [`Niche`], [`Endian`], and [`ActiveWhen`] attributes
+pub(crate) fn field_doc(field: &Field) -> Vec<String> {
+    let mut lines = niche_doc(&field.attrs);
+    if let Some(when) = find_attribute::<ActiveWhen, _>(&field.attrs) {
+        push_note(
+            &mut lines,
+            &format!("This member is active when the tag is `{}`.", when.variant),
+        );
+    }
+    match find_attribute::<Endian, _>(&field.attrs) {
+        Some(Endian::Little) => push_note(
+            &mut lines,
+            "This field is stored in little-endian byte order.",
+        ),
+        Some(Endian::Big) => {
+            push_note(&mut lines, "This field is stored in big-endian byte order.")
+        }
+        Some(Endian::Native) | None => {}
+    }
+    lines
+}
+
+fn push_note(lines: &mut Vec<String>, note: &str) {
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines.push(note.to_string());
+}
+
+/// Finds the [`MustUse`] attribute of `f`, either on the function itself or on its return value
+pub(crate) fn must_use(f: &Function) -> Option<&MustUse> {
+    find_attribute::<MustUse, _>(&f.attrs)
+        .or_else(|| find_attribute::<MustUse, _>(&f.signature.ret_attrs))
+}
+
+/// The traits requested by the [`DeriveHint`] attributes of a type, without duplicates
+pub(crate) fn derive_hints<Targ: crate::attr::AttributeTarget>(
+    attrs: &[Attribute<Targ>],
+) -> Vec<&str>
+where
+    DeriveHint: Target<Targ>,
+{
+    let mut traits = Vec::new();
+    for hint in find_attributes::<DeriveHint, _>(attrs) {
+        for name in &hint.traits {
+            if !traits.contains(&name.as_str()) {
+                traits.push(name.as_str());
+            }
+        }
+    }
+    traits
+}
+
+/// The value of a special constant that generated code cannot compute itself, on `target`
+pub(crate) fn special_value(
+    bundle: &Bundle,
+    target: &TargetSpec,
+    special: SpecialConst,
+) -> std::io::Result<u128> {
+    let specials = SpecialConsts::from_bundle(bundle);
+    specials.value(special, target).ok_or_else(|| {
+        unsupported(format!(
+            "special constant {} has no value on the target",
+            specials.name(special)
+        ))
+    })
+}
+
+/// Formats the raw bits of an integer literal in decimal, according to the signedness and width of `ty`.
+/// `long` literals are interpreted at the width of `target`.
+pub(crate) fn int_literal(ty: IntType, val: u128, target: &TargetSpec) -> String {
+    let bits = match ty.bits {
+        IntBits::Bits(bits) => bits.get() as u32,
+        IntBits::Long => target.long_bits as u32,
+    };
+
+    if !ty.signed || bits > 128 {
+        return val.to_string();
+    }
+
+    let shift = 128 - bits;
+    (((val << shift) as i128) >> shift).to_string()
+}
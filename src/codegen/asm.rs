@@ -0,0 +1,172 @@
+use core::fmt::Write as _;
+
+use crate::{
+    attr::{find_attribute, types::SystemFunction},
+    bundle::{Bundle, Path},
+    file::File,
+    layout::{LayoutEngine, LayoutError},
+    target::TargetSpec,
+    tydef::{StructBody, TypeDef, TypeDefBody},
+    value::ValueBody,
+};
+
+use super::{CodegenBackend, OutputSink, output_name};
+
+/// The assembler syntax of the files generated by [`AsmBackend`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AsmSyntax {
+    /// `.equ` directives, for the GNU assembler, in a `.S` file
+    #[default]
+    Gas,
+    /// `equ` definitions, for NASM, in a `.inc` file
+    Nasm,
+}
+
+impl AsmSyntax {
+    fn ext(self) -> &'static str {
+        match self {
+            AsmSyntax::Gas => "S",
+            AsmSyntax::Nasm => "inc",
+        }
+    }
+}
+
+/// Generates an assembler include file for each file in the bundle, for hand-written assembly that accesses the types and functions of the bundle.
+///
+/// Each struct or union without generic parameters defines `<name>_SIZE` and `<name>_ALIGN`, and each field of a struct defines `<name>_OFFSET_<field>`,
+/// as computed by the [`LayoutEngine`] for the target. Each function with a [`SystemFunction`] attribute defines its number as `SYS_<name>`.
+#[derive(Clone, Debug, Default)]
+pub struct AsmBackend {
+    pub syntax: AsmSyntax,
+}
+
+impl CodegenBackend for AsmBackend {
+    fn name(&self) -> &str {
+        "asm"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        for (path, file) in bundle {
+            let mut writer = AsmWriter {
+                path,
+                syntax: self.syntax,
+                layout: LayoutEngine::new(bundle, target),
+                out: String::new(),
+            };
+            writer.write_file(file);
+            sink.write_output(&output_name(path, self.syntax.ext()), writer.out.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+struct AsmWriter<'a> {
+    path: &'a Path,
+    syntax: AsmSyntax,
+    layout: LayoutEngine<'a>,
+    out: String,
+}
+
+impl AsmWriter<'_> {
+    fn line(&mut self, args: core::fmt::Arguments) {
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    fn comment(&mut self, comment: &str) {
+        match self.syntax {
+            AsmSyntax::Gas => self.line(format_args!("/* {comment} */")),
+            AsmSyntax::Nasm => self.line(format_args!("; {comment}")),
+        }
+    }
+
+    fn define(&mut self, name: &str, val: u128) {
+        match self.syntax {
+            AsmSyntax::Gas => self.line(format_args!(".equ {name}, {val}")),
+            AsmSyntax::Nasm => self.line(format_args!("{name} equ {val}")),
+        }
+    }
+
+    fn write_file(&mut self, file: &File) {
+        let guard = format!(
+            "IMT_{}_{}",
+            self.path.0.join("_").to_uppercase(),
+            self.syntax.ext().to_uppercase()
+        );
+
+        self.comment(&format!("Generated from {}", self.path));
+        match self.syntax {
+            AsmSyntax::Gas => {
+                self.line(format_args!(".ifndef {guard}"));
+                self.line(format_args!(".equ {guard}, 1"));
+            }
+            AsmSyntax::Nasm => {
+                self.line(format_args!("%ifndef {guard}"));
+                self.line(format_args!("%define {guard}"));
+            }
+        }
+
+        for ty in &file.types {
+            self.write_layout(ty);
+        }
+
+        let sysfns = file
+            .values
+            .iter()
+            .filter_map(|val| match &val.body {
+                ValueBody::Function(f) => find_attribute::<SystemFunction, _>(&f.attrs)
+                    .map(|sysfn| (&val.name, sysfn.function_id)),
+                ValueBody::Const(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if !sysfns.is_empty() {
+            self.out.push('\n');
+            for (name, id) in sysfns {
+                self.define(&format!("SYS_{name}"), id.into());
+            }
+        }
+
+        self.out.push('\n');
+        match self.syntax {
+            AsmSyntax::Gas => self.line(format_args!(".endif")),
+            AsmSyntax::Nasm => self.line(format_args!("%endif")),
+        }
+    }
+
+    fn write_layout(&mut self, ty: &TypeDef) {
+        if ty.num_params != 0 || !matches!(ty.body, TypeDefBody::Struct(_) | TypeDefBody::Union(_))
+        {
+            return;
+        }
+
+        let name = &ty.name;
+        let layout = match self.layout.layout_of_def(self.path, ty) {
+            Ok(layout) => layout,
+            Err(LayoutError::Unsized(_)) => return,
+            Err(e) => {
+                self.out.push('\n');
+                self.comment(&format!("no layout for {name}: {e}"));
+                return;
+            }
+        };
+        let offsets = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => self.layout.field_layouts(self.path, fields).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        self.out.push('\n');
+        self.define(&format!("{name}_SIZE"), layout.size);
+        self.define(&format!("{name}_ALIGN"), layout.align);
+        for field in offsets.into_iter().flatten() {
+            self.define(&format!("{name}_OFFSET_{}", field.name), field.offset);
+        }
+    }
+}
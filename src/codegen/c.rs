@@ -0,0 +1,726 @@
+use core::fmt::Write as _;
+use std::collections::HashMap;
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{Align, Endian, LinkName, SpecialPointerRepr, WireFormat},
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    layout::{LayoutEngine, LayoutError},
+    option::{OptionKind, option_header},
+    special::SpecialPointers,
+    target::TargetSpec,
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{BinaryOp, Expr, IntBits, IntType, Signature, SpecialConst, Type, UnaryOp},
+    value::{Value, ValueBody},
+};
+
+use super::{
+    CodegenBackend, OutputSink,
+    cpp::{defaulted_operators, needs_compare},
+    doc_lines, field_doc, function_doc, int_literal, must_use, niche_doc, output_name,
+    special_value, union_doc, unsupported,
+    wire::{Access, ScalarKind, wire_layout},
+};
+
+/// Generates a C header for each file in the bundle.
+///
+/// Structs with a [`WireFormat`] attribute have `<name>_encode` and `<name>_decode` functions, converting them to and from their wire format.
+#[derive(Clone, Debug, Default)]
+pub struct CBackend {
+    /// Emits `_Static_assert`s of the size, alignment, and field offsets of each type, as computed by the [`LayoutEngine`]
+    pub layout_assertions: bool,
+}
+
+impl CodegenBackend for CBackend {
+    fn name(&self) -> &str {
+        "c"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let specials = SpecialPointers::from_bundle(bundle);
+        for (path, file) in bundle {
+            let mut writer = CWriter {
+                bundle,
+                target,
+                path,
+                layout: self
+                    .layout_assertions
+                    .then(|| LayoutEngine::new(bundle, target)),
+                specials: &specials,
+                fn_types: HashMap::new(),
+                out: String::new(),
+            };
+            writer.write_file(path, file)?;
+            sink.write_output(&output_name(path, "h"), writer.out.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+struct CWriter<'a> {
+    bundle: &'a Bundle,
+    target: &'a TargetSpec,
+    path: &'a Path,
+    layout: Option<LayoutEngine<'a>>,
+    specials: &'a SpecialPointers,
+    /// The name of the typedef declared for each function pointer type used by the file
+    fn_types: HashMap<Type, String>,
+    out: String,
+}
+
+impl CWriter<'_> {
+    fn line(&mut self, args: core::fmt::Arguments) {
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    fn doc<S: AsRef<str>>(&mut self, indent: &str, lines: &[S]) {
+        if lines.is_empty() {
+            return;
+        }
+        self.line(format_args!("{indent}/**"));
+        for line in lines {
+            let line = line.as_ref().trim_end();
+            if line.is_empty() {
+                self.line(format_args!("{indent} *"));
+            } else {
+                self.line(format_args!("{indent} * {line}"));
+            }
+        }
+        self.line(format_args!("{indent} */"));
+    }
+
+    fn write_file(&mut self, path: &Path, file: &File) -> std::io::Result<()> {
+        let guard = format!("IMT_{}_H", path.0.join("_").to_uppercase());
+
+        self.doc("", doc_lines(&file.attributes));
+        self.line(format_args!("#ifndef {guard}"));
+        self.line(format_args!("#define {guard}"));
+        self.out.push('\n');
+        self.line(format_args!("#include <stddef.h>"));
+        self.line(format_args!("#include <stdint.h>"));
+        self.line(format_args!("#include <uchar.h>"));
+        if file.types.iter().any(|ty| {
+            matches!(&ty.body, TypeDefBody::Struct(st) if find_attribute::<WireFormat, _>(&st.attrs).is_some())
+        }) {
+            self.line(format_args!("#include <stdbool.h>"));
+            self.line(format_args!("#include <string.h>"));
+        }
+        if needs_compare(file.types.iter().filter(|ty| ty.num_params == 0)) {
+            self.line(format_args!("#ifdef __cplusplus"));
+            self.line(format_args!("#include <compare>"));
+            self.line(format_args!("#endif"));
+        }
+
+        for use_item in &file.uses {
+            let path = Path(use_item.path.clone());
+            let module = if self.bundle.get(&path).is_some() {
+                path
+            } else {
+                path.parent().unwrap_or_else(|| Path(vec![]))
+            };
+            self.line(format_args!("#include \"{}\"", output_name(&module, "h")));
+        }
+
+        for ty in &file.types {
+            self.out.push('\n');
+            self.write_fn_typedefs(ty)?;
+            self.write_typedef(ty)?;
+            self.write_layout_assertions(ty);
+            self.write_option_id(ty)?;
+            self.write_wire_format(ty)?;
+        }
+
+        for val in &file.values {
+            self.out.push('\n');
+            if let ValueBody::Function(f) = &val.body {
+                self.name_fn_sig(&val.name, &f.signature)?;
+            }
+            self.write_value(val)?;
+        }
+
+        self.out.push('\n');
+        self.line(format_args!("#endif /* {guard} */"));
+        Ok(())
+    }
+
+    /// Declares a typedef for each function pointer type used by the fields of `ty`, so that they are not spelled inline
+    fn write_fn_typedefs(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        if ty.num_params != 0 {
+            return Ok(());
+        }
+        let name = &ty.name;
+        match &ty.body {
+            // An alias of a function pointer type is already a typedef of it
+            TypeDefBody::Alias(alias) => {
+                if let Type::Pointer(_, pointee) = &alias.alias
+                    && let Type::Func(sig) = &**pointee
+                {
+                    self.name_fn_sig(name, sig)
+                } else {
+                    self.name_fn_types(name, &alias.alias)
+                }
+            }
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => self.name_field_fn_types(name, fields),
+                StructBody::Opaque(Some(repr)) => {
+                    self.name_fn_types(&format!("{name}__opaque"), repr)
+                }
+                StructBody::Opaque(None) => Ok(()),
+            },
+            TypeDefBody::Union(un) => self.name_field_fn_types(name, &un.fields),
+            TypeDefBody::Enum(_) => Ok(()),
+        }
+    }
+
+    fn name_field_fn_types(&mut self, prefix: &str, fields: &StructFields) -> std::io::Result<()> {
+        for field in &fields.field {
+            self.name_fn_types(&format!("{prefix}_{}", field.name), &field.ty)?;
+        }
+        if let Some(pad) = &fields.pad {
+            self.name_fn_types(&format!("{prefix}__pad"), pad)?;
+        }
+        Ok(())
+    }
+
+    /// Declares a typedef named `<prefix>_fn` for the function pointer type `ty`, or for each one `ty` contains.
+    /// Function pointer types that already have a typedef are not declared again
+    fn name_fn_types(&mut self, prefix: &str, ty: &Type) -> std::io::Result<()> {
+        match ty {
+            Type::Pointer(kind, _) if self.specials.repr(*kind) == SpecialPointerRepr::Address => {
+                Ok(())
+            }
+            Type::Pointer(_, pointee) => match &**pointee {
+                Type::Func(sig) if !self.fn_types.contains_key(ty) => {
+                    self.name_fn_sig(prefix, sig)?;
+                    let name = format!("{prefix}_fn");
+                    let decl = self.decl(ty, &name)?;
+                    self.line(format_args!("typedef {decl};"));
+                    self.fn_types.insert(ty.clone(), name);
+                    Ok(())
+                }
+                Type::Func(_) => Ok(()),
+                pointee => self.name_fn_types(prefix, pointee),
+            },
+            Type::Array(arr) => self.name_fn_types(prefix, &arr.base),
+            Type::Uninit(ty) => self.name_fn_types(prefix, ty),
+            _ => Ok(()),
+        }
+    }
+
+    /// Declares a typedef for each function pointer type used by the parameters or return type of `sig`
+    fn name_fn_sig(&mut self, prefix: &str, sig: &Signature) -> std::io::Result<()> {
+        for (i, param) in sig.params.iter().enumerate() {
+            let prefix = match &param.name {
+                Some(name) => format!("{prefix}_{name}"),
+                None => format!("{prefix}_arg{i}"),
+            };
+            self.name_fn_types(&prefix, &param.ty)?;
+        }
+        self.name_fn_types(&format!("{prefix}_ret"), &sig.retty)
+    }
+
+    fn write_typedef(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let name = &ty.name;
+
+        if ty.num_params != 0 {
+            self.line(format_args!(
+                "/* {name} has generic parameters, which cannot be represented in C */"
+            ));
+            return Ok(());
+        }
+
+        match &ty.body {
+            TypeDefBody::Alias(alias) => {
+                self.doc("", &niche_doc(&alias.attrs));
+                // The alias itself is spelled inline, and names its type for later uses if it is a function pointer
+                let named = self.fn_types.remove(&alias.alias);
+                let decl = self.decl(&alias.alias, name)?;
+                self.line(format_args!("typedef {decl};"));
+                if let Type::Pointer(_, pointee) = &alias.alias
+                    && matches!(**pointee, Type::Func(_))
+                {
+                    self.fn_types.insert(
+                        alias.alias.clone(),
+                        named.unwrap_or_else(|| name.to_string()),
+                    );
+                }
+            }
+            TypeDefBody::Struct(st) => {
+                self.doc("", &niche_doc(&st.attrs));
+                let align = find_attribute::<Align, _>(&st.attrs).map(|a| a.alignment);
+                match &st.body {
+                    StructBody::Fields(fields) => {
+                        self.line(format_args!("typedef struct {name} {{"));
+                        self.write_fields(fields, align)?;
+                        self.write_operators(ty);
+                        self.line(format_args!("}} {name};"));
+                    }
+                    StructBody::Opaque(Some(repr)) => {
+                        let decl = self.decl(repr, "_opaque")?;
+                        let align = Self::align_spec(align);
+                        self.line(format_args!("typedef struct {name} {{"));
+                        self.line(format_args!("    {align}{decl};"));
+                        self.write_operators(ty);
+                        self.line(format_args!("}} {name};"));
+                    }
+                    StructBody::Opaque(None) => {
+                        self.line(format_args!("typedef struct {name} {name};"));
+                    }
+                }
+            }
+            TypeDefBody::Union(un) => {
+                self.doc("", &union_doc(un));
+                let align = find_attribute::<Align, _>(&un.attrs).map(|a| a.alignment);
+                self.line(format_args!("typedef union {name} {{"));
+                self.write_fields(&un.fields, align)?;
+                self.line(format_args!("}} {name};"));
+            }
+            TypeDefBody::Enum(en) => {
+                self.doc("", doc_lines(&en.attrs));
+                let underlying = self.int_ty(en.underlying)?;
+                self.line(format_args!("typedef {underlying} {name};"));
+                for variant in &en.variants {
+                    self.doc("", doc_lines(&variant.attrs));
+                    let discrim = self.expr(&variant.discrim)?;
+                    self.line(format_args!(
+                        "#define {name}_{} (({name}){discrim})",
+                        variant.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Declares the C++ operators requested by the [`DeriveHint`](crate::attr::types::DeriveHint) attributes of `ty` as defaulted members, which requires C++20
+    fn write_operators(&mut self, ty: &TypeDef) {
+        let ops = defaulted_operators(ty);
+        if ops.is_empty() {
+            return;
+        }
+        self.line(format_args!("#ifdef __cplusplus"));
+        for op in ops {
+            self.line(format_args!("    {op}"));
+        }
+        self.line(format_args!("#endif"));
+    }
+
+    /// Alignment of a struct or union is raised by over-aligning its first member
+    fn align_spec(align: Option<u128>) -> String {
+        align
+            .map(|align| format!("_Alignas({align}) "))
+            .unwrap_or_default()
+    }
+
+    fn write_fields(&mut self, fields: &StructFields, align: Option<u128>) -> std::io::Result<()> {
+        let mut align = Self::align_spec(align);
+        for field in &fields.field {
+            self.doc("    ", &field_doc(field));
+            let decl = self.decl(&field.ty, &field.name)?;
+            self.line(format_args!("    {align}{decl};"));
+            align = String::new();
+        }
+        if let Some(pad) = &fields.pad {
+            let decl = self.decl(pad, "_pad")?;
+            self.line(format_args!("    {align}{decl};"));
+        }
+        Ok(())
+    }
+
+    /// Emits the id of an option struct, as `<name>_OPTION_ID`
+    fn write_option_id(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let TypeDefBody::Struct(st) = &ty.body else {
+            return Ok(());
+        };
+        let Some(OptionKind::Option(id)) = OptionKind::of(st) else {
+            return Ok(());
+        };
+        let Ok(header) = option_header(self.bundle, self.path, st) else {
+            return Ok(());
+        };
+
+        let decl = self.decl(&header.id.ty, &format!("{}_OPTION_ID", ty.name))?;
+        let id = self.expr(&Expr::UuidLiteral(id))?;
+        self.line(format_args!("static const {decl} = {id};"));
+        Ok(())
+    }
+
+    /// Emits `<name>_encode` and `<name>_decode` for a struct with a [`WireFormat`] attribute
+    fn write_wire_format(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let mut engine = LayoutEngine::new(self.bundle, self.target);
+        let Some(wire) = wire_layout(&mut engine, self.bundle, self.path, ty)? else {
+            return Ok(());
+        };
+
+        let name = &ty.name;
+        let size = format!("{name}_WIRE_SIZE");
+        self.line(format_args!("#define {size} {}", wire.size));
+        self.out.push('\n');
+        self.doc(
+            "",
+            &["Encodes `*val` into `buf` with the layout of its fields, and each padding byte zeroed"],
+        );
+        self.line(format_args!(
+            "static inline void {name}_encode(const {name} *val, unsigned char buf[{size}]) {{"
+        ));
+        self.line(format_args!("    memset(buf, 0, {size});"));
+        for scalar in &wire.scalars {
+            let field = Self::wire_access(&scalar.access);
+            let offset = scalar.offset;
+            match (&scalar.kind, scalar.endian) {
+                (ScalarKind::Bytes, _) => self.line(format_args!(
+                    "    memcpy(buf + {offset}, val->{field}, {});",
+                    scalar.size
+                )),
+                (_, Endian::Native) => self.line(format_args!(
+                    "    memcpy(buf + {offset}, &val->{field}, sizeof(val->{field}));"
+                )),
+                (_, endian) => {
+                    let shifts = Self::wire_shifts(name, scalar.size, endian)?;
+                    for (n, shift) in shifts.into_iter().enumerate() {
+                        self.line(format_args!(
+                            "    buf[{}] = (unsigned char)((uint64_t)val->{field} >> {shift});",
+                            offset + n as u128
+                        ));
+                    }
+                }
+            }
+        }
+        self.line(format_args!("}}"));
+        self.out.push('\n');
+        self.doc(
+            "",
+            &[
+                format!("Decodes a {name} encoded by `{name}_encode` into `*val`."),
+                "Returns false if a reserved field that must be zero is not zero".to_string(),
+            ],
+        );
+        self.line(format_args!(
+            "static inline bool {name}_decode(const unsigned char buf[{size}], {name} *val) {{"
+        ));
+        for reserved in wire
+            .reserved
+            .iter()
+            .filter(|reserved| reserved.must_be_zero)
+        {
+            self.line(format_args!(
+                "    for (size_t i = {}; i < {}; i++) {{",
+                reserved.offset,
+                reserved.offset + reserved.size
+            ));
+            self.line(format_args!("        if (buf[i] != 0) {{"));
+            self.line(format_args!("            return false;"));
+            self.line(format_args!("        }}"));
+            self.line(format_args!("    }}"));
+        }
+        self.line(format_args!("    memset(val, 0, sizeof(*val));"));
+        for scalar in &wire.scalars {
+            let field = Self::wire_access(&scalar.access);
+            let offset = scalar.offset;
+            match (&scalar.kind, scalar.endian) {
+                (ScalarKind::Bytes, _) => self.line(format_args!(
+                    "    memcpy(val->{field}, buf + {offset}, {});",
+                    scalar.size
+                )),
+                (_, Endian::Native) => self.line(format_args!(
+                    "    memcpy(&val->{field}, buf + {offset}, sizeof(val->{field}));"
+                )),
+                (ScalarKind::Int(int) | ScalarKind::Char(int), endian) => {
+                    let ty = match scalar.kind {
+                        ScalarKind::Char(_) => self.char_ty(*int)?,
+                        _ => self.int_ty(*int)?,
+                    };
+                    let bytes = Self::wire_shifts(name, scalar.size, endian)?
+                        .into_iter()
+                        .enumerate()
+                        .map(|(n, shift)| {
+                            format!("(uint64_t)buf[{}] << {shift}", offset + n as u128)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" | ");
+                    self.line(format_args!("    val->{field} = ({ty})({bytes});"));
+                }
+            }
+        }
+        self.line(format_args!("    return true;"));
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    fn wire_access(access: &[Access]) -> String {
+        let mut res = String::new();
+        for step in access {
+            match step {
+                Access::Field(name) if res.is_empty() => res.push_str(name),
+                Access::Field(name) => {
+                    let _ = write!(res, ".{name}");
+                }
+                Access::Index(n) => {
+                    let _ = write!(res, "[{n}]");
+                }
+                Access::Enum => {}
+            }
+        }
+        res
+    }
+
+    /// The shift of each byte of an integer of `size` bytes with the byte order `endian`, in the order the bytes are encoded
+    fn wire_shifts(name: &str, size: u128, endian: Endian) -> std::io::Result<Vec<u128>> {
+        if size > 8 {
+            return Err(unsupported(format!(
+                "{name} has no C wire format: integers wider than 64 bits must have the native byte order"
+            )));
+        }
+        let shifts = (0..size).map(|n| n * 8);
+        Ok(match endian {
+            Endian::Big => shifts.rev().collect(),
+            _ => shifts.collect(),
+        })
+    }
+
+    fn write_layout_assertions(&mut self, ty: &TypeDef) {
+        let Some(engine) = &mut self.layout else {
+            return;
+        };
+        if ty.num_params != 0 {
+            return;
+        }
+
+        let name = &ty.name;
+        let layout = match engine.layout_of_def(self.path, ty) {
+            Ok(layout) => layout,
+            Err(LayoutError::Unsized(_)) => return,
+            Err(e) => {
+                self.line(format_args!("/* no layout assertions for {name}: {e} */"));
+                return;
+            }
+        };
+        let offsets = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => engine.field_layouts(self.path, fields).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        self.line(format_args!(
+            "_Static_assert(sizeof({name}) == {}, \"size of {name}\");",
+            layout.size
+        ));
+        self.line(format_args!(
+            "_Static_assert(_Alignof({name}) == {}, \"alignment of {name}\");",
+            layout.align
+        ));
+        for field in offsets.into_iter().flatten() {
+            self.line(format_args!(
+                "_Static_assert(offsetof({name}, {0}) == {1}, \"offset of {name}.{0}\");",
+                field.name, field.offset
+            ));
+        }
+    }
+
+    fn write_value(&mut self, val: &Value) -> std::io::Result<()> {
+        let name = &val.name;
+        match &val.body {
+            ValueBody::Const(c) => {
+                self.doc("", doc_lines(&c.attrs));
+                let expr = self.expr(&c.val)?;
+                if matches!(c.val, Expr::UuidLiteral(_)) {
+                    let decl = self.decl(&c.ty, name)?;
+                    self.line(format_args!("static const {decl} = {expr};"));
+                } else if matches!(c.val, Expr::StringLiteral(_)) {
+                    self.line(format_args!("#define {name} {expr}"));
+                } else {
+                    let cast = self.decl(&c.ty, "")?;
+                    self.line(format_args!("#define {name} (({cast}){expr})"));
+                }
+            }
+            ValueBody::Function(f) => {
+                self.doc("", &function_doc(f));
+                let mut decl = self.decl(&Type::Func(f.signature.clone()), name)?;
+                if let Some(link) = find_attribute::<LinkName, _>(&f.attrs) {
+                    let link = self.expr(&Expr::StringLiteral(link.name.clone()))?;
+                    let _ = write!(decl, " __asm__({link})");
+                }
+                if must_use(f).is_some() {
+                    self.line(format_args!("__attribute__((warn_unused_result)) {decl};"));
+                } else {
+                    self.line(format_args!("{decl};"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn int_ty(&self, ty: IntType) -> std::io::Result<String> {
+        let sign = if ty.signed { "" } else { "u" };
+        match ty.bits {
+            IntBits::Long if ty.signed => Ok("long".to_string()),
+            IntBits::Long => Ok("unsigned long".to_string()),
+            IntBits::Bits(bits) if matches!(bits.get(), 8 | 16 | 32 | 64) => {
+                Ok(format!("{sign}int{bits}_t"))
+            }
+            IntBits::Bits(bits) if bits.get() == 128 && ty.signed => Ok("__int128".to_string()),
+            IntBits::Bits(bits) if bits.get() == 128 => Ok("unsigned __int128".to_string()),
+            IntBits::Bits(bits) => Err(unsupported(format!(
+                "{sign}int{bits}_t has no C equivalent"
+            ))),
+        }
+    }
+
+    fn char_ty(&self, ty: IntType) -> std::io::Result<String> {
+        match ty {
+            IntType::u8 => Ok("char".to_string()),
+            IntType::u16 => Ok("char16_t".to_string()),
+            IntType::u32 => Ok("char32_t".to_string()),
+            ty => Err(unsupported(format!("char type {ty} has no C equivalent"))),
+        }
+    }
+
+    /// Declares `name` with type `ty`, using C declarator syntax
+    fn decl(&self, ty: &Type, name: &str) -> std::io::Result<String> {
+        self.declarator(ty, name.to_string(), false)
+    }
+
+    fn declarator(&self, ty: &Type, inner: String, is_const: bool) -> std::io::Result<String> {
+        let base = match ty {
+            Type::Pointer(kind, _) if self.specials.repr(*kind) == SpecialPointerRepr::Address => {
+                "uintptr_t".to_string()
+            }
+            Type::Pointer(..) if self.fn_types.contains_key(ty) => self.fn_types[ty].clone(),
+            Type::Pointer(kind, pointee) => {
+                let mut inner = if is_const {
+                    format!("* const {inner}")
+                } else {
+                    format!("*{inner}")
+                };
+                if matches!(**pointee, Type::Array(_) | Type::Func(_)) {
+                    inner = format!("({inner})");
+                }
+                return self.declarator(
+                    pointee,
+                    inner,
+                    self.specials.repr(*kind) == SpecialPointerRepr::Const,
+                );
+            }
+            Type::Array(arr) => {
+                // The outermost dimension is declared first, as `x[2][4]` for `[[u8; 4]; 2]`
+                let (dims, elem) = arr.dimensions();
+                let mut inner = inner;
+                for len in dims {
+                    inner = format!("{inner}[{}]", self.expr(len)?);
+                }
+                return self.declarator(elem, inner, is_const);
+            }
+            Type::Func(sig) => {
+                let params = self.params(sig)?;
+                return self.declarator(&sig.retty, format!("{inner}({params})"), false);
+            }
+            Type::Uninit(ty) => return self.declarator(ty, inner, is_const),
+            Type::Named(name, _) => name.to_string(),
+            Type::Param(n, _) => {
+                return Err(unsupported(format!(
+                    "generic parameter {n} cannot be represented in C"
+                )));
+            }
+            Type::Const(val) => {
+                return Err(unsupported(format!(
+                    "constant generic argument {val} cannot be represented in C"
+                )));
+            }
+            Type::Int(int) => self.int_ty(*int)?,
+            Type::Char(int) => self.char_ty(*int)?,
+            Type::Void | Type::Never => "void".to_string(),
+            Type::Byte => "unsigned char".to_string(),
+        };
+
+        let qual = if is_const { "const " } else { "" };
+        if inner.is_empty() {
+            Ok(format!("{qual}{base}"))
+        } else {
+            Ok(format!("{qual}{base} {inner}"))
+        }
+    }
+
+    fn params(&self, sig: &Signature) -> std::io::Result<String> {
+        if sig.params.is_empty() {
+            return Ok("void".to_string());
+        }
+
+        let params = sig
+            .params
+            .iter()
+            .map(|param| self.decl(&param.ty, param.name.as_deref().unwrap_or("")))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(params.join(", "))
+    }
+
+    fn expr(&self, expr: &Expr) -> std::io::Result<String> {
+        Ok(match expr {
+            Expr::IntLiteral(ty, val) => {
+                let lit = int_literal(*ty, *val, self.target);
+                let suffix = if ty.signed { "LL" } else { "ULL" };
+                match lit.strip_prefix('-') {
+                    Some(lit) => format!("(-{lit}{suffix})"),
+                    None => format!("{lit}{suffix}"),
+                }
+            }
+            Expr::UuidLiteral(uuid) => {
+                format!("{{ {:#x}ULL, {:#x}ULL }}", uuid.minor, uuid.major)
+            }
+            Expr::StringLiteral(s) => {
+                let mut lit = String::from("\"");
+                for b in s.bytes() {
+                    match b {
+                        b'"' | b'\\' => {
+                            lit.push('\\');
+                            lit.push(b as char);
+                        }
+                        0x20..0x7F => lit.push(b as char),
+                        b => {
+                            let _ = write!(lit, "\\{b:03o}");
+                        }
+                    }
+                }
+                lit.push('"');
+                lit
+            }
+            Expr::Const(name) => name.clone(),
+            Expr::BinOp(op, left, right) => {
+                let op = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::And => "&",
+                    BinaryOp::Or => "|",
+                    BinaryOp::Xor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
+                };
+                format!("({} {op} {})", self.expr(left)?, self.expr(right)?)
+            }
+            Expr::UnaryOp(UnaryOp::Not, inner) => format!("(~{})", self.expr(inner)?),
+            Expr::UnaryOp(UnaryOp::Neg, inner) => format!("(-{})", self.expr(inner)?),
+            Expr::SpecialConstant(SpecialConst::SizeofPointer) => "sizeof(void *)".to_string(),
+            Expr::SpecialConstant(SpecialConst::SizeofLong) => "sizeof(long)".to_string(),
+            Expr::SpecialConstant(special) => {
+                format!("{}ULL", special_value(self.bundle, self.target, *special)?)
+            }
+            Expr::Param(n) => {
+                return Err(unsupported(format!(
+                    "generic parameter {n} cannot be represented in C"
+                )));
+            }
+        })
+    }
+}
@@ -0,0 +1,557 @@
+use core::fmt::Write as _;
+
+use crate::{
+    attr::{
+        Attribute, AttributeTarget, Target, find_attribute,
+        types::{Align, Deprecated, LinkName, MustUse, SpecialPointerRepr},
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    layout::{LayoutEngine, LayoutError},
+    option::{OptionKind, option_header},
+    special::SpecialPointers,
+    target::TargetSpec,
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{BinaryOp, Expr, IntBits, IntType, Signature, SpecialConst, Type, UnaryOp},
+    value::{Value, ValueBody},
+};
+
+use super::{
+    CodegenBackend, OutputSink, derive_hints, doc_lines, field_doc, function_doc, int_literal,
+    must_use, niche_doc, output_name, special_value, union_doc, unsupported,
+};
+
+/// Generates a C++ header for each file in the bundle.
+///
+/// Declarations are placed in a namespace named by the path of the file, enums are generated as `enum class`es,
+/// and types with generic parameters are generated as templates. Functions keep C linkage, so they are interchangeable with those declared by [`CBackend`](super::c::CBackend).
+#[derive(Clone, Debug, Default)]
+pub struct CppBackend {
+    /// Emits `static_assert`s of the size, alignment, and field offsets of each type, as computed by the [`LayoutEngine`]
+    pub layout_assertions: bool,
+}
+
+impl CodegenBackend for CppBackend {
+    fn name(&self) -> &str {
+        "cpp"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let specials = SpecialPointers::from_bundle(bundle);
+        for (path, file) in bundle {
+            let mut writer = CppWriter {
+                bundle,
+                target,
+                path,
+                layout: self
+                    .layout_assertions
+                    .then(|| LayoutEngine::new(bundle, target)),
+                specials: &specials,
+                out: String::new(),
+            };
+            writer.write_file(path, file)?;
+            sink.write_output(&output_name(path, "hpp"), writer.out.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// The defaulted comparison operators corresponding to the traits requested by the [`DeriveHint`](crate::attr::types::DeriveHint) attributes of `ty`.
+/// Enums already have every operator, and other traits (such as `Clone`) have no C++ equivalent
+pub(super) fn defaulted_operators(ty: &TypeDef) -> Vec<String> {
+    let TypeDefBody::Struct(st) = &ty.body else {
+        return Vec::new();
+    };
+    if matches!(st.body, StructBody::Opaque(None)) {
+        return Vec::new();
+    }
+    let name = &ty.name;
+    let traits = derive_hints(&st.attrs);
+    let mut ops = Vec::new();
+    if traits.iter().any(|t| matches!(*t, "PartialEq" | "Eq")) {
+        ops.push(format!("bool operator==(const {name} &) const = default;"));
+    }
+    if traits.iter().any(|t| matches!(*t, "PartialOrd" | "Ord")) {
+        ops.push(format!("auto operator<=>(const {name} &) const = default;"));
+    }
+    ops
+}
+
+/// Whether any of `types` has a defaulted `operator<=>`, which requires `<compare>`
+pub(super) fn needs_compare<'a>(types: impl IntoIterator<Item = &'a TypeDef>) -> bool {
+    types.into_iter().any(|ty| {
+        defaulted_operators(ty)
+            .iter()
+            .any(|op| op.starts_with("auto operator<=>"))
+    })
+}
+
+fn string_literal(s: &str) -> String {
+    let mut lit = String::from("\"");
+    for b in s.bytes() {
+        match b {
+            b'"' | b'\\' => {
+                lit.push('\\');
+                lit.push(b as char);
+            }
+            0x20..0x7F => lit.push(b as char),
+            b => {
+                let _ = write!(lit, "\\{b:03o}");
+            }
+        }
+    }
+    lit.push('"');
+    lit
+}
+
+/// The `[[deprecated]]` attribute for the item, followed by a space, if it has a [`Deprecated`] attribute
+fn deprecated<Targ: AttributeTarget>(attrs: &[Attribute<Targ>]) -> String
+where
+    Deprecated: Target<Targ>,
+{
+    match find_attribute::<Deprecated, _>(attrs) {
+        Some(Deprecated { note: Some(note) }) => {
+            format!("[[deprecated({})]] ", string_literal(note))
+        }
+        Some(Deprecated { note: None }) => "[[deprecated]] ".to_string(),
+        None => String::new(),
+    }
+}
+
+fn nodiscard(must_use: Option<&MustUse>) -> String {
+    match must_use {
+        Some(MustUse {
+            reason: Some(reason),
+        }) => format!("[[nodiscard({})]] ", string_literal(reason)),
+        Some(MustUse { reason: None }) => "[[nodiscard]] ".to_string(),
+        None => String::new(),
+    }
+}
+
+struct CppWriter<'a> {
+    bundle: &'a Bundle,
+    target: &'a TargetSpec,
+    path: &'a Path,
+    layout: Option<LayoutEngine<'a>>,
+    specials: &'a SpecialPointers,
+    out: String,
+}
+
+impl CppWriter<'_> {
+    fn line(&mut self, args: core::fmt::Arguments) {
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    fn doc<S: AsRef<str>>(&mut self, indent: &str, lines: &[S]) {
+        for line in lines {
+            let line = line.as_ref().trim_end();
+            if line.is_empty() {
+                self.line(format_args!("{indent}///"));
+            } else {
+                self.line(format_args!("{indent}/// {line}"));
+            }
+        }
+    }
+
+    fn write_file(&mut self, path: &Path, file: &File) -> std::io::Result<()> {
+        let guard = format!("IMT_{}_HPP", path.0.join("_").to_uppercase());
+
+        self.doc("", doc_lines(&file.attributes));
+        self.line(format_args!("#ifndef {guard}"));
+        self.line(format_args!("#define {guard}"));
+        self.out.push('\n');
+        if needs_compare(&file.types) {
+            self.line(format_args!("#include <compare>"));
+        }
+        self.line(format_args!("#include <cstddef>"));
+        self.line(format_args!("#include <cstdint>"));
+
+        let mut usings = Vec::new();
+        for use_item in &file.uses {
+            let path = Path(use_item.path.clone());
+            if self.bundle.get(&path).is_some() {
+                self.line(format_args!("#include \"{}\"", output_name(&path, "hpp")));
+                usings.push(format!("using namespace ::{path};"));
+            } else {
+                let module = path.parent().unwrap_or_else(|| Path(vec![]));
+                self.line(format_args!("#include \"{}\"", output_name(&module, "hpp")));
+                usings.push(format!("using ::{path};"));
+            }
+        }
+
+        if !path.0.is_empty() {
+            self.out.push('\n');
+            self.line(format_args!("namespace {path} {{"));
+        }
+        if !usings.is_empty() {
+            self.out.push('\n');
+            for using in usings {
+                self.line(format_args!("{using}"));
+            }
+        }
+
+        for ty in &file.types {
+            self.out.push('\n');
+            self.write_typedef(ty)?;
+            self.write_layout_assertions(ty);
+            self.write_option_id(ty)?;
+        }
+
+        for val in &file.values {
+            self.out.push('\n');
+            self.write_value(val)?;
+        }
+
+        if !path.0.is_empty() {
+            self.out.push('\n');
+            self.line(format_args!("}} // namespace {path}"));
+        }
+        self.out.push('\n');
+        self.line(format_args!("#endif // {guard}"));
+        Ok(())
+    }
+
+    fn template(ty: &TypeDef) -> String {
+        if ty.num_params == 0 {
+            return String::new();
+        }
+
+        let params = ty
+            .const_params()
+            .into_iter()
+            .enumerate()
+            .map(|(n, is_const)| match is_const {
+                true => format!("std::size_t N{n}"),
+                false => format!("typename T{n}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("template <{params}>\n")
+    }
+
+    fn write_typedef(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let name = &ty.name;
+        let template = Self::template(ty);
+
+        match &ty.body {
+            TypeDefBody::Alias(alias) => {
+                self.doc("", &niche_doc(&alias.attrs));
+                let aliased = self.decl(&alias.alias, "")?;
+                let deprecated = deprecated(&alias.attrs);
+                self.line(format_args!(
+                    "{template}using {name} {deprecated}= {aliased};"
+                ));
+            }
+            TypeDefBody::Struct(st) => {
+                self.doc("", &niche_doc(&st.attrs));
+                let align = Self::align_spec(find_attribute::<Align, _>(&st.attrs));
+                let deprecated = deprecated(&st.attrs);
+                match &st.body {
+                    StructBody::Fields(fields) => {
+                        self.line(format_args!(
+                            "{template}struct {deprecated}{align}{name} {{"
+                        ));
+                        self.write_fields(fields)?;
+                        self.write_operators(ty);
+                        self.line(format_args!("}};"));
+                    }
+                    StructBody::Opaque(Some(repr)) => {
+                        let decl = self.decl(repr, "_opaque")?;
+                        self.line(format_args!(
+                            "{template}struct {deprecated}{align}{name} {{"
+                        ));
+                        self.line(format_args!("    {decl};"));
+                        self.write_operators(ty);
+                        self.line(format_args!("}};"));
+                    }
+                    StructBody::Opaque(None) => {
+                        self.line(format_args!("{template}struct {deprecated}{name};"));
+                    }
+                }
+            }
+            TypeDefBody::Union(un) => {
+                self.doc("", &union_doc(un));
+                let align = Self::align_spec(find_attribute::<Align, _>(&un.attrs));
+                let deprecated = deprecated(&un.attrs);
+                self.line(format_args!("{template}union {deprecated}{align}{name} {{"));
+                self.write_fields(&un.fields)?;
+                self.line(format_args!("}};"));
+            }
+            TypeDefBody::Enum(en) => {
+                self.doc("", doc_lines(&en.attrs));
+                let underlying = self.int_ty(en.underlying)?;
+                self.line(format_args!(
+                    "enum class {}{name} : {underlying} {{",
+                    deprecated(&en.attrs)
+                ));
+                for variant in &en.variants {
+                    self.doc("    ", doc_lines(&variant.attrs));
+                    let discrim = self.expr(&variant.discrim)?;
+                    let deprecated = deprecated(&variant.attrs);
+                    self.line(format_args!(
+                        "    {} {deprecated}= {discrim},",
+                        variant.name
+                    ));
+                }
+                self.line(format_args!("}};"));
+            }
+        }
+        Ok(())
+    }
+
+    fn align_spec(align: Option<&Align>) -> String {
+        align
+            .map(|align| format!("alignas({}) ", align.alignment))
+            .unwrap_or_default()
+    }
+
+    fn write_fields(&mut self, fields: &StructFields) -> std::io::Result<()> {
+        for field in &fields.field {
+            self.doc("    ", &field_doc(field));
+            let decl = self.decl(&field.ty, &field.name)?;
+            let deprecated = deprecated(&field.attrs);
+            self.line(format_args!("    {deprecated}{decl};"));
+        }
+        if let Some(pad) = &fields.pad {
+            let decl = self.decl(pad, "_pad")?;
+            self.line(format_args!("    {decl};"));
+        }
+        Ok(())
+    }
+
+    fn write_operators(&mut self, ty: &TypeDef) {
+        for op in defaulted_operators(ty) {
+            self.line(format_args!("    {op}"));
+        }
+    }
+
+    /// Emits the id of an option struct, as `<name>_OPTION_ID`
+    fn write_option_id(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let TypeDefBody::Struct(st) = &ty.body else {
+            return Ok(());
+        };
+        let Some(OptionKind::Option(id)) = OptionKind::of(st) else {
+            return Ok(());
+        };
+        let Ok(header) = option_header(self.bundle, self.path, st) else {
+            return Ok(());
+        };
+
+        let decl = self.decl(&header.id.ty, &format!("{}_OPTION_ID", ty.name))?;
+        let id = self.expr(&Expr::UuidLiteral(id))?;
+        self.line(format_args!("inline constexpr {decl} = {id};"));
+        Ok(())
+    }
+
+    fn write_layout_assertions(&mut self, ty: &TypeDef) {
+        let Some(engine) = &mut self.layout else {
+            return;
+        };
+        if ty.num_params != 0 {
+            return;
+        }
+
+        let name = &ty.name;
+        let layout = match engine.layout_of_def(self.path, ty) {
+            Ok(layout) => layout,
+            Err(LayoutError::Unsized(_)) => return,
+            Err(e) => {
+                self.line(format_args!("// no layout assertions for {name}: {e}"));
+                return;
+            }
+        };
+        let offsets = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => engine.field_layouts(self.path, fields).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        self.line(format_args!(
+            "static_assert(sizeof({name}) == {}, \"size of {name}\");",
+            layout.size
+        ));
+        self.line(format_args!(
+            "static_assert(alignof({name}) == {}, \"alignment of {name}\");",
+            layout.align
+        ));
+        for field in offsets.into_iter().flatten() {
+            self.line(format_args!(
+                "static_assert(offsetof({name}, {0}) == {1}, \"offset of {name}::{0}\");",
+                field.name, field.offset
+            ));
+        }
+    }
+
+    fn write_value(&mut self, val: &Value) -> std::io::Result<()> {
+        let name = &val.name;
+        match &val.body {
+            ValueBody::Const(c) => {
+                self.doc("", doc_lines(&c.attrs));
+                let decl = self.decl(&c.ty, name)?;
+                let expr = self.expr(&c.val)?;
+                let deprecated = deprecated(&c.attrs);
+                self.line(format_args!(
+                    "{deprecated}inline constexpr {decl} = {expr};"
+                ));
+            }
+            ValueBody::Function(f) => {
+                self.doc("", &function_doc(f));
+                let mut decl = self.decl(&Type::Func(f.signature.clone()), name)?;
+                if let Some(link) = find_attribute::<LinkName, _>(&f.attrs) {
+                    let _ = write!(decl, " __asm__({})", string_literal(&link.name));
+                }
+                let noreturn = if matches!(*f.signature.retty, Type::Never) {
+                    "[[noreturn]] "
+                } else {
+                    ""
+                };
+                let nodiscard = nodiscard(must_use(f));
+                let deprecated = deprecated(&f.attrs);
+                self.line(format_args!(
+                    "extern \"C\" {noreturn}{nodiscard}{deprecated}{decl};"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn int_ty(&self, ty: IntType) -> std::io::Result<String> {
+        let sign = if ty.signed { "" } else { "u" };
+        match ty.bits {
+            IntBits::Long if ty.signed => Ok("long".to_string()),
+            IntBits::Long => Ok("unsigned long".to_string()),
+            IntBits::Bits(bits) if matches!(bits.get(), 8 | 16 | 32 | 64) => {
+                Ok(format!("std::{sign}int{bits}_t"))
+            }
+            IntBits::Bits(bits) if bits.get() == 128 && ty.signed => Ok("__int128".to_string()),
+            IntBits::Bits(bits) if bits.get() == 128 => Ok("unsigned __int128".to_string()),
+            IntBits::Bits(bits) => Err(unsupported(format!(
+                "{sign}int{bits}_t has no C++ equivalent"
+            ))),
+        }
+    }
+
+    fn char_ty(&self, ty: IntType) -> std::io::Result<String> {
+        match ty {
+            IntType::u8 => Ok("char".to_string()),
+            IntType::u16 => Ok("char16_t".to_string()),
+            IntType::u32 => Ok("char32_t".to_string()),
+            ty => Err(unsupported(format!("char type {ty} has no C++ equivalent"))),
+        }
+    }
+
+    /// Declares `name` with type `ty`, using C++ declarator syntax
+    fn decl(&self, ty: &Type, name: &str) -> std::io::Result<String> {
+        self.declarator(ty, name.to_string(), false)
+    }
+
+    fn declarator(&self, ty: &Type, inner: String, is_const: bool) -> std::io::Result<String> {
+        let base = match ty {
+            Type::Pointer(kind, _) if self.specials.repr(*kind) == SpecialPointerRepr::Address => {
+                "std::uintptr_t".to_string()
+            }
+            Type::Pointer(kind, pointee) => {
+                let mut inner = if is_const {
+                    format!("* const {inner}")
+                } else {
+                    format!("*{inner}")
+                };
+                if matches!(**pointee, Type::Array(_) | Type::Func(_)) {
+                    inner = format!("({inner})");
+                }
+                return self.declarator(
+                    pointee,
+                    inner,
+                    self.specials.repr(*kind) == SpecialPointerRepr::Const,
+                );
+            }
+            Type::Array(arr) => {
+                let len = self.expr(&arr.len)?;
+                return self.declarator(&arr.base, format!("{inner}[{len}]"), is_const);
+            }
+            Type::Func(sig) => {
+                let params = self.params(sig)?;
+                return self.declarator(&sig.retty, format!("{inner}({params})"), false);
+            }
+            Type::Uninit(ty) => return self.declarator(ty, inner, is_const),
+            Type::Named(name, None) => name.to_string(),
+            Type::Named(name, Some(args)) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.decl(arg, ""))
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                format!("{name}<{}>", args.join(", "))
+            }
+            Type::Param(n, _) => format!("T{n}"),
+            Type::Const(val) => self.expr(val)?,
+            Type::Int(int) => self.int_ty(*int)?,
+            Type::Char(int) => self.char_ty(*int)?,
+            Type::Void | Type::Never => "void".to_string(),
+            Type::Byte => "unsigned char".to_string(),
+        };
+
+        let qual = if is_const { "const " } else { "" };
+        if inner.is_empty() {
+            Ok(format!("{qual}{base}"))
+        } else {
+            Ok(format!("{qual}{base} {inner}"))
+        }
+    }
+
+    fn params(&self, sig: &Signature) -> std::io::Result<String> {
+        let params = sig
+            .params
+            .iter()
+            .map(|param| self.decl(&param.ty, param.name.as_deref().unwrap_or("")))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(params.join(", "))
+    }
+
+    fn expr(&self, expr: &Expr) -> std::io::Result<String> {
+        Ok(match expr {
+            Expr::IntLiteral(ty, val) => {
+                let lit = int_literal(*ty, *val, self.target);
+                let suffix = if ty.signed { "LL" } else { "ULL" };
+                match lit.strip_prefix('-') {
+                    Some(lit) => format!("(-{lit}{suffix})"),
+                    None => format!("{lit}{suffix}"),
+                }
+            }
+            Expr::UuidLiteral(uuid) => {
+                format!("{{ {:#x}ULL, {:#x}ULL }}", uuid.minor, uuid.major)
+            }
+            Expr::StringLiteral(s) => string_literal(s),
+            Expr::Const(name) => name.clone(),
+            Expr::BinOp(op, left, right) => {
+                let op = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::And => "&",
+                    BinaryOp::Or => "|",
+                    BinaryOp::Xor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
+                };
+                format!("({} {op} {})", self.expr(left)?, self.expr(right)?)
+            }
+            Expr::UnaryOp(UnaryOp::Not, inner) => format!("(~{})", self.expr(inner)?),
+            Expr::UnaryOp(UnaryOp::Neg, inner) => format!("(-{})", self.expr(inner)?),
+            Expr::SpecialConstant(SpecialConst::SizeofPointer) => "sizeof(void *)".to_string(),
+            Expr::SpecialConstant(SpecialConst::SizeofLong) => "sizeof(long)".to_string(),
+            Expr::SpecialConstant(special) => {
+                format!("{}ULL", special_value(self.bundle, self.target, *special)?)
+            }
+            Expr::Param(n) => format!("N{n}"),
+        })
+    }
+}
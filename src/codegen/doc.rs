@@ -0,0 +1,683 @@
+use core::fmt::Write as _;
+
+use crate::{
+    bundle::{Bundle, Path},
+    docs::{LinkStyle, doc_links, rewrite_links},
+    file::File,
+    target::TargetSpec,
+    tydef::{StructBody, TypeDef, TypeDefBody},
+    value::{Value, ValueBody},
+};
+
+use super::{
+    CodegenBackend, OutputSink, doc_strings, example_doc, field_doc, function_doc, niche_doc,
+    output_name, union_doc,
+};
+
+/// The format of the documentation generated by [`DocBackend`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DocFormat {
+    #[default]
+    Html,
+    Markdown,
+    /// Manual pages in section 3, written in `roff`
+    Man,
+}
+
+impl DocFormat {
+    fn ext(self) -> &'static str {
+        match self {
+            DocFormat::Html => "html",
+            DocFormat::Markdown => "md",
+            DocFormat::Man => "3",
+        }
+    }
+
+    fn link_style(self) -> Option<LinkStyle> {
+        match self {
+            DocFormat::Html => Some(LinkStyle::Html),
+            DocFormat::Markdown => Some(LinkStyle::Markdown),
+            DocFormat::Man => None,
+        }
+    }
+}
+
+/// Generates a page of documentation for each file in the bundle, and an `index` page listing every file.
+///
+/// Intra-doc links are rewritten to link to the page of the item they refer to (see [`rewrite_links`]).
+/// Manual pages can't link to each other, so links are replaced by their text.
+#[derive(Clone, Debug, Default)]
+pub struct DocBackend {
+    pub format: DocFormat,
+}
+
+/// An item (or member of an item) as shown on a page
+struct Entry {
+    name: String,
+    /// The declaration of the item, such as `struct Pair { ... }`
+    decl: String,
+    docs: Vec<String>,
+    members: Vec<Entry>,
+}
+
+impl CodegenBackend for DocBackend {
+    fn name(&self) -> &str {
+        "doc"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        _target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let mut index = Vec::new();
+        for (path, file) in bundle {
+            let mut writer = DocWriter {
+                bundle,
+                path,
+                format: self.format,
+                out: String::new(),
+            };
+            let docs = writer.docs(doc_strings(&file.attributes));
+            writer.write_page(&docs, &entries(file));
+            sink.write_output(&output_name(path, self.format.ext()), writer.out.as_bytes())?;
+
+            let summary = docs
+                .iter()
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string();
+            index.push((path.clone(), summary));
+        }
+
+        let mut writer = DocWriter {
+            bundle,
+            path: &Path(vec![]),
+            format: self.format,
+            out: String::new(),
+        };
+        writer.write_index(&index);
+        sink.write_output(
+            &format!("index.{}", self.format.ext()),
+            writer.out.as_bytes(),
+        )
+    }
+}
+
+fn type_entry(ty: &TypeDef) -> Entry {
+    let params = (0..ty.num_params)
+        .map(|n| format!("${n}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let name = if params.is_empty() {
+        ty.name.to_string()
+    } else {
+        format!("{}<{params}>", ty.name)
+    };
+
+    let mut members = Vec::new();
+    let (decl, docs) = match &ty.body {
+        TypeDefBody::Alias(alias) => (
+            format!("type {name} = {};", alias.alias),
+            niche_doc(&alias.attrs),
+        ),
+        TypeDefBody::Struct(st) => {
+            let decl = match &st.body {
+                StructBody::Fields(fields) => {
+                    let mut decl = format!("struct {name} {{\n");
+                    for field in &fields.field {
+                        let _ = writeln!(decl, "    {}: {},", field.name, field.ty);
+                        members.push(Entry {
+                            name: format!("{}::{}", ty.name, field.name),
+                            decl: format!("{}: {}", field.name, field.ty),
+                            docs: field_doc(field),
+                            members: Vec::new(),
+                        });
+                    }
+                    if let Some(pad) = &fields.pad {
+                        let _ = writeln!(decl, "    _pad: {pad},");
+                    }
+                    decl.push('}');
+                    decl
+                }
+                StructBody::Opaque(Some(repr)) => format!("opaque struct {name}: {repr};"),
+                StructBody::Opaque(None) => format!("opaque struct {name};"),
+            };
+            (decl, niche_doc(&st.attrs))
+        }
+        TypeDefBody::Union(un) => {
+            let mut decl = format!("union {name} {{\n");
+            for field in &un.fields.field {
+                let _ = writeln!(decl, "    {}: {},", field.name, field.ty);
+                members.push(Entry {
+                    name: format!("{}::{}", ty.name, field.name),
+                    decl: format!("{}: {}", field.name, field.ty),
+                    docs: field_doc(field),
+                    members: Vec::new(),
+                });
+            }
+            if let Some(pad) = &un.fields.pad {
+                let _ = writeln!(decl, "    _pad: {pad},");
+            }
+            decl.push('}');
+            (decl, union_doc(un))
+        }
+        TypeDefBody::Enum(en) => {
+            let mut decl = format!("enum {name}: {} {{\n", en.underlying);
+            for variant in &en.variants {
+                let _ = writeln!(decl, "    {} = {},", variant.name, variant.discrim);
+                members.push(Entry {
+                    name: format!("{}::{}", ty.name, variant.name),
+                    decl: format!("{} = {}", variant.name, variant.discrim),
+                    docs: doc_strings(&variant.attrs),
+                    members: Vec::new(),
+                });
+            }
+            decl.push('}');
+            (decl, doc_strings(&en.attrs))
+        }
+    };
+
+    let mut docs = docs;
+    docs.extend(match &ty.body {
+        TypeDefBody::Alias(alias) => example_doc(&alias.attrs),
+        TypeDefBody::Struct(st) => example_doc(&st.attrs),
+        TypeDefBody::Union(un) => example_doc(&un.attrs),
+        TypeDefBody::Enum(en) => example_doc(&en.attrs),
+    });
+
+    Entry {
+        name: ty.name.to_string(),
+        decl,
+        docs,
+        members,
+    }
+}
+
+fn value_entry(val: &Value) -> Entry {
+    let (decl, docs) = match &val.body {
+        ValueBody::Const(c) => {
+            let mut docs = doc_strings(&c.attrs);
+            docs.extend(example_doc(&c.attrs));
+            (format!("const {}: {} = {};", val.name, c.ty, c.val), docs)
+        }
+        ValueBody::Function(f) => {
+            // `Signature` displays as a function type, `fn(...) -> T`
+            let sig = f.signature.to_string();
+            let decl = format!("fn {}{};", val.name, sig.strip_prefix("fn").unwrap_or(&sig));
+            (decl, function_doc(f))
+        }
+    };
+    Entry {
+        name: val.name.to_string(),
+        decl,
+        docs,
+        members: Vec::new(),
+    }
+}
+
+/// The types and values of `file`, in order
+fn entries(file: &File) -> [(&'static str, Vec<Entry>); 2] {
+    [
+        ("Types", file.types.iter().map(type_entry).collect()),
+        ("Values", file.values.iter().map(value_entry).collect()),
+    ]
+}
+
+struct DocWriter<'a> {
+    bundle: &'a Bundle,
+    /// The module the page being written documents
+    path: &'a Path,
+    format: DocFormat,
+    out: String,
+}
+
+impl DocWriter<'_> {
+    fn line(&mut self, args: core::fmt::Arguments) {
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    /// Resolves the intra-doc links in `lines`, outside of code blocks
+    fn docs(&self, lines: Vec<String>) -> Vec<String> {
+        let mut in_code = false;
+        lines
+            .into_iter()
+            .map(|line| {
+                if line.trim_start().starts_with("```") {
+                    in_code = !in_code;
+                    return line;
+                }
+                if in_code {
+                    return line;
+                }
+                match self.format.link_style() {
+                    Some(style) => rewrite_links(self.bundle, self.path, &line, style),
+                    None => {
+                        let mut out = String::new();
+                        let mut pos = 0;
+                        for link in doc_links(&line) {
+                            out.push_str(&line[pos..link.range.start]);
+                            out.push_str(&link.text);
+                            pos = link.range.end;
+                        }
+                        out.push_str(&line[pos..]);
+                        out
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn write_page(&mut self, docs: &[String], sections: &[(&str, Vec<Entry>)]) {
+        let path = self.path;
+        let title = if path.0.is_empty() {
+            "(root)".to_string()
+        } else {
+            path.to_string()
+        };
+        match self.format {
+            DocFormat::Html => {
+                self.line(format_args!("<!DOCTYPE html>"));
+                self.line(format_args!(
+                    "<html><head><meta charset=\"utf-8\"><title>{}</title><style>{HIGHLIGHT_STYLE}</style></head><body>",
+                    html_escape(&title)
+                ));
+                self.line(format_args!(
+                    "<p><a href=\"{}index.html\">Index</a></p>",
+                    "../".repeat(path.0.len().saturating_sub(1))
+                ));
+                self.line(format_args!("<h1>Module {}</h1>", html_escape(&title)));
+                self.write_html_docs(docs);
+                for (heading, entries) in sections {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    self.line(format_args!("<h2>{heading}</h2>"));
+                    for entry in entries {
+                        self.write_html_entry(entry, 3);
+                    }
+                }
+                self.line(format_args!("</body></html>"));
+            }
+            DocFormat::Markdown => {
+                self.line(format_args!("# Module `{title}`"));
+                self.out.push('\n');
+                self.write_markdown_docs(docs);
+                for (heading, entries) in sections {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    self.line(format_args!("## {heading}"));
+                    self.out.push('\n');
+                    for entry in entries {
+                        self.write_markdown_entry(entry, 3);
+                    }
+                }
+            }
+            DocFormat::Man => {
+                let name = path.0.join("::");
+                self.line(format_args!(
+                    ".TH \"{}\" 3",
+                    roff_escape(&name.to_uppercase())
+                ));
+                self.line(format_args!(".SH NAME"));
+                self.line(format_args!("{}", roff_escape(&title)));
+                if !docs.is_empty() {
+                    self.line(format_args!(".SH DESCRIPTION"));
+                    self.write_roff_docs(docs);
+                }
+                for (heading, entries) in sections {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    self.line(format_args!(".SH {}", heading.to_uppercase()));
+                    for entry in entries {
+                        self.write_roff_entry(entry);
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_index(&mut self, modules: &[(Path, String)]) {
+        match self.format {
+            DocFormat::Html => {
+                self.line(format_args!("<!DOCTYPE html>"));
+                self.line(format_args!(
+                    "<html><head><meta charset=\"utf-8\"><title>Index</title></head><body>"
+                ));
+                self.line(format_args!("<h1>Index</h1>"));
+                self.line(format_args!("<ul>"));
+                for (path, summary) in modules {
+                    self.line(format_args!(
+                        "<li><a href=\"{}\">{}</a> {}</li>",
+                        output_name(path, "html"),
+                        html_escape(&path.to_string()),
+                        html_escape(summary)
+                    ));
+                }
+                self.line(format_args!("</ul>"));
+                self.line(format_args!("</body></html>"));
+            }
+            DocFormat::Markdown => {
+                self.line(format_args!("# Index"));
+                self.out.push('\n');
+                for (path, summary) in modules {
+                    let mut line = format!("- [`{path}`]({})", output_name(path, "md"));
+                    if !summary.is_empty() {
+                        let _ = write!(line, ": {summary}");
+                    }
+                    self.line(format_args!("{line}"));
+                }
+            }
+            DocFormat::Man => {
+                self.line(format_args!(".TH INDEX 3"));
+                self.line(format_args!(".SH NAME"));
+                self.line(format_args!("index \\- modules of the bundle"));
+                self.line(format_args!(".SH MODULES"));
+                for (path, summary) in modules {
+                    self.line(format_args!(".TP"));
+                    self.line(format_args!(".B {}", roff_escape(&path.to_string())));
+                    self.line(format_args!("{}", roff_escape(summary)));
+                }
+            }
+        }
+    }
+
+    fn write_markdown_docs(&mut self, docs: &[String]) {
+        if docs.is_empty() {
+            return;
+        }
+        let mut in_code = false;
+        for line in docs {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+            }
+            // Headings in documentation are nested below the heading of the item
+            match line.strip_prefix('#') {
+                Some(rest) if !in_code => self.line(format_args!("####{rest}")),
+                _ => self.line(format_args!("{line}")),
+            }
+        }
+        self.out.push('\n');
+    }
+
+    fn write_markdown_entry(&mut self, entry: &Entry, level: usize) {
+        self.line(format_args!("{} {}", "#".repeat(level), entry.name));
+        self.out.push('\n');
+        self.line(format_args!("```"));
+        self.line(format_args!("{}", entry.decl));
+        self.line(format_args!("```"));
+        self.out.push('\n');
+        self.write_markdown_docs(&entry.docs);
+        for member in &entry.members {
+            if member.docs.is_empty() {
+                continue;
+            }
+            let mut docs = member.docs.iter();
+            let first = docs.next().map(String::as_str).unwrap_or_default();
+            self.line(format_args!("- `{}`: {first}", member.decl));
+            for line in docs {
+                self.line(format_args!("  {line}"));
+            }
+        }
+        if entry.members.iter().any(|member| !member.docs.is_empty()) {
+            self.out.push('\n');
+        }
+    }
+
+    fn write_html_docs(&mut self, docs: &[String]) {
+        let mut paragraph = Vec::new();
+        let mut list = false;
+        let mut code: Option<Vec<&str>> = None;
+        for line in docs {
+            let trimmed = line.trim();
+            if let Some(info) = trimmed.strip_prefix("```") {
+                match code.take() {
+                    Some(lines) => {
+                        self.line(format_args!(
+                            "{}</code></pre>",
+                            html_escape(&lines.join("\n"))
+                        ));
+                    }
+                    None => {
+                        self.flush_paragraph(&mut paragraph, &mut list);
+                        let lang = info.split([',', ' ']).next().unwrap_or_default();
+                        if lang.is_empty() {
+                            self.out.push_str("<pre><code>");
+                        } else {
+                            let _ = write!(
+                                self.out,
+                                "<pre><code class=\"language-{}\">",
+                                html_escape(lang)
+                            );
+                        }
+                        code = Some(Vec::new());
+                    }
+                }
+                continue;
+            }
+            if let Some(lines) = &mut code {
+                lines.push(line);
+                continue;
+            }
+
+            if trimmed.is_empty() {
+                self.flush_paragraph(&mut paragraph, &mut list);
+            } else if let Some(heading) = trimmed.strip_prefix("# ") {
+                self.flush_paragraph(&mut paragraph, &mut list);
+                self.line(format_args!("<h4>{}</h4>", html_inline(heading)));
+            } else if let Some(item) = trimmed.strip_prefix("* ") {
+                if !paragraph.is_empty() {
+                    self.flush_paragraph(&mut paragraph, &mut list);
+                }
+                if !list {
+                    self.line(format_args!("<ul>"));
+                    list = true;
+                }
+                self.line(format_args!("<li>{}</li>", html_inline(item)));
+            } else if list && line.starts_with("  ") {
+                // A continuation of the previous list item
+                if self.out.ends_with("</li>\n") {
+                    self.out.truncate(self.out.len() - "</li>\n".len());
+                    self.line(format_args!(" {}</li>", html_inline(trimmed)));
+                }
+            } else {
+                if list {
+                    self.line(format_args!("</ul>"));
+                    list = false;
+                }
+                paragraph.push(trimmed);
+            }
+        }
+        if let Some(lines) = code {
+            self.line(format_args!(
+                "{}</code></pre>",
+                html_escape(&lines.join("\n"))
+            ));
+        }
+        self.flush_paragraph(&mut paragraph, &mut list);
+    }
+
+    fn flush_paragraph(&mut self, paragraph: &mut Vec<&str>, list: &mut bool) {
+        if *list {
+            self.line(format_args!("</ul>"));
+            *list = false;
+        }
+        if !paragraph.is_empty() {
+            self.line(format_args!("<p>{}</p>", html_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+
+    fn write_html_entry(&mut self, entry: &Entry, level: usize) {
+        self.line(format_args!(
+            "<h{level} id=\"{}\">{}</h{level}>",
+            html_escape(&entry.name),
+            html_escape(&entry.name)
+        ));
+        self.line(format_args!("<pre>{}</pre>", html_highlight(&entry.decl)));
+        self.write_html_docs(&entry.docs);
+        if entry.members.iter().any(|member| !member.docs.is_empty()) {
+            self.line(format_args!("<dl>"));
+            for member in &entry.members {
+                if member.docs.is_empty() {
+                    continue;
+                }
+                self.line(format_args!(
+                    "<dt id=\"{}\"><code>{}</code></dt>",
+                    html_escape(&member.name),
+                    html_escape(&member.decl)
+                ));
+                self.out.push_str("<dd>\n");
+                self.write_html_docs(&member.docs);
+                self.out.push_str("</dd>\n");
+            }
+            self.line(format_args!("</dl>"));
+        }
+    }
+
+    fn write_roff_docs(&mut self, docs: &[String]) {
+        let mut in_code = false;
+        for line in docs {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_code = !in_code;
+                self.line(format_args!("{}", if in_code { ".nf" } else { ".fi" }));
+                continue;
+            }
+            if in_code {
+                self.line(format_args!("{}", roff_escape(line)));
+            } else if trimmed.is_empty() {
+                self.line(format_args!(".PP"));
+            } else if let Some(heading) = trimmed.strip_prefix("# ") {
+                self.line(format_args!(".PP"));
+                self.line(format_args!(".B {}", roff_escape(heading)));
+                self.line(format_args!(".br"));
+            } else if let Some(item) = trimmed.strip_prefix("* ") {
+                self.line(format_args!(".IP \\(bu 2"));
+                self.line(format_args!("{}", roff_escape(item)));
+            } else {
+                self.line(format_args!("{}", roff_escape(trimmed)));
+            }
+        }
+        if in_code {
+            self.line(format_args!(".fi"));
+        }
+    }
+
+    fn write_roff_entry(&mut self, entry: &Entry) {
+        self.line(format_args!(".SS {}", roff_escape(&entry.name)));
+        self.line(format_args!(".nf"));
+        for line in entry.decl.lines() {
+            self.line(format_args!("{}", roff_escape(line)));
+        }
+        self.line(format_args!(".fi"));
+        self.write_roff_docs(&entry.docs);
+        for member in &entry.members {
+            if member.docs.is_empty() {
+                continue;
+            }
+            self.line(format_args!(".TP"));
+            self.line(format_args!(".B {}", roff_escape(&member.decl)));
+            self.write_roff_docs(&member.docs);
+        }
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The colors of the classes that [`html_highlight`] assigns to tokens
+const HIGHLIGHT_STYLE: &str = ".kw { color: #8959a8 } .ty { color: #3e999f } .lit { color: #718c00 } .param { color: #c82829 }";
+
+/// Escapes the declaration `decl`, and wraps its keywords, builtin types, generic parameters, and literals in `<span>`s
+/// with a class naming the kind of token, so that they are highlighted by [`HIGHLIGHT_STYLE`]
+#[cfg(feature = "text")]
+fn html_highlight(decl: &str) -> String {
+    use crate::text::lex::{TokenKind, tokenize};
+
+    let mut out = String::with_capacity(decl.len());
+    let mut pos = 0;
+    for token in tokenize(decl) {
+        out.push_str(&html_escape(&decl[pos..token.span.start]));
+        let text = html_escape(token.span.text(decl));
+        let class = match token.kind {
+            TokenKind::Keyword => Some("kw"),
+            TokenKind::BuiltinType => Some("ty"),
+            TokenKind::Int | TokenKind::Version | TokenKind::Uuid | TokenKind::Str => Some("lit"),
+            TokenKind::Param => Some("param"),
+            _ => None,
+        };
+        match class {
+            Some(class) => {
+                let _ = write!(out, "<span class=\"{class}\">{text}</span>");
+            }
+            None => out.push_str(&text),
+        }
+        pos = token.span.end;
+    }
+    out.push_str(&html_escape(&decl[pos..]));
+    out
+}
+
+/// Escapes the declaration `decl`. Highlighting it requires the lexer of the `text` feature
+#[cfg(not(feature = "text"))]
+fn html_highlight(decl: &str) -> String {
+    html_escape(decl)
+}
+
+/// Escapes `s`, and renders its inline code spans and links (`[text](url)`), which are all the inline Markdown the generators produce
+fn html_inline(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        if let Some(code) = rest.strip_prefix('`')
+            && let Some(end) = code.find('`')
+        {
+            let _ = write!(out, "<code>{}</code>", html_escape(&code[..end]));
+            rest = &code[end + 1..];
+        } else if let Some(link) = rest.strip_prefix('[')
+            && let Some(text_end) = link.find("](")
+            && let Some(url_end) = link[text_end..].find(')')
+        {
+            let text = &link[..text_end];
+            let url = &link[text_end + 2..text_end + url_end];
+            let _ = write!(
+                out,
+                "<a href=\"{}\">{}</a>",
+                html_escape(url),
+                html_inline(text)
+            );
+            rest = &link[text_end + url_end + 1..];
+        } else {
+            let c = rest.chars().next().unwrap();
+            out.push_str(&html_escape(c.encode_utf8(&mut [0; 4])));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+fn roff_escape(s: &str) -> String {
+    let s = s.replace('\\', "\\e");
+    if s.starts_with('.') || s.starts_with('\'') {
+        format!("\\&{s}")
+    } else {
+        s
+    }
+}
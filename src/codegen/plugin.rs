@@ -0,0 +1,138 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    process::{Command, Stdio},
+};
+
+use crate::{bundle::Bundle, config::format_config, target::TargetSpec};
+
+use super::{CodegenBackend, OutputSink};
+
+/// The magic number at the start of a request to a plugin
+pub const PLUGIN_MAGIC: [u8; 8] = *b"IMTPLUG\0";
+
+/// The version of the plugin protocol, which changes whenever a request or response changes incompatibly
+pub const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Generates code by running the program `program` with the arguments `args`, so that generators can be written in any language.
+///
+/// The program is run once per generation, and speaks the following protocol over the program's standard input and output.
+/// All integers are little-endian, and each string or blob is preceded by its length as a `u64`.
+///
+/// The request, written to standard input (which is then closed), is:
+/// 1. The magic number [`PLUGIN_MAGIC`], and the protocol version [`PLUGIN_PROTOCOL_VERSION`] as a `u32`
+/// 2. The target, as a spec file in the format of [`TargetSpec::write`]
+/// 3. The number of files in the bundle as a `u32`, then for each file:
+///    its path (the module names joined by `::`), and its contents, which are the file encoded as in an IMT file
+///
+/// The response, read from standard output until it is closed, is a sequence of outputs. Each output is its name (a `/`-separated relative path) and its contents.
+/// The program fails the generation by exiting with a non-zero status. Its standard error is passed through, for reporting errors.
+#[derive(Clone, Debug, Default)]
+pub struct PluginBackend {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+fn write_blob<W: Write>(w: &mut W, blob: &[u8]) -> std::io::Result<()> {
+    w.write_all(&(blob.len() as u64).to_le_bytes())?;
+    w.write_all(blob)
+}
+
+/// Reads a blob, or returns `None` at the end of the stream
+fn read_blob<R: Read>(r: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 8];
+    let mut filled = 0;
+    while filled < len.len() {
+        match r.read(&mut len[filled..])? {
+            0 if filled == 0 => return Ok(None),
+            0 => return Err(ErrorKind::UnexpectedEof.into()),
+            n => filled += n,
+        }
+    }
+    let mut blob = Vec::new();
+    r.take(u64::from_le_bytes(len)).read_to_end(&mut blob)?;
+    if blob.len() as u64 != u64::from_le_bytes(len) {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some(blob))
+}
+
+/// Encodes the request for generating code for `bundle` on `target`
+pub fn plugin_request(bundle: &Bundle, target: &TargetSpec) -> std::io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    request.extend_from_slice(&PLUGIN_MAGIC);
+    request.extend_from_slice(&PLUGIN_PROTOCOL_VERSION.to_le_bytes());
+
+    let mut spec = Vec::new();
+    target.write(&mut spec)?;
+    write_blob(&mut request, &spec)?;
+
+    let files = bundle.iter().collect::<Vec<_>>();
+    request.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    for (path, file) in files {
+        write_blob(&mut request, path.0.join("::").as_bytes())?;
+        let bytes = bincode::encode_to_vec(file, format_config())
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+        write_blob(&mut request, &bytes)?;
+    }
+    Ok(request)
+}
+
+impl CodegenBackend for PluginBackend {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let request = plugin_request(bundle, target)?;
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {e}", self.program)))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+
+        // The request is written from another thread, so that a plugin can produce output before it has read the whole request
+        let res = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || {
+                // A plugin may exit without reading the whole request, which is reported by its status instead
+                match stdin.write_all(&request) {
+                    Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+                    res => res,
+                }
+            });
+            let res = (|| {
+                while let Some(name) = read_blob(&mut stdout)? {
+                    let name = String::from_utf8(name)
+                        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+                    let contents = read_blob(&mut stdout)?.ok_or(ErrorKind::UnexpectedEof)?;
+                    if name.starts_with('/') || name.split('/').any(|part| part == "..") {
+                        return Err(std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("output {name} is not a relative path within the output"),
+                        ));
+                    }
+                    sink.write_output(&name, &contents)?;
+                }
+                Ok(())
+            })();
+            drop(stdout);
+            writer.join().unwrap().and(res)
+        });
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!(
+                "{} exited with status: {status}",
+                self.program
+            )));
+        }
+        res.map_err(|e| std::io::Error::new(e.kind(), format!("{}: {e}", self.program)))
+    }
+}
@@ -0,0 +1,1929 @@
+use core::fmt::Write as _;
+use std::collections::HashMap;
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::{
+    attr::{
+        Attribute, AttributeTarget, Target, find_attribute, find_attributes,
+        types::{
+            Align, Concurrency, Endian, ErrorReturn, ExportInline, IpcMethod, LengthOf, LinkName,
+            Niche, NulTerminated, ParamDirection, Reserved, SafetyHint, SinceField,
+            SpecialPointerRepr, SystemFunction, TargetCfg,
+        },
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    layout::{LayoutEngine, LayoutError},
+    option::{OptionKind, option_base, option_header},
+    resolve::{Item, module_of, resolve_name},
+    special::SpecialPointers,
+    tagged::tagged_unions,
+    target::TargetSpec,
+    tydef::{Field, Struct, StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{BinaryOp, Expr, IntBits, IntType, PointerKind, Signature, SpecialConst, Type, UnaryOp},
+    value::{Function, Value, ValueBody},
+};
+
+use super::{
+    CodegenBackend, OutputSink, derive_hints, doc_lines, field_doc, function_doc, int_literal,
+    must_use, niche_doc, output_name, special_value, union_doc, unsupported,
+    wire::{Access, ScalarKind, wire_layout},
+};
+
+/// How the modules generated by [`RustBackend`] are laid out
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum RustOutput {
+    /// Generates a separate `.rs` file for each path in the bundle
+    #[default]
+    ModulePerPath,
+    /// Generates a single file with the given name, nesting each path in an inline `mod`
+    SingleFile(String),
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Public,
+    Crate,
+}
+
+impl Visibility {
+    fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Public => "pub ",
+            Visibility::Crate => "pub(crate) ",
+        }
+    }
+}
+
+/// Generates Rust modules for the files in the bundle.
+///
+/// Items with [`TargetCfg`] attributes are gated by the corresponding `#[cfg]`.
+/// Functions with an [`IpcMethod`] attribute are not declared as symbols. Instead, each file with IPC methods has an `ipc` module,
+/// containing a `#[repr(C)]` request message struct for each method, a `Client` proxy that sends requests through a `Transport`,
+/// and a `dispatch` function that calls the methods of a `Service` implementation.
+/// Structs with a [`WireFormat`](crate::attr::types::WireFormat) attribute have `encode` and `decode` methods, converting them to and from their wire format.
+#[derive(Clone, Debug, Default)]
+pub struct RustBackend {
+    pub output: RustOutput,
+    /// The visibility of generated items and modules
+    pub visibility: Visibility,
+    /// Marks the crate root `#![no_std]`. Only takes effect when the crate root is generated,
+    /// by [`RustOutput::SingleFile`] or [`lib_skeleton`](Self::lib_skeleton)
+    pub no_std: bool,
+    /// Generates a `lib.rs` crate root and the `mod` declarations linking each generated file to its parent,
+    /// including for paths that only contain other modules
+    pub lib_skeleton: bool,
+    /// Emits compile-time assertions of the size, alignment, and field offsets of each type, as computed by the [`LayoutEngine`]
+    pub layout_assertions: bool,
+    /// Emits a `wrappers` module alongside the raw bindings, containing an idiomatic wrapper for each function.
+    /// Wrappers use the [`ParamDirection`], [`LengthOf`], [`NulTerminated`], [`ErrorReturn`], [`SafetyHint`], [`Concurrency`], and [`Reserved`] attributes.
+    pub safe_wrappers: bool,
+    /// Emits a `#[cfg(test)]` `conformance` module alongside the raw bindings, containing a skeleton test for each function with a [`SystemFunction`] attribute.
+    /// The arguments and checks of each test are left as `todo!()`s, to be filled in by hand
+    pub test_scaffold: bool,
+    /// Emits a `mock` module alongside the raw bindings, which defines the symbol of each function so that code using the bindings can be tested without a Lilium kernel.
+    /// `mock::set_<name>` sets the closure that implements the function `<name>` on the current thread, such as one returning a canned value.
+    /// Calling a function without an implementation on the current thread aborts. The `mock` module uses `std`, even if the crate is [`no_std`](Self::no_std)
+    pub mocks: bool,
+    /// Emits a view of each non-generic struct with [`SinceField`] fields for each of its earlier versions, containing the fields it had in that version,
+    /// and a method of the struct borrowing each view. The view of the struct before any fields were appended is named `<name>Base`,
+    /// and the view of version `<major>.<minor>` is named `<name>V<major>_<minor>`
+    pub versioned_views: bool,
+}
+
+impl CodegenBackend for RustBackend {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        // Every module path, including the parents of each file, mapped to the names of its child modules
+        let mut modules = IndexMap::<Path, IndexSet<String>>::new();
+        modules.insert(Path(vec![]), IndexSet::new());
+        for (path, _) in bundle {
+            for (n, name) in path.0.iter().enumerate() {
+                modules
+                    .entry(Path(path.0[..n].to_vec()))
+                    .or_default()
+                    .insert(name.clone());
+            }
+            modules.entry(path.clone()).or_default();
+        }
+
+        let root = Path(vec![]);
+        match &self.output {
+            RustOutput::ModulePerPath => {
+                for path in modules.keys() {
+                    let file = bundle.get(path);
+                    let is_root = path.0.is_empty() && self.lib_skeleton;
+                    if file.is_none() && !self.lib_skeleton {
+                        continue;
+                    }
+
+                    let mut writer = self.writer(bundle, target, &modules, path);
+                    if is_root {
+                        writer.crate_attrs();
+                    }
+                    writer.write_module(file, false)?;
+
+                    let name = if is_root {
+                        "lib.rs".to_string()
+                    } else {
+                        output_name(path, "rs")
+                    };
+                    sink.write_output(&name, writer.out.as_bytes())?;
+                }
+            }
+            RustOutput::SingleFile(name) => {
+                let mut writer = self.writer(bundle, target, &modules, &root);
+                writer.crate_attrs();
+                writer.write_module(bundle.get(&root), true)?;
+                sink.write_output(name, writer.out.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RustBackend {
+    fn writer<'a>(
+        &'a self,
+        bundle: &'a Bundle,
+        target: &'a TargetSpec,
+        modules: &'a IndexMap<Path, IndexSet<String>>,
+        path: &'a Path,
+    ) -> RustWriter<'a> {
+        RustWriter {
+            bundle,
+            target,
+            opts: self,
+            modules,
+            path,
+            layout: self
+                .layout_assertions
+                .then(|| LayoutEngine::new(bundle, target)),
+            specials: SpecialPointers::from_bundle(bundle),
+            vis: self.visibility.as_str(),
+            out: String::new(),
+            indent: 0,
+        }
+    }
+}
+
+struct RustWriter<'a> {
+    bundle: &'a Bundle,
+    target: &'a TargetSpec,
+    opts: &'a RustBackend,
+    modules: &'a IndexMap<Path, IndexSet<String>>,
+    path: &'a Path,
+    layout: Option<LayoutEngine<'a>>,
+    specials: SpecialPointers,
+    vis: &'static str,
+    out: String,
+    indent: usize,
+}
+
+fn cfgs<Targ: AttributeTarget>(attrs: &[Attribute<Targ>]) -> Vec<&str>
+where
+    TargetCfg: Target<Targ>,
+{
+    find_attributes::<TargetCfg, _>(attrs)
+        .map(|cfg| &*cfg.cfg)
+        .collect()
+}
+
+fn ipc_method(val: &Value) -> Option<(&IpcMethod, &Function)> {
+    match &val.body {
+        ValueBody::Function(f) => find_attribute::<IpcMethod, _>(&f.attrs).map(|ipc| (ipc, f)),
+        ValueBody::Const(_) => None,
+    }
+}
+
+/// The generated names and signature parts of an IPC method
+struct IpcStub<'a> {
+    name: &'a str,
+    method: String,
+    request: String,
+    /// The parameters after `&mut self`, including the leading comma
+    params: String,
+    ret: String,
+    retty: String,
+    fields: Vec<String>,
+    doc: Vec<String>,
+    cfgs: Vec<&'a str>,
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+fn typedef_cfgs(ty: &TypeDef) -> Vec<&str> {
+    match &ty.body {
+        TypeDefBody::Alias(alias) => cfgs(&alias.attrs),
+        TypeDefBody::Struct(st) => cfgs(&st.attrs),
+        TypeDefBody::Union(un) => cfgs(&un.attrs),
+        TypeDefBody::Enum(en) => cfgs(&en.attrs),
+    }
+}
+
+impl<'a> RustWriter<'a> {
+    fn line(&mut self, args: core::fmt::Arguments) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    fn doc<S: AsRef<str>>(&mut self, lines: &[S]) {
+        for line in lines {
+            let line = line.as_ref().trim_end();
+            if line.is_empty() {
+                self.line(format_args!("///"));
+            } else {
+                self.line(format_args!("/// {line}"));
+            }
+        }
+    }
+
+    fn cfg(&mut self, cfgs: &[&str]) {
+        for cfg in cfgs {
+            self.line(format_args!("#[cfg({cfg})]"));
+        }
+    }
+
+    fn crate_attrs(&mut self) {
+        if self.opts.no_std {
+            self.line(format_args!("#![no_std]"));
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes the contents of the module at `self.path`, followed by its child modules
+    fn write_module(&mut self, file: Option<&File>, inline: bool) -> std::io::Result<()> {
+        if let Some(file) = file {
+            self.write_file(file)?;
+        }
+
+        let path = self.path;
+        let vis = self.vis;
+        let modules = self.modules;
+        if !inline && !self.opts.lib_skeleton {
+            return Ok(());
+        }
+        if file.is_some() && !modules[path].is_empty() {
+            self.out.push('\n');
+        }
+        for child in &modules[path] {
+            if inline {
+                let (child_path, _) = modules.get_key_value(&path.join(child)).unwrap();
+                self.line(format_args!("{vis}mod {child} {{"));
+                self.indent += 1;
+                self.path = child_path;
+                self.write_module(self.bundle.get(child_path), true)?;
+                self.path = path;
+                self.indent -= 1;
+                self.line(format_args!("}}"));
+            } else {
+                self.line(format_args!("{vis}mod {child};"));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_file(&mut self, file: &File) -> std::io::Result<()> {
+        self.doc_inner(&file.attributes);
+        for cfg in cfgs(&file.attributes) {
+            self.line(format_args!("#![cfg({cfg})]"));
+        }
+
+        for use_item in &file.uses {
+            self.cfg(&cfgs(&use_item.attrs));
+            let vis = if find_attribute::<ExportInline, _>(&use_item.attrs).is_some() {
+                self.vis
+            } else {
+                ""
+            };
+            let path = Path(use_item.path.clone());
+            if self.bundle.get(&path).is_some() {
+                self.line(format_args!("{vis}use crate::{path}::*;"));
+            } else {
+                self.line(format_args!("{vis}use crate::{path};"));
+            }
+        }
+
+        for ty in &file.types {
+            self.out.push('\n');
+            self.write_typedef(ty)?;
+            self.write_layout_assertions(ty);
+            self.write_option_helpers(ty)?;
+            self.write_tagged_accessors(ty)?;
+            self.write_wire_format(ty)?;
+            if self.opts.versioned_views {
+                self.write_versioned_views(ty)?;
+            }
+        }
+
+        // IPC methods are called through the `ipc` module instead of a symbol
+        for val in file.values.iter().filter(|val| ipc_method(val).is_none()) {
+            self.out.push('\n');
+            self.write_value(val)?;
+        }
+
+        if self.opts.safe_wrappers {
+            self.write_wrappers(file)?;
+        }
+
+        if self.opts.test_scaffold {
+            self.write_test_scaffold(file)?;
+        }
+
+        if self.opts.mocks {
+            self.write_mocks(file)?;
+        }
+
+        self.write_ipc(file)?;
+
+        Ok(())
+    }
+
+    fn doc_inner(&mut self, attrs: &[Attribute<File>]) {
+        for line in doc_lines(attrs) {
+            self.line(format_args!("//! {}", line.trim_end()));
+        }
+    }
+
+    /// The generic parameters of a type, given whether each is a constant parameter
+    fn generics(const_params: &[bool]) -> String {
+        if const_params.is_empty() {
+            return String::new();
+        }
+
+        let params = const_params
+            .iter()
+            .enumerate()
+            .map(|(n, is_const)| match is_const {
+                true => format!("const N{n}: usize"),
+                false => format!("T{n}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("<{params}>")
+    }
+
+    fn write_typedef(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let name = &ty.name;
+        let const_params = ty.const_params();
+        let generics = Self::generics(&const_params);
+        let vis = self.vis;
+        let ty_cfgs = typedef_cfgs(ty);
+
+        match &ty.body {
+            TypeDefBody::Alias(alias) => {
+                self.doc(&niche_doc(&alias.attrs));
+                self.cfg(&ty_cfgs);
+                let aliased =
+                    self.niche_ty(&alias.alias, find_attribute::<Niche, _>(&alias.attrs))?;
+                self.line(format_args!("{vis}type {name}{generics} = {aliased};"));
+            }
+            TypeDefBody::Struct(st) => {
+                self.doc(&niche_doc(&st.attrs));
+                self.cfg(&ty_cfgs);
+                let align = find_attribute::<Align, _>(&st.attrs);
+                let derives = derive_hints(&st.attrs);
+                match &st.body {
+                    StructBody::Fields(fields) => {
+                        self.repr_c(align.map(|a| a.alignment));
+                        self.derive(&[], &derives);
+                        self.line(format_args!("{vis}struct {name}{generics} {{"));
+                        self.write_fields(fields, &const_params)?;
+                        self.line(format_args!("}}"));
+                    }
+                    StructBody::Opaque(Some(repr)) => {
+                        let repr = self.ty(repr)?;
+                        self.repr_c(align.map(|a| a.alignment));
+                        self.derive(&[], &derives);
+                        self.line(format_args!("{vis}struct {name}{generics}({repr});"));
+                    }
+                    StructBody::Opaque(None) => {
+                        self.repr_c(align.map(|a| a.alignment));
+                        self.derive(&[], &derives);
+                        self.line(format_args!("{vis}struct {name}{generics} {{"));
+                        self.indent += 1;
+                        self.line(format_args!("_opaque: [u8; 0],"));
+                        self.phantom(&const_params);
+                        self.indent -= 1;
+                        self.line(format_args!("}}"));
+                    }
+                }
+            }
+            TypeDefBody::Union(un) => {
+                self.doc(&union_doc(un));
+                self.cfg(&ty_cfgs);
+                self.repr_c(find_attribute::<Align, _>(&un.attrs).map(|a| a.alignment));
+                self.line(format_args!("{vis}union {name}{generics} {{"));
+                self.write_fields(&un.fields, &const_params)?;
+                self.line(format_args!("}}"));
+            }
+            TypeDefBody::Enum(en) => {
+                self.doc(doc_lines(&en.attrs));
+                self.cfg(&ty_cfgs);
+                let underlying = self.int_ty(en.underlying)?;
+                self.line(format_args!("#[repr(transparent)]"));
+                self.derive(
+                    &["Copy", "Clone", "Debug", "Hash", "PartialEq", "Eq"],
+                    &derive_hints(&en.attrs),
+                );
+                self.line(format_args!("{vis}struct {name}({vis}{underlying});"));
+                self.out.push('\n');
+                self.cfg(&ty_cfgs);
+                self.line(format_args!("impl {name} {{"));
+                self.indent += 1;
+                for variant in &en.variants {
+                    self.doc(doc_lines(&variant.attrs));
+                    self.cfg(&cfgs(&variant.attrs));
+                    let discrim = self.expr(&variant.discrim)?;
+                    self.line(format_args!(
+                        "{vis}const {}: {name} = {name}({discrim});",
+                        variant.name
+                    ));
+                }
+                self.indent -= 1;
+                self.line(format_args!("}}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn repr_c(&mut self, align: Option<u128>) {
+        match align {
+            Some(align) => self.line(format_args!("#[repr(C, align({align}))]")),
+            None => self.line(format_args!("#[repr(C)]")),
+        }
+    }
+
+    /// Derives the traits of `base`, followed by those of `hints` that are not already in `base`
+    fn derive(&mut self, base: &[&str], hints: &[&str]) {
+        let traits = base
+            .iter()
+            .chain(hints.iter().filter(|name| !base.contains(name)))
+            .copied()
+            .collect::<Vec<_>>();
+        if !traits.is_empty() {
+            self.line(format_args!("#[derive({})]", traits.join(", ")));
+        }
+    }
+
+    /// Marks the type parameters of a type as used. Constant parameters are used by the array lengths that refer to them
+    fn phantom(&mut self, const_params: &[bool]) {
+        let params = const_params
+            .iter()
+            .enumerate()
+            .filter(|(_, is_const)| !**is_const)
+            .map(|(n, _)| format!("T{n},"))
+            .collect::<String>();
+        if !params.is_empty() {
+            self.line(format_args!(
+                "_phantom: core::marker::PhantomData<({params})>,"
+            ));
+        }
+    }
+
+    fn write_fields(
+        &mut self,
+        fields: &StructFields,
+        const_params: &[bool],
+    ) -> std::io::Result<()> {
+        self.indent += 1;
+        for field in &fields.field {
+            self.doc(&field_doc(field));
+            self.cfg(&cfgs(&field.attrs));
+            let ty = self.niche_ty(&field.ty, find_attribute::<Niche, _>(&field.attrs))?;
+            let vis = self.vis;
+            self.line(format_args!("{vis}{}: {ty},", field.name));
+        }
+        if let Some(pad) = &fields.pad {
+            let ty = self.ty(pad)?;
+            self.line(format_args!("_pad: {ty},"));
+        }
+        self.phantom(const_params);
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn write_layout_assertions(&mut self, ty: &TypeDef) {
+        let Some(engine) = &mut self.layout else {
+            return;
+        };
+        if ty.num_params != 0 {
+            return;
+        }
+
+        let name = &ty.name;
+        let ty_cfgs = typedef_cfgs(ty);
+        let fields = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => Some(fields),
+                _ => None,
+            },
+            TypeDefBody::Union(un) => Some(&un.fields),
+            _ => None,
+        };
+        if fields.is_some_and(|fields| {
+            fields
+                .field
+                .iter()
+                .any(|field| !cfgs(&field.attrs).is_empty())
+        }) {
+            self.line(format_args!(
+                "// no layout assertions for {name}: its fields depend on the target configuration"
+            ));
+            return;
+        }
+
+        let layout = match engine.layout_of_def(self.path, ty) {
+            Ok(layout) => layout,
+            Err(LayoutError::Unsized(_)) => return,
+            Err(e) => {
+                self.line(format_args!("// no layout assertions for {name}: {e}"));
+                return;
+            }
+        };
+        let offsets = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => engine
+                    .field_layouts(self.path, fields)
+                    .and_then(|mut offsets| {
+                        offsets.extend(engine.pad_layout(self.path, fields)?);
+                        Ok(offsets)
+                    })
+                    .ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        self.cfg(&ty_cfgs);
+        self.line(format_args!(
+            "const _: () = assert!(core::mem::size_of::<{name}>() == {});",
+            layout.size
+        ));
+        self.cfg(&ty_cfgs);
+        self.line(format_args!(
+            "const _: () = assert!(core::mem::align_of::<{name}>() == {});",
+            layout.align
+        ));
+        for field in offsets.into_iter().flatten() {
+            self.cfg(&ty_cfgs);
+            self.line(format_args!(
+                "const _: () = assert!(core::mem::offset_of!({name}, {}) == {});",
+                field.name, field.offset
+            ));
+        }
+    }
+
+    /// Emits the views of the earlier versions of a struct with [`SinceField`] fields, and the methods borrowing them
+    fn write_versioned_views(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let TypeDefBody::Struct(st) = &ty.body else {
+            return Ok(());
+        };
+        let StructBody::Fields(fields) = &st.body else {
+            return Ok(());
+        };
+        if ty.num_params != 0 {
+            return Ok(());
+        }
+        let since =
+            |field: &Field| find_attribute::<SinceField, _>(&field.attrs).map(|s| s.version);
+        let mut versions = fields.field.iter().filter_map(since).collect::<Vec<_>>();
+        versions.sort();
+        versions.dedup();
+        // The struct itself is the newest version
+        let Some(&first) = versions.first() else {
+            return Ok(());
+        };
+        versions.pop();
+
+        let name = &ty.name;
+        // The casts to the views are only sound if each view is a prefix of the struct
+        let ordered =
+            fields
+                .field
+                .windows(2)
+                .all(|pair| match (since(&pair[0]), since(&pair[1])) {
+                    (Some(_), None) => false,
+                    (Some(a), Some(b)) => a <= b,
+                    (None, _) => true,
+                });
+        if !ordered {
+            return Err(unsupported(format!(
+                "{name} has no versioned views: its SinceField fields are out of order"
+            )));
+        }
+        let vis = self.vis;
+        let ty_cfgs = typedef_cfgs(ty);
+        let align = find_attribute::<Align, _>(&st.attrs).map(|a| a.alignment);
+        let derives = derive_hints(&st.attrs);
+        let views = core::iter::once((
+            None,
+            "Base".to_string(),
+            format!("as first defined, before the fields added in {first}"),
+        ))
+        .chain(versions.into_iter().map(|version| {
+            (
+                Some(version),
+                format!("V{}_{}", version.major(), version.minor()),
+                format!("as of version {version}"),
+            )
+        }))
+        .collect::<Vec<_>>();
+
+        for (version, suffix, desc) in &views {
+            let view = StructFields {
+                field: fields
+                    .field
+                    .iter()
+                    .filter(|field| match (since(field), version) {
+                        (None, _) => true,
+                        (Some(since), Some(version)) => since <= *version,
+                        (Some(_), None) => false,
+                    })
+                    .cloned()
+                    .collect(),
+                pad: None,
+            };
+            self.out.push('\n');
+            self.line(format_args!("/// [`{name}`] {desc}"));
+            self.cfg(&ty_cfgs);
+            self.repr_c(align);
+            self.derive(&[], &derives);
+            self.line(format_args!("{vis}struct {name}{suffix} {{"));
+            self.write_fields(&view, &[])?;
+            self.line(format_args!("}}"));
+        }
+
+        self.out.push('\n');
+        self.cfg(&ty_cfgs);
+        self.line(format_args!("impl {name} {{"));
+        self.indent += 1;
+        for (n, (_, suffix, desc)) in views.iter().enumerate() {
+            if n != 0 {
+                self.out.push('\n');
+            }
+            self.line(format_args!(
+                "/// Borrows the fields of `self` that [`{name}`] had {desc}"
+            ));
+            self.line(format_args!(
+                "{vis}fn as_{}(&self) -> &{name}{suffix} {{",
+                suffix.to_lowercase()
+            ));
+            self.line(format_args!(
+                "    // SAFETY: the fields of {name}{suffix} are a prefix of the fields of {name}, and {name} is at least as aligned"
+            ));
+            self.line(format_args!(
+                "    unsafe {{ &*core::ptr::from_ref(self).cast() }}"
+            ));
+            self.line(format_args!("}}"));
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    /// Emits the encoder and decoder of a struct with a [`WireFormat`] attribute
+    fn write_wire_format(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let mut engine = LayoutEngine::new(self.bundle, self.target);
+        let Some(wire) = wire_layout(&mut engine, self.bundle, self.path, ty)? else {
+            return Ok(());
+        };
+
+        let name = &ty.name;
+        let vis = self.vis;
+        let size = wire.size;
+        self.out.push('\n');
+        self.cfg(&typedef_cfgs(ty));
+        self.line(format_args!("impl {name} {{"));
+        self.indent += 1;
+        self.line(format_args!("/// The size of the encoding of [`{name}`]"));
+        self.line(format_args!("{vis}const WIRE_SIZE: usize = {size};"));
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Encodes `self` with the layout of its fields, and each padding byte zeroed"
+        ));
+        self.line(format_args!(
+            "{vis}fn encode(&self) -> [u8; Self::WIRE_SIZE] {{"
+        ));
+        self.indent += 1;
+        self.line(format_args!("let mut buf = [0; Self::WIRE_SIZE];"));
+        for scalar in &wire.scalars {
+            let range = format!("{}..{}", scalar.offset, scalar.offset + scalar.size);
+            let field = Self::wire_access(&scalar.access);
+            let bytes = Self::wire_bytes(scalar.endian);
+            match scalar.kind {
+                ScalarKind::Bytes => {
+                    self.line(format_args!("buf[{range}].copy_from_slice(&self{field});"));
+                }
+                ScalarKind::Char(IntType::u32) => self.line(format_args!(
+                    "buf[{range}].copy_from_slice(&u32::from(self{field}).to_{bytes}_bytes());"
+                )),
+                ScalarKind::Int(_) | ScalarKind::Char(_) => self.line(format_args!(
+                    "buf[{range}].copy_from_slice(&self{field}.to_{bytes}_bytes());"
+                )),
+            }
+        }
+        self.line(format_args!("buf"));
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Decodes a value encoded by [`encode`](Self::encode)."
+        ));
+        self.line(format_args!(
+            "/// Returns `None` if a reserved field that must be zero is not zero"
+        ));
+        self.line(format_args!(
+            "{vis}fn decode(buf: &[u8; Self::WIRE_SIZE]) -> Option<Self> {{"
+        ));
+        self.indent += 1;
+        for reserved in wire
+            .reserved
+            .iter()
+            .filter(|reserved| reserved.must_be_zero)
+        {
+            let range = format!("{}..{}", reserved.offset, reserved.offset + reserved.size);
+            self.line(format_args!(
+                "if buf[{range}].iter().any(|&byte| byte != 0) {{"
+            ));
+            self.line(format_args!("    return None;"));
+            self.line(format_args!("}}"));
+        }
+        self.line(format_args!(
+            "// SAFETY: every field of a struct with a wire format is an integer, or made of integers"
+        ));
+        self.line(format_args!(
+            "let mut val: Self = unsafe {{ core::mem::zeroed() }};"
+        ));
+        for scalar in &wire.scalars {
+            let range = format!("{}..{}", scalar.offset, scalar.offset + scalar.size);
+            let field = Self::wire_access(&scalar.access);
+            let bytes = Self::wire_bytes(scalar.endian);
+            match scalar.kind {
+                ScalarKind::Bytes => {
+                    self.line(format_args!("val{field}.copy_from_slice(&buf[{range}]);"));
+                }
+                ScalarKind::Char(IntType::u32) => self.line(format_args!(
+                    "val{field} = char::from_u32(u32::from_{bytes}_bytes(buf[{range}].try_into().unwrap())).unwrap_or(char::REPLACEMENT_CHARACTER);"
+                )),
+                ScalarKind::Int(int) | ScalarKind::Char(int) => {
+                    let ty = self.int_ty(int)?;
+                    self.line(format_args!(
+                        "val{field} = {ty}::from_{bytes}_bytes(buf[{range}].try_into().unwrap());"
+                    ));
+                }
+            }
+        }
+        self.line(format_args!("Some(val)"));
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    fn wire_access(access: &[Access]) -> String {
+        let mut res = String::new();
+        for step in access {
+            match step {
+                Access::Field(name) => {
+                    let _ = write!(res, ".{name}");
+                }
+                Access::Index(n) => {
+                    let _ = write!(res, "[{n}]");
+                }
+                Access::Enum => res.push_str(".0"),
+            }
+        }
+        res
+    }
+
+    fn wire_bytes(endian: Endian) -> &'static str {
+        match endian {
+            Endian::Little => "le",
+            Endian::Big => "be",
+            Endian::Native => "ne",
+        }
+    }
+
+    /// Emits the id of an option struct, and the helpers for matching options against a polymorphic option type
+    fn write_option_helpers(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let TypeDefBody::Struct(st) = &ty.body else {
+            return Ok(());
+        };
+        let Some(kind) = OptionKind::of(st) else {
+            return Ok(());
+        };
+        let Ok(header) = option_header(self.bundle, self.path, st) else {
+            return Ok(());
+        };
+        if ty.num_params != 0 {
+            return Ok(());
+        }
+
+        let name = &ty.name;
+        let vis = self.vis;
+        let id_ty = self.ty(&header.id.ty)?;
+        let id_field = &header.id.name;
+
+        self.out.push('\n');
+        self.cfg(&typedef_cfgs(ty));
+        self.line(format_args!("impl {name} {{"));
+        self.indent += 1;
+        match kind {
+            OptionKind::Option(id) => {
+                self.line(format_args!(
+                    "/// The id of this option, stored in its header"
+                ));
+                self.line(format_args!(
+                    "{vis}const OPTION_ID: {id_ty} = lilium_sys::uuid::parse_uuid(\"{id}\");"
+                ));
+
+                let base = option_base(st).and_then(|base| match base {
+                    Type::Named(base, None) => {
+                        let resolved = resolve_name(self.bundle, self.path, base).ok()?;
+                        let Item::Type(TypeDef {
+                            body: TypeDefBody::Struct(base_st),
+                            ..
+                        }) = resolved.item
+                        else {
+                            return None;
+                        };
+                        let header =
+                            option_header(self.bundle, &module_of(&resolved.path), base_st).ok()?;
+                        Some((base, &header.field.name))
+                    }
+                    _ => None,
+                });
+                if let Some((base, base_head)) = base {
+                    for (qual, ptr) in [("", "ref"), ("mut ", "mut")] {
+                        let suffix = if qual.is_empty() { "" } else { "_mut" };
+                        self.out.push('\n');
+                        self.line(format_args!(
+                            "/// Returns `opt` as a [`{name}`] if it holds this option"
+                        ));
+                        self.line(format_args!(
+                            "{vis}fn from_base{suffix}(opt: &{qual}{base}) -> Option<&{qual}Self> {{"
+                        ));
+                        self.indent += 1;
+                        self.line(format_args!(
+                            "const {{ assert!(core::mem::size_of::<Self>() <= core::mem::size_of::<{base}>() && core::mem::align_of::<Self>() <= core::mem::align_of::<{base}>()) }}"
+                        ));
+                        self.line(format_args!(
+                            "(opt.{base_head}.{id_field} == Self::OPTION_ID).then(|| unsafe {{ &{qual}*core::ptr::from_{ptr}(opt).cast::<Self>() }})"
+                        ));
+                        self.indent -= 1;
+                        self.line(format_args!("}}"));
+                    }
+                }
+            }
+            OptionKind::Polymorphic => {
+                let head = &header.field.name;
+                self.line(format_args!("/// The id of the option held by this value"));
+                self.line(format_args!("{vis}fn option_id(&self) -> {id_ty} {{"));
+                self.line(format_args!("    self.{head}.{id_field}"));
+                self.line(format_args!("}}"));
+            }
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    /// Emits accessors for the members of `DiscriminatedBy` unions held by a struct, which check the tag field
+    fn write_tagged_accessors(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let TypeDefBody::Struct(Struct {
+            body: StructBody::Fields(fields),
+            ..
+        }) = &ty.body
+        else {
+            return Ok(());
+        };
+        if ty.num_params != 0 {
+            return Ok(());
+        }
+
+        let mut accessors = Vec::new();
+        for tagged in tagged_unions(self.bundle, self.path, fields)
+            .into_iter()
+            .flatten()
+        {
+            let tag_ty = self.ty(&tagged.tag.ty)?;
+            for (member, variant) in tagged.members() {
+                if !tagged.has_variant(variant) {
+                    continue;
+                }
+                let member_ty =
+                    self.niche_ty(&member.ty, find_attribute::<Niche, _>(&member.attrs))?;
+                accessors.push((
+                    &tagged.field.name,
+                    &tagged.tag.name,
+                    tag_ty.clone(),
+                    member,
+                    variant,
+                    member_ty,
+                ));
+            }
+        }
+        if accessors.is_empty() {
+            return Ok(());
+        }
+
+        let name = &ty.name;
+        let vis = self.vis;
+        self.out.push('\n');
+        self.cfg(&typedef_cfgs(ty));
+        self.line(format_args!("impl {name} {{"));
+        self.indent += 1;
+        let mut first = true;
+        for (field, tag, tag_ty, member, variant, member_ty) in accessors {
+            let member_name = &member.name;
+            for qual in ["", "mut "] {
+                let suffix = if qual.is_empty() { "" } else { "_mut" };
+                let self_qual = if qual.is_empty() {
+                    "&self"
+                } else {
+                    "&mut self"
+                };
+                if !first {
+                    self.out.push('\n');
+                }
+                first = false;
+                self.line(format_args!(
+                    "/// Returns the `{member_name}` member of `{field}` if `{tag}` is `{variant}`"
+                ));
+                self.cfg(&cfgs(&member.attrs));
+                self.line(format_args!(
+                    "{vis}fn {field}_{member_name}{suffix}({self_qual}) -> Option<&{qual}{member_ty}> {{"
+                ));
+                self.line(format_args!(
+                    "    (self.{tag} == {tag_ty}::{variant}).then(|| unsafe {{ &{qual}self.{field}.{member_name} }})"
+                ));
+                self.line(format_args!("}}"));
+            }
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    fn write_value(&mut self, val: &Value) -> std::io::Result<()> {
+        match &val.body {
+            ValueBody::Const(c) => {
+                self.doc(doc_lines(&c.attrs));
+                self.cfg(&cfgs(&c.attrs));
+                let ty = self.ty(&c.ty)?;
+                let expr = match (&c.ty, &c.val) {
+                    (Type::Pointer(_, _), Expr::StringLiteral(s)) => {
+                        format!("{:?}.as_ptr().cast()", format!("{s}\0"))
+                    }
+                    _ => self.expr(&c.val)?,
+                };
+                let vis = self.vis;
+                self.line(format_args!("{vis}const {}: {ty} = {expr};", val.name));
+            }
+            ValueBody::Function(f) => {
+                let (params, ret) = self.signature_parts(&f.signature, true)?;
+                self.line(format_args!("unsafe extern \"C\" {{"));
+                self.indent += 1;
+                self.doc(&function_doc(f));
+                self.cfg(&cfgs(&f.attrs));
+                self.must_use_attr(f, false);
+                if let Some(link) = find_attribute::<LinkName, _>(&f.attrs) {
+                    self.line(format_args!("#[link_name = {:?}]", link.name));
+                }
+                let vis = self.vis;
+                self.line(format_args!("{vis}fn {}({params}){ret};", val.name));
+                self.indent -= 1;
+                self.line(format_args!("}}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_wrappers(&mut self, file: &File) -> std::io::Result<()> {
+        let funcs = file
+            .values
+            .iter()
+            .filter_map(|val| match &val.body {
+                ValueBody::Function(f) if ipc_method(val).is_none() => Some((&val.name, f)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if funcs.is_empty() {
+            return Ok(());
+        }
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Safe wrappers around the functions of this module"
+        ));
+        let vis = self.vis;
+        self.line(format_args!("{vis}mod wrappers {{"));
+        self.indent += 1;
+        self.line(format_args!("use super::*;"));
+        for (name, f) in funcs {
+            self.out.push('\n');
+            self.write_wrapper(name, f)?;
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    fn write_test_scaffold(&mut self, file: &File) -> std::io::Result<()> {
+        let sysfns = file
+            .values
+            .iter()
+            .filter_map(|val| match &val.body {
+                ValueBody::Function(f) => find_attribute::<SystemFunction, _>(&f.attrs)
+                    .map(|sysfn| (&val.name, f, sysfn.function_id)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if sysfns.is_empty() {
+            return Ok(());
+        }
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Conformance tests of the system functions of this module"
+        ));
+        self.line(format_args!("#[cfg(test)]"));
+        // Each argument is a `todo!()` until the test is written
+        self.line(format_args!("#[allow(unreachable_code, unused_variables)]"));
+        self.line(format_args!("mod conformance {{"));
+        self.indent += 1;
+        self.line(format_args!("use super::*;"));
+        for (name, f, id) in sysfns {
+            self.out.push('\n');
+            self.line(format_args!(
+                "/// Tests [`{name}`](super::{name}) (system function {id})."
+            ));
+            let doc = doc_lines(&f.attrs);
+            if !doc.is_empty() {
+                self.line(format_args!("///"));
+                self.doc(doc);
+            }
+            self.cfg(&cfgs(&f.attrs));
+            self.line(format_args!("#[test]"));
+            self.line(format_args!("fn {name}() {{"));
+            self.indent += 1;
+
+            let mut args = Vec::new();
+            for (n, param) in f.signature.params.iter().enumerate() {
+                let arg = param
+                    .name
+                    .as_ref()
+                    .map_or_else(|| format!("_{n}"), ToString::to_string);
+                let ty = self.ty(&param.ty)?;
+                self.line(format_args!("let {arg}: {ty} = todo!(\"{arg}\");"));
+                args.push(arg);
+            }
+            let call = format!("unsafe {{ super::{name}({}) }}", args.join(", "));
+            match &*f.signature.retty {
+                Type::Never => {
+                    self.line(format_args!(
+                        "// TODO: check the effects of {name}, which does not return"
+                    ));
+                    self.line(format_args!("{call};"));
+                }
+                Type::Void => {
+                    self.line(format_args!("{call};"));
+                    self.line(format_args!("todo!(\"check the effects of {name}\")"));
+                }
+                ty => {
+                    let ty = self.ty(ty)?;
+                    self.line(format_args!("let result: {ty} = {call};"));
+                    self.line(format_args!("todo!(\"check the result of {name}\")"));
+                }
+            }
+
+            self.indent -= 1;
+            self.line(format_args!("}}"));
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    fn write_mocks(&mut self, file: &File) -> std::io::Result<()> {
+        let funcs = file
+            .values
+            .iter()
+            .filter_map(|val| match &val.body {
+                ValueBody::Function(f) if ipc_method(val).is_none() => Some((&val.name, f)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if funcs.is_empty() {
+            return Ok(());
+        }
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Mock implementations of the functions of this module"
+        ));
+        let vis = self.vis;
+        self.line(format_args!("{vis}mod mock {{"));
+        self.indent += 1;
+        self.line(format_args!("extern crate std;"));
+        self.out.push('\n');
+        self.line(format_args!("use std::{{boxed::Box, cell::RefCell}};"));
+        self.out.push('\n');
+        self.line(format_args!("use super::*;"));
+        for (name, f) in funcs {
+            let sig = &f.signature;
+            let (params, ret) = self.signature_parts(sig, true)?;
+            let (param_tys, _) = self.signature_parts(sig, false)?;
+            let args = sig
+                .params
+                .iter()
+                .enumerate()
+                .map(|(n, param)| {
+                    param
+                        .name
+                        .as_ref()
+                        .map_or_else(|| format!("_{n}"), ToString::to_string)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            // A function that does not return is mocked by a closure that does, after which the mock aborts
+            let closure_ret = if matches!(*sig.retty, Type::Never) {
+                ""
+            } else {
+                &ret
+            };
+            let cfgs = cfgs(&f.attrs);
+            let handler = format!("MOCK_{}", name.to_uppercase());
+
+            self.out.push('\n');
+            self.cfg(&cfgs);
+            self.line(format_args!("std::thread_local! {{"));
+            self.line(format_args!(
+                "    static {handler}: RefCell<Option<Box<dyn FnMut({param_tys}){closure_ret}>>> = const {{ RefCell::new(None) }};"
+            ));
+            self.line(format_args!("}}"));
+
+            self.out.push('\n');
+            self.line(format_args!(
+                "/// Sets the implementation of [`{name}`] on the current thread"
+            ));
+            self.cfg(&cfgs);
+            self.line(format_args!(
+                "{vis}fn set_{name}(f: impl FnMut({param_tys}){closure_ret} + 'static) {{"
+            ));
+            self.line(format_args!(
+                "    {handler}.with(|mock| *mock.borrow_mut() = Some(Box::new(f)));"
+            ));
+            self.line(format_args!("}}"));
+
+            self.out.push('\n');
+            self.cfg(&cfgs);
+            match find_attribute::<LinkName, _>(&f.attrs) {
+                Some(link) => self.line(format_args!("#[unsafe(export_name = {:?})]", link.name)),
+                None => self.line(format_args!("#[unsafe(no_mangle)]")),
+            }
+            self.line(format_args!(
+                "{vis}unsafe extern \"C\" fn {name}({params}){ret} {{"
+            ));
+            self.indent += 1;
+            self.line(format_args!(
+                "{handler}.with(|mock| match &mut *mock.borrow_mut() {{"
+            ));
+            self.line(format_args!("    Some(f) => f({args}),"));
+            self.line(format_args!(
+                "    None => panic!(\"{name} was called without a mock implementation\"),"
+            ));
+            if matches!(*sig.retty, Type::Never) {
+                self.line(format_args!("}});"));
+                self.line(format_args!("panic!(\"{name} returned\")"));
+            } else {
+                self.line(format_args!("}})"));
+            }
+            self.indent -= 1;
+            self.line(format_args!("}}"));
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    /// Emits the client proxy and the server dispatch of the IPC methods of the file.
+    /// The request of each method is a `#[repr(C)]` struct of its parameters, and the response is its return value
+    fn write_ipc(&mut self, file: &File) -> std::io::Result<()> {
+        let methods = file
+            .values
+            .iter()
+            .filter_map(|val| ipc_method(val).map(|(ipc, f)| (&val.name, ipc.method_id, f)))
+            .collect::<Vec<_>>();
+
+        if methods.is_empty() {
+            return Ok(());
+        }
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Client proxies and server dispatch for the IPC methods of this module"
+        ));
+        let vis = self.vis;
+        self.line(format_args!("{vis}mod ipc {{"));
+        self.indent += 1;
+        self.line(format_args!("use super::*;"));
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Sends the request messages of a [`Client`] to the service, and receives its responses"
+        ));
+        self.line(format_args!("///"));
+        self.line(format_args!("/// # Safety"));
+        self.line(format_args!(
+            "/// [`call`](Transport::call) must fill `response` with the response of the service to `request`, which must be a valid value of the return type of `method`"
+        ));
+        self.line(format_args!("{vis}unsafe trait Transport {{"));
+        self.line(format_args!(
+            "    fn call(&mut self, method: u32, request: &[u8], response: &mut [u8]);"
+        ));
+        self.line(format_args!("}}"));
+        self.out.push('\n');
+        self.line(format_args!("/// The error returned by [`dispatch`]"));
+        self.line(format_args!("#[derive(Copy, Clone, Debug, PartialEq, Eq)]"));
+        self.line(format_args!("{vis}enum DispatchError {{"));
+        self.line(format_args!(
+            "    /// The method is not a method of this module"
+        ));
+        self.line(format_args!("    UnknownMethod(u32),"));
+        self.line(format_args!(
+            "    /// The request is not the size of the request message of the method"
+        ));
+        self.line(format_args!(
+            "    BadRequest {{ expected: usize, actual: usize }},"
+        ));
+        self.line(format_args!(
+            "    /// The response buffer is too small for the response of the method"
+        ));
+        self.line(format_args!(
+            "    BadResponse {{ expected: usize, actual: usize }},"
+        ));
+        self.line(format_args!("}}"));
+
+        let mut stubs = Vec::new();
+        for (name, id, f) in methods {
+            let sig = &f.signature;
+            if matches!(*sig.retty, Type::Never) {
+                return Err(unsupported(format!(
+                    "IPC method {name} does not return a response"
+                )));
+            }
+            let (params, ret) = self.signature_parts(sig, true)?;
+            let stub = IpcStub {
+                name,
+                method: format!("{}_METHOD", name.to_uppercase()),
+                request: format!("{}Request", camel_case(name)),
+                params: if params.is_empty() {
+                    params
+                } else {
+                    format!(", {params}")
+                },
+                ret,
+                retty: match &*sig.retty {
+                    Type::Void => "()".to_string(),
+                    ty => self.ty(ty)?,
+                },
+                fields: sig
+                    .params
+                    .iter()
+                    .enumerate()
+                    .map(|(n, param)| {
+                        param
+                            .name
+                            .as_ref()
+                            .map_or_else(|| format!("_{n}"), ToString::to_string)
+                    })
+                    .collect(),
+                doc: function_doc(f),
+                cfgs: cfgs(&f.attrs),
+            };
+
+            self.out.push('\n');
+            self.cfg(&stub.cfgs);
+            self.line(format_args!("{vis}const {}: u32 = {id};", stub.method));
+            self.out.push('\n');
+            self.line(format_args!(
+                "/// The request message of [`Service::{name}`]"
+            ));
+            self.cfg(&stub.cfgs);
+            self.line(format_args!("#[repr(C)]"));
+            if stub.fields.is_empty() {
+                self.line(format_args!("{vis}struct {} {{}}", stub.request));
+            } else {
+                self.line(format_args!("{vis}struct {} {{", stub.request));
+                for (field, param) in stub.fields.iter().zip(&sig.params) {
+                    let ty = self.ty(&param.ty)?;
+                    self.line(format_args!("    {vis}{field}: {ty},"));
+                }
+                self.line(format_args!("}}"));
+            }
+            self.write_request_assertion(&stub.request, sig, &stub.cfgs);
+            stubs.push(stub);
+        }
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Calls the IPC methods of this module by sending requests through a [`Transport`]"
+        ));
+        self.line(format_args!("{vis}struct Client<T>({vis}T);"));
+        self.out.push('\n');
+        self.line(format_args!("impl<T: Transport> Client<T> {{"));
+        self.indent += 1;
+        for (n, stub) in stubs.iter().enumerate() {
+            let IpcStub {
+                name,
+                method,
+                request,
+                params,
+                ret,
+                retty,
+                fields,
+                ..
+            } = stub;
+            if n != 0 {
+                self.out.push('\n');
+            }
+            self.doc(&stub.doc);
+            self.cfg(&stub.cfgs);
+            self.line(format_args!("{vis}fn {name}(&mut self{params}){ret} {{"));
+            self.indent += 1;
+            // Fields are written in place so that the padding of the request is zeroed
+            let qual = if fields.is_empty() { "" } else { "mut " };
+            self.line(format_args!(
+                "let {qual}request = core::mem::MaybeUninit::<{request}>::zeroed();"
+            ));
+            if !fields.is_empty() {
+                self.line(format_args!("let ptr = request.as_mut_ptr();"));
+                self.line(format_args!("unsafe {{"));
+                for field in fields {
+                    self.line(format_args!(
+                        "    (&raw mut (*ptr).{field}).write({field});"
+                    ));
+                }
+                self.line(format_args!("}}"));
+            }
+            self.line(format_args!(
+                "let mut response = core::mem::MaybeUninit::<{retty}>::uninit();"
+            ));
+            self.line(format_args!("unsafe {{"));
+            self.line(format_args!("    self.0.call("));
+            self.line(format_args!("        {method},"));
+            self.line(format_args!(
+                "        core::slice::from_raw_parts(request.as_ptr().cast(), size_of::<{request}>()),"
+            ));
+            self.line(format_args!(
+                "        core::slice::from_raw_parts_mut(response.as_mut_ptr().cast(), size_of::<{retty}>()),"
+            ));
+            self.line(format_args!("    );"));
+            self.line(format_args!("    response.assume_init()"));
+            self.line(format_args!("}}"));
+            self.indent -= 1;
+            self.line(format_args!("}}"));
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// The server side of the IPC methods of this module, called by [`dispatch`]"
+        ));
+        self.line(format_args!("{vis}trait Service {{"));
+        self.indent += 1;
+        for (n, stub) in stubs.iter().enumerate() {
+            if n != 0 {
+                self.out.push('\n');
+            }
+            self.doc(&stub.doc);
+            self.cfg(&stub.cfgs);
+            self.line(format_args!(
+                "fn {}(&mut self{}){};",
+                stub.name, stub.params, stub.ret
+            ));
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+
+        self.out.push('\n');
+        self.line(format_args!(
+            "/// Decodes `request` as the request message of `method`, calls the method of `service`, and writes its response to the start of `response`."
+        ));
+        self.line(format_args!("/// Returns the size of the response"));
+        self.line(format_args!("///"));
+        self.line(format_args!("/// # Safety"));
+        self.line(format_args!(
+            "/// `request` must contain a valid request message of `method`"
+        ));
+        self.line(format_args!(
+            "{vis}unsafe fn dispatch<S: Service + ?Sized>(service: &mut S, method: u32, request: &[u8], response: &mut [u8]) -> Result<usize, DispatchError> {{"
+        ));
+        self.indent += 1;
+        self.line(format_args!("match method {{"));
+        self.indent += 1;
+        for stub in &stubs {
+            let IpcStub {
+                name,
+                method,
+                request,
+                retty,
+                fields,
+                ..
+            } = stub;
+            let args = fields.join(", ");
+            self.cfg(&stub.cfgs);
+            self.line(format_args!("{method} => {{"));
+            self.indent += 1;
+            self.line(format_args!(
+                "if request.len() != size_of::<{request}>() {{"
+            ));
+            self.line(format_args!(
+                "    return Err(DispatchError::BadRequest {{ expected: size_of::<{request}>(), actual: request.len() }});"
+            ));
+            self.line(format_args!("}}"));
+            self.line(format_args!("if response.len() < size_of::<{retty}>() {{"));
+            self.line(format_args!(
+                "    return Err(DispatchError::BadResponse {{ expected: size_of::<{retty}>(), actual: response.len() }});"
+            ));
+            self.line(format_args!("}}"));
+            if !fields.is_empty() {
+                self.line(format_args!(
+                    "let {request} {{ {args} }} = unsafe {{ request.as_ptr().cast::<{request}>().read_unaligned() }};"
+                ));
+            }
+            self.line(format_args!("let res = service.{name}({args});"));
+            self.line(format_args!(
+                "unsafe {{ response.as_mut_ptr().cast::<{retty}>().write_unaligned(res) }};"
+            ));
+            self.line(format_args!("Ok(size_of::<{retty}>())"));
+            self.indent -= 1;
+            self.line(format_args!("}}"));
+        }
+        self.line(format_args!(
+            "_ => Err(DispatchError::UnknownMethod(method)),"
+        ));
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    /// Asserts the size of the request message of an IPC method, as computed by the [`LayoutEngine`] for the parameters laid out as the fields of a struct
+    fn write_request_assertion(&mut self, request: &str, sig: &Signature, cfgs: &[&str]) {
+        let Some(engine) = &mut self.layout else {
+            return;
+        };
+
+        let mut size = 0u128;
+        let mut align = 1;
+        for param in &sig.params {
+            let layout = match engine.layout_of(self.path, &param.ty) {
+                Ok(layout) => layout,
+                Err(e) => {
+                    self.line(format_args!("// no layout assertions for {request}: {e}"));
+                    return;
+                }
+            };
+            size = size.next_multiple_of(layout.align) + layout.size;
+            align = align.max(layout.align);
+        }
+        let size = size.next_multiple_of(align);
+
+        self.cfg(cfgs);
+        self.line(format_args!(
+            "const _: () = assert!(core::mem::size_of::<{request}>() == {size});"
+        ));
+    }
+
+    fn write_wrapper(&mut self, name: &str, f: &Function) -> std::io::Result<()> {
+        let sig = &f.signature;
+        let names = sig
+            .params
+            .iter()
+            .enumerate()
+            .map(|(n, param)| {
+                param
+                    .name
+                    .as_ref()
+                    .map_or_else(|| format!("_{n}"), ToString::to_string)
+            })
+            .collect::<Vec<_>>();
+
+        // Maps each pointer parameter that has a length parameter to that length parameter, and vice versa
+        let mut slices = HashMap::new();
+        let mut lengths = HashMap::new();
+        for (param, len_name) in sig.params.iter().zip(&names) {
+            if let Some(len) = find_attribute::<LengthOf, _>(&param.attrs) {
+                let target = sig
+                    .params
+                    .iter()
+                    .zip(&names)
+                    .find(|(_, name)| **name == len.param);
+                if let Some((target, name)) = target
+                    && matches!(
+                        target.ty,
+                        Type::Pointer(PointerKind::Const | PointerKind::Mut, _)
+                    )
+                {
+                    slices.insert(name.as_str(), len_name.as_str());
+                    lengths.insert(len_name.as_str(), name.as_str());
+                }
+            }
+        }
+
+        let mut args = Vec::new();
+        let mut call = Vec::new();
+        let mut setup = Vec::new();
+        let mut values = Vec::new();
+        let mut outs = Vec::new();
+        let mut checks = Vec::new();
+        let mut safe = true;
+
+        for (param, name) in sig.params.iter().zip(&names) {
+            let dir = find_attribute::<ParamDirection, _>(&param.attrs)
+                .copied()
+                .unwrap_or_default();
+            let nul_terminated = find_attribute::<NulTerminated, _>(&param.attrs).is_some();
+
+            match &param.ty {
+                _ if lengths.contains_key(name.as_str()) => {
+                    call.push(format!("{}.len() as _", lengths[name.as_str()]));
+                }
+                Type::Pointer(PointerKind::Const, pointee)
+                    if slices.contains_key(name.as_str()) =>
+                {
+                    args.push(format!("{name}: &[{}]", self.ty(pointee)?));
+                    call.push(format!("{name}.as_ptr()"));
+                }
+                Type::Pointer(PointerKind::Mut, pointee) if slices.contains_key(name.as_str()) => {
+                    args.push(format!("{name}: &mut [{}]", self.ty(pointee)?));
+                    call.push(format!("{name}.as_mut_ptr()"));
+                }
+                Type::Pointer(PointerKind::Const, _) if nul_terminated => {
+                    args.push(format!("{name}: &core::ffi::CStr"));
+                    call.push(format!("{name}.as_ptr().cast()"));
+                }
+                Type::Pointer(_, pointee) if matches!(**pointee, Type::Void | Type::Func(_)) => {
+                    safe &= matches!(**pointee, Type::Func(_));
+                    args.push(format!("{name}: {}", self.ty(&param.ty)?));
+                    call.push(name.clone());
+                }
+                Type::Pointer(PointerKind::Mut, pointee) if dir == ParamDirection::Out => {
+                    let ty = self.ty(pointee)?;
+                    setup.push(format!(
+                        "let mut {name} = core::mem::MaybeUninit::<{ty}>::uninit();"
+                    ));
+                    call.push(format!("{name}.as_mut_ptr()"));
+                    outs.push((ty, format!("unsafe {{ {name}.assume_init() }}")));
+                    for (field, field_ty, must_be_zero) in self.reserved_fields(pointee) {
+                        if must_be_zero {
+                            let field_ty = self.ty(field_ty)?;
+                            checks.push(format!(
+                                "debug_assert!(unsafe {{ {name}.assume_init_ref() }}.{field} == unsafe {{ core::mem::zeroed::<{field_ty}>() }}, \"reserved field {field} of {name} is not zero\");"
+                            ));
+                        }
+                    }
+                }
+                Type::Pointer(PointerKind::Const, pointee) => {
+                    args.push(format!("{name}: Option<&{}>", self.ty(pointee)?));
+                    call.push(format!(
+                        "{name}.map_or(core::ptr::null(), core::ptr::from_ref)"
+                    ));
+                }
+                Type::Pointer(PointerKind::Mut, pointee) => {
+                    let reserved = self.reserved_fields(pointee);
+                    let qual = if reserved.is_empty() { "" } else { "mut " };
+                    args.push(format!("{qual}{name}: Option<&mut {}>", self.ty(pointee)?));
+                    if !reserved.is_empty() {
+                        setup.push(format!("if let Some({name}) = {name}.as_deref_mut() {{"));
+                        for (field, _, _) in reserved {
+                            setup.push(format!(
+                                "    {name}.{field} = unsafe {{ core::mem::zeroed() }};"
+                            ));
+                        }
+                        setup.push("}".to_string());
+                    }
+                    call.push(format!(
+                        "{name}.map_or(core::ptr::null_mut(), core::ptr::from_mut)"
+                    ));
+                }
+                ty => {
+                    safe &= !matches!(ty, Type::Pointer(_, _));
+                    let reserved = self.reserved_fields(ty);
+                    let qual = if reserved.is_empty() { "" } else { "mut " };
+                    args.push(format!("{qual}{name}: {}", self.ty(ty)?));
+                    for (field, _, _) in reserved {
+                        setup.push(format!(
+                            "{name}.{field} = unsafe {{ core::mem::zeroed() }};"
+                        ));
+                    }
+                    call.push(name.clone());
+                }
+            }
+        }
+
+        let error = match &*sig.retty {
+            Type::Int(_) => find_attribute::<ErrorReturn, _>(&f.attrs),
+            _ => None,
+        };
+        match &*sig.retty {
+            Type::Void | Type::Never => {}
+            ty => values.push((self.ty(ty)?, "ret".to_string())),
+        }
+        values.extend(outs);
+
+        let (ok_ty, ok_expr) = match &*values {
+            [] => ("()".to_string(), "()".to_string()),
+            [(ty, expr)] => (ty.clone(), expr.clone()),
+            values => {
+                let (tys, exprs): (Vec<_>, Vec<_>) = values.iter().cloned().unzip();
+                (
+                    format!("({})", tys.join(", ")),
+                    format!("({})", exprs.join(", ")),
+                )
+            }
+        };
+        let ret_ty = match (&*sig.retty, error) {
+            (Type::Never, _) => " -> !".to_string(),
+            (_, Some(error)) => format!(" -> Result<{ok_ty}, {}>", self.ty(&error.error)?),
+            (_, None) if ok_ty == "()" => String::new(),
+            (_, None) => format!(" -> {ok_ty}"),
+        };
+
+        let thread_unsafe =
+            find_attribute::<Concurrency, _>(&f.attrs) == Some(&Concurrency::ThreadUnsafe);
+        let is_unsafe = match find_attribute::<SafetyHint, _>(&f.attrs)
+            .copied()
+            .unwrap_or_default()
+        {
+            SafetyHint::Safe => false,
+            SafetyHint::Unsafe => true,
+            SafetyHint::NoHint => !safe || thread_unsafe,
+        };
+
+        self.doc(&function_doc(f));
+        if is_unsafe {
+            self.line(format_args!("/// # Safety"));
+            self.line(format_args!(
+                "/// The caller must uphold the requirements of [`super::{name}`]"
+            ));
+            if thread_unsafe {
+                self.line(format_args!(
+                    "/// and ensure that no other call to it is in progress on any thread"
+                ));
+            }
+        }
+        self.cfg(&cfgs(&f.attrs));
+        self.must_use_attr(f, error.is_some());
+        let qual = if is_unsafe { "unsafe " } else { "" };
+        let vis = self.vis;
+        self.line(format_args!(
+            "{vis}{qual}fn {name}({}){ret_ty} {{",
+            args.join(", ")
+        ));
+        self.indent += 1;
+        for line in setup {
+            self.line(format_args!("{line}"));
+        }
+        let call = format!("unsafe {{ super::{name}({}) }}", call.join(", "));
+        match &*sig.retty {
+            Type::Never => self.line(format_args!("{call}")),
+            _ if error.is_none() && ok_expr == "ret" => self.line(format_args!("{call}")),
+            Type::Void => self.line(format_args!("{call};")),
+            _ => self.line(format_args!("let ret = {call};")),
+        }
+        if let Some(error) = error {
+            let error = self.ty(&error.error)?;
+            self.line(format_args!("if ret < 0 {{"));
+            self.line(format_args!("    return Err({error}(ret as _));"));
+            self.line(format_args!("}}"));
+            for check in checks {
+                self.line(format_args!("{check}"));
+            }
+            self.line(format_args!("Ok({ok_expr})"));
+        } else if !matches!(*sig.retty, Type::Never) && !matches!(&*ok_expr, "()" | "ret") {
+            for check in checks {
+                self.line(format_args!("{check}"));
+            }
+            self.line(format_args!("{ok_expr}"));
+        }
+        self.indent -= 1;
+        self.line(format_args!("}}"));
+        Ok(())
+    }
+
+    /// The name and type of each field with a [`Reserved`] attribute of the struct named by `ty`, and whether it must be zero.
+    /// Explicit padding is included as a reserved field named `_pad`, which need not be zero
+    fn reserved_fields(&self, ty: &Type) -> Vec<(&'a str, &'a Type, bool)> {
+        let Type::Named(name, None) = ty else {
+            return Vec::new();
+        };
+        let Ok(resolved) = resolve_name(self.bundle, self.path, name) else {
+            return Vec::new();
+        };
+        let Item::Type(TypeDef {
+            body:
+                TypeDefBody::Struct(Struct {
+                    body: StructBody::Fields(fields),
+                    ..
+                }),
+            ..
+        }) = resolved.item
+        else {
+            return Vec::new();
+        };
+        fields
+            .field
+            .iter()
+            .filter_map(|field| {
+                find_attribute::<Reserved, _>(&field.attrs)
+                    .map(|reserved| (&*field.name, &field.ty, reserved.must_be_zero))
+            })
+            .chain(fields.pad.iter().map(|pad| ("_pad", pad, false)))
+            .collect()
+    }
+
+    /// Emits `#[must_use]` for functions with a `MustUse` attribute.
+    /// Functions that already return a `#[must_use]` type only get the attribute if it has a reason
+    fn must_use_attr(&mut self, f: &Function, returns_must_use: bool) {
+        let Some(attr) = must_use(f) else {
+            return;
+        };
+        match &attr.reason {
+            _ if matches!(*f.signature.retty, Type::Void | Type::Never) => {}
+            Some(reason) => self.line(format_args!("#[must_use = {reason:?}]")),
+            None if returns_must_use => {}
+            None => self.line(format_args!("#[must_use]")),
+        }
+    }
+
+    fn signature_parts(&self, sig: &Signature, named: bool) -> std::io::Result<(String, String)> {
+        let mut params = Vec::new();
+        for (n, param) in sig.params.iter().enumerate() {
+            let ty = self.ty(&param.ty)?;
+            if named {
+                match &param.name {
+                    Some(name) => params.push(format!("{name}: {ty}")),
+                    None => params.push(format!("_{n}: {ty}")),
+                }
+            } else {
+                params.push(ty);
+            }
+        }
+
+        let ret = match &*sig.retty {
+            Type::Void => String::new(),
+            ty => format!(" -> {}", self.ty(ty)?),
+        };
+
+        Ok((params.join(", "), ret))
+    }
+
+    fn int_ty(&self, ty: IntType) -> std::io::Result<String> {
+        let sign = if ty.signed { 'i' } else { 'u' };
+        match ty.bits {
+            IntBits::Long if ty.signed => Ok("core::ffi::c_long".to_string()),
+            IntBits::Long => Ok("core::ffi::c_ulong".to_string()),
+            IntBits::Bits(bits) if matches!(bits.get(), 8 | 16 | 32 | 64 | 128) => {
+                Ok(format!("{sign}{bits}"))
+            }
+            IntBits::Bits(bits) => Err(unsupported(format!("{sign}{bits} has no Rust equivalent"))),
+        }
+    }
+
+    fn char_ty(&self, ty: IntType) -> std::io::Result<String> {
+        match ty {
+            IntType::u8 => Ok("u8".to_string()),
+            IntType::u16 => Ok("u16".to_string()),
+            IntType::u32 => Ok("char".to_string()),
+            ty => Err(unsupported(format!(
+                "char type {ty} has no Rust equivalent"
+            ))),
+        }
+    }
+
+    /// The Rust type of a field or type alias, which excludes zero if `niche` reserves it
+    fn niche_ty(&self, ty: &Type, niche: Option<&Niche>) -> std::io::Result<String> {
+        if niche != Some(&Niche::Zero) {
+            return self.ty(ty);
+        }
+        Ok(match ty {
+            Type::Int(int) => format!("core::num::NonZero<{}>", self.int_ty(*int)?),
+            Type::Pointer(_, pointee) if matches!(**pointee, Type::Func(_)) => self.ty(pointee)?,
+            Type::Pointer(kind, pointee) => match self.specials.repr(*kind) {
+                SpecialPointerRepr::Address => "core::num::NonZero<usize>".to_string(),
+                _ => format!("core::ptr::NonNull<{}>", self.ty(pointee)?),
+            },
+            ty => self.ty(ty)?,
+        })
+    }
+
+    fn ty(&self, ty: &Type) -> std::io::Result<String> {
+        Ok(match ty {
+            Type::Named(name, None) => name.to_string(),
+            Type::Named(name, Some(args)) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.ty(arg))
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                format!("{name}<{}>", args.join(", "))
+            }
+            Type::Param(n, _) => format!("T{n}"),
+            Type::Int(int) => self.int_ty(*int)?,
+            Type::Char(int) => self.char_ty(*int)?,
+            Type::Pointer(_, pointee) if matches!(**pointee, Type::Func(_)) => {
+                format!("Option<{}>", self.ty(pointee)?)
+            }
+            Type::Pointer(kind, pointee) => match self.specials.repr(*kind) {
+                SpecialPointerRepr::Const => format!("*const {}", self.ty(pointee)?),
+                SpecialPointerRepr::Mut => format!("*mut {}", self.ty(pointee)?),
+                SpecialPointerRepr::Address => "usize".to_string(),
+            },
+            Type::Func(sig) => {
+                let (params, ret) = self.signature_parts(sig, false)?;
+                format!("unsafe extern \"C\" fn({params}){ret}")
+            }
+            Type::Void => "()".to_string(),
+            Type::Never => "!".to_string(),
+            Type::Byte => "u8".to_string(),
+            Type::Array(arr) => format!("[{}; {}]", self.ty(&arr.base)?, self.expr(&arr.len)?),
+            Type::Uninit(ty) => format!("core::mem::MaybeUninit<{}>", self.ty(ty)?),
+            Type::Const(val) => format!("{{ {} }}", self.expr(val)?),
+        })
+    }
+
+    fn expr(&self, expr: &Expr) -> std::io::Result<String> {
+        Ok(match expr {
+            Expr::IntLiteral(ty, val) => int_literal(*ty, *val, self.target),
+            Expr::UuidLiteral(uuid) => {
+                format!("lilium_sys::uuid::parse_uuid(\"{uuid}\")")
+            }
+            Expr::StringLiteral(s) => format!("{s:?}"),
+            Expr::Const(name) => name.clone(),
+            Expr::BinOp(op, left, right) => {
+                let op = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::And => "&",
+                    BinaryOp::Or => "|",
+                    BinaryOp::Xor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
+                };
+                format!("({} {op} {})", self.expr(left)?, self.expr(right)?)
+            }
+            Expr::UnaryOp(UnaryOp::Not, inner) => format!("!{}", self.expr(inner)?),
+            Expr::UnaryOp(UnaryOp::Neg, inner) => format!("-{}", self.expr(inner)?),
+            Expr::SpecialConstant(SpecialConst::SizeofPointer) => {
+                "core::mem::size_of::<*const ()>()".to_string()
+            }
+            Expr::SpecialConstant(SpecialConst::SizeofLong) => {
+                "core::mem::size_of::<core::ffi::c_long>()".to_string()
+            }
+            Expr::SpecialConstant(special) => {
+                format!(
+                    "{}usize",
+                    special_value(self.bundle, self.target, *special)?
+                )
+            }
+            Expr::Param(n) => format!("N{n}"),
+        })
+    }
+}
@@ -0,0 +1,92 @@
+use core::fmt::Write as _;
+use std::collections::BTreeMap;
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{IntroducedIn, IpcMethod, LinkName, SubsystemDescriptor},
+    },
+    bundle::Bundle,
+    header::Version,
+    target::TargetSpec,
+    value::ValueBody,
+};
+
+use super::{CodegenBackend, OutputSink};
+
+/// Generates a GNU ld version script, `symbols.map`, that exports the function symbols of the bundle.
+///
+/// Each file with functions has a version node for each version of its [`SubsystemDescriptor`] that added a function, named like `BASE_IO_1.2` for the file `base::io`.
+/// A function is added in the version of its [`IntroducedIn`] attribute, or in the first minor version of the subsystem's major version if it has none,
+/// and is exported by the symbol of its [`LinkName`] attribute, or by its name. Each node inherits the previous node of the same file.
+/// Functions with an [`IpcMethod`] attribute have no symbol, and aren't exported.
+#[derive(Clone, Debug, Default)]
+pub struct VersionScriptBackend;
+
+impl CodegenBackend for VersionScriptBackend {
+    fn name(&self) -> &str {
+        "version-script"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        _target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let mut out = String::new();
+        let mut local = true;
+        for (path, file) in bundle {
+            let base = find_attribute::<SubsystemDescriptor, _>(&file.attributes)
+                .map(|desc| Version::new(desc.version.major(), 0))
+                .unwrap_or_default();
+
+            let mut versions = BTreeMap::<Version, Vec<&str>>::new();
+            for val in &file.values {
+                let ValueBody::Function(f) = &val.body else {
+                    continue;
+                };
+                if find_attribute::<IpcMethod, _>(&f.attrs).is_some() {
+                    continue;
+                }
+                let version = find_attribute::<IntroducedIn, _>(&f.attrs)
+                    .map_or(base, |introduced| introduced.version);
+                let symbol =
+                    find_attribute::<LinkName, _>(&f.attrs).map_or(&*val.name, |link| &*link.name);
+                versions.entry(version).or_default().push(symbol);
+            }
+
+            let prefix = if path.0.is_empty() {
+                "ROOT".to_string()
+            } else {
+                path.0.join("_").to_uppercase()
+            };
+            let mut prev = None;
+            for (version, symbols) in versions {
+                let node = format!("{prefix}_{version}");
+                let _ = writeln!(out, "{node} {{");
+                let _ = writeln!(out, "    global:");
+                for symbol in symbols {
+                    let _ = writeln!(out, "        {symbol};");
+                }
+                // Only one node may hide the symbols that aren't exported
+                if local {
+                    let _ = writeln!(out, "    local:");
+                    let _ = writeln!(out, "        *;");
+                    local = false;
+                }
+                match &prev {
+                    Some(prev) => {
+                        let _ = writeln!(out, "}} {prev};");
+                    }
+                    None => {
+                        let _ = writeln!(out, "}};");
+                    }
+                }
+                out.push('\n');
+                prev = Some(node);
+            }
+        }
+        sink.write_output("symbols.map", out.as_bytes())
+    }
+}
@@ -0,0 +1,478 @@
+use std::{collections::BTreeMap, io::ErrorKind};
+
+use minijinja::{Environment, Value};
+
+use crate::{
+    attr::{
+        Attribute, AttributeTarget, attribute_info, find_attribute,
+        types::{ParamDoc, ReturnDoc, SpecialPointer, SpecialPointerRepr},
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    intern::Symbol,
+    special::SpecialPointers,
+    target::{Endian, TargetSpec},
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{
+        BinaryOp, Expr, IntBits, IntType, Param, PointerKind, Signature, SpecialConst, Type,
+        UnaryOp,
+    },
+    value::{Value as ImtValue, ValueBody},
+};
+
+use super::{CodegenBackend, OutputSink, doc_strings, output_name};
+
+/// Where the output of a [`TemplateBackend`] goes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateOutput {
+    /// Renders the template once for each file in the bundle, producing an output with the given extension.
+    /// The template receives `file` (the current file), `files` (every file), `target`, and `special_pointers`
+    PerFile(String),
+    /// Renders the template once for the whole bundle, producing the named output.
+    /// The template receives `files`, `target`, and `special_pointers`
+    Single(String),
+}
+
+/// Renders a user-provided minijinja template against the bundle
+pub struct TemplateBackend {
+    source: String,
+    output: TemplateOutput,
+}
+
+impl TemplateBackend {
+    pub fn new(source: String, output: TemplateOutput) -> Self {
+        Self { source, output }
+    }
+}
+
+fn template_error(e: minijinja::Error) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidInput, e)
+}
+
+impl CodegenBackend for TemplateBackend {
+    fn name(&self) -> &str {
+        "template"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let mut env = Environment::new();
+        env.add_template("main", &self.source)
+            .map_err(template_error)?;
+        let template = env.get_template("main").map_err(template_error)?;
+
+        let target = target_value(target);
+        let special_pointers = specials_value(&SpecialPointers::from_bundle(bundle));
+        let files = bundle
+            .iter()
+            .map(|(path, file)| file_value(path, file))
+            .collect::<Vec<_>>();
+
+        match &self.output {
+            TemplateOutput::PerFile(ext) => {
+                for ((path, _), file) in bundle.iter().zip(&files) {
+                    let out = template
+                        .render(minijinja::context! { file, files, target, special_pointers })
+                        .map_err(template_error)?;
+                    sink.write_output(&output_name(path, ext), out.as_bytes())?;
+                }
+            }
+            TemplateOutput::Single(name) => {
+                let out = template
+                    .render(minijinja::context! { files, target, special_pointers })
+                    .map_err(template_error)?;
+                sink.write_output(name, out.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::from(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+fn target_value(target: &TargetSpec) -> Value {
+    map([
+        ("pointer_bits", Value::from(target.pointer_bits)),
+        ("long_bits", Value::from(target.long_bits)),
+        (
+            "endian",
+            Value::from(match target.endian {
+                Endian::Little => "little",
+                Endian::Big => "big",
+            }),
+        ),
+        ("int128_align", Value::from(target.int128_align)),
+    ])
+}
+
+/// The declared special pointer kinds, keyed by UUID, so templates can look up the `special` key of a pointer type
+fn specials_value(specials: &SpecialPointers) -> Value {
+    Value::from(
+        specials
+            .iter()
+            .map(|SpecialPointer { id, name, repr }| {
+                (
+                    id.to_string(),
+                    map([
+                        ("name", Value::from(name.as_str())),
+                        (
+                            "repr",
+                            Value::from(match repr {
+                                SpecialPointerRepr::Const => "const",
+                                SpecialPointerRepr::Mut => "mut",
+                                SpecialPointerRepr::Address => "address",
+                            }),
+                        ),
+                    ]),
+                )
+            })
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+fn attrs_value<Targ: AttributeTarget>(attrs: &[Attribute<Targ>]) -> Value {
+    Value::from(
+        attrs
+            .iter()
+            .map(|attr| {
+                map([
+                    ("id", Value::from(attr.id().to_string())),
+                    (
+                        "name",
+                        Value::from(attribute_info(*attr.id()).map(|info| info.name)),
+                    ),
+                    ("debug", Value::from(format!("{attr:?}"))),
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn file_value(path: &Path, file: &File) -> Value {
+    map([
+        ("path", Value::from(path.to_string())),
+        ("segments", Value::from(path.0.clone())),
+        ("file_id", Value::from(file.file_id.to_string())),
+        ("docs", Value::from(doc_strings(&file.attributes))),
+        ("attrs", attrs_value(&file.attributes)),
+        (
+            "uses",
+            Value::from(
+                file.uses
+                    .iter()
+                    .map(|use_item| {
+                        map([
+                            ("path", Value::from(Path(use_item.path.clone()).to_string())),
+                            ("attrs", attrs_value(&use_item.attrs)),
+                        ])
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+        (
+            "types",
+            Value::from(file.types.iter().map(typedef_value).collect::<Vec<_>>()),
+        ),
+        (
+            "values",
+            Value::from(file.values.iter().map(value_value).collect::<Vec<_>>()),
+        ),
+    ])
+}
+
+fn fields_value(fields: &StructFields) -> Value {
+    Value::from(
+        fields
+            .field
+            .iter()
+            .map(|field| {
+                map([
+                    ("name", Value::from(field.name.as_str())),
+                    ("ty", type_value(&field.ty)),
+                    ("docs", Value::from(doc_strings(&field.attrs))),
+                    ("attrs", attrs_value(&field.attrs)),
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The type of the explicit padding of a struct or union, or none
+fn pad_value(fields: &StructFields) -> Value {
+    fields.pad.as_ref().map(type_value).unwrap_or_default()
+}
+
+fn typedef_value(ty: &TypeDef) -> Value {
+    let (kind, docs, attrs, body) = match &ty.body {
+        TypeDefBody::Alias(alias) => (
+            "alias",
+            Value::from(doc_strings(&alias.attrs)),
+            attrs_value(&alias.attrs),
+            map([("alias", type_value(&alias.alias))]),
+        ),
+        TypeDefBody::Struct(st) => (
+            "struct",
+            Value::from(doc_strings(&st.attrs)),
+            attrs_value(&st.attrs),
+            match &st.body {
+                StructBody::Fields(fields) => map([
+                    ("opaque", Value::from(false)),
+                    ("fields", fields_value(fields)),
+                    ("pad", pad_value(fields)),
+                ]),
+                StructBody::Opaque(repr) => map([
+                    ("opaque", Value::from(true)),
+                    ("repr", repr.as_ref().map(type_value).unwrap_or_default()),
+                ]),
+            },
+        ),
+        TypeDefBody::Union(un) => (
+            "union",
+            Value::from(doc_strings(&un.attrs)),
+            attrs_value(&un.attrs),
+            map([
+                ("fields", fields_value(&un.fields)),
+                ("pad", pad_value(&un.fields)),
+            ]),
+        ),
+        TypeDefBody::Enum(en) => (
+            "enum",
+            Value::from(doc_strings(&en.attrs)),
+            attrs_value(&en.attrs),
+            map([
+                ("underlying", int_value(en.underlying)),
+                (
+                    "variants",
+                    Value::from(
+                        en.variants
+                            .iter()
+                            .map(|variant| {
+                                map([
+                                    ("name", Value::from(variant.name.as_str())),
+                                    ("discrim", expr_value(&variant.discrim)),
+                                    ("docs", Value::from(doc_strings(&variant.attrs))),
+                                    ("attrs", attrs_value(&variant.attrs)),
+                                ])
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                ),
+            ]),
+        ),
+    };
+
+    map([
+        ("name", Value::from(ty.name.as_str())),
+        ("num_params", Value::from(ty.num_params)),
+        ("const_params", Value::from(ty.const_params())),
+        ("kind", Value::from(kind)),
+        ("docs", docs),
+        ("attrs", attrs),
+        ("body", body),
+    ])
+}
+
+fn value_value(val: &ImtValue) -> Value {
+    let (kind, docs, attrs, body) = match &val.body {
+        ValueBody::Const(c) => (
+            "const",
+            Value::from(doc_strings(&c.attrs)),
+            attrs_value(&c.attrs),
+            map([("ty", type_value(&c.ty)), ("val", expr_value(&c.val))]),
+        ),
+        ValueBody::Function(f) => (
+            "function",
+            Value::from(doc_strings(&f.attrs)),
+            attrs_value(&f.attrs),
+            signature_value(&f.signature),
+        ),
+    };
+
+    map([
+        ("name", Value::from(val.name.as_str())),
+        ("kind", Value::from(kind)),
+        ("docs", docs),
+        ("attrs", attrs),
+        ("body", body),
+    ])
+}
+
+fn signature_value(sig: &Signature) -> Value {
+    map([
+        (
+            "params",
+            Value::from(
+                sig.params
+                    .iter()
+                    .map(|param| {
+                        map([
+                            ("name", Value::from(param.name.as_deref())),
+                            ("ty", type_value(&param.ty)),
+                            ("docs", Value::from(param_doc_strings(&param.attrs))),
+                            ("attrs", attrs_value(&param.attrs)),
+                        ])
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+        ("ret", type_value(&sig.retty)),
+        (
+            "ret_docs",
+            Value::from(
+                find_attribute::<ReturnDoc, _>(&sig.ret_attrs)
+                    .map(|doc| {
+                        doc.doc_lines
+                            .iter()
+                            .map(Symbol::to_string)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default(),
+            ),
+        ),
+        ("ret_attrs", attrs_value(&sig.ret_attrs)),
+    ])
+}
+
+fn param_doc_strings(attrs: &[Attribute<Param>]) -> Vec<String> {
+    find_attribute::<ParamDoc, _>(attrs)
+        .map(|doc| doc.doc_lines.iter().map(Symbol::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn int_value(ty: IntType) -> Value {
+    map([
+        ("signed", Value::from(ty.signed)),
+        (
+            "bits",
+            match ty.bits {
+                IntBits::Long => Value::from("long"),
+                IntBits::Bits(bits) => Value::from(bits.get()),
+            },
+        ),
+    ])
+}
+
+fn type_value(ty: &Type) -> Value {
+    match ty {
+        Type::Named(name, args) => map([
+            ("kind", Value::from("named")),
+            ("name", Value::from(name.as_str())),
+            (
+                "args",
+                Value::from(args.iter().flatten().map(type_value).collect::<Vec<_>>()),
+            ),
+        ]),
+        Type::Param(n, _) => map([("kind", Value::from("param")), ("index", Value::from(*n))]),
+        Type::Int(int) => map([("kind", Value::from("int")), ("int", int_value(*int))]),
+        Type::Char(int) => map([("kind", Value::from("char")), ("int", int_value(*int))]),
+        Type::Pointer(kind, pointee) => map([
+            ("kind", Value::from("pointer")),
+            (
+                "mutability",
+                Value::from(match kind {
+                    PointerKind::Const => "const",
+                    PointerKind::Mut => "mut",
+                    PointerKind::Special(_) => "special",
+                }),
+            ),
+            (
+                "special",
+                match kind {
+                    PointerKind::Special(id) => Value::from(id.to_string()),
+                    _ => Value::from(()),
+                },
+            ),
+            ("pointee", type_value(pointee)),
+        ]),
+        Type::Func(sig) => map([
+            ("kind", Value::from("func")),
+            ("signature", signature_value(sig)),
+        ]),
+        Type::Void => map([("kind", Value::from("void"))]),
+        Type::Never => map([("kind", Value::from("never"))]),
+        Type::Byte => map([("kind", Value::from("byte"))]),
+        Type::Array(arr) => map([
+            ("kind", Value::from("array")),
+            ("base", type_value(&arr.base)),
+            ("len", expr_value(&arr.len)),
+        ]),
+        Type::Uninit(inner) => map([
+            ("kind", Value::from("uninit")),
+            ("inner", type_value(inner)),
+        ]),
+        Type::Const(val) => map([("kind", Value::from("const")), ("value", expr_value(val))]),
+    }
+}
+
+fn expr_value(expr: &Expr) -> Value {
+    match expr {
+        Expr::IntLiteral(ty, val) => map([
+            ("kind", Value::from("int")),
+            ("int", int_value(*ty)),
+            ("value", Value::from(val.to_string())),
+        ]),
+        Expr::UuidLiteral(uuid) => map([
+            ("kind", Value::from("uuid")),
+            ("value", Value::from(uuid.to_string())),
+        ]),
+        Expr::StringLiteral(s) => map([
+            ("kind", Value::from("string")),
+            ("value", Value::from(s.clone())),
+        ]),
+        Expr::Const(name) => map([
+            ("kind", Value::from("const")),
+            ("name", Value::from(name.as_str())),
+        ]),
+        Expr::BinOp(op, left, right) => map([
+            ("kind", Value::from("binary")),
+            (
+                "op",
+                Value::from(match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::And => "&",
+                    BinaryOp::Or => "|",
+                    BinaryOp::Xor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
+                }),
+            ),
+            ("left", expr_value(left)),
+            ("right", expr_value(right)),
+        ]),
+        Expr::UnaryOp(op, inner) => map([
+            ("kind", Value::from("unary")),
+            (
+                "op",
+                Value::from(match op {
+                    UnaryOp::Not => "!",
+                    UnaryOp::Neg => "-",
+                }),
+            ),
+            ("inner", expr_value(inner)),
+        ]),
+        Expr::SpecialConstant(SpecialConst::Custom(id)) => map([
+            ("kind", Value::from("special")),
+            ("id", Value::from(id.to_string())),
+        ]),
+        Expr::SpecialConstant(special) => map([
+            ("kind", Value::from("special")),
+            ("name", Value::from(special.builtin_name())),
+        ]),
+        Expr::Param(n) => map([("kind", Value::from("param")), ("index", Value::from(*n))]),
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    attr::{
+        find_attribute,
+        types::{Endian, Niche, Reserved, WireFormat},
+    },
+    bundle::{Bundle, Path},
+    intern::Symbol,
+    layout::LayoutEngine,
+    resolve::{Item, module_of, resolve_name},
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{IntType, Type},
+};
+
+use super::unsupported;
+
+/// A step from a struct to one of the scalars it contains
+#[derive(Clone)]
+pub(super) enum Access {
+    Field(Symbol),
+    Index(u128),
+    /// The underlying integer of an enum, which is a newtype in Rust
+    Enum,
+}
+
+pub(super) enum ScalarKind {
+    Int(IntType),
+    Char(IntType),
+    /// A run of bytes, which are copied as-is
+    Bytes,
+}
+
+/// A scalar of a struct with a [`WireFormat`] attribute, which is encoded at `offset` with the byte order `endian`
+pub(super) struct WireScalar {
+    pub access: Vec<Access>,
+    pub offset: u128,
+    pub size: u128,
+    pub kind: ScalarKind,
+    pub endian: Endian,
+}
+
+/// The bytes of a field with a [`Reserved`] attribute, which are encoded as zero
+pub(super) struct ReservedRange {
+    pub offset: u128,
+    pub size: u128,
+    /// Whether decoding fails if the bytes aren't zero
+    pub must_be_zero: bool,
+}
+
+/// The encoding of a struct with a [`WireFormat`] attribute.
+/// The encoding has the layout of the struct, as computed by the [`LayoutEngine`], with each padding byte and reserved field zeroed
+pub(super) struct WireLayout {
+    pub size: u128,
+    pub scalars: Vec<WireScalar>,
+    pub reserved: Vec<ReservedRange>,
+}
+
+/// Flattens the struct `ty`, defined in `module`, into the scalars of its encoding, if it has a [`WireFormat`] attribute.
+/// Each scalar has the byte order of the innermost field containing it with an [`Endian`] attribute, or that of the [`WireFormat`] attribute.
+pub(super) fn wire_layout<'a>(
+    engine: &mut LayoutEngine<'a>,
+    bundle: &'a Bundle,
+    module: &Path,
+    ty: &TypeDef,
+) -> std::io::Result<Option<WireLayout>> {
+    let TypeDefBody::Struct(st) = &ty.body else {
+        return Ok(None);
+    };
+    let Some(wire) = find_attribute::<WireFormat, _>(&st.attrs) else {
+        return Ok(None);
+    };
+    let name = &ty.name;
+    let StructBody::Fields(fields) = &st.body else {
+        return Err(unsupported(format!(
+            "{name} has no wire format: it is an opaque struct"
+        )));
+    };
+    if ty.num_params != 0 {
+        return Err(unsupported(format!(
+            "{name} has no wire format: it has generic parameters"
+        )));
+    }
+
+    let size = engine
+        .layout_of_def(module, ty)
+        .map_err(|e| unsupported(format!("{name} has no wire format: {e}")))?
+        .size;
+    let mut flattener = Flattener {
+        engine,
+        bundle,
+        name,
+        scalars: Vec::new(),
+        reserved: Vec::new(),
+    };
+    flattener.fields(module, fields, &[], 0, wire.endian)?;
+    Ok(Some(WireLayout {
+        size,
+        scalars: flattener.scalars,
+        reserved: flattener.reserved,
+    }))
+}
+
+struct Flattener<'a, 'b> {
+    engine: &'a mut LayoutEngine<'b>,
+    bundle: &'b Bundle,
+    name: &'a str,
+    scalars: Vec<WireScalar>,
+    reserved: Vec<ReservedRange>,
+}
+
+impl Flattener<'_, '_> {
+    fn error(&self, access: &[Access], msg: &str) -> std::io::Error {
+        let mut field = String::new();
+        for step in access {
+            match step {
+                Access::Field(name) if field.is_empty() => field.push_str(name),
+                Access::Field(name) => {
+                    field.push('.');
+                    field.push_str(name);
+                }
+                Access::Index(n) => field.push_str(&format!("[{n}]")),
+                Access::Enum => {}
+            }
+        }
+        unsupported(format!("{} has no wire format: {field} {msg}", self.name))
+    }
+
+    fn fields(
+        &mut self,
+        module: &Path,
+        fields: &StructFields,
+        access: &[Access],
+        offset: u128,
+        endian: Endian,
+    ) -> std::io::Result<()> {
+        let layouts = self
+            .engine
+            .field_layouts(module, fields)
+            .map_err(|e| self.error(access, &e.to_string()))?;
+        for (field, layout) in fields.field.iter().zip(layouts) {
+            if let Some(reserved) = find_attribute::<Reserved, _>(&field.attrs) {
+                self.reserved.push(ReservedRange {
+                    offset: offset + layout.offset,
+                    size: layout.layout.size,
+                    must_be_zero: reserved.must_be_zero,
+                });
+                continue;
+            }
+            let mut access = access.to_vec();
+            access.push(Access::Field(field.name.clone()));
+            // Backends represent zero niches with types that can't be decoded from every value
+            if find_attribute::<Niche, _>(&field.attrs) == Some(&Niche::Zero) {
+                return Err(self.error(&access, "has a zero niche"));
+            }
+            let endian = find_attribute::<Endian, _>(&field.attrs)
+                .copied()
+                .unwrap_or(endian);
+            self.ty(module, &field.ty, access, offset + layout.offset, endian)?;
+        }
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        access: Vec<Access>,
+        offset: u128,
+        size: u128,
+        kind: ScalarKind,
+        endian: Endian,
+    ) {
+        self.scalars.push(WireScalar {
+            access,
+            offset,
+            size,
+            kind,
+            endian,
+        });
+    }
+
+    fn ty(
+        &mut self,
+        module: &Path,
+        ty: &Type,
+        mut access: Vec<Access>,
+        offset: u128,
+        endian: Endian,
+    ) -> std::io::Result<()> {
+        match ty {
+            Type::Int(int) => {
+                let size = self.engine.int_layout(*int).size;
+                self.push(access, offset, size, ScalarKind::Int(*int), endian);
+            }
+            Type::Char(int) => {
+                let size = self.engine.int_layout(*int).size;
+                self.push(access, offset, size, ScalarKind::Char(*int), endian);
+            }
+            Type::Byte => self.push(access, offset, 1, ScalarKind::Int(IntType::u8), endian),
+            Type::Array(arr) => {
+                let layout = self
+                    .engine
+                    .layout_of(module, ty)
+                    .map_err(|e| self.error(&access, &e.to_string()))?;
+                let elem = self
+                    .engine
+                    .layout_of(module, &arr.base)
+                    .map_err(|e| self.error(&access, &e.to_string()))?;
+                if elem.size == 0 {
+                    return Ok(());
+                }
+                // Arrays of bytes are copied at once rather than byte by byte
+                if matches!(
+                    arr.base,
+                    Type::Byte | Type::Int(IntType::u8) | Type::Char(IntType::u8)
+                ) {
+                    self.push(access, offset, layout.size, ScalarKind::Bytes, endian);
+                    return Ok(());
+                }
+                for n in 0..layout.size / elem.size {
+                    let mut access = access.clone();
+                    access.push(Access::Index(n));
+                    self.ty(module, &arr.base, access, offset + n * elem.size, endian)?;
+                }
+            }
+            Type::Named(name, None) => {
+                let resolved = resolve_name(self.bundle, module, name)
+                    .map_err(|e| self.error(&access, &e.to_string()))?;
+                let def_module = module_of(&resolved.path);
+                let Item::Type(def) = resolved.item else {
+                    return Err(self.error(&access, &format!("names the value {name}")));
+                };
+                match &def.body {
+                    TypeDefBody::Alias(alias)
+                        if find_attribute::<Niche, _>(&alias.attrs) == Some(&Niche::Zero) =>
+                    {
+                        return Err(self.error(&access, &format!("has the zero niche of {name}")));
+                    }
+                    TypeDefBody::Alias(alias) if def.num_params == 0 => {
+                        self.ty(&def_module, &alias.alias, access, offset, endian)?;
+                    }
+                    TypeDefBody::Enum(en) => {
+                        let size = self.engine.int_layout(en.underlying).size;
+                        access.push(Access::Enum);
+                        self.push(access, offset, size, ScalarKind::Int(en.underlying), endian);
+                    }
+                    TypeDefBody::Struct(st) if def.num_params == 0 => match &st.body {
+                        StructBody::Fields(fields) => {
+                            self.fields(&def_module, fields, &access, offset, endian)?;
+                        }
+                        StructBody::Opaque(_) => {
+                            return Err(self.error(&access, "is an opaque struct"));
+                        }
+                    },
+                    TypeDefBody::Union(_) => return Err(self.error(&access, "is a union")),
+                    _ => return Err(self.error(&access, "has generic parameters")),
+                }
+            }
+            Type::Named(_, Some(_)) | Type::Param(_, _) | Type::Const(_) => {
+                return Err(self.error(&access, "has generic parameters"));
+            }
+            Type::Pointer(_, _) | Type::Func(_) => {
+                return Err(self.error(&access, "is a pointer"));
+            }
+            Type::Uninit(_) => return Err(self.error(&access, "is uninitialized")),
+            Type::Void | Type::Never => return Err(self.error(&access, "has no values")),
+        }
+        Ok(())
+    }
+}
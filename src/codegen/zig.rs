@@ -0,0 +1,561 @@
+use core::fmt::Write as _;
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{Align, LinkName, Niche, SpecialPointerRepr},
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    layout::{LayoutEngine, LayoutError},
+    option::{OptionKind, option_header},
+    special::SpecialPointers,
+    target::TargetSpec,
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{BinaryOp, Expr, IntBits, IntType, Signature, SpecialConst, Type, UnaryOp},
+    value::{Value, ValueBody},
+};
+
+use super::{
+    CodegenBackend, OutputSink, doc_lines, field_doc, function_doc, int_literal, niche_doc,
+    output_name, special_value, union_doc, unsupported,
+};
+
+/// Generates a Zig file for each file in the bundle.
+///
+/// Structs and unions are generated as `extern struct`s and `extern union`s, enums as non-exhaustive enums with an explicit tag type,
+/// and types with generic parameters as functions returning a type. The model has no packed or bitfield structs, so no `packed struct`s are generated.
+#[derive(Clone, Debug, Default)]
+pub struct ZigBackend {
+    /// Emits `comptime` assertions of the size, alignment, and field offsets of each type, as computed by the [`LayoutEngine`]
+    pub layout_assertions: bool,
+}
+
+impl CodegenBackend for ZigBackend {
+    fn name(&self) -> &str {
+        "zig"
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let specials = SpecialPointers::from_bundle(bundle);
+        for (path, file) in bundle {
+            let mut writer = ZigWriter {
+                bundle,
+                target,
+                path,
+                layout: self
+                    .layout_assertions
+                    .then(|| LayoutEngine::new(bundle, target)),
+                specials: &specials,
+                indent: 0,
+                out: String::new(),
+            };
+            writer.write_file(file)?;
+            sink.write_output(&output_name(path, "zig"), writer.out.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "addrspace",
+    "align",
+    "allowzero",
+    "and",
+    "anyframe",
+    "anytype",
+    "asm",
+    "break",
+    "callconv",
+    "catch",
+    "comptime",
+    "const",
+    "continue",
+    "defer",
+    "else",
+    "enum",
+    "errdefer",
+    "error",
+    "export",
+    "extern",
+    "fn",
+    "for",
+    "if",
+    "inline",
+    "linksection",
+    "noalias",
+    "noinline",
+    "nosuspend",
+    "opaque",
+    "or",
+    "orelse",
+    "packed",
+    "pub",
+    "resume",
+    "return",
+    "struct",
+    "suspend",
+    "switch",
+    "test",
+    "threadlocal",
+    "try",
+    "type",
+    "union",
+    "unreachable",
+    "var",
+    "volatile",
+    "while",
+];
+
+/// Quotes `name` as `@"name"` if it is a Zig keyword
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("@\"{name}\"")
+    } else {
+        name.to_string()
+    }
+}
+
+/// The path of the file generated for `to`, relative to the file generated for `from`, as used by `@import`
+fn import_path(from: &Path, to: &Path) -> String {
+    let from_dirs = &from.0[..from.0.len().saturating_sub(1)];
+    let to_file = output_name(to, "zig");
+    let to = to_file.split('/').collect::<Vec<_>>();
+    let common = from_dirs
+        .iter()
+        .zip(&to)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(to.len() - 1);
+
+    let mut path = "../".repeat(from_dirs.len() - common);
+    path.push_str(&to[common..].join("/"));
+    path
+}
+
+struct ZigWriter<'a> {
+    bundle: &'a Bundle,
+    target: &'a TargetSpec,
+    path: &'a Path,
+    layout: Option<LayoutEngine<'a>>,
+    specials: &'a SpecialPointers,
+    indent: usize,
+    out: String,
+}
+
+impl ZigWriter<'_> {
+    fn line(&mut self, args: core::fmt::Arguments) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    fn doc<S: AsRef<str>>(&mut self, lines: &[S]) {
+        for line in lines {
+            let line = line.as_ref().trim_end();
+            if line.is_empty() {
+                self.line(format_args!("///"));
+            } else {
+                self.line(format_args!("/// {line}"));
+            }
+        }
+    }
+
+    fn write_file(&mut self, file: &File) -> std::io::Result<()> {
+        for line in doc_lines(&file.attributes) {
+            self.line(format_args!("//! {}", line.trim_end()));
+        }
+        if self.layout.is_some() {
+            self.line(format_args!("const std = @import(\"std\");"));
+        }
+
+        for use_item in &file.uses {
+            let path = Path(use_item.path.clone());
+            if let Some(module) = self.bundle.get(&path) {
+                // Zig has no glob imports, so each item of the module is imported by name
+                let import = import_path(self.path, &path);
+                let names = module
+                    .types
+                    .iter()
+                    .map(|ty| &ty.name)
+                    .chain(module.values.iter().map(|val| &val.name));
+                for name in names {
+                    let name = ident(name);
+                    self.line(format_args!("const {name} = @import(\"{import}\").{name};"));
+                }
+            } else if let (Some(module), Some(name)) = (path.parent(), path.name()) {
+                let import = import_path(self.path, &module);
+                let name = ident(name);
+                self.line(format_args!("const {name} = @import(\"{import}\").{name};"));
+            }
+        }
+
+        for ty in &file.types {
+            self.out.push('\n');
+            self.write_typedef(ty)?;
+            self.write_layout_assertions(ty);
+            self.write_option_id(ty)?;
+        }
+
+        for val in &file.values {
+            self.out.push('\n');
+            self.write_value(val)?;
+        }
+        Ok(())
+    }
+
+    fn write_typedef(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let name = ident(&ty.name);
+
+        let doc = match &ty.body {
+            TypeDefBody::Alias(alias) => niche_doc(&alias.attrs),
+            TypeDefBody::Struct(st) => niche_doc(&st.attrs),
+            TypeDefBody::Union(un) => union_doc(un),
+            TypeDefBody::Enum(en) => doc_lines(&en.attrs)
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+        };
+        self.doc(&doc);
+
+        // Generic types are functions from the types (or values of constant parameters) of their parameters to the type
+        let generic = ty.num_params != 0;
+        if generic {
+            let params = ty
+                .const_params()
+                .into_iter()
+                .enumerate()
+                .map(|(n, is_const)| match is_const {
+                    true => format!("comptime N{n}: usize"),
+                    false => format!("comptime T{n}: type"),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.line(format_args!("pub fn {name}({params}) type {{"));
+            self.indent += 1;
+        }
+        let decl = if generic {
+            "return ".to_string()
+        } else {
+            format!("pub const {name} = ")
+        };
+
+        match &ty.body {
+            TypeDefBody::Alias(alias) => {
+                let aliased =
+                    self.niche_ty(&alias.alias, find_attribute::<Niche, _>(&alias.attrs))?;
+                self.line(format_args!("{decl}{aliased};"));
+            }
+            TypeDefBody::Struct(st) => {
+                let align = find_attribute::<Align, _>(&st.attrs).map(|a| a.alignment);
+                match &st.body {
+                    StructBody::Fields(fields) => {
+                        self.line(format_args!("{decl}extern struct {{"));
+                        self.write_fields(fields, align)?;
+                        self.line(format_args!("}};"));
+                    }
+                    StructBody::Opaque(Some(repr)) => {
+                        let repr = self.ty(repr)?;
+                        let align = Self::align_spec(align);
+                        self.line(format_args!("{decl}extern struct {{"));
+                        self.line(format_args!("    _opaque: {repr}{align},"));
+                        self.line(format_args!("}};"));
+                    }
+                    StructBody::Opaque(None) => {
+                        self.line(format_args!("{decl}opaque {{}};"));
+                    }
+                }
+            }
+            TypeDefBody::Union(un) => {
+                let align = find_attribute::<Align, _>(&un.attrs).map(|a| a.alignment);
+                self.line(format_args!("{decl}extern union {{"));
+                self.write_fields(&un.fields, align)?;
+                self.line(format_args!("}};"));
+            }
+            TypeDefBody::Enum(en) => {
+                let underlying = self.int_ty(en.underlying)?;
+                self.line(format_args!("{decl}enum({underlying}) {{"));
+                self.indent += 1;
+                for variant in &en.variants {
+                    self.doc(doc_lines(&variant.attrs));
+                    let discrim = self.expr(&variant.discrim)?;
+                    self.line(format_args!("{} = {discrim},", ident(&variant.name)));
+                }
+                // Values other than the declared variants may be passed across the interface
+                self.line(format_args!("_,"));
+                self.indent -= 1;
+                self.line(format_args!("}};"));
+            }
+        }
+
+        if generic {
+            self.indent -= 1;
+            self.line(format_args!("}}"));
+        }
+        Ok(())
+    }
+
+    /// Alignment of a struct or union is raised by over-aligning its first member
+    fn align_spec(align: Option<u128>) -> String {
+        align
+            .map(|align| format!(" align({align})"))
+            .unwrap_or_default()
+    }
+
+    fn write_fields(&mut self, fields: &StructFields, align: Option<u128>) -> std::io::Result<()> {
+        self.indent += 1;
+        let mut align = Self::align_spec(align);
+        for field in &fields.field {
+            self.doc(&field_doc(field));
+            let ty = self.niche_ty(&field.ty, find_attribute::<Niche, _>(&field.attrs))?;
+            self.line(format_args!("{}: {ty}{align},", ident(&field.name)));
+            align = String::new();
+        }
+        if let Some(pad) = &fields.pad {
+            let ty = self.ty(pad)?;
+            self.line(format_args!("_pad: {ty}{align},"));
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    /// Emits the id of an option struct, as `<name>_OPTION_ID`
+    fn write_option_id(&mut self, ty: &TypeDef) -> std::io::Result<()> {
+        let TypeDefBody::Struct(st) = &ty.body else {
+            return Ok(());
+        };
+        let Some(OptionKind::Option(id)) = OptionKind::of(st) else {
+            return Ok(());
+        };
+        let Ok(header) = option_header(self.bundle, self.path, st) else {
+            return Ok(());
+        };
+
+        let id_ty = self.ty(&header.id.ty)?;
+        let id = self.expr(&Expr::UuidLiteral(id))?;
+        self.line(format_args!(
+            "pub const {}_OPTION_ID: {id_ty} = {id};",
+            ty.name
+        ));
+        Ok(())
+    }
+
+    fn write_layout_assertions(&mut self, ty: &TypeDef) {
+        let Some(engine) = &mut self.layout else {
+            return;
+        };
+        if ty.num_params != 0 {
+            return;
+        }
+
+        let name = ident(&ty.name);
+        let layout = match engine.layout_of_def(self.path, ty) {
+            Ok(layout) => layout,
+            Err(LayoutError::Unsized(_)) => return,
+            Err(e) => {
+                self.line(format_args!("// no layout assertions for {name}: {e}"));
+                return;
+            }
+        };
+        let offsets = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => engine.field_layouts(self.path, fields).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        self.line(format_args!("comptime {{"));
+        self.line(format_args!(
+            "    std.debug.assert(@sizeOf({name}) == {});",
+            layout.size
+        ));
+        self.line(format_args!(
+            "    std.debug.assert(@alignOf({name}) == {});",
+            layout.align
+        ));
+        for field in offsets.into_iter().flatten() {
+            self.line(format_args!(
+                "    std.debug.assert(@offsetOf({name}, \"{}\") == {});",
+                field.name, field.offset
+            ));
+        }
+        self.line(format_args!("}}"));
+    }
+
+    fn write_value(&mut self, val: &Value) -> std::io::Result<()> {
+        let name = ident(&val.name);
+        match &val.body {
+            ValueBody::Const(c) => {
+                self.doc(doc_lines(&c.attrs));
+                let expr = self.expr(&c.val)?;
+                if matches!(c.val, Expr::StringLiteral(_)) {
+                    // String literals are arrays, which only coerce to pointers to many bytes
+                    self.line(format_args!("pub const {name} = {expr};"));
+                } else {
+                    let ty = self.ty(&c.ty)?;
+                    self.line(format_args!("pub const {name}: {ty} = {expr};"));
+                }
+            }
+            ValueBody::Function(f) => {
+                self.doc(&function_doc(f));
+                let params = self.params(&f.signature)?;
+                let ret = self.ty(&f.signature.retty)?;
+                match find_attribute::<LinkName, _>(&f.attrs) {
+                    Some(link) => {
+                        let link = self.expr(&Expr::StringLiteral(link.name.clone()))?;
+                        self.line(format_args!(
+                            "pub const {name} = @extern(*const fn ({params}) callconv(.c) {ret}, .{{ .name = {link} }});"
+                        ));
+                    }
+                    None => self.line(format_args!("pub extern fn {name}({params}) {ret};")),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn int_ty(&self, ty: IntType) -> std::io::Result<String> {
+        let sign = if ty.signed { "i" } else { "u" };
+        match ty.bits {
+            IntBits::Long if ty.signed => Ok("c_long".to_string()),
+            IntBits::Long => Ok("c_ulong".to_string()),
+            IntBits::Bits(bits) if matches!(bits.get(), 8 | 16 | 32 | 64 | 128) => {
+                Ok(format!("{sign}{bits}"))
+            }
+            IntBits::Bits(bits) => Err(unsupported(format!(
+                "{sign}{bits} is not allowed in extern types"
+            ))),
+        }
+    }
+
+    fn char_ty(&self, ty: IntType) -> std::io::Result<String> {
+        match ty {
+            IntType::u8 | IntType::u16 | IntType::u32 => self.int_ty(ty),
+            ty => Err(unsupported(format!("char type {ty} has no Zig equivalent"))),
+        }
+    }
+
+    /// The Zig type of a field or type alias, which is not optional if `niche` reserves null
+    fn niche_ty(&self, ty: &Type, niche: Option<&Niche>) -> std::io::Result<String> {
+        let ty = self.ty(ty)?;
+        if niche == Some(&Niche::Zero)
+            && let Some(ty) = ty.strip_prefix('?')
+        {
+            return Ok(ty.to_string());
+        }
+        Ok(ty)
+    }
+
+    fn ty(&self, ty: &Type) -> std::io::Result<String> {
+        Ok(match ty {
+            Type::Named(name, None) => ident(name),
+            Type::Named(name, Some(args)) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.ty(arg))
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                format!("{}({})", ident(name), args.join(", "))
+            }
+            Type::Param(n, _) => format!("T{n}"),
+            Type::Int(int) => self.int_ty(*int)?,
+            Type::Char(int) => self.char_ty(*int)?,
+            Type::Pointer(kind, pointee) => {
+                let qual = match self.specials.repr(*kind) {
+                    SpecialPointerRepr::Const => "const ",
+                    SpecialPointerRepr::Mut => "",
+                    SpecialPointerRepr::Address => return Ok("usize".to_string()),
+                };
+                match &**pointee {
+                    Type::Func(_) => format!("?*const {}", self.ty(pointee)?),
+                    Type::Void => format!("?*{qual}anyopaque"),
+                    pointee => format!("?*{qual}{}", self.ty(pointee)?),
+                }
+            }
+            Type::Func(sig) => {
+                let params = self.params(sig)?;
+                format!("fn ({params}) callconv(.c) {}", self.ty(&sig.retty)?)
+            }
+            Type::Void => "void".to_string(),
+            Type::Never => "noreturn".to_string(),
+            Type::Byte => "u8".to_string(),
+            Type::Array(arr) => format!("[{}]{}", self.expr(&arr.len)?, self.ty(&arr.base)?),
+            Type::Uninit(ty) => self.ty(ty)?,
+            Type::Const(val) => self.expr(val)?,
+        })
+    }
+
+    fn params(&self, sig: &Signature) -> std::io::Result<String> {
+        let params = sig
+            .params
+            .iter()
+            .map(|param| {
+                let ty = self.ty(&param.ty)?;
+                Ok(match &param.name {
+                    Some(name) => format!("{}: {ty}", ident(name)),
+                    None => ty,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(params.join(", "))
+    }
+
+    fn expr(&self, expr: &Expr) -> std::io::Result<String> {
+        Ok(match expr {
+            Expr::IntLiteral(ty, val) => int_literal(*ty, *val, self.target),
+            Expr::UuidLiteral(uuid) => {
+                format!(
+                    ".{{ .minor = {:#x}, .major = {:#x} }}",
+                    uuid.minor, uuid.major
+                )
+            }
+            Expr::StringLiteral(s) => {
+                let mut lit = String::from("\"");
+                for b in s.bytes() {
+                    match b {
+                        b'"' | b'\\' => {
+                            lit.push('\\');
+                            lit.push(b as char);
+                        }
+                        0x20..0x7F => lit.push(b as char),
+                        b => {
+                            let _ = write!(lit, "\\x{b:02x}");
+                        }
+                    }
+                }
+                lit.push('"');
+                lit
+            }
+            Expr::Const(name) => ident(name),
+            Expr::BinOp(op, left, right) => {
+                let op = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::And => "&",
+                    BinaryOp::Or => "|",
+                    BinaryOp::Xor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
+                };
+                format!("({} {op} {})", self.expr(left)?, self.expr(right)?)
+            }
+            Expr::UnaryOp(UnaryOp::Not, inner) => format!("(~{})", self.expr(inner)?),
+            Expr::UnaryOp(UnaryOp::Neg, inner) => format!("(-{})", self.expr(inner)?),
+            Expr::SpecialConstant(SpecialConst::SizeofPointer) => "@sizeOf(usize)".to_string(),
+            Expr::SpecialConstant(SpecialConst::SizeofLong) => "@sizeOf(c_long)".to_string(),
+            Expr::SpecialConstant(special) => {
+                special_value(self.bundle, self.target, *special)?.to_string()
+            }
+            Expr::Param(n) => format!("N{n}"),
+        })
+    }
+}
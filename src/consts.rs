@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+use crate::{
+    bundle::{Bundle, Path},
+    resolve::{Item, resolve_name},
+    uses::Expr,
+    value::ValueBody,
+};
+
+/// Calls `f` with the name of every constant `expr` refers to
+fn const_refs<'a>(expr: &'a Expr, f: &mut impl FnMut(&'a str)) {
+    match expr {
+        Expr::Const(name) => f(name),
+        Expr::BinOp(_, left, right) => {
+            const_refs(left, f);
+            const_refs(right, f);
+        }
+        Expr::UnaryOp(_, inner) => const_refs(inner, f),
+        Expr::IntLiteral(..)
+        | Expr::UuidLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::SpecialConstant(_)
+        | Expr::Param(_) => {}
+    }
+}
+
+/// The dependencies between the constants of a bundle, through the [`Expr::Const`] references in their values.
+///
+/// References that do not resolve to a constant are not part of the graph; the [`Evaluator`](crate::eval::Evaluator) reports them when evaluating.
+#[derive(Clone, Debug, Default)]
+pub struct ConstGraph {
+    /// Each constant in the bundle, with the constants its value refers to
+    pub deps: IndexMap<Path, Vec<Path>>,
+}
+
+impl ConstGraph {
+    pub fn of(bundle: &Bundle) -> Self {
+        let mut deps = IndexMap::new();
+        for (module, file) in bundle {
+            for val in &file.values {
+                let ValueBody::Const(c) = &val.body else {
+                    continue;
+                };
+                let mut refs = Vec::new();
+                const_refs(&c.val, &mut |name| {
+                    if let Ok(resolved) = resolve_name(bundle, module, name)
+                        && let Item::Value(val) = resolved.item
+                        && matches!(val.body, ValueBody::Const(_))
+                        && !refs.contains(&resolved.path)
+                    {
+                        refs.push(resolved.path);
+                    }
+                });
+                deps.insert(module.join(&val.name), refs);
+            }
+        }
+        Self { deps }
+    }
+
+    /// The strongly connected components of the graph, each of which is either a single constant or a cycle.
+    /// Every component comes after all the components it depends on
+    pub fn components(&self) -> Vec<Vec<Path>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: HashMap::new(),
+            stack: Vec::new(),
+            components: Vec::new(),
+        };
+        for path in self.deps.keys() {
+            if !tarjan.index.contains_key(path) {
+                tarjan.visit(path);
+            }
+        }
+        tarjan.components
+    }
+
+    fn is_cycle(&self, component: &[Path]) -> bool {
+        match component {
+            [path] => self.deps.get(path).is_some_and(|deps| deps.contains(path)),
+            _ => true,
+        }
+    }
+
+    /// Each set of constants whose values depend on each other, and so cannot be evaluated
+    pub fn cycles(&self) -> Vec<Vec<Path>> {
+        self.components()
+            .into_iter()
+            .filter(|component| self.is_cycle(component))
+            .collect()
+    }
+
+    /// Orders the constants so that each comes after every constant it depends on.
+    /// Fails with the first cycle found, if any
+    pub fn evaluation_order(&self) -> Result<Vec<Path>, Vec<Path>> {
+        let mut order = Vec::with_capacity(self.deps.len());
+        for component in self.components() {
+            if self.is_cycle(&component) {
+                return Err(component);
+            }
+            order.extend(component);
+        }
+        Ok(order)
+    }
+}
+
+struct Tarjan<'a> {
+    graph: &'a ConstGraph,
+    /// The visit index and lowest reachable index of each visited constant.
+    /// Constants that are on the stack have a lowest index of `None` once their component is complete
+    index: HashMap<&'a Path, (usize, Option<usize>)>,
+    stack: Vec<&'a Path>,
+    components: Vec<Vec<Path>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, path: &'a Path) -> Option<usize> {
+        let index = self.index.len();
+        self.index.insert(path, (index, Some(index)));
+        self.stack.push(path);
+
+        let mut low = index;
+        for dep in self.graph.deps.get(path).into_iter().flatten() {
+            let dep_low = match self.index.get(dep) {
+                None => self.visit(dep),
+                Some(&(_, dep_low)) => dep_low,
+            };
+            if let Some(dep_low) = dep_low {
+                low = low.min(dep_low);
+            }
+        }
+
+        if low != index {
+            self.index.insert(path, (index, Some(low)));
+            return Some(low);
+        }
+
+        let mut component = Vec::new();
+        while let Some(member) = self.stack.pop() {
+            if let Some(entry) = self.index.get_mut(member) {
+                entry.1 = None;
+            }
+            component.push(member.clone());
+            if member == path {
+                break;
+            }
+        }
+        component.reverse();
+        self.components.push(component);
+        None
+    }
+}
@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+
+/// The magic number of an encrypted bundle member, which replaces the [`MAGIC`](crate::header::MAGIC) of the IMT file it contains
+pub const ENCRYPTED_MAGIC: [u8; 6] = *b"\xFEIMTAE";
+
+/// The algorithm byte of members encrypted with AES-256-GCM, the only algorithm currently defined
+const AES_256_GCM: u8 = 1;
+
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit key that bundle members are encrypted with.
+///
+/// The key is not printed by its [`Debug`] implementation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Key([u8; 32]);
+
+impl Key {
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Parses a key written as 64 hexadecimal digits, ignoring surrounding whitespace
+    pub fn parse_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return None;
+        }
+        let mut key = [0; 32];
+        for (byte, digits) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+        }
+        Some(Self(key))
+    }
+}
+
+impl core::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+/// A hook that finds the key an encrypted member names, such as by reading it from a keyring or a secret store.
+///
+/// Any `Fn(&str) -> Option<Key>` is a lookup.
+pub trait KeyLookup {
+    /// The key with the id `key_id`, if it is available
+    fn key(&self, key_id: &str) -> Option<Key>;
+}
+
+impl<F: Fn(&str) -> Option<Key>> KeyLookup for F {
+    fn key(&self, key_id: &str) -> Option<Key> {
+        self(key_id)
+    }
+}
+
+/// Looks up each key in a file named after its id with the extension `.key` in a directory,
+/// which contains the key as 64 hexadecimal digits
+#[derive(Clone, Debug)]
+pub struct KeyDir {
+    dir: PathBuf,
+}
+
+impl KeyDir {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+}
+
+impl KeyLookup for KeyDir {
+    fn key(&self, key_id: &str) -> Option<Key> {
+        // A key id read from a member must not be able to name a file outside of the directory
+        if !is_valid_key_id(key_id) || key_id.starts_with('.') || key_id.contains(['/', '\\']) {
+            return None;
+        }
+        let text = std::fs::read_to_string(self.dir.join(format!("{key_id}.key"))).ok()?;
+        Key::parse_hex(&text)
+    }
+}
+
+fn is_valid_key_id(key_id: &str) -> bool {
+    !key_id.is_empty() && key_id.len() <= usize::from(u8::MAX)
+}
+
+/// Checks that `key_id` can name the key of an encrypted member
+pub fn check_key_id(key_id: &str) -> Result<(), CryptoError> {
+    if is_valid_key_id(key_id) {
+        Ok(())
+    } else {
+        Err(CryptoError::InvalidKeyId(key_id.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub enum CryptoError {
+    /// The key id is empty or longer than 255 bytes
+    InvalidKeyId(String),
+    /// The member ends before its header, or is not valid UTF-8 where it names its key
+    Truncated,
+    UnsupportedAlgorithm(u8),
+    /// The member is encrypted with a key that the lookup does not know
+    MissingKey(String),
+    /// The member was not encrypted with the key it names, or was modified after it was encrypted
+    Authentication(String),
+}
+
+impl core::fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoError::InvalidKeyId(id) => f.write_fmt(format_args!(
+                "invalid key id {id:?}: key ids must be between 1 and 255 bytes long"
+            )),
+            CryptoError::Truncated => f.write_str("truncated encrypted member"),
+            CryptoError::UnsupportedAlgorithm(alg) => {
+                f.write_fmt(format_args!("unsupported encryption algorithm {alg}"))
+            }
+            CryptoError::MissingKey(id) => f.write_fmt(format_args!(
+                "member is encrypted with the key {id}, which is not available"
+            )),
+            CryptoError::Authentication(id) => f.write_fmt(format_args!(
+                "member cannot be decrypted with the key {id}, or was modified after it was encrypted"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+/// Whether `bytes` is an encrypted member, which starts with [`ENCRYPTED_MAGIC`]
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ENCRYPTED_MAGIC)
+}
+
+/// Encrypts the encoded IMT file `plaintext` with `key`, which is named `key_id` so that readers can look it up.
+///
+/// The member consists of [`ENCRYPTED_MAGIC`], the algorithm byte, the length and bytes of `key_id`, a random nonce,
+/// and the AES-256-GCM ciphertext of `plaintext`. The header is authenticated along with the ciphertext,
+/// so that a member cannot be renamed to another key.
+pub fn encrypt(key_id: &str, key: &Key, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    check_key_id(key_id)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let mut member = Vec::with_capacity(
+        ENCRYPTED_MAGIC.len() + 2 + key_id.len() + NONCE_LEN + plaintext.len() + 16,
+    );
+    member.extend_from_slice(&ENCRYPTED_MAGIC);
+    member.push(AES_256_GCM);
+    member.push(key_id.len() as u8);
+    member.extend_from_slice(key_id.as_bytes());
+    member.extend_from_slice(&nonce);
+
+    let ciphertext = Aes256Gcm::new(&key.0.into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &member,
+            },
+        )
+        .map_err(|_| CryptoError::Authentication(key_id.to_string()))?;
+    member.extend_from_slice(&ciphertext);
+    Ok(member)
+}
+
+/// The id of the key that the encrypted member `bytes` names
+pub fn key_id(bytes: &[u8]) -> Result<&str, CryptoError> {
+    Ok(parse_header(bytes)?.0)
+}
+
+fn parse_header(bytes: &[u8]) -> Result<(&str, usize), CryptoError> {
+    let rest = bytes
+        .strip_prefix(&ENCRYPTED_MAGIC)
+        .ok_or(CryptoError::Truncated)?;
+    let (&alg, rest) = rest.split_first().ok_or(CryptoError::Truncated)?;
+    if alg != AES_256_GCM {
+        return Err(CryptoError::UnsupportedAlgorithm(alg));
+    }
+    let (&len, rest) = rest.split_first().ok_or(CryptoError::Truncated)?;
+    let key_id = rest.get(..usize::from(len)).ok_or(CryptoError::Truncated)?;
+    let key_id = core::str::from_utf8(key_id).map_err(|_| CryptoError::Truncated)?;
+    let header_len = ENCRYPTED_MAGIC.len() + 2 + key_id.len() + NONCE_LEN;
+    if bytes.len() < header_len {
+        return Err(CryptoError::Truncated);
+    }
+    Ok((key_id, header_len))
+}
+
+/// Decrypts the member `bytes` written by [`encrypt`], with the key that `keys` finds for the id it names
+pub fn decrypt(bytes: &[u8], keys: &(impl KeyLookup + ?Sized)) -> Result<Vec<u8>, CryptoError> {
+    let (key_id, header_len) = parse_header(bytes)?;
+    let key = keys
+        .key(key_id)
+        .ok_or_else(|| CryptoError::MissingKey(key_id.to_string()))?;
+    let (header, ciphertext) = bytes.split_at(header_len);
+    let nonce = Nonce::from_slice(&header[header_len - NONCE_LEN..]);
+
+    Aes256Gcm::new(&key.0.into())
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| CryptoError::Authentication(key_id.to_string()))
+}
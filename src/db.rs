@@ -0,0 +1,252 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    attr::{find_attribute, types::ExportInline},
+    bundle::{Bundle, Path},
+    diagnostics::Diagnostic,
+    file::File,
+    layout::{Layout, LayoutEngine, LayoutError},
+    lock::content_hash,
+    resolve::{Item, ResolveError, ResolvedItem, module_of, resolve_export},
+    target::TargetSpec,
+    validate::{validate, validate_target},
+};
+
+/// The files a query's result depends on
+#[derive(Clone, Debug, Default)]
+struct Reads {
+    files: HashSet<Path>,
+    /// Whether the result may depend on any file of the bundle, so that any change affects it
+    all: bool,
+}
+
+impl Reads {
+    fn all() -> Self {
+        Self {
+            files: HashSet::new(),
+            all: true,
+        }
+    }
+}
+
+type Memos<K, T> = RefCell<HashMap<K, Memo<T>>>;
+
+/// The path an item is defined at, and the chain of re-exports followed to reach it
+type Resolution = Result<(Path, Vec<Path>), ResolveError>;
+
+#[derive(Clone, Debug)]
+struct Memo<T> {
+    value: T,
+    reads: Reads,
+    /// The revision of the database the value was computed at
+    computed_at: u64,
+}
+
+/// A [`Bundle`] that memoizes the results of resolution, layout, and validation.
+///
+/// Each result records the files it depends on, and is only recomputed once one of those files is changed by
+/// [`Database::set_file`] or [`Database::remove_file`]. Replacing a file with an identical one does not invalidate anything.
+///
+/// Resolving a path depends on the module it names and the modules that module re-exports from.
+/// Layout and validation may examine any file of the bundle, so their results are recomputed after any change.
+#[derive(Debug)]
+pub struct Database {
+    bundle: Bundle,
+    revision: u64,
+    /// The content hash of each file, and the revision it was last changed at
+    files: IndexMap<Path, (u64, u64)>,
+    /// The revision each file that is no longer present was removed at
+    removed: HashMap<Path, u64>,
+    resolutions: Memos<Path, Resolution>,
+    layouts: Memos<(TargetSpec, Path), Result<Layout, LayoutError>>,
+    diagnostics: RefCell<Option<Memo<Vec<Diagnostic>>>>,
+    target_diagnostics: Memos<TargetSpec, Vec<Diagnostic>>,
+}
+
+impl Database {
+    pub fn new(bundle: Bundle) -> Self {
+        let files = bundle
+            .iter()
+            .map(|(path, file)| (path.clone(), (content_hash(file), 0)))
+            .collect();
+        Self {
+            bundle,
+            revision: 0,
+            files,
+            removed: HashMap::new(),
+            resolutions: RefCell::new(HashMap::new()),
+            layouts: RefCell::new(HashMap::new()),
+            diagnostics: RefCell::new(None),
+            target_diagnostics: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn bundle(&self) -> &Bundle {
+        &self.bundle
+    }
+
+    pub fn into_bundle(self) -> Bundle {
+        self.bundle
+    }
+
+    /// The number of changes made to the bundle since the database was created
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Adds or replaces the file at `path`. Returns whether the file differs from the file previously at `path`
+    pub fn set_file(&mut self, path: Path, file: File) -> bool {
+        let hash = content_hash(&file);
+        match self.files.get(&path) {
+            Some(&(old, _)) if old == hash => return false,
+            Some(_) => {}
+            None => {
+                self.removed.remove(&path);
+            }
+        }
+        self.revision += 1;
+        self.files.insert(path.clone(), (hash, self.revision));
+        self.bundle.add_file(path, file);
+        true
+    }
+
+    /// Removes the file at `path`, if it is present
+    pub fn remove_file(&mut self, path: &Path) -> Option<File> {
+        let file = self.bundle.remove_file(path)?;
+        self.revision += 1;
+        self.files.shift_remove(path);
+        self.removed.insert(path.clone(), self.revision);
+        Some(file)
+    }
+
+    fn changed_at(&self, path: &Path) -> u64 {
+        match self.files.get(path) {
+            Some(&(_, changed)) => changed,
+            None => self.removed.get(path).copied().unwrap_or(0),
+        }
+    }
+
+    fn is_fresh<T>(&self, memo: &Memo<T>) -> bool {
+        if memo.reads.all {
+            return self.revision == memo.computed_at;
+        }
+        memo.reads
+            .files
+            .iter()
+            .all(|path| self.changed_at(path) <= memo.computed_at)
+    }
+
+    fn memoized<K: core::hash::Hash + Eq, T: Clone>(
+        &self,
+        cache: &Memos<K, T>,
+        key: K,
+        compute: impl FnOnce() -> (T, Reads),
+    ) -> T {
+        if let Some(memo) = cache.borrow().get(&key)
+            && self.is_fresh(memo)
+        {
+            return memo.value.clone();
+        }
+        let (value, reads) = compute();
+        cache.borrow_mut().insert(
+            key,
+            Memo {
+                value: value.clone(),
+                reads,
+                computed_at: self.revision,
+            },
+        );
+        value
+    }
+
+    /// The modules that resolving a path exported by `module` may look up: `module`, and every module it re-exports from,
+    /// whether or not they exist
+    fn export_reads(&self, module: &Path) -> Reads {
+        let mut reads = Reads::default();
+        let mut pending = vec![module.clone()];
+        while let Some(module) = pending.pop() {
+            let Some(file) = self.bundle.get(&module) else {
+                reads.files.insert(module);
+                continue;
+            };
+            if !reads.files.insert(module) {
+                continue;
+            }
+            for use_item in &file.uses {
+                if find_attribute::<ExportInline, _>(&use_item.attrs).is_none() {
+                    continue;
+                }
+                // Either an item of the module containing it, or an entire module
+                let target = Path(use_item.path.clone());
+                pending.push(module_of(&target));
+                pending.push(target);
+            }
+        }
+        reads
+    }
+
+    /// Resolves the item exported at `path`. See [`resolve_export`]
+    pub fn resolve(&self, path: &Path) -> Result<ResolvedItem<'_>, ResolveError> {
+        let (resolved, chain) = self.memoized(&self.resolutions, path.clone(), || {
+            let resolution =
+                resolve_export(&self.bundle, path).map(|resolved| (resolved.path, resolved.chain));
+            (resolution, self.export_reads(&module_of(path)))
+        })?;
+        let item = self
+            .bundle
+            .get(&module_of(&resolved))
+            .zip(resolved.name())
+            .and_then(|(file, name)| Item::find_in(file, name))
+            .ok_or_else(|| ResolveError::NotFound(resolved.clone()))?;
+        Ok(ResolvedItem {
+            path: resolved,
+            item,
+            chain,
+        })
+    }
+
+    /// Computes the layout of the non-generic type defined or exported at `path` for `target`
+    pub fn layout(&self, target: &TargetSpec, path: &Path) -> Result<Layout, LayoutError> {
+        self.memoized(&self.layouts, (target.clone(), path.clone()), || {
+            let layout = resolve_export(&self.bundle, path)
+                .map_err(LayoutError::from)
+                .and_then(|resolved| {
+                    let Item::Type(def) = resolved.item else {
+                        return Err(LayoutError::NotAType(resolved.path));
+                    };
+                    LayoutEngine::new(&self.bundle, target)
+                        .layout_of_def(&module_of(&resolved.path), def)
+                });
+            // Layouts follow names through `use`s and collect special constants from the whole bundle
+            (layout, Reads::all())
+        })
+    }
+
+    /// Validates the bundle. See [`validate`]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        if let Some(memo) = &*self.diagnostics.borrow()
+            && self.is_fresh(memo)
+        {
+            return memo.value.clone();
+        }
+        let value = validate(&self.bundle);
+        *self.diagnostics.borrow_mut() = Some(Memo {
+            value: value.clone(),
+            reads: Reads::all(),
+            computed_at: self.revision,
+        });
+        value
+    }
+
+    /// Runs the target-specific checks on the bundle. See [`validate_target`]
+    pub fn validate_target(&self, target: &TargetSpec) -> Vec<Diagnostic> {
+        self.memoized(&self.target_diagnostics, target.clone(), || {
+            (validate_target(&self.bundle, target), Reads::all())
+        })
+    }
+}
@@ -0,0 +1,217 @@
+use core::fmt::Write;
+
+pub mod codes;
+pub mod sarif;
+
+use bincode::error::DecodeError;
+
+use crate::{
+    bundle::{Bundle, Path},
+    resolve::Item,
+    tydef::{StructBody, TypeDefBody},
+    value::ValueBody,
+};
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl core::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A problem found in a file of a bundle, by decoding or validating it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub file: Path,
+    /// The item (or member of an item, such as `Struct::field`) within `file` the diagnostic is about, if any
+    pub item: Option<String>,
+    pub message: String,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, file: Path, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            file,
+            item: None,
+            message: message.into(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(file: Path, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, file, message)
+    }
+
+    pub fn warning(file: Path, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, file, message)
+    }
+
+    /// A diagnostic for a file that could not be decoded
+    pub fn decode_error(file: Path, error: &DecodeError) -> Self {
+        Self::error(file, format!("cannot decode file: {error}")).with_code(codes::UNDECODABLE_FILE)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_item(mut self, item: impl Into<String>) -> Self {
+        self.item = Some(item.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// The fingerprint of the diagnostic in a [`Baseline`](crate::baseline::Baseline), from its code, file, and item.
+    /// Diagnostics without a code are fingerprinted by their message instead
+    pub fn fingerprint(&self) -> u64 {
+        crate::baseline::fingerprint(&[
+            "diagnostic",
+            self.code.unwrap_or(&self.message),
+            &self.file.to_string(),
+            self.item.as_deref().unwrap_or(""),
+        ])
+    }
+
+    /// Renders the diagnostic over multiple lines, with an excerpt of the item it is about if that item is in `bundle`.
+    ///
+    /// ```text
+    /// error[IMT0005]: field Pair::a has a byte order but is not an integer
+    ///   --> test::Pair::a
+    ///    |
+    ///    | struct Pair {
+    ///    |     a,
+    ///    |     ^
+    ///    |     b,
+    ///    |     ...
+    ///    | }
+    /// ```
+    pub fn render(&self, bundle: &Bundle) -> String {
+        let mut out = String::new();
+        // Writing to a `String` does not fail
+        let _ = self.write_rendered(&mut out, bundle);
+        out
+    }
+
+    fn write_rendered(&self, out: &mut String, bundle: &Bundle) -> core::fmt::Result {
+        write!(out, "{}", self.severity)?;
+        if let Some(code) = self.code {
+            write!(out, "[{code}]")?;
+        }
+        writeln!(out, ": {}", self.message)?;
+
+        match &self.item {
+            Some(item) if self.file.0.is_empty() => writeln!(out, "  --> {item}")?,
+            Some(item) => writeln!(out, "  --> {}::{item}", self.file)?,
+            None => writeln!(out, "  --> {}", self.file)?,
+        }
+
+        if let Some(lines) = self.excerpt(bundle) {
+            writeln!(out, "   |")?;
+            for line in lines {
+                writeln!(out, "   | {line}")?;
+            }
+        }
+
+        for note in &self.notes {
+            writeln!(out, "   = note: {note}")?;
+        }
+        Ok(())
+    }
+
+    /// The declaration of the item the diagnostic is about, listing the members of the item and marking the one it is about
+    fn excerpt(&self, bundle: &Bundle) -> Option<Vec<String>> {
+        let mut segments = self.item.as_deref()?.split("::");
+        let name = segments.next()?;
+        let member = segments.next();
+        let file = bundle.get(&self.file)?;
+
+        let (keyword, members): (_, Vec<&str>) = match Item::find_in(file, name)? {
+            Item::Type(def) => match &def.body {
+                TypeDefBody::Alias(_) => ("type", vec![]),
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => (
+                        "struct",
+                        fields.field.iter().map(|field| &*field.name).collect(),
+                    ),
+                    StructBody::Opaque(_) => ("struct", vec![]),
+                },
+                TypeDefBody::Union(un) => (
+                    "union",
+                    un.fields.field.iter().map(|field| &*field.name).collect(),
+                ),
+                TypeDefBody::Enum(en) => (
+                    "enum",
+                    en.variants.iter().map(|variant| &*variant.name).collect(),
+                ),
+            },
+            Item::Value(val) => match &val.body {
+                ValueBody::Const(_) => ("const", vec![]),
+                ValueBody::Function(_) => ("fn", vec![]),
+            },
+        };
+
+        if members.is_empty() {
+            let line = format!("{keyword} {name}");
+            let marker = format!("{:>width$}", "^".repeat(name.len()), width = line.len());
+            return Some(vec![line, marker]);
+        }
+
+        // Only the members near the marked one are shown
+        let marked = member.and_then(|member| members.iter().position(|m| *m == member));
+        let (start, end) = match marked {
+            Some(pos) => (pos.saturating_sub(1), (pos + 2).min(members.len())),
+            None => (0, members.len().min(3)),
+        };
+
+        let mut lines = vec![format!("{keyword} {name} {{")];
+        if marked.is_none() {
+            lines.push(format!(
+                "{}{}",
+                " ".repeat(keyword.len() + 1),
+                "^".repeat(name.len())
+            ));
+        }
+        if start != 0 {
+            lines.push("    ...".to_string());
+        }
+        for (i, member) in members.iter().enumerate().take(end).skip(start) {
+            lines.push(format!("    {member},"));
+            if Some(i) == marked {
+                lines.push(format!("    {}", "^".repeat(member.len())));
+            }
+        }
+        if end != members.len() {
+            lines.push("    ...".to_string());
+        }
+        lines.push("}".to_string());
+        Some(lines)
+    }
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}: {}", self.file, self.severity))?;
+        if let Some(code) = self.code {
+            f.write_fmt(format_args!("[{code}]"))?;
+        }
+        f.write_fmt(format_args!(": {}", self.message))
+    }
+}
@@ -0,0 +1,133 @@
+/// An entry in the catalog of diagnostic codes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub summary: &'static str,
+    pub explanation: &'static str,
+}
+
+macro_rules! def_error_codes {
+    ($($name:ident = $code:literal, $summary:literal, $explanation:literal;)*) => {
+        $(pub const $name: &str = $code;)*
+
+        /// Every diagnostic code, in order. Codes are stable, and are not reused if the check that reports them is removed
+        pub static ERROR_CODES: &[ErrorCode] = &[
+            $(ErrorCode {
+                code: $code,
+                summary: $summary,
+                explanation: $explanation,
+            },)*
+        ];
+    };
+}
+
+def_error_codes! {
+    MISSING_REQUIREMENT = "IMT0001", "required subsystem is not present",
+        "A file has a `Requires` attribute naming a subsystem (or file) id that no file in the bundle provides.\n\
+        A file provides its own `file_id`, and the `subsys_id` of its `SubsystemDescriptor`.\n\
+        Add the file that provides the id to the bundle, or remove the requirement.";
+    INCOMPATIBLE_REQUIREMENT = "IMT0002", "required subsystem version is not compatible",
+        "A file has a `Requires` attribute naming a subsystem that is present in the bundle,\n\
+        but only at versions that are not compatible with the required version.\n\
+        Versions are compatible if they have the same major version and the present minor version is at least the required one\n\
+        (or, for 0.x versions, if they have the same minor version).";
+    UNRECOGNIZED_CODEGEN_ATTRIBUTE = "IMT0003", "attribute required for code generation is not recognized",
+        "An attribute has the `REQUIRED_FOR_CODEGEN` flag, but is not recognized by this version of imt.\n\
+        The attribute may be ignored when reading the file, but code generated without understanding it would be incorrect.\n\
+        Use a version of imt that recognizes the attribute.";
+    DEPRECATED_PAYLOAD = "IMT0004", "attribute uses a deprecated payload format",
+        "An attribute has the `DEPRECATED_PAYLOAD` flag, so its payload uses a format that is retained for compatibility\n\
+        and will be removed in a later version. Re-encode the file with a current version of the tool that produced it.";
+    INVALID_CHAR_TYPE = "IMT0005", "char type has an invalid width",
+        "A character type must be based on `u8`, `u16`, or `u32`, matching UTF-8, UTF-16, and UTF-32 code units.";
+    DUPLICATE_OPTION_ID = "IMT0006", "two options have the same id",
+        "Each option struct (with an `OptionType` attribute) must have an id that is unique within the bundle,\n\
+        because the id is how the kernel distinguishes the options passed to it.";
+    OPTION_KIND_CONFLICT = "IMT0007", "option is both concrete and polymorphic",
+        "A struct has both an `OptionType` attribute and a `PolymorphicOption` attribute.\n\
+        A polymorphic option stands in for any option, so it cannot have an id of its own.";
+    BASE_ON_NON_OPTION = "IMT0008", "option base type on a struct that is not an option",
+        "A struct has an `OptionBaseType` attribute but no `OptionType` or `PolymorphicOption` attribute.";
+    INVALID_OPTION_HEADER = "IMT0009", "option has an invalid header",
+        "The first field of an option struct must be a struct that starts with the `ty` and `flags` fields of the option header,\n\
+        such as `ExtendedOptionHead`. The option struct must not be opaque.";
+    BASE_NOT_POLYMORPHIC = "IMT0010", "option base type is not a polymorphic option",
+        "The type named by an `OptionBaseType` attribute must be a struct with a `PolymorphicOption` attribute.";
+    ENDIAN_ON_NON_INTEGER = "IMT0011", "byte order on a field that is not an integer",
+        "An `Endian` attribute is only meaningful on fields of integer or enum type, or of aliases of them.";
+    ACTIVE_IN_UNDISCRIMINATED_UNION = "IMT0012", "active variant on a member of an undiscriminated union",
+        "A union member has an `ActiveWhen` attribute, but the union has no `DiscriminatedBy` attribute naming its tag.";
+    INVALID_TAGGED_UNION = "IMT0013", "tagged union is invalid",
+        "A union field is discriminated by a tag (named by the union's `DiscriminatedBy` attribute) that is not a field of the same struct.";
+    TAG_NOT_INTEGER = "IMT0014", "tag field is not an integer or enum",
+        "The tag of a discriminated union must be an integer or enum, so that it can be compared with each member's active variant.";
+    ACTIVE_VARIANT_NOT_IN_TAG = "IMT0015", "active variant is not a variant of the tag",
+        "A union member is active for a variant that is not a variant of the enum type of the union's tag field.";
+    ACTIVE_VARIANT_WITHOUT_ENUM = "IMT0016", "active variant of a tag that is not an enum",
+        "A union member is active for a named variant, but the union's tag field is not an enum.";
+    UNEVALUATED_DISCRIMINANT = "IMT0017", "enum discriminant cannot be evaluated",
+        "The discriminant expression of an enum variant could not be evaluated for the target,\n\
+        for example because it refers to a constant that does not exist or overflows.";
+    DISCRIMINANT_OUT_OF_RANGE = "IMT0018", "enum discriminant does not fit the underlying type",
+        "The value of an enum variant's discriminant is outside of the range of the enum's underlying integer type on the target.";
+    DUPLICATE_DISCRIMINANT = "IMT0019", "two enum variants have the same discriminant",
+        "Each variant of an enum must have a distinct discriminant value on every target.";
+    UNDECODABLE_FILE = "IMT0020", "file cannot be decoded",
+        "The file is not a valid IMT file, is truncated, or uses attributes or payload versions this version of imt does not support.";
+    CONST_CYCLE = "IMT0021", "constants depend on each other",
+        "The value of a constant refers to itself, either directly or through other constants, so it cannot be evaluated.\n\
+        Break the cycle by giving one of the constants a value that does not refer to the others.";
+    REEXPORT_SIGNATURE_MISMATCH = "IMT0022", "function does not match the function it re-exports",
+        "A file declares a function, and also re-exports (with `ExportInline`) a function of the same name from another module,\n\
+        but the signatures of the two functions are not ABI-compatible on the target.\n\
+        Signatures are compared after expanding aliases, and integer, enum, and pointer types only need to be passed the same way.";
+    FUNCTION_BY_VALUE = "IMT0023", "function type used by value",
+        "A function type (`fn(...) -> T`) has no size, so it can only be used behind a pointer, or as the type of a type alias.\n\
+        Fields, parameters, return values, array elements, and constants cannot have a function type, even through an alias.\n\
+        Use a pointer to the function type instead.";
+    BROKEN_DOC_LINK = "IMT0024", "documentation links to an item that does not exist",
+        "A line of documentation contains an intra-doc link (such as `[Handle]` or `[base::io::read]`) whose target cannot be resolved.\n\
+        A single name is resolved like a name used in the documented file, and a path is resolved from the root of the bundle or relative to the file.\n\
+        Fix the path, or escape the brackets if the text is not meant to be a link.";
+    RESERVED_NOT_ZEROABLE = "IMT0025", "reserved field cannot be zeroed",
+        "A field with a `Reserved` attribute, or the explicit padding of a struct or union, must be an integer, a byte, or an array of them\n\
+        (possibly through aliases), so that generated code can write zero to it and check that it is zero.";
+    INVALID_GENERIC_PARAM = "IMT0026", "generic parameter is used inconsistently",
+        "An array length refers to a generic parameter (as `$n`) that the enclosing type definition does not have,\n\
+        or that is not in a type definition at all, or a generic parameter is used both as a type and as an array length.\n\
+        A parameter that an array length refers to is a constant parameter, and is given a constant argument (such as `Buffer<{16}>`) rather than a type.";
+    RECURSIVE_TYPE = "IMT0027", "type contains itself by value",
+        "A struct, union, or type alias contains itself by value, either directly or through the fields of other types,\n\
+        so it would have an infinite size. The diagnostic lists the types in the cycle, starting and ending with the same type.\n\
+        Break the cycle by putting one of the fields behind a pointer.";
+    EXPLICIT_PADDING_MISMATCH = "IMT0028", "explicit padding does not match the layout",
+        "The explicit padding of a struct or union (the padding after its last field, declared as `_pad` in generated code) should make its layout explicit on the target.\n\
+        It is reported if its alignment is stricter than that of the fields and any `Align` attribute, which changes the alignment of the type,\n\
+        or if a struct still has implicit padding, before the explicit padding or after it. Change the type of the padding so that it fills the gap exactly.";
+    FIELD_VERSION_ORDER = "IMT0029", "versioned field is out of order",
+        "Fields with a `SinceField` attribute are appended to a struct in a later version, so that the struct as of each earlier version is a prefix of it.\n\
+        They must come after every field without a `SinceField` attribute, in the order of their versions.\n\
+        Unions cannot be extended by appending fields, so the fields of a union cannot have a `SinceField` attribute.";
+    FROZEN_IN_LATER_VERSION = "IMT0030", "item is frozen in a later version than its file",
+        "The `since` version of a `Frozen` attribute is the version of the `SubsystemDescriptor` of the file that the item was frozen in,\n\
+        so it cannot be later than the current version of the file.";
+    FROZEN_USES_UNFROZEN = "IMT0031", "frozen item uses a type that is not frozen",
+        "A frozen struct contains, or a frozen function passes or returns, a struct or enum by value that does not have a `Frozen` attribute.\n\
+        Changing that type would change the layout or signature of the frozen item, so freeze it as well.\n\
+        Type aliases and unions cannot be frozen, so the types they contain by value must be frozen instead. Types behind pointers are not checked.";
+    EXPERIMENTAL_OUTSIDE_MODULE = "IMT0032", "experimental item is outside an experimental module",
+        "An item or file with a `Stability::Experimental` attribute is declared in a module that is not designated for experimental items.\n\
+        Experimental items must be declared in a module with a path component named `experimental` (such as `experimental::net`),\n\
+        where every item is experimental, so that users can tell them apart from the stable interface.";
+    STABLE_USES_EXPERIMENTAL = "IMT0033", "stable item uses an experimental type",
+        "A stable item uses a type that is experimental, because it is in an `experimental` module or has a `Stability::Experimental` attribute,\n\
+        either directly or behind a pointer. Experimental types may change or be removed in any version, which would break the stable item.\n\
+        Mark the item experimental as well, or stabilize the type.";
+}
+
+/// Finds the catalog entry for `code`, ignoring case
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES
+        .iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
@@ -0,0 +1,122 @@
+use core::fmt::Write as _;
+
+use crate::json::string;
+
+use super::{Diagnostic, Severity, codes};
+
+/// The version of SARIF that [`sarif_log`] writes
+pub const SARIF_VERSION: &str = "2.1.0";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Writes `diags` as a SARIF log with a single run of the tool `tool`, so that they can be uploaded to code review systems and editors.
+///
+/// Each diagnostic code that is reported is described by a rule, with the summary and explanation from [`codes::ERROR_CODES`].
+/// IMT files have no source text, so each result is located by the logical location of its file and item (such as `base::io::Pair::a`)
+/// instead of a line and column. Notes follow the message of the result.
+pub fn sarif_log(tool: &str, version: &str, diags: &[Diagnostic]) -> String {
+    let mut out = String::new();
+    // Writing to a `String` does not fail
+    let _ = write_log(&mut out, tool, version, diags);
+    out
+}
+
+fn write_log(
+    out: &mut String,
+    tool: &str,
+    version: &str,
+    diags: &[Diagnostic],
+) -> core::fmt::Result {
+    let mut rules = Vec::new();
+    for diag in diags {
+        if let Some(code) = diag.code
+            && !rules.contains(&code)
+        {
+            rules.push(code);
+        }
+    }
+    rules.sort_unstable();
+
+    write!(
+        out,
+        "{{\"$schema\":{},\"version\":{},\"runs\":[{{",
+        string(SARIF_SCHEMA),
+        string(SARIF_VERSION)
+    )?;
+    write!(
+        out,
+        "\"tool\":{{\"driver\":{{\"name\":{},\"version\":{},\"rules\":[",
+        string(tool),
+        string(version)
+    )?;
+    let mut sep = "";
+    for code in &rules {
+        write!(out, "{sep}{{\"id\":{}", string(code))?;
+        if let Some(entry) = codes::explain(code) {
+            write!(
+                out,
+                ",\"shortDescription\":{{\"text\":{}}},\"fullDescription\":{{\"text\":{}}}",
+                string(entry.summary),
+                string(entry.explanation)
+            )?;
+        }
+        out.push('}');
+        sep = ",";
+    }
+    out.push_str("]}},\"results\":[");
+
+    let mut sep = "";
+    for diag in diags {
+        out.push_str(sep);
+        write_result(out, diag, &rules)?;
+        sep = ",";
+    }
+    out.push_str("]}]}");
+    Ok(())
+}
+
+fn write_result(out: &mut String, diag: &Diagnostic, rules: &[&str]) -> core::fmt::Result {
+    let level = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let mut message = diag.message.clone();
+    for note in &diag.notes {
+        let _ = write!(message, "\nnote: {note}");
+    }
+
+    out.push('{');
+    if let Some(code) = diag.code {
+        write!(out, "\"ruleId\":{},", string(code))?;
+        if let Ok(index) = rules.binary_search(&code) {
+            write!(out, "\"ruleIndex\":{index},")?;
+        }
+    }
+    write!(
+        out,
+        "\"level\":\"{level}\",\"message\":{{\"text\":{}}},",
+        string(&message)
+    )?;
+
+    let name = match &diag.item {
+        Some(item) => item.rsplit("::").next().unwrap_or(item),
+        None => diag.file.0.last().map_or("", |name| &**name),
+    };
+    let fully_qualified = match &diag.item {
+        Some(item) if diag.file.0.is_empty() => item.clone(),
+        Some(item) => format!("{}::{item}", diag.file),
+        None => diag.file.to_string(),
+    };
+    write!(
+        out,
+        "\"locations\":[{{\"logicalLocations\":[{{\"name\":{},\"fullyQualifiedName\":{}",
+        string(name),
+        string(&fully_qualified)
+    )?;
+    // The item may be a type, a function, or a constant, which the diagnostic does not say
+    if diag.item.is_none() {
+        out.push_str(",\"kind\":\"module\"");
+    }
+    out.push_str("}]}]}");
+    Ok(())
+}
@@ -0,0 +1,811 @@
+use crate::{
+    abi::Scope,
+    attr::{
+        Attribute, attribute_info, find_attribute, find_attribute_mut,
+        types::{SinceField, SubsystemDescriptor},
+    },
+    bundle::{Bundle, Path},
+    eval::Evaluator,
+    file::File,
+    header::Version,
+    intern::Symbol,
+    layout::{Layout, LayoutEngine},
+    lock::{LockMismatch, Lockfile, content_hash},
+    resolve::{Item, module_of},
+    strip::StripOptions,
+    target::TargetSpec,
+    tydef::{Field, Struct, StructBody, TypeDef, TypeDefBody},
+    uses::{Signature, Type},
+    uuid::Uuid,
+    value::ValueBody,
+};
+
+/// How a change affects users of an interface, from least to most severe
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Only documentation or tool comments changed
+    Docs,
+    /// Something was added, which existing users are unaffected by
+    Compatible,
+    /// Something was removed or changed, which may break existing users
+    Breaking,
+}
+
+impl core::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Docs => "docs",
+            Severity::Compatible => "compatible",
+            Severity::Breaking => "breaking",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// The item changed. `docs_only` is set if the item is the same once docs and tool comments are stripped.
+    /// `abi_compatible` is set if the item is a function whose signature is ABI-compatible with its old signature (see [`diff_target`])
+    Changed {
+        docs_only: bool,
+        abi_compatible: bool,
+    },
+}
+
+/// What an [`ItemChange`] is a change to
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ChangeSubject {
+    /// The type or value at the path of the change
+    Item,
+    /// The `use` of the given path by the module at the path of the change (see [`UseItem`](crate::file::UseItem))
+    Use(Path),
+    /// The file attributes with the given id of the module at the path of the change
+    Attribute(Uuid),
+    /// The field with the given name of the struct or union at the path of the change
+    Field(Symbol),
+    /// The field with the given name that was appended to the struct at the path of the change, after every field of the old revision,
+    /// in a version after the version of the old revision (see [`SinceField`]). Users of the old revision are unaffected by it
+    AppendedField(Symbol),
+    /// The variant with the given name of the enum at the path of the change
+    Variant(Symbol),
+}
+
+/// A change to a single type, value, field, variant, `use`, or kind of file attribute between two revisions of a bundle
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ItemChange {
+    /// The path of the item (or the type of the field or variant), or the module of the `use` or file attribute
+    pub path: Path,
+    pub subject: ChangeSubject,
+    pub kind: ChangeKind,
+}
+
+impl ItemChange {
+    /// How the change affects users. Adding a field changes the layout of its type, so unlike adding anything else, it is breaking
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            ChangeKind::Added if matches!(self.subject, ChangeSubject::Field(_)) => {
+                Severity::Breaking
+            }
+            ChangeKind::Added => Severity::Compatible,
+            ChangeKind::Changed {
+                docs_only: true, ..
+            } => Severity::Docs,
+            ChangeKind::Changed {
+                abi_compatible: true,
+                ..
+            } => Severity::Compatible,
+            ChangeKind::Removed | ChangeKind::Changed { .. } => Severity::Breaking,
+        }
+    }
+
+    /// The module that the changed item, field, variant, `use`, or file attribute is in
+    pub fn module(&self) -> Path {
+        match self.subject {
+            ChangeSubject::Item
+            | ChangeSubject::Field(_)
+            | ChangeSubject::AppendedField(_)
+            | ChangeSubject::Variant(_) => module_of(&self.path),
+            ChangeSubject::Use(_) | ChangeSubject::Attribute(_) => self.path.clone(),
+        }
+    }
+
+    /// The fingerprint of the change in a [`Baseline`](crate::baseline::Baseline), from its subject and whether it was added, removed, or changed
+    pub fn fingerprint(&self) -> u64 {
+        let kind = match self.kind {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed { .. } => "changed",
+        };
+        let path = self.path.to_string();
+        match &self.subject {
+            ChangeSubject::Item => crate::baseline::fingerprint(&["item-change", kind, &path]),
+            ChangeSubject::Use(used) => {
+                crate::baseline::fingerprint(&["use-change", kind, &path, &used.to_string()])
+            }
+            ChangeSubject::Attribute(id) => {
+                crate::baseline::fingerprint(&["attribute-change", kind, &path, &id.to_string()])
+            }
+            ChangeSubject::Field(field) | ChangeSubject::AppendedField(field) => {
+                crate::baseline::fingerprint(&["field-change", kind, &path, field])
+            }
+            ChangeSubject::Variant(variant) => {
+                crate::baseline::fingerprint(&["variant-change", kind, &path, variant])
+            }
+        }
+    }
+}
+
+/// The name of the attribute type with the id `id`, or the id itself if it is not recognized
+pub(crate) fn attribute_name(id: Uuid) -> String {
+    match attribute_info(id) {
+        Some(info) => info.name.to_string(),
+        None => id.to_string(),
+    }
+}
+
+impl core::fmt::Display for ItemChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = match &self.subject {
+            ChangeSubject::Item => self.path.to_string(),
+            ChangeSubject::Use(used) => format!("use {used} in {}", self.path),
+            ChangeSubject::Attribute(id) => {
+                format!("attribute {} of {}", attribute_name(*id), self.path)
+            }
+            ChangeSubject::Field(field) => format!("field {field} of {}", self.path),
+            ChangeSubject::AppendedField(field) => {
+                format!("field {field} of {} (appended)", self.path)
+            }
+            ChangeSubject::Variant(variant) => format!("variant {variant} of {}", self.path),
+        };
+        match self.kind {
+            ChangeKind::Added => f.write_fmt(format_args!("added {path}")),
+            ChangeKind::Removed => f.write_fmt(format_args!("removed {path}")),
+            ChangeKind::Changed {
+                docs_only: true, ..
+            } => f.write_fmt(format_args!("changed docs of {path}")),
+            ChangeKind::Changed {
+                abi_compatible: true,
+                ..
+            } => f.write_fmt(format_args!("changed {path} (ABI compatible)")),
+            ChangeKind::Changed { .. } => f.write_fmt(format_args!("changed {path}")),
+        }
+    }
+}
+
+/// The changes to the items of a bundle between two revisions
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BundleDiff {
+    pub changes: Vec<ItemChange>,
+}
+
+impl BundleDiff {
+    /// The most severe change, or `None` if nothing changed
+    pub fn severity(&self) -> Option<Severity> {
+        self.changes.iter().map(ItemChange::severity).max()
+    }
+
+    /// The changes to the items, uses, and attributes of the module `module` (see [`ItemChange::module`])
+    pub fn in_module(&self, module: &Path) -> BundleDiff {
+        BundleDiff {
+            changes: self
+                .changes
+                .iter()
+                .filter(|change| change.module() == *module)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The version that a revision with these changes should have, if the old revision had version `old`.
+    ///
+    /// Breaking changes increment the major version, except that versions with a major version of 0 increment the minor version instead,
+    /// as minor versions of 0.x are not compatible with each other.
+    /// Returns `None` if the version that would be incremented is exhausted, as the suggested version would be out of range for [`Version`].
+    pub fn suggest_version(&self, old: Version) -> Option<Version> {
+        match self.severity() {
+            Some(Severity::Breaking) if old.major() != 0 => Version::try_new(old.major() + 1, 0),
+            Some(Severity::Breaking | Severity::Compatible) => {
+                Version::try_new(old.major(), old.minor() + 1)
+            }
+            Some(Severity::Docs) | None => Some(old),
+        }
+    }
+}
+
+/// Compares the types, values, `use`s, and file attributes of two revisions of a bundle.
+///
+/// The fields and variants of a changed struct, union, or enum are compared by name, and are reported as changes of their own.
+/// The type itself is then only reported as changed if anything else about it changed, such as its attributes or the order of its fields.
+/// Adding a field is breaking, unless it is appended to a struct in a version after the version of the old revision of its module
+/// (see [`ChangeSubject::AppendedField`]).
+/// File attributes are compared by attribute id, except that the version in a [`SubsystemDescriptor`] is ignored,
+/// as it is what [`BundleDiff::suggest_version`] is used to determine.
+pub fn diff(old: &Bundle, new: &Bundle) -> BundleDiff {
+    let stripped = |bundle: &Bundle| {
+        let mut bundle = bundle.clone();
+        bundle.strip(StripOptions::DOCS | StripOptions::TOOL_COMMENTS);
+        bundle
+    };
+    let (old_stripped, new_stripped) = (stripped(old), stripped(new));
+    let (old_lock, new_lock) = (
+        Lockfile::freeze(&old_stripped),
+        Lockfile::freeze(&new_stripped),
+    );
+
+    let mut changes = Vec::new();
+    for change in Lockfile::freeze(old).check(new) {
+        match change {
+            LockMismatch::Added(path) => changes.push(ItemChange {
+                path,
+                subject: ChangeSubject::Item,
+                kind: ChangeKind::Added,
+            }),
+            LockMismatch::Removed(path) => changes.push(ItemChange {
+                path,
+                subject: ChangeSubject::Item,
+                kind: ChangeKind::Removed,
+            }),
+            LockMismatch::Changed(path) => {
+                let defs = [old, new, &old_stripped, &new_stripped].map(|b| type_def(b, &path));
+                let old_version = old
+                    .get(&module_of(&path))
+                    .and_then(|file| find_attribute::<SubsystemDescriptor, _>(&file.attributes))
+                    .map(|desc| desc.version);
+                if let [Some(old), Some(new), Some(old_stripped), Some(new_stripped)] = defs
+                    && member_changes(
+                        &path,
+                        [old, new],
+                        [old_stripped, new_stripped],
+                        old_version,
+                        &mut changes,
+                    )
+                {
+                    continue;
+                }
+                let docs_only = old_lock.items.get(&path) == new_lock.items.get(&path);
+                changes.push(ItemChange {
+                    kind: ChangeKind::Changed {
+                        docs_only,
+                        abi_compatible: docs_only,
+                    },
+                    path,
+                    subject: ChangeSubject::Item,
+                });
+            }
+        }
+    }
+
+    let added_modules = new.iter().filter(|(module, _)| old.get(module).is_none());
+    for (module, _) in old.iter().chain(added_modules) {
+        let files = [old.get(module), new.get(module)];
+        let stripped = [old_stripped.get(module), new_stripped.get(module)];
+        module_changes(module, files, stripped, &mut changes);
+    }
+
+    BundleDiff { changes }
+}
+
+/// The kind of change between the `old` and `new` attributes of a `use` or file, which are `None` if absent,
+/// or `None` if they are the same. `docs_only` is whether they are the same once docs and tool comments are stripped
+fn subject_change<T: PartialEq>(
+    old: Option<T>,
+    new: Option<T>,
+    docs_only: bool,
+) -> Option<ChangeKind> {
+    match (old, new) {
+        (old, new) if old == new => None,
+        _ if docs_only => Some(ChangeKind::Changed {
+            docs_only: true,
+            abi_compatible: true,
+        }),
+        (None, _) => Some(ChangeKind::Added),
+        (_, None) => Some(ChangeKind::Removed),
+        _ => Some(ChangeKind::Changed {
+            docs_only: false,
+            abi_compatible: false,
+        }),
+    }
+}
+
+fn type_def<'a>(bundle: &'a Bundle, path: &Path) -> Option<&'a TypeDef> {
+    let file = bundle.get(&module_of(path))?;
+    match Item::find_in(file, path.name()?)? {
+        Item::Type(def) => Some(def),
+        Item::Value(_) => None,
+    }
+}
+
+/// The fields of a struct or union, or the variants of an enum, with the content hash of each,
+/// or `None` if `def` is an alias or opaque struct
+fn members(def: &TypeDef) -> Option<Vec<(ChangeSubject, u64)>> {
+    let fields = |fields: &[Field]| {
+        fields
+            .iter()
+            .map(|field| {
+                (
+                    ChangeSubject::Field(field.name.clone()),
+                    content_hash(field),
+                )
+            })
+            .collect()
+    };
+    match &def.body {
+        TypeDefBody::Struct(Struct {
+            body: StructBody::Fields(body),
+            ..
+        }) => Some(fields(&body.field)),
+        TypeDefBody::Union(union) => Some(fields(&union.fields.field)),
+        TypeDefBody::Enum(en) => Some(
+            en.variants
+                .iter()
+                .map(|variant| {
+                    let subject = ChangeSubject::Variant(variant.name.clone());
+                    (subject, content_hash(variant))
+                })
+                .collect(),
+        ),
+        TypeDefBody::Alias(_) | TypeDefBody::Struct(_) => None,
+    }
+}
+
+/// The content hash of `def` without its fields or variants. The names of the fields of a struct are kept in order,
+/// as reordering them changes its layout
+fn hash_without_members(def: &TypeDef) -> u64 {
+    let mut def = def.clone();
+    match &mut def.body {
+        TypeDefBody::Struct(Struct {
+            body: StructBody::Fields(body),
+            ..
+        }) => {
+            for field in &mut body.field {
+                field.attrs.clear();
+                field.ty = Type::Never;
+            }
+        }
+        TypeDefBody::Union(union) => union.fields.field.clear(),
+        TypeDefBody::Enum(en) => en.variants.clear(),
+        TypeDefBody::Alias(_) | TypeDefBody::Struct(_) => {}
+    }
+    content_hash(&def)
+}
+
+/// The fields of the `new` revision of a struct that come after every field of its `old` revision, and have a [`SinceField`] attribute
+/// with a version after `old_version`, the version of the module of the old revision
+fn appended_fields(old: &TypeDef, new: &TypeDef, old_version: Option<Version>) -> Vec<Symbol> {
+    let (
+        TypeDefBody::Struct(Struct {
+            body: StructBody::Fields(old),
+            ..
+        }),
+        TypeDefBody::Struct(Struct {
+            body: StructBody::Fields(new),
+            ..
+        }),
+        Some(old_version),
+    ) = (&old.body, &new.body, old_version)
+    else {
+        return Vec::new();
+    };
+    if new.field.len() < old.field.len()
+        || old
+            .field
+            .iter()
+            .zip(&new.field)
+            .any(|(old, new)| old.name != new.name)
+    {
+        return Vec::new();
+    }
+    new.field[old.field.len()..]
+        .iter()
+        .filter(|field| {
+            find_attribute::<SinceField, _>(&field.attrs)
+                .is_some_and(|since| since.version > old_version)
+        })
+        .map(|field| field.name.clone())
+        .collect()
+}
+
+/// Compares the fields or variants of two revisions of the type definition at `path`, and then the rest of the definition.
+/// `old_version` is the version of the module of the old revision, if it has one.
+/// Returns `false` without comparing anything if the revisions are not both structs with fields, unions, or enums
+fn member_changes(
+    path: &Path,
+    [old, new]: [&TypeDef; 2],
+    [old_stripped, new_stripped]: [&TypeDef; 2],
+    old_version: Option<Version>,
+    changes: &mut Vec<ItemChange>,
+) -> bool {
+    if core::mem::discriminant(&old.body) != core::mem::discriminant(&new.body) {
+        return false;
+    }
+    let (Some(old_members), Some(new_members)) = (members(old), members(new)) else {
+        return false;
+    };
+    let stripped_members = [members(old_stripped), members(new_stripped)];
+    let find = |members: Option<&Vec<(ChangeSubject, u64)>>, subject: &ChangeSubject| {
+        members?
+            .iter()
+            .find(|(other, _)| other == subject)
+            .map(|(_, hash)| *hash)
+    };
+    let appended = appended_fields(old, new, old_version);
+
+    let mut seen = Vec::new();
+    for (subject, _) in old_members.iter().chain(&new_members) {
+        if seen.contains(&subject) {
+            continue;
+        }
+        seen.push(subject);
+        let docs_only = find(stripped_members[0].as_ref(), subject)
+            == find(stripped_members[1].as_ref(), subject);
+        let kind = subject_change(
+            find(Some(&old_members), subject),
+            find(Some(&new_members), subject),
+            docs_only,
+        );
+        if let Some(kind) = kind {
+            let subject = match subject {
+                ChangeSubject::Field(field) if appended.contains(field) => {
+                    ChangeSubject::AppendedField(field.clone())
+                }
+                subject => subject.clone(),
+            };
+            changes.push(ItemChange {
+                path: path.clone(),
+                subject,
+                kind,
+            });
+        }
+    }
+
+    // Fields that were added or removed are reported above, so only the order of the others matters
+    let [old, new, old_stripped, new_stripped] =
+        [old, new, old_stripped, new_stripped].map(|def| {
+            let mut def = def.clone();
+            if let TypeDefBody::Struct(Struct {
+                body: StructBody::Fields(body),
+                ..
+            }) = &mut def.body
+            {
+                body.field.retain(|field| {
+                    let subject = ChangeSubject::Field(field.name.clone());
+                    find(Some(&old_members), &subject).is_some()
+                        && find(Some(&new_members), &subject).is_some()
+                });
+            }
+            hash_without_members(&def)
+        });
+    if old != new {
+        let docs_only = old_stripped == new_stripped;
+        changes.push(ItemChange {
+            path: path.clone(),
+            subject: ChangeSubject::Item,
+            kind: ChangeKind::Changed {
+                docs_only,
+                abi_compatible: docs_only,
+            },
+        });
+    }
+    true
+}
+
+/// The attributes of `file` with the id `id` (if it has any), with the version of a [`SubsystemDescriptor`] cleared
+fn file_attributes(file: Option<&File>, id: Uuid) -> Option<Vec<Attribute<File>>> {
+    let mut attrs = file?
+        .attributes
+        .iter()
+        .filter(|attr| *attr.id() == id)
+        .cloned()
+        .collect::<Vec<_>>();
+    if let Some(desc) = find_attribute_mut::<SubsystemDescriptor, _>(&mut attrs) {
+        desc.version = Version::default();
+    }
+    Some(attrs).filter(|attrs| !attrs.is_empty())
+}
+
+/// Compares the `use`s and file attributes of the module `module`, which is absent from a revision if its file is `None`
+fn module_changes(
+    module: &Path,
+    [old, new]: [Option<&File>; 2],
+    [old_stripped, new_stripped]: [Option<&File>; 2],
+    changes: &mut Vec<ItemChange>,
+) {
+    let use_attrs = |file: Option<&File>, used: &[String]| {
+        file?
+            .uses
+            .iter()
+            .find(|use_item| use_item.path == used)
+            .map(|use_item| use_item.attrs.clone())
+    };
+    let mut used = Vec::new();
+    for use_item in old.into_iter().chain(new).flat_map(|file| &file.uses) {
+        if used.contains(&&use_item.path) {
+            continue;
+        }
+        used.push(&use_item.path);
+        let docs_only =
+            use_attrs(old_stripped, &use_item.path) == use_attrs(new_stripped, &use_item.path);
+        let kind = subject_change(
+            use_attrs(old, &use_item.path),
+            use_attrs(new, &use_item.path),
+            docs_only,
+        );
+        if let Some(kind) = kind {
+            changes.push(ItemChange {
+                path: module.clone(),
+                subject: ChangeSubject::Use(Path(use_item.path.clone())),
+                kind,
+            });
+        }
+    }
+
+    let mut ids = Vec::new();
+    for attr in old.into_iter().chain(new).flat_map(|file| &file.attributes) {
+        let id = *attr.id();
+        if ids.contains(&id) {
+            continue;
+        }
+        ids.push(id);
+        let docs_only = file_attributes(old_stripped, id) == file_attributes(new_stripped, id);
+        let kind = subject_change(
+            file_attributes(old, id),
+            file_attributes(new, id),
+            docs_only,
+        );
+        if let Some(kind) = kind {
+            changes.push(ItemChange {
+                path: module.clone(),
+                subject: ChangeSubject::Attribute(id),
+                kind,
+            });
+        }
+    }
+}
+
+fn function_signature<'a>(bundle: &'a Bundle, path: &Path) -> Option<&'a Signature> {
+    let file = bundle.get(&module_of(path))?;
+    match Item::find_in(file, path.name()?)? {
+        Item::Value(val) => match &val.body {
+            ValueBody::Function(f) => Some(&f.signature),
+            ValueBody::Const(_) => None,
+        },
+        Item::Type(_) => None,
+    }
+}
+
+/// Compares the types and values of two revisions of a bundle like [`diff`], and also determines which changed functions are still
+/// ABI-compatible on `target`, so that existing callers continue to work. See [`Signature::abi_eq`]
+pub fn diff_target(old: &Bundle, new: &Bundle, target: &TargetSpec) -> BundleDiff {
+    let mut diff = diff(old, new);
+    for change in &mut diff.changes {
+        let (ChangeSubject::Item, ChangeKind::Changed { abi_compatible, .. }) =
+            (&change.subject, &mut change.kind)
+        else {
+            continue;
+        };
+        let module = module_of(&change.path);
+        if let (Some(old_sig), Some(new_sig)) = (
+            function_signature(old, &change.path),
+            function_signature(new, &change.path),
+        ) {
+            *abi_compatible |= old_sig.abi_eq(
+                new_sig,
+                target,
+                Scope::new(old, &module),
+                Scope::new(new, &module),
+            );
+        }
+    }
+    diff
+}
+
+/// How a change to a frozen item (one with a [`Frozen`](crate::attr::types::Frozen) attribute) affects its layout or signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrozenChangeKind {
+    Removed,
+    /// The item no longer has a [`Frozen`](crate::attr::types::Frozen) attribute with the same version
+    Unfrozen,
+    /// The item became a different kind of item, such as a struct that became a union, or its number of generic parameters changed
+    KindChanged,
+    /// The size or alignment of the type changed
+    Layout {
+        old: Layout,
+        new: Layout,
+    },
+    /// The offset or size of the field changed, or the field was added or removed
+    Field(Symbol),
+    /// The underlying type of the enum changed
+    Underlying,
+    /// The value of the variant changed, or the variant was removed
+    Variant(Symbol),
+    /// The signature of the function is not ABI-compatible with its old signature
+    Signature,
+}
+
+/// A change to a frozen item that is not allowed, found by [`frozen_changes`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrozenChange {
+    pub path: Path,
+    /// The version the item was frozen in
+    pub since: Version,
+    pub kind: FrozenChangeKind,
+}
+
+impl FrozenChange {
+    /// The fingerprint of the change in a [`Baseline`](crate::baseline::Baseline), from its path and kind.
+    /// Changes to the layout of the same item are the same finding, regardless of the old and new layout
+    pub fn fingerprint(&self) -> u64 {
+        let path = self.path.to_string();
+        let parts: &[&str] = match &self.kind {
+            FrozenChangeKind::Removed => &["removed"],
+            FrozenChangeKind::Unfrozen => &["unfrozen"],
+            FrozenChangeKind::KindChanged => &["kind-changed"],
+            FrozenChangeKind::Layout { .. } => &["layout"],
+            FrozenChangeKind::Field(field) => &["field", field],
+            FrozenChangeKind::Underlying => &["underlying"],
+            FrozenChangeKind::Variant(variant) => &["variant", variant],
+            FrozenChangeKind::Signature => &["signature"],
+        };
+        crate::baseline::fingerprint(&[&["frozen-change", &path], parts].concat())
+    }
+}
+
+impl core::fmt::Display for FrozenChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = &self.path;
+        f.write_fmt(format_args!("{path} is frozen since {}, but ", self.since))?;
+        match &self.kind {
+            FrozenChangeKind::Removed => f.write_str("was removed"),
+            FrozenChangeKind::Unfrozen => {
+                f.write_str("its Frozen attribute was removed or changed")
+            }
+            FrozenChangeKind::KindChanged => f.write_str("it became a different kind of item"),
+            FrozenChangeKind::Layout { old, new } => f.write_fmt(format_args!(
+                "its size and alignment changed from {} and {} to {} and {}",
+                old.size, old.align, new.size, new.align
+            )),
+            FrozenChangeKind::Field(field) => {
+                f.write_fmt(format_args!("the layout of its field {field} changed"))
+            }
+            FrozenChangeKind::Underlying => f.write_str("its underlying type changed"),
+            FrozenChangeKind::Variant(variant) => {
+                f.write_fmt(format_args!("the value of its variant {variant} changed"))
+            }
+            FrozenChangeKind::Signature => {
+                f.write_str("its signature is not ABI-compatible with its old signature")
+            }
+        }
+    }
+}
+
+/// Finds the changes between two revisions of a bundle that affect the layout (on `target`) or signature of an item that is frozen in `old`.
+///
+/// Changes to documentation, names of fields, and types that have the same layout are allowed, and so is adding variants to a frozen enum.
+/// Items whose layout cannot be computed in either revision are left to validation
+pub fn frozen_changes(old: &Bundle, new: &Bundle, target: &TargetSpec) -> Vec<FrozenChange> {
+    let mut engines = [
+        LayoutEngine::new(old, target),
+        LayoutEngine::new(new, target),
+    ];
+    let mut evals = [Evaluator::new(old, target), Evaluator::new(new, target)];
+    let mut changes = Vec::new();
+
+    for (module, file) in old {
+        let items = file
+            .types
+            .iter()
+            .map(Item::Type)
+            .chain(file.values.iter().map(Item::Value));
+        for old_item in items {
+            let Some(since) = old_item.frozen() else {
+                continue;
+            };
+            let path = module.join(old_item.name());
+            let mut push = |kind| {
+                changes.push(FrozenChange {
+                    path: path.clone(),
+                    since,
+                    kind,
+                })
+            };
+            let Some(new_item) = new
+                .get(module)
+                .and_then(|file| Item::find_in(file, old_item.name()))
+            else {
+                push(FrozenChangeKind::Removed);
+                continue;
+            };
+            if new_item.frozen() != Some(since) {
+                push(FrozenChangeKind::Unfrozen);
+            }
+
+            match (old_item, new_item) {
+                (Item::Type(a), Item::Type(b)) => {
+                    for kind in typedef_changes(module, [a, b], &mut engines, &mut evals) {
+                        push(kind);
+                    }
+                }
+                (Item::Value(a), Item::Value(b)) => match (&a.body, &b.body) {
+                    (ValueBody::Function(a), ValueBody::Function(b)) => {
+                        if !a.signature.abi_eq(
+                            &b.signature,
+                            target,
+                            Scope::new(old, module),
+                            Scope::new(new, module),
+                        ) {
+                            push(FrozenChangeKind::Signature);
+                        }
+                    }
+                    _ => push(FrozenChangeKind::KindChanged),
+                },
+                _ => push(FrozenChangeKind::KindChanged),
+            }
+        }
+    }
+    changes
+}
+
+/// The changes to the layout of a frozen type definition, defined in `module` in both revisions
+fn typedef_changes(
+    module: &Path,
+    [a, b]: [&TypeDef; 2],
+    [old, new]: &mut [LayoutEngine; 2],
+    evals: &mut [Evaluator; 2],
+) -> Vec<FrozenChangeKind> {
+    let mut changes = Vec::new();
+    match (&a.body, &b.body) {
+        (TypeDefBody::Struct(sa), TypeDefBody::Struct(sb)) if a.num_params == b.num_params => {
+            if a.num_params != 0 {
+                return changes;
+            }
+            if let (Ok(la), Ok(lb)) = (old.layout_of_def(module, a), new.layout_of_def(module, b))
+                && la != lb
+            {
+                changes.push(FrozenChangeKind::Layout { old: la, new: lb });
+            }
+            let (StructBody::Fields(fa), StructBody::Fields(fb)) = (&sa.body, &sb.body) else {
+                if matches!(sa.body, StructBody::Fields(_))
+                    != matches!(sb.body, StructBody::Fields(_))
+                {
+                    changes.push(FrozenChangeKind::KindChanged);
+                }
+                return changes;
+            };
+            let (Ok(fa), Ok(fb)) = (old.field_layouts(module, fa), new.field_layouts(module, fb))
+            else {
+                return changes;
+            };
+            for n in 0..fa.len().max(fb.len()) {
+                match (fa.get(n), fb.get(n)) {
+                    (Some(a), Some(b)) if a.offset == b.offset && a.layout == b.layout => {}
+                    (Some(field), _) | (None, Some(field)) => {
+                        changes.push(FrozenChangeKind::Field(field.name.clone()));
+                    }
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (TypeDefBody::Enum(ea), TypeDefBody::Enum(eb)) => {
+            if ea.underlying != eb.underlying {
+                changes.push(FrozenChangeKind::Underlying);
+            }
+            for variant in &ea.variants {
+                let old_value = evals[0].eval(module, &variant.discrim);
+                let new_value = eb
+                    .variants
+                    .iter()
+                    .find(|other| other.name == variant.name)
+                    .map(|other| evals[1].eval(module, &other.discrim));
+                match (old_value, new_value) {
+                    (Ok(a), Some(Ok(b))) if a == b => {}
+                    // Discriminants that cannot be evaluated are reported by validation
+                    (Err(_), _) | (_, Some(Err(_))) => {}
+                    _ => changes.push(FrozenChangeKind::Variant(variant.name.clone())),
+                }
+            }
+        }
+        _ => changes.push(FrozenChangeKind::KindChanged),
+    }
+    changes
+}
@@ -0,0 +1,441 @@
+use core::ops::Range;
+
+use crate::{
+    attr::{
+        Attribute, AttributeTarget, find_attribute, find_attributes,
+        types::{ErrorDoc, ExampleDoc, ItemDoc, LocalizedDoc, ParamDoc, ReturnDoc},
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    intern::Symbol,
+    resolve::{ResolveError, resolve_export, resolve_name},
+    tydef::{StructBody, TypeDefBody},
+    value::ValueBody,
+    visit::{AttributeVisitorMut, visit_file_mut},
+};
+
+/// The documentation of an item, or of a member of an item
+#[derive(Clone, Debug)]
+pub struct ItemDocs<'a> {
+    /// The item (or member of an item, such as `Struct::field`, `function::param`, or `function::return`) the documentation is about,
+    /// or `None` for the documentation of the file itself
+    pub item: Option<String>,
+    pub lines: &'a [Symbol],
+}
+
+/// Every documentation attribute of `file` that contains prose, which are [`ItemDoc`], [`ParamDoc`], [`ReturnDoc`], and [`ErrorDoc`].
+/// The documentation of members follows the documentation of the item they belong to
+pub fn file_docs(file: &File) -> Vec<ItemDocs<'_>> {
+    let mut docs = Vec::new();
+
+    push_doc(
+        &mut docs,
+        None,
+        find_attribute::<ItemDoc, _>(&file.attributes),
+    );
+
+    for ty in &file.types {
+        push_doc(
+            &mut docs,
+            Some(ty.name.to_string()),
+            ty.body.find_attribute::<ItemDoc>(),
+        );
+        let fields = match &ty.body {
+            TypeDefBody::Struct(st) => match &st.body {
+                StructBody::Fields(fields) => &fields.field[..],
+                StructBody::Opaque(_) => &[],
+            },
+            TypeDefBody::Union(un) => &un.fields.field[..],
+            TypeDefBody::Enum(en) => {
+                for variant in &en.variants {
+                    push_doc(
+                        &mut docs,
+                        Some(format!("{}::{}", ty.name, variant.name)),
+                        find_attribute::<ItemDoc, _>(&variant.attrs),
+                    );
+                }
+                &[]
+            }
+            TypeDefBody::Alias(_) => &[],
+        };
+        for field in fields {
+            push_doc(
+                &mut docs,
+                Some(format!("{}::{}", ty.name, field.name)),
+                find_attribute::<ItemDoc, _>(&field.attrs),
+            );
+        }
+    }
+
+    for val in &file.values {
+        push_doc(
+            &mut docs,
+            Some(val.name.to_string()),
+            val.body.find_attribute::<ItemDoc>(),
+        );
+        let ValueBody::Function(f) = &val.body else {
+            continue;
+        };
+        for (i, param) in f.signature.params.iter().enumerate() {
+            let item = match &param.name {
+                Some(name) => format!("{}::{name}", val.name),
+                None => format!("{}::#{i}", val.name),
+            };
+            let doc = find_attribute::<ParamDoc, _>(&param.attrs).map(|doc| &*doc.doc_lines);
+            push_lines(&mut docs, Some(item), doc);
+        }
+        let doc = find_attribute::<ReturnDoc, _>(&f.signature.ret_attrs).map(|doc| &*doc.doc_lines);
+        push_lines(&mut docs, Some(format!("{}::return", val.name)), doc);
+        for doc in find_attributes::<ErrorDoc, _>(&f.attrs) {
+            push_lines(
+                &mut docs,
+                Some(format!("{}::{}", val.name, doc.error)),
+                Some(&doc.doc_lines),
+            );
+        }
+    }
+
+    docs
+}
+
+fn push_doc<'a>(docs: &mut Vec<ItemDocs<'a>>, item: Option<String>, doc: Option<&'a ItemDoc>) {
+    push_lines(docs, item, doc.map(|doc| &*doc.doc_lines));
+}
+
+fn push_lines<'a>(docs: &mut Vec<ItemDocs<'a>>, item: Option<String>, lines: Option<&'a [Symbol]>) {
+    if let Some(lines) = lines {
+        docs.push(ItemDocs { item, lines });
+    }
+}
+
+/// The language of a fenced code block, if `line` opens or closes one (such as ```` ```c ````). A closing fence has an empty language
+fn fence(line: &str) -> Option<&str> {
+    let info = line.trim_start().strip_prefix("```")?;
+    Some(info.split([',', ' ']).next().unwrap_or_default().trim())
+}
+
+/// The lines of documentation that are not part of a fenced code block (including the fences themselves)
+pub fn prose_lines(lines: &[Symbol]) -> Vec<&str> {
+    let mut prose = Vec::new();
+    let mut in_code = false;
+    for line in lines {
+        if fence(line).is_some() {
+            in_code = !in_code;
+        } else if !in_code {
+            prose.push(&**line);
+        }
+    }
+    prose
+}
+
+/// A code example in the documentation of an item
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Example {
+    /// The item the example documents, as in [`ItemDocs::item`]
+    pub item: Option<String>,
+    /// The language of the example (such as `c` or `rust`), or empty if the example does not give one
+    pub lang: String,
+    pub code: String,
+}
+
+/// The fenced code blocks in `lines`, as `(lang, code)` pairs. A block that is not closed extends to the end of `lines`
+pub fn code_blocks(lines: &[Symbol]) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for line in lines {
+        match (fence(line), &mut current) {
+            (Some(_), Some(_)) => blocks.extend(current.take()),
+            (Some(lang), None) => current = Some((lang.to_string(), String::new())),
+            (None, Some((_, code))) => {
+                code.push_str(line);
+                code.push('\n');
+            }
+            (None, None) => {}
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+/// Every code example in `file`: the fenced code blocks in its documentation (see [`file_docs`]),
+/// followed by the [`ExampleDoc`] attributes of the file and of its types and values.
+///
+/// This allows a tool to compile and run the examples of a bundle, such as to test the documentation of syscall wrappers.
+pub fn extract_examples(file: &File) -> Vec<Example> {
+    let mut examples = Vec::new();
+    for docs in file_docs(file) {
+        for (lang, code) in code_blocks(docs.lines) {
+            examples.push(Example {
+                item: docs.item.clone(),
+                lang,
+                code,
+            });
+        }
+    }
+
+    let mut push = |item: Option<String>, docs: Vec<&ExampleDoc>| {
+        for doc in docs {
+            let mut code = String::new();
+            for line in &doc.code {
+                code.push_str(line);
+                code.push('\n');
+            }
+            examples.push(Example {
+                item: item.clone(),
+                lang: doc.lang.clone(),
+                code,
+            });
+        }
+    };
+
+    push(None, find_attributes(&file.attributes).collect());
+    for ty in &file.types {
+        let docs = match &ty.body {
+            TypeDefBody::Alias(alias) => find_attributes(&alias.attrs).collect(),
+            TypeDefBody::Struct(st) => find_attributes(&st.attrs).collect(),
+            TypeDefBody::Union(un) => find_attributes(&un.attrs).collect(),
+            TypeDefBody::Enum(en) => find_attributes(&en.attrs).collect(),
+        };
+        push(Some(ty.name.to_string()), docs);
+    }
+    for val in &file.values {
+        let docs = match &val.body {
+            ValueBody::Const(c) => find_attributes(&c.attrs).collect(),
+            ValueBody::Function(f) => find_attributes(&f.attrs).collect(),
+        };
+        push(Some(val.name.to_string()), docs);
+    }
+
+    examples
+}
+
+/// An intra-doc link in a line of documentation, such as `[Handle]`, `` [`base::io::read`] ``, or `[the handle][Handle]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocLink {
+    /// The bytes of the line the link spans, including its brackets
+    pub range: Range<usize>,
+    /// The text the link is displayed as
+    pub text: String,
+    /// The path the link refers to, without any surrounding backticks
+    pub target: String,
+}
+
+fn is_link_target(target: &str) -> bool {
+    !target.is_empty()
+        && target
+            .split("::")
+            .all(|seg| !seg.is_empty() && seg.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+/// Finds the intra-doc links in `line`. Links with a URL (`[text](url)`) and bracketed text that is not a path are not intra-doc links
+pub fn doc_links(line: &str) -> Vec<DocLink> {
+    let mut links = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = line[pos..].find('[').map(|off| pos + off) {
+        let Some(end) = line[start + 1..].find(']').map(|off| start + 1 + off) else {
+            break;
+        };
+        let text = &line[start + 1..end];
+        let rest = &line[end + 1..];
+
+        let (target, len) = if rest.starts_with('(') {
+            // An ordinary Markdown link
+            pos = rest.find(')').map_or(end + 1, |off| end + 1 + off + 1);
+            continue;
+        } else if let Some(reference) = rest.strip_prefix('[')
+            && let Some(ref_end) = reference.find(']')
+        {
+            (&reference[..ref_end], end + 1 + ref_end + 2 - start)
+        } else {
+            (text, end + 1 - start)
+        };
+
+        let target = target.trim_matches('`');
+        if is_link_target(target) {
+            links.push(DocLink {
+                range: start..start + len,
+                text: text.to_string(),
+                target: target.to_string(),
+            });
+            pos = start + len;
+        } else {
+            pos = start + 1;
+        }
+    }
+    links
+}
+
+/// What an intra-doc link refers to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// The item defined at the path
+    Item(Path),
+    /// The file of the bundle at the path
+    Module(Path),
+}
+
+/// Resolves the target of an intra-doc link in the documentation of `module`.
+///
+/// A single name is resolved like a name used in `module` (see [`resolve_name`]), or else as a submodule of `module` or a top-level module.
+/// A path is resolved as an item or module from the root of the bundle, or else relative to `module`.
+pub fn resolve_link(
+    bundle: &Bundle,
+    module: &Path,
+    target: &str,
+) -> Result<LinkTarget, ResolveError> {
+    let segments = target.split("::").map(str::to_string).collect::<Vec<_>>();
+    if let [name] = &segments[..] {
+        return match resolve_name(bundle, module, name) {
+            Err(ResolveError::NotFound(_)) if bundle.get(&module.join(name)).is_some() => {
+                Ok(LinkTarget::Module(module.join(name)))
+            }
+            Err(ResolveError::NotFound(_)) if bundle.get(&Path(segments.clone())).is_some() => {
+                Ok(LinkTarget::Module(Path(segments.clone())))
+            }
+            res => res.map(|resolved| LinkTarget::Item(resolved.path)),
+        };
+    }
+
+    let relative = Path(
+        module
+            .0
+            .iter()
+            .cloned()
+            .chain(segments.iter().cloned())
+            .collect(),
+    );
+    let mut err = None;
+    for path in [Path(segments), relative] {
+        if bundle.get(&path).is_some() {
+            return Ok(LinkTarget::Module(path));
+        }
+        match resolve_export(bundle, &path) {
+            Ok(resolved) => return Ok(LinkTarget::Item(resolved.path)),
+            Err(e) => {
+                err.get_or_insert(e);
+            }
+        }
+    }
+    Err(err.unwrap())
+}
+
+/// How the generated documentation that links point into is laid out
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// A Markdown page for each module, such as `base/io.md`, with a heading for each item
+    Markdown,
+    /// An HTML page for each module, such as `base/io.html`, with an anchor named after each item
+    Html,
+}
+
+impl LinkStyle {
+    fn ext(self) -> &'static str {
+        match self {
+            LinkStyle::Markdown => "md",
+            LinkStyle::Html => "html",
+        }
+    }
+
+    /// The page documenting `module`, split into directories. The page of the root module is `root`, as for other generated output
+    fn page(self, module: &Path) -> Vec<String> {
+        let mut page = module.0.clone();
+        match page.last_mut() {
+            Some(last) => *last = format!("{last}.{}", self.ext()),
+            None => page.push(format!("root.{}", self.ext())),
+        }
+        page
+    }
+
+    /// The URL of `target`, relative to the page documenting `from`
+    pub fn url(self, from: &Path, target: &LinkTarget) -> String {
+        let (module, anchor) = match target {
+            LinkTarget::Module(module) => (module.clone(), None),
+            LinkTarget::Item(path) => (
+                path.parent().unwrap_or_else(|| Path(vec![])),
+                path.name().map(|name| match self {
+                    LinkStyle::Markdown => name.to_lowercase(),
+                    LinkStyle::Html => name.to_string(),
+                }),
+            ),
+        };
+
+        let from = self.page(from);
+        let to = self.page(&module);
+        let from_dirs = &from[..from.len() - 1];
+        let common = from_dirs
+            .iter()
+            .zip(&to)
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(to.len() - 1);
+
+        let mut url = "../".repeat(from_dirs.len() - common);
+        url.push_str(&to[common..].join("/"));
+        if let Some(anchor) = anchor {
+            url.push('#');
+            url.push_str(&anchor);
+        }
+        url
+    }
+}
+
+/// Rewrites each intra-doc link in `line`, which documents part of `module`, to an ordinary Markdown link to the page that documents its target.
+/// Links that cannot be resolved are left as they are
+pub fn rewrite_links(bundle: &Bundle, module: &Path, line: &str, style: LinkStyle) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut pos = 0;
+    for link in doc_links(line) {
+        let Ok(target) = resolve_link(bundle, module, &link.target) else {
+            continue;
+        };
+        out.push_str(&line[pos..link.range.start]);
+        out.push_str(&format!("[{}]({})", link.text, style.url(module, &target)));
+        pos = link.range.end;
+    }
+    out.push_str(&line[pos..]);
+    out
+}
+
+/// Whether a [`LocalizedDoc`] in language `have` is a translation into `want`.
+/// A translation into a language without a region (such as `pt`) is used for every region of that language (such as `pt-BR`)
+fn lang_matches(have: &str, want: &str) -> bool {
+    let primary = want.split(['-', '_']).next().unwrap_or(want);
+    have.eq_ignore_ascii_case(want) || have.eq_ignore_ascii_case(primary)
+}
+
+struct Localizer<'a>(&'a str);
+
+impl AttributeVisitorMut for Localizer<'_> {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>) {
+        let localized = find_attributes::<LocalizedDoc, _>(attrs).collect::<Vec<_>>();
+        // An exact match is preferred over a translation for the whole language
+        let Some(doc) = localized
+            .iter()
+            .find(|doc| doc.lang.eq_ignore_ascii_case(self.0))
+            .or_else(|| localized.iter().find(|doc| lang_matches(&doc.lang, self.0)))
+        else {
+            return;
+        };
+        let doc = ItemDoc {
+            doc_lines: doc
+                .doc_lines
+                .iter()
+                .map(|line| Symbol::from(&**line))
+                .collect(),
+        };
+
+        match attrs
+            .iter_mut()
+            .find_map(Attribute::downcast_mut::<ItemDoc>)
+        {
+            Some(item_doc) => *item_doc = doc,
+            None => attrs.push(Attribute::new(doc)),
+        }
+    }
+}
+
+/// Replaces the [`ItemDoc`] of each item of `file` with its [`LocalizedDoc`] in the language `lang`, if it has one.
+/// Items without a translation keep their canonical documentation, so that the docs generators can be used unchanged
+pub fn localize_file(file: &mut File, lang: &str) {
+    visit_file_mut(&mut Localizer(lang), file);
+}
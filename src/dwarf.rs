@@ -0,0 +1,497 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use gimli::{AttributeValue, EndianSlice, RunTimeEndian, UnitOffset};
+use object::{Object, ObjectSection};
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{IpcMethod, LinkName},
+    },
+    bundle::{Bundle, Path},
+    layout::{LayoutEngine, LayoutError},
+    target::TargetSpec,
+    tydef::{StructBody, TypeDefBody},
+    uses::Type,
+    value::ValueBody,
+};
+
+/// A field of a struct described by DWARF
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DwarfField {
+    pub name: String,
+    /// The offset of the field, if it is a constant
+    pub offset: Option<u64>,
+}
+
+/// A struct described by DWARF, by its tag name or the name of a typedef of an unnamed struct
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DwarfStruct {
+    pub name: String,
+    pub size: u64,
+    pub fields: Vec<DwarfField>,
+}
+
+/// A function described by DWARF, with the sizes of its parameter and return types where DWARF determines them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DwarfFunction {
+    /// The symbol of the function (its linkage name, or its name if it has none)
+    pub name: String,
+    pub params: Vec<Option<u64>>,
+    /// The size of the return type, which is zero for functions returning `void`
+    pub ret: Option<u64>,
+}
+
+/// The structs and functions described by the DWARF debug information of a compiled object, such as a kernel image or libc
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub structs: HashMap<String, DwarfStruct>,
+    pub functions: HashMap<String, DwarfFunction>,
+}
+
+#[derive(Debug)]
+pub enum DwarfError {
+    Object(object::Error),
+    Dwarf(gimli::Error),
+}
+
+impl core::fmt::Display for DwarfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DwarfError::Object(e) => e.fmt(f),
+            DwarfError::Dwarf(e) => f.write_fmt(format_args!("invalid DWARF: {e}")),
+        }
+    }
+}
+
+impl std::error::Error for DwarfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DwarfError::Object(e) => Some(e),
+            DwarfError::Dwarf(e) => Some(e),
+        }
+    }
+}
+
+impl From<object::Error> for DwarfError {
+    fn from(value: object::Error) -> Self {
+        Self::Object(value)
+    }
+}
+
+impl From<gimli::Error> for DwarfError {
+    fn from(value: gimli::Error) -> Self {
+        Self::Dwarf(value)
+    }
+}
+
+type R<'a> = EndianSlice<'a, RunTimeEndian>;
+
+/// Typedefs and qualifiers nested deeper than this are assumed to be cyclic
+const MAX_TYPE_DEPTH: usize = 32;
+
+struct UnitReader<'a, 'u> {
+    dwarf: &'u gimli::Dwarf<R<'a>>,
+    unit: &'u gimli::Unit<R<'a>>,
+}
+
+impl<'a> UnitReader<'a, '_> {
+    fn name(&self, entry: &gimli::DebuggingInformationEntry<R<'a>>) -> Option<String> {
+        self.string(entry, gimli::DW_AT_name)
+    }
+
+    fn string(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<R<'a>>,
+        attr: gimli::DwAt,
+    ) -> Option<String> {
+        let value = entry.attr_value(attr).ok()??;
+        let name = self.dwarf.attr_string(self.unit, value).ok()?;
+        Some(name.to_string_lossy().into_owned())
+    }
+
+    fn type_ref(&self, entry: &gimli::DebuggingInformationEntry<R<'a>>) -> Option<UnitOffset> {
+        match entry.attr_value(gimli::DW_AT_type).ok()?? {
+            AttributeValue::UnitRef(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    fn udata(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<R<'a>>,
+        attr: gimli::DwAt,
+    ) -> Option<u64> {
+        entry.attr_value(attr).ok()??.udata_value()
+    }
+
+    /// The size of the type at `offset`, looking through typedefs and qualifiers
+    fn type_size(&self, offset: UnitOffset, depth: usize) -> Option<u64> {
+        let entry = self.unit.entry(offset).ok()?;
+        match entry.tag() {
+            gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type => self
+                .udata(&entry, gimli::DW_AT_byte_size)
+                .or(Some(u64::from(self.unit.encoding().address_size))),
+            gimli::DW_TAG_typedef
+            | gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_restrict_type
+            | gimli::DW_TAG_atomic_type
+                if depth < MAX_TYPE_DEPTH =>
+            {
+                match self.type_ref(&entry) {
+                    Some(ty) => self.type_size(ty, depth + 1),
+                    // A qualified `void`
+                    None => Some(0),
+                }
+            }
+            _ => self.udata(&entry, gimli::DW_AT_byte_size),
+        }
+    }
+
+    /// The unnamed struct that the typedef `entry` names, looking through qualifiers
+    fn typedef_struct(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<R<'a>>,
+    ) -> Option<UnitOffset> {
+        let mut offset = self.type_ref(entry)?;
+        for _ in 0..MAX_TYPE_DEPTH {
+            let target = self.unit.entry(offset).ok()?;
+            match target.tag() {
+                gimli::DW_TAG_structure_type if self.name(&target).is_none() => {
+                    return Some(offset);
+                }
+                gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type => {
+                    offset = self.type_ref(&target)?;
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn structure(
+        &self,
+        name: String,
+        offset: UnitOffset,
+    ) -> Result<Option<DwarfStruct>, gimli::Error> {
+        let entry = self.unit.entry(offset)?;
+        // Declarations of incomplete types have no size or members
+        let Some(size) = self.udata(&entry, gimli::DW_AT_byte_size) else {
+            return Ok(None);
+        };
+        let mut fields = Vec::new();
+        let mut tree = self.unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let member = child.entry();
+            if member.tag() != gimli::DW_TAG_member {
+                continue;
+            }
+            let Some(name) = self.name(member) else {
+                continue;
+            };
+            fields.push(DwarfField {
+                name,
+                offset: self.udata(member, gimli::DW_AT_data_member_location),
+            });
+        }
+        Ok(Some(DwarfStruct { name, size, fields }))
+    }
+
+    fn function(&self, offset: UnitOffset) -> Result<Option<DwarfFunction>, gimli::Error> {
+        let entry = self.unit.entry(offset)?;
+        let Some(name) = self
+            .string(&entry, gimli::DW_AT_linkage_name)
+            .or_else(|| self.name(&entry))
+        else {
+            return Ok(None);
+        };
+        let ret = match self.type_ref(&entry) {
+            Some(ty) => self.type_size(ty, 0),
+            None => Some(0),
+        };
+        let mut params = Vec::new();
+        let mut tree = self.unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let param = child.entry();
+            if param.tag() == gimli::DW_TAG_formal_parameter {
+                params.push(self.type_ref(param).and_then(|ty| self.type_size(ty, 0)));
+            }
+        }
+        Ok(Some(DwarfFunction { name, params, ret }))
+    }
+}
+
+impl DebugInfo {
+    /// Reads the DWARF debug information of the object file (such as an ELF executable or shared object) `bytes`
+    ///
+    /// Relocations are not applied, so the debug information of relocatable objects, which refers to strings and types through relocations,
+    /// is not read correctly.
+    pub fn read(bytes: &[u8]) -> Result<Self, DwarfError> {
+        let object = object::File::parse(bytes)?;
+        let endian = if object.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        let sections = gimli::DwarfSections::load(|id| -> Result<Cow<[u8]>, object::Error> {
+            match object.section_by_name(id.name()) {
+                Some(section) => section.uncompressed_data(),
+                None => Ok(Cow::Borrowed(&[])),
+            }
+        })?;
+        let dwarf = sections.borrow(|section| EndianSlice::new(section, endian));
+
+        let mut info = Self::default();
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let unit = dwarf.unit(header)?;
+            let reader = UnitReader {
+                dwarf: &dwarf,
+                unit: &unit,
+            };
+            let mut entries = unit.entries();
+            while let Some((_, entry)) = entries.next_dfs()? {
+                match entry.tag() {
+                    gimli::DW_TAG_structure_type => {
+                        let Some(name) = reader.name(entry) else {
+                            continue;
+                        };
+                        if !info.structs.contains_key(&name)
+                            && let Some(st) = reader.structure(name, entry.offset())?
+                        {
+                            info.structs.insert(st.name.clone(), st);
+                        }
+                    }
+                    gimli::DW_TAG_typedef => {
+                        let (Some(name), Some(offset)) =
+                            (reader.name(entry), reader.typedef_struct(entry))
+                        else {
+                            continue;
+                        };
+                        if !info.structs.contains_key(&name)
+                            && let Some(st) = reader.structure(name, offset)?
+                        {
+                            info.structs.insert(st.name.clone(), st);
+                        }
+                    }
+                    gimli::DW_TAG_subprogram => {
+                        // Definitions are preferred over declarations, which may be from a different (possibly stale) header
+                        let declaration = matches!(
+                            entry.attr_value(gimli::DW_AT_declaration)?,
+                            Some(AttributeValue::Flag(true))
+                        );
+                        let Some(func) = reader.function(entry.offset())? else {
+                            continue;
+                        };
+                        if !declaration || !info.functions.contains_key(&func.name) {
+                            info.functions.insert(func.name.clone(), func);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// How the layout or signature that DWARF describes differs from the one the bundle computes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MismatchKind {
+    Size {
+        expected: u128,
+        found: u64,
+    },
+    /// DWARF describes no member of the name of a field of the struct
+    MissingField(String),
+    FieldOffset {
+        field: String,
+        expected: u128,
+        found: u64,
+    },
+    ParamCount {
+        expected: usize,
+        found: usize,
+    },
+    ParamSize {
+        index: usize,
+        expected: u128,
+        found: u64,
+    },
+    ReturnSize {
+        expected: u128,
+        found: u64,
+    },
+    /// The layout of the item cannot be computed, so it cannot be compared
+    Layout(LayoutError),
+}
+
+/// A difference between an item of a bundle and its description in DWARF
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub path: Path,
+    pub kind: MismatchKind,
+}
+
+impl core::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = &self.path;
+        match &self.kind {
+            MismatchKind::Size { expected, found } => f.write_fmt(format_args!(
+                "{path} has size {expected}, but DWARF describes size {found}"
+            )),
+            MismatchKind::MissingField(field) => f.write_fmt(format_args!(
+                "{path} has a field {field}, which DWARF does not describe"
+            )),
+            MismatchKind::FieldOffset {
+                field,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "field {field} of {path} is at offset {expected}, but DWARF describes offset {found}"
+            )),
+            MismatchKind::ParamCount { expected, found } => f.write_fmt(format_args!(
+                "{path} has {expected} parameter(s), but DWARF describes {found}"
+            )),
+            MismatchKind::ParamSize {
+                index,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "parameter {index} of {path} has size {expected}, but DWARF describes size {found}"
+            )),
+            MismatchKind::ReturnSize { expected, found } => f.write_fmt(format_args!(
+                "the return type of {path} has size {expected}, but DWARF describes size {found}"
+            )),
+            MismatchKind::Layout(e) => {
+                f.write_fmt(format_args!("the layout of {path} cannot be computed: {e}"))
+            }
+        }
+    }
+}
+
+/// Verifies the size and field offsets of each struct of `bundle`, and the parameter count and the sizes of the parameters and return type of each function,
+/// against the layouts computed for `target` and the struct or function of the same name described by `info`.
+///
+/// Functions are looked up by the symbol they are linked by. Items that `info` does not describe are not reported,
+/// since an object usually only describes the types and functions it uses.
+pub fn cross_check(bundle: &Bundle, info: &DebugInfo, target: &TargetSpec) -> Vec<Mismatch> {
+    let mut engine = LayoutEngine::new(bundle, target);
+    let mut res = Vec::new();
+    for (module, file) in bundle {
+        for ty in &file.types {
+            let TypeDefBody::Struct(st) = &ty.body else {
+                continue;
+            };
+            let (StructBody::Fields(fields), Some(dwarf)) = (&st.body, info.structs.get(&*ty.name))
+            else {
+                continue;
+            };
+            let path = module.join(&ty.name);
+            let mut push = |kind| {
+                res.push(Mismatch {
+                    path: path.clone(),
+                    kind,
+                })
+            };
+            let layouts = engine.layout_of_def(module, ty).and_then(|layout| {
+                let mut fields_layout = engine.field_layouts(module, fields)?;
+                fields_layout.extend(engine.pad_layout(module, fields)?);
+                Ok((layout, fields_layout))
+            });
+            let (layout, field_layouts) = match layouts {
+                Ok(layouts) => layouts,
+                Err(e) => {
+                    push(MismatchKind::Layout(e));
+                    continue;
+                }
+            };
+            if layout.size != u128::from(dwarf.size) {
+                push(MismatchKind::Size {
+                    expected: layout.size,
+                    found: dwarf.size,
+                });
+            }
+            for field in &field_layouts {
+                match dwarf.fields.iter().find(|f| f.name == *field.name) {
+                    // Generated C declares the explicit padding of a struct, but hand-written headers need not
+                    None if *field.name == *"_pad" => {}
+                    None => push(MismatchKind::MissingField(field.name.to_string())),
+                    Some(DwarfField {
+                        offset: Some(offset),
+                        ..
+                    }) if u128::from(*offset) != field.offset => push(MismatchKind::FieldOffset {
+                        field: field.name.to_string(),
+                        expected: field.offset,
+                        found: *offset,
+                    }),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        for val in &file.values {
+            let ValueBody::Function(f) = &val.body else {
+                continue;
+            };
+            if find_attribute::<IpcMethod, _>(&f.attrs).is_some() {
+                continue;
+            }
+            let symbol =
+                find_attribute::<LinkName, _>(&f.attrs).map_or(&*val.name, |link| &*link.name);
+            let Some(dwarf) = info.functions.get(symbol) else {
+                continue;
+            };
+            let path = module.join(&val.name);
+            let mut push = |kind| {
+                res.push(Mismatch {
+                    path: path.clone(),
+                    kind,
+                })
+            };
+            let mut size_of = |ty: &Type| match ty {
+                Type::Void | Type::Never => Ok(0),
+                ty => engine.layout_of(module, ty).map(|layout| layout.size),
+            };
+
+            let params = &f.signature.params;
+            if params.len() != dwarf.params.len() {
+                push(MismatchKind::ParamCount {
+                    expected: params.len(),
+                    found: dwarf.params.len(),
+                });
+            }
+            for (index, (param, found)) in params.iter().zip(&dwarf.params).enumerate() {
+                let Some(found) = *found else {
+                    continue;
+                };
+                match size_of(&param.ty) {
+                    Ok(expected) if expected != u128::from(found) => {
+                        push(MismatchKind::ParamSize {
+                            index,
+                            expected,
+                            found,
+                        })
+                    }
+                    Ok(_) => {}
+                    Err(e) => push(MismatchKind::Layout(e)),
+                }
+            }
+            if let Some(found) = dwarf.ret {
+                match size_of(&f.signature.retty) {
+                    Ok(expected) if expected != u128::from(found) => {
+                        push(MismatchKind::ReturnSize { expected, found })
+                    }
+                    Ok(_) => {}
+                    Err(e) => push(MismatchKind::Layout(e)),
+                }
+            }
+        }
+    }
+    res
+}
@@ -0,0 +1,340 @@
+use std::{
+    ffi::{CStr, c_char, c_void},
+    path::{Path as FsPath, PathBuf},
+    sync::Arc,
+};
+
+use libloading::Library;
+
+use crate::{
+    bundle::{Bundle, Path},
+    codegen::{CodegenBackend, OutputSink, plugin::plugin_request},
+    diagnostics::{Diagnostic, Severity},
+    target::TargetSpec,
+};
+
+/// The version of [`PluginVTable`], which changes whenever the layout of the vtable or the meaning of its functions changes incompatibly
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The symbol a dynamic plugin exports, of type [`PluginEntry`]
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"imt_plugin_entry";
+
+/// The function a dynamic plugin exports as [`PLUGIN_ENTRY_SYMBOL`], returning its vtable, which lives as long as the plugin is loaded
+pub type PluginEntry = unsafe extern "C" fn() -> *const PluginVTable;
+
+/// A buffer passed between imt and a plugin. Strings are UTF-8, and are not NUL-terminated
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PluginBytes {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl PluginBytes {
+    fn new(bytes: &[u8]) -> Self {
+        Self {
+            ptr: bytes.as_ptr(),
+            len: bytes.len(),
+        }
+    }
+
+    /// # Safety
+    /// `self` must point to `len` bytes that are valid for `'a`
+    unsafe fn as_slice<'a>(self) -> &'a [u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+/// A diagnostic reported by a lint plugin
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PluginDiagnostic {
+    /// 0 for a warning, and 1 for an error
+    pub severity: u32,
+    /// The path of the file of the bundle the diagnostic is about, like `base::io`
+    pub file: PluginBytes,
+    /// The item (or member of an item, like `Struct::field`) within `file`, or is empty
+    pub item: PluginBytes,
+    pub message: PluginBytes,
+}
+
+/// Receives an output of a generator plugin, with its name (a `/`-separated relative path) and contents
+pub type PluginOutputFn =
+    unsafe extern "C" fn(ctx: *mut c_void, name: PluginBytes, contents: PluginBytes);
+
+/// Receives a diagnostic of a lint plugin, which only needs to be valid for the duration of the call
+pub type PluginDiagnosticFn =
+    unsafe extern "C" fn(ctx: *mut c_void, diagnostic: *const PluginDiagnostic);
+
+/// The interface of a dynamic plugin, which is a generator, a lint, or both.
+///
+/// Each function receives a request in the format written to a [`PluginBackend`](crate::codegen::plugin::PluginBackend),
+/// calls its callback with `ctx` for each output or diagnostic, and returns 0 on success.
+#[repr(C)]
+pub struct PluginVTable {
+    /// The [`PLUGIN_ABI_VERSION`] the plugin was built for. Plugins with a different version are not loaded
+    pub abi_version: u32,
+    /// The name of the plugin, which is NUL-terminated
+    pub name: *const c_char,
+    pub generate: Option<
+        unsafe extern "C" fn(request: PluginBytes, ctx: *mut c_void, output: PluginOutputFn) -> i32,
+    >,
+    pub lint: Option<
+        unsafe extern "C" fn(
+            request: PluginBytes,
+            ctx: *mut c_void,
+            diagnostic: PluginDiagnosticFn,
+        ) -> i32,
+    >,
+}
+
+#[derive(Debug)]
+pub enum DylibError {
+    Io(std::io::Error),
+    Load(PathBuf, libloading::Error),
+    /// The library does not export [`PLUGIN_ENTRY_SYMBOL`], or returned a null vtable
+    NotAPlugin(PathBuf),
+    /// The plugin was built for a different [`PLUGIN_ABI_VERSION`]
+    AbiVersion(PathBuf, u32),
+}
+
+impl core::fmt::Display for DylibError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DylibError::Io(e) => e.fmt(f),
+            DylibError::Load(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            DylibError::NotAPlugin(path) => f.write_fmt(format_args!(
+                "{}: not an imt plugin (no imt_plugin_entry)",
+                path.display()
+            )),
+            DylibError::AbiVersion(path, version) => f.write_fmt(format_args!(
+                "{}: built for plugin ABI version {version}, but version {PLUGIN_ABI_VERSION} is required",
+                path.display()
+            )),
+        }
+    }
+}
+
+impl std::error::Error for DylibError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DylibError::Io(e) => Some(e),
+            DylibError::Load(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DylibError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// A plugin loaded from a dynamic library
+#[derive(Clone)]
+pub struct DylibPlugin {
+    name: String,
+    path: PathBuf,
+    vtable: *const PluginVTable,
+    /// Keeps the library loaded while the vtable is in use
+    _library: Arc<Library>,
+}
+
+impl core::fmt::Debug for DylibPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DylibPlugin")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Collects the outputs of a generator plugin
+struct OutputContext<'a> {
+    sink: &'a mut dyn OutputSink,
+    res: std::io::Result<()>,
+}
+
+unsafe extern "C" fn receive_output(ctx: *mut c_void, name: PluginBytes, contents: PluginBytes) {
+    // SAFETY: `ctx` is the `OutputContext` passed to the plugin, and the plugin passes valid buffers
+    let ctx = unsafe { &mut *ctx.cast::<OutputContext>() };
+    let (name, contents) = unsafe { (name.as_slice(), contents.as_slice()) };
+    if ctx.res.is_err() {
+        return;
+    }
+    ctx.res = match core::str::from_utf8(name) {
+        Ok(name) if name.starts_with('/') || name.split('/').any(|part| part == "..") => {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("output {name} is not a relative path within the output"),
+            ))
+        }
+        Ok(name) => ctx.sink.write_output(name, contents),
+        Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+    };
+}
+
+unsafe extern "C" fn receive_diagnostic(ctx: *mut c_void, diagnostic: *const PluginDiagnostic) {
+    // SAFETY: `ctx` is the `Vec<Diagnostic>` passed to the plugin, and the plugin passes a valid diagnostic
+    let diags = unsafe { &mut *ctx.cast::<Vec<Diagnostic>>() };
+    let diagnostic = unsafe { *diagnostic };
+    let [file, item, message] = [diagnostic.file, diagnostic.item, diagnostic.message]
+        .map(|bytes| String::from_utf8_lossy(unsafe { bytes.as_slice() }));
+    let file = if file.is_empty() {
+        Path(vec![])
+    } else {
+        Path(file.split("::").map(str::to_string).collect())
+    };
+    let severity = match diagnostic.severity {
+        0 => Severity::Warning,
+        _ => Severity::Error,
+    };
+    let mut diag = Diagnostic::new(severity, file, message);
+    if !item.is_empty() {
+        diag = diag.with_item(item);
+    }
+    diags.push(diag);
+}
+
+impl DylibPlugin {
+    /// Loads the plugin in the dynamic library at `path`.
+    ///
+    /// # Safety
+    /// Loading a library runs its initialization code, and the plugin's functions are trusted to follow the contract of [`PluginVTable`]
+    pub unsafe fn load(path: &FsPath) -> Result<Self, DylibError> {
+        let library =
+            unsafe { Library::new(path) }.map_err(|e| DylibError::Load(path.to_path_buf(), e))?;
+        let vtable = unsafe {
+            let entry = library
+                .get::<PluginEntry>(PLUGIN_ENTRY_SYMBOL)
+                .map_err(|_| DylibError::NotAPlugin(path.to_path_buf()))?;
+            entry()
+        };
+        if vtable.is_null() {
+            return Err(DylibError::NotAPlugin(path.to_path_buf()));
+        }
+        // SAFETY: the vtable lives as long as the library
+        let vtable_ref = unsafe { &*vtable };
+        if vtable_ref.abi_version != PLUGIN_ABI_VERSION {
+            return Err(DylibError::AbiVersion(
+                path.to_path_buf(),
+                vtable_ref.abi_version,
+            ));
+        }
+        let name = if vtable_ref.name.is_null() {
+            path.file_stem()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+        } else {
+            unsafe { CStr::from_ptr(vtable_ref.name) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        Ok(Self {
+            name,
+            path: path.to_path_buf(),
+            vtable,
+            _library: Arc::new(library),
+        })
+    }
+
+    fn vtable(&self) -> &PluginVTable {
+        // SAFETY: the vtable lives as long as the library, which `self` keeps loaded
+        unsafe { &*self.vtable }
+    }
+
+    pub fn path(&self) -> &FsPath {
+        &self.path
+    }
+
+    pub fn is_generator(&self) -> bool {
+        self.vtable().generate.is_some()
+    }
+
+    pub fn is_lint(&self) -> bool {
+        self.vtable().lint.is_some()
+    }
+
+    /// Runs the lint of the plugin on `bundle`, returning the diagnostics it reports.
+    /// Plugins that are not lints report no diagnostics
+    pub fn lint(&self, bundle: &Bundle, target: &TargetSpec) -> std::io::Result<Vec<Diagnostic>> {
+        let Some(lint) = self.vtable().lint else {
+            return Ok(Vec::new());
+        };
+        let request = plugin_request(bundle, target)?;
+        let mut diags = Vec::<Diagnostic>::new();
+        let status = unsafe {
+            lint(
+                PluginBytes::new(&request),
+                (&raw mut diags).cast(),
+                receive_diagnostic,
+            )
+        };
+        if status != 0 {
+            return Err(std::io::Error::other(format!(
+                "{}: lint failed with status {status}",
+                self.name
+            )));
+        }
+        Ok(diags)
+    }
+}
+
+impl CodegenBackend for DylibPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn generate(
+        &self,
+        bundle: &Bundle,
+        target: &TargetSpec,
+        sink: &mut dyn OutputSink,
+    ) -> std::io::Result<()> {
+        let Some(generate) = self.vtable().generate else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("{} is not a generator", self.name),
+            ));
+        };
+        let request = plugin_request(bundle, target)?;
+        let mut ctx = OutputContext { sink, res: Ok(()) };
+        let status = unsafe {
+            generate(
+                PluginBytes::new(&request),
+                (&raw mut ctx).cast(),
+                receive_output,
+            )
+        };
+        ctx.res?;
+        if status != 0 {
+            return Err(std::io::Error::other(format!(
+                "{}: generation failed with status {status}",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Loads each dynamic library (with the platform's extension, such as `.so`) in the directory `dir` as a plugin, in order of file name
+///
+/// # Safety
+/// See [`DylibPlugin::load`]
+pub unsafe fn discover(dir: &FsPath) -> Result<Vec<DylibPlugin>, DylibError> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+    });
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| unsafe { DylibPlugin::load(path) })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use object::{Object, ObjectSymbol, SymbolKind};
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{IpcMethod, LinkName},
+    },
+    bundle::{Bundle, Path},
+    value::ValueBody,
+};
+
+/// The names of the functions that the ELF shared object `bytes` exports from its dynamic symbol table
+pub fn exported_functions(bytes: &[u8]) -> Result<BTreeSet<String>, object::Error> {
+    let object = object::File::parse(bytes)?;
+    let mut res = BTreeSet::new();
+    for symbol in object.dynamic_symbols() {
+        if !symbol.is_definition() || !symbol.is_global() || symbol.kind() != SymbolKind::Text {
+            continue;
+        }
+        res.insert(symbol.name()?.to_string());
+    }
+    Ok(res)
+}
+
+/// A difference between the functions of a bundle and the symbols exported by a shared object
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymbolMismatch {
+    /// The function of the bundle is linked by a symbol that the object does not export
+    Missing { path: Path, symbol: String },
+    /// The object exports a function symbol that no function of the bundle is linked by
+    Undocumented(String),
+}
+
+impl core::fmt::Display for SymbolMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolMismatch::Missing { path, symbol } => f.write_fmt(format_args!(
+                "{path} is linked by the symbol {symbol}, which is not exported"
+            )),
+            SymbolMismatch::Undocumented(symbol) => f.write_fmt(format_args!(
+                "{symbol} is exported, but is not a function of the bundle"
+            )),
+        }
+    }
+}
+
+/// Checks that each function of `bundle` is exported by `exported` under the symbol of its [`LinkName`] attribute, or its name,
+/// and, if `undocumented` is set, that each symbol of `exported` is the symbol of a function of `bundle`.
+///
+/// Functions with an [`IpcMethod`] attribute have no symbol, and aren't checked.
+/// Constants have no symbol either, since bindings declare them as macros or `static` constants.
+pub fn cross_check(
+    bundle: &Bundle,
+    exported: &BTreeSet<String>,
+    undocumented: bool,
+) -> Vec<SymbolMismatch> {
+    let mut symbols = BTreeMap::new();
+    for (module, file) in bundle {
+        for val in &file.values {
+            let ValueBody::Function(f) = &val.body else {
+                continue;
+            };
+            if find_attribute::<IpcMethod, _>(&f.attrs).is_some() {
+                continue;
+            }
+            let symbol =
+                find_attribute::<LinkName, _>(&f.attrs).map_or(&*val.name, |link| &*link.name);
+            symbols
+                .entry(symbol)
+                .or_insert_with(|| module.join(&val.name));
+        }
+    }
+
+    let mut res = Vec::new();
+    for (&symbol, path) in &symbols {
+        if !exported.contains(symbol) {
+            res.push(SymbolMismatch::Missing {
+                path: path.clone(),
+                symbol: symbol.to_string(),
+            });
+        }
+    }
+    if undocumented {
+        for symbol in exported {
+            if !symbols.contains_key(&**symbol) {
+                res.push(SymbolMismatch::Undocumented(symbol.clone()));
+            }
+        }
+    }
+    res
+}
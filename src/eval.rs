@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use crate::{
+    bundle::{Bundle, Path},
+    resolve::{Item, ResolveError, module_of, resolve_name},
+    special::SpecialConsts,
+    target::TargetSpec,
+    tydef::Enum,
+    uses::{BinaryOp, Expr, IntBits, IntType, SpecialConst, UnaryOp},
+    value::ValueBody,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    Resolve(ResolveError),
+    NotAConstant(Path),
+    NotAnInteger,
+    Overflow,
+    DivideByZero,
+    Cycle(Path),
+    /// The special constant has no value on the target
+    NoSpecialValue(SpecialConst),
+    /// The expression refers to a generic parameter that has no constant argument
+    UnboundParam(u32),
+}
+
+impl core::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::Resolve(e) => e.fmt(f),
+            EvalError::NotAConstant(path) => f.write_fmt(format_args!("{path} is not a constant")),
+            EvalError::NotAnInteger => f.write_str("expression is not an integer"),
+            EvalError::Overflow => f.write_str("arithmetic overflow in constant expression"),
+            EvalError::DivideByZero => f.write_str("division by zero in constant expression"),
+            EvalError::Cycle(path) => f.write_fmt(format_args!("{path} depends on itself")),
+            EvalError::NoSpecialValue(special) => f.write_fmt(format_args!(
+                "special constant {special} has no value on the target"
+            )),
+            EvalError::UnboundParam(n) => f.write_fmt(format_args!(
+                "generic parameter {n} has no constant argument"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl From<ResolveError> for EvalError {
+    fn from(value: ResolveError) -> Self {
+        Self::Resolve(value)
+    }
+}
+
+/// The width of `ty` on `target`, in bits
+pub fn int_bits(ty: IntType, target: &TargetSpec) -> u32 {
+    match ty.bits {
+        IntBits::Bits(bits) => bits.get() as u32,
+        IntBits::Long => target.long_bits as u32,
+    }
+}
+
+/// Interprets the raw bits of an integer literal according to the signedness and width of `ty`
+pub fn literal_value(ty: IntType, val: u128, target: &TargetSpec) -> Result<i128, EvalError> {
+    literal_value_bits(ty.signed, int_bits(ty, target), val)
+}
+
+/// Interprets the raw bits of an integer literal whose type has the given signedness and width
+pub(crate) fn literal_value_bits(signed: bool, bits: u32, val: u128) -> Result<i128, EvalError> {
+    if !signed || bits > 128 {
+        return i128::try_from(val).map_err(|_| EvalError::Overflow);
+    }
+
+    let shift = 128 - bits;
+    Ok(((val << shift) as i128) >> shift)
+}
+
+pub(crate) fn range_of(signed: bool, bits: u32) -> (i128, i128) {
+    match (signed, bits) {
+        (true, 128..) => (i128::MIN, i128::MAX),
+        (true, bits) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        (false, 127..) => (0, i128::MAX),
+        (false, bits) => (0, (1 << bits) - 1),
+    }
+}
+
+/// The smallest and largest values of `ty` on `target`, limited to the range of `i128`
+pub fn int_range(ty: IntType, target: &TargetSpec) -> (i128, i128) {
+    range_of(ty.signed, int_bits(ty, target))
+}
+
+/// Computes the smallest fixed-width integer type that can represent each of `values`.
+/// The type is unsigned unless one of `values` is negative.
+pub fn minimal_int_type<I: IntoIterator<Item = i128>>(values: I) -> IntType {
+    let (min, max) = values
+        .into_iter()
+        .fold((0, 0), |(min, max), val| (val.min(min), val.max(max)));
+
+    let candidates = if min < 0 {
+        [
+            IntType::i8,
+            IntType::i16,
+            IntType::i32,
+            IntType::i64,
+            IntType::i128,
+        ]
+    } else {
+        [
+            IntType::u8,
+            IntType::u16,
+            IntType::u32,
+            IntType::u64,
+            IntType::u128,
+        ]
+    };
+
+    candidates
+        .into_iter()
+        .find(|ty| {
+            let IntBits::Bits(bits) = ty.bits else {
+                return false;
+            };
+            let (lo, hi) = range_of(ty.signed, bits.get() as u32);
+            lo <= min && max <= hi
+        })
+        .unwrap_or(candidates[4])
+}
+
+/// Applies `op` to `left` and `right`, failing rather than wrapping
+pub(crate) fn binary_op(op: BinaryOp, left: i128, right: i128) -> Result<i128, EvalError> {
+    let val = match op {
+        BinaryOp::Add => left.checked_add(right),
+        BinaryOp::Sub => left.checked_sub(right),
+        BinaryOp::Mul => left.checked_mul(right),
+        BinaryOp::Div if right == 0 => return Err(EvalError::DivideByZero),
+        BinaryOp::Div => left.checked_div(right),
+        BinaryOp::And => Some(left & right),
+        BinaryOp::Or => Some(left | right),
+        BinaryOp::Xor => Some(left ^ right),
+        BinaryOp::ShiftLeft => u32::try_from(right)
+            .ok()
+            .and_then(|right| Some(right).zip(left.checked_shl(right)))
+            .filter(|&(right, val)| val >> right == left)
+            .map(|(_, val)| val),
+        BinaryOp::ShiftRight => u32::try_from(right)
+            .ok()
+            .map(|right| left.checked_shr(right).unwrap_or(left >> 127)),
+    };
+    val.ok_or(EvalError::Overflow)
+}
+
+/// Applies `op` to `val`. `ty` is the signedness and width of the type of `val`, if it is known.
+/// The complement of an unsigned value is taken within its width
+pub(crate) fn unary_op(op: UnaryOp, val: i128, ty: Option<(bool, u32)>) -> Result<i128, EvalError> {
+    match (op, ty) {
+        (UnaryOp::Neg, _) => val.checked_neg().ok_or(EvalError::Overflow),
+        (UnaryOp::Not, Some((false, bits))) => {
+            let mask = u128::MAX >> 128u32.saturating_sub(bits);
+            literal_value_bits(false, bits, !(val as u128) & mask)
+        }
+        (UnaryOp::Not, _) => Ok(!val),
+    }
+}
+
+/// Evaluates integer constant expressions, following [`Expr::Const`] references through the bundle.
+///
+/// Arithmetic is performed at infinite precision (within `i128`), and fails rather than wrapping.
+pub struct Evaluator<'a> {
+    bundle: &'a Bundle,
+    target: &'a TargetSpec,
+    visiting: HashSet<Path>,
+    /// The custom special constants of the bundle, collected when first needed
+    specials: Option<SpecialConsts>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(bundle: &'a Bundle, target: &'a TargetSpec) -> Self {
+        Self {
+            bundle,
+            target,
+            visiting: HashSet::new(),
+            specials: None,
+        }
+    }
+
+    /// Evaluates `expr`, resolving names as seen from `module`
+    pub fn eval(&mut self, module: &Path, expr: &Expr) -> Result<i128, EvalError> {
+        self.eval_with_params(module, expr, &[])
+    }
+
+    /// Evaluates `expr`, which appears in a generic type definition, where `params` are the values of the constant arguments to its parameters (or `None` for type arguments)
+    pub fn eval_with_params(
+        &mut self,
+        module: &Path,
+        expr: &Expr,
+        params: &[Option<i128>],
+    ) -> Result<i128, EvalError> {
+        self.eval_typed(module, expr, params).map(|(val, _)| val)
+    }
+
+    /// Evaluates every discriminant of `en`, and computes the smallest integer type that can represent them
+    pub fn infer_enum_underlying(
+        &mut self,
+        module: &Path,
+        en: &Enum,
+    ) -> Result<IntType, EvalError> {
+        let values = en
+            .variants
+            .iter()
+            .map(|variant| self.eval(module, &variant.discrim))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(minimal_int_type(values))
+    }
+
+    fn eval_typed(
+        &mut self,
+        module: &Path,
+        expr: &Expr,
+        params: &[Option<i128>],
+    ) -> Result<(i128, Option<IntType>), EvalError> {
+        match expr {
+            Expr::IntLiteral(ty, val) => Ok((literal_value(*ty, *val, self.target)?, Some(*ty))),
+            Expr::UuidLiteral(_) | Expr::StringLiteral(_) => Err(EvalError::NotAnInteger),
+            Expr::Const(name) => {
+                let resolved = resolve_name(self.bundle, module, name)?;
+                let Item::Value(val) = resolved.item else {
+                    return Err(EvalError::NotAConstant(resolved.path));
+                };
+                let ValueBody::Const(c) = &val.body else {
+                    return Err(EvalError::NotAConstant(resolved.path));
+                };
+
+                if !self.visiting.insert(resolved.path.clone()) {
+                    return Err(EvalError::Cycle(resolved.path));
+                }
+                let res = self.eval_typed(&module_of(&resolved.path), &c.val, &[]);
+                self.visiting.remove(&resolved.path);
+                res
+            }
+            Expr::BinOp(op, left, right) => {
+                let (left, lty) = self.eval_typed(module, left, params)?;
+                let (right, rty) = self.eval_typed(module, right, params)?;
+                Ok((binary_op(*op, left, right)?, lty.or(rty)))
+            }
+            Expr::UnaryOp(op, inner) => {
+                let (val, ty) = self.eval_typed(module, inner, params)?;
+                let width = ty.map(|ty| (ty.signed, int_bits(ty, self.target)));
+                Ok((unary_op(*op, val, width)?, ty))
+            }
+            Expr::SpecialConstant(special @ SpecialConst::Custom(_)) => {
+                let value = self
+                    .specials
+                    .get_or_insert_with(|| SpecialConsts::from_bundle(self.bundle))
+                    .value(*special, self.target)
+                    .ok_or(EvalError::NoSpecialValue(*special))?;
+                Ok((
+                    i128::try_from(value).map_err(|_| EvalError::Overflow)?,
+                    None,
+                ))
+            }
+            Expr::Param(n) => params
+                .get(*n as usize)
+                .copied()
+                .flatten()
+                .map(|val| (val, None))
+                .ok_or(EvalError::UnboundParam(*n)),
+            Expr::SpecialConstant(special) => {
+                let value = self
+                    .target
+                    .special_value(*special)
+                    .ok_or(EvalError::NoSpecialValue(*special))?;
+                Ok((
+                    i128::try_from(value).map_err(|_| EvalError::Overflow)?,
+                    None,
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{
+    attr::{Attribute, AttributeTarget, Target, find_attributes, types::Feature},
+    file::File,
+    tydef::TypeDefBody,
+    value::ValueBody,
+};
+
+pub fn is_enabled<Targ: AttributeTarget>(attrs: &[Attribute<Targ>], enabled: &[&str]) -> bool
+where
+    Feature: Target<Targ>,
+{
+    find_attributes::<Feature, _>(attrs).all(|feature| enabled.contains(&&*feature.name))
+}
+
+/// Removes all items of `file` that belong to disabled features.
+/// Returns `false` if the file itself belongs to a disabled feature.
+pub fn filter_file(file: &mut File, enabled: &[&str]) -> bool {
+    if !is_enabled(&file.attributes, enabled) {
+        return false;
+    }
+
+    file.uses
+        .retain(|use_item| is_enabled(&use_item.attrs, enabled));
+
+    file.types.retain_mut(|ty| match &mut ty.body {
+        TypeDefBody::Alias(alias) => is_enabled(&alias.attrs, enabled),
+        // Fields cannot belong to a feature, so that the layout does not depend on the enabled features
+        TypeDefBody::Struct(st) => is_enabled(&st.attrs, enabled),
+        TypeDefBody::Union(un) => is_enabled(&un.attrs, enabled),
+        TypeDefBody::Enum(en) => {
+            en.variants
+                .retain(|variant| is_enabled(&variant.attrs, enabled));
+            is_enabled(&en.attrs, enabled)
+        }
+    });
+
+    file.values.retain(|val| match &val.body {
+        ValueBody::Const(c) => is_enabled(&c.attrs, enabled),
+        ValueBody::Function(f) => is_enabled(&f.attrs, enabled),
+    });
+
+    true
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use bincode::error::DecodeError;
+
+use crate::{
+    bundle::{Bundle, Format, Path},
+    registry::{IndexEntry, parse_sha256, sha256, sha256_hex},
+};
+
+/// The expected SHA-256 checksums of fetched bundles, in the format written by `sha256sum`:
+/// each line is a hexadecimal checksum, whitespace, and the name of a bundle (optionally preceded by `*`).
+///
+/// A bundle is looked up by its full URL, and then by the last segment of the URL's path, such as `base.tar.gz`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub checksums: BTreeMap<String, [u8; 32]>,
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Self, FetchError> {
+        let mut checksums = BTreeMap::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (hash, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or(FetchError::InvalidManifestEntry(n + 1))?;
+            let name = name.trim_start();
+            let name = name.strip_prefix('*').unwrap_or(name);
+            let hash = parse_sha256(hash).ok_or(FetchError::InvalidManifestEntry(n + 1))?;
+            checksums.insert(name.to_string(), hash);
+        }
+        Ok(Self { checksums })
+    }
+
+    /// The expected checksum of the bundle at `url`, if the manifest lists it
+    pub fn checksum(&self, url: &str) -> Option<&[u8; 32]> {
+        self.checksums.get(url).or_else(|| {
+            let path = url.split(['?', '#']).next()?;
+            self.checksums.get(path.rsplit('/').next()?)
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// The URL is not an `http://` or `https://` URL
+    UnsupportedUrl(String),
+    /// The request failed, or the server responded with an error
+    Http(String, ureq::Error),
+    /// The line (starting from 1) of a manifest is not a checksum followed by a name
+    InvalidManifestEntry(usize),
+    /// The manifest does not list the bundle at the URL
+    MissingChecksum(String),
+    ChecksumMismatch {
+        url: String,
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    Decode(DecodeError),
+}
+
+impl core::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::UnsupportedUrl(url) => f.write_fmt(format_args!(
+                "{url}: only http:// and https:// URLs can be fetched"
+            )),
+            FetchError::Http(url, e) => f.write_fmt(format_args!("{url}: {e}")),
+            FetchError::InvalidManifestEntry(line) => {
+                f.write_fmt(format_args!("invalid manifest entry on line {line}"))
+            }
+            FetchError::MissingChecksum(url) => {
+                f.write_fmt(format_args!("{url}: not listed in the manifest"))
+            }
+            FetchError::ChecksumMismatch {
+                url,
+                expected,
+                actual,
+            } => f.write_fmt(format_args!(
+                "{url}: checksum mismatch (expected sha256 {}, got {})",
+                sha256_hex(expected),
+                sha256_hex(actual)
+            )),
+            FetchError::Decode(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FetchError::Http(_, e) => Some(e),
+            FetchError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for FetchError {
+    fn from(value: DecodeError) -> Self {
+        Self::Decode(value)
+    }
+}
+
+/// Whether `input` names a bundle to fetch rather than a local file
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("https://") || input.starts_with("http://")
+}
+
+/// Downloads the contents at `url`. If `manifest` is given, the contents must match the checksum it lists for `url`.
+pub fn fetch(url: &str, manifest: Option<&Manifest>) -> Result<Vec<u8>, FetchError> {
+    let expected = manifest
+        .map(|manifest| {
+            manifest
+                .checksum(url)
+                .ok_or_else(|| FetchError::MissingChecksum(url.to_string()))
+        })
+        .transpose()?;
+    fetch_checked(url, expected)
+}
+
+/// Downloads the bundle listed by `entry` of the index at `index`, and checks it against the hash listed by `entry`
+pub fn fetch_entry(index: &str, entry: &IndexEntry) -> Result<Vec<u8>, FetchError> {
+    fetch_checked(&entry.resolve_location(index), Some(&entry.sha256))
+}
+
+/// Downloads the contents at `url`, which must have the SHA-256 hash `expected` if given
+fn fetch_checked(url: &str, expected: Option<&[u8; 32]>) -> Result<Vec<u8>, FetchError> {
+    if !is_url(url) {
+        return Err(FetchError::UnsupportedUrl(url.to_string()));
+    }
+
+    let bytes = ureq::get(url)
+        .call()
+        .and_then(|mut response| {
+            response
+                .body_mut()
+                .with_config()
+                .limit(u64::MAX)
+                .read_to_vec()
+        })
+        .map_err(|e| FetchError::Http(url.to_string(), e))?;
+
+    if let Some(expected) = expected {
+        let actual = sha256(&bytes);
+        if actual != *expected {
+            return Err(FetchError::ChecksumMismatch {
+                url: url.to_string(),
+                expected: *expected,
+                actual,
+            });
+        }
+    }
+    Ok(bytes)
+}
+
+impl Bundle {
+    /// Downloads the bundle at `url`, verifying it against `manifest` if given, and parses it with [`Bundle::parse_sniffed`] under `prefix`
+    pub fn fetch(
+        &mut self,
+        prefix: Path,
+        url: &str,
+        manifest: Option<&Manifest>,
+    ) -> Result<Format, FetchError> {
+        let bytes = fetch(url, manifest)?;
+        Ok(self.parse_sniffed(prefix, &bytes)?)
+    }
+}
@@ -1,15 +1,23 @@
-use bincode::{Decode, Encode};
+use std::io::Read;
+
+use bincode::{
+    BorrowDecode, Decode, Encode,
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+};
 
 use crate::{
-    attr::{Attribute, AttributeTarget, AttributeTargetKind},
-    header::Header,
+    attr::{Attribute, UnknownAttributePolicy},
+    config::format_config,
+    header::{CURRENT_VERSION, Header, with_format_version},
     tydef::TypeDef,
     uuid::Uuid,
     value::Value,
 };
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug)]
 pub struct File {
+    /// The header the file was decoded with. Files are always encoded in the current format, with [`Header::CURRENT`]
     pub header: Header,
     pub file_id: Uuid,
     pub attributes: Vec<Attribute<File>>,
@@ -18,7 +26,112 @@ pub struct File {
     pub values: Vec<Value>,
 }
 
+impl Encode for File {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        Header::CURRENT.encode(encoder)?;
+        self.file_id.encode(encoder)?;
+        self.attributes.encode(encoder)?;
+        self.uses.encode(encoder)?;
+        self.types.encode(encoder)?;
+        self.values.encode(encoder)
+    }
+}
+
+/// Unrecognized attributes are decoded according to the [`UnknownAttributePolicy`] of the context.
+/// With [`UnknownAttributePolicy::Drop`], they are removed from the decoded file
+impl Decode<UnknownAttributePolicy> for File {
+    fn decode<D: bincode::de::Decoder<Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        let policy = *decoder.context();
+        let header = Header::decode(decoder)?;
+        // Files encoded in newer versions of the format may have items this version cannot decode, or decodes wrongly
+        if header.version > CURRENT_VERSION {
+            return Err(DecodeError::OtherString(format!(
+                "the file is encoded in version {} of the format, but only versions up to {CURRENT_VERSION} are supported",
+                header.version
+            )));
+        }
+        // Files encoded in older versions of the format are migrated as they are decoded
+        let mut file = with_format_version(header.version, || {
+            Ok::<_, DecodeError>(Self {
+                header,
+                file_id: Decode::decode(decoder)?,
+                attributes: Decode::decode(decoder)?,
+                uses: Decode::decode(decoder)?,
+                types: Decode::decode(decoder)?,
+                values: Decode::decode(decoder)?,
+            })
+        })?;
+        if policy == UnknownAttributePolicy::Drop {
+            crate::strip::strip_unignorable(&mut file);
+        }
+        Ok(file)
+    }
+}
+
+impl<'de> BorrowDecode<'de, UnknownAttributePolicy> for File {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        Self::decode(decoder)
+    }
+}
+
+impl File {
+    pub fn find_type(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|ty| ty.name == name)
+    }
+
+    pub fn find_value(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|val| val.name == name)
+    }
+}
+
+/// The leading fields of a [`File`], which can be decoded without decoding the uses, types, and values that follow them
+#[derive(Clone, Debug, Encode)]
+pub struct FileMetadata {
+    pub header: Header,
+    pub file_id: Uuid,
+    pub attributes: Vec<Attribute<File>>,
+}
+
+/// Unrecognized attributes are decoded in the same way as those of a [`File`]
+impl Decode<UnknownAttributePolicy> for FileMetadata {
+    fn decode<D: bincode::de::Decoder<Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        let policy = *decoder.context();
+        let mut metadata = Self {
+            header: Decode::decode(decoder)?,
+            file_id: Decode::decode(decoder)?,
+            attributes: Decode::decode(decoder)?,
+        };
+        if policy == UnknownAttributePolicy::Drop {
+            crate::strip::strip_unignorable_attrs(&mut metadata.attributes);
+        }
+        Ok(metadata)
+    }
+}
+
+impl<'de> BorrowDecode<'de, UnknownAttributePolicy> for FileMetadata {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        Self::decode(decoder)
+    }
+}
+
+impl FileMetadata {
+    /// Reads the metadata from the start of an encoded [`File`], leaving the remainder of `file` unread.
+    /// Unrecognized attributes are handled according to `policy`
+    pub fn read<R: Read>(mut file: R, policy: UnknownAttributePolicy) -> Result<Self, DecodeError> {
+        bincode::decode_from_std_read_with_context(&mut file, format_config(), policy)
+    }
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct UseItem {
     pub attrs: Vec<Attribute<UseItem>>,
     pub path: Vec<String>,
@@ -0,0 +1,147 @@
+use crate::{
+    bundle::{Bundle, Path},
+    docs::file_docs,
+    tydef::{StructBody, TypeDefBody},
+    uses::Expr,
+    value::ValueBody,
+};
+
+/// Where a [`GrepMatch`] was found
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// The name of an item or member, which is of the given kind (such as `struct`, `field`, or `fn`)
+    Name(&'static str),
+    /// A line of documentation
+    Doc,
+    /// The value of a string constant
+    String,
+}
+
+impl MatchKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatchKind::Name(kind) => kind,
+            MatchKind::Doc => "doc",
+            MatchKind::String => "string",
+        }
+    }
+}
+
+/// A line of a bundle that matches the pattern searched by [`grep`]
+#[derive(Clone, Debug)]
+pub struct GrepMatch<'a> {
+    pub module: &'a Path,
+    /// The item (or member of an item, named as in [`ItemDocs`](crate::docs::ItemDocs)) the line belongs to,
+    /// or `None` for the documentation of the module itself
+    pub item: Option<String>,
+    pub kind: MatchKind,
+    /// Each line of the text containing the match, such as all lines of the documentation of the item
+    pub lines: Vec<&'a str>,
+    /// The index of the matching line in `lines`
+    pub line: usize,
+}
+
+impl GrepMatch<'_> {
+    /// The full path of the item the match belongs to, such as `base::io::O_NONBLOCK` or `base::io::Pair::a`
+    pub fn path(&self) -> String {
+        match (&self.item, self.module.0.is_empty()) {
+            (None, _) => self.module.to_string(),
+            (Some(item), true) => item.clone(),
+            (Some(item), false) => format!("{}::{item}", self.module),
+        }
+    }
+}
+
+/// Searches the names of the items of `bundle` and their members, their documentation, and the values of string constants
+/// for lines where `is_match` returns `true`. Matches are returned in the order of the bundle, and within each file,
+/// names and strings precede documentation.
+pub fn grep<'a>(bundle: &'a Bundle, mut is_match: impl FnMut(&str) -> bool) -> Vec<GrepMatch<'a>> {
+    let mut matches = Vec::new();
+
+    for (path, file) in bundle {
+        let mut push = |item: Option<String>, kind: MatchKind, lines: Vec<&'a str>| {
+            for (line, text) in lines.iter().enumerate() {
+                if is_match(text) {
+                    matches.push(GrepMatch {
+                        module: path,
+                        item: item.clone(),
+                        kind,
+                        lines: lines.clone(),
+                        line,
+                    });
+                }
+            }
+        };
+
+        for ty in &file.types {
+            let kind = match &ty.body {
+                TypeDefBody::Alias(_) => "type",
+                TypeDefBody::Struct(_) => "struct",
+                TypeDefBody::Union(_) => "union",
+                TypeDefBody::Enum(_) => "enum",
+            };
+            push(
+                Some(ty.name.to_string()),
+                MatchKind::Name(kind),
+                vec![&ty.name],
+            );
+
+            let (fields, kind) = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => (&fields.field[..], "field"),
+                    StructBody::Opaque(_) => (&[][..], "field"),
+                },
+                TypeDefBody::Union(un) => (&un.fields.field[..], "field"),
+                TypeDefBody::Enum(en) => {
+                    for variant in &en.variants {
+                        push(
+                            Some(format!("{}::{}", ty.name, variant.name)),
+                            MatchKind::Name("variant"),
+                            vec![&variant.name],
+                        );
+                    }
+                    (&[][..], "field")
+                }
+                TypeDefBody::Alias(_) => (&[][..], "field"),
+            };
+            for field in fields {
+                push(
+                    Some(format!("{}::{}", ty.name, field.name)),
+                    MatchKind::Name(kind),
+                    vec![&field.name],
+                );
+            }
+        }
+
+        for val in &file.values {
+            let kind = match &val.body {
+                ValueBody::Const(_) => "const",
+                ValueBody::Function(_) => "fn",
+            };
+            push(
+                Some(val.name.to_string()),
+                MatchKind::Name(kind),
+                vec![&val.name],
+            );
+            if let ValueBody::Const(c) = &val.body
+                && let Expr::StringLiteral(s) = &c.val
+            {
+                push(
+                    Some(val.name.to_string()),
+                    MatchKind::String,
+                    s.lines().collect(),
+                );
+            }
+        }
+
+        for docs in file_docs(file) {
+            push(
+                docs.item,
+                MatchKind::Doc,
+                docs.lines.iter().map(|line| &**line).collect(),
+            );
+        }
+    }
+
+    matches
+}
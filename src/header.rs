@@ -1,6 +1,6 @@
-use bincode::{BorrowDecode, Decode, Encode, de::read::Reader};
+use std::cell::Cell;
 
-use crate::uuid::Uuid;
+use bincode::{BorrowDecode, Decode, Encode};
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct MagicNumber;
@@ -48,6 +48,15 @@ impl Version {
         Self((major << 9) | minor)
     }
 
+    /// Creates a version like [`Version::new`], but returns `None` rather than panicking if a component is out of range
+    pub const fn try_new(major: u16, minor: u16) -> Option<Self> {
+        if major < 128 && minor < 512 {
+            Some(Self::new(major, minor))
+        } else {
+            None
+        }
+    }
+
     pub const fn parse(v: &str) -> Self {
         let mut major = 0;
         let mut minor = 0;
@@ -93,6 +102,18 @@ impl Version {
         Self::new(major, minor)
     }
 
+    /// Parses a version of the form `<major>.<minor>` like [`Version::parse`], but returns `None` rather than panicking if `v` is not of that form or a component is out of range
+    pub fn try_parse(v: &str) -> Option<Self> {
+        let (major, minor) = v.split_once('.')?;
+        let component = |c: &str, max: u16| {
+            if c.is_empty() || !c.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            c.parse::<u16>().ok().filter(|&c| c < max)
+        };
+        Some(Self::new(component(major, 128)?, component(minor, 512)?))
+    }
+
     pub const fn major(self) -> u16 {
         self.0 >> 9
     }
@@ -123,11 +144,37 @@ impl core::fmt::Display for Version {
     }
 }
 
-pub const CURRENT_VERSION: Version = Version::parse(core::concat!(
-    core::env!("CARGO_PKG_VERSION_MAJOR"),
-    ".",
-    core::env!("CARGO_PKG_VERSION_MINOR")
-));
+/// The version of the format that files are encoded in, which changes independently of the version of this crate
+pub const CURRENT_VERSION: Version = Version::new(0, 2);
+
+/// The first version of the format in which a [`Signature`](crate::uses::Signature) has `ret_attrs`
+pub const RETURN_ATTRS_VERSION: Version = Version::new(0, 2);
+
+thread_local! {
+    static FORMAT_VERSION: Cell<Version> = const { Cell::new(CURRENT_VERSION) };
+}
+
+/// Decodes items as they were encoded in the format version `version` for the duration of `f`.
+///
+/// Decoding a [`File`](crate::file::File) does this with the version in its header, so that files encoded in an older version of the format are
+/// migrated to the current one. The version is tracked per thread.
+pub fn with_format_version<R>(version: Version, f: impl FnOnce() -> R) -> R {
+    struct Restore(Version);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORMAT_VERSION.set(self.0);
+        }
+    }
+
+    let _restore = Restore(FORMAT_VERSION.replace(version));
+    f()
+}
+
+/// The format version that items are being decoded from, set by [`with_format_version`]
+pub(crate) fn format_version() -> Version {
+    FORMAT_VERSION.get()
+}
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
 pub struct Header {
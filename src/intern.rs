@@ -0,0 +1,187 @@
+use std::{cell::RefCell, collections::HashSet, sync::Arc};
+
+use bincode::{BorrowDecode, Decode, Encode};
+
+/// An immutable, cheaply cloneable string, used for names and doc lines.
+///
+/// Symbols decoded into a [`Bundle`](crate::bundle::Bundle) are interned by the bundle's [`Interner`],
+/// so each distinct name is only stored once. Symbols are encoded the same way as a [`String`].
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::ops::Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::borrow::Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl core::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(value: Symbol) -> Self {
+        value.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        *self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<Symbol> for str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == *other.0
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        **self == *other.0
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        **self == *other.0
+    }
+}
+
+impl Encode for Symbol {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.as_str().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for Symbol {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let s = String::decode(decoder)?;
+        Ok(ACTIVE_INTERNER.with_borrow_mut(|interner| match interner {
+            Some(interner) => interner.intern(&s),
+            None => Symbol::from(s),
+        }))
+    }
+}
+
+impl<'de, C> BorrowDecode<'de, C> for Symbol {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = C>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Self::decode(decoder)
+    }
+}
+
+/// Deduplicates the storage of [`Symbol`]s
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a symbol for `s`, sharing storage with any symbol previously interned with the same contents
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(s) = self.strings.get(s) {
+            return Symbol(s.clone());
+        }
+        let s: Arc<str> = s.into();
+        self.strings.insert(s.clone());
+        Symbol(s)
+    }
+
+    /// The number of distinct strings interned
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+thread_local! {
+    static ACTIVE_INTERNER: RefCell<Option<Interner>> = const { RefCell::new(None) };
+}
+
+/// Interns every [`Symbol`] decoded on this thread with `interner` for the duration of `f`.
+///
+/// The interner is tracked per thread, so that symbols decoded with any bincode context are interned.
+pub fn with_interner<R>(interner: &mut Interner, f: impl FnOnce() -> R) -> R {
+    struct Restore<'a> {
+        interner: &'a mut Interner,
+        prev: Option<Interner>,
+    }
+
+    impl Drop for Restore<'_> {
+        fn drop(&mut self) {
+            if let Some(interner) = ACTIVE_INTERNER.replace(self.prev.take()) {
+                *self.interner = interner;
+            }
+        }
+    }
+
+    let prev = ACTIVE_INTERNER.replace(Some(core::mem::take(interner)));
+    let _restore = Restore { interner, prev };
+    f()
+}
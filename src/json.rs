@@ -0,0 +1,580 @@
+use core::fmt::Write as _;
+
+use crate::{
+    attr::{Attribute, AttributeTarget, attribute_info, schema::payload_json},
+    bundle::Bundle,
+    file::{File, UseItem},
+    header::{Header, Version},
+    intern::Symbol,
+    metrics::{DecodeTime, Metrics, OutputSize},
+    tydef::{
+        Enum, Field, Struct, StructBody, StructFields, TypeAlias, TypeDef, TypeDefBody, Union,
+        Variant,
+    },
+    uses::{
+        ArrayType, BinaryOp, Expr, IntBits, IntType, Param, PointerKind, Signature, SpecialConst,
+        Type, UnaryOp,
+    },
+    uuid::Uuid,
+    value::{Const, Function, Value, ValueBody},
+};
+
+/// A type that can be written as JSON, so that tools can consume decoded files without depending on imt.
+///
+/// Structs are written as objects with a key for each field, and enums like serde's externally tagged representation:
+/// unit variants are their name, and other variants are an object with their name as its only key,
+/// whose value is the single field of the variant, or an array of its fields.
+/// Names, UUIDs, and versions are strings.
+/// Integers are numbers, unless their magnitude exceeds 2^53 - 1 (the largest integer that an IEEE 754 double holds exactly,
+/// which many JSON parsers read numbers as), in which case they are strings of their decimal value, so that no precision is lost.
+pub trait ToJson {
+    fn write_json(&self, out: &mut String);
+
+    fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+}
+
+/// `s` as a JSON string
+pub fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    write_string(&mut out, s);
+    out
+}
+
+/// The largest integer that a JSON parser reading numbers as IEEE 754 doubles holds exactly
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// `n` as a JSON number, or as a string if its magnitude exceeds [`MAX_SAFE_INTEGER`] (see [`ToJson`])
+pub(crate) fn integer<T: Copy + core::fmt::Display + TryInto<i64>>(n: T) -> String {
+    match TryInto::<i64>::try_into(n) {
+        Ok(small) if small.unsigned_abs() <= MAX_SAFE_INTEGER => n.to_string(),
+        _ => string(&n.to_string()),
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes the fields of an object, one [`Object::field`] at a time
+struct Object<'a> {
+    out: &'a mut String,
+    sep: &'static str,
+}
+
+impl<'a> Object<'a> {
+    fn new(out: &'a mut String) -> Self {
+        out.push('{');
+        Self { out, sep: "" }
+    }
+
+    fn field(mut self, name: &str, val: &(impl ToJson + ?Sized)) -> Self {
+        self.out.push_str(self.sep);
+        write_string(self.out, name);
+        self.out.push(':');
+        val.write_json(self.out);
+        self.sep = ",";
+        self
+    }
+
+    fn finish(self) {
+        self.out.push('}');
+    }
+}
+
+/// Writes the variant `name` with the fields `val`
+fn variant(out: &mut String, name: &str, val: &(impl ToJson + ?Sized)) {
+    Object::new(out).field(name, val).finish()
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn write_json(&self, out: &mut String) {
+        (**self).write_json(out)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn write_json(&self, out: &mut String) {
+        (**self).write_json(out)
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(val) => val.write_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        let mut sep = "";
+        for val in self {
+            out.push_str(sep);
+            val.write_json(out);
+            sep = ",";
+        }
+        out.push(']');
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn write_json(&self, out: &mut String) {
+        (**self).write_json(out)
+    }
+}
+
+impl<A: ToJson, B: ToJson> ToJson for (A, B) {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        self.0.write_json(out);
+        out.push(',');
+        self.1.write_json(out);
+        out.push(']');
+    }
+}
+
+impl<A: ToJson, B: ToJson, C: ToJson> ToJson for (A, B, C) {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        self.0.write_json(out);
+        out.push(',');
+        self.1.write_json(out);
+        out.push(',');
+        self.2.write_json(out);
+        out.push(']');
+    }
+}
+
+impl ToJson for str {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, self)
+    }
+}
+
+impl ToJson for String {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, self)
+    }
+}
+
+impl ToJson for Symbol {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, self)
+    }
+}
+
+impl ToJson for Uuid {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, &self.to_string())
+    }
+}
+
+impl ToJson for Version {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, &self.to_string())
+    }
+}
+
+macro_rules! display_json {
+    ($($ty:ty),*) => {
+        $(impl ToJson for $ty {
+            fn write_json(&self, out: &mut String) {
+                let _ = write!(out, "{self}");
+            }
+        })*
+    };
+}
+
+display_json!(bool);
+
+macro_rules! integer_json {
+    ($($ty:ty),*) => {
+        $(impl ToJson for $ty {
+            fn write_json(&self, out: &mut String) {
+                out.push_str(&integer(*self));
+            }
+        })*
+    };
+}
+
+integer_json!(u8, u32, u64, u128);
+
+/// The bundle as an object with a key for each file, which is the module of the file
+impl ToJson for Bundle {
+    fn write_json(&self, out: &mut String) {
+        let mut obj = Object::new(out);
+        for (path, file) in self {
+            obj = obj.field(&path.to_string(), file);
+        }
+        obj.finish()
+    }
+}
+
+impl ToJson for File {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("header", &self.header)
+            .field("file_id", &self.file_id)
+            .field("attributes", &self.attributes)
+            .field("uses", &self.uses)
+            .field("types", &self.types)
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
+impl ToJson for Header {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out).field("version", &self.version).finish()
+    }
+}
+
+impl ToJson for UseItem {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// An attribute as an object with its `id`, `name` (if it is recognized), `class`, `flags`, and `payload`.
+/// The payload is rendered with the [`Schema`](crate::attr::schema::Schema) of the attribute, and is `null` if the attribute is unknown
+impl<Targ: AttributeTarget> ToJson for Attribute<Targ> {
+    fn write_json(&self, out: &mut String) {
+        let payload = self
+            .described_payload()
+            .and_then(|payload| payload.ok())
+            .and_then(|(schema, bytes)| payload_json(&schema, &bytes).ok());
+        Object::new(out)
+            .field("id", self.id())
+            .field("name", &attribute_info(*self.id()).map(|info| info.name))
+            .field("class", &self.flags().type_class().to_string())
+            .field("flags", &self.flags().bits())
+            .field("payload", &payload.as_deref().map(Raw))
+            .finish()
+    }
+}
+
+/// Text that is already JSON
+struct Raw<'a>(&'a str);
+
+impl ToJson for Raw<'_> {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(self.0)
+    }
+}
+
+impl ToJson for TypeDef {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("name", &self.name)
+            .field("num_params", &self.num_params)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl ToJson for TypeDefBody {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            TypeDefBody::Alias(alias) => variant(out, "Alias", alias),
+            TypeDefBody::Struct(st) => variant(out, "Struct", st),
+            TypeDefBody::Union(un) => variant(out, "Union", un),
+            TypeDefBody::Enum(en) => variant(out, "Enum", en),
+        }
+    }
+}
+
+impl ToJson for TypeAlias {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("alias", &self.alias)
+            .finish()
+    }
+}
+
+impl ToJson for Struct {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl ToJson for StructBody {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            StructBody::Fields(fields) => variant(out, "Fields", fields),
+            StructBody::Opaque(ty) => variant(out, "Opaque", ty),
+        }
+    }
+}
+
+impl ToJson for StructFields {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("field", &self.field)
+            .field("pad", &self.pad)
+            .finish()
+    }
+}
+
+impl ToJson for Field {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("name", &self.name)
+            .field("ty", &self.ty)
+            .finish()
+    }
+}
+
+impl ToJson for Union {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("fields", &self.fields)
+            .finish()
+    }
+}
+
+impl ToJson for Enum {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("underlying", &self.underlying)
+            .field("variants", &self.variants)
+            .finish()
+    }
+}
+
+impl ToJson for Variant {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("name", &self.name)
+            .field("discrim", &self.discrim)
+            .finish()
+    }
+}
+
+impl ToJson for Value {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("name", &self.name)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl ToJson for ValueBody {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            ValueBody::Const(c) => variant(out, "Const", c),
+            ValueBody::Function(f) => variant(out, "Function", f),
+        }
+    }
+}
+
+impl ToJson for Const {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("ty", &self.ty)
+            .field("val", &self.val)
+            .finish()
+    }
+}
+
+impl ToJson for Function {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+impl ToJson for Type {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Type::Named(name, args) => variant(out, "Named", &(name, args)),
+            Type::Param(n, default) => variant(out, "Param", &(n, default)),
+            Type::Int(int) => variant(out, "Int", int),
+            Type::Pointer(kind, ty) => variant(out, "Pointer", &(kind, ty)),
+            Type::Func(sig) => variant(out, "Func", sig),
+            Type::Void => write_string(out, "Void"),
+            Type::Never => write_string(out, "Never"),
+            Type::Byte => write_string(out, "Byte"),
+            Type::Char(int) => variant(out, "Char", int),
+            Type::Array(arr) => variant(out, "Array", arr),
+            Type::Uninit(ty) => variant(out, "Uninit", ty),
+            Type::Const(val) => variant(out, "Const", val),
+        }
+    }
+}
+
+impl ToJson for ArrayType {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("base", &self.base)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl ToJson for IntType {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("signed", &self.signed)
+            .field("bits", &self.bits)
+            .finish()
+    }
+}
+
+impl ToJson for IntBits {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            IntBits::Long => write_string(out, "Long"),
+            IntBits::Bits(bits) => variant(out, "Bits", &bits.get()),
+        }
+    }
+}
+
+impl ToJson for PointerKind {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            PointerKind::Const => write_string(out, "Const"),
+            PointerKind::Mut => write_string(out, "Mut"),
+            PointerKind::Special(id) => variant(out, "Special", id),
+        }
+    }
+}
+
+impl ToJson for Signature {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("params", &self.params)
+            .field("ret_attrs", &self.ret_attrs)
+            .field("retty", &self.retty)
+            .finish()
+    }
+}
+
+impl ToJson for Param {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("attrs", &self.attrs)
+            .field("name", &self.name)
+            .field("ty", &self.ty)
+            .finish()
+    }
+}
+
+impl ToJson for Expr {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Expr::IntLiteral(ty, val) => variant(out, "IntLiteral", &(ty, val)),
+            Expr::UuidLiteral(id) => variant(out, "UuidLiteral", id),
+            Expr::StringLiteral(s) => variant(out, "StringLiteral", s),
+            Expr::Const(name) => variant(out, "Const", name),
+            Expr::BinOp(op, left, right) => variant(out, "BinOp", &(op, left, right)),
+            Expr::UnaryOp(op, inner) => variant(out, "UnaryOp", &(op, inner)),
+            Expr::SpecialConstant(special) => variant(out, "SpecialConstant", special),
+            Expr::Param(n) => variant(out, "Param", n),
+        }
+    }
+}
+
+impl ToJson for SpecialConst {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            SpecialConst::SizeofPointer => write_string(out, "SizeofPointer"),
+            SpecialConst::SizeofLong => write_string(out, "SizeofLong"),
+            SpecialConst::PageSize => write_string(out, "PageSize"),
+            SpecialConst::MaxPathLen => write_string(out, "MaxPathLen"),
+            SpecialConst::MaxHandleCount => write_string(out, "MaxHandleCount"),
+            SpecialConst::Custom(id) => variant(out, "Custom", id),
+        }
+    }
+}
+
+impl ToJson for BinaryOp {
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "\"{self:?}\"");
+    }
+}
+
+impl ToJson for UnaryOp {
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "\"{self:?}\"");
+    }
+}
+
+/// The metrics as an object with the `decode` time of each file, the total time of each of the `phases`, the `counts`,
+/// and the size of each of the `outputs`. Times are in microseconds
+impl ToJson for Metrics {
+    fn write_json(&self, out: &mut String) {
+        let mut phases = String::new();
+        let mut obj = Object::new(&mut phases);
+        for (name, time) in &self.phases {
+            obj = obj.field(name, &time.as_micros());
+        }
+        obj.finish();
+
+        let mut counts = String::new();
+        let mut obj = Object::new(&mut counts);
+        for (name, count) in &self.counts {
+            obj = obj.field(name, count);
+        }
+        obj.finish();
+
+        Object::new(out)
+            .field("decode", &self.decode)
+            .field("phases", &Raw(&phases))
+            .field("counts", &Raw(&counts))
+            .field("outputs", &self.outputs)
+            .finish()
+    }
+}
+
+impl ToJson for DecodeTime {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("path", &self.path.to_string())
+            .field("time_us", &self.time.as_micros())
+            .finish()
+    }
+}
+
+impl ToJson for OutputSize {
+    fn write_json(&self, out: &mut String) {
+        Object::new(out)
+            .field("name", &self.name)
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashSet;
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{Align, Niche},
+    },
+    bundle::{Bundle, Path},
+    eval::{EvalError, Evaluator, int_bits},
+    intern::Symbol,
+    resolve::{Item, ResolveError, module_of, resolve_name},
+    target::TargetSpec,
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{IntType, Type},
+};
+
+/// The size and alignment of a type, in bytes
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u128,
+    pub align: u128,
+}
+
+impl Layout {
+    pub const fn new(size: u128, align: u128) -> Self {
+        Self { size, align }
+    }
+
+    fn pad_to_align(self) -> Result<Self, LayoutError> {
+        Ok(Self {
+            size: round_up(self.size, self.align)?,
+            align: self.align,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: Symbol,
+    pub offset: u128,
+    pub layout: Layout,
+    /// The value reserved by the field or its type, if any
+    pub niche: Option<Niche>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    Resolve(ResolveError),
+    Eval(EvalError),
+    NotAType(Path),
+    /// The type is a function type or opaque struct, which has no layout of its own
+    Unsized(String),
+    Generic(String),
+    Overflow,
+    InvalidAlign(u128),
+    Cycle(Path),
+}
+
+impl core::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::Resolve(e) => e.fmt(f),
+            LayoutError::Eval(e) => e.fmt(f),
+            LayoutError::NotAType(path) => f.write_fmt(format_args!("{path} is not a type")),
+            LayoutError::Unsized(what) => f.write_fmt(format_args!("{what} has no known size")),
+            LayoutError::Generic(what) => {
+                f.write_fmt(format_args!("{what} has unsubstituted generic parameters"))
+            }
+            LayoutError::Overflow => f.write_str("type is too large"),
+            LayoutError::InvalidAlign(align) => {
+                f.write_fmt(format_args!("alignment {align} is not a power of two"))
+            }
+            LayoutError::Cycle(path) => f.write_fmt(format_args!(
+                "{path} contains itself by value, so it has infinite size"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+impl From<ResolveError> for LayoutError {
+    fn from(value: ResolveError) -> Self {
+        Self::Resolve(value)
+    }
+}
+
+impl From<EvalError> for LayoutError {
+    fn from(value: EvalError) -> Self {
+        Self::Eval(value)
+    }
+}
+
+/// An argument to a generic parameter.
+/// The layout of a type argument is only needed if the generic type contains it by value, so errors computing it are reported when it is used
+#[derive(Clone, Debug)]
+enum GenericArg {
+    Type(Result<Layout, LayoutError>),
+    Const(i128),
+}
+
+/// The values of the constant arguments in `params`, for evaluating [`Expr::Param`](crate::uses::Expr::Param)
+fn const_args(params: &[GenericArg]) -> Vec<Option<i128>> {
+    params
+        .iter()
+        .map(|arg| match arg {
+            GenericArg::Type(_) => None,
+            GenericArg::Const(val) => Some(*val),
+        })
+        .collect()
+}
+
+fn round_up(size: u128, align: u128) -> Result<u128, LayoutError> {
+    size.checked_next_multiple_of(align)
+        .ok_or(LayoutError::Overflow)
+}
+
+/// Computes the C layout of types in a bundle for a particular target
+pub struct LayoutEngine<'a> {
+    bundle: &'a Bundle,
+    target: &'a TargetSpec,
+    eval: Evaluator<'a>,
+    visiting: HashSet<Path>,
+}
+
+impl<'a> LayoutEngine<'a> {
+    pub fn new(bundle: &'a Bundle, target: &'a TargetSpec) -> Self {
+        Self {
+            bundle,
+            target,
+            eval: Evaluator::new(bundle, target),
+            visiting: HashSet::new(),
+        }
+    }
+
+    pub fn int_layout(&self, ty: IntType) -> Layout {
+        let bytes = int_bits(ty, self.target).div_ceil(8).next_power_of_two() as u128;
+        if bytes == 16 {
+            Layout::new(16, self.target.int128_align as u128)
+        } else {
+            Layout::new(bytes, bytes)
+        }
+    }
+
+    /// Computes the layout of `ty`, resolving names as seen from `module`.
+    pub fn layout_of(&mut self, module: &Path, ty: &Type) -> Result<Layout, LayoutError> {
+        self.layout_with_params(module, ty, &[])
+    }
+
+    /// Computes the layout of the non-generic type definition `def`, which is defined in `module`.
+    pub fn layout_of_def(&mut self, module: &Path, def: &TypeDef) -> Result<Layout, LayoutError> {
+        if def.num_params != 0 {
+            return Err(LayoutError::Generic(module.join(&def.name).to_string()));
+        }
+        self.def_layout(module, def, &[])
+    }
+
+    /// Computes the offset and layout of each field of a struct.
+    pub fn field_layouts(
+        &mut self,
+        module: &Path,
+        fields: &StructFields,
+    ) -> Result<Vec<FieldLayout>, LayoutError> {
+        let mut offset = 0;
+        let mut res = Vec::with_capacity(fields.field.len());
+        for field in &fields.field {
+            let layout = self.layout_of(module, &field.ty)?;
+            offset = round_up(offset, layout.align)?;
+            let niche = match find_attribute::<Niche, _>(&field.attrs) {
+                Some(niche) => Some(*niche),
+                None => self.niche_of(module, &field.ty)?,
+            };
+            res.push(FieldLayout {
+                name: field.name.clone(),
+                offset,
+                layout,
+                niche,
+            });
+            offset = offset
+                .checked_add(layout.size)
+                .ok_or(LayoutError::Overflow)?;
+        }
+        Ok(res)
+    }
+
+    /// Computes the offset and layout of the explicit padding of a struct (its [`StructFields::pad`]), if it has any.
+    /// The padding is named `_pad`, as in generated code, and is placed after the last field like another field
+    pub fn pad_layout(
+        &mut self,
+        module: &Path,
+        fields: &StructFields,
+    ) -> Result<Option<FieldLayout>, LayoutError> {
+        let Some(pad) = &fields.pad else {
+            return Ok(None);
+        };
+        let end = self
+            .field_layouts(module, fields)?
+            .last()
+            .map_or(Ok(0), |field| {
+                field
+                    .offset
+                    .checked_add(field.layout.size)
+                    .ok_or(LayoutError::Overflow)
+            })?;
+        let layout = self.layout_of(module, pad)?;
+        Ok(Some(FieldLayout {
+            name: "_pad".into(),
+            offset: round_up(end, layout.align)?,
+            layout,
+            niche: None,
+        }))
+    }
+
+    /// Finds the value reserved by `ty` through a [`Niche`] attribute on the struct or type alias it names, if any.
+    /// Type aliases without a [`Niche`] attribute reserve the same value as the aliased type.
+    pub fn niche_of(&mut self, module: &Path, ty: &Type) -> Result<Option<Niche>, LayoutError> {
+        let Type::Named(name, _) = ty else {
+            return Ok(None);
+        };
+        let resolved = resolve_name(self.bundle, module, name)?;
+        let Item::Type(def) = resolved.item else {
+            return Err(LayoutError::NotAType(resolved.path));
+        };
+        match &def.body {
+            TypeDefBody::Alias(alias) => match find_attribute::<Niche, _>(&alias.attrs) {
+                Some(niche) => Ok(Some(*niche)),
+                None => {
+                    if !self.visiting.insert(resolved.path.clone()) {
+                        return Err(LayoutError::Cycle(resolved.path));
+                    }
+                    let res = self.niche_of(&module_of(&resolved.path), &alias.alias);
+                    self.visiting.remove(&resolved.path);
+                    res
+                }
+            },
+            TypeDefBody::Struct(st) => Ok(find_attribute::<Niche, _>(&st.attrs).copied()),
+            _ => Ok(None),
+        }
+    }
+
+    fn layout_with_params(
+        &mut self,
+        module: &Path,
+        ty: &Type,
+        params: &[GenericArg],
+    ) -> Result<Layout, LayoutError> {
+        match ty {
+            Type::Named(name, args) => {
+                let args = args
+                    .iter()
+                    .flatten()
+                    .map(|arg| match arg {
+                        Type::Const(val) => Ok(GenericArg::Const(self.eval.eval_with_params(
+                            module,
+                            val,
+                            &const_args(params),
+                        )?)),
+                        arg => Ok(GenericArg::Type(
+                            self.layout_with_params(module, arg, params),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, LayoutError>>()?;
+
+                let resolved = resolve_name(self.bundle, module, name)?;
+                let Item::Type(def) = resolved.item else {
+                    return Err(LayoutError::NotAType(resolved.path));
+                };
+                if args.len() != def.num_params as usize {
+                    return Err(LayoutError::Generic(resolved.path.to_string()));
+                }
+
+                if !self.visiting.insert(resolved.path.clone()) {
+                    return Err(LayoutError::Cycle(resolved.path));
+                }
+                let res = self.def_layout(&module_of(&resolved.path), def, &args);
+                self.visiting.remove(&resolved.path);
+                res
+            }
+            Type::Param(n, _) => match params.get(*n as usize) {
+                Some(GenericArg::Type(layout)) => layout.clone(),
+                _ => Err(LayoutError::Generic(format!("parameter {n}"))),
+            },
+            Type::Int(int) | Type::Char(int) => Ok(self.int_layout(*int)),
+            Type::Pointer(_, _) => {
+                let bytes = (self.target.pointer_bits / 8) as u128;
+                Ok(Layout::new(bytes, bytes))
+            }
+            Type::Func(_) => Err(LayoutError::Unsized("function type".to_string())),
+            Type::Void | Type::Never => Ok(Layout::new(0, 1)),
+            Type::Byte => Ok(Layout::new(1, 1)),
+            Type::Array(arr) => {
+                let base = self.layout_with_params(module, &arr.base, params)?;
+                let len = self
+                    .eval
+                    .eval_with_params(module, &arr.len, &const_args(params))?;
+                let len = u128::try_from(len).map_err(|_| EvalError::Overflow)?;
+                let size = base.size.checked_mul(len).ok_or(LayoutError::Overflow)?;
+                Ok(Layout::new(size, base.align))
+            }
+            Type::Uninit(ty) => self.layout_with_params(module, ty, params),
+            Type::Const(val) => Err(LayoutError::Unsized(format!("constant {val}"))),
+        }
+    }
+
+    fn fields_layout(
+        &mut self,
+        module: &Path,
+        fields: &StructFields,
+        params: &[GenericArg],
+        union: bool,
+    ) -> Result<Layout, LayoutError> {
+        let mut layout = Layout::new(0, 1);
+        for ty in fields.field.iter().map(|f| &f.ty).chain(&fields.pad) {
+            let field = self.layout_with_params(module, ty, params)?;
+            layout.align = layout.align.max(field.align);
+            layout.size = if union {
+                layout.size.max(field.size)
+            } else {
+                round_up(layout.size, field.align)?
+                    .checked_add(field.size)
+                    .ok_or(LayoutError::Overflow)?
+            };
+        }
+        Ok(layout)
+    }
+
+    fn def_layout(
+        &mut self,
+        module: &Path,
+        def: &TypeDef,
+        params: &[GenericArg],
+    ) -> Result<Layout, LayoutError> {
+        let (layout, align) = match &def.body {
+            TypeDefBody::Alias(alias) => {
+                return self.layout_with_params(module, &alias.alias, params);
+            }
+            TypeDefBody::Struct(st) => {
+                let layout = match &st.body {
+                    StructBody::Fields(fields) => {
+                        self.fields_layout(module, fields, params, false)?
+                    }
+                    StructBody::Opaque(Some(repr)) => {
+                        self.layout_with_params(module, repr, params)?
+                    }
+                    StructBody::Opaque(None) => {
+                        return Err(LayoutError::Unsized(module.join(&def.name).to_string()));
+                    }
+                };
+                (layout, find_attribute::<Align, _>(&st.attrs))
+            }
+            TypeDefBody::Union(un) => (
+                self.fields_layout(module, &un.fields, params, true)?,
+                find_attribute::<Align, _>(&un.attrs),
+            ),
+            TypeDefBody::Enum(en) => return Ok(self.int_layout(en.underlying)),
+        };
+
+        let mut layout = layout;
+        if let Some(align) = align {
+            if !align.alignment.is_power_of_two() {
+                return Err(LayoutError::InvalidAlign(align.alignment));
+            }
+            layout.align = layout.align.max(align.alignment);
+        }
+        layout.pad_to_align()
+    }
+}
@@ -1,5 +1,6 @@
 pub mod attr;
 pub mod header;
+pub mod intern;
 
 pub mod config;
 pub mod uuid;
@@ -10,4 +11,57 @@ pub mod value;
 
 pub mod uses;
 
+pub mod consts;
+pub mod eval;
+pub mod layout;
+pub mod option;
+pub mod resolve;
+pub mod special;
+pub mod tagged;
+
 pub mod bundle;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+pub mod db;
+
+pub mod features;
+pub mod json;
+pub mod normalize;
+pub mod print;
+pub mod provenance;
+pub mod strip;
+pub mod text;
+pub mod visit;
+
+pub mod abi;
+pub mod audit;
+pub mod baseline;
+pub mod changelog;
+pub mod diagnostics;
+pub mod diff;
+pub mod docs;
+#[cfg(feature = "dwarf")]
+pub mod dwarf;
+#[cfg(feature = "elf")]
+pub mod elf;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+pub mod grep;
+pub mod lock;
+pub mod metrics;
+pub mod padding;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(feature = "rustdoc")]
+pub mod rustdoc;
+pub mod size;
+pub mod validate;
+
+pub mod codegen;
+#[cfg(feature = "dylib")]
+pub mod dylib;
+#[cfg(feature = "project")]
+pub mod project;
+pub mod target;
@@ -0,0 +1,145 @@
+use std::io::{BufRead, Write};
+
+use bincode::Encode;
+use indexmap::IndexMap;
+
+use crate::{
+    bundle::{Bundle, Path},
+    config::format_config,
+};
+
+/// Hashes the encoded form of `item` with 64-bit FNV-1a.
+///
+/// The hash only depends on the encoded bytes, so it is stable between builds and platforms.
+pub fn content_hash<T: Encode>(item: &T) -> u64 {
+    let bytes =
+        bincode::encode_to_vec(item, format_config()).expect("encoding into a Vec does not fail");
+    fnv1a(&bytes)
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The content hash of each item of a bundle, used to detect unintended changes to an interface
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    pub items: IndexMap<Path, u64>,
+}
+
+/// A difference between a [`Lockfile`] and a bundle
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockMismatch {
+    /// The item is in the bundle but not in the lockfile
+    Added(Path),
+    /// The item is in the lockfile but not in the bundle
+    Removed(Path),
+    /// The content of the item differs from when the lockfile was generated
+    Changed(Path),
+}
+
+impl core::fmt::Display for LockMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockMismatch::Added(path) => f.write_fmt(format_args!("{path} was added")),
+            LockMismatch::Removed(path) => f.write_fmt(format_args!("{path} was removed")),
+            LockMismatch::Changed(path) => f.write_fmt(format_args!("{path} was changed")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LockfileError {
+    Io(std::io::Error),
+    /// The line (starting from 1) is not a path followed by a hexadecimal hash
+    InvalidEntry(usize),
+}
+
+impl core::fmt::Display for LockfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockfileError::Io(e) => e.fmt(f),
+            LockfileError::InvalidEntry(line) => {
+                f.write_fmt(format_args!("invalid lockfile entry on line {line}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LockfileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LockfileError::Io(e) => Some(e),
+            LockfileError::InvalidEntry(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LockfileError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl Lockfile {
+    /// Computes the content hash of every type and value in `bundle`
+    pub fn freeze(bundle: &Bundle) -> Self {
+        let mut items = IndexMap::new();
+        for (path, file) in bundle.iter() {
+            for ty in &file.types {
+                items.insert(path.join(&ty.name), content_hash(ty));
+            }
+            for val in &file.values {
+                items.insert(path.join(&val.name), content_hash(val));
+            }
+        }
+        Self { items }
+    }
+
+    /// Compares `bundle` against the lockfile, reporting each item that was added, removed, or changed
+    pub fn check(&self, bundle: &Bundle) -> Vec<LockMismatch> {
+        let current = Self::freeze(bundle);
+        let mut res = Vec::new();
+        for (path, hash) in &self.items {
+            match current.items.get(path) {
+                None => res.push(LockMismatch::Removed(path.clone())),
+                Some(current) if current != hash => res.push(LockMismatch::Changed(path.clone())),
+                Some(_) => {}
+            }
+        }
+        for path in current.items.keys() {
+            if !self.items.contains_key(path) {
+                res.push(LockMismatch::Added(path.clone()));
+            }
+        }
+        res
+    }
+
+    /// Writes the lockfile as one `path hash` line per item
+    pub fn write<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        for (path, hash) in &self.items {
+            writeln!(w, "{path} {hash:016x}")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a lockfile in the format produced by [`Lockfile::write`]. Empty lines and lines starting with `#` are ignored.
+    pub fn read<R: BufRead>(r: R) -> Result<Self, LockfileError> {
+        let mut items = IndexMap::new();
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (path, hash) = line
+                .split_once(' ')
+                .and_then(|(path, hash)| Some((path, u64::from_str_radix(hash.trim(), 16).ok()?)))
+                .ok_or(LockfileError::InvalidEntry(n + 1))?;
+            items.insert(Path(path.split("::").map(str::to_string).collect()), hash);
+        }
+        Ok(Self { items })
+    }
+}
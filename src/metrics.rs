@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use indexmap::IndexMap;
+
+use crate::bundle::{Bundle, Path};
+
+/// The time taken to parse a file into a bundle, recorded by [`Bundle::record_decode_times`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeTime {
+    pub path: Path,
+    pub time: Duration,
+}
+
+/// The size of an output written by a code generator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputSize {
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// Timing and count metrics of processing a bundle, so that tooling pipelines can track the performance of interface processing.
+/// See [`ToJson`](crate::json::ToJson) for the machine-readable form
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The time taken to parse each file, in the order they were parsed
+    pub decode: Vec<DecodeTime>,
+    /// The total time taken by each phase of processing (such as `decode`, `validate`, or `generate`), by name
+    pub phases: IndexMap<String, Duration>,
+    /// Counts of what was processed, such as the number of `types` and `values` in the bundle, by name
+    pub counts: IndexMap<String, u64>,
+    /// The size of each output of a code generator, in the order they were written
+    pub outputs: Vec<OutputSize>,
+}
+
+impl Metrics {
+    /// Adds `time` to the time taken by the phase `name`
+    pub fn add_phase(&mut self, name: &str, time: Duration) {
+        *self.phases.entry(name.to_string()).or_default() += time;
+    }
+
+    /// Adds `n` to the count `name`
+    pub fn add_count(&mut self, name: &str, n: u64) {
+        *self.counts.entry(name.to_string()).or_default() += n;
+    }
+
+    /// Records the decode times recorded by `bundle` (if any), and counts its `files`, `types`, and `values`
+    pub fn add_bundle(&mut self, bundle: &Bundle) {
+        if let Some(times) = bundle.decode_times() {
+            self.decode.extend_from_slice(times);
+        }
+        let (mut types, mut values) = (0, 0);
+        for (_, file) in bundle {
+            types += file.types.len() as u64;
+            values += file.values.len() as u64;
+        }
+        self.add_count("files", bundle.iter().len() as u64);
+        self.add_count("types", types);
+        self.add_count("values", values);
+    }
+
+    /// Records an output of a code generator named `name`, with the contents `contents`
+    pub fn add_output(&mut self, name: &str, contents: &[u8]) {
+        self.outputs.push(OutputSize {
+            name: name.to_string(),
+            bytes: contents.len() as u64,
+        });
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    attr::{Attribute, AttributeTarget},
+    bundle::Bundle,
+    eval::{EvalError, Evaluator, binary_op, literal_value_bits, range_of, unary_op},
+    file::File,
+    target::TargetSpec,
+    tydef::TypeDefBody,
+    uses::{Expr, IntBits, IntType, Type},
+    value::ValueBody,
+    visit::{AttributeVisitorMut, visit_file_mut},
+};
+
+/// Replaces the underlying type of every enum in `bundle` with the smallest integer type that can represent its discriminants.
+///
+/// Nothing is changed if any discriminant cannot be evaluated.
+pub fn infer_enum_underlying(bundle: &mut Bundle, target: &TargetSpec) -> Result<(), EvalError> {
+    let mut inferred = Vec::new();
+    let mut eval = Evaluator::new(bundle, target);
+
+    for (path, file) in &*bundle {
+        for (n, ty) in file.types.iter().enumerate() {
+            if let TypeDefBody::Enum(en) = &ty.body {
+                inferred.push((path.clone(), n, eval.infer_enum_underlying(path, en)?));
+            }
+        }
+    }
+
+    for (path, n, underlying) in inferred {
+        let file = bundle.get_mut(&path).unwrap();
+        if let TypeDefBody::Enum(en) = &mut file.types[n].body {
+            en.underlying = underlying;
+        }
+    }
+
+    Ok(())
+}
+
+/// The signedness and width of `ty`, if its width does not depend on the target
+fn fixed_width(ty: IntType) -> Option<(bool, u32)> {
+    match ty.bits {
+        IntBits::Bits(bits) => Some((ty.signed, bits.get() as u32)),
+        IntBits::Long => None,
+    }
+}
+
+/// The value of `expr` if it is an integer literal of a fixed-width type, along with that type
+fn literal(expr: &Expr) -> Option<(i128, IntType, (bool, u32))> {
+    let Expr::IntLiteral(ty, raw) = *expr else {
+        return None;
+    };
+    let (signed, bits) = fixed_width(ty)?;
+    Some((
+        literal_value_bits(signed, bits, raw).ok()?,
+        ty,
+        (signed, bits),
+    ))
+}
+
+/// A literal of type `ty` with value `val`, if `val` fits in `ty`
+fn make_literal(ty: IntType, (signed, bits): (bool, u32), val: i128) -> Option<Expr> {
+    let (min, max) = range_of(signed, bits);
+    if val < min || val > max {
+        return None;
+    }
+    let mask = u128::MAX >> 128u32.saturating_sub(bits);
+    Some(Expr::IntLiteral(ty, val as u128 & mask))
+}
+
+/// Simplifies `expr` without changing its value on any target.
+///
+/// Arithmetic on integer literals of fixed-width types is replaced with its result, when the result fits in the type of the expression.
+/// In particular, negated literals of signed types become negative literals. Double negations and complements are removed.
+/// Expressions that depend on the target, refer to constants, or would fail to evaluate are left in place.
+pub fn fold_expr(expr: &mut Expr) {
+    let folded = match expr {
+        Expr::BinOp(op, left, right) => {
+            fold_expr(left);
+            fold_expr(right);
+            literal(left)
+                .zip(literal(right))
+                .and_then(|((left, ty, width), (right, _, _))| {
+                    make_literal(ty, width, binary_op(*op, left, right).ok()?)
+                })
+        }
+        Expr::UnaryOp(op, inner) => {
+            fold_expr(inner);
+            match &**inner {
+                Expr::UnaryOp(inner_op, inner) if inner_op == op => Some((**inner).clone()),
+                inner => literal(inner).and_then(|(val, ty, width)| {
+                    make_literal(ty, width, unary_op(*op, val, Some(width)).ok()?)
+                }),
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(folded) = folded {
+        *expr = folded;
+    }
+}
+
+struct ConstFolder;
+
+impl AttributeVisitorMut for ConstFolder {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, _: &mut Vec<Attribute<Targ>>) {}
+
+    fn enter_type(&mut self, ty: &mut Type) {
+        if let Type::Array(arr) = ty {
+            fold_expr(&mut arr.len);
+        }
+    }
+}
+
+/// Folds every expression in `file` (enum discriminants, constant values, and array lengths). See [`fold_expr`]
+pub fn fold_file(file: &mut File) {
+    for ty in &mut file.types {
+        if let TypeDefBody::Enum(en) = &mut ty.body {
+            for variant in &mut en.variants {
+                fold_expr(&mut variant.discrim);
+            }
+        }
+    }
+
+    for val in &mut file.values {
+        if let ValueBody::Const(c) = &mut val.body {
+            fold_expr(&mut c.val);
+        }
+    }
+
+    visit_file_mut(&mut ConstFolder, file);
+}
@@ -0,0 +1,131 @@
+use crate::{
+    attr::{
+        find_attribute,
+        types::{OptionBaseType, OptionType, PolymorphicOption},
+    },
+    bundle::{Bundle, Path},
+    resolve::{Item, ResolveError, resolve_name},
+    tydef::{Field, Struct, StructBody, TypeDefBody},
+    uses::{IntType, Type},
+    uuid::Uuid,
+};
+
+/// The role of a struct with an [`OptionType`] or [`PolymorphicOption`] attribute
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum OptionKind {
+    /// A concrete option, identified by the given id
+    Option(Uuid),
+    /// A type that can hold any option of its group, and is inspected through its header
+    Polymorphic,
+}
+
+impl OptionKind {
+    pub fn of(st: &Struct) -> Option<Self> {
+        if let Some(opt) = find_attribute::<OptionType, _>(&st.attrs) {
+            Some(OptionKind::Option(opt.option))
+        } else if find_attribute::<PolymorphicOption, _>(&st.attrs).is_some() {
+            Some(OptionKind::Polymorphic)
+        } else {
+            None
+        }
+    }
+}
+
+/// The header at the start of every option struct.
+///
+/// The header is the first field of the option, and is itself a struct whose first two fields are the option id (`ty`)
+/// and flags (`flags: u32`).
+#[derive(Copy, Clone, Debug)]
+pub struct OptionHeader<'a> {
+    /// The field of the option struct that holds the header
+    pub field: &'a Field,
+    /// The field of the header that holds the option id
+    pub id: &'a Field,
+    /// The field of the header that holds the option flags
+    pub flags: &'a Field,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionError {
+    Resolve(ResolveError),
+    /// The option struct is opaque
+    Opaque,
+    /// The option struct has no fields
+    NoHeader,
+    /// The type of the header field is not a named type
+    HeaderNotNamed(String),
+    /// The header field does not name a struct type
+    HeaderNotStruct(Path),
+    /// The header struct does not start with `ty` and `flags` fields
+    InvalidHeader(Path),
+}
+
+impl core::fmt::Display for OptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionError::Resolve(e) => e.fmt(f),
+            OptionError::Opaque => f.write_str("option struct is opaque"),
+            OptionError::NoHeader => f.write_str("option struct has no header field"),
+            OptionError::HeaderNotNamed(field) => f.write_fmt(format_args!(
+                "option header field {field} does not name a struct"
+            )),
+            OptionError::HeaderNotStruct(path) => {
+                f.write_fmt(format_args!("option header {path} is not a struct"))
+            }
+            OptionError::InvalidHeader(path) => f.write_fmt(format_args!(
+                "option header {path} does not start with `ty` and `flags: u32` fields"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+impl From<ResolveError> for OptionError {
+    fn from(value: ResolveError) -> Self {
+        Self::Resolve(value)
+    }
+}
+
+/// Finds and checks the header of the option struct `st`, which is defined in `module`
+pub fn option_header<'a>(
+    bundle: &'a Bundle,
+    module: &Path,
+    st: &'a Struct,
+) -> Result<OptionHeader<'a>, OptionError> {
+    let StructBody::Fields(fields) = &st.body else {
+        return Err(OptionError::Opaque);
+    };
+    let field = fields.field.first().ok_or(OptionError::NoHeader)?;
+    let Type::Named(name, None) = &field.ty else {
+        return Err(OptionError::HeaderNotNamed(field.name.to_string()));
+    };
+
+    let resolved = resolve_name(bundle, module, name)?;
+    let header = match resolved.item {
+        Item::Type(def) => match &def.body {
+            TypeDefBody::Struct(Struct {
+                body: StructBody::Fields(fields),
+                ..
+            }) => fields,
+            _ => return Err(OptionError::HeaderNotStruct(resolved.path)),
+        },
+        Item::Value(_) => return Err(OptionError::HeaderNotStruct(resolved.path)),
+    };
+
+    match &*header.field {
+        [id, flags, ..]
+            if id.name == "ty" && flags.name == "flags" && flags.ty == Type::Int(IntType::u32) =>
+        {
+            Ok(OptionHeader { field, id, flags })
+        }
+        _ => Err(OptionError::InvalidHeader(resolved.path)),
+    }
+}
+
+/// The polymorphic option type that the option struct `st` belongs to, as given by its [`OptionBaseType`] attribute
+pub fn option_base(st: &Struct) -> Option<&Type> {
+    find_attribute::<OptionBaseType, _>(&st.attrs)
+        .map(|base| &base.ty)
+        .filter(|ty| **ty != Type::Void)
+}
@@ -0,0 +1,253 @@
+use crate::{
+    attr::{
+        Attribute,
+        types::{Reserved, Synthetic},
+    },
+    bundle::{Bundle, Path},
+    intern::Symbol,
+    layout::{FieldLayout, Layout, LayoutEngine},
+    resolve::module_of,
+    target::TargetSpec,
+    tydef::{Field, StructBody, TypeDefBody},
+    uses::{ArrayType, Expr, IntType, Type},
+};
+
+/// Padding bytes between the fields of a struct, or at its end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaddingGap {
+    pub offset: u128,
+    pub size: u128,
+    /// The field the padding comes before (`_pad` for the explicit padding of the struct), or `None` for padding at the end of the struct
+    pub before: Option<Symbol>,
+}
+
+/// A field whose offset is not a multiple of its size, so it is not naturally aligned.
+/// Such fields are placed differently on targets that align the type more strictly
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MisalignedField {
+    pub name: Symbol,
+    pub offset: u128,
+    pub size: u128,
+}
+
+/// An order of the fields of a struct that needs less padding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reordering {
+    pub fields: Vec<Symbol>,
+    /// The size of the struct with its fields in this order
+    pub size: u128,
+}
+
+/// How the fields of a struct are laid out on a target, and the bytes that layout wastes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructPadding {
+    pub path: Path,
+    pub layout: Layout,
+    pub gaps: Vec<PaddingGap>,
+    pub misaligned: Vec<MisalignedField>,
+    /// A suggested order of the fields, if one makes the struct smaller. It is not applied, because reordering fields breaks the ABI
+    pub reordering: Option<Reordering>,
+}
+
+impl StructPadding {
+    /// The number of padding bytes in the struct
+    pub fn padding(&self) -> u128 {
+        self.gaps.iter().map(|gap| gap.size).sum()
+    }
+}
+
+/// The padding of each struct of a bundle on a target.
+///
+/// Generic structs, opaque structs, and structs whose layout cannot be computed (which are reported by validation) are not included
+#[derive(Clone, Debug, Default)]
+pub struct PaddingReport {
+    pub structs: Vec<StructPadding>,
+}
+
+impl PaddingReport {
+    pub fn of(bundle: &Bundle, target: &TargetSpec) -> Self {
+        let mut engine = LayoutEngine::new(bundle, target);
+        let mut structs = Vec::new();
+        for (module, file) in bundle {
+            for ty in &file.types {
+                let TypeDefBody::Struct(st) = &ty.body else {
+                    continue;
+                };
+                let StructBody::Fields(fields) = &st.body else {
+                    continue;
+                };
+                if ty.num_params != 0 {
+                    continue;
+                }
+                let (Ok(layout), Ok(field_layouts)) = (
+                    engine.layout_of_def(module, ty),
+                    engine.field_layouts(module, fields),
+                ) else {
+                    continue;
+                };
+
+                let mut gaps = Vec::new();
+                let mut end = 0;
+                for field in &field_layouts {
+                    if field.offset > end {
+                        gaps.push(PaddingGap {
+                            offset: end,
+                            size: field.offset - end,
+                            before: Some(field.name.clone()),
+                        });
+                    }
+                    end = field.offset + field.layout.size;
+                }
+                // Explicit padding is intended, so it is not counted
+                if let Ok(Some(pad)) = engine.pad_layout(module, fields) {
+                    if pad.offset > end {
+                        gaps.push(PaddingGap {
+                            offset: end,
+                            size: pad.offset - end,
+                            before: Some(pad.name),
+                        });
+                    }
+                    end = pad.offset + pad.layout.size;
+                }
+                if layout.size > end {
+                    gaps.push(PaddingGap {
+                        offset: end,
+                        size: layout.size - end,
+                        before: None,
+                    });
+                }
+
+                let misaligned = field_layouts
+                    .iter()
+                    .filter(|field| {
+                        field.layout.size.is_power_of_two()
+                            && field.layout.size <= 16
+                            && field.offset % field.layout.size != 0
+                    })
+                    .map(|field| MisalignedField {
+                        name: field.name.clone(),
+                        offset: field.offset,
+                        size: field.layout.size,
+                    })
+                    .collect();
+
+                let reordering = reorder(
+                    &field_layouts,
+                    layout.align,
+                    end - field_end(&field_layouts),
+                )
+                .filter(|reordering| reordering.size < layout.size);
+
+                structs.push(StructPadding {
+                    path: module.join(&ty.name),
+                    layout,
+                    gaps,
+                    misaligned,
+                    reordering,
+                });
+            }
+        }
+        Self { structs }
+    }
+
+    /// The structs that have padding or misaligned fields
+    pub fn wasteful(&self) -> impl Iterator<Item = &StructPadding> {
+        self.structs
+            .iter()
+            .filter(|st| !st.gaps.is_empty() || !st.misaligned.is_empty())
+    }
+}
+
+fn field_end(fields: &[FieldLayout]) -> u128 {
+    fields
+        .last()
+        .map_or(0, |field| field.offset + field.layout.size)
+}
+
+/// Lays out `fields` from most to least strictly aligned (keeping the order of equally aligned fields), which needs the least padding.
+/// `tail` is the size of the explicit padding after the fields
+fn reorder(fields: &[FieldLayout], align: u128, tail: u128) -> Option<Reordering> {
+    let mut sorted = fields.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|field| core::cmp::Reverse(field.layout.align));
+    let mut end = 0u128;
+    for field in &sorted {
+        end = end
+            .checked_next_multiple_of(field.layout.align)?
+            .checked_add(field.layout.size)?;
+    }
+    Some(Reordering {
+        fields: sorted.iter().map(|field| field.name.clone()).collect(),
+        size: end.checked_add(tail)?.checked_next_multiple_of(align)?,
+    })
+}
+
+/// An array of `size` bytes, for padding
+fn padding_type(size: u128) -> Type {
+    Type::Array(Box::new(ArrayType {
+        base: Type::Byte,
+        len: Expr::IntLiteral(IntType::u64, size),
+    }))
+}
+
+impl Bundle {
+    /// Makes the padding of each struct on `target` (as found by [`PaddingReport`]) explicit, so that the layout of the struct does not rely on implicit padding.
+    ///
+    /// Padding before a field becomes a byte array field named `_pad<offset>`, marked [`Synthetic`] and [`Reserved`].
+    /// Padding at the end of a struct becomes its [`StructFields::pad`](crate::tydef::StructFields::pad), unless the struct already has one.
+    /// Padding depends on the target, so the explicit fields are only correct for targets with the same layout.
+    ///
+    /// Returns the number of padding fields added
+    pub fn materialize_padding(&mut self, target: &TargetSpec) -> usize {
+        let report = PaddingReport::of(self, target);
+
+        let mut added = 0;
+        for padding in report.structs.iter().filter(|st| !st.gaps.is_empty()) {
+            let Some(file) = self.get_mut(&module_of(&padding.path)) else {
+                continue;
+            };
+            let name = padding.path.name().unwrap_or_default();
+            for ty in file.types.iter_mut().filter(|ty| *ty.name == *name) {
+                let TypeDefBody::Struct(st) = &mut ty.body else {
+                    continue;
+                };
+                let StructBody::Fields(fields) = &mut st.body else {
+                    continue;
+                };
+                // Later gaps are inserted first, so that the positions of earlier fields do not change
+                for gap in padding.gaps.iter().rev() {
+                    let Some(before) = &gap.before else {
+                        if fields.pad.is_none() {
+                            fields.pad = Some(padding_type(gap.size));
+                            added += 1;
+                        }
+                        continue;
+                    };
+                    let pos = match fields.field.iter().position(|f| f.name == *before) {
+                        Some(pos) => pos,
+                        None if **before == *"_pad" && fields.pad.is_some() => fields.field.len(),
+                        None => continue,
+                    };
+                    let mut name = format!("_pad{}", gap.offset);
+                    while fields.field.iter().any(|f| *f.name == *name) {
+                        name.insert(0, '_');
+                    }
+                    fields.field.insert(
+                        pos,
+                        Field {
+                            attrs: vec![
+                                Attribute::new(Synthetic),
+                                Attribute::new(Reserved {
+                                    must_be_zero: false,
+                                }),
+                            ],
+                            name: name.as_str().into(),
+                            ty: padding_type(gap.size),
+                        },
+                    );
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+}
@@ -0,0 +1,305 @@
+use core::fmt::Write as _;
+
+use crate::{
+    attr::{Attribute, AttributeTarget, attribute_info, schema::render_payload, types::ItemDoc},
+    file::File,
+    text::{Anchor, Comment, Include, ItemRef, SourceFile},
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::Signature,
+    value::{Value, ValueBody},
+};
+
+/// Renders `file` in a Rust-like IDL syntax, for reading and diffing files that are too large to read in their debug representation.
+///
+/// The rendering is stable: items appear in the order they are declared in the file, and each item is rendered the same way
+/// regardless of the rest of the file. [`ItemDoc`] attributes are rendered as doc comments, and other attributes as
+/// `#[Name payload]`, with the payload rendered by [`render_payload`]. Generic parameters are named `$0`, `$1`, and so on,
+/// like [`Type::Param`](crate::uses::Type::Param).
+///
+/// The rendering is a source of the textual format (see [`text`](crate::text)), preceded by a comment giving the version of the format
+/// the file was decoded from. Unrecognized attributes, and the attributes of the parameters and return values of function pointer types,
+/// cannot be written in a source, so files that have them do not parse back from their rendering.
+///
+/// ```text
+/// // format version 1.0
+/// file 01234567-89ab-cdef-0123-456789abcdef;
+/// #![SubsystemDescriptor { subsys_id: ..., subsys_index: None, version: 1.0, max_sysfn: 4 }]
+///
+/// use base::types;
+///
+/// /// A pair of integers
+/// struct Pair {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// fn swap(pair: *mut Pair) -> void;
+/// ```
+pub fn render_file(file: &File) -> String {
+    let mut out = String::new();
+    // Writing to a `String` does not fail
+    let _ = write_file(&mut out, file, None, &[]);
+    out
+}
+
+/// Renders a parsed source like [`render_file`], in the canonical style of the textual format. Each comment is written on its own line
+/// before the declaration it is anchored to, and the format version is not written, as sources are compiled in the current format
+pub fn render_source(source: &SourceFile) -> String {
+    let mut out = String::new();
+    // Writing to a `String` does not fail
+    let _ = write_file(
+        &mut out,
+        &source.file,
+        Some(&source.comments),
+        &source.includes,
+    );
+    out
+}
+
+/// The comments of a source, by the declaration they are anchored to
+#[derive(Copy, Clone)]
+struct Comments<'a>(&'a [Comment]);
+
+impl Comments<'_> {
+    fn write(self, out: &mut String, indent: &str, anchor: Anchor) -> core::fmt::Result {
+        for comment in self.0.iter().filter(|comment| comment.anchor == anchor) {
+            writeln!(out, "{indent}{}", comment.text)?;
+        }
+        Ok(())
+    }
+
+    fn has(self, anchor: Anchor) -> bool {
+        self.0.iter().any(|comment| comment.anchor == anchor)
+    }
+
+    /// Whether any comment is anchored to a member or the end of `item`
+    fn in_body(self, item: ItemRef) -> bool {
+        self.0.iter().any(|comment| {
+            matches!(comment.anchor, Anchor::Member(of, _) | Anchor::EndOfItem(of) if of == item)
+        })
+    }
+}
+
+fn write_file(
+    out: &mut String,
+    file: &File,
+    comments: Option<&[Comment]>,
+    includes: &[Include],
+) -> core::fmt::Result {
+    if comments.is_none() {
+        writeln!(out, "// format version {}", file.header.version)?;
+    }
+    let comments = Comments(comments.unwrap_or_default());
+    comments.write(out, "", Anchor::File)?;
+    writeln!(out, "file {};", file.file_id)?;
+    write_attrs(out, "", &file.attributes, true)?;
+
+    if !file.uses.is_empty() {
+        writeln!(out)?;
+    }
+    for (n, item) in file.uses.iter().enumerate() {
+        comments.write(out, "", Anchor::Use(n))?;
+        write_attrs(out, "", &item.attrs, false)?;
+        match includes.iter().find(|include| include.use_index == n) {
+            Some(include) => writeln!(out, "include {:?};", include.path)?,
+            None => writeln!(out, "use {};", item.path.join("::"))?,
+        }
+    }
+
+    for (n, def) in file.types.iter().enumerate() {
+        writeln!(out)?;
+        write_typedef(out, def, ItemRef::Type(n), comments)?;
+    }
+    for (n, val) in file.values.iter().enumerate() {
+        writeln!(out)?;
+        write_value(out, val, ItemRef::Value(n), comments)?;
+    }
+
+    if comments.has(Anchor::End) {
+        writeln!(out)?;
+        comments.write(out, "", Anchor::End)?;
+    }
+    Ok(())
+}
+
+fn write_typedef(
+    out: &mut String,
+    def: &TypeDef,
+    item: ItemRef,
+    comments: Comments,
+) -> core::fmt::Result {
+    let mut params = String::new();
+    if def.num_params != 0 {
+        params.push('<');
+        for (n, is_const) in def.const_params().into_iter().enumerate() {
+            if n != 0 {
+                params.push_str(", ");
+            }
+            if is_const {
+                params.push_str("const ");
+            }
+            write!(params, "${n}")?;
+        }
+        params.push('>');
+    }
+    let name = &def.name;
+
+    comments.write(out, "", Anchor::Item(item))?;
+    match &def.body {
+        TypeDefBody::Alias(alias) => {
+            write_attrs(out, "", &alias.attrs, false)?;
+            writeln!(out, "type {name}{params} = {};", alias.alias)
+        }
+        TypeDefBody::Struct(st) => {
+            write_attrs(out, "", &st.attrs, false)?;
+            match &st.body {
+                StructBody::Fields(fields) => {
+                    writeln!(out, "struct {name}{params} {{")?;
+                    write_fields(out, fields, item, comments)?;
+                    writeln!(out, "}}")
+                }
+                StructBody::Opaque(None) => writeln!(out, "opaque struct {name}{params};"),
+                StructBody::Opaque(Some(repr)) => {
+                    writeln!(out, "opaque struct {name}{params}: {repr};")
+                }
+            }
+        }
+        TypeDefBody::Union(un) => {
+            write_attrs(out, "", &un.attrs, false)?;
+            writeln!(out, "union {name}{params} {{")?;
+            write_fields(out, &un.fields, item, comments)?;
+            writeln!(out, "}}")
+        }
+        TypeDefBody::Enum(en) => {
+            write_attrs(out, "", &en.attrs, false)?;
+            writeln!(out, "enum {name}{params}: {} {{", en.underlying)?;
+            for (n, variant) in en.variants.iter().enumerate() {
+                comments.write(out, "    ", Anchor::Member(item, n))?;
+                write_attrs(out, "    ", &variant.attrs, false)?;
+                writeln!(out, "    {} = {},", variant.name, variant.discrim)?;
+            }
+            comments.write(out, "    ", Anchor::EndOfItem(item))?;
+            writeln!(out, "}}")
+        }
+    }
+}
+
+/// Writes each field on its own line, followed by the padding at the end of the struct (if any) as `.. ty`
+fn write_fields(
+    out: &mut String,
+    fields: &StructFields,
+    item: ItemRef,
+    comments: Comments,
+) -> core::fmt::Result {
+    for (n, field) in fields.field.iter().enumerate() {
+        comments.write(out, "    ", Anchor::Member(item, n))?;
+        write_attrs(out, "    ", &field.attrs, false)?;
+        writeln!(out, "    {}: {},", field.name, field.ty)?;
+    }
+    if let Some(pad) = &fields.pad {
+        comments.write(out, "    ", Anchor::Member(item, fields.field.len()))?;
+        writeln!(out, "    .. {pad}")?;
+    }
+    comments.write(out, "    ", Anchor::EndOfItem(item))
+}
+
+fn write_value(
+    out: &mut String,
+    val: &Value,
+    item: ItemRef,
+    comments: Comments,
+) -> core::fmt::Result {
+    let name = &val.name;
+    comments.write(out, "", Anchor::Item(item))?;
+    match &val.body {
+        ValueBody::Const(c) => {
+            write_attrs(out, "", &c.attrs, false)?;
+            writeln!(out, "const {name}: {} = {};", c.ty, c.val)
+        }
+        ValueBody::Function(f) => {
+            write_attrs(out, "", &f.attrs, false)?;
+            write_signature(out, name, &f.signature, item, comments)
+        }
+    }
+}
+
+/// Writes the declaration of the function `name`. The parameters are written on their own lines if any of them have attributes
+/// or comments, and the attributes of the return value are written before the declaration as `#[return: Name payload]`
+fn write_signature(
+    out: &mut String,
+    name: &str,
+    sig: &Signature,
+    item: ItemRef,
+    comments: Comments,
+) -> core::fmt::Result {
+    for attr in &sig.ret_attrs {
+        writeln!(out, "#[return: {}]", attribute_text(attr))?;
+    }
+
+    write!(out, "fn {name}(")?;
+    if sig.params.iter().any(|param| !param.attrs.is_empty()) || comments.in_body(item) {
+        writeln!(out)?;
+        for (n, param) in sig.params.iter().enumerate() {
+            comments.write(out, "    ", Anchor::Member(item, n))?;
+            write_attrs(out, "    ", &param.attrs, false)?;
+            match &param.name {
+                Some(name) => writeln!(out, "    {name}: {},", param.ty)?,
+                None => writeln!(out, "    {},", param.ty)?,
+            }
+        }
+        comments.write(out, "    ", Anchor::EndOfItem(item))?;
+    } else {
+        let mut sep = "";
+        for param in &sig.params {
+            out.push_str(sep);
+            sep = ", ";
+            match &param.name {
+                Some(name) => write!(out, "{name}: {}", param.ty)?,
+                None => write!(out, "{}", param.ty)?,
+            }
+        }
+    }
+    writeln!(out, ") -> {};", sig.retty)
+}
+
+/// Writes each attribute on its own line at `indent`. Inner attributes (those of the file) are written as `#![..]` and `//!`
+fn write_attrs<Targ: AttributeTarget>(
+    out: &mut String,
+    indent: &str,
+    attrs: &[Attribute<Targ>],
+    inner: bool,
+) -> core::fmt::Result {
+    let (doc, bang) = if inner { ("//!", "!") } else { ("///", "") };
+    for attr in attrs {
+        match attr.downcast::<ItemDoc>() {
+            Some(item_doc) => {
+                for line in &item_doc.doc_lines {
+                    match &**line {
+                        "" => writeln!(out, "{indent}{doc}")?,
+                        line => writeln!(out, "{indent}{doc} {line}")?,
+                    }
+                }
+            }
+            None => writeln!(out, "{indent}#{bang}[{}]", attribute_text(attr))?,
+        }
+    }
+    Ok(())
+}
+
+/// The name of the attribute followed by its payload, omitting an empty payload.
+/// Unknown attributes are rendered as `unknown(id)`, and the payloads of attributes that cannot be rendered as `..`
+fn attribute_text<Targ: AttributeTarget>(attr: &Attribute<Targ>) -> String {
+    let Some(info) = attribute_info(*attr.id()) else {
+        return format!("unknown({})", attr.id());
+    };
+    let payload = attr
+        .described_payload()
+        .and_then(|payload| payload.ok())
+        .and_then(|(schema, bytes)| render_payload(&schema, &bytes).ok());
+    match payload.as_deref() {
+        Some("()" | "{}") => info.name.to_string(),
+        Some(payload) if payload.starts_with('{') => format!("{} {payload}", info.name),
+        Some(payload) => format!("{}({payload})", info.name),
+        None => format!("{}(..)", info.name),
+    }
+}
@@ -0,0 +1,717 @@
+use std::path::PathBuf;
+
+use toml::{Table, Value};
+
+use crate::{
+    attr::UnknownAttributePolicy,
+    bundle::Path,
+    codegen::{
+        CodegenBackend,
+        asm::{AsmBackend, AsmSyntax},
+        c::CBackend,
+        cpp::CppBackend,
+        doc::{DocBackend, DocFormat},
+        plugin::PluginBackend,
+        rust::{RustBackend, RustOutput, Visibility},
+        symbols::VersionScriptBackend,
+        zig::ZigBackend,
+    },
+    target::{TargetSpec, TargetSpecError},
+};
+
+/// The name of the project manifest in the root directory of a project
+pub const MANIFEST_NAME: &str = "imt.toml";
+
+/// A project described by an `imt.toml` manifest, which builds a bundle from its sources and generates code from it for each of its targets.
+///
+/// The manifest has a `[package]` table and a `[codegen.<name>]` table for each backend:
+///
+/// ```toml
+/// [package]
+/// name = "base"
+/// prefix = "base"
+/// sources = ["src"]
+/// output = "build/base.tar.gz"
+/// features = ["threads"]
+/// targets = ["x86_64-pc-lilium-std", "targets/custom.spec"]
+///
+/// [codegen.rust]
+/// out = "build/rust"
+/// lib-skeleton = true
+/// ```
+///
+/// Paths are relative to the directory containing the manifest.
+#[derive(Clone, Debug)]
+pub struct Project {
+    /// The directory containing the manifest
+    pub root: PathBuf,
+    pub name: String,
+    /// The module that the root of each source corresponds to
+    pub prefix: Path,
+    /// The IMT files, TAR archives, and directories of IMT files the bundle is built from. Defaults to `src`
+    pub sources: Vec<PathBuf>,
+    /// The file the bundle is written to, which is an IMT file if it has the extension `.imt` (possibly followed by that of a compressed format),
+    /// and a TAR archive otherwise. Defaults to `build/<name>.tar`
+    pub output: PathBuf,
+    /// The features that are enabled, or `None` to keep the items of every feature
+    pub features: Option<Vec<String>>,
+    /// Each target, by the name given in the manifest. Defaults to `x86_64`
+    pub targets: Vec<(String, TargetSpec)>,
+    /// The names of the packages of the same [`Workspace`] that the sources of the package refer to
+    pub dependencies: Vec<String>,
+    /// Whether the bundle is validated (for each target) before it is written. Defaults to `true`
+    pub validate: bool,
+    pub unknown_attrs: UnknownAttributePolicy,
+    pub codegen: Vec<Codegen>,
+}
+
+/// A backend run by a [`Project`], from a `[codegen.<name>]` table
+#[derive(Clone, Debug)]
+pub struct Codegen {
+    /// The name of the table
+    pub name: String,
+    pub backend: BackendConfig,
+    /// The directory the output is written to. With more than one target, the output for each target is written to a subdirectory named after it.
+    /// Defaults to `build/<name>`
+    pub out: PathBuf,
+}
+
+/// The backend of a `[codegen.<name>]` table, which is given by its `backend` key, or else is `<name>`
+#[derive(Clone, Debug)]
+pub enum BackendConfig {
+    C(CBackend),
+    Cpp(CppBackend),
+    Rust(RustBackend),
+    Zig(ZigBackend),
+    Asm(AsmBackend),
+    Doc(DocBackend),
+    VersionScript(VersionScriptBackend),
+    /// An external generator. A `program` containing a `/` is relative to the directory containing the manifest
+    Plugin(PluginBackend),
+    /// A template read from the file `source`
+    #[cfg(feature = "template")]
+    Template {
+        source: PathBuf,
+        output: crate::codegen::template::TemplateOutput,
+    },
+}
+
+impl BackendConfig {
+    /// Creates the backend, reading its template if it has one
+    pub fn backend(&self) -> std::io::Result<Box<dyn CodegenBackend>> {
+        Ok(match self {
+            BackendConfig::C(backend) => Box::new(backend.clone()),
+            BackendConfig::Cpp(backend) => Box::new(backend.clone()),
+            BackendConfig::Rust(backend) => Box::new(backend.clone()),
+            BackendConfig::Zig(backend) => Box::new(backend.clone()),
+            BackendConfig::Asm(backend) => Box::new(backend.clone()),
+            BackendConfig::Doc(backend) => Box::new(backend.clone()),
+            BackendConfig::VersionScript(backend) => Box::new(backend.clone()),
+            BackendConfig::Plugin(backend) => Box::new(backend.clone()),
+            #[cfg(feature = "template")]
+            BackendConfig::Template { source, output } => {
+                Box::new(crate::codegen::template::TemplateBackend::new(
+                    std::fs::read_to_string(source)?,
+                    output.clone(),
+                ))
+            }
+        })
+    }
+
+    /// Whether the output of the backend is the same for every target, so that it is only generated once
+    pub fn is_target_independent(&self) -> bool {
+        matches!(
+            self,
+            BackendConfig::Doc(_) | BackendConfig::VersionScript(_)
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Io(std::io::Error),
+    Syntax(toml::de::Error),
+    /// The key (written like `package.name`) is not known
+    UnknownKey(String),
+    /// The key (written like `package.name`) is missing, but is required
+    MissingKey(String),
+    /// The value of the key (written like `package.name`) is not the expected kind of value
+    InvalidValue {
+        key: String,
+        expected: &'static str,
+    },
+    /// The target is neither a target spec file nor a target triple
+    Target(String, TargetSpecError),
+    /// More than one package of a workspace has the name
+    DuplicatePackage(String),
+    /// The package depends on a package that is not in the workspace
+    UnknownDependency {
+        package: String,
+        dependency: String,
+    },
+    /// The packages depend on each other in a cycle, starting from the first package
+    DependencyCycle(Vec<String>),
+}
+
+impl core::fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::Io(e) => e.fmt(f),
+            ProjectError::Syntax(e) => e.fmt(f),
+            ProjectError::UnknownKey(key) => f.write_fmt(format_args!("unknown key {key}")),
+            ProjectError::MissingKey(key) => f.write_fmt(format_args!("missing key {key}")),
+            ProjectError::InvalidValue { key, expected } => {
+                f.write_fmt(format_args!("{key} must be {expected}"))
+            }
+            ProjectError::Target(target, e) => f.write_fmt(format_args!("target {target}: {e}")),
+            ProjectError::DuplicatePackage(name) => {
+                f.write_fmt(format_args!("more than one package is named {name}"))
+            }
+            ProjectError::UnknownDependency {
+                package,
+                dependency,
+            } => f.write_fmt(format_args!(
+                "{package} depends on {dependency}, which is not in the workspace"
+            )),
+            ProjectError::DependencyCycle(cycle) => f.write_fmt(format_args!(
+                "packages depend on each other in a cycle: {} -> {}",
+                cycle.join(" -> "),
+                cycle[0]
+            )),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProjectError::Io(e) => Some(e),
+            ProjectError::Syntax(e) => Some(e),
+            ProjectError::Target(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ProjectError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// The keys of a table of the manifest
+struct Fields<'a> {
+    table: &'a Table,
+    /// The name of the table, such as `package` or `codegen.rust`
+    name: &'a str,
+}
+
+impl<'a> Fields<'a> {
+    /// Checks that every key of `table` is one of `known`
+    fn new(table: &'a Table, name: &'a str, known: &[&str]) -> Result<Self, ProjectError> {
+        if let Some(key) = table.keys().find(|key| !known.contains(&key.as_str())) {
+            return Err(ProjectError::UnknownKey(format!("{name}.{key}")));
+        }
+        Ok(Self { table, name })
+    }
+
+    fn invalid(&self, key: &str, expected: &'static str) -> ProjectError {
+        ProjectError::InvalidValue {
+            key: format!("{}.{key}", self.name),
+            expected,
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<&'a str>, ProjectError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(self.invalid(key, "a string")),
+        }
+    }
+
+    fn bool(&self, key: &str) -> Result<Option<bool>, ProjectError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(Value::Boolean(b)) => Ok(Some(*b)),
+            Some(_) => Err(self.invalid(key, "true or false")),
+        }
+    }
+
+    fn strings(&self, key: &str) -> Result<Option<Vec<&'a str>>, ProjectError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| value.as_str())
+                .collect::<Option<Vec<_>>>()
+                .map(Some)
+                .ok_or_else(|| self.invalid(key, "an array of strings")),
+            Some(_) => Err(self.invalid(key, "an array of strings")),
+        }
+    }
+
+    /// The value of `key`, which is one of `choices`
+    fn choice<T: Copy>(
+        &self,
+        key: &str,
+        choices: &[(&str, T)],
+        expected: &'static str,
+    ) -> Result<Option<T>, ProjectError> {
+        self.string(key)?
+            .map(|s| {
+                choices
+                    .iter()
+                    .find(|(name, _)| *name == s)
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| self.invalid(key, expected))
+            })
+            .transpose()
+    }
+}
+
+/// Loads the target named `name` in a manifest, which is a target spec file relative to `root`, or else a target triple
+fn load_target(root: &std::path::Path, name: &str) -> Result<TargetSpec, ProjectError> {
+    let res = match std::fs::File::open(root.join(name)) {
+        Ok(file) => TargetSpec::read(std::io::BufReader::new(file)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => TargetSpec::from_triple(name),
+        Err(e) => return Err(e.into()),
+    };
+    res.map_err(|e| ProjectError::Target(name.to_string(), e))
+}
+
+/// Reads the manifest at `path`, or the `imt.toml` in `path` if it is a directory, along with the directory containing it
+fn read_manifest(path: &std::path::Path) -> Result<(PathBuf, Table), ProjectError> {
+    let path = if path.is_dir() {
+        path.join(MANIFEST_NAME)
+    } else {
+        path.to_path_buf()
+    };
+    let text = std::fs::read_to_string(&path)?;
+    let root = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    Ok((root, text.parse().map_err(ProjectError::Syntax)?))
+}
+
+impl Project {
+    /// Reads the manifest at `path`, or the `imt.toml` in `path` if it is a directory
+    pub fn read(path: &std::path::Path) -> Result<Self, ProjectError> {
+        let (root, manifest) = read_manifest(path)?;
+        Self::from_manifest(root, &manifest)
+    }
+
+    /// Parses the manifest `text`, resolving its paths against `root`. Targets that name files are read from `root`
+    pub fn parse(root: PathBuf, text: &str) -> Result<Self, ProjectError> {
+        Self::from_manifest(root, &text.parse().map_err(ProjectError::Syntax)?)
+    }
+
+    fn from_manifest(root: PathBuf, manifest: &Table) -> Result<Self, ProjectError> {
+        if let Some(key) = manifest
+            .keys()
+            .find(|key| !matches!(&***key, "package" | "codegen"))
+        {
+            return Err(ProjectError::UnknownKey(key.clone()));
+        }
+
+        let package = match manifest.get("package") {
+            Some(Value::Table(package)) => package,
+            Some(_) => {
+                return Err(ProjectError::InvalidValue {
+                    key: "package".to_string(),
+                    expected: "a table",
+                });
+            }
+            None => return Err(ProjectError::MissingKey("package".to_string())),
+        };
+        let package = Fields::new(
+            package,
+            "package",
+            &[
+                "name",
+                "prefix",
+                "sources",
+                "output",
+                "features",
+                "targets",
+                "validate",
+                "unknown-attrs",
+                "dependencies",
+            ],
+        )?;
+
+        let name = package
+            .string("name")?
+            .ok_or_else(|| ProjectError::MissingKey("package.name".to_string()))?
+            .to_string();
+        let prefix = match package.string("prefix")? {
+            None | Some("") => Path(vec![]),
+            Some(prefix) => Path(prefix.split("::").map(str::to_string).collect()),
+        };
+        let sources = package
+            .strings("sources")?
+            .unwrap_or(vec!["src"])
+            .into_iter()
+            .map(|source| root.join(source))
+            .collect();
+        let output = match package.string("output")? {
+            Some(output) => root.join(output),
+            None => root.join("build").join(format!("{name}.tar")),
+        };
+        let features = package
+            .strings("features")?
+            .map(|features| features.into_iter().map(str::to_string).collect());
+        let targets = package
+            .strings("targets")?
+            .unwrap_or(vec!["x86_64"])
+            .into_iter()
+            .map(|target| Ok((target.to_string(), load_target(&root, target)?)))
+            .collect::<Result<Vec<_>, ProjectError>>()?;
+        let dependencies = package
+            .strings("dependencies")?
+            .unwrap_or_default()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let validate = package.bool("validate")?.unwrap_or(true);
+        let unknown_attrs = package
+            .choice(
+                "unknown-attrs",
+                &[
+                    ("error", UnknownAttributePolicy::Error),
+                    ("warn", UnknownAttributePolicy::WarnAndPreserve),
+                    ("drop", UnknownAttributePolicy::Drop),
+                ],
+                "error, warn, or drop",
+            )?
+            .unwrap_or_default();
+
+        let mut codegen = Vec::new();
+        match manifest.get("codegen") {
+            None => {}
+            Some(Value::Table(tables)) => {
+                for (name, table) in tables {
+                    let key = format!("codegen.{name}");
+                    let Value::Table(table) = table else {
+                        return Err(ProjectError::InvalidValue {
+                            key,
+                            expected: "a table",
+                        });
+                    };
+                    codegen.push(Codegen::parse(&root, name, &key, table)?);
+                }
+            }
+            Some(_) => {
+                return Err(ProjectError::InvalidValue {
+                    key: "codegen".to_string(),
+                    expected: "a table",
+                });
+            }
+        }
+
+        Ok(Self {
+            root,
+            name,
+            prefix,
+            sources,
+            output,
+            features,
+            targets,
+            dependencies,
+            validate,
+            unknown_attrs,
+            codegen,
+        })
+    }
+}
+
+impl Codegen {
+    fn parse(
+        root: &std::path::Path,
+        name: &str,
+        key: &str,
+        table: &Table,
+    ) -> Result<Self, ProjectError> {
+        let backend = match table.get("backend") {
+            None => name,
+            Some(Value::String(backend)) => backend,
+            Some(_) => {
+                return Err(ProjectError::InvalidValue {
+                    key: format!("{key}.backend"),
+                    expected: "a string",
+                });
+            }
+        };
+        let known: &[&str] = match backend {
+            "c" | "cpp" | "zig" => &["layout-assertions"],
+            "rust" => &[
+                "single-file",
+                "visibility",
+                "no-std",
+                "lib-skeleton",
+                "layout-assertions",
+                "safe-wrappers",
+                "test-scaffold",
+                "mocks",
+                "versioned-views",
+            ],
+            "asm" => &["syntax"],
+            "doc" => &["format"],
+            "version-script" => &[],
+            "plugin" => &["program", "args"],
+            #[cfg(feature = "template")]
+            "template" => &["source", "per-file", "single"],
+            _ => {
+                return Err(ProjectError::InvalidValue {
+                    key: format!("{key}.backend"),
+                    expected: "one of c, cpp, rust, zig, asm, doc, version-script, plugin, or template (with the template feature)",
+                });
+            }
+        };
+        let fields = Fields::new(table, key, &[&["backend", "out"][..], known].concat())?;
+
+        let layout_assertions = fields.bool("layout-assertions")?.unwrap_or(false);
+        let config = match backend {
+            "c" => BackendConfig::C(CBackend { layout_assertions }),
+            "cpp" => BackendConfig::Cpp(CppBackend { layout_assertions }),
+            "zig" => BackendConfig::Zig(ZigBackend { layout_assertions }),
+            "rust" => BackendConfig::Rust(RustBackend {
+                output: match fields.string("single-file")? {
+                    Some(file) => RustOutput::SingleFile(file.to_string()),
+                    None => RustOutput::ModulePerPath,
+                },
+                visibility: fields
+                    .choice(
+                        "visibility",
+                        &[("pub", Visibility::Public), ("crate", Visibility::Crate)],
+                        "pub or crate",
+                    )?
+                    .unwrap_or_default(),
+                no_std: fields.bool("no-std")?.unwrap_or(false),
+                lib_skeleton: fields.bool("lib-skeleton")?.unwrap_or(false),
+                layout_assertions,
+                safe_wrappers: fields.bool("safe-wrappers")?.unwrap_or(false),
+                test_scaffold: fields.bool("test-scaffold")?.unwrap_or(false),
+                mocks: fields.bool("mocks")?.unwrap_or(false),
+                versioned_views: fields.bool("versioned-views")?.unwrap_or(false),
+            }),
+            "asm" => BackendConfig::Asm(AsmBackend {
+                syntax: fields
+                    .choice(
+                        "syntax",
+                        &[("gas", AsmSyntax::Gas), ("nasm", AsmSyntax::Nasm)],
+                        "gas or nasm",
+                    )?
+                    .unwrap_or_default(),
+            }),
+            "doc" => BackendConfig::Doc(DocBackend {
+                format: fields
+                    .choice(
+                        "format",
+                        &[
+                            ("html", DocFormat::Html),
+                            ("markdown", DocFormat::Markdown),
+                            ("man", DocFormat::Man),
+                        ],
+                        "html, markdown, or man",
+                    )?
+                    .unwrap_or_default(),
+            }),
+            "version-script" => BackendConfig::VersionScript(VersionScriptBackend),
+            "plugin" => {
+                let program = fields
+                    .string("program")?
+                    .ok_or_else(|| ProjectError::MissingKey(format!("{key}.program")))?;
+                // Programs without a directory are looked up in PATH, like in a shell
+                let program = if program.contains('/') {
+                    root.join(program).to_string_lossy().into_owned()
+                } else {
+                    program.to_string()
+                };
+                BackendConfig::Plugin(PluginBackend {
+                    program,
+                    args: fields
+                        .strings("args")?
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                })
+            }
+            #[cfg(feature = "template")]
+            "template" => {
+                use crate::codegen::template::TemplateOutput;
+
+                let source = fields
+                    .string("source")?
+                    .ok_or_else(|| ProjectError::MissingKey(format!("{key}.source")))?;
+                let output = match (fields.string("per-file")?, fields.string("single")?) {
+                    (Some(ext), None) => TemplateOutput::PerFile(ext.to_string()),
+                    (None, Some(name)) => TemplateOutput::Single(name.to_string()),
+                    _ => {
+                        return Err(ProjectError::InvalidValue {
+                            key: key.to_string(),
+                            expected: "a table with exactly one of per-file or single",
+                        });
+                    }
+                };
+                BackendConfig::Template {
+                    source: root.join(source),
+                    output,
+                }
+            }
+            _ => unreachable!("unknown backends are rejected before their keys"),
+        };
+
+        let out = match fields.string("out")? {
+            Some(out) => root.join(out),
+            None => root.join("build").join(name),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            backend: config,
+            out,
+        })
+    }
+}
+
+/// A set of packages built together, described by a manifest with a `[workspace]` table:
+///
+/// ```toml
+/// [workspace]
+/// members = ["base", "io"]
+/// output = "build/system.tar"
+/// ```
+///
+/// Each member is a directory (or manifest) of a [`Project`], whose `dependencies` name the other members that its sources refer to.
+/// Each package is built into its own bundle, and the bundles of every package are merged into one system bundle.
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    /// The directory containing the manifest
+    pub root: PathBuf,
+    /// The packages of the workspace, ordered so that each package follows its dependencies
+    pub members: Vec<Project>,
+    /// The file the bundle merging every package is written to, in the same formats as [`Project::output`].
+    /// Defaults to `build/system.tar`, or to `None` for the implicit workspace of a single package
+    pub output: Option<PathBuf>,
+}
+
+impl Workspace {
+    /// Reads the workspace manifest at `path`, or the `imt.toml` in `path` if it is a directory.
+    /// A manifest with a `[package]` table is read as a workspace containing only that package
+    pub fn read(path: &std::path::Path) -> Result<Self, ProjectError> {
+        let (root, manifest) = read_manifest(path)?;
+        let workspace = match manifest.get("workspace") {
+            None => {
+                return Ok(Self {
+                    members: vec![Project::from_manifest(root.clone(), &manifest)?],
+                    root,
+                    output: None,
+                });
+            }
+            Some(Value::Table(workspace)) => workspace,
+            Some(_) => {
+                return Err(ProjectError::InvalidValue {
+                    key: "workspace".to_string(),
+                    expected: "a table",
+                });
+            }
+        };
+        if let Some(key) = manifest.keys().find(|key| *key != "workspace") {
+            return Err(ProjectError::UnknownKey(key.clone()));
+        }
+        let workspace = Fields::new(workspace, "workspace", &["members", "output"])?;
+
+        let members = workspace
+            .strings("members")?
+            .ok_or_else(|| ProjectError::MissingKey("workspace.members".to_string()))?
+            .into_iter()
+            .map(|member| Project::read(&root.join(member)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let output = match workspace.string("output")? {
+            Some(output) => root.join(output),
+            None => root.join("build").join("system.tar"),
+        };
+        Ok(Self {
+            members: order_packages(members)?,
+            output: Some(output),
+            root,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Project> {
+        self.members.iter().find(|package| package.name == name)
+    }
+
+    /// The packages that `package` depends on, directly or indirectly, in the order of [`Workspace::members`]
+    pub fn dependencies(&self, package: &Project) -> Vec<&Project> {
+        let mut needed = package.dependencies.iter().collect::<Vec<_>>();
+        let mut found = Vec::new();
+        while let Some(name) = needed.pop() {
+            if found.contains(&name) {
+                continue;
+            }
+            found.push(name);
+            if let Some(dependency) = self.get(name) {
+                needed.extend(&dependency.dependencies);
+            }
+        }
+        self.members
+            .iter()
+            .filter(|member| found.contains(&&member.name))
+            .collect()
+    }
+}
+
+/// Orders `packages` so that each package follows its dependencies, keeping the order of the manifest where it can
+fn order_packages(mut packages: Vec<Project>) -> Result<Vec<Project>, ProjectError> {
+    for (i, package) in packages.iter().enumerate() {
+        if packages[..i].iter().any(|p| p.name == package.name) {
+            return Err(ProjectError::DuplicatePackage(package.name.clone()));
+        }
+        if let Some(dependency) = package
+            .dependencies
+            .iter()
+            .find(|dependency| !packages.iter().any(|p| p.name == **dependency))
+        {
+            return Err(ProjectError::UnknownDependency {
+                package: package.name.clone(),
+                dependency: dependency.clone(),
+            });
+        }
+    }
+
+    let mut ordered = Vec::<Project>::with_capacity(packages.len());
+    while !packages.is_empty() {
+        let Some(next) = packages.iter().position(|package| {
+            package
+                .dependencies
+                .iter()
+                .all(|dependency| ordered.iter().any(|p| p.name == *dependency))
+        }) else {
+            return Err(ProjectError::DependencyCycle(find_cycle(&packages)));
+        };
+        ordered.push(packages.remove(next));
+    }
+    Ok(ordered)
+}
+
+/// A cycle of dependencies among `packages`, which each depend on at least one other of `packages`
+fn find_cycle(packages: &[Project]) -> Vec<String> {
+    let mut path = vec![&packages[0]];
+    loop {
+        let last = path[path.len() - 1];
+        let next = packages
+            .iter()
+            .find(|package| last.dependencies.contains(&package.name))
+            .expect("every remaining package depends on another remaining package");
+        if let Some(start) = path.iter().position(|package| package.name == next.name) {
+            return path[start..]
+                .iter()
+                .map(|package| package.name.clone())
+                .collect();
+        }
+        path.push(next);
+    }
+}
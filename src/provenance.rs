@@ -0,0 +1,167 @@
+use std::io::{BufRead, Write};
+
+/// The name of the member of a TAR archive (or file of a directory) that holds the [`Provenance`] of the bundle.
+/// Since it does not have the extension `.imt`, it is never read as a module.
+pub const PROVENANCE_MEMBER: &str = "PROVENANCE";
+
+/// A record of how a bundle was built, written alongside its files so that a build can be audited and reproduced
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// The time the bundle was built at, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// The person or system that built the bundle
+    pub builder: Option<String>,
+    /// The revision, in the version control system of its source, that the bundle was built from
+    pub revision: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ProvenanceError {
+    Io(std::io::Error),
+    /// The line (starting from 1) is not a known key followed by a value
+    InvalidEntry(usize),
+    /// The record has no `timestamp` line
+    MissingTimestamp,
+}
+
+impl core::fmt::Display for ProvenanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProvenanceError::Io(e) => e.fmt(f),
+            ProvenanceError::InvalidEntry(line) => {
+                f.write_fmt(format_args!("invalid provenance entry on line {line}"))
+            }
+            ProvenanceError::MissingTimestamp => f.write_str("provenance has no timestamp"),
+        }
+    }
+}
+
+impl std::error::Error for ProvenanceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProvenanceError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ProvenanceError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl Provenance {
+    /// Describes a build happening now, from the environment:
+    /// * The timestamp is `SOURCE_DATE_EPOCH`, if it is set, so that reproducible builds record the same time, or the current time otherwise
+    /// * The builder is `IMT_BUILDER`, or the user running the build (`USER` or `USERNAME`)
+    /// * The revision is `IMT_SOURCE_REVISION`, if it is set
+    pub fn from_env() -> Self {
+        let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|epoch| epoch.trim().parse().ok())
+            .unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |time| time.as_secs())
+            });
+        let var = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|val: &String| !val.is_empty())
+        };
+        Self {
+            timestamp,
+            builder: var("IMT_BUILDER")
+                .or_else(|| var("USER"))
+                .or_else(|| var("USERNAME")),
+            revision: var("IMT_SOURCE_REVISION"),
+        }
+    }
+
+    /// Writes the record as one `key value` line per field that is set
+    pub fn write<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, "timestamp {}", self.timestamp)?;
+        if let Some(builder) = &self.builder {
+            writeln!(w, "builder {builder}")?;
+        }
+        if let Some(revision) = &self.revision {
+            writeln!(w, "revision {revision}")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a record in the format produced by [`Provenance::write`]. Empty lines and lines starting with `#` are ignored,
+    /// as are unknown keys, so that later versions can record more about a build.
+    pub fn read<R: BufRead>(r: R) -> Result<Self, ProvenanceError> {
+        let mut timestamp = None;
+        let mut builder = None;
+        let mut revision = None;
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(' ')
+                .map(|(key, value)| (key, value.trim()))
+                .ok_or(ProvenanceError::InvalidEntry(n + 1))?;
+            match key {
+                "timestamp" => {
+                    timestamp = Some(
+                        value
+                            .parse()
+                            .map_err(|_| ProvenanceError::InvalidEntry(n + 1))?,
+                    )
+                }
+                "builder" => builder = Some(value.to_string()),
+                "revision" => revision = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            timestamp: timestamp.ok_or(ProvenanceError::MissingTimestamp)?,
+            builder,
+            revision,
+        })
+    }
+}
+
+/// Prints the timestamp in UTC, in the format of RFC 3339 (such as `2024-05-01T12:00:00Z`), followed by the builder and revision
+impl core::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let days = self.timestamp / 86400;
+        let secs = self.timestamp % 86400;
+        let (year, month, day) = civil_from_days(days);
+        f.write_fmt(format_args!(
+            "built at {year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        ))?;
+        if let Some(builder) = &self.builder {
+            f.write_fmt(format_args!(" by {builder}"))?;
+        }
+        if let Some(revision) = &self.revision {
+            f.write_fmt(format_args!(" from revision {revision}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// The year, month, and day of the date `days` after 1970-01-01 in the proleptic Gregorian calendar
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // Counts from 0000-03-01, so that the leap day is the last day of each 400-year era
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
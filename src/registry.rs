@@ -0,0 +1,159 @@
+use std::io::{BufRead, Write};
+
+use sha2::{Digest, Sha256};
+
+use crate::header::Version;
+
+/// The SHA-256 hash of `bytes`
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// Parses a SHA-256 hash written as 64 hexadecimal digits
+pub fn parse_sha256(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (byte, digits) in hash.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// `hash` written as hexadecimal digits, as parsed by [`parse_sha256`]
+pub fn sha256_hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// A published version of a bundle, listed by an [`Index`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub name: String,
+    pub version: Version,
+    /// The SHA-256 hash of the bundle as it is downloaded
+    pub sha256: [u8; 32],
+    /// The URL or path the bundle is downloaded from, which is relative to the location of the index unless it is absolute
+    pub location: String,
+}
+
+impl IndexEntry {
+    /// Whether `bytes` are the bundle that the entry lists
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        sha256(bytes) == self.sha256
+    }
+
+    /// The location of the bundle, resolved against `index`, the URL or path of the index listing the entry
+    pub fn resolve_location(&self, index: &str) -> String {
+        if self.location.contains("://") || self.location.starts_with('/') {
+            return self.location.clone();
+        }
+        match index.rfind('/') {
+            Some(pos) => format!("{}{}", &index[..=pos], self.location),
+            None => self.location.clone(),
+        }
+    }
+}
+
+/// The published versions of a set of bundles, for distributing interface bundles to the components that use them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Index {
+    pub entries: Vec<IndexEntry>,
+}
+
+#[derive(Debug)]
+pub enum IndexError {
+    Io(std::io::Error),
+    /// The line (starting from 1) is not a name, version, hexadecimal SHA-256 hash, and location
+    InvalidEntry(usize),
+}
+
+impl core::fmt::Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexError::Io(e) => e.fmt(f),
+            IndexError::InvalidEntry(line) => {
+                f.write_fmt(format_args!("invalid index entry on line {line}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for IndexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IndexError::Io(e) => Some(e),
+            IndexError::InvalidEntry(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for IndexError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl Index {
+    /// The latest version of the bundle `name`, or the latest version compatible with `version` if given
+    pub fn find(&self, name: &str, version: Option<Version>) -> Option<&IndexEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .filter(|entry| version.is_none_or(|version| version.is_compatible(entry.version)))
+            .max_by_key(|entry| entry.version)
+    }
+
+    /// Adds `entry`, replacing the entry for the same version of the same bundle if there is one
+    pub fn publish(&mut self, entry: IndexEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.name == entry.name && e.version == entry.version)
+        {
+            Some(e) => *e = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Writes the index as one `name version sha256 location` line per entry, sorted by name and then by version
+    pub fn write<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| (&a.name, a.version).cmp(&(&b.name, b.version)));
+        for entry in entries {
+            writeln!(
+                w,
+                "{} {} {} {}",
+                entry.name,
+                entry.version,
+                sha256_hex(&entry.sha256),
+                entry.location
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads an index in the format produced by [`Index::write`]. Empty lines and lines starting with `#` are ignored.
+    pub fn read<R: BufRead>(r: R) -> Result<Self, IndexError> {
+        let mut entries = Vec::new();
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let mut entry = || {
+                let entry = IndexEntry {
+                    name: fields.next()?.to_string(),
+                    version: Version::try_parse(fields.next()?)?,
+                    sha256: parse_sha256(fields.next()?)?,
+                    location: fields.next()?.to_string(),
+                };
+                fields.next().is_none().then_some(entry)
+            };
+            entries.push(entry().ok_or(IndexError::InvalidEntry(n + 1))?);
+        }
+        Ok(Self { entries })
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+
+use crate::{
+    attr::{
+        find_attribute,
+        types::{ExportInline, Frozen},
+    },
+    bundle::{Bundle, Path},
+    file::File,
+    header::Version,
+    tydef::{TypeDef, TypeDefBody},
+    uses::{Expr, Type},
+    value::{Value, ValueBody},
+};
+
+#[derive(Copy, Clone, Debug)]
+pub enum Item<'a> {
+    Type(&'a TypeDef),
+    Value(&'a Value),
+}
+
+impl<'a> Item<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Item::Type(ty) => &ty.name,
+            Item::Value(val) => &val.name,
+        }
+    }
+
+    /// The version the item was frozen in, if it is a struct, enum, or function with a [`Frozen`] attribute
+    pub fn frozen(&self) -> Option<Version> {
+        let frozen = match self {
+            Item::Type(TypeDef {
+                body: TypeDefBody::Struct(st),
+                ..
+            }) => find_attribute::<Frozen, _>(&st.attrs),
+            Item::Type(TypeDef {
+                body: TypeDefBody::Enum(en),
+                ..
+            }) => find_attribute::<Frozen, _>(&en.attrs),
+            Item::Value(Value {
+                body: ValueBody::Function(f),
+                ..
+            }) => find_attribute::<Frozen, _>(&f.attrs),
+            _ => None,
+        };
+        frozen.map(|frozen| frozen.since)
+    }
+
+    pub fn find_in(file: &'a File, name: &str) -> Option<Self> {
+        file.find_type(name)
+            .map(Item::Type)
+            .or_else(|| file.find_value(name).map(Item::Value))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResolvedItem<'a> {
+    /// The path the item is actually defined at
+    pub path: Path,
+    pub item: Item<'a>,
+    /// Each path visited while resolving, starting with the requested path and ending with `path`
+    pub chain: Vec<Path>,
+}
+
+impl ResolvedItem<'_> {
+    /// Whether the item is an extensible-option record. See [`Struct::is_option_struct`](crate::tydef::Struct::is_option_struct)
+    pub fn is_option_struct(&self) -> bool {
+        matches!(
+            self.item,
+            Item::Type(TypeDef { body: TypeDefBody::Struct(st), .. }) if st.is_option_struct()
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    EmptyPath,
+    NoSuchModule(Path),
+    NotFound(Path),
+    Cycle(Vec<Path>),
+}
+
+impl core::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::EmptyPath => f.write_str("cannot resolve an empty path"),
+            ResolveError::NoSuchModule(path) => f.write_fmt(format_args!("no module {path}")),
+            ResolveError::NotFound(path) => f.write_fmt(format_args!("no item {path}")),
+            ResolveError::Cycle(chain) => {
+                f.write_str("re-export cycle: ")?;
+                let mut sep = "";
+                for path in chain {
+                    f.write_str(sep)?;
+                    sep = " -> ";
+                    path.fmt(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+pub fn resolve_export<'a>(
+    bundle: &'a Bundle,
+    path: &Path,
+) -> Result<ResolvedItem<'a>, ResolveError> {
+    let mut resolver = Resolver {
+        bundle,
+        visiting: HashSet::new(),
+        chain: Vec::new(),
+    };
+
+    resolver.resolve(path)
+}
+
+/// Resolves `name` as seen from within `module`, considering both the items of `module` and its `use`s
+pub fn resolve_name<'a>(
+    bundle: &'a Bundle,
+    module: &Path,
+    name: &str,
+) -> Result<ResolvedItem<'a>, ResolveError> {
+    let path = module.join(name);
+    match resolve_export(bundle, &path) {
+        Err(ResolveError::NotFound(_)) => {}
+        res => return res,
+    }
+
+    let file = bundle
+        .get(module)
+        .ok_or_else(|| ResolveError::NoSuchModule(module.clone()))?;
+
+    for use_item in &file.uses {
+        let target = Path(use_item.path.clone());
+
+        if target.name() == Some(name) {
+            return resolve_export(bundle, &target);
+        }
+
+        if bundle.get(&target).is_some() {
+            match resolve_export(bundle, &target.join(name)) {
+                Err(ResolveError::NotFound(_)) => {}
+                res => return res,
+            }
+        }
+    }
+
+    Err(ResolveError::NotFound(path))
+}
+
+struct Resolver<'a> {
+    bundle: &'a Bundle,
+    visiting: HashSet<Path>,
+    chain: Vec<Path>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve(&mut self, path: &Path) -> Result<ResolvedItem<'a>, ResolveError> {
+        let name = path.name().ok_or(ResolveError::EmptyPath)?;
+        let module = module_of(path);
+
+        if !self.visiting.insert(path.clone()) {
+            let mut cycle = self.chain.clone();
+            cycle.push(path.clone());
+            return Err(ResolveError::Cycle(cycle));
+        }
+        self.chain.push(path.clone());
+
+        let file = self
+            .bundle
+            .get(&module)
+            .ok_or_else(|| ResolveError::NoSuchModule(module.clone()))?;
+
+        if let Some(item) = Item::find_in(file, name) {
+            return Ok(ResolvedItem {
+                path: path.clone(),
+                item,
+                chain: self.chain.clone(),
+            });
+        }
+
+        for use_item in &file.uses {
+            if find_attribute::<ExportInline, _>(&use_item.attrs).is_none() {
+                continue;
+            }
+
+            let target = Path(use_item.path.clone());
+
+            if target.name() == Some(name) {
+                return self.resolve(&target);
+            }
+
+            // A re-export of an entire module exports every item of that module.
+            // Modules may re-export each other, so a revisit here just means the item isn't down this route
+            let target = target.join(name);
+            if self.bundle.get(&module_of(&target)).is_some() && !self.visiting.contains(&target) {
+                let depth = self.chain.len();
+                match self.resolve(&target) {
+                    Ok(resolved) => return Ok(resolved),
+                    Err(ResolveError::NotFound(_)) => {
+                        for path in self.chain.drain(depth..) {
+                            self.visiting.remove(&path);
+                        }
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Err(ResolveError::NotFound(path.clone()))
+    }
+}
+
+/// Expands `ty`, as seen from `module`, replacing each type alias with the type it aliases,
+/// and naming every other named type by the full path it is defined at
+pub fn expand_type(bundle: &Bundle, module: &Path, ty: &Type) -> Result<Type, ResolveError> {
+    let mut expander = Expander {
+        bundle,
+        visiting: Vec::new(),
+    };
+
+    expander.expand(module, ty, None)
+}
+
+struct Expander<'a> {
+    bundle: &'a Bundle,
+    visiting: Vec<Path>,
+}
+
+impl Expander<'_> {
+    fn expand(
+        &mut self,
+        module: &Path,
+        ty: &Type,
+        params: Option<&[Type]>,
+    ) -> Result<Type, ResolveError> {
+        Ok(match ty {
+            Type::Named(name, args) => {
+                let expanded = args
+                    .iter()
+                    .flatten()
+                    .map(|arg| self.expand(module, arg, params))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let resolved = resolve_name(self.bundle, module, name)?;
+                let Item::Type(TypeDef {
+                    body: TypeDefBody::Alias(alias),
+                    ..
+                }) = resolved.item
+                else {
+                    return Ok(Type::Named(
+                        resolved.path.to_string().into(),
+                        args.as_ref().map(|_| expanded),
+                    ));
+                };
+
+                if self.visiting.contains(&resolved.path) {
+                    let mut cycle = self.visiting.clone();
+                    cycle.push(resolved.path);
+                    return Err(ResolveError::Cycle(cycle));
+                }
+                self.visiting.push(resolved.path.clone());
+                let res = self.expand(&module_of(&resolved.path), &alias.alias, Some(&expanded));
+                self.visiting.pop();
+                res?
+            }
+            Type::Param(n, default) => match (params, default) {
+                (Some(params), _) if (*n as usize) < params.len() => params[*n as usize].clone(),
+                (Some(_), Some(default)) => self.expand(module, default, params)?,
+                _ => ty.clone(),
+            },
+            Type::Pointer(kind, inner) => {
+                Type::Pointer(*kind, Box::new(self.expand(module, inner, params)?))
+            }
+            Type::Func(sig) => {
+                let mut sig = sig.clone();
+                for param in &mut sig.params {
+                    param.ty = self.expand(module, &param.ty, params)?;
+                }
+                sig.retty = Box::new(self.expand(module, &sig.retty, params)?);
+                Type::Func(sig)
+            }
+            Type::Array(arr) => {
+                let mut arr = arr.clone();
+                arr.base = self.expand(module, &arr.base, params)?;
+                if let Some(params) = params {
+                    substitute_params(&mut arr.len, params);
+                }
+                Type::Array(arr)
+            }
+            Type::Uninit(inner) => Type::Uninit(Box::new(self.expand(module, inner, params)?)),
+            Type::Const(val) => {
+                let mut val = val.clone();
+                if let Some(params) = params {
+                    substitute_params(&mut val, params);
+                }
+                Type::Const(val)
+            }
+            ty => ty.clone(),
+        })
+    }
+}
+
+/// Replaces the references to generic parameters in `expr` with their constant arguments in `params`
+fn substitute_params(expr: &mut Expr, params: &[Type]) {
+    match expr {
+        Expr::Param(n) => {
+            if let Some(Type::Const(val)) = params.get(*n as usize) {
+                *expr = val.clone();
+            }
+        }
+        Expr::BinOp(_, left, right) => {
+            substitute_params(left, params);
+            substitute_params(right, params);
+        }
+        Expr::UnaryOp(_, inner) => substitute_params(inner, params),
+        _ => {}
+    }
+}
+
+pub(crate) fn module_of(path: &Path) -> Path {
+    path.parent().unwrap_or_else(|| Path(vec![]))
+}
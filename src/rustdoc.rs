@@ -0,0 +1,652 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    bundle::{Bundle, Path},
+    resolve::{expand_type, module_of},
+    tydef::{StructBody, TypeDefBody},
+    uses::{IntBits, PointerKind, Type},
+    value::ValueBody,
+};
+
+/// The shape of a type in hand-written Rust bindings, as far as it matters for comparing it to an IMT type.
+/// Type aliases of the crate are expanded, and the C types of `core::ffi` are replaced with the integer types they stand for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RustType {
+    /// An integer type, with no size for `usize` and `isize`
+    Int {
+        signed: bool,
+        bits: Option<u16>,
+    },
+    /// `c_long` or `c_ulong`
+    Long {
+        signed: bool,
+    },
+    Char,
+    Bool,
+    /// A raw pointer or reference
+    Pointer {
+        mutable: bool,
+        pointee: Box<RustType>,
+    },
+    /// A function pointer, possibly wrapped in an `Option`
+    FnPtr,
+    Array(Box<RustType>),
+    /// `()` or `c_void`
+    Unit,
+    Never,
+    /// A type named by its path, which is compared by its last component only
+    Named(String, Vec<RustType>),
+    /// A type that is not compared, such as a generic parameter, written in the form rustdoc describes it
+    Other(String),
+}
+
+impl core::fmt::Display for RustType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RustType::Int { signed, bits } => {
+                let sign = if *signed { 'i' } else { 'u' };
+                match bits {
+                    Some(bits) => f.write_fmt(format_args!("{sign}{bits}")),
+                    None => f.write_fmt(format_args!("{sign}size")),
+                }
+            }
+            RustType::Long { signed: true } => f.write_str("c_long"),
+            RustType::Long { signed: false } => f.write_str("c_ulong"),
+            RustType::Char => f.write_str("char"),
+            RustType::Bool => f.write_str("bool"),
+            RustType::Pointer {
+                mutable: true,
+                pointee,
+            } => f.write_fmt(format_args!("*mut {pointee}")),
+            RustType::Pointer {
+                mutable: false,
+                pointee,
+            } => f.write_fmt(format_args!("*const {pointee}")),
+            RustType::FnPtr => f.write_str("fn(..)"),
+            RustType::Array(elem) => f.write_fmt(format_args!("[{elem}; _]")),
+            RustType::Unit => f.write_str("()"),
+            RustType::Never => f.write_str("!"),
+            RustType::Named(name, args) if args.is_empty() => f.write_str(name),
+            RustType::Named(name, args) => {
+                f.write_fmt(format_args!("{name}<"))?;
+                let mut sep = "";
+                for arg in args {
+                    f.write_fmt(format_args!("{sep}{arg}"))?;
+                    sep = ", ";
+                }
+                f.write_str(">")
+            }
+            RustType::Other(desc) => f.write_str(desc),
+        }
+    }
+}
+
+/// A function declared by the bindings, such as in an `extern "C"` block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RustFunction {
+    /// The path of the function within the crate, or only its name if rustdoc does not record its path
+    pub path: Vec<String>,
+    pub params: Vec<(String, RustType)>,
+    pub ret: RustType,
+}
+
+/// A struct with named fields declared by the bindings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RustStruct {
+    pub path: Vec<String>,
+    pub fields: Vec<(String, RustType)>,
+}
+
+/// The functions and structs of a crate, read from the JSON output of `rustdoc` (`-Z unstable-options --output-format json`)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RustdocCrate {
+    pub functions: Vec<RustFunction>,
+    pub structs: Vec<RustStruct>,
+}
+
+#[derive(Debug)]
+pub enum RustdocError {
+    Json(serde_json::Error),
+    /// The JSON does not have the structure of rustdoc's output, such as when it has no `index`
+    Malformed(&'static str),
+}
+
+impl core::fmt::Display for RustdocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RustdocError::Json(e) => e.fmt(f),
+            RustdocError::Malformed(reason) => {
+                f.write_fmt(format_args!("not rustdoc JSON output: {reason}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RustdocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RustdocError::Json(e) => Some(e),
+            RustdocError::Malformed(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for RustdocError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// The key of an item id in the `index` and `paths` of rustdoc's output, which older formats write as strings and newer ones as integers
+fn id_key(id: &Value) -> Option<String> {
+    match id {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads the fields that were renamed between format versions, under either name
+fn field<'a>(obj: &'a Value, names: &[&str]) -> Option<&'a Value> {
+    names.iter().find_map(|name| obj.get(name))
+}
+
+/// Aliases nested deeper than this are assumed to be cyclic, and are not expanded further
+const MAX_ALIAS_DEPTH: usize = 32;
+
+struct Reader<'a> {
+    index: &'a serde_json::Map<String, Value>,
+    paths: Option<&'a serde_json::Map<String, Value>>,
+}
+
+impl Reader<'_> {
+    fn path(&self, id: &Value, name: &str) -> Vec<String> {
+        id_key(id)
+            .and_then(|id| self.paths?.get(&id)?.get("path")?.as_array())
+            .map(|path| {
+                path.iter()
+                    .filter_map(|elem| elem.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![name.to_string()])
+    }
+
+    fn ty(&self, ty: &Value, depth: usize) -> RustType {
+        let Some((kind, inner)) = ty.as_object().and_then(|ty| ty.iter().next()) else {
+            return RustType::Other(ty.to_string());
+        };
+        match &**kind {
+            "primitive" => match inner.as_str().unwrap_or("") {
+                "bool" => RustType::Bool,
+                "char" => RustType::Char,
+                "never" => RustType::Never,
+                "usize" => RustType::Int {
+                    signed: false,
+                    bits: None,
+                },
+                "isize" => RustType::Int {
+                    signed: true,
+                    bits: None,
+                },
+                prim => match prim.split_at_checked(1) {
+                    Some((sign @ ("i" | "u"), bits)) if bits.parse::<u16>().is_ok() => {
+                        RustType::Int {
+                            signed: sign == "i",
+                            bits: bits.parse().ok(),
+                        }
+                    }
+                    _ => RustType::Other(prim.to_string()),
+                },
+            },
+            "tuple" if inner.as_array().is_some_and(Vec::is_empty) => RustType::Unit,
+            "raw_pointer" | "borrowed_ref" => RustType::Pointer {
+                mutable: field(inner, &["is_mutable", "mutable"])
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+                pointee: Box::new(
+                    inner
+                        .get("type")
+                        .map_or(RustType::Unit, |ty| self.ty(ty, depth)),
+                ),
+            },
+            "function_pointer" => RustType::FnPtr,
+            "array" => RustType::Array(Box::new(
+                inner
+                    .get("type")
+                    .map_or(RustType::Unit, |ty| self.ty(ty, depth)),
+            )),
+            "resolved_path" => self.resolved_path(inner, depth),
+            _ => RustType::Other(ty.to_string()),
+        }
+    }
+
+    fn resolved_path(&self, path: &Value, depth: usize) -> RustType {
+        let full = field(path, &["path", "name"])
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        let name = full.rsplit("::").next().unwrap_or(full);
+        let args = path
+            .get("args")
+            .and_then(|args| args.get("angle_bracketed"))
+            .and_then(|args| args.get("args"))
+            .and_then(Value::as_array)
+            .map(|args| {
+                args.iter()
+                    .filter_map(|arg| arg.get("type"))
+                    .map(|ty| self.ty(ty, depth))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        // Aliases of the bindings themselves are expanded, since IMT aliases are expanded before comparing
+        let alias = path
+            .get("id")
+            .and_then(id_key)
+            .and_then(|id| self.index.get(&id))
+            .and_then(|item| item.get("inner"))
+            .and_then(|inner| field(inner, &["type_alias", "typedef"]))
+            .and_then(|alias| alias.get("type"));
+        if let Some(alias) = alias
+            && depth < MAX_ALIAS_DEPTH
+        {
+            return self.ty(alias, depth + 1);
+        }
+
+        match (name, &args[..]) {
+            ("c_char" | "c_schar", _) => RustType::Int {
+                signed: true,
+                bits: Some(8),
+            },
+            ("c_uchar", _) => RustType::Int {
+                signed: false,
+                bits: Some(8),
+            },
+            ("c_short", _) => RustType::Int {
+                signed: true,
+                bits: Some(16),
+            },
+            ("c_ushort", _) => RustType::Int {
+                signed: false,
+                bits: Some(16),
+            },
+            ("c_int", _) => RustType::Int {
+                signed: true,
+                bits: Some(32),
+            },
+            ("c_uint", _) => RustType::Int {
+                signed: false,
+                bits: Some(32),
+            },
+            ("c_longlong", _) => RustType::Int {
+                signed: true,
+                bits: Some(64),
+            },
+            ("c_ulonglong", _) => RustType::Int {
+                signed: false,
+                bits: Some(64),
+            },
+            ("c_long", _) => RustType::Long { signed: true },
+            ("c_ulong", _) => RustType::Long { signed: false },
+            ("c_void", _) => RustType::Unit,
+            // Nullable function pointers are written as `Option<fn(..)>`
+            ("Option", [RustType::FnPtr]) => RustType::FnPtr,
+            _ => RustType::Named(name.to_string(), args),
+        }
+    }
+
+    fn function(&self, id: &Value, name: &str, func: &Value) -> Option<RustFunction> {
+        let sig = field(func, &["sig", "decl"])?;
+        let params = sig
+            .get("inputs")?
+            .as_array()?
+            .iter()
+            .filter_map(|input| {
+                let input = input.as_array()?;
+                Some((
+                    input.first()?.as_str()?.to_string(),
+                    self.ty(input.get(1)?, 0),
+                ))
+            })
+            .collect();
+        let ret = match sig.get("output") {
+            None | Some(Value::Null) => RustType::Unit,
+            Some(ty) => self.ty(ty, 0),
+        };
+        Some(RustFunction {
+            path: self.path(id, name),
+            params,
+            ret,
+        })
+    }
+
+    fn structure(&self, id: &Value, name: &str, st: &Value) -> Option<RustStruct> {
+        let fields = st.get("kind")?.get("plain")?.get("fields")?.as_array()?;
+        let fields = fields
+            .iter()
+            .filter_map(|field| {
+                let field = self.index.get(&id_key(field)?)?;
+                let name = field.get("name")?.as_str()?;
+                let ty = field.get("inner")?.get("struct_field")?;
+                Some((name.to_string(), self.ty(ty, 0)))
+            })
+            .collect();
+        Some(RustStruct {
+            path: self.path(id, name),
+            fields,
+        })
+    }
+}
+
+impl RustdocCrate {
+    /// Reads the functions and structs of the crate described by the rustdoc JSON output `json`.
+    /// Items of other kinds, and items that rustdoc describes in an unrecognized form, are skipped
+    pub fn parse(json: &str) -> Result<Self, RustdocError> {
+        let root: Value = serde_json::from_str(json)?;
+        let index = root
+            .get("index")
+            .and_then(Value::as_object)
+            .ok_or(RustdocError::Malformed("no index of items"))?;
+        let reader = Reader {
+            index,
+            paths: root.get("paths").and_then(Value::as_object),
+        };
+
+        let mut krate = Self::default();
+        for item in index.values() {
+            let (Some(id), Some(name), Some(inner)) = (
+                item.get("id"),
+                item.get("name").and_then(Value::as_str),
+                item.get("inner"),
+            ) else {
+                continue;
+            };
+            if let Some(func) = inner.get("function") {
+                krate.functions.extend(reader.function(id, name, func));
+            } else if let Some(st) = inner.get("struct") {
+                krate.structs.extend(reader.structure(id, name, st));
+            }
+        }
+        Ok(krate)
+    }
+}
+
+/// How a declaration of the bindings differs from the item of the bundle it binds
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DriftKind {
+    /// The bindings declare no function or struct of the same name
+    Missing,
+    ParamCount {
+        expected: usize,
+        found: usize,
+    },
+    ParamType {
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    ReturnType {
+        expected: String,
+        found: String,
+    },
+    MissingField(String),
+    /// The bindings declare a field that the struct does not have
+    ExtraField(String),
+    FieldType {
+        field: String,
+        expected: String,
+        found: String,
+    },
+}
+
+/// A difference between an item of a bundle and the declaration of the bindings for it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Drift {
+    pub path: Path,
+    /// The path of the declaration in the bindings, unless it is [`DriftKind::Missing`]
+    pub binding: Option<String>,
+    pub kind: DriftKind,
+}
+
+impl core::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = &self.path;
+        let binding = self.binding.as_deref().unwrap_or("the bindings");
+        match &self.kind {
+            DriftKind::Missing => f.write_fmt(format_args!("{path} is not declared by the bindings")),
+            DriftKind::ParamCount { expected, found } => f.write_fmt(format_args!(
+                "{path} has {expected} parameter(s), but {binding} has {found}"
+            )),
+            DriftKind::ParamType {
+                index,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "parameter {index} of {path} has type {expected}, but that of {binding} has type {found}"
+            )),
+            DriftKind::ReturnType { expected, found } => f.write_fmt(format_args!(
+                "{path} returns {expected}, but {binding} returns {found}"
+            )),
+            DriftKind::MissingField(name) => f.write_fmt(format_args!(
+                "{path} has a field {name}, which {binding} does not declare"
+            )),
+            DriftKind::ExtraField(name) => f.write_fmt(format_args!(
+                "{binding} declares a field {name}, which {path} does not have"
+            )),
+            DriftKind::FieldType {
+                field,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "field {field} of {path} has type {expected}, but that of {binding} has type {found}"
+            )),
+        }
+    }
+}
+
+/// Whether the expanded IMT type `imt` has the same shape as the bindings type `rust`.
+/// Types that the bindings write in a form that is not understood match any type, so that drift is not reported where there might be none
+fn same_shape(imt: &Type, rust: &RustType) -> bool {
+    match (imt, rust) {
+        (_, RustType::Other(_)) | (Type::Param(..), _) => true,
+        (Type::Int(int), RustType::Int { signed, bits }) => {
+            int.signed == *signed
+                && match (int.bits, bits) {
+                    (IntBits::Bits(imt), Some(rust)) => u16::from(imt.get()) == *rust,
+                    // The width of `usize` depends on the target, so any width a pointer might have matches it
+                    (IntBits::Bits(imt), None) => matches!(imt.get(), 16 | 32 | 64),
+                    (IntBits::Long, _) => false,
+                }
+        }
+        (Type::Int(int), RustType::Long { signed }) => {
+            int.bits == IntBits::Long && int.signed == *signed
+        }
+        (Type::Byte, RustType::Int { bits: Some(8), .. }) => true,
+        (Type::Char(int), RustType::Char) => *int == crate::uses::IntType::u32,
+        (
+            Type::Char(int),
+            RustType::Int {
+                signed: false,
+                bits: Some(bits),
+            },
+        ) => matches!(int.bits, IntBits::Bits(imt) if u16::from(imt.get()) == *bits),
+        (Type::Pointer(_, pointee), RustType::FnPtr) => matches!(**pointee, Type::Func(_)),
+        (Type::Func(_), RustType::FnPtr) => true,
+        // Special pointers may be represented by a pointer of either kind, or an address
+        (Type::Pointer(PointerKind::Special(_), _), RustType::Pointer { .. })
+        | (Type::Pointer(PointerKind::Special(_), _), RustType::Int { bits: None, .. }) => true,
+        (
+            Type::Pointer(kind, pointee),
+            RustType::Pointer {
+                mutable,
+                pointee: rust,
+            },
+        ) => (*kind == PointerKind::Mut) == *mutable && same_shape(pointee, rust),
+        (Type::Array(arr), RustType::Array(elem)) => same_shape(&arr.base, elem),
+        (Type::Uninit(ty), RustType::Named(name, args)) if name == "MaybeUninit" => {
+            args.first().is_none_or(|arg| same_shape(ty, arg))
+        }
+        (Type::Void, RustType::Unit) | (Type::Never, RustType::Never) => true,
+        (Type::Named(path, _), RustType::Named(name, _)) => {
+            path.rsplit("::").next() == Some(&**name)
+        }
+        _ => false,
+    }
+}
+
+/// Cross-checks each function and struct of `bundle` against the declaration of the same name in `krate`,
+/// reporting the items that the bindings do not declare, or declare with a different signature or fields.
+///
+/// Types are compared after expanding aliases on both sides, and named types are compared by name only.
+/// Declarations of the bindings that have no item in the bundle are not reported, since bindings may cover more than one bundle
+pub fn cross_check(bundle: &Bundle, krate: &RustdocCrate) -> Vec<Drift> {
+    let mut functions = HashMap::<&str, Vec<&RustFunction>>::new();
+    for func in &krate.functions {
+        if let Some(name) = func.path.last() {
+            functions.entry(name).or_default().push(func);
+        }
+    }
+    let mut structs = HashMap::<&str, Vec<&RustStruct>>::new();
+    for st in &krate.structs {
+        if let Some(name) = st.path.last() {
+            structs.entry(name).or_default().push(st);
+        }
+    }
+
+    let mut drift = Vec::new();
+    for (module, file) in bundle {
+        let expand = |ty: &Type| expand_type(bundle, module, ty).unwrap_or_else(|_| ty.clone());
+
+        for val in &file.values {
+            let ValueBody::Function(f) = &val.body else {
+                continue;
+            };
+            let path = module.join(&val.name);
+            let Some(candidates) = functions.get(&*val.name) else {
+                drift.push(Drift {
+                    path,
+                    binding: None,
+                    kind: DriftKind::Missing,
+                });
+                continue;
+            };
+            let params = f
+                .signature
+                .params
+                .iter()
+                .map(|param| expand(&param.ty))
+                .collect::<Vec<_>>();
+            let ret = expand(&f.signature.retty);
+            let differences = |func: &RustFunction| {
+                let mut kinds = Vec::new();
+                if func.params.len() != params.len() {
+                    kinds.push(DriftKind::ParamCount {
+                        expected: params.len(),
+                        found: func.params.len(),
+                    });
+                }
+                for (index, (imt, (_, rust))) in params.iter().zip(&func.params).enumerate() {
+                    if !same_shape(imt, rust) {
+                        kinds.push(DriftKind::ParamType {
+                            index,
+                            expected: imt.to_string(),
+                            found: rust.to_string(),
+                        });
+                    }
+                }
+                if !same_shape(&ret, &func.ret) {
+                    kinds.push(DriftKind::ReturnType {
+                        expected: ret.to_string(),
+                        found: func.ret.to_string(),
+                    });
+                }
+                kinds
+            };
+            report_best(
+                &mut drift,
+                &path,
+                candidates,
+                |func| &func.path,
+                differences,
+            );
+        }
+
+        for ty in &file.types {
+            let TypeDefBody::Struct(st) = &ty.body else {
+                continue;
+            };
+            let StructBody::Fields(fields) = &st.body else {
+                continue;
+            };
+            let path = module.join(&ty.name);
+            let Some(candidates) = structs.get(&*ty.name) else {
+                drift.push(Drift {
+                    path,
+                    binding: None,
+                    kind: DriftKind::Missing,
+                });
+                continue;
+            };
+            let mut expected = fields
+                .field
+                .iter()
+                .map(|field| (field.name.to_string(), expand(&field.ty)))
+                .collect::<Vec<_>>();
+            if let Some(pad) = &fields.pad {
+                expected.push(("_pad".to_string(), expand(pad)));
+            }
+            let differences = |rust: &RustStruct| {
+                let mut kinds = Vec::new();
+                for (name, imt) in &expected {
+                    match rust.fields.iter().find(|(field, _)| field == name) {
+                        None => kinds.push(DriftKind::MissingField(name.clone())),
+                        Some((_, ty)) if !same_shape(imt, ty) => kinds.push(DriftKind::FieldType {
+                            field: name.clone(),
+                            expected: imt.to_string(),
+                            found: ty.to_string(),
+                        }),
+                        Some(_) => {}
+                    }
+                }
+                for (name, _) in &rust.fields {
+                    if !expected.iter().any(|(field, _)| field == name) {
+                        kinds.push(DriftKind::ExtraField(name.clone()));
+                    }
+                }
+                kinds
+            };
+            report_best(&mut drift, &path, candidates, |st| &st.path, differences);
+        }
+    }
+    drift
+}
+
+/// Reports the differences from the declaration among `candidates` that differs least from the item at `path`,
+/// preferring one in a module of the same name when several do not differ at all
+fn report_best<T>(
+    drift: &mut Vec<Drift>,
+    path: &Path,
+    candidates: &[&T],
+    binding_path: impl Fn(&T) -> &Vec<String>,
+    differences: impl Fn(&T) -> Vec<DriftKind>,
+) {
+    let module = module_of(path);
+    let same_module = |candidate: &T| {
+        let binding = binding_path(candidate);
+        binding.len() >= 2 && module.name() == Some(&*binding[binding.len() - 2])
+    };
+    let Some((candidate, kinds)) = candidates
+        .iter()
+        .map(|candidate| (*candidate, differences(candidate)))
+        .min_by_key(|(candidate, kinds)| (kinds.len(), !same_module(candidate)))
+    else {
+        return;
+    };
+    let binding = binding_path(candidate).join("::");
+    drift.extend(kinds.into_iter().map(|kind| Drift {
+        path: path.clone(),
+        binding: Some(binding.clone()),
+        kind,
+    }));
+}
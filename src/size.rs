@@ -0,0 +1,172 @@
+use std::cmp::Reverse;
+
+use bincode::{Encode, error::EncodeError};
+use indexmap::IndexMap;
+
+use crate::{
+    attr::{Attribute, AttributeInfo, AttributeTarget, attribute_info},
+    bundle::{Bundle, Path},
+    config::format_config,
+    tydef::TypeDefBody,
+    uuid::Uuid,
+    value::ValueBody,
+    visit::{AttributeVisitor, visit_file},
+};
+
+fn encoded_len<T: Encode>(val: &T) -> Result<usize, EncodeError> {
+    let mut writer = bincode::enc::write::SizeWriter::default();
+    bincode::encode_into_writer(val, &mut writer, format_config())?;
+    Ok(writer.bytes_written)
+}
+
+/// The encoded size of an item of a file, including its attributes and the attributes of its members
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemSize {
+    /// The kind of the item, and its name (or path for a `use`), such as `struct Handle` or `use base::io`
+    pub name: String,
+    pub bytes: usize,
+}
+
+/// The encoded size of every instance of an attribute within a file
+#[derive(Clone, Debug)]
+pub struct AttributeSize {
+    pub id: Uuid,
+    /// The attribute type with this id, if it is recognized by this version of imt
+    pub info: Option<&'static AttributeInfo>,
+    /// The number of instances of the attribute
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// The encoded size of a file of a bundle, and how it is divided between items and attributes
+#[derive(Clone, Debug)]
+pub struct MemberSize {
+    pub path: Path,
+    /// The size of the whole file
+    pub bytes: usize,
+    /// Each use, type, and value of the file, in the order they appear in
+    pub items: Vec<ItemSize>,
+    /// Each attribute used anywhere in the file. Attributes are also counted in the size of the items they are on
+    pub attributes: IndexMap<Uuid, AttributeSize>,
+}
+
+impl MemberSize {
+    /// The `n` largest items, largest first
+    pub fn largest_items(&self, n: usize) -> Vec<&ItemSize> {
+        let mut items = self.items.iter().collect::<Vec<_>>();
+        items.sort_by_key(|size| Reverse(size.bytes));
+        items.truncate(n);
+        items
+    }
+
+    /// The `n` attributes with the most bytes of instances, largest first
+    pub fn largest_attributes(&self, n: usize) -> Vec<&AttributeSize> {
+        let mut attrs = self.attributes.values().collect::<Vec<_>>();
+        attrs.sort_by_key(|size| Reverse(size.bytes));
+        attrs.truncate(n);
+        attrs
+    }
+}
+
+/// The encoded size of each file of a bundle, as written by [`Bundle::write_files`].
+///
+/// Documentation attributes are often most of a distribution bundle, which a report makes easy to find.
+#[derive(Clone, Debug, Default)]
+pub struct SizeReport {
+    pub members: Vec<MemberSize>,
+}
+
+impl SizeReport {
+    pub fn of(bundle: &Bundle) -> Result<Self, EncodeError> {
+        let mut members = Vec::new();
+        for (path, file) in bundle {
+            let mut items = Vec::new();
+            for use_item in &file.uses {
+                items.push(ItemSize {
+                    name: format!("use {}", use_item.path.join("::")),
+                    bytes: encoded_len(use_item)?,
+                });
+            }
+            for ty in &file.types {
+                let keyword = match &ty.body {
+                    TypeDefBody::Alias(_) => "type",
+                    TypeDefBody::Struct(_) => "struct",
+                    TypeDefBody::Union(_) => "union",
+                    TypeDefBody::Enum(_) => "enum",
+                };
+                items.push(ItemSize {
+                    name: format!("{keyword} {}", ty.name),
+                    bytes: encoded_len(ty)?,
+                });
+            }
+            for val in &file.values {
+                let keyword = match &val.body {
+                    ValueBody::Const(_) => "const",
+                    ValueBody::Function(_) => "fn",
+                };
+                items.push(ItemSize {
+                    name: format!("{keyword} {}", val.name),
+                    bytes: encoded_len(val)?,
+                });
+            }
+
+            let mut visitor = SizeVisitor {
+                attributes: IndexMap::new(),
+                error: None,
+            };
+            visit_file(&mut visitor, file);
+            if let Some(e) = visitor.error {
+                return Err(e);
+            }
+
+            members.push(MemberSize {
+                path: path.clone(),
+                bytes: encoded_len(file)?,
+                items,
+                attributes: visitor.attributes,
+            });
+        }
+        Ok(Self { members })
+    }
+
+    /// The size of every file of the bundle
+    pub fn total(&self) -> usize {
+        self.members.iter().map(|member| member.bytes).sum()
+    }
+
+    /// The `n` largest files, largest first
+    pub fn largest_members(&self, n: usize) -> Vec<&MemberSize> {
+        let mut members = self.members.iter().collect::<Vec<_>>();
+        members.sort_by_key(|size| Reverse(size.bytes));
+        members.truncate(n);
+        members
+    }
+}
+
+struct SizeVisitor {
+    attributes: IndexMap<Uuid, AttributeSize>,
+    error: Option<EncodeError>,
+}
+
+impl AttributeVisitor for SizeVisitor {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &[Attribute<Targ>]) {
+        for attr in attrs {
+            let bytes = match encoded_len(attr) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    self.error.get_or_insert(e);
+                    continue;
+                }
+            };
+            let id = *attr.id();
+            let size = self.attributes.entry(id).or_insert_with(|| AttributeSize {
+                id,
+                info: attribute_info(id),
+                count: 0,
+                bytes: 0,
+            });
+            size.count += 1;
+            size.bytes += bytes;
+        }
+    }
+}
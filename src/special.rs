@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::{
+    attr::{
+        find_attributes,
+        types::{SpecialConstant, SpecialPointer, SpecialPointerRepr},
+    },
+    bundle::Bundle,
+    target::TargetSpec,
+    uses::{PointerKind, SpecialConst},
+    uuid::Uuid,
+};
+
+/// The special pointer kinds known to a bundle, keyed by UUID
+#[derive(Clone, Debug, Default)]
+pub struct SpecialPointers {
+    kinds: HashMap<Uuid, SpecialPointer>,
+}
+
+impl SpecialPointers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the special pointer kinds declared by [`SpecialPointer`] attributes on the files of `bundle`
+    pub fn from_bundle(bundle: &Bundle) -> Self {
+        let mut this = Self::new();
+        for (_, file) in bundle {
+            for kind in find_attributes::<SpecialPointer, _>(&file.attributes) {
+                this.register(kind.clone());
+            }
+        }
+        this
+    }
+
+    /// Registers a special pointer kind, replacing any previous kind with the same id
+    pub fn register(&mut self, kind: SpecialPointer) {
+        self.kinds.insert(kind.id, kind);
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&SpecialPointer> {
+        self.kinds.get(&id)
+    }
+
+    /// The representation of pointers of `kind`. Unknown special kinds are represented as mutable pointers
+    pub fn repr(&self, kind: PointerKind) -> SpecialPointerRepr {
+        match kind {
+            PointerKind::Const => SpecialPointerRepr::Const,
+            PointerKind::Mut => SpecialPointerRepr::Mut,
+            PointerKind::Special(id) => self.get(id).map_or(SpecialPointerRepr::Mut, |k| k.repr),
+        }
+    }
+
+    /// A human-readable name for `kind`
+    pub fn name(&self, kind: PointerKind) -> String {
+        match kind {
+            PointerKind::Const => "const".to_string(),
+            PointerKind::Mut => "mut".to_string(),
+            PointerKind::Special(id) => self
+                .get(id)
+                .map_or_else(|| format!("special {id}"), |k| k.name.clone()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpecialPointer> {
+        self.kinds.values()
+    }
+}
+
+/// The custom special constants known to a bundle, keyed by UUID
+#[derive(Clone, Debug, Default)]
+pub struct SpecialConsts {
+    consts: HashMap<Uuid, SpecialConstant>,
+}
+
+impl SpecialConsts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the special constants declared by [`SpecialConstant`] attributes on the files of `bundle`
+    pub fn from_bundle(bundle: &Bundle) -> Self {
+        let mut this = Self::new();
+        for (_, file) in bundle {
+            for decl in find_attributes::<SpecialConstant, _>(&file.attributes) {
+                this.register(decl.clone());
+            }
+        }
+        this
+    }
+
+    /// Registers a special constant, replacing any previous constant with the same id
+    pub fn register(&mut self, decl: SpecialConstant) {
+        self.consts.insert(decl.id, decl);
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&SpecialConstant> {
+        self.consts.get(&id)
+    }
+
+    /// The value of `special` on `target`. Custom constants that the target does not give a value use their declared default
+    pub fn value(&self, special: SpecialConst, target: &TargetSpec) -> Option<u128> {
+        target.special_value(special).or_else(|| match special {
+            SpecialConst::Custom(id) => self.get(id)?.default,
+            _ => None,
+        })
+    }
+
+    /// A human-readable name for `special`
+    pub fn name(&self, special: SpecialConst) -> String {
+        match special {
+            SpecialConst::Custom(id) => self
+                .get(id)
+                .map_or_else(|| special.to_string(), |decl| decl.name.clone()),
+            builtin => builtin.to_string(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpecialConstant> {
+        self.consts.values()
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    attr::{
+        Attribute, AttributeFlags, AttributeTarget, AttributeType, find_attribute,
+        types::{
+            ErrorDoc, ExampleDoc, ItemDoc, LocalizedDoc, ParamDoc, ReturnDoc, Synthetic,
+            ToolComment,
+        },
+    },
+    file::File,
+    visit::{AttributeVisitorMut, visit_file_mut},
+};
+
+bitflags::bitflags! {
+    #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+    pub struct StripOptions : u32 {
+        /// Removes [`ItemDoc`] attributes, and the [`ParamDoc`], [`ReturnDoc`], [`ErrorDoc`], [`ExampleDoc`], and [`LocalizedDoc`] attributes that extend them
+        const DOCS = 0x0000_0001;
+        /// Removes [`ToolComment`] attributes
+        const TOOL_COMMENTS = 0x0000_0002;
+        /// Removes uses, types, and values marked [`Synthetic`]
+        const SYNTHETIC = 0x0000_0004;
+        /// Removes unrecognized attributes (which are necessarily [`AttributeFlags::IGNORE`])
+        const UNKNOWN = 0x0000_0008;
+    }
+}
+
+impl StripOptions {
+    pub fn parse(x: &str) -> Option<Self> {
+        x.split(',')
+            .map(|opt| match opt.trim() {
+                "docs" => Some(StripOptions::DOCS),
+                "comments" => Some(StripOptions::TOOL_COMMENTS),
+                "synthetic" => Some(StripOptions::SYNTHETIC),
+                "unknown" => Some(StripOptions::UNKNOWN),
+                "all" => Some(StripOptions::all()),
+                _ => None,
+            })
+            .try_fold(StripOptions::empty(), |acc, opt| Some(acc | opt?))
+    }
+}
+
+struct AttributeStripper(StripOptions);
+
+impl AttributeStripper {
+    fn strips<Targ: AttributeTarget>(&self, attr: &Attribute<Targ>) -> bool {
+        let id = *attr.id();
+
+        (self.0.contains(StripOptions::DOCS)
+            && [
+                ItemDoc::ID,
+                ParamDoc::ID,
+                ReturnDoc::ID,
+                ErrorDoc::ID,
+                ExampleDoc::ID,
+                LocalizedDoc::ID,
+            ]
+            .contains(&id))
+            || (self.0.contains(StripOptions::TOOL_COMMENTS) && id == ToolComment::ID)
+            || (self.0.contains(StripOptions::UNKNOWN)
+                && attr.is_unknown()
+                && attr.flags().contains(AttributeFlags::IGNORE))
+    }
+}
+
+impl AttributeVisitorMut for AttributeStripper {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>) {
+        attrs.retain(|attr| !self.strips(attr));
+    }
+}
+
+pub fn strip_file(file: &mut File, opts: StripOptions) {
+    if opts.contains(StripOptions::SYNTHETIC) {
+        file.uses
+            .retain(|use_item| find_attribute::<Synthetic, _>(&use_item.attrs).is_none());
+        file.types
+            .retain(|ty| ty.body.find_attribute::<Synthetic>().is_none());
+        file.values
+            .retain(|val| val.body.find_attribute::<Synthetic>().is_none());
+    }
+
+    visit_file_mut(&mut AttributeStripper(opts), file);
+}
+
+struct UnignorableStripper;
+
+impl AttributeVisitorMut for UnignorableStripper {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>) {
+        strip_unignorable_attrs(attrs);
+    }
+}
+
+/// Removes the unrecognized attributes of `attrs` that do not have [`AttributeFlags::IGNORE`] set
+pub fn strip_unignorable_attrs<Targ: AttributeTarget>(attrs: &mut Vec<Attribute<Targ>>) {
+    attrs.retain(|attr| !attr.is_unknown() || attr.flags().contains(AttributeFlags::IGNORE));
+}
+
+/// Removes unrecognized attributes that do not have [`AttributeFlags::IGNORE`] set.
+/// These are only present in files decoded with [`UnknownAttributePolicy::WarnAndPreserve`](crate::attr::UnknownAttributePolicy::WarnAndPreserve)
+pub fn strip_unignorable(file: &mut File) {
+    visit_file_mut(&mut UnignorableStripper, file);
+}
@@ -0,0 +1,125 @@
+use crate::{
+    attr::{
+        find_attribute,
+        types::{ActiveWhen, DiscriminatedBy},
+    },
+    bundle::{Bundle, Path},
+    resolve::{Item, module_of, resolve_name},
+    tydef::{Field, StructFields, TypeDef, TypeDefBody, Union},
+    uses::Type,
+};
+
+/// A field of a struct whose type is a union with a [`DiscriminatedBy`] attribute
+#[derive(Clone, Debug)]
+pub struct TaggedUnion<'a> {
+    /// The field holding the union
+    pub field: &'a Field,
+    pub union: &'a Union,
+    /// The sibling of `field` that determines which member of the union is active
+    pub tag: &'a Field,
+    /// The enum type of `tag`, and the module it is defined in, if `tag` has an enum type
+    pub tag_enum: Option<(Path, &'a TypeDef)>,
+}
+
+impl<'a> TaggedUnion<'a> {
+    /// Whether `variant` names a variant of the enum type of the tag field
+    pub fn has_variant(&self, variant: &str) -> bool {
+        matches!(
+            &self.tag_enum,
+            Some((_, TypeDef { body: TypeDefBody::Enum(en), .. }))
+                if en.variants.iter().any(|v| v.name == variant)
+        )
+    }
+
+    /// Each member of the union with an [`ActiveWhen`] attribute, with the name of the tag variant it is active for
+    pub fn members(&self) -> impl Iterator<Item = (&'a Field, &'a str)> + use<'a> {
+        self.union.fields.field.iter().filter_map(|member| {
+            find_attribute::<ActiveWhen, _>(&member.attrs).map(|when| (member, &*when.variant))
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaggedUnionError {
+    /// The struct has no field with the name given by the union's [`DiscriminatedBy`] attribute
+    NoTagField { field: String, tag: String },
+}
+
+impl core::fmt::Display for TaggedUnionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaggedUnionError::NoTagField { field, tag } => f.write_fmt(format_args!(
+                "union field {field} is discriminated by {tag}, which is not a field of the same struct"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for TaggedUnionError {}
+
+/// Finds the non-generic enum named by `ty`, following type aliases
+fn find_enum<'a>(bundle: &'a Bundle, module: &Path, ty: &Type) -> Option<(Path, &'a TypeDef)> {
+    let mut module = module.clone();
+    let mut ty = ty;
+    let mut seen = Vec::new();
+    loop {
+        let Type::Named(name, None) = ty else {
+            return None;
+        };
+        let resolved = resolve_name(bundle, &module, name).ok()?;
+        let Item::Type(def) = resolved.item else {
+            return None;
+        };
+        match &def.body {
+            TypeDefBody::Enum(_) => return Some((module_of(&resolved.path), def)),
+            TypeDefBody::Alias(alias) if !seen.contains(&resolved.path) => {
+                module = module_of(&resolved.path);
+                seen.push(resolved.path);
+                ty = &alias.alias;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Finds the fields of a struct (defined in `module`) that hold a union with a [`DiscriminatedBy`] attribute
+pub fn tagged_unions<'a>(
+    bundle: &'a Bundle,
+    module: &Path,
+    fields: &'a StructFields,
+) -> Vec<Result<TaggedUnion<'a>, TaggedUnionError>> {
+    let mut res = Vec::new();
+    for field in &fields.field {
+        let Type::Named(name, None) = &field.ty else {
+            continue;
+        };
+        let Ok(resolved) = resolve_name(bundle, module, name) else {
+            continue;
+        };
+        let Item::Type(TypeDef {
+            body: TypeDefBody::Union(union),
+            ..
+        }) = resolved.item
+        else {
+            continue;
+        };
+        let Some(discrim) = find_attribute::<DiscriminatedBy, _>(&union.attrs) else {
+            continue;
+        };
+
+        let Some(tag) = fields.field.iter().find(|f| f.name == discrim.field) else {
+            res.push(Err(TaggedUnionError::NoTagField {
+                field: field.name.to_string(),
+                tag: discrim.field.clone(),
+            }));
+            continue;
+        };
+        res.push(Ok(TaggedUnion {
+            field,
+            union,
+            tag,
+            tag_enum: find_enum(bundle, module, &tag.ty),
+        }));
+    }
+    res
+}
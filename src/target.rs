@@ -0,0 +1,241 @@
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+};
+
+use crate::{uses::SpecialConst, uuid::Uuid};
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// Describes the properties of a target that affect the ABI of interfaces
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TargetSpec {
+    pub pointer_bits: u16,
+    pub long_bits: u16,
+    pub endian: Endian,
+    /// The alignment of 128-bit integers, in bytes
+    pub int128_align: u16,
+    /// The size of a page of memory, in bytes
+    pub page_size: u64,
+    /// The maximum length of a path, in bytes
+    pub max_path_len: u64,
+    /// The maximum number of handles a thread can have open at once
+    pub max_handle_count: u64,
+    /// The values of [`SpecialConst::Custom`] constants on this target, by id
+    pub special_consts: BTreeMap<Uuid, u128>,
+}
+
+/// The names of the targets known to [`TargetSpec::preset`]
+pub const PRESETS: &[&str] = &[
+    "x86_64", "x86", "aarch64", "arm", "riscv64", "riscv32", "clever",
+];
+
+#[derive(Debug)]
+pub enum TargetSpecError {
+    Io(std::io::Error),
+    /// The target is not a preset, and its architecture is not one Lilium supports
+    UnknownTarget(String),
+    /// The line (starting from 1) is not a `key = value` entry with a known key and a valid value
+    InvalidEntry(usize),
+    /// The spec file has no `base`, and does not set the key
+    MissingKey(&'static str),
+}
+
+impl core::fmt::Display for TargetSpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetSpecError::Io(e) => e.fmt(f),
+            TargetSpecError::UnknownTarget(target) => {
+                f.write_fmt(format_args!("unknown target {target}"))
+            }
+            TargetSpecError::InvalidEntry(line) => {
+                f.write_fmt(format_args!("invalid target spec entry on line {line}"))
+            }
+            TargetSpecError::MissingKey(key) => {
+                f.write_fmt(format_args!("target spec does not set {key}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for TargetSpecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TargetSpecError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TargetSpecError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl TargetSpec {
+    fn lilium(pointer_bits: u16, int128_align: u16) -> Self {
+        Self {
+            pointer_bits,
+            long_bits: pointer_bits,
+            endian: Endian::Little,
+            int128_align,
+            page_size: 4096,
+            max_path_len: 4096,
+            max_handle_count: 65536,
+            special_consts: BTreeMap::new(),
+        }
+    }
+
+    /// The target named `name`, which is one of [`PRESETS`]
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "x86_64" | "aarch64" | "riscv64" | "clever" => Some(Self::lilium(64, 16)),
+            "x86" | "riscv32" => Some(Self::lilium(32, 16)),
+            "arm" => Some(Self::lilium(32, 8)),
+            _ => None,
+        }
+    }
+
+    /// The target described by a target triple such as `x86_64-pc-lilium-std`, or by the name of a preset.
+    ///
+    /// Only the architecture (the first component of the triple) is significant.
+    /// Big-endian variants of architectures, such as `aarch64_be`, are supported.
+    pub fn from_triple(triple: &str) -> Result<Self, TargetSpecError> {
+        let arch = triple.split('-').next().unwrap_or(triple);
+        let (preset, endian) = match arch {
+            "x86_64" | "amd64" => ("x86_64", Endian::Little),
+            "x86" | "i386" | "i486" | "i586" | "i686" => ("x86", Endian::Little),
+            "aarch64" | "arm64" => ("aarch64", Endian::Little),
+            "aarch64_be" => ("aarch64", Endian::Big),
+            arch if arch.starts_with("armeb") || arch.starts_with("thumbeb") => {
+                ("arm", Endian::Big)
+            }
+            arch if arch.starts_with("arm") || arch.starts_with("thumb") => ("arm", Endian::Little),
+            arch if arch.starts_with("riscv64") => ("riscv64", Endian::Little),
+            arch if arch.starts_with("riscv32") => ("riscv32", Endian::Little),
+            "clever" | "cleverisa" => ("clever", Endian::Little),
+            _ => return Err(TargetSpecError::UnknownTarget(triple.to_string())),
+        };
+        let mut spec = Self::preset(preset).expect("every architecture has a preset");
+        spec.endian = endian;
+        Ok(spec)
+    }
+
+    /// The value of `special` on this target, if the target gives it one.
+    ///
+    /// Custom constants without a value here may still have a default value, see [`SpecialConsts::value`](crate::special::SpecialConsts::value)
+    pub fn special_value(&self, special: SpecialConst) -> Option<u128> {
+        match special {
+            SpecialConst::SizeofPointer => Some((self.pointer_bits / 8) as u128),
+            SpecialConst::SizeofLong => Some((self.long_bits / 8) as u128),
+            SpecialConst::PageSize => Some(self.page_size as u128),
+            SpecialConst::MaxPathLen => Some(self.max_path_len as u128),
+            SpecialConst::MaxHandleCount => Some(self.max_handle_count as u128),
+            SpecialConst::Custom(id) => self.special_consts.get(&id).copied(),
+        }
+    }
+
+    /// Writes the target as a spec file, with one `key = value` line per property
+    pub fn write<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, "pointer_bits = {}", self.pointer_bits)?;
+        writeln!(w, "long_bits = {}", self.long_bits)?;
+        match self.endian {
+            Endian::Little => writeln!(w, "endian = little")?,
+            Endian::Big => writeln!(w, "endian = big")?,
+        }
+        writeln!(w, "int128_align = {}", self.int128_align)?;
+        writeln!(w, "page_size = {}", self.page_size)?;
+        writeln!(w, "max_path_len = {}", self.max_path_len)?;
+        writeln!(w, "max_handle_count = {}", self.max_handle_count)?;
+        for (id, val) in &self.special_consts {
+            writeln!(w, "special.{id} = {val}")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a spec file in the format produced by [`TargetSpec::write`]. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// A `base = <target>` entry starts from the target given to [`TargetSpec::from_triple`], so that later entries only need to
+    /// override the properties that differ. Without a base, every property except the special constants must be set.
+    pub fn read<R: BufRead>(r: R) -> Result<Self, TargetSpecError> {
+        let mut pointer_bits = None;
+        let mut long_bits = None;
+        let mut endian = None;
+        let mut int128_align = None;
+        let mut page_size = None;
+        let mut max_path_len = None;
+        let mut max_handle_count = None;
+        let mut special_consts = BTreeMap::new();
+
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = TargetSpecError::InvalidEntry(n + 1);
+            let Some((key, val)) = line.split_once('=') else {
+                return Err(invalid);
+            };
+            let (key, val) = (key.trim(), val.trim());
+            match key {
+                "base" => {
+                    let base = Self::from_triple(val)?;
+                    pointer_bits = Some(base.pointer_bits);
+                    long_bits = Some(base.long_bits);
+                    endian = Some(base.endian);
+                    int128_align = Some(base.int128_align);
+                    page_size = Some(base.page_size);
+                    max_path_len = Some(base.max_path_len);
+                    max_handle_count = Some(base.max_handle_count);
+                    special_consts = base.special_consts;
+                }
+                "pointer_bits" => pointer_bits = Some(val.parse().map_err(|_| invalid)?),
+                "long_bits" => long_bits = Some(val.parse().map_err(|_| invalid)?),
+                "endian" => {
+                    endian = Some(match val {
+                        "little" => Endian::Little,
+                        "big" => Endian::Big,
+                        _ => return Err(invalid),
+                    })
+                }
+                "int128_align" => int128_align = Some(val.parse().map_err(|_| invalid)?),
+                "page_size" => page_size = Some(val.parse().map_err(|_| invalid)?),
+                "max_path_len" => max_path_len = Some(val.parse().map_err(|_| invalid)?),
+                "max_handle_count" => max_handle_count = Some(val.parse().map_err(|_| invalid)?),
+                key => {
+                    let id = key
+                        .strip_prefix("special.")
+                        .and_then(Uuid::try_parse)
+                        .ok_or(TargetSpecError::InvalidEntry(n + 1))?;
+                    special_consts.insert(id, val.parse().map_err(|_| invalid)?);
+                }
+            }
+        }
+
+        Ok(Self {
+            pointer_bits: pointer_bits.ok_or(TargetSpecError::MissingKey("pointer_bits"))?,
+            long_bits: long_bits.ok_or(TargetSpecError::MissingKey("long_bits"))?,
+            endian: endian.ok_or(TargetSpecError::MissingKey("endian"))?,
+            int128_align: int128_align.ok_or(TargetSpecError::MissingKey("int128_align"))?,
+            page_size: page_size.ok_or(TargetSpecError::MissingKey("page_size"))?,
+            max_path_len: max_path_len.ok_or(TargetSpecError::MissingKey("max_path_len"))?,
+            max_handle_count: max_handle_count
+                .ok_or(TargetSpecError::MissingKey("max_handle_count"))?,
+            special_consts,
+        })
+    }
+}
+
+impl core::str::FromStr for TargetSpec {
+    type Err = TargetSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_triple(s)
+    }
+}
@@ -0,0 +1,141 @@
+//! The textual form of IMT files, a Rust-like syntax for writing the interfaces of subsystems by hand.
+//!
+//! A source starts with `file <uuid>;`, which gives the id of the file, followed by its inner attributes, uses, and declarations.
+//! Sources are parsed by [`parse`](parse::parse), compiled into the files of a bundle by [`compile`], and reprinted in a canonical style
+//! by [`format`](mod@format). A source can `include "<path>";` another source, such as a prelude of common types, which [`compile`]
+//! resolves to a use of its module.
+//!
+//! Types and expressions are written as their [`Display`](core::fmt::Display) implementations format them, so the declarations of a
+//! source read like the types that tools print. Attributes are written by their registered name, with their payload in the syntax of
+//! its [`Schema`](crate::attr::schema::Schema). The lexer, parser, compiler, and formatter are only built with the `text` feature.
+//!
+//! # Grammar
+//!
+//! ```text
+//! source    = "file" UUID ";" inner-attr* item*
+//! inner-attr = "#![" attr "]" | "//!" doc-line
+//! outer-attr = "#[" attr "]" | "#[return:" attr "]" | "///" doc-line
+//! attr      = path ( "(" payload ")" | "{" field-payloads "}" )?
+//! item      = outer-attr* ( use | include | typedef | const | fn )
+//! use       = "use" path ";"
+//! include   = "include" STRING ";"
+//! typedef   = "type" name generics? "=" type ";"
+//!           | "opaque" "struct" name generics? ( ":" type )? ";"
+//!           | ( "struct" | "union" ) name generics? "{" ( field "," )* ( ".." type )? "}"
+//!           | "enum" name generics? ":" int-type "{" ( outer-attr* name ( "=" expr )? "," )* "}"
+//! generics  = "<" ( "const"? PARAM "," )* ">"
+//! const     = "const" name ":" type "=" expr ";"
+//! fn        = "fn" name "(" ( outer-attr* ( name ":" )? type "," )* ")" ( "->" type )? ";"
+//! type      = builtin | path ( "<" type,* ">" )? | PARAM ( "=" type )? | "!" | "*" ( "const" | "mut" | "special(" UUID ")" ) type
+//!           | "[" type ";" expr "]" | "{" expr "}" | "fn" "(" .. ")" ( "->" type )? | "char(" int-type ")" | "uninit(" type ")"
+//! ```
+//!
+//! Trailing commas are optional. Tokens are classified by [`lex::tokenize`], which editors can use to highlight sources: `PARAM` is
+//! a generic parameter such as `$0`, which are numbered in order, and `STRING` is a string literal in the syntax of Rust. `//`
+//! comments can appear between any tokens, and are kept by [`parse::parse`] as [`Comment`]s, so that [`format`](mod@format) can
+//! reprint them.
+
+use crate::file::File;
+
+#[cfg(feature = "text")]
+pub mod compile;
+#[cfg(feature = "text")]
+pub mod format;
+#[cfg(feature = "text")]
+pub mod lex;
+#[cfg(feature = "text")]
+pub mod parse;
+
+/// A parsed source. Its comments have no meaning in the file, but are kept so that the source can be reprinted with them
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub file: File,
+    pub comments: Vec<Comment>,
+    pub includes: Vec<Include>,
+    /// The span of the name of each type and value of the file, in the order they are declared
+    pub declarations: Vec<(ItemRef, Span)>,
+}
+
+impl SourceFile {
+    /// The span of the name of `item` where it is declared
+    pub fn declaration(&self, item: ItemRef) -> Option<Span> {
+        self.declarations
+            .iter()
+            .find(|(decl, _)| *decl == item)
+            .map(|(_, span)| *span)
+    }
+}
+
+/// An `include "<path>";` of a source, which uses the module of the source at `path`, relative to the directory of the including source.
+///
+/// The [`UseItem`](crate::file::UseItem) at `use_index` holds the attributes of the include, and has an empty path until the source is
+/// compiled with [`compile::compile_source`]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Include {
+    pub use_index: usize,
+    pub path: String,
+    /// The span of the string literal giving `path`
+    pub span: Span,
+}
+
+/// A `//` comment of a source, which is printed on its own line before the declaration at its anchor
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Comment {
+    pub anchor: Anchor,
+    /// The text of the comment, starting with `//`
+    pub text: String,
+}
+
+/// The position of a [`Comment`] in a source. Comments are anchored to the declaration that follows them
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Anchor {
+    /// The `file` declaration at the start of the source
+    File,
+    /// The use with the given index
+    Use(usize),
+    Item(ItemRef),
+    /// The field, variant, or parameter of the item with the given index. The padding of a struct or union follows its last field
+    Member(ItemRef, usize),
+    /// The closing `}` or `)` of the body of the item
+    EndOfItem(ItemRef),
+    /// The end of the source
+    End,
+}
+
+/// A type or value of a [`File`], by its index
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ItemRef {
+    Type(usize),
+    Value(usize),
+}
+
+/// A range of bytes of a source text
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The text of `src` that the span covers
+    pub fn text(self, src: &str) -> &str {
+        &src[self.start..self.end]
+    }
+
+    /// The smallest span that covers both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// The line and column (both counted from 1, with the column in characters) of the start of the span in `src`
+    pub fn line_col(self, src: &str) -> (usize, usize) {
+        let before = &src[..self.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+}
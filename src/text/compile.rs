@@ -0,0 +1,193 @@
+use std::path::{Component, PathBuf};
+
+use crate::{
+    bundle::{Bundle, Path, dir_members},
+    file::File,
+    text::{
+        SourceFile,
+        parse::{ParseError, parse},
+    },
+};
+
+/// The extension of textual sources, which [`compile_dir`] compiles
+pub const SOURCE_EXTENSION: &str = "imts";
+
+#[derive(Debug)]
+pub enum CompileError {
+    /// A source or directory could not be read
+    Io(PathBuf, std::io::Error),
+    /// A source is not valid, or includes a source that does not exist. The line and column are those of the start of the span of the error
+    Parse {
+        path: PathBuf,
+        line: usize,
+        col: usize,
+        error: ParseError,
+    },
+}
+
+impl core::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileError::Io(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            CompileError::Parse {
+                path,
+                line,
+                col,
+                error,
+            } => f.write_fmt(format_args!("{}:{line}:{col}: {error}", path.display())),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompileError::Io(_, e) => Some(e),
+            CompileError::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Removes the `.` and `..` components of `path`, without looking at the filesystem
+fn normalize(path: &std::path::Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(out.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                out.pop();
+            }
+            component => out.push(component),
+        }
+    }
+    out
+}
+
+/// The module that the source at `fs_path` in the directory tree `root` is compiled to, under `prefix`, in the layout read by
+/// [`compile_dir`]. This is `None` if `fs_path` is not a source within `root`
+pub fn module_path(
+    prefix: &Path,
+    root: &std::path::Path,
+    fs_path: &std::path::Path,
+) -> Option<Path> {
+    let rel = normalize(fs_path);
+    let rel = rel.strip_prefix(normalize(root)).ok()?;
+    let mut path = prefix.clone();
+    let mut components = rel.components().peekable();
+    while let Some(component) = components.next() {
+        let Component::Normal(name) = component else {
+            return None;
+        };
+        let name = name.to_str()?;
+        match components.peek() {
+            Some(_) => path = path.join(name),
+            None => {
+                let name = name.strip_suffix(SOURCE_EXTENSION)?.strip_suffix('.')?;
+                path = path.join(name);
+            }
+        }
+    }
+    (path.0.len() > prefix.0.len()).then_some(path)
+}
+
+/// Compiles the source `src`, read from `fs_path` in the directory tree `root`, into the file it declares.
+///
+/// Each `include` of the source is resolved by [`resolve_includes`]. The included source is not compiled, so it must be compiled into
+/// the same bundle under `prefix` for the use to resolve.
+pub fn compile_source(
+    prefix: &Path,
+    root: &std::path::Path,
+    fs_path: &std::path::Path,
+    src: &str,
+) -> Result<File, CompileError> {
+    let err = |error: ParseError| {
+        let (line, col) = error.span.line_col(src);
+        CompileError::Parse {
+            path: fs_path.to_path_buf(),
+            line,
+            col,
+            error,
+        }
+    };
+
+    let mut source = parse(src).map_err(err)?;
+    resolve_includes(&mut source, prefix, root, fs_path).map_err(err)?;
+    Ok(source.file)
+}
+
+/// Resolves each `include` of `source`, read from `fs_path` in the directory tree `root`, to a use of the [module](module_path()) of
+/// the included source, which must be a source within `root` other than the source itself
+pub fn resolve_includes(
+    source: &mut SourceFile,
+    prefix: &Path,
+    root: &std::path::Path,
+    fs_path: &std::path::Path,
+) -> Result<(), ParseError> {
+    let dir = fs_path.parent().unwrap_or(std::path::Path::new(""));
+    for include in &source.includes {
+        let included = dir.join(&include.path);
+        let Some(module) = module_path(prefix, root, &included) else {
+            return Err(ParseError::new(
+                include.span,
+                format!(
+                    "included `{}` is not a .{SOURCE_EXTENSION} source within {}",
+                    include.path,
+                    root.display()
+                ),
+            ));
+        };
+        if normalize(&included) == normalize(fs_path) {
+            return Err(ParseError::new(
+                include.span,
+                "a source cannot include itself",
+            ));
+        }
+        if !included.is_file() {
+            return Err(ParseError::new(
+                include.span,
+                format!("included source `{}` does not exist", include.path),
+            ));
+        }
+        source.file.uses[include.use_index].path = module.0;
+    }
+    Ok(())
+}
+
+/// Reads the source at `fs_path` in the directory tree `root`, and compiles it with [`compile_source`]
+pub fn compile_file(
+    prefix: &Path,
+    root: &std::path::Path,
+    fs_path: &std::path::Path,
+) -> Result<File, CompileError> {
+    let src =
+        std::fs::read_to_string(fs_path).map_err(|e| CompileError::Io(fs_path.to_path_buf(), e))?;
+    compile_source(prefix, root, fs_path, &src)
+}
+
+/// The `.imts` sources in the directory tree `dir`, with the module under `prefix` that each one is compiled to, in order of their paths
+pub fn source_members(
+    prefix: Path,
+    dir: &std::path::Path,
+) -> std::io::Result<Vec<(Path, PathBuf)>> {
+    let mut members = Vec::new();
+    dir_members(prefix, dir, SOURCE_EXTENSION, &mut members)?;
+    Ok(members)
+}
+
+/// Compiles each `.imts` source in the directory tree `dir` into the module at its path relative to `dir`, under `prefix`.
+/// Sources can include each other, as every source of `dir` is added to the bundle
+pub fn compile_dir(
+    bundle: &mut Bundle,
+    prefix: Path,
+    dir: &std::path::Path,
+) -> Result<(), CompileError> {
+    let members =
+        source_members(prefix.clone(), dir).map_err(|e| CompileError::Io(dir.to_path_buf(), e))?;
+    for (path, fs_path) in members {
+        let file = compile_file(&prefix, dir, &fs_path)?;
+        bundle.add_file(path, file);
+    }
+    Ok(())
+}
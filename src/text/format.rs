@@ -0,0 +1,116 @@
+use crate::{
+    attr::{Attribute, AttributeTarget, types::ItemDoc},
+    diff::attribute_name,
+    file::File,
+    intern::Symbol,
+    print::render_source,
+    text::parse::{ParseError, parse},
+    visit::{AttributeVisitorMut, visit_file_mut},
+};
+
+/// The width that [`canonicalize`] wraps lines of documentation to, not counting the indentation and `///` before them
+pub const DOC_WIDTH: usize = 80;
+
+/// Formats the source `src` in the canonical style, by parsing it, [canonicalizing](canonicalize) its file, and printing it with
+/// [`render_source`]. Formatting a formatted source does not change it
+pub fn format_source(src: &str) -> Result<String, ParseError> {
+    let mut source = parse(src)?;
+    canonicalize(&mut source.file);
+    Ok(render_source(&source))
+}
+
+/// Puts the attributes of each item of `file` in the canonical order: its documentation, then its other attributes sorted by name,
+/// keeping attributes of the same name in their order. Documentation split by other attributes is joined, and lines of documentation
+/// longer than [`DOC_WIDTH`] are wrapped at spaces, except within code blocks, headings, and tables
+pub fn canonicalize(file: &mut File) {
+    visit_file_mut(&mut Canonicalize, file);
+}
+
+struct Canonicalize;
+
+impl AttributeVisitorMut for Canonicalize {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>) {
+        let mut doc_lines = Vec::new();
+        let mut first_doc = true;
+        attrs.retain(|attr| match attr.downcast::<ItemDoc>() {
+            Some(doc) => {
+                doc_lines.extend(doc.doc_lines.iter().cloned());
+                // The first is kept (with its flags) to hold the joined documentation
+                core::mem::replace(&mut first_doc, false)
+            }
+            None => true,
+        });
+        if let Some(doc) = attrs
+            .iter_mut()
+            .find_map(Attribute::downcast_mut::<ItemDoc>)
+        {
+            doc.doc_lines = wrap_doc(doc_lines);
+        }
+
+        attrs.sort_by_cached_key(|attr| {
+            (
+                attr.downcast::<ItemDoc>().is_none(),
+                attribute_name(*attr.id()),
+            )
+        });
+    }
+}
+
+fn wrap_doc(lines: Vec<Symbol>) -> Vec<Symbol> {
+    let mut wrapped = Vec::with_capacity(lines.len());
+    let mut in_code = false;
+    for line in lines {
+        let text = line.trim_start();
+        let fence = text.starts_with("```");
+        in_code ^= fence;
+        if in_code
+            || fence
+            || text.starts_with(['#', '|', '>'])
+            || line.chars().count() <= DOC_WIDTH
+        {
+            wrapped.push(line);
+            continue;
+        }
+        wrap_line(&line, &mut wrapped);
+    }
+    wrapped
+}
+
+/// Splits `line` at spaces into lines of at most [`DOC_WIDTH`] characters, where its words allow. The lines after the first are indented
+/// to the text of `line`, after its list marker if it is the item of a list, so that they continue it
+fn wrap_line(line: &str, wrapped: &mut Vec<Symbol>) {
+    let text = line.trim_start();
+    let indent = line.len() - text.len();
+    let marker = if ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| text.starts_with(marker))
+    {
+        2
+    } else {
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits != 0 && text[digits..].starts_with(". ") {
+            digits + 2
+        } else {
+            0
+        }
+    };
+    let continuation = " ".repeat(indent + marker);
+
+    let mut current = line[..indent].to_string();
+    let mut empty = true;
+    for word in text.split(' ').filter(|word| !word.is_empty()) {
+        if !empty && current.chars().count() + 1 + word.chars().count() > DOC_WIDTH {
+            wrapped.push(Symbol::from(core::mem::replace(
+                &mut current,
+                continuation.clone(),
+            )));
+            empty = true;
+        }
+        if !empty {
+            current.push(' ');
+        }
+        current.push_str(word);
+        empty = false;
+    }
+    wrapped.push(current.into());
+}
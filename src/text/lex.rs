@@ -0,0 +1,190 @@
+use crate::text::Span;
+
+/// The class of a [`Token`], which editors and documentation generators highlight it by
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A keyword, such as `struct`, `fn`, or `use`
+    Keyword,
+    /// A builtin type, such as `u32`, `void`, or `byte`
+    BuiltinType,
+    /// The name of an item, field, parameter, or attribute, or a component of a path
+    Ident,
+    /// A generic parameter, such as `$0`
+    Param,
+    /// An integer literal, possibly with a type suffix, such as `16` or `7u32`
+    Int,
+    /// A version, such as `1.0`
+    Version,
+    /// A UUID in its hyphenated form
+    Uuid,
+    /// A string literal, in the syntax of Rust string literals
+    Str,
+    /// A punctuation character, or one of `::`, `->`, and `..`
+    Punct,
+    /// A `//` comment
+    Comment,
+    /// A `///` doc comment, which documents the item after it
+    DocComment,
+    /// A `//!` doc comment, which documents the file
+    InnerDocComment,
+    /// A character that cannot start a token, or a string literal that is not terminated
+    Unknown,
+}
+
+/// A classified token of a source text. Whitespace between tokens is not included
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+pub const KEYWORDS: &[&str] = &[
+    "file", "use", "include", "struct", "union", "enum", "type", "const", "fn", "opaque", "mut",
+    "special", "uuid", "char", "uninit", "return", "true", "false", "Some", "None",
+];
+
+pub const BUILTIN_TYPES: &[&str] = &[
+    "void", "byte", "u8", "u16", "u32", "u64", "u128", "ulong", "i8", "i16", "i32", "i64", "i128",
+    "ilong",
+];
+
+const PUNCTS: &[&str] = &["::", "->", ".."];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The length of the UUID at the start of `s`, if it starts with one that is not followed by more of an identifier
+fn uuid_len(s: &str) -> Option<usize> {
+    let candidate = s.get(..36)?;
+    let well_formed = candidate.char_indices().all(|(n, c)| match n {
+        8 | 13 | 18 | 23 => c == '-',
+        _ => c.is_ascii_hexdigit(),
+    });
+    let ends = !s[36..].starts_with(is_ident_continue);
+    (well_formed && ends).then_some(36)
+}
+
+/// Splits `src` into classified tokens, skipping whitespace.
+///
+/// Tokenizing never fails: characters that cannot start a token are [`TokenKind::Unknown`] tokens, so that editors can highlight
+/// partially written sources. Comments are included, so the tokens cover every non-whitespace character of `src`
+pub fn tokenize(src: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = src[pos..].chars().next() {
+        let rest = &src[pos..];
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+
+        let (kind, len) = if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            let kind = if rest.starts_with("///") && !rest.starts_with("////") {
+                TokenKind::DocComment
+            } else if rest.starts_with("//!") {
+                TokenKind::InnerDocComment
+            } else {
+                TokenKind::Comment
+            };
+            (kind, len)
+        } else if let Some(len) = uuid_len(rest) {
+            (TokenKind::Uuid, len)
+        } else if is_ident_start(c) {
+            let len = rest.find(|c| !is_ident_continue(c)).unwrap_or(rest.len());
+            let word = &rest[..len];
+            let kind = if KEYWORDS.contains(&word) {
+                TokenKind::Keyword
+            } else if BUILTIN_TYPES.contains(&word) {
+                TokenKind::BuiltinType
+            } else {
+                TokenKind::Ident
+            };
+            (kind, len)
+        } else if c.is_ascii_digit() {
+            let len = rest.find(|c| !is_ident_continue(c)).unwrap_or(rest.len());
+            let minor = rest[len..]
+                .strip_prefix('.')
+                .filter(|minor| minor.starts_with(|c: char| c.is_ascii_digit()))
+                .map(|minor| {
+                    minor
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(minor.len())
+                });
+            match minor {
+                Some(minor) => (TokenKind::Version, len + 1 + minor),
+                None => (TokenKind::Int, len),
+            }
+        } else if c == '$' && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            let len = rest[1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(rest.len(), |n| n + 1);
+            (TokenKind::Param, len)
+        } else if c == '"' {
+            string_len(rest)
+        } else if let Some(punct) = PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+            (TokenKind::Punct, punct.len())
+        } else if "{}()[]<>;:,=*&|^+-/!#".contains(c) {
+            (TokenKind::Punct, 1)
+        } else {
+            (TokenKind::Unknown, c.len_utf8())
+        };
+
+        tokens.push(Token {
+            kind,
+            span: Span::new(pos, pos + len),
+        });
+        pos += len;
+    }
+    tokens
+}
+
+/// The kind and length of the string literal at the start of `s`, which is [`TokenKind::Unknown`] up to the end of the line if it is not terminated
+fn string_len(s: &str) -> (TokenKind, usize) {
+    let mut escaped = false;
+    for (n, c) in s.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => return (TokenKind::Str, n + 1),
+            '\n' => return (TokenKind::Unknown, n),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    (TokenKind::Unknown, s.len())
+}
+
+/// The value of the string literal `lit` (including its quotes), or `None` if it has an invalid escape.
+/// This accepts the escapes that [`Debug`] writes for a [`str`], so that it reverses rendering strings with `{:?}`
+pub fn unescape(lit: &str) -> Option<String> {
+    let body = lit.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let (hex, rest) = rest.split_once('}')?;
+                chars = rest.chars();
+                char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+            }
+            _ => return None,
+        });
+    }
+    Some(out)
+}
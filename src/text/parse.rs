@@ -0,0 +1,1233 @@
+use bincode::Encode;
+
+use crate::{
+    attr::{
+        Attribute, AttributeInfo, AttributeTarget, registered_attributes, schema::Schema,
+        types::ItemDoc,
+    },
+    config::format_config,
+    file::{File, UseItem},
+    header::{Header, Version},
+    intern::Symbol,
+    text::{
+        Anchor, Comment, Include, ItemRef, SourceFile, Span,
+        lex::{Token, TokenKind, tokenize, unescape},
+    },
+    tydef::{
+        EnumBuilder, Field, Struct, StructBody, StructFields, TypeAlias, TypeDef, TypeDefBody,
+        Union,
+    },
+    uses::{
+        ArrayType, BinaryOp, Expr, IntBits, IntType, Param, PointerKind, Signature, SpecialConst,
+        Type, UnaryOp,
+    },
+    uuid::Uuid,
+    value::{Const, Function, Value, ValueBody},
+};
+
+/// An error in a source, at the span of the tokens it is about
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses the source `src` into the file it declares, in the current format.
+///
+/// Attributes are looked up by their full name (such as `types::Align`) or the last component of it (`Align`), and their payload
+/// is written as [`render_payload`](crate::attr::schema::render_payload) renders it. In a struct payload, fields of an option or list type may be
+/// omitted, and are `None` or empty. Integer literals need a type suffix (such as `4u32`) unless their type is known from where
+/// they are written: the type of a const, the underlying type of an enum, or `u64` for the length of an array
+pub fn parse(src: &str) -> Result<SourceFile, ParseError> {
+    Parser::new(src)?.source()
+}
+
+/// An attribute that has been parsed, but not yet created for the kind of item it is attached to
+enum RawAttr {
+    Doc(Vec<Symbol>),
+    Payload {
+        span: Span,
+        info: &'static AttributeInfo,
+        bytes: Vec<u8>,
+    },
+}
+
+/// The attributes before a declaration or member, including the `#[return: ..]` attributes of a function
+#[derive(Default)]
+struct OuterAttrs {
+    attrs: Vec<RawAttr>,
+    ret: Vec<RawAttr>,
+    ret_span: Option<Span>,
+}
+
+impl OuterAttrs {
+    /// The attributes, failing if there are `return` attributes, which are only allowed on functions
+    fn without_ret(self) -> Result<Vec<RawAttr>, ParseError> {
+        match self.ret_span {
+            Some(span) => Err(ParseError::new(
+                span,
+                "`return` attributes are only allowed on functions",
+            )),
+            None => Ok(self.attrs),
+        }
+    }
+}
+
+fn lower_attrs<Targ: AttributeTarget>(
+    attrs: Vec<RawAttr>,
+) -> Result<Vec<Attribute<Targ>>, ParseError> {
+    attrs
+        .into_iter()
+        .map(|attr| match attr {
+            RawAttr::Doc(doc_lines) => Ok(Attribute::new(ItemDoc { doc_lines })),
+            RawAttr::Payload { span, info, bytes } => {
+                match Attribute::from_payload(info.id, &bytes) {
+                    Some(Ok(attr)) => Ok(attr),
+                    Some(Err(e)) => Err(ParseError::new(
+                        span,
+                        format!("invalid payload for attribute `{}`: {e}", info.name),
+                    )),
+                    None => Err(ParseError::new(
+                        span,
+                        format!(
+                            "attribute `{}` cannot be attached to a {:?}",
+                            info.name,
+                            Targ::KIND
+                        ),
+                    )),
+                }
+            }
+        })
+        .collect()
+}
+
+/// The registered attribute named `name`, or whose name ends with `::name`
+fn find_attribute_info(name: &str) -> Option<&'static AttributeInfo> {
+    registered_attributes().iter().find(|info| {
+        info.name == name
+            || info
+                .name
+                .strip_suffix(name)
+                .is_some_and(|prefix| prefix.ends_with("::"))
+    })
+}
+
+fn int_type_named(name: &str) -> Option<IntType> {
+    Some(match name {
+        "u8" => IntType::u8,
+        "u16" => IntType::u16,
+        "u32" => IntType::u32,
+        "u64" => IntType::u64,
+        "u128" => IntType::u128,
+        "ulong" => IntType::ulong,
+        "i8" => IntType::i8,
+        "i16" => IntType::i16,
+        "i32" => IntType::i32,
+        "i64" => IntType::i64,
+        "i128" => IntType::i128,
+        "ilong" => IntType::ilong,
+        _ => return None,
+    })
+}
+
+const INT_SUFFIXES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "ulong", "i8", "i16", "i32", "i64", "i128", "ilong",
+];
+
+/// The value of the digits of an integer literal, which are decimal, or hexadecimal, octal, or binary with a `0x`, `0o`, or `0b` prefix.
+/// Digits may be separated by `_`
+fn int_digits_value(digits: &str) -> Option<u128> {
+    let digits = digits.replace('_', "");
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, &*digits),
+    };
+    if digits.is_empty() || digits.starts_with('+') {
+        return None;
+    }
+    u128::from_str_radix(digits, radix).ok()
+}
+
+const BINARY_OPS: &[(&str, BinaryOp, u8)] = &[
+    ("|", BinaryOp::Or, 0),
+    ("^", BinaryOp::Xor, 1),
+    ("&", BinaryOp::And, 2),
+    ("<<", BinaryOp::ShiftLeft, 3),
+    (">>", BinaryOp::ShiftRight, 3),
+    ("+", BinaryOp::Add, 4),
+    ("-", BinaryOp::Sub, 4),
+    ("*", BinaryOp::Mul, 5),
+    ("/", BinaryOp::Div, 5),
+];
+
+const BUILTIN_SPECIAL_CONSTS: &[SpecialConst] = &[
+    SpecialConst::SizeofPointer,
+    SpecialConst::SizeofLong,
+    SpecialConst::PageSize,
+    SpecialConst::MaxPathLen,
+    SpecialConst::MaxHandleCount,
+];
+
+fn put(out: &mut Vec<u8>, val: &impl Encode) {
+    // Encoding into a `Vec` does not fail
+    let _ = bincode::encode_into_std_write(val, out, format_config());
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    /// The tokens of the source, without its `//` comments
+    tokens: Vec<Token>,
+    pos: usize,
+    comments: Vec<Token>,
+    /// The first comment that has not been anchored
+    next_comment: usize,
+    anchored: Vec<Comment>,
+    includes: Vec<Include>,
+    declarations: Vec<(ItemRef, Span)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Result<Self, ParseError> {
+        let mut tokens = Vec::new();
+        let mut comments = Vec::new();
+        for token in tokenize(src) {
+            match token.kind {
+                TokenKind::Comment => comments.push(token),
+                TokenKind::Unknown if token.span.text(src).starts_with('"') => {
+                    return Err(ParseError::new(token.span, "unterminated string literal"));
+                }
+                TokenKind::Unknown => {
+                    return Err(ParseError::new(
+                        token.span,
+                        format!("unexpected character `{}`", token.span.text(src)),
+                    ));
+                }
+                _ => tokens.push(token),
+            }
+        }
+        Ok(Self {
+            src,
+            tokens,
+            pos: 0,
+            comments,
+            next_comment: 0,
+            anchored: Vec::new(),
+            includes: Vec::new(),
+            declarations: Vec::new(),
+        })
+    }
+
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn text(&self, token: Token) -> &'a str {
+        token.span.text(self.src)
+    }
+
+    /// Whether the token `n` tokens ahead is `text`
+    fn nth_is(&self, n: usize, text: &str) -> bool {
+        self.tokens
+            .get(self.pos + n)
+            .is_some_and(|token| token.kind != TokenKind::Str && self.text(*token) == text)
+    }
+
+    fn is(&self, text: &str) -> bool {
+        self.nth_is(0, text)
+    }
+
+    fn is_kind(&self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|token| token.kind == kind)
+    }
+
+    fn bump(&mut self) -> Token {
+        let token = self.tokens[self.pos];
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let is = self.is(text);
+        if is {
+            self.pos += 1;
+        }
+        is
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Span, ParseError> {
+        if self.is(text) {
+            Ok(self.bump().span)
+        } else {
+            Err(self.expected(format_args!("`{text}`")))
+        }
+    }
+
+    /// An error for the next token, which is not `what` was expected
+    fn expected(&self, what: impl core::fmt::Display) -> ParseError {
+        match self.peek() {
+            Some(token) => ParseError::new(
+                token.span,
+                format!("expected {what}, found `{}`", self.text(token)),
+            ),
+            None => ParseError::new(
+                Span::new(self.src.len(), self.src.len()),
+                format!("expected {what}, found the end of the source"),
+            ),
+        }
+    }
+
+    /// The span of the last token consumed
+    fn prev_span(&self) -> Span {
+        self.tokens[self.pos - 1].span
+    }
+
+    /// Anchors the comments before the next token to `anchor`
+    fn anchor(&mut self, anchor: Anchor) {
+        let end = self.peek().map_or(usize::MAX, |token| token.span.start);
+        while let Some(comment) = self
+            .comments
+            .get(self.next_comment)
+            .filter(|comment| comment.span.start < end)
+        {
+            self.anchored.push(Comment {
+                anchor,
+                text: comment.span.text(self.src).trim_end().to_string(),
+            });
+            self.next_comment += 1;
+        }
+    }
+
+    /// A name, which may be a keyword or builtin type
+    fn name(&mut self, what: &str) -> Result<(String, Span), ParseError> {
+        match self.peek() {
+            Some(
+                token @ Token {
+                    kind: TokenKind::Ident | TokenKind::Keyword | TokenKind::BuiltinType,
+                    ..
+                },
+            ) => {
+                self.pos += 1;
+                Ok((self.text(token).to_string(), token.span))
+            }
+            _ => Err(self.expected(what)),
+        }
+    }
+
+    /// A path of names separated by `::`
+    fn path(&mut self, what: &str) -> Result<(Vec<String>, Span), ParseError> {
+        let (first, start) = self.name(what)?;
+        let mut path = vec![first];
+        while self.eat("::") {
+            path.push(self.name("a name")?.0);
+        }
+        Ok((path, start.to(self.prev_span())))
+    }
+
+    fn uuid(&mut self) -> Result<Uuid, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Uuid => {
+                self.pos += 1;
+                Uuid::try_parse(self.text(token))
+                    .ok_or_else(|| ParseError::new(token.span, "invalid UUID"))
+            }
+            _ => Err(self.expected("a UUID")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Str => {
+                self.pos += 1;
+                unescape(self.text(token))
+                    .ok_or_else(|| ParseError::new(token.span, "invalid escape in string literal"))
+            }
+            _ => Err(self.expected("a string literal")),
+        }
+    }
+
+    fn version(&mut self) -> Result<Version, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Version => {
+                self.pos += 1;
+                Version::try_parse(self.text(token))
+                    .ok_or_else(|| ParseError::new(token.span, "version out of range"))
+            }
+            _ => Err(self.expected("a version, such as `1.0`")),
+        }
+    }
+
+    /// The index of the generic parameter `$n`
+    fn param(&mut self) -> Result<u32, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Param => {
+                self.pos += 1;
+                self.text(token)[1..]
+                    .parse()
+                    .map_err(|_| ParseError::new(token.span, "generic parameter out of range"))
+            }
+            _ => Err(self.expected("a generic parameter, such as `$0`")),
+        }
+    }
+
+    fn int_type(&mut self) -> Result<IntType, ParseError> {
+        match self.peek() {
+            Some(token) if let Some(ty) = int_type_named(self.text(token)) => {
+                self.pos += 1;
+                Ok(ty)
+            }
+            _ => Err(self.expected("an integer type")),
+        }
+    }
+
+    fn source(mut self) -> Result<SourceFile, ParseError> {
+        self.anchor(Anchor::File);
+        if !self.eat("file") {
+            return Err(self.expected("`file <uuid>;` at the start of the source"));
+        }
+        let file_id = self.uuid()?;
+        self.expect(";")?;
+
+        let mut attrs = Vec::new();
+        loop {
+            if self.is_kind(TokenKind::InnerDocComment) {
+                attrs.push(self.doc_comment(TokenKind::InnerDocComment, "//!"));
+            } else if self.is("#") && self.nth_is(1, "!") {
+                self.pos += 2;
+                self.expect("[")?;
+                attrs.push(self.attr_body()?);
+                self.expect("]")?;
+            } else {
+                break;
+            }
+        }
+
+        let mut file = File {
+            header: Header::CURRENT,
+            file_id,
+            attributes: lower_attrs(attrs)?,
+            uses: Vec::new(),
+            types: Vec::new(),
+            values: Vec::new(),
+        };
+        while self.peek().is_some() {
+            self.item(&mut file)?;
+        }
+        self.anchor(Anchor::End);
+
+        Ok(SourceFile {
+            file,
+            comments: self.anchored,
+            includes: self.includes,
+            declarations: self.declarations,
+        })
+    }
+
+    /// Consecutive doc comments starting with `prefix`, as an [`ItemDoc`]
+    fn doc_comment(&mut self, kind: TokenKind, prefix: &str) -> RawAttr {
+        let mut doc_lines = Vec::new();
+        while self.is_kind(kind) {
+            let token = self.bump();
+            let line = self.text(token);
+            let line = line[prefix.len()..].trim_end_matches('\r');
+            doc_lines.push(Symbol::from(line.strip_prefix(' ').unwrap_or(line)));
+        }
+        RawAttr::Doc(doc_lines)
+    }
+
+    fn outer_attrs(&mut self) -> Result<OuterAttrs, ParseError> {
+        let mut attrs = OuterAttrs::default();
+        loop {
+            if self.is_kind(TokenKind::DocComment) {
+                attrs
+                    .attrs
+                    .push(self.doc_comment(TokenKind::DocComment, "///"));
+            } else if self.is_kind(TokenKind::InnerDocComment)
+                || (self.is("#") && self.nth_is(1, "!"))
+            {
+                return Err(ParseError::new(
+                    self.bump().span,
+                    "inner attributes and doc comments must come before the declarations of the file",
+                ));
+            } else if self.is("#") {
+                let start = self.bump().span;
+                self.expect("[")?;
+                if self.is("return") && self.nth_is(1, ":") {
+                    self.pos += 2;
+                    attrs.ret.push(self.attr_body()?);
+                    attrs.ret_span.get_or_insert(start);
+                } else {
+                    attrs.attrs.push(self.attr_body()?);
+                }
+                self.expect("]")?;
+            } else {
+                return Ok(attrs);
+            }
+        }
+    }
+
+    /// The name and payload of an attribute, within its `#[..]`
+    fn attr_body(&mut self) -> Result<RawAttr, ParseError> {
+        let (name, span) = self.path("an attribute name")?;
+        let name = name.join("::");
+        let info = find_attribute_info(&name)
+            .ok_or_else(|| ParseError::new(span, format!("unknown attribute `{name}`")))?;
+
+        let mut bytes = Vec::new();
+        match info.schema {
+            Schema::Struct(fields) if self.is("{") => self.struct_payload(fields, &mut bytes)?,
+            schema if self.eat("(") => {
+                self.payload(&schema, &mut bytes)?;
+                self.expect(")")?;
+            }
+            schema => {
+                if default_payload(&schema, &mut bytes).is_none() {
+                    return Err(ParseError::new(
+                        span,
+                        format!(
+                            "attribute `{}` requires a payload of the form {schema}",
+                            info.name
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(RawAttr::Payload {
+            span: span.to(self.prev_span()),
+            info,
+            bytes,
+        })
+    }
+
+    /// Encodes the payload described by `schema` into `out`, as [`render_payload`](crate::attr::schema::render_payload) renders it
+    fn payload(&mut self, schema: &Schema, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        match *schema {
+            Schema::Unit => {
+                self.expect("(")?;
+                self.expect(")")?;
+            }
+            Schema::Bool if self.eat("true") => put(out, &true),
+            Schema::Bool if self.eat("false") => put(out, &false),
+            Schema::Bool => return Err(self.expected("`true` or `false`")),
+            Schema::UInt(bits) | Schema::Int(bits) => {
+                let start = self.peek().map(|token| token.span);
+                let negative = self.eat("-");
+                let magnitude = match self.peek() {
+                    Some(token) if token.kind == TokenKind::Int => {
+                        self.pos += 1;
+                        int_digits_value(self.text(token))
+                            .ok_or_else(|| ParseError::new(token.span, "invalid integer"))?
+                    }
+                    _ => return Err(self.expected("an integer")),
+                };
+                let span = start.unwrap_or_default().to(self.prev_span());
+                let unsigned = (!negative || magnitude == 0).then_some(magnitude);
+                let signed = if negative {
+                    0i128.checked_sub_unsigned(magnitude)
+                } else {
+                    i128::try_from(magnitude).ok()
+                };
+                let encoded = match *schema {
+                    Schema::UInt(8) => unsigned
+                        .and_then(|v| u8::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::UInt(16) => unsigned
+                        .and_then(|v| u16::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::UInt(32) => unsigned
+                        .and_then(|v| u32::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::UInt(64) => unsigned
+                        .and_then(|v| u64::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::UInt(128) => unsigned.map(|v| put(out, &v)),
+                    Schema::Int(8) => signed
+                        .and_then(|v| i8::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::Int(16) => signed
+                        .and_then(|v| i16::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::Int(32) => signed
+                        .and_then(|v| i32::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::Int(64) => signed
+                        .and_then(|v| i64::try_from(v).ok())
+                        .map(|v| put(out, &v)),
+                    Schema::Int(128) => signed.map(|v| put(out, &v)),
+                    _ => {
+                        return Err(ParseError::new(
+                            span,
+                            format!("unsupported integer width {bits}"),
+                        ));
+                    }
+                };
+                if encoded.is_none() {
+                    return Err(ParseError::new(
+                        span,
+                        format!("integer out of range for {schema}"),
+                    ));
+                }
+            }
+            Schema::String => put(out, &self.string()?),
+            Schema::Uuid => put(out, &self.uuid()?),
+            Schema::Version => put(out, &self.version()?),
+            Schema::Type => put(out, &self.ty()?),
+            Schema::Option(_) if self.eat("None") => put(out, &false),
+            Schema::Option(inner) => {
+                if !self.eat("Some") {
+                    return Err(self.expected("`Some` or `None`"));
+                }
+                self.expect("(")?;
+                put(out, &true);
+                self.payload(inner, out)?;
+                self.expect(")")?;
+            }
+            Schema::List(inner) => {
+                self.expect("[")?;
+                let mut len = 0u64;
+                let mut items = Vec::new();
+                while !self.eat("]") {
+                    self.payload(inner, &mut items)?;
+                    len += 1;
+                    if !self.eat(",") {
+                        self.expect("]")?;
+                        break;
+                    }
+                }
+                put(out, &len);
+                out.extend(items);
+            }
+            Schema::Struct(fields) => self.struct_payload(fields, out)?,
+            Schema::Enum(variants) => {
+                let (name, span) = self.name("a variant")?;
+                let Some(idx) = variants.iter().position(|(variant, _)| *variant == name) else {
+                    return Err(ParseError::new(
+                        span,
+                        format!("unknown variant `{name}`, expected one of {schema}"),
+                    ));
+                };
+                put(out, &(idx as u32));
+                let (_, fields) = &variants[idx];
+                if *fields != Schema::Unit {
+                    self.expect("(")?;
+                    self.payload(fields, out)?;
+                    self.expect(")")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the fields of a struct payload, which may be written in any order, into `out`
+    fn struct_payload(
+        &mut self,
+        fields: &'static [(&'static str, Schema)],
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        self.expect("{")?;
+        let mut values = vec![None; fields.len()];
+        while !self.eat("}") {
+            let (name, span) = self.name("a field name")?;
+            let Some(idx) = fields.iter().position(|(field, _)| *field == name) else {
+                return Err(ParseError::new(
+                    span,
+                    format!(
+                        "unknown field `{name}`, expected one of {}",
+                        Schema::Struct(fields)
+                    ),
+                ));
+            };
+            if values[idx].is_some() {
+                return Err(ParseError::new(
+                    span,
+                    format!("field `{name}` is given twice"),
+                ));
+            }
+            self.expect(":")?;
+            let mut bytes = Vec::new();
+            self.payload(&fields[idx].1, &mut bytes)?;
+            values[idx] = Some(bytes);
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+
+        for ((name, schema), value) in fields.iter().zip(values) {
+            match value {
+                Some(bytes) => out.extend(bytes),
+                None if default_payload(schema, out).is_some() => {}
+                None => {
+                    return Err(ParseError::new(
+                        self.prev_span(),
+                        format!("missing field `{name}`"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn item(&mut self, file: &mut File) -> Result<(), ParseError> {
+        let attrs = self.outer_attrs()?;
+        let Some(token) = self.peek() else {
+            return Err(self.expected("a declaration"));
+        };
+        if let Some(span) = attrs.ret_span
+            && !self.is("fn")
+        {
+            return Err(ParseError::new(
+                span,
+                "`return` attributes are only allowed on functions",
+            ));
+        }
+
+        match self.text(token) {
+            "use" => {
+                self.anchor(Anchor::Use(file.uses.len()));
+                self.pos += 1;
+                let (path, _) = self.path("a module path")?;
+                self.expect(";")?;
+                file.uses.push(UseItem {
+                    attrs: lower_attrs(attrs.attrs)?,
+                    path,
+                });
+            }
+            "include" => {
+                self.anchor(Anchor::Use(file.uses.len()));
+                self.pos += 1;
+                let span = self.peek().map(|token| token.span).unwrap_or_default();
+                let path = self.string()?;
+                self.expect(";")?;
+                self.includes.push(Include {
+                    use_index: file.uses.len(),
+                    path,
+                    span,
+                });
+                // The path of the module is filled in when the source is compiled
+                file.uses.push(UseItem {
+                    attrs: lower_attrs(attrs.attrs)?,
+                    path: Vec::new(),
+                });
+            }
+            "type" | "struct" | "union" | "enum" | "opaque" => {
+                let item = ItemRef::Type(file.types.len());
+                self.anchor(Anchor::Item(item));
+                let def = self.typedef(attrs.attrs, item)?;
+                file.types.push(def);
+            }
+            "const" => {
+                let item = ItemRef::Value(file.values.len());
+                self.anchor(Anchor::Item(item));
+                self.pos += 1;
+                let (name, span) = self.name("a const name")?;
+                self.declarations.push((item, span));
+                self.expect(":")?;
+                let ty = self.ty()?;
+                self.expect("=")?;
+                let int = match ty {
+                    Type::Int(int) => Some(int),
+                    _ => None,
+                };
+                let val = self.expr(int)?;
+                self.expect(";")?;
+                file.values.push(Value {
+                    name: name.into(),
+                    body: ValueBody::Const(Const {
+                        attrs: lower_attrs(attrs.attrs)?,
+                        ty,
+                        val,
+                    }),
+                });
+            }
+            "fn" => {
+                let item = ItemRef::Value(file.values.len());
+                self.anchor(Anchor::Item(item));
+                self.pos += 1;
+                let (name, span) = self.name("a function name")?;
+                self.declarations.push((item, span));
+                let mut signature = self.signature(Some(item))?;
+                signature.ret_attrs = lower_attrs(attrs.ret)?;
+                self.expect(";")?;
+                file.values.push(Value {
+                    name: name.into(),
+                    body: ValueBody::Function(Function {
+                        attrs: lower_attrs(attrs.attrs)?,
+                        signature,
+                    }),
+                });
+            }
+            _ => return Err(self.expected("a declaration")),
+        }
+        Ok(())
+    }
+
+    fn typedef(&mut self, attrs: Vec<RawAttr>, item: ItemRef) -> Result<TypeDef, ParseError> {
+        let opaque = self.eat("opaque");
+        let keyword = if opaque {
+            self.expect("struct")?;
+            "struct"
+        } else {
+            let token = self.bump();
+            self.text(token)
+        };
+        let (name, span) = self.name("a type name")?;
+        self.declarations.push((item, span));
+
+        // Generic parameters are numbered in order. Whether they are constant parameters is found from their uses
+        let mut num_params = 0;
+        if self.eat("<") {
+            while !self.eat(">") {
+                self.eat("const");
+                let span = self.peek().map(|token| token.span);
+                if self.param()? != num_params {
+                    return Err(ParseError::new(
+                        span.unwrap_or_default(),
+                        format!(
+                            "expected generic parameter `${num_params}`, as parameters are numbered in order"
+                        ),
+                    ));
+                }
+                num_params += 1;
+                if !self.eat(",") {
+                    self.expect(">")?;
+                    break;
+                }
+            }
+        }
+
+        let body = match keyword {
+            "type" => {
+                self.expect("=")?;
+                let alias = self.ty()?;
+                self.expect(";")?;
+                TypeDefBody::Alias(TypeAlias {
+                    attrs: lower_attrs(attrs)?,
+                    alias,
+                })
+            }
+            "struct" => {
+                let body = if opaque {
+                    let repr = if self.eat(":") {
+                        Some(self.ty()?)
+                    } else {
+                        None
+                    };
+                    self.expect(";")?;
+                    StructBody::Opaque(repr)
+                } else {
+                    StructBody::Fields(self.fields(item)?)
+                };
+                TypeDefBody::Struct(Struct {
+                    attrs: lower_attrs(attrs)?,
+                    body,
+                })
+            }
+            "union" => TypeDefBody::Union(Union {
+                attrs: lower_attrs(attrs)?,
+                fields: self.fields(item)?,
+            }),
+            _ => {
+                self.expect(":")?;
+                let underlying = self.int_type()?;
+                let mut builder = EnumBuilder::new(underlying);
+                self.expect("{")?;
+                let mut variants = 0;
+                loop {
+                    if self.is("}") {
+                        self.anchor(Anchor::EndOfItem(item));
+                        self.pos += 1;
+                        break;
+                    }
+                    let attrs = self.outer_attrs()?.without_ret()?;
+                    self.anchor(Anchor::Member(item, variants));
+                    let (name, _) = self.name("a variant name")?;
+                    let discrim = if self.eat("=") {
+                        Some(self.expr(Some(underlying))?)
+                    } else {
+                        None
+                    };
+                    builder = builder.variant_with_attrs(lower_attrs(attrs)?, name, discrim);
+                    variants += 1;
+                    if !self.eat(",") {
+                        self.anchor(Anchor::EndOfItem(item));
+                        self.expect("}")?;
+                        break;
+                    }
+                }
+                let mut en = builder.build();
+                en.attrs = lower_attrs(attrs)?;
+                TypeDefBody::Enum(en)
+            }
+        };
+
+        Ok(TypeDef {
+            name: name.into(),
+            num_params,
+            body,
+        })
+    }
+
+    /// The fields of a struct or union, followed by its padding (if any) as `.. ty`, within `{}`
+    fn fields(&mut self, item: ItemRef) -> Result<StructFields, ParseError> {
+        self.expect("{")?;
+        let mut fields = StructFields {
+            field: Vec::new(),
+            pad: None,
+        };
+        loop {
+            if self.is("}") {
+                self.anchor(Anchor::EndOfItem(item));
+                self.pos += 1;
+                break;
+            }
+            let attrs = self.outer_attrs()?.without_ret()?;
+            self.anchor(Anchor::Member(item, fields.field.len()));
+            if self.is("..") {
+                if !attrs.is_empty() {
+                    return Err(self.expected("a field after attributes"));
+                }
+                self.pos += 1;
+                fields.pad = Some(self.ty()?);
+                self.eat(",");
+                self.anchor(Anchor::EndOfItem(item));
+                self.expect("}")?;
+                break;
+            }
+            let (name, _) = self.name("a field name")?;
+            self.expect(":")?;
+            let ty = self.ty()?;
+            fields.field.push(Field {
+                attrs: lower_attrs(attrs)?,
+                name: name.into(),
+                ty,
+            });
+            if !self.eat(",") {
+                self.anchor(Anchor::EndOfItem(item));
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(fields)
+    }
+
+    /// The parameters and return type of a function, or of a function pointer type if `item` is `None`.
+    /// The return type may be omitted, and is then `void`
+    fn signature(&mut self, item: Option<ItemRef>) -> Result<Signature, ParseError> {
+        self.expect("(")?;
+        let mut params = Vec::new();
+        loop {
+            if self.is(")") {
+                if let Some(item) = item {
+                    self.anchor(Anchor::EndOfItem(item));
+                }
+                self.pos += 1;
+                break;
+            }
+            let attrs = self.outer_attrs()?.without_ret()?;
+            if let Some(item) = item {
+                self.anchor(Anchor::Member(item, params.len()));
+            }
+            let name = if self.nth_is(1, ":") {
+                Some(self.name("a parameter name")?.0.into())
+            } else {
+                None
+            };
+            if name.is_some() {
+                self.pos += 1;
+            }
+            params.push(Param {
+                attrs: lower_attrs(attrs)?,
+                name,
+                ty: self.ty()?,
+            });
+            if !self.eat(",") {
+                if let Some(item) = item {
+                    self.anchor(Anchor::EndOfItem(item));
+                }
+                self.expect(")")?;
+                break;
+            }
+        }
+        let retty = if self.eat("->") {
+            self.ty()?
+        } else {
+            Type::Void
+        };
+        Ok(Signature {
+            params,
+            ret_attrs: Vec::new(),
+            retty: Box::new(retty),
+        })
+    }
+
+    fn ty(&mut self) -> Result<Type, ParseError> {
+        let Some(token) = self.peek() else {
+            return Err(self.expected("a type"));
+        };
+        let text = self.text(token);
+        match token.kind {
+            TokenKind::Param => {
+                let n = self.param()?;
+                let default = if self.eat("=") {
+                    Some(Box::new(self.ty()?))
+                } else {
+                    None
+                };
+                Ok(Type::Param(n, default))
+            }
+            TokenKind::BuiltinType => {
+                self.pos += 1;
+                Ok(match text {
+                    "void" => Type::Void,
+                    "byte" => Type::Byte,
+                    int => Type::Int(int_type_named(int).unwrap_or(IntType::u8)),
+                })
+            }
+            TokenKind::Punct if text == "*" => {
+                self.pos += 1;
+                let kind = if self.eat("const") {
+                    PointerKind::Const
+                } else if self.eat("mut") {
+                    PointerKind::Mut
+                } else if self.eat("special") {
+                    self.expect("(")?;
+                    let id = self.uuid()?;
+                    self.expect(")")?;
+                    PointerKind::Special(id)
+                } else {
+                    return Err(self.expected("`const`, `mut`, or `special`"));
+                };
+                Ok(Type::Pointer(kind, Box::new(self.ty()?)))
+            }
+            TokenKind::Punct if text == "!" => {
+                self.pos += 1;
+                Ok(Type::Never)
+            }
+            TokenKind::Punct if text == "[" => {
+                self.pos += 1;
+                let base = self.ty()?;
+                self.expect(";")?;
+                let len = self.expr(Some(IntType::u64))?;
+                self.expect("]")?;
+                Ok(Type::Array(Box::new(ArrayType { base, len })))
+            }
+            TokenKind::Punct if text == "{" => {
+                self.pos += 1;
+                let val = self.expr(None)?;
+                self.expect("}")?;
+                Ok(Type::Const(val))
+            }
+            TokenKind::Keyword if text == "fn" => {
+                self.pos += 1;
+                Ok(Type::Func(self.signature(None)?))
+            }
+            TokenKind::Keyword if text == "char" || text == "uninit" => {
+                self.pos += 1;
+                self.expect("(")?;
+                let ty = if text == "char" {
+                    Type::Char(self.int_type()?)
+                } else {
+                    Type::Uninit(Box::new(self.ty()?))
+                };
+                self.expect(")")?;
+                Ok(ty)
+            }
+            TokenKind::Ident => {
+                let (path, _) = self.path("a type")?;
+                let args = if self.eat("<") {
+                    let mut args = Vec::new();
+                    while !self.eat(">") {
+                        args.push(self.ty()?);
+                        if !self.eat(",") {
+                            self.expect(">")?;
+                            break;
+                        }
+                    }
+                    Some(args)
+                } else {
+                    None
+                };
+                Ok(Type::Named(path.join("::").into(), args))
+            }
+            _ => Err(self.expected("a type")),
+        }
+    }
+
+    /// An expression, whose unsuffixed integer literals have the type `int`
+    fn expr(&mut self, int: Option<IntType>) -> Result<Expr, ParseError> {
+        self.binary(int, 0)
+    }
+
+    /// A binary expression whose operators have at least the precedence `min_prec`, or an operand of one
+    fn binary(&mut self, int: Option<IntType>, min_prec: u8) -> Result<Expr, ParseError> {
+        let mut left = self.unary(int)?;
+        while let Some((op, prec, len)) = self.binary_op().filter(|(_, prec, _)| *prec >= min_prec)
+        {
+            self.pos += len;
+            let right = self.binary(int, prec + 1)?;
+            left = Expr::BinOp(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    /// The binary operator at the next token, its precedence, and the number of tokens it is written with.
+    /// Shifts are two adjacent `<` or `>` tokens
+    fn binary_op(&self) -> Option<(BinaryOp, u8, usize)> {
+        let token = self.peek()?;
+        if token.kind != TokenKind::Punct {
+            return None;
+        }
+        let text = self.text(token);
+        let (text, len) = match self.tokens.get(self.pos + 1) {
+            Some(next)
+                if (text == "<" || text == ">")
+                    && self.text(*next) == text
+                    && next.span.start == token.span.end =>
+            {
+                (token.span.to(next.span).text(self.src), 2)
+            }
+            _ => (text, 1),
+        };
+        BINARY_OPS
+            .iter()
+            .find(|(op, ..)| *op == text)
+            .map(|(_, op, prec)| (*op, *prec, len))
+    }
+
+    fn unary(&mut self, int: Option<IntType>) -> Result<Expr, ParseError> {
+        if self.eat("-") {
+            if self.is_kind(TokenKind::Int) {
+                return self.int_literal(int, true);
+            }
+            return Ok(Expr::UnaryOp(UnaryOp::Neg, Box::new(self.unary(int)?)));
+        }
+        if self.eat("!") {
+            return Ok(Expr::UnaryOp(UnaryOp::Not, Box::new(self.unary(int)?)));
+        }
+        self.atom(int)
+    }
+
+    /// An integer literal, which is negated if `negative`. Negative literals of signed fixed-width types are stored two's complement
+    /// in their width, as they are rendered, and others are negated with [`UnaryOp::Neg`]
+    fn int_literal(&mut self, int: Option<IntType>, negative: bool) -> Result<Expr, ParseError> {
+        let token = self.bump();
+        let text = self.text(token);
+        let (digits, ty) = match INT_SUFFIXES
+            .iter()
+            .find_map(|suffix| Some((text.strip_suffix(suffix)?, *suffix)))
+        {
+            Some((digits, suffix)) => (digits, int_type_named(suffix)),
+            None => (text, int),
+        };
+        let Some(ty) = ty else {
+            return Err(ParseError::new(
+                token.span,
+                format!("integer literal `{text}` needs a type suffix, such as `{text}u32`"),
+            ));
+        };
+        let val = int_digits_value(digits).ok_or_else(|| {
+            ParseError::new(token.span, format!("invalid integer literal `{text}`"))
+        })?;
+
+        let IntType {
+            signed: true,
+            bits: IntBits::Bits(bits),
+        } = ty
+        else {
+            let lit = Expr::IntLiteral(ty, val);
+            return Ok(match negative {
+                true => Expr::UnaryOp(UnaryOp::Neg, Box::new(lit)),
+                false => lit,
+            });
+        };
+        let bits = u32::from(bits.get());
+        let mask = u128::MAX >> (128 - bits);
+        let max = 1u128 << (bits - 1);
+        if val > max || (val == max && !negative) {
+            return Err(ParseError::new(
+                token.span,
+                format!("integer literal `{text}` out of range for {ty}"),
+            ));
+        }
+        let val = if negative { val.wrapping_neg() } else { val };
+        Ok(Expr::IntLiteral(ty, val & mask))
+    }
+
+    fn atom(&mut self, int: Option<IntType>) -> Result<Expr, ParseError> {
+        let Some(token) = self.peek() else {
+            return Err(self.expected("an expression"));
+        };
+        let text = self.text(token);
+        match token.kind {
+            TokenKind::Int => self.int_literal(int, false),
+            TokenKind::Str => Ok(Expr::StringLiteral(self.string()?)),
+            TokenKind::Param => Ok(Expr::Param(self.param()?)),
+            TokenKind::Keyword if text == "uuid" || text == "special" => {
+                self.pos += 1;
+                self.expect("(")?;
+                let id = self.uuid()?;
+                self.expect(")")?;
+                Ok(match text {
+                    "uuid" => Expr::UuidLiteral(id),
+                    _ => Expr::SpecialConstant(SpecialConst::Custom(id)),
+                })
+            }
+            TokenKind::Punct if text == "(" => {
+                self.pos += 1;
+                let expr = self.expr(int)?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            TokenKind::Ident => {
+                let (path, _) = self.path("an expression")?;
+                let name = path.join("::");
+                Ok(
+                    match BUILTIN_SPECIAL_CONSTS
+                        .iter()
+                        .find(|special| special.builtin_name() == Some(&name))
+                    {
+                        Some(special) => Expr::SpecialConstant(*special),
+                        None => Expr::Const(name),
+                    },
+                )
+            }
+            _ => Err(self.expected("an expression")),
+        }
+    }
+}
+
+/// Encodes the payload that an omitted struct field or attribute payload described by `schema` has, if it has one:
+/// nothing for `()`, `None` for an option, an empty list, or a struct whose fields all have one
+fn default_payload(schema: &Schema, out: &mut Vec<u8>) -> Option<()> {
+    match schema {
+        Schema::Unit => {}
+        Schema::Option(_) => put(out, &false),
+        Schema::List(_) => put(out, &0u64),
+        Schema::Struct(fields) => {
+            for (_, field) in *fields {
+                default_payload(field, out)?;
+            }
+        }
+        _ => return None,
+    }
+    Some(())
+}
@@ -1,18 +1,49 @@
 use bincode::{Decode, Encode};
 
 use crate::{
-    attr::{Attribute, AttributeTarget, AttributeTargetKind},
-    uses::{Expr, IntType, Type},
+    attr::{Attribute, AttributeTarget, Target, find_attribute},
+    intern::Symbol,
+    option::OptionKind,
+    uses::{BinaryOp, Expr, IntType, Type},
+    visit::{AttributeVisitor, visit_typedef},
 };
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct TypeDef {
-    pub name: String,
+    pub name: Symbol,
     pub num_params: u32,
     pub body: TypeDefBody,
 }
 
+impl TypeDef {
+    /// Whether each generic parameter is a constant parameter, which is the case when an array length in the definition refers to it with [`Expr::Param`].
+    /// Constant parameters are given [`Type::Const`] arguments
+    pub fn const_params(&self) -> Vec<bool> {
+        let mut visitor = ConstParams(vec![false; self.num_params as usize]);
+        visit_typedef(&mut visitor, self);
+        visitor.0
+    }
+}
+
+struct ConstParams(Vec<bool>);
+
+impl AttributeVisitor for ConstParams {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, _: &[Attribute<Targ>]) {}
+
+    fn enter_type(&mut self, ty: &Type) {
+        if let Type::Array(arr) = ty {
+            arr.len.visit_params(&mut |n| {
+                if let Some(is_const) = self.0.get_mut(n as usize) {
+                    *is_const = true;
+                }
+            });
+        }
+    }
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 #[non_exhaustive]
 pub enum TypeDefBody {
     Alias(TypeAlias),
@@ -21,44 +52,77 @@ pub enum TypeDefBody {
     Enum(Enum),
 }
 
+impl TypeDefBody {
+    pub fn find_attribute<T>(&self) -> Option<&T>
+    where
+        T: Target<TypeAlias> + Target<Struct> + Target<Union> + Target<Enum>,
+    {
+        match self {
+            TypeDefBody::Alias(alias) => find_attribute(&alias.attrs),
+            TypeDefBody::Struct(st) => find_attribute(&st.attrs),
+            TypeDefBody::Union(un) => find_attribute(&un.attrs),
+            TypeDefBody::Enum(en) => find_attribute(&en.attrs),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct TypeAlias {
     pub attrs: Vec<Attribute<TypeAlias>>,
     pub alias: Type,
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Struct {
     pub attrs: Vec<Attribute<Struct>>,
     pub body: StructBody,
 }
 
+impl Struct {
+    /// Whether the struct is an extensible-option record, with an [`OptionType`](crate::attr::types::OptionType)
+    /// or [`PolymorphicOption`](crate::attr::types::PolymorphicOption) attribute
+    pub fn is_option_struct(&self) -> bool {
+        OptionKind::of(self).is_some()
+    }
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub enum StructBody {
     Fields(StructFields),
     Opaque(Option<Type>),
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct StructFields {
     pub field: Vec<Field>,
+    /// Padding after the last field, which generated code declares as a field named `_pad`.
+    /// It is laid out like another field (so its alignment counts towards that of the type), and its bytes are reserved:
+    /// generated code zeroes them, but does not read them.
+    /// In a union, it is laid out like another member, so its size is the minimum size of the union
     pub pad: Option<Type>,
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Field {
     pub attrs: Vec<Attribute<Field>>,
-    pub name: String,
+    pub name: Symbol,
     pub ty: Type,
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Union {
     pub attrs: Vec<Attribute<Union>>,
     pub fields: StructFields,
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Enum {
     pub attrs: Vec<Attribute<Enum>>,
     pub underlying: IntType,
@@ -66,8 +130,70 @@ pub struct Enum {
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Variant {
     pub attrs: Vec<Attribute<Variant>>,
-    pub name: String,
+    pub name: Symbol,
     pub discrim: Expr,
 }
+
+/// Builds an [`Enum`], assigning discriminants to variants that don't specify one.
+///
+/// A variant without a discriminant takes the discriminant of the previous variant plus one, or zero if it is the first variant.
+#[derive(Clone, Debug)]
+pub struct EnumBuilder {
+    en: Enum,
+}
+
+impl EnumBuilder {
+    pub fn new(underlying: IntType) -> Self {
+        Self {
+            en: Enum {
+                attrs: Vec::new(),
+                underlying,
+                variants: Vec::new(),
+            },
+        }
+    }
+
+    pub fn attr(mut self, attr: Attribute<Enum>) -> Self {
+        self.en.attrs.push(attr);
+        self
+    }
+
+    pub fn variant(self, name: impl Into<Symbol>, discrim: Option<Expr>) -> Self {
+        self.variant_with_attrs(Vec::new(), name, discrim)
+    }
+
+    pub fn variant_with_attrs(
+        mut self,
+        attrs: Vec<Attribute<Variant>>,
+        name: impl Into<Symbol>,
+        discrim: Option<Expr>,
+    ) -> Self {
+        let discrim = discrim.unwrap_or_else(|| self.next_discrim());
+        self.en.variants.push(Variant {
+            attrs,
+            name: name.into(),
+            discrim,
+        });
+        self
+    }
+
+    fn next_discrim(&self) -> Expr {
+        let underlying = self.en.underlying;
+        match self.en.variants.last().map(|v| &v.discrim) {
+            None => Expr::IntLiteral(underlying, 0),
+            Some(Expr::IntLiteral(ty, val)) => Expr::IntLiteral(*ty, val.wrapping_add(1)),
+            Some(prev) => Expr::BinOp(
+                BinaryOp::Add,
+                Box::new(prev.clone()),
+                Box::new(Expr::IntLiteral(underlying, 1)),
+            ),
+        }
+    }
+
+    pub fn build(self) -> Enum {
+        self.en
+    }
+}
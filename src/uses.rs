@@ -1,16 +1,19 @@
 use std::num::NonZero;
 
-use bincode::{Decode, Encode};
+use bincode::{BorrowDecode, Decode, Encode, error::DecodeError};
 
 use crate::{
-    attr::{Attribute, AttributeTarget, AttributeTargetKind},
+    attr::{Attribute, UnknownAttributePolicy},
+    header::{RETURN_ATTRS_VERSION, format_version},
+    intern::Symbol,
     uuid::Uuid,
 };
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 #[non_exhaustive]
 pub enum Type {
-    Named(String, Option<Vec<Type>>),
+    Named(Symbol, Option<Vec<Type>>),
     Param(u32, Option<Box<Type>>),
     Int(IntType),
     Pointer(PointerKind, Box<Type>),
@@ -18,17 +21,73 @@ pub enum Type {
     Void,
     Never,
     Byte,
+    /// A character type, with the representation of the given integer type.
+    /// Only unsigned 8, 16, and 32-bit types are valid, corresponding to UTF-8, UTF-16, and UTF-32 code units respectively.
     Char(IntType),
     Array(Box<ArrayType>),
     Uninit(Box<Type>),
+    /// A constant argument to a generic parameter, which array lengths in the generic type refer to with [`Expr::Param`].
+    /// Only valid as an argument of [`Type::Named`]
+    Const(Expr),
+}
+
+impl core::fmt::Display for Type {
+    /// Formats the type in the Rust-like syntax of the [textual format](crate::text). Arrays of arrays nest like Rust arrays, so
+    /// `[[u8; 4]; 2]` is 2 arrays of 4 bytes
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Named(name, None) => name.fmt(f),
+            Type::Named(name, Some(args)) => {
+                f.write_fmt(format_args!("{name}<"))?;
+                let mut sep = "";
+                for arg in args {
+                    f.write_fmt(format_args!("{sep}{arg}"))?;
+                    sep = ", ";
+                }
+                f.write_str(">")
+            }
+            Type::Param(n, None) => f.write_fmt(format_args!("${n}")),
+            Type::Param(n, Some(default)) => f.write_fmt(format_args!("${n} = {default}")),
+            Type::Int(int) => int.fmt(f),
+            Type::Pointer(PointerKind::Const, ty) => f.write_fmt(format_args!("*const {ty}")),
+            Type::Pointer(PointerKind::Mut, ty) => f.write_fmt(format_args!("*mut {ty}")),
+            Type::Pointer(PointerKind::Special(id), ty) => {
+                f.write_fmt(format_args!("*special({id}) {ty}"))
+            }
+            Type::Func(sig) => sig.fmt(f),
+            Type::Void => f.write_str("void"),
+            Type::Never => f.write_str("!"),
+            Type::Byte => f.write_str("byte"),
+            Type::Char(int) => f.write_fmt(format_args!("char({int})")),
+            Type::Array(arr) => f.write_fmt(format_args!("[{}; {}]", arr.base, arr.len)),
+            Type::Uninit(ty) => f.write_fmt(format_args!("uninit({ty})")),
+            Type::Const(val) => f.write_fmt(format_args!("{{{val}}}")),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct ArrayType {
     pub base: Type,
     pub len: Expr,
 }
 
+impl ArrayType {
+    /// The length of each dimension of a (possibly multidimensional) array, outermost first, and the type of its elements.
+    ///
+    /// For example, the dimensions of `[[u8; 4]; 2]` are `2` then `4`, and its elements are `u8`.
+    pub fn dimensions(&self) -> (Vec<&Expr>, &Type) {
+        let mut dims = vec![&self.len];
+        let mut elem = &self.base;
+        while let Type::Array(inner) = elem {
+            dims.push(&inner.len);
+            elem = &inner.base;
+        }
+        (dims, elem)
+    }
+}
+
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
 pub struct IntType {
     pub signed: bool,
@@ -98,6 +157,16 @@ impl IntType {
     };
 }
 
+impl core::fmt::Display for IntType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.signed { 'i' } else { 'u' };
+        match self.bits {
+            IntBits::Long => f.write_fmt(format_args!("{sign}long")),
+            IntBits::Bits(bits) => f.write_fmt(format_args!("{sign}{bits}")),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
 pub enum IntBits {
     Long,
@@ -111,16 +180,66 @@ pub enum PointerKind {
     Special(Uuid),
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Encode)]
 pub struct Signature {
     pub params: Vec<Param>,
+    /// The attributes of the return type. Files encoded before [`RETURN_ATTRS_VERSION`] do not have them, and decode with none
+    pub ret_attrs: Vec<Attribute<ReturnValue>>,
     pub retty: Box<Type>,
 }
 
+impl Decode<UnknownAttributePolicy> for Signature {
+    fn decode<D: bincode::de::Decoder<Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        let params = Decode::decode(decoder)?;
+        let ret_attrs = if format_version() < RETURN_ATTRS_VERSION {
+            Vec::new()
+        } else {
+            Decode::decode(decoder)?
+        };
+        let retty = Decode::decode(decoder)?;
+        Ok(Self {
+            params,
+            ret_attrs,
+            retty,
+        })
+    }
+}
+
+impl<'de> BorrowDecode<'de, UnknownAttributePolicy> for Signature {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = UnknownAttributePolicy>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        Self::decode(decoder)
+    }
+}
+
+impl core::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("fn(")?;
+        let mut sep = "";
+        for param in &self.params {
+            f.write_str(sep)?;
+            sep = ", ";
+            if let Some(name) = &param.name {
+                f.write_fmt(format_args!("{name}: "))?;
+            }
+            param.ty.fmt(f)?;
+        }
+        f.write_fmt(format_args!(") -> {}", self.retty))
+    }
+}
+
+/// The return value of a [`Signature`], as the target of the attributes in [`Signature::ret_attrs`]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ReturnValue {}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Param {
     pub attrs: Vec<Attribute<Param>>,
-    pub name: Option<String>,
+    pub name: Option<Symbol>,
     pub ty: Type,
 }
 
@@ -134,12 +253,106 @@ pub enum Expr {
     BinOp(BinaryOp, Box<Expr>, Box<Expr>),
     UnaryOp(UnaryOp, Box<Expr>),
     SpecialConstant(SpecialConst),
+    /// The value of the generic parameter of the enclosing type definition with the given index, given by a [`Type::Const`] argument
+    Param(u32),
+}
+
+impl Expr {
+    /// Calls `f` with the index of every generic parameter the expression refers to with [`Expr::Param`]
+    pub fn visit_params(&self, f: &mut impl FnMut(u32)) {
+        match self {
+            Expr::Param(n) => f(*n),
+            Expr::BinOp(_, left, right) => {
+                left.visit_params(f);
+                right.visit_params(f);
+            }
+            Expr::UnaryOp(_, inner) => inner.visit_params(f),
+            Expr::IntLiteral(..)
+            | Expr::UuidLiteral(_)
+            | Expr::StringLiteral(_)
+            | Expr::Const(_)
+            | Expr::SpecialConstant(_) => {}
+        }
+    }
+}
+
+impl core::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Signed literals are stored two's complement in their width, so show them as the value they denote
+            Expr::IntLiteral(
+                ty @ IntType {
+                    signed: true,
+                    bits: IntBits::Bits(bits),
+                },
+                val,
+            ) if let Ok(val) = crate::eval::literal_value_bits(true, bits.get() as u32, *val) => {
+                f.write_fmt(format_args!("{val}{ty}"))
+            }
+            Expr::IntLiteral(ty, val) => f.write_fmt(format_args!("{val}{ty}")),
+            Expr::UuidLiteral(id) => f.write_fmt(format_args!("uuid({id})")),
+            Expr::StringLiteral(s) => f.write_fmt(format_args!("{s:?}")),
+            Expr::Const(name) => f.write_str(name),
+            Expr::BinOp(op, left, right) => {
+                let op = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::And => "&",
+                    BinaryOp::Or => "|",
+                    BinaryOp::Xor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
+                };
+                f.write_fmt(format_args!("({left} {op} {right})"))
+            }
+            Expr::UnaryOp(UnaryOp::Neg, inner) => f.write_fmt(format_args!("-{inner}")),
+            Expr::UnaryOp(UnaryOp::Not, inner) => f.write_fmt(format_args!("!{inner}")),
+            Expr::SpecialConstant(special) => special.fmt(f),
+            Expr::Param(n) => f.write_fmt(format_args!("${n}")),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
 #[non_exhaustive]
 pub enum SpecialConst {
+    /// The size of a pointer, in bytes
     SizeofPointer,
+    /// The size of `long`, in bytes
+    SizeofLong,
+    /// The size of a page of memory, in bytes
+    PageSize,
+    /// The maximum length of a path, in bytes
+    MaxPathLen,
+    /// The maximum number of handles a thread can have open at once
+    MaxHandleCount,
+    /// A constant declared by a [`SpecialConstant`](crate::attr::types::SpecialConstant) attribute
+    Custom(Uuid),
+}
+
+impl SpecialConst {
+    /// The name of a builtin special constant, or `None` for a [`SpecialConst::Custom`] constant
+    pub fn builtin_name(self) -> Option<&'static str> {
+        match self {
+            SpecialConst::SizeofPointer => Some("sizeof_pointer"),
+            SpecialConst::SizeofLong => Some("sizeof_long"),
+            SpecialConst::PageSize => Some("page_size"),
+            SpecialConst::MaxPathLen => Some("max_path_len"),
+            SpecialConst::MaxHandleCount => Some("max_handle_count"),
+            SpecialConst::Custom(_) => None,
+        }
+    }
+}
+
+impl core::fmt::Display for SpecialConst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecialConst::Custom(id) => f.write_fmt(format_args!("special({id})")),
+            builtin => f.write_str(builtin.builtin_name().unwrap_or_default()),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Encode, Decode)]
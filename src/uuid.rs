@@ -13,11 +13,39 @@ impl Default for Uuid {
     }
 }
 
+/// Uuids are ordered by their numeric value
+impl Ord for Uuid {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (self.0.major, self.0.minor).cmp(&(other.0.major, other.0.minor))
+    }
+}
+
+impl PartialOrd for Uuid {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Uuid {
     pub const fn parse(x: &str) -> Self {
         Self(lilium_sys::uuid::parse_uuid(x))
     }
 
+    /// Parses a UUID in the hyphenated form `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`, or returns `None` if `x` is not in that form.
+    /// Unlike [`Uuid::parse`], this does not panic on invalid input
+    pub fn try_parse(x: &str) -> Option<Self> {
+        if x.split('-').map(str::len).ne([8, 4, 4, 4, 12])
+            || !x.bytes().all(|b| b == b'-' || b.is_ascii_hexdigit())
+        {
+            return None;
+        }
+        let val = u128::from_str_radix(&x.replace('-', ""), 16).ok()?;
+        Some(Self(Underlying {
+            minor: val as u64,
+            major: (val >> 64) as u64,
+        }))
+    }
+
     pub const fn into_inner(self) -> Underlying {
         self.0
     }
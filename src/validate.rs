@@ -0,0 +1,1321 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    abi::Scope,
+    attr::{
+        Attribute, AttributeFlags, AttributeTarget, find_attribute, find_attributes,
+        types::{
+            ActiveWhen, Align, DiscriminatedBy, Endian, ExportInline, PolymorphicOption, Requires,
+            Reserved, SinceField, Stability, SubsystemDescriptor,
+        },
+    },
+    bundle::{Bundle, Path},
+    consts::ConstGraph,
+    diagnostics::{Diagnostic, codes},
+    docs::{doc_links, file_docs, prose_lines, resolve_link},
+    eval::{Evaluator, int_range},
+    file::File,
+    header::Version,
+    intern::Symbol,
+    layout::LayoutEngine,
+    option::{OptionKind, option_base, option_header},
+    resolve::{Item, expand_type, module_of, resolve_export, resolve_name},
+    tagged::tagged_unions,
+    target::TargetSpec,
+    tydef::{StructBody, TypeDef, TypeDefBody},
+    uses::{IntType, Type},
+    uuid::Uuid,
+    value::{Value, ValueBody},
+    visit::{AttributeVisitor, visit_file, visit_signature, visit_type, visit_typedef},
+};
+
+pub fn validate(bundle: &Bundle) -> Vec<Diagnostic> {
+    let mut diags = Vec::new();
+
+    check_requirements(bundle, &mut diags);
+    check_attribute_flags(bundle, &mut diags);
+    check_char_types(bundle, &mut diags);
+    check_option_structs(bundle, &mut diags);
+    check_field_endian(bundle, &mut diags);
+    check_reserved_fields(bundle, &mut diags);
+    check_field_versions(bundle, &mut diags);
+    check_tagged_unions(bundle, &mut diags);
+    check_const_cycles(bundle, &mut diags);
+    check_recursive_types(bundle, &mut diags);
+    check_frozen_items(bundle, &mut diags);
+    check_experimental_items(bundle, &mut diags);
+    check_function_types(bundle, &mut diags);
+    check_generic_params(bundle, &mut diags);
+    check_doc_links(bundle, &mut diags);
+
+    diags
+}
+
+/// Runs the checks that depend on the properties of a particular target, such as those that evaluate expressions
+pub fn validate_target(bundle: &Bundle, target: &TargetSpec) -> Vec<Diagnostic> {
+    let mut diags = Vec::new();
+
+    check_enum_discriminants(bundle, target, &mut diags);
+    check_reexported_functions(bundle, target, &mut diags);
+    check_explicit_padding(bundle, target, &mut diags);
+
+    diags
+}
+
+/// Returns the version `file` provides `id` at, if it provides `id` at all.
+/// A file provides its own `file_id` (at any version) and the `subsys_id` of its [`SubsystemDescriptor`].
+fn provided_version(file: &File, id: Uuid) -> Option<Option<Version>> {
+    let desc = find_attribute::<SubsystemDescriptor, _>(&file.attributes);
+
+    match desc {
+        Some(desc) if desc.subsys_id == id => Some(Some(desc.version)),
+        Some(desc) if file.file_id == id => Some(Some(desc.version)),
+        None if file.file_id == id => Some(None),
+        _ => None,
+    }
+}
+
+fn check_requirements(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for req in find_attributes::<Requires, _>(&file.attributes) {
+            let mut found = None;
+
+            for (_, other) in bundle {
+                if let Some(version) = provided_version(other, req.id) {
+                    found = Some(version);
+                    if version.is_none_or(|v| req.version.is_compatible(v)) {
+                        break;
+                    }
+                }
+            }
+
+            let (code, message) = match found {
+                None => (
+                    codes::MISSING_REQUIREMENT,
+                    format!("requires {} which is not present in the bundle", req.id),
+                ),
+                Some(Some(version)) if !req.version.is_compatible(version) => (
+                    codes::INCOMPATIBLE_REQUIREMENT,
+                    format!(
+                        "requires {} version {}, but version {version} is present",
+                        req.id, req.version
+                    ),
+                ),
+                Some(_) => continue,
+            };
+
+            diags.push(Diagnostic::error(path.clone(), message).with_code(code));
+        }
+    }
+}
+
+struct AttributeFlagChecker<'a> {
+    file: &'a Path,
+    diags: &'a mut Vec<Diagnostic>,
+}
+
+impl AttributeVisitor for AttributeFlagChecker<'_> {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &[Attribute<Targ>]) {
+        for attr in attrs {
+            let flags = *attr.flags();
+
+            if attr.is_unknown() && flags.contains(AttributeFlags::REQUIRED_FOR_CODEGEN) {
+                self.diags.push(
+                    Diagnostic::error(
+                        self.file.clone(),
+                        format!(
+                            "attribute {} is required for code generation but is not recognized",
+                            attr.id()
+                        ),
+                    )
+                    .with_code(codes::UNRECOGNIZED_CODEGEN_ATTRIBUTE),
+                );
+            }
+
+            if flags.contains(AttributeFlags::DEPRECATED_PAYLOAD) {
+                self.diags.push(
+                    Diagnostic::warning(
+                        self.file.clone(),
+                        format!("attribute {} uses a deprecated payload format", attr.id()),
+                    )
+                    .with_code(codes::DEPRECATED_PAYLOAD),
+                );
+            }
+        }
+    }
+}
+
+fn check_attribute_flags(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        visit_file(&mut AttributeFlagChecker { file: path, diags }, file);
+    }
+}
+
+struct CharTypeChecker<'a> {
+    file: &'a Path,
+    diags: &'a mut Vec<Diagnostic>,
+}
+
+impl AttributeVisitor for CharTypeChecker<'_> {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, _: &[Attribute<Targ>]) {}
+
+    fn enter_type(&mut self, ty: &Type) {
+        if let Type::Char(int) = ty
+            && ![IntType::u8, IntType::u16, IntType::u32].contains(int)
+        {
+            self.diags.push(
+                Diagnostic::error(
+                    self.file.clone(),
+                    format!("char type {int} is not one of u8, u16, or u32"),
+                )
+                .with_code(codes::INVALID_CHAR_TYPE),
+            );
+        }
+    }
+}
+
+fn check_char_types(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        visit_file(&mut CharTypeChecker { file: path, diags }, file);
+    }
+}
+
+fn check_option_structs(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    let mut seen = Vec::<(Uuid, Path)>::new();
+
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let TypeDefBody::Struct(st) = &ty.body else {
+                continue;
+            };
+            let mut messages = Vec::new();
+
+            let kind = OptionKind::of(st);
+            match kind {
+                Some(OptionKind::Option(id)) => {
+                    match seen.iter().find(|(other, _)| *other == id) {
+                        Some((_, other)) => messages.push((
+                            codes::DUPLICATE_OPTION_ID,
+                            format!("option {} has the same id ({id}) as {other}", ty.name),
+                        )),
+                        None => seen.push((id, path.join(&ty.name))),
+                    }
+                    if find_attribute::<PolymorphicOption, _>(&st.attrs).is_some() {
+                        messages.push((
+                            codes::OPTION_KIND_CONFLICT,
+                            format!(
+                                "option {} cannot be both a concrete and a polymorphic option",
+                                ty.name
+                            ),
+                        ));
+                    }
+                }
+                Some(OptionKind::Polymorphic) => {}
+                None => {
+                    if option_base(st).is_some() {
+                        messages.push((
+                            codes::BASE_ON_NON_OPTION,
+                            format!("{} has an option base type but is not an option", ty.name),
+                        ));
+                    }
+                }
+            }
+
+            if kind.is_some()
+                && let Err(e) = option_header(bundle, path, st)
+            {
+                messages.push((
+                    codes::INVALID_OPTION_HEADER,
+                    format!("option {}: {e}", ty.name),
+                ));
+            }
+
+            if let Some(Type::Named(base, _)) = option_base(st) {
+                let is_polymorphic = match resolve_name(bundle, path, base) {
+                    Ok(resolved) => matches!(
+                        resolved.item,
+                        Item::Type(def) if matches!(
+                            &def.body,
+                            TypeDefBody::Struct(base) if OptionKind::of(base) == Some(OptionKind::Polymorphic)
+                        )
+                    ),
+                    Err(_) => false,
+                };
+                if !is_polymorphic {
+                    messages.push((
+                        codes::BASE_NOT_POLYMORPHIC,
+                        format!(
+                            "option base type {base} of {} is not a polymorphic option",
+                            ty.name
+                        ),
+                    ));
+                }
+            }
+
+            diags.extend(messages.into_iter().map(|(code, message)| {
+                Diagnostic::error(path.clone(), message)
+                    .with_code(code)
+                    .with_item(&*ty.name)
+            }));
+        }
+    }
+}
+
+/// Whether `ty` is an integer type, a byte, or an array of them, or an alias of one, as seen from `module`
+fn is_zeroable(bundle: &Bundle, module: &Path, ty: &Type) -> bool {
+    let mut module = module.clone();
+    let mut ty = ty;
+    let mut seen = Vec::new();
+    loop {
+        match ty {
+            Type::Int(_) | Type::Byte => return true,
+            Type::Array(arr) => ty = &arr.base,
+            Type::Named(name, None) => {
+                let Ok(resolved) = resolve_name(bundle, &module, name) else {
+                    return false;
+                };
+                match resolved.item {
+                    Item::Type(def) => match &def.body {
+                        TypeDefBody::Alias(alias) if !seen.contains(&resolved.path) => {
+                            module = module_of(&resolved.path);
+                            seen.push(resolved.path);
+                            ty = &alias.alias;
+                        }
+                        _ => return false,
+                    },
+                    Item::Value(_) => return false,
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Whether `ty` is an integer type, an enum, or an alias of one, as seen from `module`
+fn is_integer(bundle: &Bundle, module: &Path, ty: &Type) -> bool {
+    let mut module = module.clone();
+    let mut ty = ty;
+    let mut seen = Vec::new();
+    loop {
+        match ty {
+            Type::Int(_) => return true,
+            Type::Named(name, None) => {
+                let Ok(resolved) = resolve_name(bundle, &module, name) else {
+                    return false;
+                };
+                let Item::Type(def) = resolved.item else {
+                    return false;
+                };
+                match &def.body {
+                    TypeDefBody::Enum(_) => return true,
+                    TypeDefBody::Alias(alias) if !seen.contains(&resolved.path) => {
+                        module = module_of(&resolved.path);
+                        seen.push(resolved.path);
+                        ty = &alias.alias;
+                    }
+                    _ => return false,
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+fn check_field_endian(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let fields = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => fields,
+                    StructBody::Opaque(_) => continue,
+                },
+                TypeDefBody::Union(un) => &un.fields,
+                _ => continue,
+            };
+
+            for field in &fields.field {
+                if find_attribute::<Endian, _>(&field.attrs).is_some()
+                    && !is_integer(bundle, path, &field.ty)
+                {
+                    diags.push(
+                        Diagnostic::error(
+                            path.clone(),
+                            format!(
+                                "field {}::{} has a byte order but is not an integer",
+                                ty.name, field.name
+                            ),
+                        )
+                        .with_code(codes::ENDIAN_ON_NON_INTEGER)
+                        .with_item(format!("{}::{}", ty.name, field.name)),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_reserved_fields(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let fields = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => fields,
+                    StructBody::Opaque(_) => continue,
+                },
+                TypeDefBody::Union(un) => &un.fields,
+                _ => continue,
+            };
+
+            for field in &fields.field {
+                if find_attribute::<Reserved, _>(&field.attrs).is_some()
+                    && !is_zeroable(bundle, path, &field.ty)
+                {
+                    diags.push(
+                        Diagnostic::error(
+                            path.clone(),
+                            format!(
+                                "field {}::{} is reserved but is not an integer or an array of integers",
+                                ty.name, field.name
+                            ),
+                        )
+                        .with_code(codes::RESERVED_NOT_ZEROABLE)
+                        .with_item(format!("{}::{}", ty.name, field.name)),
+                    );
+                }
+            }
+            if let Some(pad) = &fields.pad
+                && !is_zeroable(bundle, path, pad)
+            {
+                diags.push(
+                    Diagnostic::error(
+                        path.clone(),
+                        format!(
+                            "the explicit padding of {} is not an integer or an array of integers",
+                            ty.name
+                        ),
+                    )
+                    .with_code(codes::RESERVED_NOT_ZEROABLE)
+                    .with_item(format!("{}::_pad", ty.name)),
+                );
+            }
+        }
+    }
+}
+
+/// Checks that the fields of each struct with a [`SinceField`] attribute come after the fields without one, in the order of their versions.
+/// Unions cannot be extended by appending fields, so their fields cannot have a [`SinceField`] attribute
+fn check_field_versions(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let (fields, union) = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => (fields, false),
+                    StructBody::Opaque(_) => continue,
+                },
+                TypeDefBody::Union(un) => (&un.fields, true),
+                _ => continue,
+            };
+
+            let mut latest = None::<(Version, &Symbol)>;
+            for field in &fields.field {
+                let since =
+                    find_attribute::<SinceField, _>(&field.attrs).map(|since| since.version);
+                let message = match (since, latest) {
+                    (Some(_), _) if union => format!(
+                        "field {}::{} has a SinceField attribute, but only the fields of a struct can be versioned",
+                        ty.name, field.name
+                    ),
+                    (None, Some((version, other))) => format!(
+                        "field {}::{} has no SinceField attribute, but comes after {}::{other}, which was added in {version}",
+                        ty.name, field.name, ty.name
+                    ),
+                    (Some(since), Some((version, other))) if since < version => format!(
+                        "field {}::{} was added in {since}, but comes after {}::{other}, which was added in {version}",
+                        ty.name, field.name, ty.name
+                    ),
+                    (Some(since), _) => {
+                        latest = Some((since, &field.name));
+                        continue;
+                    }
+                    (None, None) => continue,
+                };
+                diags.push(
+                    Diagnostic::error(path.clone(), message)
+                        .with_code(codes::FIELD_VERSION_ORDER)
+                        .with_item(format!("{}::{}", ty.name, field.name)),
+                );
+            }
+        }
+    }
+}
+
+fn check_tagged_unions(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let mut messages = Vec::new();
+            let fields = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => fields,
+                    StructBody::Opaque(_) => continue,
+                },
+                TypeDefBody::Union(un) => {
+                    if find_attribute::<DiscriminatedBy, _>(&un.attrs).is_none() {
+                        for member in &un.fields.field {
+                            if find_attribute::<ActiveWhen, _>(&member.attrs).is_some() {
+                                messages.push((
+                                    codes::ACTIVE_IN_UNDISCRIMINATED_UNION,
+                                    format!(
+                                        "member {}::{} has an active variant, but {} is not discriminated",
+                                        ty.name, member.name, ty.name
+                                    ),
+                                ));
+                            }
+                        }
+                    }
+                    &un.fields
+                }
+                _ => continue,
+            };
+
+            for tagged in tagged_unions(bundle, path, fields) {
+                let tagged = match tagged {
+                    Ok(tagged) => tagged,
+                    Err(e) => {
+                        messages.push((codes::INVALID_TAGGED_UNION, format!("{}: {e}", ty.name)));
+                        continue;
+                    }
+                };
+
+                if !is_integer(bundle, path, &tagged.tag.ty) {
+                    messages.push((
+                        codes::TAG_NOT_INTEGER,
+                        format!(
+                            "tag field {}::{} is not an integer or enum",
+                            ty.name, tagged.tag.name
+                        ),
+                    ));
+                }
+
+                for (member, variant) in tagged.members() {
+                    match tagged.tag_enum {
+                        _ if tagged.has_variant(variant) => {}
+                        Some(_) => messages.push((
+                            codes::ACTIVE_VARIANT_NOT_IN_TAG,
+                            format!(
+                                "member {} of {}::{} is active for {variant}, which is not a variant of the type of {}",
+                                member.name, ty.name, tagged.field.name, tagged.tag.name
+                            ),
+                        )),
+                        None => messages.push((
+                            codes::ACTIVE_VARIANT_WITHOUT_ENUM,
+                            format!(
+                                "member {} of {}::{} is active for {variant}, but tag field {} is not an enum",
+                                member.name, ty.name, tagged.field.name, tagged.tag.name
+                            ),
+                        )),
+                    }
+                }
+            }
+
+            diags.extend(messages.into_iter().map(|(code, message)| {
+                Diagnostic::error(path.clone(), message)
+                    .with_code(code)
+                    .with_item(&*ty.name)
+            }));
+        }
+    }
+}
+
+fn check_const_cycles(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for cycle in ConstGraph::of(bundle).cycles() {
+        let first = &cycle[0];
+        let message = match &*cycle {
+            [_] => format!("constant {first} refers to itself"),
+            _ => format!(
+                "constants {} depend on each other",
+                cycle
+                    .iter()
+                    .map(Path::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        diags.push(
+            Diagnostic::error(module_of(first), message)
+                .with_code(codes::CONST_CYCLE)
+                .with_item(first.name().unwrap_or_default()),
+        );
+    }
+}
+
+/// The types that a type definition contains by value, which are its fields, its padding, the representation of an opaque struct, or the aliased type
+fn def_types(def: &TypeDef) -> Vec<&Type> {
+    match &def.body {
+        TypeDefBody::Alias(alias) => vec![&alias.alias],
+        TypeDefBody::Struct(st) => match &st.body {
+            StructBody::Fields(fields) => fields
+                .field
+                .iter()
+                .map(|f| &f.ty)
+                .chain(&fields.pad)
+                .collect(),
+            StructBody::Opaque(repr) => repr.iter().collect(),
+        },
+        TypeDefBody::Union(un) => un
+            .fields
+            .field
+            .iter()
+            .map(|f| &f.ty)
+            .chain(&un.fields.pad)
+            .collect(),
+        TypeDefBody::Enum(_) => Vec::new(),
+    }
+}
+
+/// Finds the type definitions that a type contains by value, rather than behind a pointer
+struct Containment<'a> {
+    bundle: &'a Bundle,
+    /// The generic parameters being checked by [`Containment::param_by_value`], which stops at generic types that contain themselves
+    visiting: HashSet<(Path, u32)>,
+}
+
+impl Containment<'_> {
+    /// Adds the path of each type definition that `ty`, as seen from `module`, contains by value to `out`.
+    /// The arguments of a generic type are contained if the type contains the corresponding parameter by value
+    fn contained(&mut self, module: &Path, ty: &Type, out: &mut Vec<Path>) {
+        match ty {
+            Type::Named(name, args) => {
+                let Ok(resolved) = resolve_name(self.bundle, module, name) else {
+                    return;
+                };
+                let Item::Type(def) = resolved.item else {
+                    return;
+                };
+                for (n, arg) in args.iter().flatten().enumerate() {
+                    if self.param_by_value(&resolved.path, def, n as u32) {
+                        self.contained(module, arg, out);
+                    }
+                }
+                if !out.contains(&resolved.path) {
+                    out.push(resolved.path);
+                }
+            }
+            Type::Array(arr) => self.contained(module, &arr.base, out),
+            Type::Uninit(ty) => self.contained(module, ty, out),
+            _ => {}
+        }
+    }
+
+    /// Whether the type definition `def` at `path` contains its generic parameter `n` by value
+    fn param_by_value(&mut self, path: &Path, def: &TypeDef, n: u32) -> bool {
+        if !self.visiting.insert((path.clone(), n)) {
+            return false;
+        }
+        let module = module_of(path);
+        let res = def_types(def)
+            .into_iter()
+            .any(|ty| self.contains_param(&module, ty, n));
+        self.visiting.remove(&(path.clone(), n));
+        res
+    }
+
+    fn contains_param(&mut self, module: &Path, ty: &Type, n: u32) -> bool {
+        match ty {
+            Type::Param(m, _) => *m == n,
+            Type::Named(name, Some(args)) => {
+                let Ok(resolved) = resolve_name(self.bundle, module, name) else {
+                    return false;
+                };
+                let Item::Type(def) = resolved.item else {
+                    return false;
+                };
+                args.iter().enumerate().any(|(i, arg)| {
+                    self.contains_param(module, arg, n)
+                        && self.param_by_value(&resolved.path, def, i as u32)
+                })
+            }
+            Type::Array(arr) => self.contains_param(module, &arr.base, n),
+            Type::Uninit(ty) => self.contains_param(module, ty, n),
+            _ => false,
+        }
+    }
+}
+
+/// Finds a cycle through each set of type definitions that contain each other by value
+struct CycleFinder<'a> {
+    /// The type definitions each type definition contains by value
+    contains: &'a HashMap<Path, Vec<Path>>,
+    /// Whether each visited type definition is still being visited
+    visited: HashMap<&'a Path, bool>,
+    stack: Vec<&'a Path>,
+    cycles: Vec<Vec<Path>>,
+}
+
+impl<'a> CycleFinder<'a> {
+    fn visit(&mut self, path: &'a Path) {
+        self.visited.insert(path, true);
+        self.stack.push(path);
+        for dep in self.contains.get(path).into_iter().flatten() {
+            match self.visited.get(dep) {
+                None => self.visit(dep),
+                Some(true) => {
+                    let start = self.stack.iter().position(|p| *p == dep).unwrap();
+                    let mut cycle = self.stack[start..]
+                        .iter()
+                        .map(|p| (*p).clone())
+                        .collect::<Vec<_>>();
+                    cycle.push(dep.clone());
+                    self.cycles.push(cycle);
+                }
+                Some(false) => {}
+            }
+        }
+        self.stack.pop();
+        self.visited.insert(path, false);
+    }
+}
+
+fn check_recursive_types(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    let mut containment = Containment {
+        bundle,
+        visiting: HashSet::new(),
+    };
+    let mut order = Vec::new();
+    let mut contains = HashMap::new();
+    for (module, file) in bundle {
+        for ty in &file.types {
+            let mut out = Vec::new();
+            for field in def_types(ty) {
+                containment.contained(module, field, &mut out);
+            }
+            let path = module.join(&ty.name);
+            order.push(path.clone());
+            contains.insert(path, out);
+        }
+    }
+
+    let mut finder = CycleFinder {
+        contains: &contains,
+        visited: HashMap::new(),
+        stack: Vec::new(),
+        cycles: Vec::new(),
+    };
+    for path in &order {
+        if !finder.visited.contains_key(path) {
+            finder.visit(path);
+        }
+    }
+
+    for cycle in finder.cycles {
+        let first = &cycle[0];
+        let path = cycle
+            .iter()
+            .map(Path::to_string)
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        diags.push(
+            Diagnostic::error(
+                module_of(first),
+                format!("{first} has infinite size, because it contains itself by value: {path}"),
+            )
+            .with_code(codes::RECURSIVE_TYPE)
+            .with_item(first.name().unwrap_or_default()),
+        );
+    }
+}
+
+/// Whether `ty` (with aliases expanded) is or contains a function type that is not behind a pointer
+fn has_function_by_value(ty: &Type) -> bool {
+    match ty {
+        Type::Func(_) => true,
+        Type::Pointer(_, pointee) => match &**pointee {
+            Type::Func(sig) => {
+                sig.params
+                    .iter()
+                    .any(|param| has_function_by_value(&param.ty))
+                    || has_function_by_value(&sig.retty)
+            }
+            pointee => has_function_by_value(pointee),
+        },
+        Type::Array(arr) => has_function_by_value(&arr.base),
+        Type::Uninit(ty) => has_function_by_value(ty),
+        _ => false,
+    }
+}
+
+/// Checks that each frozen item was frozen no later than the version of its file, and that the structs and enums it uses by value are frozen too,
+/// so that changing them cannot change its layout or signature. Type aliases and unions cannot be frozen, so the types they contain by value are checked instead
+fn check_frozen_items(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    let mut containment = Containment {
+        bundle,
+        visiting: HashSet::new(),
+    };
+
+    for (path, file) in bundle {
+        let version =
+            find_attribute::<SubsystemDescriptor, _>(&file.attributes).map(|desc| desc.version);
+        let items = file
+            .types
+            .iter()
+            .map(Item::Type)
+            .chain(file.values.iter().map(Item::Value));
+        for item in items {
+            let Some(since) = item.frozen() else {
+                continue;
+            };
+            let name = item.name();
+            if let Some(version) = version
+                && since > version
+            {
+                diags.push(
+                    Diagnostic::error(
+                        path.clone(),
+                        format!(
+                            "{name} is frozen since {since}, which is later than the version of its file ({version})"
+                        ),
+                    )
+                    .with_code(codes::FROZEN_IN_LATER_VERSION)
+                    .with_item(name),
+                );
+            }
+
+            let mut pending = match item {
+                Item::Type(def) => def_types(def),
+                Item::Value(Value {
+                    body: ValueBody::Function(f),
+                    ..
+                }) => f
+                    .signature
+                    .params
+                    .iter()
+                    .map(|param| &param.ty)
+                    .chain([&*f.signature.retty])
+                    .collect(),
+                Item::Value(_) => continue,
+            }
+            .into_iter()
+            .map(|ty| (path.clone(), ty))
+            .collect::<Vec<_>>();
+            let mut seen = HashSet::new();
+            let mut unfrozen = Vec::new();
+            while let Some((module, ty)) = pending.pop() {
+                let mut out = Vec::new();
+                containment.contained(&module, ty, &mut out);
+                for dep in out {
+                    if !seen.insert(dep.clone()) {
+                        continue;
+                    }
+                    let Some(def) = bundle
+                        .get(&module_of(&dep))
+                        .and_then(|file| file.find_type(dep.name()?))
+                    else {
+                        continue;
+                    };
+                    match &def.body {
+                        TypeDefBody::Alias(_) | TypeDefBody::Union(_) => {
+                            let dep_module = module_of(&dep);
+                            pending.extend(
+                                def_types(def)
+                                    .into_iter()
+                                    .map(|ty| (dep_module.clone(), ty)),
+                            );
+                        }
+                        _ if Item::Type(def).frozen().is_none() => unfrozen.push(dep),
+                        _ => {}
+                    }
+                }
+            }
+
+            for dep in unfrozen {
+                diags.push(
+                    Diagnostic::error(
+                        path.clone(),
+                        format!("{name} is frozen, but uses {dep} by value, which is not frozen"),
+                    )
+                    .with_code(codes::FROZEN_USES_UNFROZEN)
+                    .with_item(name),
+                );
+            }
+        }
+    }
+}
+
+/// Items in a module with a path component of this name (such as `experimental::net`) are experimental.
+/// Items with a [`Stability::Experimental`] attribute, or in a file with one, may only be declared in such modules
+pub const EXPERIMENTAL_MODULE: &str = "experimental";
+
+fn is_experimental_module(path: &Path) -> bool {
+    path.0
+        .iter()
+        .any(|component| *component == EXPERIMENTAL_MODULE)
+}
+
+fn item_stability(item: Item) -> Option<Stability> {
+    let stability = match item {
+        Item::Type(def) => match &def.body {
+            TypeDefBody::Alias(alias) => find_attribute::<Stability, _>(&alias.attrs),
+            TypeDefBody::Struct(st) => find_attribute::<Stability, _>(&st.attrs),
+            TypeDefBody::Union(un) => find_attribute::<Stability, _>(&un.attrs),
+            TypeDefBody::Enum(en) => find_attribute::<Stability, _>(&en.attrs),
+        },
+        Item::Value(val) => match &val.body {
+            ValueBody::Const(c) => find_attribute::<Stability, _>(&c.attrs),
+            ValueBody::Function(f) => find_attribute::<Stability, _>(&f.attrs),
+        },
+    };
+    stability.copied()
+}
+
+/// Whether `item`, declared in `file` at `module`, is experimental, because of its module, its file, or itself
+fn is_experimental(module: &Path, file: &File, item: Item) -> bool {
+    is_experimental_module(module)
+        || find_attribute::<Stability, _>(&file.attributes) == Some(&Stability::Experimental)
+        || item_stability(item) == Some(Stability::Experimental)
+}
+
+/// Whether the file at `path` declares experimental items, because of its module, its own attribute, or that of any item in it.
+/// Such files hold pre-release interfaces, which are encrypted when writing a bundle with `Bundle::set_member_encryption` (with the `crypto` feature)
+pub fn has_experimental_items(path: &Path, file: &File) -> bool {
+    is_experimental_module(path)
+        || find_attribute::<Stability, _>(&file.attributes) == Some(&Stability::Experimental)
+        || file
+            .types
+            .iter()
+            .map(Item::Type)
+            .chain(file.values.iter().map(Item::Value))
+            .any(|item| item_stability(item) == Some(Stability::Experimental))
+}
+
+/// Collects the names of the types that an item uses
+#[derive(Default)]
+struct NamedTypes {
+    names: Vec<Symbol>,
+}
+
+impl AttributeVisitor for NamedTypes {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, _attrs: &[Attribute<Targ>]) {}
+
+    fn enter_type(&mut self, ty: &Type) {
+        if let Type::Named(name, _) = ty {
+            self.names.push(name.clone());
+        }
+    }
+}
+
+/// Checks that experimental items are only declared in experimental modules (see [`EXPERIMENTAL_MODULE`]),
+/// and that stable items do not use experimental types, even behind pointers
+fn check_experimental_items(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        let quarantined = is_experimental_module(path);
+        if !quarantined
+            && find_attribute::<Stability, _>(&file.attributes) == Some(&Stability::Experimental)
+        {
+            diags.push(
+                Diagnostic::error(
+                    path.clone(),
+                    format!(
+                        "{path} is experimental, but is not in an `{EXPERIMENTAL_MODULE}` module"
+                    ),
+                )
+                .with_code(codes::EXPERIMENTAL_OUTSIDE_MODULE),
+            );
+        }
+
+        let items = file
+            .types
+            .iter()
+            .map(Item::Type)
+            .chain(file.values.iter().map(Item::Value));
+        for item in items {
+            let name = item.name();
+            if !quarantined && item_stability(item) == Some(Stability::Experimental) {
+                diags.push(
+                    Diagnostic::error(
+                        path.clone(),
+                        format!("{name} is experimental, but {path} is not an `{EXPERIMENTAL_MODULE}` module"),
+                    )
+                    .with_code(codes::EXPERIMENTAL_OUTSIDE_MODULE)
+                    .with_item(name),
+                );
+            }
+            if is_experimental(path, file, item) {
+                continue;
+            }
+
+            let mut types = NamedTypes::default();
+            match item {
+                Item::Type(def) => visit_typedef(&mut types, def),
+                Item::Value(val) => match &val.body {
+                    ValueBody::Const(c) => visit_type(&mut types, &c.ty),
+                    ValueBody::Function(f) => visit_signature(&mut types, &f.signature),
+                },
+            }
+            let mut reported = HashSet::new();
+            for ty in &types.names {
+                let Ok(resolved) = resolve_name(bundle, path, ty) else {
+                    continue;
+                };
+                let module = module_of(&resolved.path);
+                let Some(other) = bundle.get(&module) else {
+                    continue;
+                };
+                if is_experimental(&module, other, resolved.item)
+                    && reported.insert(resolved.path.clone())
+                {
+                    diags.push(
+                        Diagnostic::error(
+                            path.clone(),
+                            format!(
+                                "{name} is stable, but uses the experimental type {}",
+                                resolved.path
+                            ),
+                        )
+                        .with_code(codes::STABLE_USES_EXPERIMENTAL)
+                        .with_item(name),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_function_types(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        let mut check = |item: String, ty: &Type| {
+            // Unresolvable names are reported by the checks that need to resolve them
+            if let Ok(expanded) = expand_type(bundle, path, ty)
+                && has_function_by_value(&expanded)
+            {
+                diags.push(
+                    Diagnostic::error(
+                        path.clone(),
+                        format!(
+                            "{item} has a function type {expanded}, which is not behind a pointer"
+                        ),
+                    )
+                    .with_code(codes::FUNCTION_BY_VALUE)
+                    .with_item(item),
+                );
+            }
+        };
+
+        for ty in &file.types {
+            let fields = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => fields,
+                    StructBody::Opaque(Some(repr)) => {
+                        check(ty.name.to_string(), repr);
+                        continue;
+                    }
+                    StructBody::Opaque(None) => continue,
+                },
+                TypeDefBody::Union(un) => &un.fields,
+                TypeDefBody::Alias(_) | TypeDefBody::Enum(_) => continue,
+            };
+            for field in &fields.field {
+                check(format!("{}::{}", ty.name, field.name), &field.ty);
+            }
+            if let Some(pad) = &fields.pad {
+                check(format!("{}::_pad", ty.name), pad);
+            }
+        }
+
+        for val in &file.values {
+            match &val.body {
+                ValueBody::Const(c) => check(val.name.to_string(), &c.ty),
+                ValueBody::Function(f) => {
+                    for (i, param) in f.signature.params.iter().enumerate() {
+                        let item = match &param.name {
+                            Some(name) => format!("{}::{name}", val.name),
+                            None => format!("{}::{i}", val.name),
+                        };
+                        check(item, &param.ty);
+                    }
+                    check(val.name.to_string(), &f.signature.retty);
+                }
+            }
+        }
+    }
+}
+
+struct GenericParamChecker<'a> {
+    file: &'a Path,
+    item: String,
+    /// Whether each generic parameter of the item is a constant parameter
+    const_params: Vec<bool>,
+    diags: &'a mut Vec<Diagnostic>,
+}
+
+impl GenericParamChecker<'_> {
+    fn error(&mut self, message: String) {
+        self.diags.push(
+            Diagnostic::error(self.file.clone(), message)
+                .with_code(codes::INVALID_GENERIC_PARAM)
+                .with_item(self.item.clone()),
+        );
+    }
+}
+
+impl AttributeVisitor for GenericParamChecker<'_> {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, _: &[Attribute<Targ>]) {}
+
+    fn enter_type(&mut self, ty: &Type) {
+        match ty {
+            Type::Array(arr) => {
+                let mut missing = Vec::new();
+                arr.len.visit_params(&mut |n| {
+                    if n as usize >= self.const_params.len() {
+                        missing.push(n);
+                    }
+                });
+                for n in missing {
+                    self.error(format!(
+                        "array length {} refers to generic parameter ${n}, which {} does not have",
+                        arr.len, self.item
+                    ));
+                }
+            }
+            Type::Param(n, _) if self.const_params.get(*n as usize) == Some(&true) => {
+                self.error(format!(
+                    "generic parameter ${n} of {} is used both as a type and as an array length",
+                    self.item
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_generic_params(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let mut checker = GenericParamChecker {
+                file: path,
+                item: ty.name.to_string(),
+                const_params: ty.const_params(),
+                diags,
+            };
+            visit_typedef(&mut checker, ty);
+        }
+
+        // Values have no generic parameters for array lengths to refer to
+        for val in &file.values {
+            let mut checker = GenericParamChecker {
+                file: path,
+                item: val.name.to_string(),
+                const_params: Vec::new(),
+                diags,
+            };
+            match &val.body {
+                ValueBody::Const(c) => visit_type(&mut checker, &c.ty),
+                ValueBody::Function(f) => visit_signature(&mut checker, &f.signature),
+            }
+        }
+    }
+}
+
+fn check_enum_discriminants(bundle: &Bundle, target: &TargetSpec, diags: &mut Vec<Diagnostic>) {
+    let mut eval = Evaluator::new(bundle, target);
+
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let TypeDefBody::Enum(en) = &ty.body else {
+                continue;
+            };
+
+            let (min, max) = int_range(en.underlying, target);
+            let mut seen = Vec::<(i128, &str)>::new();
+
+            for variant in &en.variants {
+                let (code, message) = match eval.eval(path, &variant.discrim) {
+                    Err(e) => (
+                        codes::UNEVALUATED_DISCRIMINANT,
+                        format!(
+                            "discriminant of {}::{} cannot be evaluated: {e}",
+                            ty.name, variant.name
+                        ),
+                    ),
+                    Ok(val) if val < min || val > max => (
+                        codes::DISCRIMINANT_OUT_OF_RANGE,
+                        format!(
+                            "discriminant {val} of {}::{} does not fit the underlying type of {}",
+                            ty.name, variant.name, ty.name
+                        ),
+                    ),
+                    Ok(val) => match seen.iter().find(|(v, _)| *v == val) {
+                        Some((_, other)) => (
+                            codes::DUPLICATE_DISCRIMINANT,
+                            format!(
+                                "{}::{} has the same discriminant ({val}) as {}::{other}",
+                                ty.name, variant.name, ty.name
+                            ),
+                        ),
+                        None => {
+                            seen.push((val, &variant.name));
+                            continue;
+                        }
+                    },
+                };
+
+                diags.push(
+                    Diagnostic::error(path.clone(), message)
+                        .with_code(code)
+                        .with_item(format!("{}::{}", ty.name, variant.name)),
+                );
+            }
+        }
+    }
+}
+
+/// Checks that each function declared by a file matches any function of the same name the file re-exports
+fn check_reexported_functions(bundle: &Bundle, target: &TargetSpec, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for val in &file.values {
+            let ValueBody::Function(f) = &val.body else {
+                continue;
+            };
+
+            for use_item in &file.uses {
+                if find_attribute::<ExportInline, _>(&use_item.attrs).is_none() {
+                    continue;
+                }
+                let use_path = Path(use_item.path.clone());
+                let reexport = if use_path.name() == Some(&*val.name) {
+                    use_path
+                } else if bundle.get(&use_path).is_some() {
+                    use_path.join(&val.name)
+                } else {
+                    continue;
+                };
+
+                let Ok(resolved) = resolve_export(bundle, &reexport) else {
+                    continue;
+                };
+                let Item::Value(Value {
+                    body: ValueBody::Function(other),
+                    ..
+                }) = resolved.item
+                else {
+                    continue;
+                };
+
+                let other_module = module_of(&resolved.path);
+                if !f.signature.abi_eq(
+                    &other.signature,
+                    target,
+                    Scope::new(bundle, path),
+                    Scope::new(bundle, &other_module),
+                ) {
+                    diags.push(
+                        Diagnostic::error(
+                            path.clone(),
+                            format!(
+                                "function {} does not match the re-exported function {}",
+                                val.name, resolved.path
+                            ),
+                        )
+                        .with_code(codes::REEXPORT_SIGNATURE_MISMATCH)
+                        .with_item(&*val.name)
+                        .with_note(format!("{} is declared as {}", val.name, f.signature))
+                        .with_note(format!(
+                            "{} is declared as {}",
+                            resolved.path, other.signature
+                        )),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Checks that the explicit padding of each struct and union fits its layout on `target`:
+/// it must not raise the alignment of the type, and a struct must have no implicit padding besides it
+fn check_explicit_padding(bundle: &Bundle, target: &TargetSpec, diags: &mut Vec<Diagnostic>) {
+    let mut engine = LayoutEngine::new(bundle, target);
+
+    for (path, file) in bundle {
+        for ty in &file.types {
+            let (fields, align, union) = match &ty.body {
+                TypeDefBody::Struct(st) => match &st.body {
+                    StructBody::Fields(fields) => {
+                        (fields, find_attribute::<Align, _>(&st.attrs), false)
+                    }
+                    StructBody::Opaque(_) => continue,
+                },
+                TypeDefBody::Union(un) => (&un.fields, find_attribute::<Align, _>(&un.attrs), true),
+                TypeDefBody::Alias(_) | TypeDefBody::Enum(_) => continue,
+            };
+            if fields.pad.is_none() || ty.num_params != 0 {
+                continue;
+            }
+            // Types without a layout are reported by other checks
+            let (Ok(layout), Ok(field_layouts), Ok(Some(pad))) = (
+                engine.layout_of_def(path, ty),
+                engine.field_layouts(path, fields),
+                engine.pad_layout(path, fields),
+            ) else {
+                continue;
+            };
+
+            let mut messages = Vec::new();
+            let align = field_layouts
+                .iter()
+                .map(|field| field.layout.align)
+                .chain(align.map(|align| align.alignment))
+                .fold(1, u128::max);
+            if pad.layout.align > align {
+                messages.push(format!(
+                    "the explicit padding of {} raises its alignment from {align} to {}",
+                    ty.name, pad.layout.align
+                ));
+            }
+            if !union {
+                let end = field_layouts
+                    .last()
+                    .map_or(0, |field| field.offset + field.layout.size);
+                let implicit = (pad.offset - end) + (layout.size - pad.offset - pad.layout.size);
+                if implicit != 0 {
+                    messages.push(format!(
+                        "{} has {implicit} byte(s) of implicit padding besides its explicit padding",
+                        ty.name
+                    ));
+                }
+            }
+
+            for message in messages {
+                diags.push(
+                    Diagnostic::warning(path.clone(), message)
+                        .with_code(codes::EXPLICIT_PADDING_MISMATCH)
+                        .with_item(format!("{}::_pad", ty.name))
+                        .with_note(format!(
+                            "{} has size {} and alignment {}, and its explicit padding is {} byte(s) at offset {}",
+                            ty.name, layout.size, layout.align, pad.layout.size, pad.offset
+                        )),
+                );
+            }
+        }
+    }
+}
+
+fn check_doc_links(bundle: &Bundle, diags: &mut Vec<Diagnostic>) {
+    for (path, file) in bundle {
+        for docs in file_docs(file) {
+            for line in prose_lines(docs.lines) {
+                for link in doc_links(line) {
+                    if let Err(e) = resolve_link(bundle, path, &link.target) {
+                        let mut diag = Diagnostic::warning(
+                            path.clone(),
+                            format!("broken documentation link to `{}`", link.target),
+                        )
+                        .with_code(codes::BROKEN_DOC_LINK)
+                        .with_note(e.to_string());
+                        if let Some(item) = &docs.item {
+                            diag = diag.with_item(item.clone());
+                        }
+                        diags.push(diag);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,23 +1,39 @@
 use bincode::{Decode, Encode};
 
 use crate::{
-    attr::Attribute,
+    attr::{Attribute, Target, find_attribute},
+    intern::Symbol,
     uses::{Expr, Signature, Type},
 };
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Value {
-    pub name: String,
+    pub name: Symbol,
     pub body: ValueBody,
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub enum ValueBody {
     Const(Const),
     Function(Function),
 }
 
+impl ValueBody {
+    pub fn find_attribute<T>(&self) -> Option<&T>
+    where
+        T: Target<Const> + Target<Function>,
+    {
+        match self {
+            ValueBody::Const(c) => find_attribute(&c.attrs),
+            ValueBody::Function(f) => find_attribute(&f.attrs),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Const {
     pub attrs: Vec<Attribute<Const>>,
     pub ty: Type,
@@ -25,6 +41,7 @@ pub struct Const {
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
+#[bincode(decode_context = "crate::attr::UnknownAttributePolicy")]
 pub struct Function {
     pub attrs: Vec<Attribute<Function>>,
     pub signature: Signature,
@@ -0,0 +1,208 @@
+use crate::{
+    attr::{Attribute, AttributeTarget},
+    file::File,
+    tydef::{StructBody, StructFields, TypeDef, TypeDefBody},
+    uses::{Signature, Type},
+    value::ValueBody,
+};
+
+pub trait AttributeVisitor {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &[Attribute<Targ>]);
+
+    /// Called for every type in the file, before any types it contains
+    fn enter_type(&mut self, _ty: &Type) {}
+}
+
+pub fn visit_file<V: AttributeVisitor + ?Sized>(visitor: &mut V, file: &File) {
+    visitor.visit_attrs(&file.attributes);
+
+    for use_item in &file.uses {
+        visitor.visit_attrs(&use_item.attrs);
+    }
+
+    for ty in &file.types {
+        visit_typedef(visitor, ty);
+    }
+
+    for val in &file.values {
+        match &val.body {
+            ValueBody::Const(c) => {
+                visitor.visit_attrs(&c.attrs);
+                visit_type(visitor, &c.ty);
+            }
+            ValueBody::Function(f) => {
+                visitor.visit_attrs(&f.attrs);
+                visit_signature(visitor, &f.signature);
+            }
+        }
+    }
+}
+
+/// Visits the attributes and types of the type definition `ty`
+pub fn visit_typedef<V: AttributeVisitor + ?Sized>(visitor: &mut V, ty: &TypeDef) {
+    match &ty.body {
+        TypeDefBody::Alias(alias) => {
+            visitor.visit_attrs(&alias.attrs);
+            visit_type(visitor, &alias.alias);
+        }
+        TypeDefBody::Struct(st) => {
+            visitor.visit_attrs(&st.attrs);
+            match &st.body {
+                StructBody::Fields(fields) => visit_fields(visitor, fields),
+                StructBody::Opaque(Some(ty)) => visit_type(visitor, ty),
+                StructBody::Opaque(None) => {}
+            }
+        }
+        TypeDefBody::Union(un) => {
+            visitor.visit_attrs(&un.attrs);
+            visit_fields(visitor, &un.fields);
+        }
+        TypeDefBody::Enum(en) => {
+            visitor.visit_attrs(&en.attrs);
+            for variant in &en.variants {
+                visitor.visit_attrs(&variant.attrs);
+            }
+        }
+    }
+}
+
+fn visit_fields<V: AttributeVisitor + ?Sized>(visitor: &mut V, fields: &StructFields) {
+    for field in &fields.field {
+        visitor.visit_attrs(&field.attrs);
+        visit_type(visitor, &field.ty);
+    }
+    if let Some(pad) = &fields.pad {
+        visit_type(visitor, pad);
+    }
+}
+
+pub fn visit_signature<V: AttributeVisitor + ?Sized>(visitor: &mut V, sig: &Signature) {
+    for param in &sig.params {
+        visitor.visit_attrs(&param.attrs);
+        visit_type(visitor, &param.ty);
+    }
+    visitor.visit_attrs(&sig.ret_attrs);
+    visit_type(visitor, &sig.retty);
+}
+
+pub fn visit_type<V: AttributeVisitor + ?Sized>(visitor: &mut V, ty: &Type) {
+    visitor.enter_type(ty);
+    match ty {
+        Type::Named(_, Some(args)) => {
+            for arg in args {
+                visit_type(visitor, arg);
+            }
+        }
+        Type::Param(_, Some(ty)) | Type::Pointer(_, ty) | Type::Uninit(ty) => {
+            visit_type(visitor, ty)
+        }
+        Type::Func(sig) => visit_signature(visitor, sig),
+        Type::Array(arr) => visit_type(visitor, &arr.base),
+        Type::Named(_, None)
+        | Type::Param(_, None)
+        | Type::Int(_)
+        | Type::Void
+        | Type::Never
+        | Type::Byte
+        | Type::Char(_)
+        | Type::Const(_) => {}
+    }
+}
+
+pub trait AttributeVisitorMut {
+    fn visit_attrs<Targ: AttributeTarget>(&mut self, attrs: &mut Vec<Attribute<Targ>>);
+
+    /// Called for every type in the file, before any types it contains
+    fn enter_type(&mut self, _ty: &mut Type) {}
+}
+
+pub fn visit_file_mut<V: AttributeVisitorMut + ?Sized>(visitor: &mut V, file: &mut File) {
+    visitor.visit_attrs(&mut file.attributes);
+
+    for use_item in &mut file.uses {
+        visitor.visit_attrs(&mut use_item.attrs);
+    }
+
+    for ty in &mut file.types {
+        match &mut ty.body {
+            TypeDefBody::Alias(alias) => {
+                visitor.visit_attrs(&mut alias.attrs);
+                visit_type_mut(visitor, &mut alias.alias);
+            }
+            TypeDefBody::Struct(st) => {
+                visitor.visit_attrs(&mut st.attrs);
+                match &mut st.body {
+                    StructBody::Fields(fields) => visit_fields_mut(visitor, fields),
+                    StructBody::Opaque(Some(ty)) => visit_type_mut(visitor, ty),
+                    StructBody::Opaque(None) => {}
+                }
+            }
+            TypeDefBody::Union(un) => {
+                visitor.visit_attrs(&mut un.attrs);
+                visit_fields_mut(visitor, &mut un.fields);
+            }
+            TypeDefBody::Enum(en) => {
+                visitor.visit_attrs(&mut en.attrs);
+                for variant in &mut en.variants {
+                    visitor.visit_attrs(&mut variant.attrs);
+                }
+            }
+        }
+    }
+
+    for val in &mut file.values {
+        match &mut val.body {
+            ValueBody::Const(c) => {
+                visitor.visit_attrs(&mut c.attrs);
+                visit_type_mut(visitor, &mut c.ty);
+            }
+            ValueBody::Function(f) => {
+                visitor.visit_attrs(&mut f.attrs);
+                visit_signature_mut(visitor, &mut f.signature);
+            }
+        }
+    }
+}
+
+fn visit_fields_mut<V: AttributeVisitorMut + ?Sized>(visitor: &mut V, fields: &mut StructFields) {
+    for field in &mut fields.field {
+        visitor.visit_attrs(&mut field.attrs);
+        visit_type_mut(visitor, &mut field.ty);
+    }
+    if let Some(pad) = &mut fields.pad {
+        visit_type_mut(visitor, pad);
+    }
+}
+
+pub fn visit_signature_mut<V: AttributeVisitorMut + ?Sized>(visitor: &mut V, sig: &mut Signature) {
+    for param in &mut sig.params {
+        visitor.visit_attrs(&mut param.attrs);
+        visit_type_mut(visitor, &mut param.ty);
+    }
+    visitor.visit_attrs(&mut sig.ret_attrs);
+    visit_type_mut(visitor, &mut sig.retty);
+}
+
+pub fn visit_type_mut<V: AttributeVisitorMut + ?Sized>(visitor: &mut V, ty: &mut Type) {
+    visitor.enter_type(ty);
+    match ty {
+        Type::Named(_, Some(args)) => {
+            for arg in args {
+                visit_type_mut(visitor, arg);
+            }
+        }
+        Type::Param(_, Some(ty)) | Type::Pointer(_, ty) | Type::Uninit(ty) => {
+            visit_type_mut(visitor, ty)
+        }
+        Type::Func(sig) => visit_signature_mut(visitor, sig),
+        Type::Array(arr) => visit_type_mut(visitor, &mut arr.base),
+        Type::Named(_, None)
+        | Type::Param(_, None)
+        | Type::Int(_)
+        | Type::Void
+        | Type::Never
+        | Type::Byte
+        | Type::Char(_)
+        | Type::Const(_) => {}
+    }
+}
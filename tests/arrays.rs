@@ -0,0 +1,58 @@
+mod common;
+
+use common::{bundle, file, generate, path, structure};
+use imt::{
+    codegen::c::CBackend,
+    layout::{Layout, LayoutEngine},
+    target::TargetSpec,
+    uses::{ArrayType, Expr, IntType, Type},
+};
+
+fn array(base: Type, len: u64) -> Type {
+    Type::Array(Box::new(ArrayType {
+        base,
+        len: Expr::IntLiteral(IntType::u64, len.into()),
+    }))
+}
+
+/// `[[u8; 4]; 2]`, which is 2 arrays of 4 bytes
+fn matrix() -> Type {
+    array(array(Type::Int(IntType::u8), 4), 2)
+}
+
+#[test]
+fn displays_nested_arrays_like_rust() {
+    // Lengths are written with their type, like other integer literals
+    assert_eq!(matrix().to_string(), "[[u8; 4u64]; 2u64]");
+}
+
+#[test]
+fn lists_dimensions_outermost_first() {
+    let Type::Array(arr) = matrix() else {
+        unreachable!()
+    };
+    let (dims, elem) = arr.dimensions();
+    let dims = dims.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(dims, ["2u64", "4u64"]);
+    assert_eq!(*elem, Type::Int(IntType::u8));
+}
+
+#[test]
+fn lays_out_nested_arrays() {
+    let bundle = bundle(vec![]);
+    let target = TargetSpec::preset("x86_64").unwrap();
+    let layout = LayoutEngine::new(&bundle, &target)
+        .layout_of(&path("test"), &matrix())
+        .unwrap();
+    assert_eq!(layout, Layout::new(8, 1));
+}
+
+#[test]
+fn declares_nested_arrays_outermost_first_in_c() {
+    let bundle = bundle(vec![(
+        "test",
+        file(vec![structure("Matrix", &[("x", matrix())])], vec![]),
+    )]);
+    let c = &generate(&CBackend::default(), &bundle)["test.h"];
+    assert!(c.contains("uint8_t x[2ULL][4ULL];"), "{c}");
+}
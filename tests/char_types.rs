@@ -0,0 +1,73 @@
+mod common;
+
+use common::{bundle, codes, file, generate, generate_err, structure};
+use imt::{
+    bundle::Bundle,
+    codegen::{c::CBackend, rust::RustBackend},
+    uses::{IntBits, IntType, Type},
+};
+
+/// A bundle with a struct that has a field of each of the char types `chars`
+fn strings(chars: &[IntType]) -> Bundle {
+    let names = ["a", "b", "c", "d"];
+    let fields = names
+        .iter()
+        .zip(chars)
+        .map(|(name, &int)| (*name, Type::Char(int)))
+        .collect::<Vec<_>>();
+    bundle(vec![(
+        "io",
+        file(vec![structure("Units", &fields)], vec![]),
+    )])
+}
+
+#[test]
+fn accepts_unsigned_code_units() {
+    let bundle = strings(&[IntType::u8, IntType::u16, IntType::u32]);
+    assert!(codes(&bundle.validate()).is_empty());
+}
+
+#[test]
+fn rejects_other_char_types() {
+    let ulong = IntType {
+        signed: false,
+        bits: IntBits::Long,
+    };
+    let bundle = strings(&[IntType::i8, IntType::u64, ulong, IntType::u16]);
+    let diags = bundle.validate();
+    assert_eq!(codes(&diags), ["IMT0005"; 3]);
+    let messages = diags.iter().map(|diag| &*diag.message).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "char type i8 is not one of u8, u16, or u32",
+            "char type u64 is not one of u8, u16, or u32",
+            "char type ulong is not one of u8, u16, or u32",
+        ]
+    );
+}
+
+#[test]
+fn generates_character_types() {
+    let bundle = strings(&[IntType::u8, IntType::u16, IntType::u32]);
+
+    let c = &generate(&CBackend::default(), &bundle)["io.h"];
+    assert!(c.contains("#include <uchar.h>"), "{c}");
+    for field in ["char a;", "char16_t b;", "char32_t c;"] {
+        assert!(c.contains(field), "{field} in {c}");
+    }
+
+    let rust = &generate(&RustBackend::default(), &bundle)["io.rs"];
+    for field in ["a: u8,", "b: u16,", "c: char,"] {
+        assert!(rust.contains(field), "{field} in {rust}");
+    }
+}
+
+#[test]
+fn does_not_generate_invalid_char_types() {
+    let bundle = strings(&[IntType::i16]);
+    let e = generate_err(&CBackend::default(), &bundle);
+    assert_eq!(e.to_string(), "char type i16 has no C equivalent");
+    let e = generate_err(&RustBackend::default(), &bundle);
+    assert_eq!(e.to_string(), "char type i16 has no Rust equivalent");
+}
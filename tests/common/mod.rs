@@ -0,0 +1,236 @@
+//! Builders for the bundles the tests check
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use imt::{
+    attr::{Attribute, types::ExportInline},
+    bundle::{Bundle, Path},
+    codegen::CodegenBackend,
+    config::format_config,
+    diagnostics::Diagnostic,
+    file::{File, UseItem},
+    header::Header,
+    target::TargetSpec,
+    tydef::{
+        Enum, Field, Struct, StructBody, StructFields, TypeAlias, TypeDef, TypeDefBody, Union,
+    },
+    uses::{Expr, Param, Signature, Type},
+    uuid::Uuid,
+    value::{Const, Function, Value, ValueBody},
+};
+
+/// The path written `a::b::c`
+pub fn path(s: &str) -> Path {
+    Path(s.split("::").map(str::to_string).collect())
+}
+
+pub fn file(types: Vec<TypeDef>, values: Vec<Value>) -> File {
+    File {
+        header: Header::CURRENT,
+        file_id: Uuid::parse("01234567-89ab-cdef-0123-456789abcdef"),
+        attributes: Vec::new(),
+        uses: Vec::new(),
+        types,
+        values,
+    }
+}
+
+/// A bundle with each file of `files` at its path
+pub fn bundle(files: Vec<(&str, File)>) -> Bundle {
+    let mut bundle = Bundle::create();
+    for (at, file) in files {
+        bundle.add_file(path(at), file);
+    }
+    bundle
+}
+
+pub fn named(name: &str) -> Type {
+    Type::Named(name.into(), None)
+}
+
+pub fn fields(fields: &[(&str, Type)]) -> StructFields {
+    StructFields {
+        field: fields
+            .iter()
+            .map(|(name, ty)| Field {
+                attrs: Vec::new(),
+                name: (*name).into(),
+                ty: ty.clone(),
+            })
+            .collect(),
+        pad: None,
+    }
+}
+
+pub fn structure(name: &str, body: &[(&str, Type)]) -> TypeDef {
+    TypeDef {
+        name: name.into(),
+        num_params: 0,
+        body: TypeDefBody::Struct(Struct {
+            attrs: Vec::new(),
+            body: StructBody::Fields(fields(body)),
+        }),
+    }
+}
+
+pub fn field(attrs: Vec<Attribute<Field>>, name: &str, ty: Type) -> Field {
+    Field {
+        attrs,
+        name: name.into(),
+        ty,
+    }
+}
+
+fn typedef(name: &str, body: TypeDefBody) -> TypeDef {
+    TypeDef {
+        name: name.into(),
+        num_params: 0,
+        body,
+    }
+}
+
+/// A struct with the attributes `attrs` and the fields `fields`, which are not padded
+pub fn struct_of(name: &str, attrs: Vec<Attribute<Struct>>, fields: Vec<Field>) -> TypeDef {
+    typedef(
+        name,
+        TypeDefBody::Struct(Struct {
+            attrs,
+            body: StructBody::Fields(StructFields {
+                field: fields,
+                pad: None,
+            }),
+        }),
+    )
+}
+
+pub fn union_of(name: &str, attrs: Vec<Attribute<Union>>, fields: Vec<Field>) -> TypeDef {
+    typedef(
+        name,
+        TypeDefBody::Union(Union {
+            attrs,
+            fields: StructFields {
+                field: fields,
+                pad: None,
+            },
+        }),
+    )
+}
+
+pub fn enumeration(name: &str, en: Enum) -> TypeDef {
+    typedef(name, TypeDefBody::Enum(en))
+}
+
+pub fn alias(name: &str, ty: Type) -> TypeDef {
+    typedef(
+        name,
+        TypeDefBody::Alias(TypeAlias {
+            attrs: Vec::new(),
+            alias: ty,
+        }),
+    )
+}
+
+pub fn constant(name: &str, ty: Type, val: Expr) -> Value {
+    Value {
+        name: name.into(),
+        body: ValueBody::Const(Const {
+            attrs: Vec::new(),
+            ty,
+            val,
+        }),
+    }
+}
+
+/// A function with the named parameters `params`, which returns `ret`
+pub fn function(name: &str, params: &[(&str, Type)], ret: Type) -> Value {
+    Value {
+        name: name.into(),
+        body: ValueBody::Function(Function {
+            attrs: Vec::new(),
+            signature: Signature {
+                params: params
+                    .iter()
+                    .map(|(name, ty)| Param {
+                        attrs: Vec::new(),
+                        name: Some((*name).into()),
+                        ty: ty.clone(),
+                    })
+                    .collect(),
+                ret_attrs: Vec::new(),
+                retty: Box::new(ret),
+            },
+        }),
+    }
+}
+
+/// A `use` of `target`
+pub fn use_item(target: &str) -> UseItem {
+    UseItem {
+        attrs: Vec::new(),
+        path: path(target).0,
+    }
+}
+
+/// A `use` that re-exports `target`
+pub fn reexport(target: &str) -> UseItem {
+    UseItem {
+        attrs: vec![Attribute::new(ExportInline)],
+        path: path(target).0,
+    }
+}
+
+/// The encoded file `bytes`, with the first attribute with the id `id` given an id that no attribute is registered with
+pub fn unrecognize(bytes: &[u8], id: Uuid) -> Vec<u8> {
+    let encode = |id: Uuid| bincode::encode_to_vec(id, format_config()).unwrap();
+    let known = encode(id);
+    let mut unknown_id = id;
+    unknown_id.minor ^= 1;
+    let unknown = encode(unknown_id);
+    assert_eq!(known.len(), unknown.len());
+    let at = bytes
+        .windows(known.len())
+        .position(|window| window == known)
+        .unwrap();
+    [&bytes[..at], &unknown, &bytes[at + known.len()..]].concat()
+}
+
+/// The code of each diagnostic of `diags` that has one, in order
+pub fn codes(diags: &[Diagnostic]) -> Vec<&'static str> {
+    diags.iter().filter_map(|diag| diag.code).collect()
+}
+
+/// The message of each diagnostic of `diags`, in order
+pub fn messages(diags: &[Diagnostic]) -> Vec<&str> {
+    diags.iter().map(|diag| &*diag.message).collect()
+}
+
+/// Each file that `backend` generates for `bundle`, for the `x86_64` target, by name
+pub fn generate(backend: &dyn CodegenBackend, bundle: &Bundle) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    backend
+        .generate(
+            bundle,
+            &TargetSpec::preset("x86_64").unwrap(),
+            &mut |name: &str, contents: &[u8]| {
+                out.insert(
+                    name.to_string(),
+                    String::from_utf8(contents.to_vec()).unwrap(),
+                );
+                Ok(())
+            },
+        )
+        .unwrap();
+    out
+}
+
+/// The error that `backend` fails with when generating `bundle`, for the `x86_64` target
+pub fn generate_err(backend: &dyn CodegenBackend, bundle: &Bundle) -> std::io::Error {
+    backend
+        .generate(
+            bundle,
+            &TargetSpec::preset("x86_64").unwrap(),
+            &mut |_: &str, _: &[u8]| Ok(()),
+        )
+        .unwrap_err()
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "compress")]
+
+mod common;
+
+use common::{file, path, structure, unrecognize};
+use imt::{
+    attr::{
+        Attribute, AttributeFlags, AttributeType, UnknownAttributePolicy, find_attribute,
+        types::{ItemDoc, ToolComment},
+    },
+    bundle::Bundle,
+    config::format_config,
+    file::File,
+    tydef::TypeDefBody,
+    uses::{IntType, Type},
+};
+
+/// Several kilobytes of documentation
+fn long_doc() -> ItemDoc {
+    ItemDoc {
+        doc_lines: (0..100)
+            .map(|n| format!("Line {n} of the documentation of the handle type").into())
+            .collect(),
+    }
+}
+
+/// A file with a struct documented by `doc`, with the additional attribute flags `flags`
+fn documented(doc: ItemDoc, flags: AttributeFlags) -> File {
+    let mut handle = structure("Handle", &[("raw", Type::Int(IntType::u64))]);
+    let TypeDefBody::Struct(st) = &mut handle.body else {
+        unreachable!()
+    };
+    st.attrs.push(Attribute::new(doc).with_flags(flags));
+    file(vec![handle], vec![])
+}
+
+fn encode(file: &File) -> Vec<u8> {
+    bincode::encode_to_vec(file, format_config()).unwrap()
+}
+
+fn decode(bytes: &[u8], policy: UnknownAttributePolicy) -> File {
+    bincode::decode_from_slice_with_context(bytes, format_config(), policy)
+        .unwrap()
+        .0
+}
+
+#[test]
+fn round_trips_compressed_payloads() {
+    let compressed = encode(&documented(long_doc(), AttributeFlags::COMPRESSED));
+    let plain = encode(&documented(long_doc(), AttributeFlags::empty()));
+    assert!(compressed.len() * 4 < plain.len());
+    assert!(
+        !compressed
+            .windows(b"of the handle type".len())
+            .any(|window| window == b"of the handle type")
+    );
+
+    let mut bundle = Bundle::create();
+    bundle.parse_file(path("io"), &compressed[..]).unwrap();
+    let io = bundle.get(&path("io")).unwrap();
+    let TypeDefBody::Struct(st) = &io.types[0].body else {
+        panic!("Handle is not a struct");
+    };
+    assert_eq!(find_attribute::<ItemDoc, _>(&st.attrs), Some(&long_doc()));
+    // The payload stays compressed when the file is encoded again
+    assert!(st.attrs[0].flags().contains(AttributeFlags::COMPRESSED));
+    assert_eq!(encode(io), compressed);
+}
+
+#[test]
+fn preserves_unknown_compressed_payloads() {
+    let mut io = file(vec![], vec![]);
+    io.attributes.push(
+        Attribute::new(ToolComment {
+            comment: "generated ".repeat(100),
+        })
+        .with_flags(AttributeFlags::COMPRESSED),
+    );
+    let bytes = unrecognize(&encode(&io), ToolComment::ID);
+
+    // The payload is kept as it was encoded, without being decompressed
+    let io = decode(&bytes, UnknownAttributePolicy::WarnAndPreserve);
+    assert!(io.attributes[0].is_unknown());
+    assert_eq!(encode(&io), bytes);
+}
+
+#[test]
+fn rejects_invalid_compressed_payloads() {
+    let doc = ItemDoc {
+        doc_lines: vec!["A handle".into()],
+    };
+    let bytes = encode(&documented(doc.clone(), AttributeFlags::empty()));
+
+    // Mark the payload compressed without compressing it, so that it is not a valid deflate stream
+    let encode_flags =
+        |flags: AttributeFlags| bincode::encode_to_vec(flags, format_config()).unwrap();
+    let id = bincode::encode_to_vec(ItemDoc::ID, format_config()).unwrap();
+    let flags = *Attribute::<imt::tydef::Struct>::new(doc).flags();
+    let (plain, compressed) = (
+        encode_flags(flags),
+        encode_flags(flags | AttributeFlags::COMPRESSED),
+    );
+    assert_eq!(plain.len(), compressed.len());
+    let at = bytes
+        .windows(id.len())
+        .position(|window| window == id)
+        .unwrap()
+        + id.len();
+    assert_eq!(bytes[at..at + plain.len()], plain);
+    let bytes = [&bytes[..at], &compressed, &bytes[at + plain.len()..]].concat();
+
+    let e = bincode::decode_from_slice_with_context::<_, File, _>(
+        &bytes,
+        format_config(),
+        UnknownAttributePolicy::Error,
+    )
+    .unwrap_err();
+    assert!(e.to_string().contains("invalid compressed payload"), "{e}");
+}
+
+#[test]
+fn rejects_payloads_that_decompress_to_too_much() {
+    let doc = ItemDoc {
+        doc_lines: vec!["x".repeat(imt::attr::MAX_DECOMPRESSED_PAYLOAD).into()],
+    };
+    let compressed = encode(&documented(doc, AttributeFlags::COMPRESSED));
+    assert!(compressed.len() < imt::attr::MAX_DECOMPRESSED_PAYLOAD / 100);
+
+    let mut bundle = Bundle::create();
+    let err = bundle
+        .parse_file(path("io"), &compressed[..])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("decompresses to more than"), "{err}");
+}
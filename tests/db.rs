@@ -0,0 +1,101 @@
+mod common;
+
+use common::{bundle, file, named, path, reexport, structure};
+use imt::{
+    db::Database,
+    layout::Layout,
+    resolve::ResolveError,
+    target::TargetSpec,
+    uses::{IntType, Type},
+};
+
+/// A bundle where `api` re-exports `Handle` from `imp`, and `other` is unrelated
+fn database() -> Database {
+    let mut api = file(vec![structure("Pair", &[("a", named("Handle"))])], vec![]);
+    api.uses.push(reexport("imp::Handle"));
+    Database::new(bundle(vec![
+        ("api", api),
+        (
+            "imp",
+            file(
+                vec![structure("Handle", &[("raw", Type::Int(IntType::u8))])],
+                vec![],
+            ),
+        ),
+        ("other", file(vec![], vec![])),
+    ]))
+}
+
+#[test]
+fn resolves_through_changed_reexports() {
+    let mut db = database();
+    let resolved = db.resolve(&path("api::Handle")).unwrap();
+    assert_eq!(resolved.path, path("imp::Handle"));
+    assert_eq!(resolved.chain, [path("api::Handle"), path("imp::Handle")]);
+
+    // Changing an unrelated module keeps the resolution
+    db.set_file(path("other"), file(vec![structure("S", &[])], vec![]));
+    assert_eq!(
+        db.resolve(&path("api::Handle")).unwrap().path,
+        path("imp::Handle")
+    );
+
+    // Changing the module re-exported from is seen, although `api` did not change
+    db.set_file(path("imp"), file(vec![], vec![]));
+    assert_eq!(
+        db.resolve(&path("api::Handle")).unwrap_err(),
+        ResolveError::NotFound(path("imp::Handle"))
+    );
+    db.remove_file(&path("imp"));
+    assert_eq!(
+        db.resolve(&path("api::Handle")).unwrap_err(),
+        ResolveError::NoSuchModule(path("imp"))
+    );
+    db.set_file(path("imp"), file(vec![structure("Handle", &[])], vec![]));
+    assert_eq!(
+        db.resolve(&path("api::Handle")).unwrap().path,
+        path("imp::Handle")
+    );
+}
+
+#[test]
+fn ignores_identical_files() {
+    let mut db = database();
+    let imp = db.bundle().get(&path("imp")).unwrap().clone();
+    assert!(!db.set_file(path("imp"), imp));
+    assert_eq!(db.revision(), 0);
+    assert!(db.set_file(path("other"), file(vec![structure("S", &[])], vec![])));
+    assert_eq!(db.revision(), 1);
+}
+
+#[test]
+fn recomputes_layouts_and_diagnostics() {
+    let mut db = database();
+    let target = TargetSpec::preset("x86_64").unwrap();
+    assert_eq!(
+        db.layout(&target, &path("api::Pair")).unwrap(),
+        Layout::new(1, 1)
+    );
+    assert!(db.validate().is_empty());
+
+    db.set_file(
+        path("imp"),
+        file(
+            vec![structure("Handle", &[("raw", Type::Int(IntType::u32))])],
+            vec![],
+        ),
+    );
+    assert_eq!(
+        db.layout(&target, &path("api::Pair")).unwrap(),
+        Layout::new(4, 4)
+    );
+
+    // A type containing itself
+    db.set_file(
+        path("other"),
+        file(vec![structure("S", &[("s", named("S"))])], vec![]),
+    );
+    let diagnostics = db.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, Some("IMT0027"));
+}
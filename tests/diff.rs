@@ -0,0 +1,208 @@
+mod common;
+
+use common::{bundle, enumeration, field, file, path, struct_of, structure};
+use imt::{
+    attr::{
+        Attribute,
+        types::{SinceField, SubsystemDescriptor},
+    },
+    bundle::Bundle,
+    diff::{ChangeKind, ChangeSubject, ItemChange, Severity, diff},
+    header::Version,
+    tydef::{EnumBuilder, TypeDef},
+    uses::{Expr, IntType, Type},
+};
+
+fn in_test(defs: Vec<TypeDef>) -> Bundle {
+    bundle(vec![("test", file(defs, vec![]))])
+}
+
+fn modes(variants: &[(&str, u64)]) -> TypeDef {
+    let en = variants
+        .iter()
+        .fold(EnumBuilder::new(IntType::u8), |en, &(name, value)| {
+            en.variant(name, Some(Expr::IntLiteral(IntType::u8, value.into())))
+        });
+    enumeration("Mode", en.build())
+}
+
+fn change(subject: ChangeSubject, kind: ChangeKind) -> ItemChange {
+    ItemChange {
+        path: path("test::Mode"),
+        subject,
+        kind,
+    }
+}
+
+const BREAKING: ChangeKind = ChangeKind::Changed {
+    docs_only: false,
+    abi_compatible: false,
+};
+
+#[test]
+fn added_variants_are_compatible() {
+    let old = in_test(vec![modes(&[("Read", 0), ("Write", 1)])]);
+    let new = in_test(vec![modes(&[("Read", 0), ("Write", 1), ("Append", 2)])]);
+    let diff = diff(&old, &new);
+    let added = change(ChangeSubject::Variant("Append".into()), ChangeKind::Added);
+    assert_eq!(diff.changes, std::slice::from_ref(&added));
+    assert_eq!(added.to_string(), "added variant Append of test::Mode");
+    assert_eq!(diff.severity(), Some(Severity::Compatible));
+    assert_eq!(
+        diff.suggest_version(Version::new(1, 2)),
+        Some(Version::new(1, 3))
+    );
+}
+
+#[test]
+fn reports_changed_and_removed_variants() {
+    let old = in_test(vec![modes(&[("Read", 0), ("Write", 1)])]);
+    let new = in_test(vec![modes(&[("Read", 1)])]);
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.changes,
+        [
+            change(ChangeSubject::Variant("Read".into()), BREAKING),
+            change(ChangeSubject::Variant("Write".into()), ChangeKind::Removed),
+        ]
+    );
+    assert_eq!(diff.severity(), Some(Severity::Breaking));
+}
+
+#[test]
+fn added_fields_are_breaking() {
+    let u32 = || Type::Int(IntType::u32);
+    let old = in_test(vec![structure("Mode", &[("a", u32())])]);
+    let new = in_test(vec![structure("Mode", &[("a", u32()), ("b", u32())])]);
+    let diff = diff(&old, &new);
+    assert_eq!(
+        diff.changes,
+        [change(ChangeSubject::Field("b".into()), ChangeKind::Added)]
+    );
+    assert_eq!(diff.severity(), Some(Severity::Breaking));
+}
+
+/// A struct of version 1.0 of a module, with a field `b` appended in `since`, or without it if `since` is `None`
+fn versioned(since: Option<Version>) -> Bundle {
+    let u32 = || Type::Int(IntType::u32);
+    let mut fields = vec![field(vec![], "a", u32())];
+    fields.extend(
+        since.map(|version| field(vec![Attribute::new(SinceField { version })], "b", u32())),
+    );
+    let mut file = file(vec![struct_of("Mode", vec![], fields)], vec![]);
+    file.attributes.push(Attribute::new(SubsystemDescriptor {
+        version: Version::new(1, 0),
+        ..Default::default()
+    }));
+    bundle(vec![("test", file)])
+}
+
+#[test]
+fn fields_appended_in_later_versions_are_compatible() {
+    let appended = diff(&versioned(None), &versioned(Some(Version::new(1, 1))));
+    assert_eq!(
+        appended.changes,
+        [change(
+            ChangeSubject::AppendedField("b".into()),
+            ChangeKind::Added
+        )]
+    );
+    assert_eq!(appended.severity(), Some(Severity::Compatible));
+
+    // A field since the version of the old revision should already have been in it, so it was not appended after it
+    let added = diff(&versioned(None), &versioned(Some(Version::new(1, 0))));
+    assert_eq!(
+        added.changes,
+        [change(ChangeSubject::Field("b".into()), ChangeKind::Added)]
+    );
+    assert_eq!(added.severity(), Some(Severity::Breaking));
+}
+
+#[test]
+fn reordered_fields_change_the_struct() {
+    let (u8, u32) = (|| Type::Int(IntType::u8), || Type::Int(IntType::u32));
+    let old = in_test(vec![structure("Mode", &[("a", u8()), ("b", u32())])]);
+    let new = in_test(vec![structure("Mode", &[("b", u32()), ("a", u8())])]);
+    assert_eq!(
+        diff(&old, &new).changes,
+        [change(ChangeSubject::Item, BREAKING)]
+    );
+}
+
+/// Runs imt-tool on archives, which requires the tar feature
+#[cfg(feature = "tar")]
+mod cli {
+    use std::process::{Command, Output};
+
+    use imt::bundle::{Bundle, Path};
+
+    const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+    fn imt_tool(args: &[&std::ffi::OsStr]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn detects_the_format_of_the_older_revision() {
+        let dir = std::env::temp_dir().join(format!("imt-diff-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (file, archive) = (dir.join("io.imt"), dir.join("old.tar"));
+        std::fs::write(&file, BASELINE_FUNCTION).unwrap();
+        let mut old = Bundle::create();
+        old.parse_file(Path(vec!["io".into()]), BASELINE_FUNCTION)
+            .unwrap();
+        old.write_tar(&Path(vec![]), std::fs::File::create(&archive).unwrap())
+            .unwrap();
+
+        // The archive is read as one without --bundle, and its member is the module of the input
+        for cmd in ["diff", "bump"] {
+            let out = imt_tool(&[cmd.as_ref(), archive.as_os_str(), file.as_os_str()]);
+            assert!(
+                out.status.success(),
+                "{cmd}: {}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        }
+        let out = imt_tool(&["diff".as_ref(), file.as_os_str(), file.as_os_str()]);
+        assert!(out.status.success());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_options_before_and_between_revisions() {
+        let dir = std::env::temp_dir().join(format!("imt-diff-options-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (old, new) = (dir.join("old.tar"), dir.join("new.tar"));
+        let mut bundle = Bundle::create();
+        bundle
+            .parse_file(Path(vec!["io".into()]), BASELINE_FUNCTION)
+            .unwrap();
+        for archive in [&old, &new] {
+            bundle
+                .write_tar(&Path(vec![]), std::fs::File::create(archive).unwrap())
+                .unwrap();
+        }
+
+        let (old, new) = (old.as_os_str(), new.as_os_str());
+        for args in [
+            ["diff".as_ref(), "--bundle".as_ref(), old, new],
+            ["diff".as_ref(), old, "--bundle".as_ref(), new],
+            ["bump".as_ref(), "--bundle".as_ref(), old, new],
+        ] {
+            let out = imt_tool(&args);
+            assert!(
+                out.status.success(),
+                "{args:?}: {}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+use std::process::Command;
+
+const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+#[test]
+fn writes_the_only_input_file() {
+    let dir = std::env::temp_dir().join(format!("imt-dump-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let (a, b, output) = (dir.join("a.imt"), dir.join("b.imt"), dir.join("out.imt"));
+    std::fs::write(&a, BASELINE_FUNCTION).unwrap();
+    std::fs::write(&b, BASELINE_FUNCTION).unwrap();
+    std::fs::write(&output, b"previous").unwrap();
+
+    let dump = |inputs: &[&std::path::Path]| {
+        Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+            .args(["dump", "--output"])
+            .arg(&output)
+            .args(inputs)
+            .output()
+            .unwrap()
+    };
+
+    // More than one file is rejected without touching the output
+    let out = dump(&[&a, &b]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&out.stderr)
+            .contains("--output requires exactly one input file unless --bundle is specified")
+    );
+    assert_eq!(std::fs::read(&output).unwrap(), b"previous");
+
+    let out = dump(&[&a]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let mut bundle = imt::bundle::Bundle::create();
+    bundle
+        .parse_file(
+            imt::bundle::Path(vec!["out".into()]),
+            &std::fs::read(&output).unwrap()[..],
+        )
+        .unwrap();
+    assert!(
+        bundle
+            .get(&imt::bundle::Path(vec!["out".into()]))
+            .unwrap()
+            .find_value("Register")
+            .is_some()
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{
+    alias, bundle, codes, enumeration, field, file, generate, messages, named, path, struct_of,
+    union_of, use_item,
+};
+use imt::{
+    attr::{Attribute, types::Endian},
+    bundle::Bundle,
+    codegen::{c::CBackend, rust::RustBackend},
+    tydef::{EnumBuilder, Field},
+    uses::{ArrayType, Expr, IntType, PointerKind, Type},
+};
+
+fn big(name: &str, ty: Type) -> Field {
+    field(vec![Attribute::new(Endian::Big)], name, ty)
+}
+
+/// A bundle with the struct `Packet`, whose fields are `fields`, alongside an enum, an alias of an integer, and an alias of the struct `Route`.
+/// The integer alias is declared in another module
+fn packet(fields: Vec<Field>) -> Bundle {
+    let mode = EnumBuilder::new(IntType::u8).variant("Read", None).build();
+    let mut net = file(
+        vec![
+            enumeration("Mode", mode),
+            struct_of(
+                "Route",
+                vec![],
+                vec![field(vec![], "hops", Type::Int(IntType::u8))],
+            ),
+            alias("Header", named("Route")),
+            struct_of("Packet", vec![], fields),
+        ],
+        vec![],
+    );
+    net.uses.push(use_item("sys::types::Port"));
+    let types = file(vec![alias("Port", Type::Int(IntType::u16))], vec![]);
+    bundle(vec![("sys::net", net), ("sys::types", types)])
+}
+
+#[test]
+fn accepts_integer_fields() {
+    let bundle = packet(vec![
+        big("len", Type::Int(IntType::u32)),
+        field(vec![Attribute::new(Endian::Little)], "mode", named("Mode")),
+        field(vec![Attribute::new(Endian::Native)], "port", named("Port")),
+    ]);
+    assert!(codes(&bundle.validate()).is_empty());
+}
+
+#[test]
+fn rejects_other_fields() {
+    let bytes = ArrayType {
+        base: Type::Int(IntType::u8),
+        len: Expr::IntLiteral(IntType::u64, 4),
+    };
+    let bundle = packet(vec![
+        big("len", Type::Int(IntType::u32)),
+        big("addr", Type::Array(Box::new(bytes))),
+        big(
+            "next",
+            Type::Pointer(PointerKind::Const, Box::new(named("Packet"))),
+        ),
+        big("header", named("Header")),
+    ]);
+    let diags = bundle.validate();
+    assert_eq!(codes(&diags), ["IMT0011"; 3]);
+    assert_eq!(
+        messages(&diags),
+        [
+            "field Packet::addr has a byte order but is not an integer",
+            "field Packet::next has a byte order but is not an integer",
+            "field Packet::header has a byte order but is not an integer",
+        ]
+    );
+    assert_eq!(diags[0].item.as_deref(), Some("Packet::addr"));
+}
+
+#[test]
+fn checks_the_members_of_unions() {
+    let mut bundle = packet(vec![]);
+    let net = bundle.get_mut(&path("sys::net")).unwrap();
+    net.types.push(union_of(
+        "Addr",
+        vec![],
+        vec![
+            big("v4", Type::Int(IntType::u32)),
+            big("raw", named("Route")),
+        ],
+    ));
+    assert_eq!(
+        messages(&bundle.validate()),
+        ["field Addr::raw has a byte order but is not an integer"]
+    );
+}
+
+#[test]
+fn documents_the_byte_order() {
+    let bundle = packet(vec![
+        big("len", Type::Int(IntType::u32)),
+        field(vec![Attribute::new(Endian::Little)], "port", named("Port")),
+        field(vec![Attribute::new(Endian::Native)], "mode", named("Mode")),
+    ]);
+
+    let rust = &generate(&RustBackend::default(), &bundle)["sys/net.rs"];
+    assert!(
+        rust.contains("/// This field is stored in big-endian byte order.\n    pub len: u32,"),
+        "{rust}"
+    );
+    assert!(
+        rust.contains("/// This field is stored in little-endian byte order.\n    pub port: Port,"),
+        "{rust}"
+    );
+    assert_eq!(rust.matches("byte order").count(), 2);
+
+    let c = &generate(&CBackend::default(), &bundle)["sys/net.h"];
+    assert!(
+        c.contains("This field is stored in big-endian byte order."),
+        "{c}"
+    );
+    assert!(
+        c.contains("This field is stored in little-endian byte order."),
+        "{c}"
+    );
+}
@@ -0,0 +1,55 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+/// Runs `imt-tool --error-format json` with `args` and the standard input `stdin`, returning its exit status and the class it reports
+fn run(args: &[&str], stdin: &[u8]) -> (i32, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+        .args(["--error-format", "json"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // imt-tool may fail before it reads its input
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    let out = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    let class = stderr
+        .split_once("\"class\":\"")
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .unwrap_or_else(|| panic!("no class in {stderr}"))
+        .0
+        .to_string();
+    (out.status.code().unwrap(), class)
+}
+
+#[test]
+fn classifies_usage_errors() {
+    assert_eq!(run(&["dump", "--no-such-option"], b""), (2, "usage".into()));
+    assert_eq!(run(&["convert", "-"], b""), (2, "usage".into()));
+    assert_eq!(
+        run(&["dump", "--strip", "types", "-"], BASELINE_FUNCTION),
+        (2, "usage".into())
+    );
+}
+
+#[test]
+fn classifies_other_errors_by_source() {
+    assert_eq!(run(&["dump", "-"], b"garbage"), (3, "decode".into()));
+
+    // The library rejects writing the module at the prefix itself to a directory, which is not an error in the command line
+    let dir = std::env::temp_dir().join(format!("imt-failure-{}", std::process::id()));
+    assert_eq!(
+        run(
+            &["convert", "--to", "dir", "-", dir.to_str().unwrap()],
+            BASELINE_FUNCTION
+        ),
+        (1, "other".into())
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
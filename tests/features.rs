@@ -0,0 +1,158 @@
+mod common;
+
+use common::{bundle, constant, enumeration, file, function, named, path, structure, use_item};
+use imt::{
+    attr::{Attribute, AttributeTarget, AttributeType, Target, types::Feature},
+    config::format_config,
+    file::File,
+    tydef::{EnumBuilder, Field, Struct, TypeDefBody},
+    uses::{Expr, IntType, Type},
+    value::ValueBody,
+};
+
+fn feature<Targ: AttributeTarget>(name: &str) -> Attribute<Targ>
+where
+    Feature: Target<Targ>,
+{
+    Attribute::new(Feature { name: name.into() })
+}
+
+/// A file with an item of each kind that belongs to the feature `net`, a struct that belongs to both `net` and `tls`,
+/// and items that belong to no feature
+fn io() -> File {
+    let mut handle = structure("Handle", &[("raw", Type::Int(IntType::u64))]);
+    let mut socket = structure("Socket", &[("raw", Type::Int(IntType::u64))]);
+    let TypeDefBody::Struct(st) = &mut socket.body else {
+        unreachable!()
+    };
+    st.attrs.push(feature("net"));
+    let TypeDefBody::Struct(st) = &mut handle.body else {
+        unreachable!()
+    };
+    st.attrs.extend([feature("net"), feature("tls")]);
+    let mode = EnumBuilder::new(IntType::u8)
+        .variant("Read", None)
+        .variant_with_attrs(vec![feature("net")], "Stream", None)
+        .variant("Write", None)
+        .build();
+
+    let mut max = constant(
+        "MAX_SOCKETS",
+        Type::Int(IntType::u32),
+        Expr::IntLiteral(IntType::u32, 64),
+    );
+    let ValueBody::Const(c) = &mut max.body else {
+        unreachable!()
+    };
+    c.attrs.push(feature("net"));
+    let mut connect = function("connect", &[("socket", named("Socket"))], Type::Void);
+    let ValueBody::Function(f) = &mut connect.body else {
+        unreachable!()
+    };
+    f.attrs.push(feature("net"));
+
+    let mut net = use_item("sys::net");
+    net.attrs.push(feature("net"));
+
+    let mut io = file(
+        vec![
+            structure("File", &[("raw", Type::Int(IntType::u64))]),
+            handle,
+            socket,
+            enumeration("Mode", mode),
+        ],
+        vec![max, connect, function("close", &[], Type::Void)],
+    );
+    io.uses = vec![use_item("sys::mem"), net];
+    io
+}
+
+/// The names of the items, variants, and uses that remain in `io` with the features `enabled`
+fn remaining(enabled: &[&str]) -> Vec<String> {
+    let mut bundle = bundle(vec![("io", io())]);
+    bundle.filter_features(enabled);
+    let io = bundle.get(&path("io")).unwrap();
+    let uses = io.uses.iter().map(|use_item| use_item.path.join("::"));
+    let types = io.types.iter().flat_map(|ty| match &ty.body {
+        TypeDefBody::Enum(en) => en
+            .variants
+            .iter()
+            .map(|variant| format!("{}::{}", ty.name, variant.name))
+            .collect(),
+        _ => vec![ty.name.to_string()],
+    });
+    let values = io.values.iter().map(|val| val.name.to_string());
+    uses.chain(types).chain(values).collect()
+}
+
+#[test]
+fn removes_items_of_disabled_features() {
+    assert_eq!(
+        remaining(&[]),
+        ["sys::mem", "File", "Mode::Read", "Mode::Write", "close"]
+    );
+    assert_eq!(
+        remaining(&["net"]),
+        [
+            "sys::mem",
+            "sys::net",
+            "File",
+            "Socket",
+            "Mode::Read",
+            "Mode::Stream",
+            "Mode::Write",
+            "MAX_SOCKETS",
+            "connect",
+            "close"
+        ]
+    );
+    // An item with several features requires all of them
+    assert!(remaining(&["tls"]).iter().all(|name| name != "Handle"));
+    assert!(
+        remaining(&["net", "tls"])
+            .iter()
+            .any(|name| name == "Handle")
+    );
+}
+
+#[test]
+fn removes_files_of_disabled_features() {
+    let mut net = file(vec![], vec![]);
+    net.attributes.push(feature("net"));
+    let files = || bundle(vec![("io", io()), ("net", net.clone())]);
+
+    let mut bundle = files();
+    bundle.filter_features(&[]);
+    assert!(bundle.get(&path("net")).is_none());
+    assert!(bundle.get(&path("io")).is_some());
+
+    let mut bundle = files();
+    bundle.filter_features(&["net"]);
+    assert!(bundle.get(&path("net")).is_some());
+}
+
+#[test]
+fn fields_cannot_belong_to_a_feature() {
+    let payload = bincode::encode_to_vec(Feature { name: "net".into() }, format_config()).unwrap();
+    assert!(matches!(
+        Attribute::<Struct>::from_payload(Feature::ID, &payload),
+        Some(Ok(_))
+    ));
+    assert!(Attribute::<Field>::from_payload(Feature::ID, &payload).is_none());
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn rejects_features_on_fields_in_sources() {
+    use imt::text::parse::parse;
+
+    let e = parse(
+        "file 01234567-89ab-cdef-0123-456789abcdef;\n\
+         struct Socket {\n    #[types::Feature { name: \"net\" }]\n    raw: u64,\n}\n",
+    )
+    .unwrap_err();
+    assert_eq!(
+        e.message,
+        "attribute `types::Feature` cannot be attached to a Field"
+    );
+}
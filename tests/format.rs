@@ -0,0 +1,85 @@
+use imt::{
+    bundle::{Bundle, Path},
+    config::format_config,
+    header::{CURRENT_VERSION, Header, RETURN_ATTRS_VERSION, Version},
+    uses::{IntType, Type},
+    value::ValueBody,
+};
+
+/// A file encoded by imt 0.1, which has a function `Register(callback: *const fn(), flags: u32) -> i32` and a const `MAX: u32 = 7`
+const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+fn parse(bytes: &[u8]) -> Bundle {
+    let mut bundle = Bundle::create();
+    bundle
+        .parse_file(Path(vec!["test".into()]), bytes)
+        .expect("fixture decodes");
+    bundle
+}
+
+fn check_function(bundle: &Bundle) {
+    let file = bundle.get(&Path(vec!["test".into()])).unwrap();
+    let ValueBody::Function(f) = &file.find_value("Register").unwrap().body else {
+        panic!("Register is not a function");
+    };
+    assert_eq!(f.signature.params.len(), 2);
+    assert!(f.signature.ret_attrs.is_empty());
+    assert_eq!(*f.signature.retty, Type::Int(IntType::i32));
+    let Type::Pointer(_, callback) = &f.signature.params[0].ty else {
+        panic!("callback is not a pointer");
+    };
+    let Type::Func(callback) = &**callback else {
+        panic!("callback is not a function pointer");
+    };
+    assert!(callback.ret_attrs.is_empty());
+    assert!(matches!(
+        file.find_value("MAX").unwrap().body,
+        ValueBody::Const(_)
+    ));
+}
+
+#[test]
+fn decodes_signatures_without_return_attributes() {
+    let bundle = parse(BASELINE_FUNCTION);
+    let file = bundle.get(&Path(vec!["test".into()])).unwrap();
+    assert_eq!(file.header.version, Version::new(0, 1));
+    assert!(file.header.version < RETURN_ATTRS_VERSION);
+    check_function(&bundle);
+}
+
+#[test]
+fn reencodes_in_current_format() {
+    let bundle = parse(BASELINE_FUNCTION);
+    let file = bundle.get(&Path(vec!["test".into()])).unwrap();
+    let bytes = bincode::encode_to_vec(file, format_config()).unwrap();
+
+    let bundle = parse(&bytes);
+    let file = bundle.get(&Path(vec!["test".into()])).unwrap();
+    assert_eq!(file.header.version, CURRENT_VERSION);
+    check_function(&bundle);
+}
+
+#[test]
+fn rejects_newer_format_versions() {
+    let bundle = parse(BASELINE_FUNCTION);
+    let file = bundle.get(&Path(vec!["test".into()])).unwrap();
+    let bytes = bincode::encode_to_vec(file, format_config()).unwrap();
+    let current = bincode::encode_to_vec(Header::CURRENT, format_config()).unwrap();
+
+    for version in [Version::new(0, 3), Version::new(1, 0)] {
+        let header = Header {
+            version,
+            ..Header::CURRENT
+        };
+        let mut newer = bincode::encode_to_vec(header, format_config()).unwrap();
+        newer.extend_from_slice(&bytes[current.len()..]);
+        let err = Bundle::create()
+            .parse_file(Path(vec!["test".into()]), &newer[..])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(&format!("version {version} of the format")),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::process::Command;
+
+fn help(args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+        .args(args)
+        .arg("--help")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+/// The name and description of each command listed by `imt-tool --help`
+fn commands() -> Vec<(String, String)> {
+    help(&[])
+        .lines()
+        .skip_while(|line| *line != "Commands:")
+        .skip(1)
+        .map_while(|line| line.strip_prefix('\t'))
+        .map(|line| {
+            let (name, about) = line.split_once(": ").unwrap();
+            (name.to_string(), about.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn describes_each_command_consistently() {
+    let commands = commands();
+    assert!(commands.iter().any(|(name, _)| name == "strip"));
+    for (name, about) in &commands {
+        let help = help(&[name]);
+        let mut lines = help.lines();
+        let usage = lines.next().unwrap();
+        assert!(
+            usage.starts_with("Usage: ") && usage.contains(&format!(" {name} ")),
+            "{usage}"
+        );
+        assert_eq!(lines.next(), Some(&**about), "{name}");
+
+        let options = help
+            .lines()
+            .skip_while(|line| *line != "Options:")
+            .skip(1)
+            .collect::<Vec<_>>();
+        assert!(
+            options.contains(&"\t--help: Print this message, and exit"),
+            "{name}"
+        );
+        assert!(
+            options.iter().all(|line| line.starts_with("\t-")),
+            "{name}: {options:?}"
+        );
+    }
+}
+
+#[test]
+fn lists_exit_statuses() {
+    let help = help(&[]);
+    let statuses = help
+        .lines()
+        .skip_while(|line| *line != "Exit status:")
+        .skip(1)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses.len(), 6);
+    assert_eq!(statuses[2], "\t2: The command line is invalid");
+}
@@ -0,0 +1,236 @@
+#![cfg(feature = "lsp")]
+
+use std::{
+    io::{BufRead, Read, Write},
+    process::{Command, Stdio},
+};
+
+use serde_json::{Value, json};
+
+const HEADER: &str = "file 01234567-89ab-cdef-0123-456789abcdef;\n";
+
+const PRELUDE: &str = "/// A handle to an open object\nopaque struct Handle: u64;\n";
+
+const IO: &str = "include \"prelude.imts\";\n\nfn open(name: *const byte) -> Handle;\n";
+
+fn frame(msg: &Value) -> Vec<u8> {
+    let body = msg.to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len()).into_bytes()
+}
+
+/// Runs `imt-lsp` on the messages `msgs`, returning the messages it sends and whether it exited successfully
+fn run(msgs: &[Value]) -> (Vec<Value>, bool) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_imt-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for msg in msgs {
+        stdin.write_all(&frame(msg)).unwrap();
+    }
+    drop(stdin);
+
+    let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+    let mut sent = Vec::new();
+    loop {
+        let mut header = String::new();
+        if stdout.read_line(&mut header).unwrap() == 0 {
+            break;
+        }
+        let len = header
+            .trim_end()
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        stdout.read_line(&mut String::new()).unwrap();
+        let mut body = vec![0; len];
+        stdout.read_exact(&mut body).unwrap();
+        sent.push(serde_json::from_slice(&body).unwrap());
+    }
+    (sent, child.wait().unwrap().success())
+}
+
+fn uri(path: &std::path::Path) -> String {
+    format!("file://{}", path.display())
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn response(sent: &[Value], id: u64) -> &Value {
+    let msg = sent
+        .iter()
+        .find(|msg| msg["id"] == id)
+        .unwrap_or_else(|| panic!("no response to {id} in {sent:?}"));
+    &msg["result"]
+}
+
+/// The workspace of the test `name`, with a prelude and a source that includes it
+fn workspace(name: &str) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("imt-lsp-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("prelude.imts"), format!("{HEADER}{PRELUDE}")).unwrap();
+    std::fs::write(root.join("io.imts"), format!("{HEADER}{IO}")).unwrap();
+    root
+}
+
+fn session(root: &std::path::Path, msgs: Vec<Value>) -> Vec<Value> {
+    let mut all = vec![
+        request(1, "initialize", json!({ "rootUri": uri(root) })),
+        notification("initialized", json!({})),
+    ];
+    all.extend(msgs);
+    all.push(request(99, "shutdown", Value::Null));
+    all.push(notification("exit", Value::Null));
+
+    let (sent, success) = run(&all);
+    assert!(success, "imt-lsp did not exit successfully");
+    assert_eq!(*response(&sent, 99), Value::Null);
+    sent
+}
+
+/// The diagnostics published for `uri`, in order
+fn published<'a>(sent: &'a [Value], uri: &str) -> Vec<&'a Vec<Value>> {
+    sent.iter()
+        .filter(|msg| msg["method"] == "textDocument/publishDiagnostics")
+        .filter(|msg| msg["params"]["uri"] == uri)
+        .map(|msg| msg["params"]["diagnostics"].as_array().unwrap())
+        .collect()
+}
+
+#[test]
+fn publishes_and_clears_errors() {
+    let root = workspace("diagnostics");
+    let io = uri(&root.join("io.imts"));
+    let sent = session(
+        &root,
+        vec![
+            notification(
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": io, "languageId": "imt", "version": 1, "text": format!("{HEADER}fn open( -> Handle;\n") } }),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({ "textDocument": { "uri": io, "version": 2 }, "contentChanges": [{ "text": format!("{HEADER}{IO}") }] }),
+            ),
+        ],
+    );
+
+    let capabilities = &response(&sent, 1)["capabilities"];
+    assert_eq!(capabilities["definitionProvider"], true);
+    assert_eq!(capabilities["textDocumentSync"], 1);
+
+    let published = published(&sent, &io);
+    assert_eq!(published.len(), 2);
+    assert_eq!(published[0].len(), 1);
+    let error = &published[0][0];
+    assert_eq!(error["severity"], 1);
+    assert_eq!(error["message"], "expected a type, found `->`");
+    assert_eq!(
+        error["range"],
+        json!({ "start": { "line": 1, "character": 9 }, "end": { "line": 1, "character": 11 } })
+    );
+    // Fixing the error publishes no diagnostics, to clear it
+    assert!(published[1].is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn resolves_names_across_sources() {
+    let root = workspace("names");
+    let io = uri(&root.join("io.imts"));
+    // `Handle` in the declaration of `open`
+    let handle = json!({ "line": 3, "character": 31 });
+    let sent = session(
+        &root,
+        vec![
+            request(
+                2,
+                "textDocument/definition",
+                json!({ "textDocument": { "uri": io }, "position": handle }),
+            ),
+            request(
+                3,
+                "textDocument/hover",
+                json!({ "textDocument": { "uri": io }, "position": handle }),
+            ),
+            request(
+                4,
+                "textDocument/definition",
+                json!({ "textDocument": { "uri": io }, "position": { "line": 1, "character": 12 } }),
+            ),
+        ],
+    );
+
+    assert_eq!(
+        *response(&sent, 2),
+        json!({
+            "uri": uri(&root.join("prelude.imts")),
+            "range": { "start": { "line": 2, "character": 14 }, "end": { "line": 2, "character": 20 } },
+        })
+    );
+    assert_eq!(
+        response(&sent, 3)["contents"]["value"],
+        "```imt\nstruct prelude::Handle\n```\n\nA handle to an open object\n"
+    );
+    // An include goes to the included source
+    assert_eq!(
+        response(&sent, 4)["uri"],
+        uri(&std::fs::canonicalize(root.join("prelude.imts")).unwrap())
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn completes_types_and_attributes() {
+    let root = workspace("completion");
+    let io = uri(&root.join("io.imts"));
+    let text = format!("{HEADER}{IO}#[types::\nstruct S {{ a: H }}\n");
+    let sent = session(
+        &root,
+        vec![
+            notification(
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": io, "languageId": "imt", "version": 1, "text": text } }),
+            ),
+            request(
+                2,
+                "textDocument/completion",
+                json!({ "textDocument": { "uri": io }, "position": { "line": 5, "character": 15 } }),
+            ),
+            request(
+                3,
+                "textDocument/completion",
+                json!({ "textDocument": { "uri": io }, "position": { "line": 4, "character": 9 } }),
+            ),
+        ],
+    );
+
+    let labels = |id| {
+        response(&sent, id)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let types = labels(2);
+    assert!(types.contains(&"u32".to_string()));
+    assert!(types.contains(&"Handle".to_string()));
+    let attrs = labels(3);
+    assert!(attrs.contains(&"Align".to_string()));
+    assert!(!attrs.iter().any(|label| label.contains("::")));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
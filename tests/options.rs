@@ -0,0 +1,254 @@
+mod common;
+
+use common::{bundle, codes, field, file, generate, messages, named, path, struct_of};
+use imt::{
+    attr::{
+        Attribute,
+        types::{OptionBaseType, OptionType, PolymorphicOption},
+    },
+    bundle::Bundle,
+    codegen::{c::CBackend, rust::RustBackend},
+    diagnostics::Diagnostic,
+    option::{OptionError, option_header},
+    resolve::{Item, resolve_name},
+    tydef::{Struct, TypeDef, TypeDefBody},
+    uses::{IntType, Type},
+    uuid::Uuid,
+};
+
+const TIMEOUT: Uuid = Uuid::parse("5b7f2a0c-3e1d-5c9b-8a4f-6d2e1c0b9a87");
+
+fn u32() -> Type {
+    Type::Int(IntType::u32)
+}
+
+/// The header `OptionHead { ty: Uuid, flags: u32 }`
+fn head() -> TypeDef {
+    struct_of(
+        "OptionHead",
+        vec![],
+        vec![
+            field(vec![], "ty", named("Uuid")),
+            field(vec![], "flags", u32()),
+        ],
+    )
+}
+
+/// An option with the id `id` and the attributes `attrs`, starting with the header `OptionHead`
+fn option(name: &str, id: Uuid, mut attrs: Vec<Attribute<Struct>>) -> TypeDef {
+    attrs.insert(0, Attribute::new(OptionType { option: id }));
+    struct_of(
+        name,
+        attrs,
+        vec![
+            field(vec![], "head", named("OptionHead")),
+            field(vec![], "nanos", Type::Int(IntType::u64)),
+        ],
+    )
+}
+
+fn base(ty: &str) -> Attribute<Struct> {
+    Attribute::new(OptionBaseType { ty: named(ty) })
+}
+
+/// A file with the polymorphic option `ExtendedOption`, the option `Timeout` based on it, and `extra`
+fn options(extra: Vec<TypeDef>) -> Bundle {
+    let mut types = vec![
+        struct_of(
+            "Uuid",
+            vec![],
+            vec![
+                field(vec![], "lo", Type::Int(IntType::u64)),
+                field(vec![], "hi", Type::Int(IntType::u64)),
+            ],
+        ),
+        head(),
+        struct_of(
+            "ExtendedOption",
+            vec![Attribute::new(PolymorphicOption)],
+            vec![
+                field(vec![], "head", named("OptionHead")),
+                field(vec![], "payload", named("Uuid")),
+            ],
+        ),
+        option("Timeout", TIMEOUT, vec![base("ExtendedOption")]),
+    ];
+    types.extend(extra);
+    bundle(vec![("sys::option", file(types, vec![]))])
+}
+
+fn validate(extra: TypeDef) -> Vec<Diagnostic> {
+    options(vec![extra]).validate()
+}
+
+fn find_struct<'a>(bundle: &'a Bundle, name: &str) -> &'a Struct {
+    let resolved = resolve_name(bundle, &path("sys::option"), name).unwrap();
+    let Item::Type(TypeDef {
+        body: TypeDefBody::Struct(st),
+        ..
+    }) = resolved.item
+    else {
+        panic!("{name} is not a struct");
+    };
+    st
+}
+
+#[test]
+fn recognizes_option_structs() {
+    let bundle = options(vec![]);
+    assert!(codes(&bundle.validate()).is_empty());
+
+    for (name, is_option) in [
+        ("Timeout", true),
+        ("ExtendedOption", true),
+        ("OptionHead", false),
+    ] {
+        let resolved = resolve_name(&bundle, &path("sys::option"), name).unwrap();
+        assert_eq!(resolved.is_option_struct(), is_option, "{name}");
+        assert_eq!(find_struct(&bundle, name).is_option_struct(), is_option);
+    }
+
+    let header = option_header(
+        &bundle,
+        &path("sys::option"),
+        find_struct(&bundle, "Timeout"),
+    )
+    .unwrap();
+    assert_eq!(header.field.name, "head");
+    assert_eq!(header.id.name, "ty");
+    assert_eq!(header.flags.name, "flags");
+}
+
+#[test]
+fn reports_duplicate_option_ids() {
+    let diags = validate(option("Deadline", TIMEOUT, vec![]));
+    assert_eq!(codes(&diags), ["IMT0006"]);
+    assert_eq!(
+        messages(&diags),
+        [format!(
+            "option Deadline has the same id ({TIMEOUT}) as sys::option::Timeout"
+        )]
+    );
+    assert_eq!(diags[0].item.as_deref(), Some("Deadline"));
+}
+
+#[test]
+fn reports_options_that_are_also_polymorphic() {
+    let id = Uuid::parse("0e6d5c4b-3a29-5817-9f6e-5d4c3b2a1908");
+    let diags = validate(option("Both", id, vec![Attribute::new(PolymorphicOption)]));
+    assert_eq!(codes(&diags), ["IMT0007"]);
+    assert_eq!(
+        messages(&diags),
+        ["option Both cannot be both a concrete and a polymorphic option"]
+    );
+}
+
+#[test]
+fn reports_base_types_of_other_structs() {
+    let diags = validate(struct_of(
+        "Plain",
+        vec![base("ExtendedOption")],
+        vec![field(vec![], "head", named("OptionHead"))],
+    ));
+    assert_eq!(codes(&diags), ["IMT0008"]);
+    assert_eq!(
+        messages(&diags),
+        ["Plain has an option base type but is not an option"]
+    );
+}
+
+#[test]
+fn reports_invalid_headers() {
+    let id = Uuid::parse("0e6d5c4b-3a29-5817-9f6e-5d4c3b2a1908");
+    let no_header = struct_of(
+        "Raw",
+        vec![Attribute::new(OptionType { option: id })],
+        vec![field(vec![], "raw", u32())],
+    );
+    let diags = validate(no_header);
+    assert_eq!(codes(&diags), ["IMT0009"]);
+    assert_eq!(
+        messages(&diags),
+        ["option Raw: option header field raw does not name a struct"]
+    );
+
+    // The header must start with the id, then the flags
+    let swapped = struct_of(
+        "SwappedHead",
+        vec![],
+        vec![
+            field(vec![], "flags", u32()),
+            field(vec![], "ty", named("Uuid")),
+        ],
+    );
+    let mut bundle = options(vec![
+        swapped,
+        struct_of(
+            "Swapped",
+            vec![Attribute::new(PolymorphicOption)],
+            vec![field(vec![], "head", named("SwappedHead"))],
+        ),
+    ]);
+    let diags = bundle.validate();
+    assert_eq!(codes(&diags), ["IMT0009"]);
+    assert_eq!(
+        messages(&diags),
+        [
+            "option Swapped: option header sys::option::SwappedHead does not start with `ty` and `flags: u32` fields"
+        ]
+    );
+
+    bundle.add_file(
+        path("sys::empty"),
+        file(
+            vec![struct_of(
+                "Empty",
+                vec![Attribute::new(PolymorphicOption)],
+                vec![],
+            )],
+            vec![],
+        ),
+    );
+    let empty = bundle.get(&path("sys::empty")).unwrap();
+    let TypeDefBody::Struct(st) = &empty.types[0].body else {
+        unreachable!()
+    };
+    assert_eq!(
+        option_header(&bundle, &path("sys::empty"), st).unwrap_err(),
+        OptionError::NoHeader
+    );
+}
+
+#[test]
+fn reports_base_types_that_are_not_polymorphic() {
+    let id = Uuid::parse("0e6d5c4b-3a29-5817-9f6e-5d4c3b2a1908");
+    let diags = validate(option("Nested", id, vec![base("Timeout")]));
+    assert_eq!(codes(&diags), ["IMT0010"]);
+    assert_eq!(
+        messages(&diags),
+        ["option base type Timeout of Nested is not a polymorphic option"]
+    );
+}
+
+#[test]
+fn generates_option_helpers() {
+    let bundle = options(vec![]);
+
+    let rust = &generate(&RustBackend::default(), &bundle)["sys/option.rs"];
+    for helper in [
+        format!("const OPTION_ID: Uuid = lilium_sys::uuid::parse_uuid(\"{TIMEOUT}\");"),
+        "fn from_base(opt: &ExtendedOption) -> Option<&Self> {".to_string(),
+        "fn from_base_mut(opt: &mut ExtendedOption) -> Option<&mut Self> {".to_string(),
+        "(opt.head.ty == Self::OPTION_ID)".to_string(),
+        "fn option_id(&self) -> Uuid {".to_string(),
+    ] {
+        assert!(rust.contains(&helper), "{helper} in {rust}");
+    }
+
+    let c = &generate(&CBackend::default(), &bundle)["sys/option.h"];
+    let id = format!(
+        "static const Uuid Timeout_OPTION_ID = {{ {:#x}ULL, {:#x}ULL }};",
+        TIMEOUT.minor, TIMEOUT.major
+    );
+    assert!(c.contains(&id), "{id} in {c}");
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "tar")]
+
+use std::process::{Command, Output};
+
+use imt::{
+    bundle::{Bundle, Path},
+    provenance::Provenance,
+};
+
+const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+fn imt_tool(args: &[&std::ffi::OsStr]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn prints_the_provenance_of_bundles() {
+    let dir = std::env::temp_dir().join(format!("imt-provenance-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let archive = dir.join("io.tar");
+    let mut bundle = Bundle::create();
+    bundle
+        .parse_file(Path(vec!["io".into()]), BASELINE_FUNCTION)
+        .unwrap();
+    bundle.set_provenance(Provenance {
+        timestamp: 86400,
+        builder: Some("ci".into()),
+        revision: Some("abc123".into()),
+    });
+    bundle
+        .write_tar(&Path(vec![]), std::fs::File::create(&archive).unwrap())
+        .unwrap();
+
+    let out = imt_tool(&[
+        "provenance".as_ref(),
+        "--bundle".as_ref(),
+        archive.as_os_str(),
+    ]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "built at 1970-01-02T00:00:00Z by ci from revision abc123\n"
+    );
+
+    // The sizes of a bundle are printed after its provenance
+    let out = imt_tool(&["size".as_ref(), archive.as_os_str()]);
+    assert!(out.status.success());
+    assert!(
+        String::from_utf8_lossy(&out.stdout)
+            .starts_with("provenance: built at 1970-01-02T00:00:00Z by ci from revision abc123\n")
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{bundle, codes, file, path};
+use imt::{
+    attr::{
+        Attribute,
+        types::{Requires, SubsystemDescriptor},
+    },
+    file::File,
+    header::Version,
+    uuid::Uuid,
+};
+
+const IO: Uuid = Uuid::parse("4f3c6b4e-1f0d-5a8e-9c2b-7d6e5f4a3b21");
+const IO_FILE: Uuid = Uuid::parse("9a8b7c6d-5e4f-5a3b-8c2d-1e0f9a8b7c6d");
+
+/// A file with the id `file_id` that requires each of `requires`
+fn requiring(file_id: Uuid, requires: &[(Uuid, Version)]) -> File {
+    let mut file = file(vec![], vec![]);
+    file.file_id = file_id;
+    file.attributes = requires
+        .iter()
+        .map(|&(id, version)| Attribute::new(Requires { id, version }))
+        .collect();
+    file
+}
+
+fn app(requires: &[(Uuid, Version)]) -> File {
+    requiring(
+        Uuid::parse("01234567-89ab-cdef-0123-456789abcdef"),
+        requires,
+    )
+}
+
+/// A file that provides the subsystem `IO` at `version`
+fn io(version: Version) -> File {
+    let mut file = requiring(IO_FILE, &[]);
+    file.attributes.push(Attribute::new(SubsystemDescriptor {
+        subsys_id: IO,
+        version,
+        ..Default::default()
+    }));
+    file
+}
+
+#[test]
+fn accepts_satisfied_requirements() {
+    let subsystem = bundle(vec![
+        ("app", app(&[(IO, Version::new(1, 2))])),
+        ("io", io(Version::new(1, 3))),
+    ]);
+    assert!(codes(&subsystem.validate()).is_empty());
+
+    // A file without a subsystem descriptor provides its own id at any version
+    let file = bundle(vec![
+        ("app", app(&[(IO_FILE, Version::new(7, 0))])),
+        ("io", requiring(IO_FILE, &[])),
+    ]);
+    assert!(codes(&file.validate()).is_empty());
+}
+
+#[test]
+fn reports_missing_requirements() {
+    let bundle = bundle(vec![("app", app(&[(IO, Version::new(1, 0))]))]);
+    let diags = bundle.validate();
+    assert_eq!(codes(&diags), ["IMT0001"]);
+    assert_eq!(diags[0].file, path("app"));
+    assert_eq!(
+        diags[0].message,
+        format!("requires {IO} which is not present in the bundle")
+    );
+}
+
+#[test]
+fn reports_incompatible_versions() {
+    for (required, present) in [
+        (Version::new(1, 2), Version::new(1, 1)),
+        (Version::new(1, 2), Version::new(2, 2)),
+        // Before 1.0, each minor version is incompatible with the others
+        (Version::new(0, 2), Version::new(0, 3)),
+    ] {
+        let bundle = bundle(vec![("app", app(&[(IO, required)])), ("io", io(present))]);
+        let diags = bundle.validate();
+        assert_eq!(codes(&diags), ["IMT0002"], "{required} of {present}");
+        assert_eq!(
+            diags[0].message,
+            format!("requires {IO} version {required}, but version {present} is present")
+        );
+    }
+}
+
+#[test]
+fn accepts_any_compatible_provider() {
+    let bundle = bundle(vec![
+        ("app", app(&[(IO, Version::new(1, 2))])),
+        ("io::old", io(Version::new(1, 1))),
+        ("io::new", io(Version::new(1, 4))),
+    ]);
+    assert!(codes(&bundle.validate()).is_empty());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{bundle, file, path, reexport, structure, use_item};
+use imt::{
+    bundle::{Bundle, Path},
+    file::{File, UseItem},
+    resolve::{ResolveError, resolve_name},
+    uses::{IntType, Type},
+};
+
+/// A file that defines the struct `Handle`
+fn handle() -> File {
+    file(
+        vec![structure("Handle", &[("raw", Type::Int(IntType::u64))])],
+        vec![],
+    )
+}
+
+/// A file that defines nothing, with the uses `uses`
+fn uses(uses: Vec<UseItem>) -> File {
+    let mut file = file(vec![], vec![]);
+    file.uses = uses;
+    file
+}
+
+fn paths(chain: &[Path]) -> Vec<String> {
+    chain.iter().map(Path::to_string).collect()
+}
+
+#[test]
+fn follows_chains_of_reexports() {
+    let bundle = bundle(vec![
+        ("sys::handle", handle()),
+        ("sys::io", uses(vec![reexport("sys::handle::Handle")])),
+        ("api", uses(vec![reexport("sys::io::Handle")])),
+    ]);
+
+    let resolved = bundle.resolve_export(&path("api::Handle")).unwrap();
+    assert_eq!(resolved.path, path("sys::handle::Handle"));
+    assert_eq!(resolved.item.name(), "Handle");
+    assert_eq!(
+        paths(&resolved.chain),
+        ["api::Handle", "sys::io::Handle", "sys::handle::Handle"]
+    );
+
+    // An item defined where it is requested resolves to itself
+    let resolved = bundle.resolve_export(&path("sys::handle::Handle")).unwrap();
+    assert_eq!(paths(&resolved.chain), ["sys::handle::Handle"]);
+}
+
+#[test]
+fn follows_reexports_of_modules() {
+    let bundle = bundle(vec![
+        ("sys::handle", handle()),
+        ("api", uses(vec![reexport("sys::handle")])),
+    ]);
+
+    let resolved = bundle.resolve_export(&path("api::Handle")).unwrap();
+    assert_eq!(resolved.path, path("sys::handle::Handle"));
+    assert_eq!(
+        bundle.resolve_export(&path("api::File")).unwrap_err(),
+        ResolveError::NotFound(path("api::File"))
+    );
+}
+
+#[test]
+fn does_not_follow_plain_uses() {
+    let bundle = bundle(vec![
+        ("sys::handle", handle()),
+        ("api", uses(vec![use_item("sys::handle::Handle")])),
+    ]);
+
+    assert_eq!(
+        bundle.resolve_export(&path("api::Handle")).unwrap_err(),
+        ResolveError::NotFound(path("api::Handle"))
+    );
+    // Names used within the module still resolve to the item
+    let resolved = resolve_name(&bundle, &path("api"), "Handle").unwrap();
+    assert_eq!(resolved.path, path("sys::handle::Handle"));
+}
+
+#[test]
+fn detects_cycles() {
+    let bundle = bundle(vec![
+        ("a", uses(vec![reexport("b::Handle")])),
+        ("b", uses(vec![reexport("c::Handle")])),
+        ("c", uses(vec![reexport("a::Handle")])),
+    ]);
+
+    let e = bundle.resolve_export(&path("a::Handle")).unwrap_err();
+    let ResolveError::Cycle(chain) = &e else {
+        panic!("expected a cycle, got {e:?}");
+    };
+    assert_eq!(
+        paths(chain),
+        ["a::Handle", "b::Handle", "c::Handle", "a::Handle"]
+    );
+    assert_eq!(
+        e.to_string(),
+        "re-export cycle: a::Handle -> b::Handle -> c::Handle -> a::Handle"
+    );
+}
+
+#[test]
+fn allows_modules_that_reexport_each_other() {
+    let mut a = handle();
+    a.uses.push(reexport("b"));
+    let bundle = bundle(vec![("a", a), ("b", uses(vec![reexport("a")]))]);
+
+    let resolved = bundle.resolve_export(&path("b::Handle")).unwrap();
+    assert_eq!(resolved.path, path("a::Handle"));
+    assert_eq!(
+        bundle.resolve_export(&path("b::File")).unwrap_err(),
+        ResolveError::NotFound(path("b::File"))
+    );
+}
+
+#[test]
+fn reports_missing_modules() {
+    let bundle = bundle(vec![("api", uses(vec![reexport("sys::gone::Handle")]))]);
+
+    assert_eq!(
+        bundle.resolve_export(&path("api::Handle")).unwrap_err(),
+        ResolveError::NoSuchModule(path("sys::gone"))
+    );
+    assert_eq!(
+        bundle.resolve_export(&path("sys::Handle")).unwrap_err(),
+        ResolveError::NoSuchModule(path("sys"))
+    );
+    assert_eq!(
+        Bundle::create()
+            .resolve_export(&Path(Vec::new()))
+            .unwrap_err(),
+        ResolveError::EmptyPath
+    );
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::{file, path, structure};
+use imt::{
+    bundle::{Bundle, Format},
+    config::format_config,
+    header::MAGIC,
+    uses::{IntType, Type},
+};
+
+fn encoded() -> Vec<u8> {
+    let io = file(
+        vec![structure("Handle", &[("raw", Type::Int(IntType::u64))])],
+        vec![],
+    );
+    bincode::encode_to_vec(&io, format_config()).unwrap()
+}
+
+#[test]
+fn sniffs_magic_numbers() {
+    let mut tar = vec![0; 512];
+    tar[257..262].copy_from_slice(b"ustar");
+    let cases: [(&[u8], _); 10] = [
+        (&MAGIC, Some(Format::Imt)),
+        (b"\xFEIMTAE", Some(Format::Encrypted)),
+        (b"\x1F\x8B\x08\0", Some(Format::Gzip)),
+        (b"\xFD7zXZ\0", Some(Format::Xz)),
+        (b"\x28\xB5\x2F\xFD", Some(Format::Zstd)),
+        (b"BZh9", Some(Format::Bzip2)),
+        (b"\x5D\0\0\x80\0", Some(Format::Lzma)),
+        (&tar, Some(Format::Tar)),
+        (b"PK\x05\x06", Some(Format::Zip)),
+        (b"file 0123", None),
+    ];
+    for (bytes, format) in cases {
+        assert_eq!(Format::sniff(bytes), format, "{bytes:02x?}");
+    }
+    assert_eq!(Format::sniff(&encoded()), Some(Format::Imt));
+
+    assert!(Format::Gzip.is_compressed());
+    assert_eq!(Format::Xz.decompressor(), Some("xz"));
+    assert!(!Format::Tar.is_compressed());
+    assert_eq!(Format::Tar.decompressor(), None);
+}
+
+#[test]
+fn rejects_streams_it_cannot_decompress() {
+    let mut bundle = Bundle::create();
+    let e = bundle
+        .parse_sniffed(path("io"), b"\xFD7zXZ\0\0\0")
+        .unwrap_err();
+    assert!(
+        e.to_string()
+            .contains("cannot decompress Xz streams, which must be decompressed first"),
+        "{e}"
+    );
+    assert!(bundle.get(&path("io")).is_none());
+}
+
+#[cfg(feature = "compress")]
+mod gzip {
+    use super::*;
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in bytes {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    /// A gzip stream of `bytes`, with the file name `name` in its header
+    fn gzip(bytes: &[u8], name: &str) -> Vec<u8> {
+        const FNAME: u8 = 0x08;
+        let mut out = vec![0x1F, 0x8B, 8, FNAME, 0, 0, 0, 0, 0, 0xFF];
+        out.extend_from_slice(name.as_bytes());
+        out.push(0);
+        out.extend(miniz_oxide::deflate::compress_to_vec(bytes, 6));
+        out.extend_from_slice(&crc32(bytes).to_le_bytes());
+        out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn decompresses_gzip_files() {
+        let bytes = encoded();
+        let mut bundle = Bundle::create();
+        assert_eq!(
+            bundle
+                .parse_sniffed(path("io"), &gzip(&bytes, "io.imt"))
+                .unwrap(),
+            Format::Gzip
+        );
+        assert_eq!(
+            bincode::encode_to_vec(bundle.get(&path("io")).unwrap(), format_config()).unwrap(),
+            bytes
+        );
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn decompresses_gzip_archives() {
+        let mut original = Bundle::create();
+        original
+            .parse_file(path("sys::io"), &encoded()[..])
+            .unwrap();
+        original
+            .parse_file(path("sys::mem"), &encoded()[..])
+            .unwrap();
+        let mut tar = std::io::Cursor::new(Vec::new());
+        original.write_tar(&path("sys"), &mut tar).unwrap();
+
+        let mut bundle = Bundle::create();
+        assert_eq!(
+            bundle
+                .parse_sniffed(path("vendor"), &gzip(&tar.into_inner(), "sys.tar"))
+                .unwrap(),
+            Format::Gzip
+        );
+        let modules = bundle
+            .iter()
+            .map(|(path, _)| path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(modules, ["vendor::io", "vendor::mem"]);
+    }
+
+    #[test]
+    fn rejects_truncated_gzip_streams() {
+        let stream = gzip(&encoded(), "io.imt");
+        let mut bundle = Bundle::create();
+        assert!(bundle.parse_sniffed(path("io"), &stream[..6]).is_err());
+        assert!(bundle.parse_sniffed(path("io"), &stream[..20]).is_err());
+        assert!(bundle.get(&path("io")).is_none());
+    }
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use common::{file, path, structure};
+use imt::{
+    attr::{
+        Attribute, find_attribute,
+        types::{ItemDoc, ToolComment},
+    },
+    bundle::Bundle,
+    config::format_config,
+    file::File,
+    tydef::TypeDefBody,
+    uses::{IntType, Type},
+};
+
+fn strip(dir: &std::path::Path, args: &[&str], output: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+        .arg("strip")
+        .args(args)
+        .arg(dir.join("in.imt"))
+        .arg(dir.join(output))
+        .output()
+        .unwrap()
+}
+
+/// Whether the file at `name` in `dir` has a tool comment, and whether its struct is documented
+fn read(dir: &std::path::Path, name: &str) -> (bool, bool) {
+    let mut bundle = Bundle::create();
+    bundle
+        .parse_file(path("io"), &std::fs::read(dir.join(name)).unwrap()[..])
+        .unwrap();
+    let io = bundle.get(&path("io")).unwrap();
+    let TypeDefBody::Struct(st) = &io.types[0].body else {
+        panic!("Handle is not a struct");
+    };
+    assert_eq!(io.types[0].name, "Handle");
+    (
+        find_attribute::<ToolComment, _>(&io.attributes).is_some(),
+        find_attribute::<ItemDoc, _>(&st.attrs).is_some(),
+    )
+}
+
+/// A file with a tool comment, and a documented struct `Handle`
+fn documented() -> File {
+    let mut handle = structure("Handle", &[("raw", Type::Int(IntType::u64))]);
+    let TypeDefBody::Struct(st) = &mut handle.body else {
+        unreachable!()
+    };
+    st.attrs.push(Attribute::new(ItemDoc {
+        doc_lines: vec!["A handle".into()],
+    }));
+    let mut io = file(vec![handle], vec![]);
+    io.attributes.push(Attribute::new(ToolComment {
+        comment: "generated".into(),
+    }));
+    io
+}
+
+#[test]
+fn strips_selected_content() {
+    let dir = std::env::temp_dir().join(format!("imt-strip-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("in.imt"),
+        bincode::encode_to_vec(documented(), format_config()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(read(&dir, "in.imt"), (true, true));
+
+    let out = strip(&dir, &["--content", "comments"], "comments.imt");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(read(&dir, "comments.imt"), (false, true));
+
+    // Everything is stripped by default
+    let out = strip(&dir, &[], "all.imt");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(read(&dir, "all.imt"), (false, false));
+
+    let out = strip(&dir, &["--content", "types"], "types.imt");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--content: unknown content in types"));
+    assert!(!dir.join("types.imt").exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,218 @@
+mod common;
+
+use common::{
+    bundle, codes, enumeration, field, file, generate, messages, named, path, struct_of, union_of,
+};
+use imt::{
+    attr::{
+        Attribute,
+        types::{ActiveWhen, DiscriminatedBy},
+    },
+    bundle::Bundle,
+    codegen::{c::CBackend, rust::RustBackend},
+    tagged::{TaggedUnionError, tagged_unions},
+    tydef::{EnumBuilder, Field, StructBody, TypeDef, TypeDefBody},
+    uses::{ArrayType, Expr, IntType, Type},
+};
+
+fn active(variant: &str, name: &str, ty: Type) -> Field {
+    field(
+        vec![Attribute::new(ActiveWhen {
+            variant: variant.into(),
+        })],
+        name,
+        ty,
+    )
+}
+
+/// The union `Addr`, discriminated by the field `tag` if it is given, with an IPv4 and an IPv6 member
+fn addr(tag: Option<&str>, v6: &str) -> TypeDef {
+    let attrs = tag
+        .map(|field| {
+            Attribute::new(DiscriminatedBy {
+                field: field.into(),
+            })
+        })
+        .into_iter()
+        .collect();
+    let octets = ArrayType {
+        base: Type::Int(IntType::u8),
+        len: Expr::IntLiteral(IntType::u64, 16),
+    };
+    union_of(
+        "Addr",
+        attrs,
+        vec![
+            active("Ipv4", "v4", Type::Int(IntType::u32)),
+            active(v6, "v6", Type::Array(Box::new(octets))),
+        ],
+    )
+}
+
+/// A bundle with the enum `Kind` and the struct `SockAddr`, which holds `addr` in its field `addr`, tagged by its field `kind` of type `kind`
+fn sockaddr(addr: TypeDef, kind: Type) -> Bundle {
+    let kinds = EnumBuilder::new(IntType::u8)
+        .variant("Ipv4", None)
+        .variant("Ipv6", None)
+        .build();
+    let sockaddr = struct_of(
+        "SockAddr",
+        vec![],
+        vec![
+            field(vec![], "kind", kind),
+            field(vec![], "addr", named("Addr")),
+        ],
+    );
+    bundle(vec![(
+        "sys::net",
+        file(vec![enumeration("Kind", kinds), addr, sockaddr], vec![]),
+    )])
+}
+
+fn valid() -> Bundle {
+    sockaddr(addr(Some("kind"), "Ipv6"), named("Kind"))
+}
+
+#[test]
+fn finds_tagged_unions() {
+    let bundle = valid();
+    assert!(codes(&bundle.validate()).is_empty());
+
+    let io = bundle.get(&path("sys::net")).unwrap();
+    let TypeDefBody::Struct(st) = &io.types[2].body else {
+        unreachable!()
+    };
+    let StructBody::Fields(fields) = &st.body else {
+        unreachable!()
+    };
+    let tagged = tagged_unions(&bundle, &path("sys::net"), fields);
+    let [Ok(tagged)] = &*tagged else {
+        panic!("expected one tagged union, got {tagged:?}");
+    };
+    assert_eq!(tagged.field.name, "addr");
+    assert_eq!(tagged.tag.name, "kind");
+    let (module, tag) = tagged.tag_enum.as_ref().unwrap();
+    assert_eq!(
+        (module.to_string(), &*tag.name),
+        ("sys::net".into(), "Kind")
+    );
+    let members = tagged
+        .members()
+        .map(|(member, variant)| (member.name.to_string(), variant))
+        .collect::<Vec<_>>();
+    assert_eq!(members, [("v4".into(), "Ipv4"), ("v6".into(), "Ipv6")]);
+    assert!(tagged.has_variant("Ipv6"));
+    assert!(!tagged.has_variant("Unix"));
+}
+
+#[test]
+fn reports_active_members_of_undiscriminated_unions() {
+    let diags = sockaddr(addr(None, "Ipv6"), named("Kind")).validate();
+    assert_eq!(codes(&diags), ["IMT0012"; 2]);
+    assert_eq!(
+        messages(&diags)[0],
+        "member Addr::v4 has an active variant, but Addr is not discriminated"
+    );
+    assert_eq!(diags[0].item.as_deref(), Some("Addr"));
+}
+
+#[test]
+fn reports_missing_tag_fields() {
+    let bundle = sockaddr(addr(Some("family"), "Ipv6"), named("Kind"));
+    let diags = bundle.validate();
+    assert_eq!(codes(&diags), ["IMT0013"]);
+    assert_eq!(
+        messages(&diags),
+        [
+            "SockAddr: union field addr is discriminated by family, which is not a field of the same struct"
+        ]
+    );
+
+    let io = bundle.get(&path("sys::net")).unwrap();
+    let TypeDefBody::Struct(st) = &io.types[2].body else {
+        unreachable!()
+    };
+    let StructBody::Fields(fields) = &st.body else {
+        unreachable!()
+    };
+    assert_eq!(
+        tagged_unions(&bundle, &path("sys::net"), fields)[0]
+            .as_ref()
+            .unwrap_err(),
+        &TaggedUnionError::NoTagField {
+            field: "addr".into(),
+            tag: "family".into()
+        }
+    );
+}
+
+#[test]
+fn reports_tags_that_are_not_enums() {
+    // An integer tag is valid, but its members cannot name variants
+    let diags = sockaddr(addr(Some("kind"), "Ipv6"), Type::Int(IntType::u8)).validate();
+    assert_eq!(codes(&diags), ["IMT0016"; 2]);
+    assert_eq!(
+        messages(&diags)[1],
+        "member v6 of SockAddr::addr is active for Ipv6, but tag field kind is not an enum"
+    );
+
+    let diags = sockaddr(addr(Some("kind"), "Ipv6"), Type::Byte).validate();
+    assert_eq!(codes(&diags), ["IMT0014", "IMT0016", "IMT0016"]);
+    assert_eq!(
+        messages(&diags)[0],
+        "tag field SockAddr::kind is not an integer or enum"
+    );
+}
+
+#[test]
+fn reports_variants_missing_from_the_tag() {
+    let diags = sockaddr(addr(Some("kind"), "Unix"), named("Kind")).validate();
+    assert_eq!(codes(&diags), ["IMT0015"]);
+    assert_eq!(
+        messages(&diags),
+        [
+            "member v6 of SockAddr::addr is active for Unix, which is not a variant of the type of kind"
+        ]
+    );
+}
+
+#[test]
+fn generates_checked_accessors() {
+    let bundle = valid();
+
+    let rust = &generate(&RustBackend::default(), &bundle)["sys/net.rs"];
+    for line in [
+        "/// The active member is determined by the `kind` field of the containing struct.",
+        "/// This member is active when the tag is `Ipv4`.",
+        "/// Returns the `v4` member of `addr` if `kind` is `Ipv4`",
+        "pub fn addr_v4(&self) -> Option<&u32> {",
+        "(self.kind == Kind::Ipv4).then(|| unsafe { &self.addr.v4 })",
+        "pub fn addr_v6_mut(&mut self) -> Option<&mut [u8; 16]> {",
+        "(self.kind == Kind::Ipv6).then(|| unsafe { &mut self.addr.v6 })",
+    ] {
+        assert!(rust.contains(line), "{line} in {rust}");
+    }
+
+    // Members active for a variant that does not exist have no accessor
+    let bundle = sockaddr(addr(Some("kind"), "Unix"), named("Kind"));
+    let rust = &generate(&RustBackend::default(), &bundle)["sys/net.rs"];
+    assert!(rust.contains("fn addr_v4(&self)"));
+    assert!(!rust.contains("fn addr_v6(&self)"));
+
+    let c = &generate(&CBackend::default(), &valid())["sys/net.h"];
+    assert!(
+        c.contains("The active member is determined by the `kind` field of the containing struct."),
+        "{c}"
+    );
+    assert!(
+        c.contains("This member is active when the tag is `Ipv6`."),
+        "{c}"
+    );
+}
+
+/// Unions are checked where they are used, so a union that no struct holds is not checked against a tag
+#[test]
+fn does_not_check_unused_unions() {
+    let file = file(vec![addr(Some("kind"), "Unix")], vec![]);
+    assert!(codes(&bundle(vec![("sys::net", file)]).validate()).is_empty());
+}
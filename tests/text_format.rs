@@ -0,0 +1,185 @@
+#![cfg(feature = "text")]
+
+use imt::{
+    bundle::{Bundle, Path},
+    config::format_config,
+    file::File,
+    print::{render_file, render_source},
+    text::{
+        Anchor, ItemRef, Span,
+        format::{DOC_WIDTH, format_source},
+        parse::parse,
+    },
+};
+
+/// A file encoded by imt 0.1, which has a function `Register(callback: *const fn(), flags: u32) -> i32` and a const `MAX: u32 = 7`
+const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+/// A source in the canonical style, which uses each kind of declaration
+const CANONICAL: &str = "\
+file 01234567-89ab-cdef-0123-456789abcdef;
+//! An example subsystem
+
+use base::types;
+
+// The handle type
+/// A pair of integers
+#[types::Align { alignment: 8 }]
+struct Pair<$0> {
+    /// The first
+    a: u32,
+    // the second
+    b: *const $0,
+    .. [byte; 4u64]
+}
+
+opaque struct Handle: u64;
+
+type Callback = fn(u32) -> i32;
+
+enum Mode: u8 {
+    Read = 1u8,
+    Write = 2u8,
+}
+
+const MAX: u32 = (7u32 + 1u32);
+
+fn open(name: *const byte, mode: Mode) -> Handle;
+
+// trailing
+";
+
+fn encode(file: &File) -> Vec<u8> {
+    bincode::encode_to_vec(file, format_config()).unwrap()
+}
+
+/// The rendering of `file`, without the comment giving its format version
+fn render_body(file: &File) -> String {
+    let rendered = render_file(file);
+    let (version, body) = rendered.split_once('\n').unwrap();
+    assert!(version.starts_with("// format version "));
+    body.to_string()
+}
+
+#[test]
+fn parses_rendered_files() {
+    let mut bundle = Bundle::create();
+    let path = Path(vec!["test".into()]);
+    bundle.parse_file(path.clone(), BASELINE_FUNCTION).unwrap();
+    let file = bundle.get(&path).unwrap();
+
+    let parsed = parse(&render_file(file)).unwrap().file;
+    assert_eq!(parsed.file_id, file.file_id);
+    assert_eq!(render_body(&parsed), render_body(file));
+}
+
+#[test]
+fn round_trips_through_render_file() {
+    let file = parse(CANONICAL).unwrap().file;
+    let reparsed = parse(&render_file(&file)).unwrap().file;
+    assert_eq!(encode(&reparsed), encode(&file));
+    assert_eq!(render_file(&reparsed), render_file(&file));
+}
+
+#[test]
+fn reprints_canonical_sources_unchanged() {
+    let source = parse(CANONICAL).unwrap();
+    assert_eq!(render_source(&source), CANONICAL);
+    assert_eq!(format_source(CANONICAL).unwrap(), CANONICAL);
+}
+
+#[test]
+fn keeps_comments_at_their_declarations() {
+    let source = parse(CANONICAL).unwrap();
+    let anchors = source
+        .comments
+        .iter()
+        .map(|comment| (comment.anchor, &*comment.text))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        anchors,
+        [
+            (Anchor::Item(ItemRef::Type(0)), "// The handle type"),
+            (Anchor::Member(ItemRef::Type(0), 1), "// the second"),
+            (Anchor::End, "// trailing"),
+        ]
+    );
+    let pair = source.declaration(ItemRef::Type(0)).unwrap();
+    assert_eq!(pair.text(CANONICAL), "Pair");
+}
+
+#[test]
+fn formats_into_the_canonical_style() {
+    let src = "\
+file 01234567-89ab-cdef-0123-456789abcdef;
+use base::types;
+#[types::Align { alignment: 8 }]
+/// A pair
+struct Pair { a: u32, b: u32 }
+enum Mode: u8 { Read = 1, Write }
+const MAX: u32 = 7 + 1;
+";
+    let formatted = format_source(src).unwrap();
+    assert_eq!(
+        formatted,
+        "\
+file 01234567-89ab-cdef-0123-456789abcdef;
+
+use base::types;
+
+/// A pair
+#[types::Align { alignment: 8 }]
+struct Pair {
+    a: u32,
+    b: u32,
+}
+
+enum Mode: u8 {
+    Read = 1u8,
+    Write = 2u8,
+}
+
+const MAX: u32 = (7u32 + 1u32);
+"
+    );
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn wraps_long_documentation() {
+    let words = vec!["word"; 40].join(" ");
+    let src = format!(
+        "file 01234567-89ab-cdef-0123-456789abcdef;\n/// - {words}\n/// ```\n/// {words}\n/// ```\nstruct S {{}}\n"
+    );
+    let formatted = format_source(&src).unwrap();
+    let docs = formatted
+        .lines()
+        .filter_map(|line| line.strip_prefix("/// "))
+        .collect::<Vec<_>>();
+    // The list item is wrapped, and continues its item, but the code block is not
+    assert!(docs.len() > 4);
+    assert!(docs[0].starts_with("- word"));
+    assert!(docs[1].starts_with("  word"));
+    assert!(
+        docs.iter()
+            .all(|line| line.chars().count() <= DOC_WIDTH || *line == words)
+    );
+    assert!(docs.contains(&&*words));
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn reports_errors_at_their_span() {
+    let e = parse("struct S {}").unwrap_err();
+    assert_eq!(e.span, Span::new(0, 6));
+
+    let src = "file 01234567-89ab-cdef-0123-456789abcdef;\n#[NoSuchAttribute]\nstruct S {}";
+    let e = parse(src).unwrap_err();
+    assert_eq!(e.span.text(src), "NoSuchAttribute");
+    assert_eq!(e.span.line_col(src), (2, 3));
+    assert_eq!(e.message, "unknown attribute `NoSuchAttribute`");
+
+    let src = "file 01234567-89ab-cdef-0123-456789abcdef;\nstruct S<$1> {}";
+    let e = parse(src).unwrap_err();
+    assert_eq!(e.span.text(src), "$1");
+}
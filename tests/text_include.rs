@@ -0,0 +1,172 @@
+#![cfg(feature = "text")]
+
+use imt::{
+    bundle::{Bundle, Path},
+    print::render_source,
+    resolve::resolve_name,
+    text::{
+        compile::{CompileError, compile_dir, compile_source, module_path, resolve_includes},
+        parse::parse,
+    },
+};
+
+const HEADER: &str = "file 01234567-89ab-cdef-0123-456789abcdef;\n";
+
+fn path(elems: &[&str]) -> Path {
+    Path(elems.iter().map(|elem| elem.to_string()).collect())
+}
+
+/// A new directory tree for the test `name`, with the sources `files` (by their path relative to the tree)
+fn source_tree(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("imt-include-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (rel, src) in files {
+        let fs_path = root.join(rel);
+        std::fs::create_dir_all(fs_path.parent().unwrap()).unwrap();
+        std::fs::write(fs_path, format!("{HEADER}{src}")).unwrap();
+    }
+    root
+}
+
+/// The error of compiling the source `rel` of `root`, with the text it is reported at
+fn include_error(root: &std::path::Path, rel: &str) -> (String, String) {
+    let fs_path = root.join(rel);
+    let src = std::fs::read_to_string(&fs_path).unwrap();
+    match compile_source(&path(&["sys"]), root, &fs_path, &src) {
+        Err(CompileError::Parse { error, .. }) => {
+            (error.span.text(&src).to_string(), error.message)
+        }
+        res => panic!("expected an error, got {res:?}"),
+    }
+}
+
+#[test]
+fn maps_sources_to_modules() {
+    let root = std::path::Path::new("/src");
+    let prefix = path(&["sys"]);
+    assert_eq!(
+        module_path(&prefix, root, std::path::Path::new("/src/io/file.imts")),
+        Some(path(&["sys", "io", "file"]))
+    );
+    assert_eq!(
+        module_path(
+            &prefix,
+            root,
+            std::path::Path::new("/src/io/../prelude.imts")
+        ),
+        Some(path(&["sys", "prelude"]))
+    );
+    assert_eq!(
+        module_path(&prefix, root, std::path::Path::new("/other/prelude.imts")),
+        None
+    );
+    assert_eq!(
+        module_path(&prefix, root, std::path::Path::new("/src/io/file.imt")),
+        None
+    );
+}
+
+#[test]
+fn resolves_includes_to_uses() {
+    let root = source_tree(
+        "uses",
+        &[
+            ("prelude.imts", "opaque struct Handle: u64;\n"),
+            (
+                "io/file.imts",
+                "include \"../prelude.imts\";\nfn open(name: *const byte) -> Handle;\n",
+            ),
+        ],
+    );
+    let mut bundle = Bundle::create();
+    compile_dir(&mut bundle, path(&["sys"]), &root).unwrap();
+
+    let file = bundle.get(&path(&["sys", "io", "file"])).unwrap();
+    assert_eq!(file.uses.len(), 1);
+    assert_eq!(file.uses[0].path, ["sys", "prelude"]);
+
+    let handle = resolve_name(&bundle, &path(&["sys", "io", "file"]), "Handle").unwrap();
+    assert_eq!(handle.path, path(&["sys", "prelude", "Handle"]));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn reprints_includes() {
+    let src = format!("{HEADER}\ninclude \"../prelude.imts\";\nuse base::types;\n");
+    let mut source = parse(&src).unwrap();
+    assert_eq!(source.includes.len(), 1);
+    assert_eq!(source.includes[0].path, "../prelude.imts");
+    assert_eq!(source.includes[0].use_index, 0);
+    assert_eq!(source.includes[0].span.text(&src), "\"../prelude.imts\"");
+    assert!(source.file.uses[0].path.is_empty());
+    assert_eq!(render_source(&source), src);
+
+    // Resolving the include does not change how the source is printed
+    let root = source_tree("reprint", &[("prelude.imts", ""), ("io/file.imts", "")]);
+    resolve_includes(&mut source, &path(&[]), &root, &root.join("io/file.imts")).unwrap();
+    assert_eq!(source.file.uses[0].path, ["prelude"]);
+    assert_eq!(render_source(&source), src);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn rejects_missing_sources() {
+    let root = source_tree("missing", &[("io.imts", "include \"prelude.imts\";\n")]);
+    assert_eq!(
+        include_error(&root, "io.imts"),
+        (
+            "\"prelude.imts\"".to_string(),
+            "included source `prelude.imts` does not exist".to_string()
+        )
+    );
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn rejects_sources_outside_the_tree() {
+    let root = source_tree(
+        "outside",
+        &[
+            ("io.imts", "include \"../prelude.imts\";\n"),
+            ("types.imts", "include \"types.txt\";\n"),
+        ],
+    );
+    let (text, message) = include_error(&root, "io.imts");
+    assert_eq!(text, "\"../prelude.imts\"");
+    assert!(message.starts_with("included `../prelude.imts` is not a .imts source within "));
+
+    let (_, message) = include_error(&root, "types.imts");
+    assert!(message.starts_with("included `types.txt` is not a .imts source within "));
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn rejects_including_itself() {
+    let root = source_tree(
+        "itself",
+        &[("io/file.imts", "include \"../io/file.imts\";\n")],
+    );
+    assert_eq!(
+        include_error(&root, "io/file.imts").1,
+        "a source cannot include itself"
+    );
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn reports_the_including_source() {
+    let root = source_tree("location", &[("io.imts", "\n\ninclude \"gone.imts\";\n")]);
+    let mut bundle = Bundle::create();
+    let e = compile_dir(&mut bundle, path(&[]), &root).unwrap_err();
+    let CompileError::Parse {
+        path, line, col, ..
+    } = &e
+    else {
+        panic!("expected a parse error, got {e:?}");
+    };
+    assert_eq!(path, &root.join("io.imts"));
+    assert_eq!((*line, *col), (4, 9));
+    std::fs::remove_dir_all(root).unwrap();
+}
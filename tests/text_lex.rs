@@ -0,0 +1,150 @@
+#![cfg(feature = "text")]
+
+use imt::text::{
+    Span,
+    lex::{Token, TokenKind, tokenize, unescape},
+};
+
+/// The kind and text of each token of `src`
+fn tokens(src: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(src)
+        .into_iter()
+        .map(|token| (token.kind, token.span.text(src)))
+        .collect()
+}
+
+#[test]
+fn classifies_declarations() {
+    use TokenKind::*;
+
+    assert_eq!(
+        tokens("struct Pair<$0> { a: u32, b: *const $0 }"),
+        [
+            (Keyword, "struct"),
+            (Ident, "Pair"),
+            (Punct, "<"),
+            (Param, "$0"),
+            (Punct, ">"),
+            (Punct, "{"),
+            (Ident, "a"),
+            (Punct, ":"),
+            (BuiltinType, "u32"),
+            (Punct, ","),
+            (Ident, "b"),
+            (Punct, ":"),
+            (Punct, "*"),
+            (Keyword, "const"),
+            (Param, "$0"),
+            (Punct, "}"),
+        ]
+    );
+    assert_eq!(
+        tokens("fn f(x: base::types::Handle) -> !;"),
+        [
+            (Keyword, "fn"),
+            (Ident, "f"),
+            (Punct, "("),
+            (Ident, "x"),
+            (Punct, ":"),
+            (Ident, "base"),
+            (Punct, "::"),
+            (Ident, "types"),
+            (Punct, "::"),
+            (Ident, "Handle"),
+            (Punct, ")"),
+            (Punct, "->"),
+            (Punct, "!"),
+            (Punct, ";"),
+        ]
+    );
+}
+
+#[test]
+fn classifies_literals() {
+    use TokenKind::*;
+
+    assert_eq!(
+        tokens(r#"7u32 16 1.0 "a \"b\"" 01234567-89ab-cdef-0123-456789abcdef"#),
+        [
+            (Int, "7u32"),
+            (Int, "16"),
+            (Version, "1.0"),
+            (Str, r#""a \"b\"""#),
+            (Uuid, "01234567-89ab-cdef-0123-456789abcdef"),
+        ]
+    );
+    // A UUID followed by more of an identifier is not a UUID
+    assert_eq!(
+        tokens("01234567-89ab-cdef-0123-456789abcdefx")[0],
+        (Int, "01234567")
+    );
+}
+
+#[test]
+fn classifies_comments() {
+    use TokenKind::*;
+
+    let src = "//! file docs\n/// item docs\n// note\n//// not docs\nconst";
+    assert_eq!(
+        tokens(src),
+        [
+            (InnerDocComment, "//! file docs"),
+            (DocComment, "/// item docs"),
+            (Comment, "// note"),
+            (Comment, "//// not docs"),
+            (Keyword, "const"),
+        ]
+    );
+}
+
+#[test]
+fn covers_every_character() {
+    let src = "struct S { a: u8 } @ \"unterminated\nenum";
+    let tokens = tokenize(src);
+    assert_eq!(
+        tokens[tokens.len() - 3..],
+        [
+            Token {
+                kind: TokenKind::Unknown,
+                span: Span::new(19, 20),
+            },
+            Token {
+                kind: TokenKind::Unknown,
+                span: Span::new(21, 34),
+            },
+            Token {
+                kind: TokenKind::Keyword,
+                span: Span::new(35, 39),
+            },
+        ]
+    );
+    let covered = tokens
+        .iter()
+        .map(|token| token.span.text(src))
+        .collect::<String>();
+    let non_whitespace = src.split_whitespace().collect::<String>();
+    assert_eq!(covered, non_whitespace);
+}
+
+#[test]
+fn locates_spans() {
+    let src = "file x;\n  struct Ü {}";
+    let brace = src.find('{').unwrap();
+    assert_eq!(Span::new(brace, brace + 1).line_col(src), (2, 12));
+    assert_eq!(Span::new(0, 4).line_col(src), (1, 1));
+}
+
+#[test]
+fn unescapes_debug_strings() {
+    for s in [
+        "plain",
+        "quote \" and \\ backslash",
+        "tab\tnewline\n",
+        "nul\0",
+        "\u{7f}é",
+    ] {
+        assert_eq!(unescape(&format!("{s:?}")).as_deref(), Some(s));
+    }
+    assert_eq!(unescape(r#""\q""#), None);
+    assert_eq!(unescape("unquoted"), None);
+}
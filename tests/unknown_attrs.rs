@@ -0,0 +1,66 @@
+mod common;
+
+use common::{file, path, unrecognize};
+use imt::{
+    attr::{Attribute, AttributeType, UnknownAttributePolicy, types::ToolComment},
+    bundle::Bundle,
+    config::format_config,
+    file::{File, FileMetadata},
+};
+
+/// A file with a file attribute that this version of imt does not recognize
+fn unrecognized() -> Vec<u8> {
+    let mut io = file(vec![], vec![]);
+    io.attributes.push(Attribute::new(ToolComment {
+        comment: "generated".into(),
+    }));
+    let bytes = bincode::encode_to_vec(&io, format_config()).unwrap();
+    unrecognize(&bytes, ToolComment::ID)
+}
+
+fn decode(bytes: &[u8], policy: UnknownAttributePolicy) -> Option<File> {
+    bincode::decode_from_slice_with_context(bytes, format_config(), policy)
+        .ok()
+        .map(|(file, _)| file)
+}
+
+#[test]
+fn decodes_with_the_policy_of_the_context() {
+    let bytes = unrecognized();
+    assert!(decode(&bytes, UnknownAttributePolicy::Error).is_none());
+
+    let io = decode(&bytes, UnknownAttributePolicy::WarnAndPreserve).unwrap();
+    assert_eq!(io.attributes.len(), 1);
+    assert!(io.attributes[0].is_unknown());
+
+    let io = decode(&bytes, UnknownAttributePolicy::Drop).unwrap();
+    assert!(io.attributes.is_empty());
+}
+
+#[test]
+fn reads_metadata_with_each_policy() {
+    let bytes = unrecognized();
+    assert!(FileMetadata::read(&bytes[..], UnknownAttributePolicy::Error).is_err());
+
+    let metadata = FileMetadata::read(&bytes[..], UnknownAttributePolicy::WarnAndPreserve).unwrap();
+    assert_eq!(metadata.attributes.len(), 1);
+    assert!(metadata.attributes[0].is_unknown());
+
+    let metadata = FileMetadata::read(&bytes[..], UnknownAttributePolicy::Drop).unwrap();
+    assert!(metadata.attributes.is_empty());
+}
+
+#[test]
+fn bundles_drop_unknown_attributes() {
+    let bytes = unrecognized();
+    let mut bundle = Bundle::create();
+    assert!(bundle.parse_file(path("io"), &bytes[..]).is_err());
+
+    bundle.set_unknown_attribute_policy(UnknownAttributePolicy::Drop);
+    bundle.parse_file(path("io"), &bytes[..]).unwrap();
+    assert!(bundle.get(&path("io")).unwrap().attributes.is_empty());
+
+    bundle.set_unknown_attribute_policy(UnknownAttributePolicy::WarnAndPreserve);
+    bundle.parse_file(path("preserved"), &bytes[..]).unwrap();
+    assert_eq!(bundle.get(&path("preserved")).unwrap().attributes.len(), 1);
+}
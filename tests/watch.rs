@@ -0,0 +1,117 @@
+#![cfg(feature = "watch")]
+
+use std::{
+    io::BufRead,
+    process::{Child, Command, Stdio},
+    sync::mpsc::{Receiver, channel},
+    time::Duration,
+};
+
+const HEADER: &str = "file 01234567-89ab-cdef-0123-456789abcdef;\n";
+
+/// A running `imt-tool watch`, which is killed when dropped
+struct Watch {
+    child: Child,
+    lines: Receiver<String>,
+}
+
+impl Watch {
+    fn start(dir: &std::path::Path) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_imt-tool"))
+            .arg("watch")
+            .arg(dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+        let (tx, lines) = channel();
+        std::thread::spawn(move || {
+            for line in stdout.lines() {
+                if tx.send(line.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { child, lines }
+    }
+
+    /// The lines printed up to and including the next summary of a validation
+    fn next_report(&self) -> Vec<String> {
+        let mut report = Vec::new();
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(Duration::from_secs(20))
+                .unwrap_or_else(|_| panic!("no summary after {report:?}"));
+            let done = line.contains(" module(s): ");
+            report.push(line);
+            if done {
+                return report;
+            }
+        }
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn write_source(path: &std::path::Path, src: &str) {
+    std::fs::write(path, format!("{HEADER}{src}")).unwrap();
+}
+
+#[test]
+fn reports_new_and_fixed_problems() {
+    let dir = std::env::temp_dir().join(format!("imt-watch-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("io.imts");
+    write_source(&source, "struct S { a: u32 }\n");
+
+    let watch = Watch::start(&dir);
+    assert_eq!(
+        watch.next_report(),
+        ["1 module(s): 0 error(s), 0 warning(s), 0 fixed"]
+    );
+
+    // A source that does not compile is reported where it fails, and the file it compiled to before is kept
+    write_source(&source, "struct S { a: }\n");
+    let report = watch.next_report();
+    assert_eq!(report[0], "error: expected a type, found `}`");
+    assert_eq!(report[1], format!("  --> {}:2:15", source.display()));
+    assert_eq!(
+        report.last().unwrap(),
+        "1 module(s): 0 error(s), 0 warning(s), 0 fixed"
+    );
+
+    write_source(&source, "struct S { a: S }\n");
+    let report = watch.next_report();
+    assert!(report.iter().any(|line| line.contains("IMT0027")));
+    assert_eq!(
+        report.last().unwrap(),
+        "1 module(s): 1 error(s), 0 warning(s), 0 fixed"
+    );
+
+    write_source(&source, "struct S { a: u32 }\n");
+    assert_eq!(
+        watch.next_report(),
+        ["1 module(s): 0 error(s), 0 warning(s), 1 fixed"]
+    );
+
+    // Removing a source removes its module
+    std::fs::remove_file(&source).unwrap();
+    assert_eq!(
+        watch.next_report(),
+        [
+            "removed io".to_string(),
+            "0 module(s): 0 error(s), 0 warning(s), 0 fixed".to_string()
+        ]
+    );
+
+    drop(watch);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "zip")]
+
+use imt::{
+    bundle::{Bundle, Format, Path},
+    config::format_config,
+};
+
+const BASELINE_FUNCTION: &[u8] = include_bytes!("fixtures/baseline-function.imt");
+
+fn path(elems: &[&str]) -> Path {
+    Path(elems.iter().map(|elem| elem.to_string()).collect())
+}
+
+fn bundle() -> Bundle {
+    let mut bundle = Bundle::create();
+    for module in [&["sys", "io"][..], &["sys", "io", "file"], &["sys", "mem"]] {
+        bundle
+            .parse_file(path(module), BASELINE_FUNCTION)
+            .expect("fixture decodes");
+    }
+    bundle
+}
+
+fn write_zip(bundle: &mut Bundle, prefix: &Path) -> Vec<u8> {
+    let mut zip = std::io::Cursor::new(Vec::new());
+    bundle.write_zip(prefix, &mut zip).unwrap();
+    zip.into_inner()
+}
+
+#[test]
+fn round_trips_bundles() {
+    let mut original = bundle();
+    let zip = write_zip(&mut original, &path(&["sys"]));
+
+    let mut bundle = Bundle::create();
+    bundle
+        .parse_zip(path(&["sys"]), std::io::Cursor::new(&zip))
+        .unwrap();
+    let modules = bundle
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        modules,
+        [
+            path(&["sys", "io"]),
+            path(&["sys", "io", "file"]),
+            path(&["sys", "mem"])
+        ]
+    );
+    for (path, file) in &original {
+        let decoded = bundle.get(path).unwrap();
+        assert_eq!(
+            bincode::encode_to_vec(decoded, format_config()).unwrap(),
+            bincode::encode_to_vec(file, format_config()).unwrap()
+        );
+    }
+}
+
+#[test]
+fn reads_deflated_members() {
+    use std::io::Write;
+    use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("io/file.imt", options).unwrap();
+    zip.write_all(BASELINE_FUNCTION).unwrap();
+    let zip = zip.finish().unwrap().into_inner();
+
+    let mut bundle = Bundle::create();
+    bundle
+        .parse_zip(path(&["sys"]), std::io::Cursor::new(&zip))
+        .unwrap();
+    let mut expected = Bundle::create();
+    expected
+        .parse_file(path(&["sys", "io", "file"]), BASELINE_FUNCTION)
+        .unwrap();
+    assert_eq!(
+        bincode::encode_to_vec(
+            bundle.get(&path(&["sys", "io", "file"])).unwrap(),
+            format_config()
+        )
+        .unwrap(),
+        bincode::encode_to_vec(
+            expected.get(&path(&["sys", "io", "file"])).unwrap(),
+            format_config()
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn parses_under_another_prefix() {
+    let zip = write_zip(&mut bundle(), &path(&["sys"]));
+
+    let mut bundle = Bundle::create();
+    bundle
+        .parse_zip(path(&["vendor"]), std::io::Cursor::new(&zip))
+        .unwrap();
+    assert!(bundle.get(&path(&["vendor", "io", "file"])).is_some());
+    assert!(bundle.get(&path(&["sys", "io", "file"])).is_none());
+}
+
+#[test]
+fn sniffs_archives() {
+    let zip = write_zip(&mut bundle(), &path(&[]));
+    assert_eq!(Format::sniff(&zip), Some(Format::Zip));
+
+    let mut bundle = Bundle::create();
+    assert_eq!(bundle.parse_sniffed(path(&[]), &zip).unwrap(), Format::Zip);
+    assert_eq!(bundle.iter().count(), 3);
+}
+
+#[test]
+fn sniffs_empty_archives() {
+    let zip = write_zip(&mut Bundle::create(), &path(&[]));
+    assert_eq!(Format::sniff(&zip), Some(Format::Zip));
+}